@@ -23,7 +23,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // 2) 多轮对话测试，验证历史记录功能
-    let conversations = vec![
+    let conversations = [
         "你好，我的名字叫小明，是一名程序员。",
         "我最喜欢的编程语言是Rust，你知道为什么吗？",
         "what's my name?",
@@ -69,7 +69,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
-        println!("{}", format!("\n{}", "-".repeat(50)));
+        println!("\n{}", "-".repeat(50));
     }
 
     // 4) 画像特征查询功能已移除
@@ -16,20 +16,37 @@ pub struct MemoryConfig {
     pub importance_weight: f32,
     /// 最大上下文长度（字符数）
     pub max_context_length: usize,
+    /// 对话上下文的 token 预算（0 表示不限制）
+    #[serde(default = "default_context_token_budget")]
+    pub context_token_budget: usize,
 
     // === 语义聚合配置 ===
     /// 语义片段创建的重要性阈值 (1-10)
     pub semantic_chunk_threshold: i32,
+    /// 批量聚合的轮次数：会话累计这么多轮未聚合的对话后，在后台合并总结为一个语义片段（0 表示不启用）
+    #[serde(default)]
+    pub semantic_batch_turns: usize,
     /// 是否启用知识图谱更新（情节/程序记忆）
     pub enable_graph_updates: bool,
     /// 共现边权重映射策略：将 importance_score 转换为权重的除数
     pub cooccur_weight_divisor: f32,
+    /// 共现窗口：触发词列表中相距不超过该位置数的触发词才连边（0 表示两两全部连边）
+    #[serde(default)]
+    pub cooccur_window: usize,
     /// 边权重的最小值（避免过小的权重）
     pub min_edge_weight: f32,
     /// 边权重的最大值（防止权重过大）
     pub max_edge_weight: f32,
     /// 是否启用权重累加（而非直接返回已存在边的ID）
     pub enable_weight_accumulation: bool,
+
+    // === 画像衰减配置 ===
+    /// 画像特征置信度的半衰期（天）
+    #[serde(default = "default_persona_decay_half_life_days")]
+    pub persona_decay_half_life_days: f32,
+    /// 画像特征的最低置信度，衰减后低于该值的特征会被删除
+    #[serde(default = "default_persona_min_confidence")]
+    pub persona_min_confidence: f32,
 }
 
 impl Default for MemoryConfig {
@@ -40,14 +57,20 @@ impl Default for MemoryConfig {
             time_decay_factor: 24.0,  // 24小时衰减
             importance_weight: 1.5,   // 重要性提升倍数
             max_context_length: 2000, // 最大上下文2000字符
+            context_token_budget: default_context_token_budget(),
 
             // 语义聚合默认配置
             semantic_chunk_threshold: 5,      // 重要性>=5才创建语义片段
+            semantic_batch_turns: 0,          // 默认不启用批量聚合
             enable_graph_updates: false,      // 默认不启用知识图谱更新
             cooccur_weight_divisor: 10.0,     // importance_score / 10.0
+            cooccur_window: 0,                // 默认两两全部连边
             min_edge_weight: 0.1,             // 最小权重0.1
             max_edge_weight: 2.0,             // 最大权重2.0
             enable_weight_accumulation: true, // 默认启用权重累加
+
+            persona_decay_half_life_days: default_persona_decay_half_life_days(),
+            persona_min_confidence: default_persona_min_confidence(),
         }
     }
 }
@@ -93,12 +116,24 @@ impl MemoryConfig {
         self
     }
 
+    /// 设置对话上下文的 token 预算
+    pub fn with_context_token_budget(mut self, budget: usize) -> Self {
+        self.context_token_budget = budget;
+        self
+    }
+
     /// 设置语义片段阈值
     pub fn with_semantic_chunk_threshold(mut self, threshold: i32) -> Self {
         self.semantic_chunk_threshold = threshold;
         self
     }
 
+    /// 设置批量语义聚合的轮次数（0 表示不启用）
+    pub fn with_semantic_batch_turns(mut self, turns: usize) -> Self {
+        self.semantic_batch_turns = turns;
+        self
+    }
+
     /// 设置是否启用知识图谱更新
     pub fn with_graph_updates(mut self, enable: bool) -> Self {
         self.enable_graph_updates = enable;
@@ -111,6 +146,12 @@ impl MemoryConfig {
         self
     }
 
+    /// 设置共现窗口（0 表示两两全部连边）
+    pub fn with_cooccur_window(mut self, window: usize) -> Self {
+        self.cooccur_window = window;
+        self
+    }
+
     /// 设置边权重范围
     pub fn with_edge_weight_range(mut self, min: f32, max: f32) -> Self {
         self.min_edge_weight = min;
@@ -123,6 +164,28 @@ impl MemoryConfig {
         self.enable_weight_accumulation = enable;
         self
     }
+
+    /// 设置画像特征衰减的半衰期（天）与最低置信度
+    pub fn with_persona_decay(mut self, half_life_days: f32, min_confidence: f32) -> Self {
+        self.persona_decay_half_life_days = half_life_days;
+        self.persona_min_confidence = min_confidence;
+        self
+    }
+}
+
+/// 默认上下文 token 预算（不限制）
+fn default_context_token_budget() -> usize {
+    0
+}
+
+/// 默认画像特征半衰期（30天）
+fn default_persona_decay_half_life_days() -> f32 {
+    30.0
+}
+
+/// 默认画像特征最低置信度
+fn default_persona_min_confidence() -> f32 {
+    0.1
 }
 
 /// 智能体配置
@@ -173,6 +236,21 @@ impl AgentConfig {
         self.save_conversations = save;
         self
     }
+
+    /// 设置默认停止序列，调用方未指定停止序列时使用，生成内容在第一个停止序列处截断
+    pub fn with_stop_sequences<S: Into<String>>(
+        mut self,
+        stop_sequences: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.inference_params.stop_sequences = stop_sequences.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// 设置单次回复的最大生成 token 数
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.inference_params.max_tokens = max_tokens;
+        self
+    }
 }
 
 /// 默认保存对话设置
@@ -196,14 +274,50 @@ impl Default for AgentConfig {
 }
 
 /// 推理参数
+///
+/// 每个智能体独立持有，创建智能体时据此构建其采样器。
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InferenceParams {
     pub max_tokens: usize,
     pub temperature: f32,
     pub top_p: f32,
+    /// Top-K 采样，只在概率最高的 K 个 token 中采样
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
     pub presence_penalty: f32,
     pub frequency_penalty: f32,
     pub stop_sequences: Vec<String>,
+    /// 随机种子，设置后相同提示词生成相同内容（未设置时使用模型配置中的种子）
+    pub seed: Option<u64>,
+    /// KBNF 语法约束，调用方未传入 `bnf_schema` 时使用
+    pub bnf_schema: Option<String>,
+}
+
+/// 默认 Top-K
+fn default_top_k() -> usize {
+    128
+}
+
+impl InferenceParams {
+    /// 校验参数取值范围
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.max_tokens == 0 {
+            anyhow::bail!("max_tokens must be greater than 0");
+        }
+        if !self.temperature.is_finite() || self.temperature < 0.0 {
+            anyhow::bail!("temperature must be >= 0, got {}", self.temperature);
+        }
+        if !(0.0..=1.0).contains(&self.top_p) {
+            anyhow::bail!("top_p must be within [0, 1], got {}", self.top_p);
+        }
+        if self.top_k == 0 {
+            anyhow::bail!("top_k must be greater than 0");
+        }
+        if !self.presence_penalty.is_finite() || !self.frequency_penalty.is_finite() {
+            anyhow::bail!("presence_penalty and frequency_penalty must be finite");
+        }
+        Ok(())
+    }
 }
 
 impl Default for InferenceParams {
@@ -212,9 +326,12 @@ impl Default for InferenceParams {
             max_tokens: 1024,
             temperature: 0.7,
             top_p: 0.9,
+            top_k: default_top_k(),
             presence_penalty: 0.0,
             frequency_penalty: 0.0,
             stop_sequences: vec!["\n\n".to_string()],
+            seed: None,
+            bnf_schema: None,
         }
     }
 }
@@ -1,11 +1,14 @@
 //! 智能体实例模块
 
-use ai00_core::{sampler::Sampler, InputState};
+use ai00_core::{
+    sampler::{nucleus::NucleusSampler, Sampler},
+    InputState,
+};
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
-use super::{AgentConfig, Memory};
+use super::{AgentConfig, InferenceParams, Memory, SeededSampler};
 use crate::rwkv::{ModelConfig, StateManager};
 
 /// 智能体实例
@@ -20,9 +23,39 @@ pub struct Agent {
 impl Agent {
     /// 创建新的智能体实例
     pub fn new(config: AgentConfig, model_config: &ModelConfig) -> Result<Self> {
+        let state = Self::resolve_state(&config, model_config);
+
+        // 根据配置创建采样器
+        config.inference_params.validate().map_err(|e| {
+            anyhow::anyhow!(
+                "Invalid inference params for agent '{}': {}",
+                config.name,
+                e
+            )
+        })?;
+        let nucleus_sampler = Self::build_sampler(&config.inference_params);
+
+        Ok(Self {
+            config,
+            sampler: Arc::new(RwLock::new(nucleus_sampler)),
+            state,
+            memory: Memory::new(),
+        })
+    }
+
+    /// 切换到新模型：按新模型配置重新选择 state，保留采样器与对话记忆
+    pub fn with_model(&self, model_config: &ModelConfig) -> Self {
+        Self {
+            state: Self::resolve_state(&self.config, model_config),
+            ..self.clone()
+        }
+    }
+
+    /// 在模型配置的 state 中选择智能体使用的 state
+    fn resolve_state(config: &AgentConfig, model_config: &ModelConfig) -> Arc<InputState> {
         let state_manager = StateManager::new(model_config.states.clone());
 
-        let state = if let Some(state_name) = &config.state {
+        if let Some(state_name) = &config.state {
             // 查找指定名称的state
             state_manager
                 .get_state_by_name(state_name)
@@ -30,21 +63,35 @@ impl Agent {
         } else {
             // 使用默认state
             state_manager.get_default_state()
-        };
+        }
+    }
 
-        // 根据配置创建采样器
-        let mut nucleus_sampler = ai00_core::sampler::nucleus::NucleusSampler::default();
-        nucleus_sampler.params.temperature = config.inference_params.temperature;
-        nucleus_sampler.params.top_p = config.inference_params.top_p;
-        nucleus_sampler.params.presence_penalty = config.inference_params.presence_penalty;
-        nucleus_sampler.params.frequency_penalty = config.inference_params.frequency_penalty;
+    /// 按推理参数构建核采样器
+    pub fn build_sampler(params: &InferenceParams) -> NucleusSampler {
+        let mut sampler = NucleusSampler::default();
+        sampler.params.temperature = params.temperature;
+        sampler.params.top_p = params.top_p;
+        sampler.params.top_k = params.top_k;
+        sampler.params.presence_penalty = params.presence_penalty;
+        sampler.params.frequency_penalty = params.frequency_penalty;
+        sampler
+    }
 
-        Ok(Self {
-            config,
-            sampler: Arc::new(RwLock::new(nucleus_sampler)),
-            state,
-            memory: Memory::new(),
-        })
+    /// 获取本次推理使用的采样器
+    ///
+    /// 设置了种子（智能体的优先于 `default_seed`）时每次请求新建固定种子的采样器，
+    /// 惩罚状态与随机序列都从头开始；否则复用智能体共享的采样器。
+    pub fn request_sampler(
+        &self,
+        default_seed: Option<u64>,
+    ) -> Arc<RwLock<dyn Sampler + Send + Sync>> {
+        match self.config.inference_params.seed.or(default_seed) {
+            Some(seed) => Arc::new(RwLock::new(SeededSampler::new(
+                Self::build_sampler(&self.config.inference_params),
+                seed,
+            ))),
+            None => self.sampler.clone(),
+        }
     }
 
     /// 获取智能体的记忆管理器
@@ -52,3 +99,79 @@ impl Agent {
         &self.memory
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent_config(name: &str, temperature: f32) -> AgentConfig {
+        AgentConfig {
+            name: name.to_string(),
+            inference_params: InferenceParams {
+                temperature,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_agents_keep_their_own_sampling_params() {
+        let model_config = ModelConfig::default();
+        let summarizer = Agent::new(agent_config("summarizer", 0.0), &model_config).unwrap();
+        let chat = Agent::new(agent_config("chat", 1.2), &model_config).unwrap();
+
+        let summarizer_sampler = Agent::build_sampler(&summarizer.config.inference_params);
+        let chat_sampler = Agent::build_sampler(&chat.config.inference_params);
+        assert_eq!(summarizer_sampler.params.temperature, 0.0);
+        assert_eq!(chat_sampler.params.temperature, 1.2);
+        assert_eq!(chat_sampler.params.top_k, 128);
+    }
+
+    #[test]
+    fn test_invalid_inference_params_are_rejected() {
+        let model_config = ModelConfig::default();
+        assert!(Agent::new(agent_config("chat", -0.5), &model_config).is_err());
+
+        let mut config = agent_config("chat", 0.7);
+        config.inference_params.top_p = 1.5;
+        assert!(Agent::new(config, &model_config).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_with_model_keeps_conversation_memory() {
+        let agent = Agent::new(agent_config("chat", 0.7), &ModelConfig::default()).unwrap();
+        agent
+            .memory()
+            .add_conversation("你好".to_string(), "你好！".to_string())
+            .await;
+
+        let new_model = ModelConfig {
+            model_path: "new.st".to_string(),
+            ..Default::default()
+        };
+        let reloaded = agent.with_model(&new_model);
+        assert_eq!(reloaded.memory().history_count().await, 1);
+        assert!(Arc::ptr_eq(&agent.sampler, &reloaded.sampler));
+    }
+
+    #[tokio::test]
+    async fn test_seeded_requests_sample_identically() {
+        let model_config = ModelConfig::default();
+        let agent = Agent::new(agent_config("chat", 1.0), &model_config).unwrap();
+        let probs = [0.1, 0.2, 0.3, 0.25, 0.15];
+
+        let run = |sampler: Arc<RwLock<dyn Sampler + Send + Sync>>| async move {
+            let mut sampler = sampler.write().await;
+            sampler.init(&[1, 2, 3]);
+            (0..32).map(|_| sampler.sample(&probs)).collect::<Vec<_>>()
+        };
+
+        let first = run(agent.request_sampler(Some(42))).await;
+        let second = run(agent.request_sampler(Some(42))).await;
+        assert_eq!(first, second);
+
+        // 未设置种子时复用共享采样器
+        assert!(Arc::ptr_eq(&agent.request_sampler(None), &agent.sampler));
+    }
+}
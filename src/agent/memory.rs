@@ -1,8 +1,116 @@
 //! 智能体记忆模块
 
+use crate::utils::count_tokens;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use web_rwkv::tokenizer::Tokenizer;
+
+/// 参与上下文组装的一轮对话
+#[derive(Debug, Clone)]
+pub struct ContextTurn {
+    pub user: String,
+    pub assistant: String,
+    /// 该轮所属语义片段的摘要，轮次被裁剪时用来替代原文
+    pub summary: Option<String>,
+}
+
+/// 在 token 预算内组装出的对话上下文
+#[derive(Debug, Clone, Default)]
+pub struct ContextWindow {
+    /// 保留的语义片段
+    pub chunks: Vec<String>,
+    /// 替代被裁剪轮次的摘要（从旧到新）
+    pub summaries: Vec<String>,
+    /// 保留的最近轮次（从旧到新）
+    pub turns: Vec<(String, String)>,
+    /// 被裁剪的轮次数
+    pub dropped_turns: usize,
+    /// 已占用的 token 数
+    pub token_count: usize,
+}
+
+impl ContextWindow {
+    /// 渲染为提示词片段：语义片段、摘要、最近轮次依次排列
+    pub fn render(&self) -> String {
+        let mut rendered: String = self
+            .chunks
+            .iter()
+            .chain(self.summaries.iter())
+            .map(|text| format_note(text))
+            .chain(self.turns.iter().map(|(u, a)| format_turn(u, a)))
+            .collect();
+
+        // 移除最后的换行符
+        if rendered.ends_with('\n') {
+            rendered.pop();
+        }
+        rendered
+    }
+}
+
+fn format_note(text: &str) -> String {
+    format!("{}\n\n", text.replace("\n", ""))
+}
+
+fn format_turn(user_msg: &str, assistant_msg: &str) -> String {
+    format!(
+        "User: {}\n\nAssistant: {}\n\n",
+        user_msg,
+        assistant_msg.replace("\n", "")
+    )
+}
+
+/// 在 token 预算内组装对话上下文
+///
+/// 优先保留最近的轮次（从新到旧，遇到放不下的轮次即停止，更早的轮次全部裁剪），
+/// 再放入语义片段，最后用被裁剪轮次的摘要（去重）填充剩余预算。
+pub fn fit_context_window(
+    turns: &[ContextTurn],
+    chunks: &[String],
+    budget: usize,
+    count_tokens: impl Fn(&str) -> usize,
+) -> ContextWindow {
+    let mut window = ContextWindow::default();
+
+    let mut kept = 0;
+    for turn in turns.iter().rev() {
+        let cost = count_tokens(&format_turn(&turn.user, &turn.assistant));
+        if window.token_count + cost > budget {
+            break;
+        }
+        window.token_count += cost;
+        window
+            .turns
+            .push((turn.user.clone(), turn.assistant.clone()));
+        kept += 1;
+    }
+    window.turns.reverse();
+    window.dropped_turns = turns.len() - kept;
+
+    for chunk in chunks {
+        let cost = count_tokens(&format_note(chunk));
+        if window.token_count + cost <= budget {
+            window.token_count += cost;
+            window.chunks.push(chunk.clone());
+        }
+    }
+
+    let dropped = &turns[..window.dropped_turns];
+    for summary in dropped.iter().rev().filter_map(|t| t.summary.as_ref()) {
+        if window.summaries.contains(summary) || window.chunks.contains(summary) {
+            continue;
+        }
+        let cost = count_tokens(&format_note(summary));
+        if window.token_count + cost <= budget {
+            window.token_count += cost;
+            window.summaries.push(summary.clone());
+        }
+    }
+    window.summaries.reverse();
+
+    window
+}
 
 /// 智能体记忆管理器
 #[derive(Debug, Clone)]
@@ -58,11 +166,7 @@ impl Memory {
 
         let mut formatted_history = String::new();
         for (user_msg, assistant_msg) in history.iter() {
-            formatted_history.push_str(&format!(
-                "User: {}\n\nAssistant: {}\n\n",
-                user_msg,
-                assistant_msg.replace("\n", "")
-            ));
+            formatted_history.push_str(&format_turn(user_msg, assistant_msg));
         }
 
         // 移除最后的换行符
@@ -73,6 +177,33 @@ impl Memory {
         formatted_history
     }
 
+    /// 获取不超过 token 预算的历史对话记录（预算为 0 时不限制）
+    ///
+    /// 未提供分词器时按字符近似计数。
+    pub async fn get_history_within_budget(
+        &self,
+        budget: usize,
+        tokenizer: Option<&Tokenizer>,
+    ) -> String {
+        if budget == 0 {
+            return self.get_history().await;
+        }
+
+        let turns: Vec<ContextTurn> = self
+            .history
+            .read()
+            .await
+            .iter()
+            .map(|(user, assistant)| ContextTurn {
+                user: user.clone(),
+                assistant: assistant.clone(),
+                summary: None,
+            })
+            .collect();
+
+        fit_context_window(&turns, &[], budget, |text| count_tokens(tokenizer, text)).render()
+    }
+
     /// 清空历史记录
     pub async fn clear_history(&self) {
         let mut history = self.history.write().await;
@@ -91,3 +222,35 @@ impl Default for Memory {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_window_trims_oldest_turns() {
+        let turns: Vec<ContextTurn> = (0..8)
+            .map(|i| ContextTurn {
+                user: format!("question number {} about the weather today", i),
+                assistant: format!("answer number {} with some details", i),
+                summary: (i < 4).then(|| "user asked about weather".to_string()),
+            })
+            .collect();
+        let chunks = vec!["user lives in Hangzhou".to_string()];
+        let budget = 60;
+
+        let count = |text: &str| count_tokens(None, text);
+        let window = fit_context_window(&turns, &chunks, budget, count);
+
+        assert!(window.dropped_turns > 0);
+        assert!(window.token_count <= budget);
+        assert!(count(&window.render()) <= budget);
+        // 保留的是最近的轮次
+        let (last_user, _) = window.turns.last().unwrap();
+        assert!(last_user.contains("number 7"));
+        assert!(!window.render().contains("number 0 "));
+        assert_eq!(window.turns.len() + window.dropped_turns, turns.len());
+        // 同一摘要只出现一次
+        assert!(window.summaries.len() <= 1);
+    }
+}
@@ -5,8 +5,10 @@ pub mod core;
 pub mod config;
 pub mod memory;
 pub mod prompt;
+pub mod sampler;
 
 pub use core::*;
 pub use config::*;
 pub use memory::*;
 pub use prompt::*;
+pub use sampler::*;
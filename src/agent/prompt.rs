@@ -21,6 +21,8 @@ pub enum PromptBuilderInstance {
     Chat(super::super::agents::ChatPromptBuilder),
     Router(super::super::agents::RouterPromptBuilder),
     ConversationSummarizer(super::super::agents::ConversationSummarizerPromptBuilder),
+    AttributeExtractor(super::super::agents::AttributeExtractorPromptBuilder),
+    EntityClassifier(super::super::agents::EntityClassifierPromptBuilder),
 }
 
 #[async_trait]
@@ -44,6 +46,12 @@ impl PromptBuilder for PromptBuilderInstance {
             PromptBuilderInstance::ConversationSummarizer(builder) => {
                 builder.build_prompt(agent, user_input, tools).await
             }
+            PromptBuilderInstance::AttributeExtractor(builder) => {
+                builder.build_prompt(agent, user_input, tools).await
+            }
+            PromptBuilderInstance::EntityClassifier(builder) => {
+                builder.build_prompt(agent, user_input, tools).await
+            }
         }
     }
 }
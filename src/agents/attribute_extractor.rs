@@ -0,0 +1,129 @@
+//! Attribute Extractor Agent
+//!
+//! Extracts keywords, tags, a one-line context, emotion and an importance estimate from memory content as JSON
+
+use crate::{
+    agent::config::MemoryConfig,
+    agent::InferenceParams,
+    agent::{Agent, AgentConfig, PromptBuilder, PromptBuilderInstance},
+    core::service::RwkvAgentKit as CoreService,
+    core_types::Context,
+    error::MemoryError,
+    memory::AttributeExtractor,
+    rwkv::ModelConfig,
+};
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Default agent name
+pub const ATTRIBUTE_EXTRACTOR_AGENT: &str = "attribute_extractor";
+
+/// Attribute Extractor Prompt Builder
+#[derive(Debug, Clone)]
+pub struct AttributeExtractorPromptBuilder;
+
+#[async_trait::async_trait]
+impl PromptBuilder for AttributeExtractorPromptBuilder {
+    async fn build_prompt(
+        &self,
+        _agent: &Agent,
+        user_input: &str,
+        _tools: &[String],
+    ) -> Result<String> {
+        let prompt = format!(
+            r#"You are a memory indexer. Extract attributes from the text and output ONLY valid JSON.
+JSON: {{"keywords": ["3-8 key entities or concepts"], "tags": ["1-3 short category tags"], "context": "one-line context, max 30 tokens", "emotion": "dominant emotion, empty if neutral", "importance": float 0.0-1.0}}
+Constraints:
+- keywords: Real words or phrases from the text, not split characters or whole sentences;
+- tags: Broad categories such as work, travel, health;
+- importance: How worth remembering the text is for future conversations.
+IMPORTANT: Use the text language for fields.
+
+User: {}
+
+Assistant: <think>
+</think>
+```json
+"#,
+            user_input.replace("\n", "")
+        );
+        Ok(prompt)
+    }
+}
+
+/// Create attribute extractor agent configuration
+pub fn create_attribute_extractor_config() -> AgentConfig {
+    AgentConfig {
+        name: ATTRIBUTE_EXTRACTOR_AGENT.to_string(),
+        prompt_template: "{user_input}".to_string(), // Use custom prompt builder
+        inference_params: InferenceParams {
+            max_tokens: 512,
+            temperature: 0.0, // Lower temperature for stable output
+            top_p: 0.0,
+            top_k: 128,
+            presence_penalty: 0.0,
+            frequency_penalty: 0.0,
+            stop_sequences: vec!["```".to_string()],
+            seed: None,
+            bnf_schema: None,
+        },
+        tools: vec![],
+        state: Some("None".to_string()),
+        prompt_builder: Some(Arc::new(PromptBuilderInstance::AttributeExtractor(
+            AttributeExtractorPromptBuilder,
+        ))),
+        save_conversations: false, // 属性提取智能体不保存对话
+        memory: MemoryConfig::default(),
+    }
+}
+
+/// Create attribute extractor agent
+pub async fn create_attribute_extractor() -> Result<Agent> {
+    let config = create_attribute_extractor_config();
+    let model_config = ModelConfig::default();
+    Agent::new(config, &model_config)
+}
+
+/// 基于智能体的属性提取器，供 `MemoryManager::set_attribute_extractor` 使用
+///
+/// 对应的智能体（默认 `attribute_extractor`）需要已注册到核心服务。
+#[derive(Debug, Clone)]
+pub struct AgentAttributeExtractor {
+    core_service: Arc<RwLock<CoreService>>,
+    agent_name: String,
+}
+
+impl AgentAttributeExtractor {
+    pub fn new(core_service: Arc<RwLock<CoreService>>, agent_name: impl Into<String>) -> Self {
+        Self {
+            core_service,
+            agent_name: agent_name.into(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl AttributeExtractor for AgentAttributeExtractor {
+    async fn extract(&self, content: &str, _context: &Context) -> crate::error::Result<String> {
+        self.core_service
+            .read()
+            .await
+            .chat(&self.agent_name, content)
+            .await
+            .map_err(|e| MemoryError::Other(anyhow::anyhow!("{}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attribute_extractor_config() {
+        let config = create_attribute_extractor_config();
+        assert_eq!(config.name, ATTRIBUTE_EXTRACTOR_AGENT);
+        assert!(!config.save_conversations);
+        assert!(config.prompt_builder.is_some());
+    }
+}
@@ -76,7 +76,10 @@ User: {}
 
 Assistant: <think>"#,
             self.agent_nick,
-            _agent.memory().get_history().await,
+            _agent
+                .memory()
+                .get_history_within_budget(_agent.config.memory.context_token_budget, None)
+                .await,
             user_input.replace("\n", ""),
         );
 
@@ -94,10 +97,13 @@ pub fn create_chat_config() -> AgentConfig {
             max_tokens: 2048,
             temperature: 0.5,
             top_p: 0.3,
+            top_k: 128,
             presence_penalty: 0.5,
             frequency_penalty: 0.5,
             // 使用默认换行终止，避免过度截断
             stop_sequences: vec!["\n\nUser:".to_string(), "\n\nSystem:".to_string()],
+            seed: None,
+            bnf_schema: None,
         },
         tools: vec![],
         state: Some("chat".to_string()), // 默认加载 chat.state
@@ -11,6 +11,18 @@ use crate::{
 use anyhow::Result;
 use std::sync::Arc;
 
+/// KBNF grammar constraining the summarizer output to the summary JSON schema
+///
+/// The four fields appear in a fixed order and `importance_score` is an integer in 0-10.
+/// Whitespace is folded into the punctuation terminals so the grammar has no nullable rules.
+pub const CONVERSATION_SUMMARY_BNF: &str = r##"start ::= #"\\{[ \n]*" '"importance_score"' colon score comma '"user_intent_summary"' colon string comma '"summary"' colon string comma '"memory_triggers"' colon triggers #"[ \n]*\\}";
+colon ::= #"[ \n]*:[ \n]*";
+comma ::= #"[ \n]*,[ \n]*";
+score ::= #"10|[0-9]";
+string ::= '"' #'([^"\\\\\n]|\\\\["\\\\/nt])*' '"';
+triggers ::= #"\\[[ \n]*\\]" | #"\\[[ \n]*" string {comma string} #"[ \n]*\\]";
+"##;
+
 /// Conversation Summarizer Prompt Builder
 #[derive(Debug, Clone)]
 pub struct ConversationSummarizerPromptBuilder;
@@ -25,7 +37,7 @@ impl PromptBuilder for ConversationSummarizerPromptBuilder {
     ) -> Result<String> {
         let prompt = format!(
             r#"You are a conversation analyzer. Analyze multi-turn conversations and output JSON summary with 4 fields. Output ONLY valid JSON.
-Strictly base on history only. No hallucinations. If lacks substance, score low and output {{}}.
+Strictly base on history only. No hallucinations. If lacks substance, score low and leave the other fields empty.
 Evaluate importance 0-10;  
 Otherwise, fill fields reflecting input.
 JSON: {{"importance_score": int 0-10, "user_intent_summary": "intent from input, max 50 tokens", "summary": "accurate summary, max 100 tokens", "memory_triggers": ["3-5 keywords, max 5 tokens each"]}}
@@ -57,9 +69,12 @@ pub fn create_conversation_summarizer_config() -> AgentConfig {
             max_tokens: 4500, // Increased to accommodate entity extraction
             temperature: 0.0, // Lower temperature for stable output
             top_p: 0.0,
+            top_k: 128,
             presence_penalty: 0.0,
             frequency_penalty: 0.0,
             stop_sequences: vec![r"]}}".to_string()], // Remove stop sequences, let model end naturally
+            seed: None,
+            bnf_schema: Some(CONVERSATION_SUMMARY_BNF.to_string()),
         },
         tools: vec![],                   // No tools needed
         state: Some("None".to_string()), // Use tool-call state
@@ -106,4 +121,39 @@ mod tests {
         let test_input_no_newlines = test_input.replace("\n", "");
         assert!(prompt.contains(&test_input_no_newlines));
     }
+
+    #[test]
+    fn test_summary_bnf_compiles_and_constrains_output() {
+        use kbnf::{AcceptTokenResult, Engine, EngineLike, Token, Vocabulary};
+
+        // One token per byte, so any UTF-8 output can be fed through the engine
+        let vocabulary = Vocabulary::new(
+            (0..=255u8)
+                .map(|byte| (byte as u32, Token(vec![byte].into_boxed_slice())))
+                .collect(),
+            (0..=255u8)
+                .map(|byte| (byte as u32, format!("<{:02x}>", byte)))
+                .collect(),
+        )
+        .unwrap();
+        let mut engine = Engine::new(CONVERSATION_SUMMARY_BNF, vocabulary).unwrap();
+
+        let valid = "{\"importance_score\": 10,\n\"user_intent_summary\": \"计划去北京\", \"summary\": \"用户说 \\\"你好\\\"\", \"memory_triggers\": [\"北京\", \"会议\"]}";
+        let mut result = AcceptTokenResult::Ongoing;
+        for byte in valid.bytes() {
+            result = engine.try_accept_new_token(byte as u32).unwrap();
+        }
+        assert_eq!(result, AcceptTokenResult::Finished);
+
+        let mut rejected = |output: &str| {
+            engine.reset();
+            output
+                .bytes()
+                .try_for_each(|byte| engine.try_accept_new_token(byte as u32).map(|_| ()))
+                .is_err()
+        };
+        assert!(rejected("{\"importance_score\": 11"));
+        assert!(rejected("{\"summary\": \"\"}"));
+        assert!(rejected("好的"));
+    }
 }
@@ -0,0 +1,197 @@
+//! Entity Classifier Agent
+//!
+//! Classifies memory triggers into entity types (person, organization, ...) as JSON
+
+use crate::{
+    agent::config::MemoryConfig,
+    agent::InferenceParams,
+    agent::{Agent, AgentConfig, PromptBuilder, PromptBuilderInstance},
+    core::service::RwkvAgentKit as CoreService,
+    entity::{EntityClassifier, RuleBasedEntityClassifier, ENTITY_TYPES},
+    rwkv::ModelConfig,
+    rwkv_agent_kit::extract_json_object,
+};
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Default agent name
+pub const ENTITY_CLASSIFIER_AGENT: &str = "entity_classifier";
+
+/// Entity Classifier Prompt Builder
+#[derive(Debug, Clone)]
+pub struct EntityClassifierPromptBuilder;
+
+#[async_trait::async_trait]
+impl PromptBuilder for EntityClassifierPromptBuilder {
+    async fn build_prompt(
+        &self,
+        _agent: &Agent,
+        user_input: &str,
+        _tools: &[String],
+    ) -> Result<String> {
+        let prompt = format!(
+            r#"You are a named entity classifier. Classify each term into exactly one type and output ONLY valid JSON.
+Types: {}
+JSON: {{"term": "type"}}
+Constraints:
+- Keys: Every input term, unchanged;
+- person: Names of people, including Chinese names such as 张伟;
+- organization: Companies, schools, institutions;
+- concept: Anything that fits no other type.
+
+User: {}
+
+Assistant: <think>
+</think>
+```json
+"#,
+            ENTITY_TYPES.join(", "),
+            user_input.replace("\n", "")
+        );
+        Ok(prompt)
+    }
+}
+
+/// Create entity classifier agent configuration
+pub fn create_entity_classifier_config() -> AgentConfig {
+    AgentConfig {
+        name: ENTITY_CLASSIFIER_AGENT.to_string(),
+        prompt_template: "{user_input}".to_string(), // Use custom prompt builder
+        inference_params: InferenceParams {
+            max_tokens: 512,
+            temperature: 0.0, // Lower temperature for stable output
+            top_p: 0.0,
+            top_k: 128,
+            presence_penalty: 0.0,
+            frequency_penalty: 0.0,
+            stop_sequences: vec!["```".to_string()],
+            seed: None,
+            bnf_schema: None,
+        },
+        tools: vec![],
+        state: Some("None".to_string()),
+        prompt_builder: Some(Arc::new(PromptBuilderInstance::EntityClassifier(
+            EntityClassifierPromptBuilder,
+        ))),
+        save_conversations: false, // 实体分类智能体不保存对话
+        memory: MemoryConfig::default(),
+    }
+}
+
+/// Create entity classifier agent
+pub async fn create_entity_classifier() -> Result<Agent> {
+    let config = create_entity_classifier_config();
+    let model_config = ModelConfig::default();
+    Agent::new(config, &model_config)
+}
+
+type CompleteFn = dyn Fn(String) -> BoxFuture<'static, Result<String>> + Send + Sync;
+
+/// 基于 LLM 的实体分类器
+///
+/// 把所有触发词一次发给分类智能体，回复中缺失或类型不受支持的触发词退回规则分类。
+#[derive(Clone)]
+pub struct LlmEntityClassifier {
+    complete: Arc<CompleteFn>,
+    fallback: RuleBasedEntityClassifier,
+}
+
+impl std::fmt::Debug for LlmEntityClassifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LlmEntityClassifier")
+            .finish_non_exhaustive()
+    }
+}
+
+impl LlmEntityClassifier {
+    /// 用任意补全函数创建分类器，函数接收触发词的 JSON 数组并返回模型回复
+    pub fn new<F>(complete: F) -> Self
+    where
+        F: Fn(String) -> BoxFuture<'static, Result<String>> + Send + Sync + 'static,
+    {
+        Self {
+            complete: Arc::new(complete),
+            fallback: RuleBasedEntityClassifier,
+        }
+    }
+
+    /// 使用核心服务中已注册的分类智能体（默认 `entity_classifier`）
+    pub fn from_agent(
+        core_service: Arc<RwLock<CoreService>>,
+        agent_name: impl Into<String>,
+    ) -> Self {
+        let agent_name = agent_name.into();
+        Self::new(move |input| {
+            let core_service = core_service.clone();
+            let agent_name = agent_name.clone();
+            Box::pin(async move {
+                core_service
+                    .read()
+                    .await
+                    .chat(&agent_name, &input)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("{}", e))
+            })
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl EntityClassifier for LlmEntityClassifier {
+    async fn classify(&self, triggers: &[String]) -> Result<Vec<String>> {
+        if triggers.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let input = serde_json::to_string(triggers)?;
+        let response = (self.complete)(input).await?;
+        let json = extract_json_object(&response).unwrap_or(response);
+        let parsed: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_str(&json).unwrap_or_default();
+
+        Ok(triggers
+            .iter()
+            .map(|trigger| {
+                parsed
+                    .get(trigger)
+                    .and_then(|value| value.as_str())
+                    .map(|value| value.trim().to_lowercase())
+                    .filter(|value| ENTITY_TYPES.contains(&value.as_str()))
+                    .unwrap_or_else(|| self.fallback.classify_one(trigger))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_llm_classifier_types_chinese_entities() {
+        let triggers = vec![
+            "张伟".to_string(),
+            "阿里巴巴".to_string(),
+            "学习任务".to_string(),
+        ];
+
+        let heuristic = RuleBasedEntityClassifier.classify(&triggers).await.unwrap();
+        assert_eq!(heuristic[0], "concept");
+        assert_eq!(heuristic[1], "concept");
+
+        let classifier = LlmEntityClassifier::new(|input| {
+            assert_eq!(input, r#"["张伟","阿里巴巴","学习任务"]"#);
+            Box::pin(async {
+                Ok(
+                    r#"{"张伟": "person", "阿里巴巴": "Organization", "学习任务": "unknown"}"#
+                        .to_string(),
+                )
+            })
+        });
+        let types = classifier.classify(&triggers).await.unwrap();
+        // 不受支持的类型退回规则分类
+        assert_eq!(types, vec!["person", "organization", "task"]);
+    }
+}
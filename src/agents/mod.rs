@@ -2,21 +2,36 @@
 //!
 //! 提供一系列开箱即用的智能体，无需复杂配置即可启动使用
 
+pub mod attribute_extractor;
 pub mod conversation_summarizer;
+pub mod entity_classifier;
 // pub mod persona_extractor; // 已移除画像智能体
 pub mod chat;
 pub mod router;
 pub mod workflow_aggregator;
 
 // 重新导出主要的创建函数和配置
+pub use attribute_extractor::{
+    create_attribute_extractor, create_attribute_extractor_config, AgentAttributeExtractor,
+    AttributeExtractorPromptBuilder,
+};
 pub use conversation_summarizer::{
     create_conversation_summarizer, create_conversation_summarizer_config,
     ConversationSummarizerPromptBuilder,
 };
+pub use entity_classifier::{
+    create_entity_classifier, create_entity_classifier_config, EntityClassifierPromptBuilder,
+    LlmEntityClassifier,
+};
 // PersonaExtractor 相关导出已移除
 pub use chat::{create_chat, create_chat_config, ChatPromptBuilder};
-pub use router::{create_router, create_router_config, RouterPromptBuilder};
-pub use workflow_aggregator::{AgentResult, ToolResult, WorkflowAggregator, WorkflowResult};
+pub use router::{
+    create_router, create_router_config, RouterDecision, RouterOutputFormat, RouterPromptBuilder,
+};
+pub use workflow_aggregator::{
+    AgentResult, ToolResult, WorkflowAggregator, WorkflowResult, WorkflowTask, WorkflowTaskKind,
+    WorkflowTaskStatus,
+};
 
 use crate::agent::{Agent, AgentConfig};
 use anyhow::Result;
@@ -31,6 +46,10 @@ pub enum AgentType {
     Chat,
     /// 路由智能体
     Router,
+    /// 记忆属性提取智能体
+    AttributeExtractor,
+    /// 实体分类智能体
+    EntityClassifier,
 }
 
 /// 智能体工厂
@@ -46,6 +65,8 @@ impl AgentFactory {
             // AgentType::PersonaExtractor => create_persona_extractor_config(), // 已移除
             AgentType::Chat => create_chat_config(),
             AgentType::Router => create_router_config(),
+            AgentType::AttributeExtractor => create_attribute_extractor_config(),
+            AgentType::EntityClassifier => create_entity_classifier_config(),
         }
     }
 
@@ -56,6 +77,8 @@ impl AgentFactory {
             // AgentType::PersonaExtractor => create_persona_extractor().await, // 已移除
             AgentType::Chat => create_chat().await,
             AgentType::Router => create_router().await,
+            AgentType::AttributeExtractor => create_attribute_extractor().await,
+            AgentType::EntityClassifier => create_entity_classifier().await,
         }
     }
 
@@ -66,6 +89,8 @@ impl AgentFactory {
             // AgentType::PersonaExtractor, // 已移除
             AgentType::Chat,
             AgentType::Router,
+            AgentType::AttributeExtractor,
+            AgentType::EntityClassifier,
         ]
     }
 
@@ -76,6 +101,8 @@ impl AgentFactory {
             // "persona_extractor" => Some(AgentType::PersonaExtractor), // 已移除
             "chat" => Some(AgentType::Chat),
             "router" => Some(AgentType::Router),
+            "attribute_extractor" => Some(AgentType::AttributeExtractor),
+            "entity_classifier" => Some(AgentType::EntityClassifier),
             _ => None,
         }
     }
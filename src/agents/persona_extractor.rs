@@ -72,6 +72,7 @@ pub fn create_persona_extractor_config() -> AgentConfig {
             max_tokens: 2000,
             temperature: 0.1, // Low temperature for consistent output
             top_p: 0.0,
+            top_k: 128,
             stop_sequences: vec!["```".to_string()], // Stop at end of JSON block
         },
         tools: vec![], // No tools needed
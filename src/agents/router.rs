@@ -9,9 +9,123 @@ use crate::{
     rwkv::ModelConfig,
 };
 use anyhow::Result;
-use std::sync::Arc;
-// use serde::{Deserialize, Serialize}; // 暂时未使用
 use chrono::Datelike; // for weekday()
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Router output format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RouterOutputFormat {
+    /// Execution plan with agents and tools
+    #[default]
+    ExecutionPlan,
+    /// Single routing decision, parsed into [`RouterDecision`]
+    Decision,
+}
+
+/// Structured routing decision
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouterDecision {
+    /// Chosen agent
+    pub agent: String,
+    /// Confidence in 0.0 - 1.0
+    pub confidence: f32,
+    /// Why the agent was chosen
+    pub reasoning: String,
+    /// Other candidate agents with their confidence, highest first
+    pub alternatives: Vec<(String, f32)>,
+}
+
+impl RouterDecision {
+    /// Parse a router reply
+    ///
+    /// Unknown agent names map to `default_agent` with confidence 0; unknown alternatives are dropped.
+    pub fn parse(reply: &str, available_agents: &[String], default_agent: &str) -> Result<Self> {
+        let start = reply
+            .find('{')
+            .ok_or_else(|| anyhow::anyhow!("Router reply contains no JSON object: {}", reply))?;
+        let end = reply
+            .rfind('}')
+            .filter(|end| *end > start)
+            .ok_or_else(|| anyhow::anyhow!("Router reply contains no JSON object: {}", reply))?;
+        let value: serde_json::Value = serde_json::from_str(&reply[start..=end])?;
+
+        let confidence_of = |value: Option<&serde_json::Value>| {
+            value
+                .and_then(|v| v.as_f64())
+                .map(|v| (v as f32).clamp(0.0, 1.0))
+                .unwrap_or(0.0)
+        };
+        let is_known = |name: &str| available_agents.iter().any(|agent| agent == name);
+
+        let reasoning = value
+            .get("reasoning")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let (agent, confidence) = match value.get("agent").and_then(|v| v.as_str()) {
+            Some(agent) if is_known(agent) => {
+                (agent.to_string(), confidence_of(value.get("confidence")))
+            }
+            _ => (default_agent.to_string(), 0.0),
+        };
+
+        // 候选既可以是 ["name", 0.3]，也可以是 {"agent": "name", "confidence": 0.3}
+        let mut alternatives: Vec<(String, f32)> = value
+            .get("alternatives")
+            .and_then(|v| v.as_array())
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| match item {
+                        serde_json::Value::Array(pair) => Some((
+                            pair.first()?.as_str()?.to_string(),
+                            confidence_of(pair.get(1)),
+                        )),
+                        serde_json::Value::Object(object) => Some((
+                            object.get("agent")?.as_str()?.to_string(),
+                            confidence_of(object.get("confidence")),
+                        )),
+                        _ => None,
+                    })
+                    .filter(|(name, _)| is_known(name) && *name != agent)
+                    .collect()
+            })
+            .unwrap_or_default();
+        alternatives.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        Ok(Self {
+            agent,
+            confidence,
+            reasoning,
+            alternatives,
+        })
+    }
+
+    /// KBNF schema constraining the router reply to the decision JSON
+    pub fn schema(available_agents: &[String]) -> String {
+        let agents = if available_agents.is_empty() {
+            "'chat'".to_string()
+        } else {
+            available_agents
+                .iter()
+                .map(|agent| format!("'{}'", agent.replace(['\\', '\''], "")))
+                .collect::<Vec<_>>()
+                .join("|")
+        };
+
+        format!(
+            r#"start::='{{"agent":"'agent'","confidence":'confidence',"reasoning":"'reasoning'","alternatives":['alternatives?']}}';
+agent::={};
+confidence::=#"(0(\\.[0-9]{{1,2}})?|1(\\.0{{1,2}})?)";
+reasoning::=#"[^\"\\\\\n]{{0,200}}";
+alternatives::=alternative|alternative','alternatives;
+alternative::='["'agent'",'confidence']';
+"#,
+            agents
+        )
+    }
+}
 
 /// Router Prompt Builder
 #[derive(Debug, Clone)]
@@ -20,6 +134,8 @@ pub struct RouterPromptBuilder {
     pub available_agents: Vec<String>,
     /// Available tools for routing
     pub available_tools: Vec<String>,
+    /// Output format requested from the model
+    pub output_format: RouterOutputFormat,
 }
 
 impl Default for RouterPromptBuilder {
@@ -31,6 +147,7 @@ impl Default for RouterPromptBuilder {
                 "persona_extractor".to_string(),
             ],
             available_tools: vec![],
+            output_format: RouterOutputFormat::default(),
         }
     }
 }
@@ -41,6 +158,16 @@ impl RouterPromptBuilder {
         Self {
             available_agents: agents,
             available_tools: tools,
+            output_format: RouterOutputFormat::default(),
+        }
+    }
+
+    /// Create a builder that asks for a single [`RouterDecision`] among the given agents
+    pub fn for_decision(agents: Vec<String>) -> Self {
+        Self {
+            available_agents: agents,
+            available_tools: vec![],
+            output_format: RouterOutputFormat::Decision,
         }
     }
 }
@@ -71,6 +198,24 @@ impl PromptBuilder for RouterPromptBuilder {
             self.available_tools.join(", ")
         };
 
+        if self.output_format == RouterOutputFormat::Decision {
+            let prompt = format!(
+                r#"System: 你是一个路由智能体，负责判断用户输入应该交给哪个智能体处理。你需要输出标准JSON格式的路由决策。
+
+当前时间: {}，{}
+可用智能体: {}
+
+输出JSON格式：
+{{"agent": "最合适的智能体名称", "confidence": 0到1之间的置信度, "reasoning": "选择原因", "alternatives": [["其他候选智能体名称", 置信度]]}}
+
+User: {user_input}
+
+Assistant: "#,
+                now, weekday_cn, agents_list,
+            );
+            return Ok(prompt);
+        }
+
         let base_prompt = format!(
             r#"System: 你是一个路由智能体，负责分析用户输入并决定调用哪些智能体和工具来完成任务。你需要输出标准JSON格式的路由决策。
 
@@ -130,9 +275,12 @@ pub fn create_router_config() -> AgentConfig {
             max_tokens: 1024,
             temperature: 0.3,
             top_p: 0.9,
+            top_k: 128,
             presence_penalty: 0.0,
             frequency_penalty: 0.0,
             stop_sequences: vec!["\n\n".to_string()],
+            seed: None,
+            bnf_schema: None,
         },
         tools: vec![],
         state: Some("router".to_string()),
@@ -150,3 +298,96 @@ pub async fn create_router() -> Result<Agent> {
     let model_config = ModelConfig::default();
     Agent::new(config, &model_config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agents() -> Vec<String> {
+        vec!["chat".to_string(), "conversation_summarizer".to_string()]
+    }
+
+    #[test]
+    fn test_router_decision_parses_reply() {
+        let reply = r#"{"agent": "conversation_summarizer", "confidence": 0.82, "reasoning": "用户要求总结对话", "alternatives": [["chat", 0.15], ["unknown", 0.5]]}"#;
+
+        let decision = RouterDecision::parse(reply, &agents(), "chat").unwrap();
+        assert_eq!(
+            decision,
+            RouterDecision {
+                agent: "conversation_summarizer".to_string(),
+                confidence: 0.82,
+                reasoning: "用户要求总结对话".to_string(),
+                alternatives: vec![("chat".to_string(), 0.15)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_router_decision_unknown_agent_falls_back_to_default() {
+        let reply = r#"好的 {"agent": "weather", "confidence": 0.9, "reasoning": "查询天气", "alternatives": [{"agent": "conversation_summarizer", "confidence": 0.1}]}"#;
+
+        let decision = RouterDecision::parse(reply, &agents(), "chat").unwrap();
+        assert_eq!(decision.agent, "chat");
+        assert_eq!(decision.confidence, 0.0);
+        assert_eq!(
+            decision.alternatives,
+            vec![("conversation_summarizer".to_string(), 0.1)]
+        );
+
+        assert!(RouterDecision::parse("无法判断", &agents(), "chat").is_err());
+    }
+
+    #[test]
+    fn test_router_decision_schema_lists_agents() {
+        let schema = RouterDecision::schema(&agents());
+        assert!(schema.contains("agent::='chat'|'conversation_summarizer';"));
+    }
+
+    #[test]
+    fn test_router_decision_schema_compiles_and_constrains_output() {
+        use kbnf::{AcceptTokenResult, Engine, EngineLike, Token, Vocabulary};
+
+        // One token per byte, so any UTF-8 output can be fed through the engine
+        let vocabulary = Vocabulary::new(
+            (0..=255u8)
+                .map(|byte| (byte as u32, Token(vec![byte].into_boxed_slice())))
+                .collect(),
+            (0..=255u8)
+                .map(|byte| (byte as u32, format!("<{:02x}>", byte)))
+                .collect(),
+        )
+        .unwrap();
+        let mut engine = Engine::new(&RouterDecision::schema(&agents()), vocabulary).unwrap();
+
+        let mut accepted = |output: &str| {
+            engine.reset();
+            let mut result = AcceptTokenResult::Ongoing;
+            for byte in output.bytes() {
+                match engine.try_accept_new_token(byte as u32) {
+                    Ok(next) => result = next,
+                    Err(_) => return false,
+                }
+            }
+            result == AcceptTokenResult::Finished
+        };
+        let valid = r#"{"agent":"conversation_summarizer","confidence":0.82,"reasoning":"用户要求总结对话","alternatives":[["chat",0.15]]}"#;
+        assert!(accepted(valid));
+        assert!(accepted(
+            r#"{"agent":"chat","confidence":1,"reasoning":"","alternatives":[]}"#
+        ));
+        assert!(!accepted(
+            r#"{"agent":"weather","confidence":0.9,"reasoning":"","alternatives":[]}"#
+        ));
+        assert!(!accepted(
+            r#"{"agent":"chat","confidence":1.5,"reasoning":"","alternatives":[]}"#
+        ));
+        assert!(!accepted(
+            r#"{"agent":"chat","confidence":1,"reasoning":"a\b","alternatives":[]}"#
+        ));
+        assert!(!accepted("好的"));
+
+        let decision = RouterDecision::parse(valid, &agents(), "chat").unwrap();
+        assert_eq!(decision.agent, "conversation_summarizer");
+    }
+}
@@ -3,7 +3,13 @@
 //! Responsible for aggregating results from multiple agents and tools into a coherent response
 
 use anyhow::Result;
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Semaphore;
+
+use crate::core::tools::{ToolError, ToolErrorKind};
 // use std::collections::HashMap; // 暂时未使用
 
 /// Workflow execution result from an agent
@@ -21,6 +27,9 @@ pub struct ToolResult {
     pub tool_name: String,
     pub result: serde_json::Value,
     pub error: Option<String>,
+    /// Category of the error when the task failed with a `ToolError`
+    #[serde(default)]
+    pub error_kind: Option<ToolErrorKind>,
     pub execution_time_ms: u64,
 }
 
@@ -34,14 +43,227 @@ pub struct WorkflowResult {
     pub aggregated_response: String,
     pub total_execution_time_ms: u64,
     pub parallel_execution: bool,
+    /// Per-task status in submission order (filled by `run_parallel`)
+    #[serde(default)]
+    pub task_statuses: Vec<WorkflowTaskStatus>,
+}
+
+/// Kind of a workflow task
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WorkflowTaskKind {
+    Agent,
+    Tool,
+}
+
+/// Status of a single workflow task
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowTaskStatus {
+    pub name: String,
+    pub kind: WorkflowTaskKind,
+    pub error: Option<String>,
+    pub execution_time_ms: u64,
+}
+
+impl WorkflowTaskStatus {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// An independent agent or tool call to run in a workflow
+pub enum WorkflowTask {
+    Agent {
+        agent_name: String,
+        run: BoxFuture<'static, Result<String>>,
+    },
+    Tool {
+        tool_name: String,
+        run: BoxFuture<'static, Result<serde_json::Value>>,
+    },
+}
+
+impl WorkflowTask {
+    /// Create an agent task from a future producing the agent response
+    pub fn agent<F>(agent_name: impl Into<String>, run: F) -> Self
+    where
+        F: std::future::Future<Output = Result<String>> + Send + 'static,
+    {
+        Self::Agent {
+            agent_name: agent_name.into(),
+            run: Box::pin(run),
+        }
+    }
+
+    /// Create a tool task from a future producing the tool output
+    pub fn tool<F>(tool_name: impl Into<String>, run: F) -> Self
+    where
+        F: std::future::Future<Output = Result<serde_json::Value>> + Send + 'static,
+    {
+        Self::Tool {
+            tool_name: tool_name.into(),
+            run: Box::pin(run),
+        }
+    }
 }
 
+/// Output of a finished task before it is sorted into agent/tool results
+enum TaskOutput {
+    Agent(AgentResult),
+    Tool(ToolResult),
+}
+
+/// Default number of tasks allowed to run at the same time
+pub const DEFAULT_MAX_CONCURRENCY: usize = 4;
+
 /// Workflow Aggregator
 ///
 /// Aggregates results from multiple agents and tools into a coherent response
-pub struct WorkflowAggregator;
+#[derive(Debug, Clone)]
+pub struct WorkflowAggregator {
+    /// Maximum number of tasks running concurrently in `run_parallel`
+    max_concurrency: usize,
+}
+
+impl Default for WorkflowAggregator {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CONCURRENCY)
+    }
+}
 
 impl WorkflowAggregator {
+    /// Create an aggregator running at most `max_concurrency` tasks at once
+    pub fn new(max_concurrency: usize) -> Self {
+        Self {
+            max_concurrency: max_concurrency.max(1),
+        }
+    }
+
+    /// Run independent agent/tool tasks concurrently
+    ///
+    /// Results keep submission order; a failing (or panicking) task only records its own error.
+    /// The aggregated response is a simple concatenation of the successful results.
+    pub async fn run_parallel(&self, tasks: Vec<WorkflowTask>) -> WorkflowResult {
+        let started = Instant::now();
+        let semaphore = Arc::new(Semaphore::new(self.max_concurrency));
+
+        let handles: Vec<_> = tasks
+            .into_iter()
+            .map(|task| {
+                let semaphore = semaphore.clone();
+                let (name, kind) = match &task {
+                    WorkflowTask::Agent { agent_name, .. } => {
+                        (agent_name.clone(), WorkflowTaskKind::Agent)
+                    }
+                    WorkflowTask::Tool { tool_name, .. } => {
+                        (tool_name.clone(), WorkflowTaskKind::Tool)
+                    }
+                };
+                let handle = tokio::spawn(async move {
+                    let _permit = semaphore.acquire_owned().await;
+                    let task_started = Instant::now();
+                    match task {
+                        WorkflowTask::Agent { agent_name, run } => {
+                            let outcome = run.await;
+                            let execution_time_ms = task_started.elapsed().as_millis() as u64;
+                            let (response, error) = match outcome {
+                                Ok(response) => (response, None),
+                                Err(e) => (String::new(), Some(e.to_string())),
+                            };
+                            TaskOutput::Agent(AgentResult {
+                                agent_name,
+                                response,
+                                error,
+                                execution_time_ms,
+                            })
+                        }
+                        WorkflowTask::Tool { tool_name, run } => {
+                            let outcome = run.await;
+                            let execution_time_ms = task_started.elapsed().as_millis() as u64;
+                            let (result, error, error_kind) = match outcome {
+                                Ok(result) => (result, None, None),
+                                Err(e) => (
+                                    serde_json::Value::Null,
+                                    Some(e.to_string()),
+                                    e.downcast_ref::<ToolError>().map(ToolError::kind),
+                                ),
+                            };
+                            TaskOutput::Tool(ToolResult {
+                                tool_name,
+                                result,
+                                error,
+                                error_kind,
+                                execution_time_ms,
+                            })
+                        }
+                    }
+                });
+                (name, kind, handle)
+            })
+            .collect();
+
+        let mut agent_results = Vec::new();
+        let mut tool_results = Vec::new();
+        let mut task_statuses = Vec::new();
+        for (name, kind, handle) in handles {
+            let output = match handle.await {
+                Ok(output) => output,
+                Err(e) => {
+                    let error = Some(format!("task aborted: {}", e));
+                    match kind {
+                        WorkflowTaskKind::Agent => TaskOutput::Agent(AgentResult {
+                            agent_name: name.clone(),
+                            response: String::new(),
+                            error,
+                            execution_time_ms: 0,
+                        }),
+                        WorkflowTaskKind::Tool => TaskOutput::Tool(ToolResult {
+                            tool_name: name.clone(),
+                            result: serde_json::Value::Null,
+                            error,
+                            error_kind: None,
+                            execution_time_ms: 0,
+                        }),
+                    }
+                }
+            };
+
+            match output {
+                TaskOutput::Agent(result) => {
+                    task_statuses.push(WorkflowTaskStatus {
+                        name,
+                        kind,
+                        error: result.error.clone(),
+                        execution_time_ms: result.execution_time_ms,
+                    });
+                    agent_results.push(result);
+                }
+                TaskOutput::Tool(result) => {
+                    task_statuses.push(WorkflowTaskStatus {
+                        name,
+                        kind,
+                        error: result.error.clone(),
+                        execution_time_ms: result.execution_time_ms,
+                    });
+                    tool_results.push(result);
+                }
+            }
+        }
+
+        let aggregated_response =
+            Self::simple_concatenation(&agent_results, &tool_results).unwrap_or_default();
+
+        WorkflowResult {
+            user_input: String::new(),
+            analysis: String::new(),
+            agent_results,
+            tool_results,
+            aggregated_response,
+            total_execution_time_ms: started.elapsed().as_millis() as u64,
+            parallel_execution: true,
+            task_statuses,
+        }
+    }
+
     /// Aggregate results from multiple agents and tools
     pub fn aggregate_results(
         user_input: &str,
@@ -68,6 +290,7 @@ impl WorkflowAggregator {
             aggregated_response,
             total_execution_time_ms,
             parallel_execution,
+            task_statuses: Vec::new(),
         })
     }
 
@@ -397,4 +620,68 @@ mod tests {
 
         assert_eq!(result, "单一回应");
     }
+
+    #[tokio::test]
+    async fn test_run_parallel_isolates_failures() {
+        use std::time::Duration;
+
+        let aggregator = WorkflowAggregator::new(3);
+        let tasks = vec![
+            WorkflowTask::agent("chat", async {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                Ok("你好".to_string())
+            }),
+            WorkflowTask::tool("weather", async {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                Err(anyhow::anyhow!("service unavailable"))
+            }),
+            WorkflowTask::tool("calculator", async {
+                tokio::time::sleep(Duration::from_millis(150)).await;
+                Ok(serde_json::json!("42"))
+            }),
+        ];
+
+        let started = Instant::now();
+        let result = aggregator.run_parallel(tasks).await;
+        let elapsed = started.elapsed();
+
+        // 并发执行时总耗时接近最慢任务，而不是三者之和
+        assert!(elapsed >= Duration::from_millis(200));
+        assert!(elapsed < Duration::from_millis(400), "took {:?}", elapsed);
+
+        let names: Vec<_> = result
+            .task_statuses
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["chat", "weather", "calculator"]);
+        assert!(result.task_statuses[0].succeeded());
+        assert_eq!(
+            result.task_statuses[1].error.as_deref(),
+            Some("service unavailable")
+        );
+        assert!(result.task_statuses[2].succeeded());
+
+        assert_eq!(result.agent_results[0].response, "你好");
+        assert_eq!(result.tool_results.len(), 2);
+        assert!(result.aggregated_response.contains("42"));
+    }
+
+    #[tokio::test]
+    async fn test_run_parallel_classifies_tool_errors() {
+        let aggregator = WorkflowAggregator::default();
+        let tasks = vec![
+            WorkflowTask::tool("search", async {
+                Err(ToolError::NotFound("search".to_string()).into())
+            }),
+            WorkflowTask::tool("weather", async { Err(anyhow::anyhow!("timeout")) }),
+        ];
+
+        let result = aggregator.run_parallel(tasks).await;
+        assert_eq!(
+            result.tool_results[0].error_kind,
+            Some(ToolErrorKind::NotFound)
+        );
+        assert_eq!(result.tool_results[1].error_kind, None);
+    }
 }
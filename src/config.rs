@@ -3,7 +3,9 @@
 //! 本模块负责管理RWKV-Agent-Kit记忆系统的配置选项，包括数据库连接、缓存设置、
 //! 性能参数等。支持从环境变量、配置文件等多种方式加载配置。
 
+use crate::core_types::QueryType;
 use crate::error::{MemoryError, Result};
+use crate::retrieval::RetrievalStrategy;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -13,6 +15,7 @@ use std::time::Duration;
 ///
 /// 包含系统运行所需的所有配置选项。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
 pub struct Config {
     /// 数据库配置
     pub database: DatabaseConfig,
@@ -40,6 +43,7 @@ pub struct Config {
 
 /// 数据库配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct DatabaseConfig {
     /// 数据库类型
     pub database_type: DatabaseType,
@@ -67,6 +71,9 @@ pub struct DatabaseConfig {
     pub auto_migrate: bool,
     /// 备份配置
     pub backup: BackupConfig,
+    /// 命名空间：设置后向量、图节点与边等表都带上该命名空间前缀，
+    /// 同一数据库中不同命名空间的数据互相隔离。`None` 表示不区分命名空间
+    pub namespace: Option<String>,
 }
 
 /// 数据库类型枚举
@@ -77,6 +84,7 @@ pub enum DatabaseType {
 
 /// 备份配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct BackupConfig {
     /// 是否启用自动备份
     pub enabled: bool,
@@ -92,6 +100,7 @@ pub struct BackupConfig {
 
 /// 缓存配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct CacheConfig {
     /// 是否启用缓存
     pub enabled: bool,
@@ -136,6 +145,7 @@ pub struct RedisConfig {
 
 /// 预热配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct WarmupConfig {
     /// 是否启用预热
     pub enabled: bool,
@@ -160,10 +170,14 @@ pub enum WarmupStrategy {
 
 /// 向量配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct VectorConfig {
     /// 向量维度
     pub dimension: usize,
-    /// 相似度阈值
+    /// 语义检索的默认相似度阈值，默认 0.3
+    ///
+    /// 阈值过高会让相关结果被全部过滤；查询可通过 `QueryFilters::min_similarity`、
+    /// 检索约束可通过 `min_relevance` 覆盖此值。
     pub similarity_threshold: f32,
     /// 索引类型
     pub index_type: VectorIndexType,
@@ -173,6 +187,48 @@ pub struct VectorConfig {
     pub index_params: IndexParams,
     /// 搜索参数
     pub search_params: SearchParams,
+    /// 嵌入缓存容量（条目数，0 表示禁用缓存）
+    #[serde(default = "default_embedding_cache_size")]
+    pub embedding_cache_size: usize,
+    /// 写入时将向量归一化为单位向量，余弦检索时只需计算点积
+    ///
+    /// 只影响开启后写入的向量，已有数据需要重新写入。
+    #[serde(default)]
+    pub normalize_on_insert: bool,
+    /// 生成嵌入时是否做 L2 归一化，`None` 按距离度量决定：余弦度量归一化，其他度量保持模型输出
+    ///
+    /// 归一化后的向量余弦相似度等于点积，相似度阈值在不同模型间含义一致。
+    /// 存储向量与查询向量使用同一设置生成；修改后已有数据需要重新生成嵌入。
+    #[serde(default)]
+    pub normalize: Option<bool>,
+    /// 批量写入时每批生成嵌入的文本数
+    pub embed_batch_size: usize,
+    /// 批量写入时同时进行的嵌入批次数上限
+    pub embed_concurrency: usize,
+    /// 每个嵌入服务同时进行的嵌入生成数上限，超出的请求排队等待，`None` 表示不限制
+    ///
+    /// 上限按嵌入服务计算，不是进程级的：每个记忆管理器与全局嵌入服务各自按此配置
+    /// 创建限流器，记忆管理器的策略检索器与其共用同一个限流器。需要进程级上限时，
+    /// 通过 `EmbeddingService::with_limiter` 让各服务共用一个限流器。
+    #[serde(default)]
+    pub max_concurrent_embeddings: Option<usize>,
+    /// IVF 索引的旁路文件路径，`None` 表示每次启动都从向量表重建索引
+    ///
+    /// 设置命名空间时文件名前会加上命名空间，各命名空间的索引互不覆盖。
+    #[serde(default)]
+    pub index_path: Option<PathBuf>,
+}
+
+impl VectorConfig {
+    /// 生成的嵌入是否需要 L2 归一化（见 `normalize`）
+    pub fn normalizes_embeddings(&self) -> bool {
+        self.normalize
+            .unwrap_or(matches!(self.distance_metric, DistanceMetric::Cosine))
+    }
+}
+
+fn default_embedding_cache_size() -> usize {
+    1024
 }
 
 /// 向量索引类型
@@ -189,6 +245,9 @@ pub enum VectorIndexType {
 }
 
 /// 距离度量
+///
+/// 向量检索的阈值总是作用于相似度：余弦为 [-1, 1]，点积为原始点积，
+/// 欧几里得/曼哈顿为 `1 / (1 + 距离)`。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum DistanceMetric {
     /// 余弦相似度
@@ -203,6 +262,7 @@ pub enum DistanceMetric {
 
 /// 索引参数
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct IndexParams {
     /// 聚类数量（IVF）
     pub nlist: Option<usize>,
@@ -218,6 +278,7 @@ pub struct IndexParams {
 
 /// 搜索参数
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct SearchParams {
     /// 搜索的聚类数量（IVF）
     pub nprobe: Option<usize>,
@@ -231,6 +292,7 @@ pub struct SearchParams {
 
 /// 图配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct GraphConfig {
     /// 最大连接数
     pub max_connections_per_node: usize,
@@ -246,6 +308,7 @@ pub struct GraphConfig {
 
 /// PageRank配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct PageRankConfig {
     /// 阻尼因子
     pub damping_factor: f32,
@@ -255,10 +318,14 @@ pub struct PageRankConfig {
     pub convergence_threshold: f32,
     /// 个性化向量权重
     pub personalization_weight: f32,
+    /// PageRank 重排序时原相关性分数的占比，其余为归一化后的 PageRank 分数；
+    /// 1.0 表示忽略 PageRank，0.0 表示只按 PageRank 排序
+    pub rerank_alpha: f32,
 }
 
 /// 图遍历配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct TraversalConfig {
     /// 最大遍历深度
     pub max_depth: usize,
@@ -268,10 +335,32 @@ pub struct TraversalConfig {
     pub timeout_ms: u64,
     /// 是否启用循环检测
     pub cycle_detection: bool,
+    /// 单次遍历最多检查的边数，防止稠密图上的路径爆炸
+    #[serde(default = "default_max_explored_edges")]
+    pub max_explored_edges: usize,
+    /// 单次遍历最多收集的节点数，达到后停止展开并把结果标记为截断
+    #[serde(default = "default_max_nodes")]
+    pub max_nodes: usize,
+    /// 单次遍历最多收集的边数，达到后停止展开并把结果标记为截断
+    #[serde(default = "default_max_edges")]
+    pub max_edges: usize,
+}
+
+fn default_max_explored_edges() -> usize {
+    10000
+}
+
+fn default_max_nodes() -> usize {
+    1000
+}
+
+fn default_max_edges() -> usize {
+    5000
 }
 
 /// 图压缩配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct CompressionConfig {
     /// 是否启用压缩
     pub enabled: bool,
@@ -285,6 +374,7 @@ pub struct CompressionConfig {
 
 /// 性能配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct PerformanceConfig {
     /// 工作线程数
     pub worker_threads: usize,
@@ -306,6 +396,7 @@ pub struct PerformanceConfig {
 
 /// 检索配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct RetrievalConfig {
     /// 融合权重配置
     pub fusion_weights: FusionWeights,
@@ -317,10 +408,74 @@ pub struct RetrievalConfig {
     pub enable_cache: bool,
     /// 缓存TTL（秒）
     pub cache_ttl_seconds: u64,
+    /// 时间相关性的衰减时间常数 τ（天），分数为 exp(-age/τ)
+    pub temporal_tau_days: f32,
+    /// 是否把时间窗口作为硬过滤条件，窗口外的记忆不参与时间检索
+    pub temporal_hard_window: bool,
+    /// 检索解释中记录的备选结果（被阈值、标签或数量上限排除的候选）的最大数量
+    pub max_alternatives: usize,
+    /// 记忆情感与查询偏好情感（`QueryFilters::preferred_emotion`）一致时的相关性加分
+    pub emotion_match_boost: f32,
+    /// 按语言（如 `en`、`zh`）追加的主题提取停用词，与内置停用词合并使用
+    pub theme_stopwords: HashMap<String, Vec<String>>,
+    /// 按查询类型指定的检索策略，优先于内置的查询类型映射
+    pub strategy_overrides: HashMap<QueryType, RetrievalStrategy>,
+    /// 未被 `strategy_overrides` 覆盖的查询类型统一使用的检索策略，未设置时使用内置映射
+    pub default_strategy: Option<RetrievalStrategy>,
+    /// HippoRAG 检索的各阶段是否在同一个只读快照中读取（见 `VectorGraphDB::read_snapshot`）
+    ///
+    /// 快照只有一个连接，各阶段原本并发的查询会在该连接上排队执行，以检索延迟换取一致性。
+    /// 内存数据库无法创建快照，不要启用。
+    pub snapshot_reads: bool,
+}
+
+impl RetrievalConfig {
+    /// 返回查询类型对应的配置策略，未配置时返回 `None`（使用内置映射）
+    pub fn strategy_for(&self, query_type: &QueryType) -> Option<&RetrievalStrategy> {
+        self.strategy_overrides
+            .get(query_type)
+            .or(self.default_strategy.as_ref())
+    }
+
+    /// 是否配置了任何检索策略
+    pub fn has_configured_strategies(&self) -> bool {
+        !self.strategy_overrides.is_empty() || self.default_strategy.is_some()
+    }
+}
+
+/// 检查检索策略能否执行，返回不可执行的原因
+///
+/// 混合策略需要非空、与权重数量一致、权重为非负有限值，且不能嵌套混合或 HippoRAG 策略。
+fn unrunnable_strategy_reason(strategy: &RetrievalStrategy) -> Option<&'static str> {
+    match strategy {
+        RetrievalStrategy::Hybrid {
+            strategies,
+            weights,
+        } => {
+            if strategies.is_empty() {
+                Some("hybrid strategy must contain at least one strategy")
+            } else if strategies.len() != weights.len() {
+                Some("hybrid strategies and weights must have the same length")
+            } else if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+                Some("hybrid weights must be finite and non-negative")
+            } else if strategies.iter().any(|s| {
+                matches!(
+                    s,
+                    RetrievalStrategy::Hybrid { .. } | RetrievalStrategy::HippoRAG { .. }
+                )
+            }) {
+                Some("hybrid strategy cannot nest hybrid or HippoRAG strategies")
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
 }
 
 /// 融合权重配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct FusionWeights {
     /// 语义权重
     pub semantic_weight: f32,
@@ -328,7 +483,7 @@ pub struct FusionWeights {
     pub temporal_weight: f32,
     /// 结构权重
     pub structural_weight: f32,
-    /// 重要性权重
+    /// 重要性权重，取值 [0, 1]；语义检索按 `sim * (1 - w) + importance * w` 排序，0 表示纯语义
     pub importance_weight: f32,
     /// 个性化权重
     pub personalization_weight: f32,
@@ -336,6 +491,7 @@ pub struct FusionWeights {
 
 /// 学习配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct LearningConfig {
     /// 是否启用学习
     pub enabled: bool,
@@ -353,10 +509,42 @@ pub struct LearningConfig {
     pub max_interaction_history: usize,
     /// 个性化学习配置
     pub personalization: PersonalizationConfig,
+    /// 重要性评分配置
+    pub importance: ImportanceConfig,
+}
+
+/// 重要性评分配置
+///
+/// 初始重要性为 `base_importance` 加上内容长度与上下文优先级的调整，结果限制在 [0, 1]；
+/// 各权重用于按访问、时间、连接、反馈与内容信号重新计算重要性。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ImportanceConfig {
+    /// 基础重要性，取值 [0, 1]
+    pub base_importance: f32,
+    /// `Low` 优先级的偏移
+    pub low_priority_offset: f32,
+    /// `Normal` 优先级的偏移
+    pub normal_priority_offset: f32,
+    /// `High` 优先级的偏移
+    pub high_priority_offset: f32,
+    /// `Critical` 优先级的偏移
+    pub critical_priority_offset: f32,
+    /// 访问权重
+    pub access_weight: f32,
+    /// 时间权重
+    pub recency_weight: f32,
+    /// 连接权重
+    pub connection_weight: f32,
+    /// 反馈权重
+    pub feedback_weight: f32,
+    /// 内容权重
+    pub content_weight: f32,
 }
 
 /// 个性化学习配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct PersonalizationConfig {
     /// 是否启用个性化
     pub enabled: bool,
@@ -372,6 +560,7 @@ pub struct PersonalizationConfig {
 
 /// 安全配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct SecurityConfig {
     /// 是否启用认证
     pub enable_auth: bool,
@@ -391,6 +580,7 @@ pub struct SecurityConfig {
 
 /// 访问控制配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct AccessControlConfig {
     /// 是否启用访问控制
     pub enabled: bool,
@@ -404,6 +594,7 @@ pub struct AccessControlConfig {
 
 /// 审计配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct AuditConfig {
     /// 是否启用审计
     pub enabled: bool,
@@ -417,6 +608,7 @@ pub struct AuditConfig {
 
 /// 日志配置
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
 pub struct LoggingConfig {
     /// 日志级别
     pub level: LogLevel,
@@ -462,6 +654,7 @@ pub enum LogTarget {
 
 /// 特性开关
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
 pub struct FeatureFlags {
     /// 是否启用实验性特性
     pub experimental_features: bool,
@@ -475,6 +668,44 @@ pub struct FeatureFlags {
     pub custom_flags: HashMap<String, bool>,
 }
 
+/// 配置校验错误
+///
+/// 指明出错的字段路径、实际取值以及期望的约束。
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid config `{field}` = {value}: {reason}")]
+pub struct ConfigError {
+    /// 字段路径，如 `vector.similarity_threshold`
+    pub field: String,
+    /// 出错的取值
+    pub value: String,
+    /// 期望的约束
+    pub reason: String,
+}
+
+impl ConfigError {
+    /// 创建配置校验错误
+    pub fn new(
+        field: impl Into<String>,
+        value: impl std::fmt::Display,
+        reason: impl Into<String>,
+    ) -> Self {
+        Self {
+            field: field.into(),
+            value: value.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    /// 将一组校验错误拼接为单行描述
+    pub fn join(errors: &[ConfigError]) -> String {
+        errors
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
 /// 配置构建器
 #[derive(Debug, Default)]
 pub struct ConfigBuilder {
@@ -549,10 +780,53 @@ impl ConfigBuilder {
     }
 }
 
+/// 分层配置使用的环境变量前缀
+///
+/// 层级之间以 `__` 分隔，例如 `RWKV_AGENT_KIT__RETRIEVAL__MAX_RESULTS=20`
+/// 会覆盖 `[retrieval]` 下的 `max_results`。
+pub const ENV_PREFIX: &str = "RWKV_AGENT_KIT";
+
+/// 命名空间会拼入表名，只允许非空的 ASCII 字母、数字与下划线
+pub fn is_valid_namespace(namespace: &str) -> bool {
+    !namespace.is_empty()
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 impl Config {
+    /// 从 TOML 文件加载配置，并用 `RWKV_AGENT_KIT__*` 环境变量覆盖
+    ///
+    /// 文件中缺失的键使用 `Default` 中的值。
+    pub fn from_toml_path<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::load_layered(Some(path.as_ref()))
+    }
+
+    /// 按 默认值 → 配置文件 → 环境变量 的顺序合并配置
+    fn load_layered(path: Option<&std::path::Path>) -> Result<Self> {
+        let mut builder = ::config::Config::builder();
+        if let Some(path) = path {
+            builder = builder
+                .add_source(::config::File::from(path).format(::config::FileFormat::Toml));
+        }
+
+        let layered = builder
+            .add_source(
+                ::config::Environment::with_prefix(ENV_PREFIX)
+                    .separator("__")
+                    .try_parsing(true),
+            )
+            .build()?;
+
+        Ok(layered.try_deserialize()?)
+    }
+
     /// 从环境变量加载配置
+    ///
+    /// 先读取 `RWKV_AGENT_KIT__<section>__<key>` 形式的分层变量，
+    /// 再应用兼容旧版本的 `AI00_MEM_*` 变量。
     pub fn from_env() -> Result<Self> {
-        let mut config = Self::default();
+        let mut config = Self::load_layered(None)?;
 
         // 数据库URL
         if let Ok(url) = std::env::var("AI00_MEM_DATABASE_URL") {
@@ -621,74 +895,255 @@ impl Config {
         Ok(config)
     }
 
+    /// 实际使用的表前缀：设置了命名空间时为 `{table_prefix}{namespace}_`
+    pub fn namespaced_table_prefix(&self) -> String {
+        match &self.database.namespace {
+            Some(namespace) => format!("{}{}_", self.database.table_prefix, namespace),
+            None => self.database.table_prefix.clone(),
+        }
+    }
+
     /// 验证配置
-    pub fn validate(&self) -> Result<()> {
+    ///
+    /// 一次性收集所有不合法的字段，而不是在第一个错误处返回。
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
         // 验证数据库配置
-        if self.database.url.is_empty() {
-            return Err(MemoryError::validation_error(
-                "Database URL cannot be empty".to_string(),
+        if self.database.url.trim().is_empty() {
+            errors.push(ConfigError::new(
+                "database.url",
+                format!("{:?}", self.database.url),
+                "database URL cannot be empty",
             ));
         }
-
         if self.database.max_connections == 0 {
-            return Err(MemoryError::validation_error(
-                "Max connections must be greater than 0".to_string(),
+            errors.push(ConfigError::new(
+                "database.max_connections",
+                0,
+                "must be greater than 0",
             ));
         }
-
+        if let Some(namespace) = &self.database.namespace {
+            if !is_valid_namespace(namespace) {
+                errors.push(ConfigError::new(
+                    "database.namespace",
+                    format!("{:?}", namespace),
+                    "must be non-empty and contain only ASCII letters, digits or '_'",
+                ));
+            }
+        }
         if self.database.min_connections > self.database.max_connections {
-            return Err(MemoryError::validation_error(
-                "Min connections cannot be greater than max connections".to_string(),
+            errors.push(ConfigError::new(
+                "database.min_connections",
+                self.database.min_connections,
+                format!(
+                    "cannot be greater than database.max_connections ({})",
+                    self.database.max_connections
+                ),
+            ));
+        }
+
+        // 验证缓存配置
+        if self.cache.lru_capacity == 0 {
+            errors.push(ConfigError::new(
+                "cache.lru_capacity",
+                0,
+                "must be greater than 0",
             ));
         }
 
         // 验证向量配置
         if self.vector.dimension == 0 {
-            return Err(MemoryError::validation_error(
-                "Vector dimension must be greater than 0".to_string(),
+            errors.push(ConfigError::new(
+                "vector.dimension",
+                0,
+                "must be greater than 0",
             ));
         }
-
         if !(0.0..=1.0).contains(&self.vector.similarity_threshold) {
-            return Err(MemoryError::validation_error(
-                "Similarity threshold must be between 0.0 and 1.0".to_string(),
+            errors.push(ConfigError::new(
+                "vector.similarity_threshold",
+                self.vector.similarity_threshold,
+                "must be within [0.0, 1.0]",
+            ));
+        }
+        if self.vector.embed_batch_size == 0 {
+            errors.push(ConfigError::new(
+                "vector.embed_batch_size",
+                0,
+                "must be greater than 0",
+            ));
+        }
+        if self.vector.embed_concurrency == 0 {
+            errors.push(ConfigError::new(
+                "vector.embed_concurrency",
+                0,
+                "must be greater than 0",
+            ));
+        }
+        if self.vector.max_concurrent_embeddings == Some(0) {
+            errors.push(ConfigError::new(
+                "vector.max_concurrent_embeddings",
+                0,
+                "must be greater than 0",
             ));
         }
 
         // 验证图配置
         if self.graph.max_connections_per_node == 0 {
-            return Err(MemoryError::validation_error(
-                "Max connections per node must be greater than 0".to_string(),
+            errors.push(ConfigError::new(
+                "graph.max_connections_per_node",
+                0,
+                "must be greater than 0",
             ));
         }
-
         if !(0.0..=1.0).contains(&self.graph.connection_threshold) {
-            return Err(MemoryError::validation_error(
-                "Connection threshold must be between 0.0 and 1.0".to_string(),
+            errors.push(ConfigError::new(
+                "graph.connection_threshold",
+                self.graph.connection_threshold,
+                "must be within [0.0, 1.0]",
             ));
         }
 
-        // 验证PageRank配置
-        if !(0.0..=1.0).contains(&self.graph.pagerank.damping_factor) {
-            return Err(MemoryError::validation_error(
-                "PageRank damping factor must be between 0.0 and 1.0".to_string(),
+        // 验证PageRank配置：阻尼因子为 0 或 1 时迭代不会收敛到有意义的结果
+        let damping = self.graph.pagerank.damping_factor;
+        if !(damping > 0.0 && damping < 1.0) {
+            errors.push(ConfigError::new(
+                "graph.pagerank.damping_factor",
+                damping,
+                "must be within the open interval (0.0, 1.0)",
+            ));
+        }
+        if self.graph.pagerank.max_iterations == 0 {
+            errors.push(ConfigError::new(
+                "graph.pagerank.max_iterations",
+                0,
+                "must be greater than 0",
             ));
         }
+        if !(0.0..=1.0).contains(&self.graph.pagerank.rerank_alpha) {
+            errors.push(ConfigError::new(
+                "graph.pagerank.rerank_alpha",
+                self.graph.pagerank.rerank_alpha,
+                "must be within [0.0, 1.0]",
+            ));
+        }
+
+        // 验证检索配置
+        if !(0.0..=1.0).contains(&self.retrieval.fusion_weights.importance_weight) {
+            errors.push(ConfigError::new(
+                "retrieval.fusion_weights.importance_weight",
+                self.retrieval.fusion_weights.importance_weight,
+                "must be within [0.0, 1.0]",
+            ));
+        }
+        if self.retrieval.temporal_tau_days <= 0.0 || self.retrieval.temporal_tau_days.is_nan() {
+            errors.push(ConfigError::new(
+                "retrieval.temporal_tau_days",
+                self.retrieval.temporal_tau_days,
+                "must be greater than 0",
+            ));
+        }
+        if self.retrieval.emotion_match_boost < 0.0 || self.retrieval.emotion_match_boost.is_nan() {
+            errors.push(ConfigError::new(
+                "retrieval.emotion_match_boost",
+                self.retrieval.emotion_match_boost,
+                "must not be negative",
+            ));
+        }
+        let mut overrides: Vec<_> = self.retrieval.strategy_overrides.iter().collect();
+        overrides.sort_by_key(|(query_type, _)| format!("{:?}", query_type));
+        let configured_strategies = overrides
+            .into_iter()
+            .map(|(query_type, strategy)| {
+                (
+                    format!("retrieval.strategy_overrides.{:?}", query_type),
+                    strategy,
+                )
+            })
+            .chain(
+                self.retrieval
+                    .default_strategy
+                    .iter()
+                    .map(|strategy| ("retrieval.default_strategy".to_string(), strategy)),
+            );
+        for (field, strategy) in configured_strategies {
+            if let Some(reason) = unrunnable_strategy_reason(strategy) {
+                errors.push(ConfigError::new(field, format!("{:?}", strategy), reason));
+            }
+        }
+
+        // 验证学习配置
+        if !(0.0..=1.0).contains(&self.learning.learning_rate) {
+            errors.push(ConfigError::new(
+                "learning.learning_rate",
+                self.learning.learning_rate,
+                "must be within [0.0, 1.0]",
+            ));
+        }
+        let importance = &self.learning.importance;
+        if !(0.0..=1.0).contains(&importance.base_importance) {
+            errors.push(ConfigError::new(
+                "learning.importance.base_importance",
+                importance.base_importance,
+                "must be within [0.0, 1.0]",
+            ));
+        }
+        for (field, offset) in [
+            ("low_priority_offset", importance.low_priority_offset),
+            ("normal_priority_offset", importance.normal_priority_offset),
+            ("high_priority_offset", importance.high_priority_offset),
+            (
+                "critical_priority_offset",
+                importance.critical_priority_offset,
+            ),
+        ] {
+            if !(-1.0..=1.0).contains(&offset) {
+                errors.push(ConfigError::new(
+                    format!("learning.importance.{}", field),
+                    offset,
+                    "must be within [-1.0, 1.0]",
+                ));
+            }
+        }
+        for (field, weight) in [
+            ("access_weight", importance.access_weight),
+            ("recency_weight", importance.recency_weight),
+            ("connection_weight", importance.connection_weight),
+            ("feedback_weight", importance.feedback_weight),
+            ("content_weight", importance.content_weight),
+        ] {
+            if weight < 0.0 || weight.is_nan() {
+                errors.push(ConfigError::new(
+                    format!("learning.importance.{}", field),
+                    weight,
+                    "must not be negative",
+                ));
+            }
+        }
 
         // 验证性能配置
         if self.performance.worker_threads == 0 {
-            return Err(MemoryError::validation_error(
-                "Worker threads must be greater than 0".to_string(),
+            errors.push(ConfigError::new(
+                "performance.worker_threads",
+                0,
+                "must be greater than 0",
             ));
         }
-
         if self.performance.batch_size == 0 {
-            return Err(MemoryError::validation_error(
-                "Batch size must be greater than 0".to_string(),
+            errors.push(ConfigError::new(
+                "performance.batch_size",
+                0,
+                "must be greater than 0",
             ));
         }
 
-        Ok(())
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
     }
 
     /// 获取数据库连接超时时间
@@ -725,6 +1180,7 @@ impl Default for DatabaseConfig {
             table_prefix: "ai00_".to_string(),
             auto_migrate: true,
             backup: BackupConfig::default(),
+            namespace: None,
         }
     }
 }
@@ -769,11 +1225,18 @@ impl Default for VectorConfig {
     fn default() -> Self {
         Self {
             dimension: 256, // 标准BERT模型输出维度
-            similarity_threshold: 0.7,
+            similarity_threshold: 0.3,
             index_type: VectorIndexType::Flat,
             distance_metric: DistanceMetric::Cosine,
             index_params: IndexParams::default(),
             search_params: SearchParams::default(),
+            embedding_cache_size: default_embedding_cache_size(),
+            normalize_on_insert: false,
+            normalize: None,
+            embed_batch_size: 32,
+            embed_concurrency: 2,
+            max_concurrent_embeddings: None,
+            index_path: None,
         }
     }
 }
@@ -820,6 +1283,7 @@ impl Default for PageRankConfig {
             max_iterations: 100,
             convergence_threshold: 1e-6,
             personalization_weight: 0.15,
+            rerank_alpha: 0.7,
         }
     }
 }
@@ -831,6 +1295,9 @@ impl Default for TraversalConfig {
             max_visited_nodes: 1000,
             timeout_ms: 10000,
             cycle_detection: true,
+            max_explored_edges: default_max_explored_edges(),
+            max_nodes: default_max_nodes(),
+            max_edges: default_max_edges(),
         }
     }
 }
@@ -872,6 +1339,24 @@ impl Default for LearningConfig {
             min_learning_samples: 10,
             max_interaction_history: 1000,
             personalization: PersonalizationConfig::default(),
+            importance: ImportanceConfig::default(),
+        }
+    }
+}
+
+impl Default for ImportanceConfig {
+    fn default() -> Self {
+        Self {
+            base_importance: 0.5,
+            low_priority_offset: -0.1,
+            normal_priority_offset: 0.0,
+            high_priority_offset: 0.2,
+            critical_priority_offset: 0.3,
+            access_weight: 0.3,
+            recency_weight: 0.2,
+            connection_weight: 0.2,
+            feedback_weight: 0.2,
+            content_weight: 0.1,
         }
     }
 }
@@ -884,6 +1369,14 @@ impl Default for RetrievalConfig {
             timeout_ms: 5000,
             enable_cache: true,
             cache_ttl_seconds: 3600,
+            temporal_tau_days: 7.0,
+            temporal_hard_window: false,
+            max_alternatives: 10,
+            emotion_match_boost: 0.1,
+            theme_stopwords: HashMap::new(),
+            strategy_overrides: HashMap::new(),
+            default_strategy: None,
+            snapshot_reads: false,
         }
     }
 }
@@ -1018,6 +1511,51 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_config_validation_reports_fields() {
+        let mut config = Config::default();
+        config.vector.similarity_threshold = 1.5;
+        config.graph.pagerank.damping_factor = 1.0;
+        config.vector.dimension = 0;
+
+        let errors = config.validate().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "vector.dimension",
+                "vector.similarity_threshold",
+                "graph.pagerank.damping_factor"
+            ]
+        );
+        assert_eq!(errors[1].value, "1.5");
+        assert_eq!(errors[2].value, "1");
+        assert!(errors[1].to_string().contains("vector.similarity_threshold"));
+    }
+
+    #[test]
+    fn test_config_validation_rejects_zero_damping() {
+        let mut config = Config::default();
+        config.graph.pagerank.damping_factor = 0.0;
+
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "graph.pagerank.damping_factor");
+        assert_eq!(errors[0].value, "0");
+    }
+
+    #[test]
+    fn test_config_validation_rejects_out_of_range_rerank_alpha() {
+        let mut config = Config::default();
+        config.graph.pagerank.rerank_alpha = 1.0;
+        assert!(config.validate().is_ok());
+
+        config.graph.pagerank.rerank_alpha = 1.2;
+        let errors = config.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "graph.pagerank.rerank_alpha");
+    }
+
     #[test]
     fn test_config_from_env() {
         std::env::set_var("AI00_MEM_DATABASE_URL", "postgresql://localhost/test");
@@ -1035,6 +1573,74 @@ mod tests {
         std::env::remove_var("AI00_MEM_VECTOR_DIMENSION");
     }
 
+    const README_TOML: &str = r#"
+[database]
+url = "sqlite:memory.db"
+
+[cache]
+lru_capacity = 1000
+
+[vector]
+similarity_threshold = 0.75
+
+[retrieval]
+max_results = 50
+
+[learning]
+learning_rate = 0.02
+importance_decay_factor = 0.9
+"#;
+
+    #[test]
+    fn test_config_from_toml_path() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(README_TOML.as_bytes()).unwrap();
+
+        let config = temp_env::with_vars_unset(
+            [
+                "RWKV_AGENT_KIT__RETRIEVAL__MAX_RESULTS",
+                "RWKV_AGENT_KIT__VECTOR__SIMILARITY_THRESHOLD",
+            ],
+            || Config::from_toml_path(temp_file.path()).unwrap(),
+        );
+
+        assert_eq!(config.database.url, "sqlite:memory.db");
+        assert_eq!(config.cache.lru_capacity, 1000);
+        assert_eq!(config.vector.similarity_threshold, 0.75);
+        assert_eq!(config.retrieval.max_results, 50);
+        assert_eq!(config.learning.learning_rate, 0.02);
+        assert_eq!(config.learning.importance_decay_factor, 0.9);
+
+        // 未出现的键回退到默认值
+        assert_eq!(config.database.max_connections, 10);
+        assert_eq!(config.vector.dimension, 256);
+        assert_eq!(config.retrieval.timeout_ms, 5000);
+        assert_eq!(config.graph, GraphConfig::default());
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_env_overrides_toml() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(README_TOML.as_bytes()).unwrap();
+
+        let config = temp_env::with_vars(
+            [
+                ("RWKV_AGENT_KIT__RETRIEVAL__MAX_RESULTS", Some("20")),
+                ("RWKV_AGENT_KIT__VECTOR__SIMILARITY_THRESHOLD", Some("0.5")),
+                ("RWKV_AGENT_KIT__GRAPH__PAGERANK__MAX_ITERATIONS", Some("7")),
+            ],
+            || Config::from_toml_path(temp_file.path()).unwrap(),
+        );
+
+        assert_eq!(config.retrieval.max_results, 20);
+        assert_eq!(config.vector.similarity_threshold, 0.5);
+        assert_eq!(config.graph.pagerank.max_iterations, 7);
+        // 未被覆盖的键保持文件中的值
+        assert_eq!(config.database.url, "sqlite:memory.db");
+        assert_eq!(config.learning.learning_rate, 0.02);
+    }
+
     #[test]
     fn test_config_from_file() {
         let config_content = r#"
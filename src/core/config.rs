@@ -5,12 +5,22 @@ use crate::db::DatabaseConfig;
 use crate::rwkv::ModelConfig;
 use serde::{Deserialize, Serialize};
 
+/// 流式对话通道的默认缓冲容量（分段数）
+pub const DEFAULT_STREAM_BUFFER: usize = 32;
+
+fn default_stream_buffer() -> usize {
+    DEFAULT_STREAM_BUFFER
+}
+
 /// 主配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KitConfig {
     pub model: ModelConfig,
     pub agents: Vec<AgentConfig>,
     pub database: DatabaseConfig,
+    /// 流式对话通道的缓冲容量，消费方跟不上时生成会等待而不是无限堆积
+    #[serde(default = "default_stream_buffer")]
+    pub stream_buffer: usize,
 }
 
 impl Default for KitConfig {
@@ -19,6 +29,7 @@ impl Default for KitConfig {
             model: ModelConfig::default(),
             agents: vec![AgentConfig::default()],
             database: DatabaseConfig::default(),
+            stream_buffer: DEFAULT_STREAM_BUFFER,
         }
     }
 }
@@ -518,7 +518,7 @@ mod tests {
     fn test_error_json_serialization() {
         let error = RwkvError::new(ErrorCode::UserInputTooLong, "输入超过限制");
         let json = error.to_json();
-        assert!(json.contains("7001"));
+        assert!(json.contains("\"code\":\"UserInputTooLong\""));
         assert!(json.contains("输入超过限制"));
     }
 
@@ -323,22 +323,73 @@ impl ErrorHandler {
                     );
 
                     tokio::time::sleep(delay).await;
+                    delay = self.next_retry_delay(delay);
+                }
+            }
+        }
+    }
+
+    /// 按重试配置执行操作，只重试 `retryable` 判定为瞬时的错误
+    ///
+    /// 与 [`Self::execute_with_retry`] 不同，错误保持调用方自己的类型原样返回。
+    pub async fn execute_with_retry_when<F, Fut, T, E, R>(
+        &self,
+        operation: F,
+        retryable: R,
+        context: &str,
+    ) -> Result<T, E>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<T, E>>,
+        R: Fn(&E) -> bool,
+        E: std::fmt::Display,
+    {
+        let mut attempts = 0;
+        let mut delay = Duration::from_millis(self.config.retry_delay_ms);
 
-                    // 计算下次延迟
-                    if self.config.exponential_backoff {
-                        delay = Duration::from_millis(
-                            ((delay.as_millis() as f64 * self.config.backoff_multiplier) as u64)
-                                .min(self.config.max_retry_delay_ms),
-                        );
+        loop {
+            match operation().await {
+                Ok(result) => return Ok(result),
+                Err(error) => {
+                    attempts += 1;
+                    if !self.config.enable_recovery
+                        || attempts > self.config.max_retries
+                        || !retryable(&error)
+                    {
+                        return Err(error);
                     }
+
+                    log::warn!(
+                        "{} 失败，{}ms后重试 (第{}/{}次): {}",
+                        context,
+                        delay.as_millis(),
+                        attempts,
+                        self.config.max_retries,
+                        error
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = self.next_retry_delay(delay);
                 }
             }
         }
     }
 
+    /// 计算下次重试的等待时间
+    fn next_retry_delay(&self, delay: Duration) -> Duration {
+        if self.config.exponential_backoff {
+            Duration::from_millis(
+                ((delay.as_millis() as f64 * self.config.backoff_multiplier) as u64)
+                    .min(self.config.max_retry_delay_ms),
+            )
+        } else {
+            delay
+        }
+    }
+
     /// 检查是否应该重试
     fn should_retry(&self, error: &RwkvError, attempts: u32) -> bool {
-        if attempts >= self.config.max_retries {
+        // max_retries 是首次执行之外的重试次数
+        if attempts > self.config.max_retries {
             return false;
         }
 
@@ -5,6 +5,7 @@ pub mod agent_config;
 pub mod config;
 pub mod error;
 pub mod error_handler;
+pub mod observer;
 pub mod rwkv_singleton;
 pub mod service;
 pub mod tools;
@@ -14,6 +15,7 @@ pub use agent_config::*;
 pub use config::*;
 pub use error::*;
 pub use error_handler::*;
+pub use observer::*;
 pub use rwkv_singleton::*;
 pub use service::*;
 pub use tools::*;
@@ -0,0 +1,245 @@
+//! 对话生命周期观察者
+//!
+//! 集成方可以通过实现 [`ChatObserver`] 观察对话过程中的关键事件（提示词构建、
+//! 生成完成、记忆保存、语义片段创建），用于日志、审核或统计，而无需修改本库。
+
+use anyhow::Result;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use super::types::ChatResponse;
+use crate::db::SemanticChunk;
+
+/// 对话生命周期观察者
+///
+/// 所有回调都有空的默认实现，只需实现关心的事件。回调在后台任务中执行，
+/// 不会阻塞对话；返回的错误只记录日志，不会影响对话结果。
+#[async_trait]
+pub trait ChatObserver: Send + Sync + std::fmt::Debug {
+    /// 提示词构建完成、即将发送给模型
+    async fn on_prompt_built(&self, _agent_name: &str, _prompt: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// 模型生成完成
+    async fn on_response(
+        &self,
+        _agent_name: &str,
+        _user_input: &str,
+        _response: &ChatResponse,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// 对话轮次已写入会话
+    async fn on_memory_saved(
+        &self,
+        _session_id: i64,
+        _agent_name: &str,
+        _user_input: &str,
+        _response: &str,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// 语义片段已创建
+    async fn on_semantic_chunk(
+        &self,
+        _session_id: i64,
+        _chunk_id: i64,
+        _chunk: &SemanticChunk,
+    ) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// 对话生命周期事件
+#[derive(Debug, Clone)]
+pub enum ChatEvent {
+    PromptBuilt {
+        agent_name: String,
+        prompt: String,
+    },
+    Response {
+        agent_name: String,
+        user_input: String,
+        response: ChatResponse,
+    },
+    MemorySaved {
+        session_id: i64,
+        agent_name: String,
+        user_input: String,
+        response: String,
+    },
+    SemanticChunk {
+        session_id: i64,
+        chunk_id: i64,
+        chunk: SemanticChunk,
+    },
+}
+
+impl ChatEvent {
+    /// 将事件分发给观察者对应的回调
+    async fn dispatch(&self, observer: &dyn ChatObserver) -> Result<()> {
+        match self {
+            ChatEvent::PromptBuilt { agent_name, prompt } => {
+                observer.on_prompt_built(agent_name, prompt).await
+            }
+            ChatEvent::Response {
+                agent_name,
+                user_input,
+                response,
+            } => observer.on_response(agent_name, user_input, response).await,
+            ChatEvent::MemorySaved {
+                session_id,
+                agent_name,
+                user_input,
+                response,
+            } => {
+                observer
+                    .on_memory_saved(*session_id, agent_name, user_input, response)
+                    .await
+            }
+            ChatEvent::SemanticChunk {
+                session_id,
+                chunk_id,
+                chunk,
+            } => {
+                observer
+                    .on_semantic_chunk(*session_id, *chunk_id, chunk)
+                    .await
+            }
+        }
+    }
+}
+
+/// 在后台任务中通知所有观察者，错误只记录日志
+pub fn notify_observers(observers: &[Arc<dyn ChatObserver>], event: ChatEvent) {
+    if observers.is_empty() {
+        return;
+    }
+
+    let observers = observers.to_vec();
+    tokio::spawn(async move {
+        for observer in observers {
+            if let Err(e) = event.dispatch(observer.as_ref()).await {
+                log::warn!("Chat observer {:?} failed: {}", observer, e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[derive(Debug)]
+    struct RecordingObserver {
+        sender: flume::Sender<String>,
+    }
+
+    #[async_trait]
+    impl ChatObserver for RecordingObserver {
+        async fn on_prompt_built(&self, agent_name: &str, prompt: &str) -> Result<()> {
+            self.sender
+                .send(format!("prompt:{}:{}", agent_name, prompt))?;
+            Ok(())
+        }
+
+        async fn on_response(
+            &self,
+            agent_name: &str,
+            _user_input: &str,
+            response: &ChatResponse,
+        ) -> Result<()> {
+            self.sender.send(format!(
+                "response:{}:{}:{}",
+                agent_name, response.text, response.total_tokens
+            ))?;
+            Ok(())
+        }
+
+        async fn on_memory_saved(
+            &self,
+            session_id: i64,
+            _agent_name: &str,
+            user_input: &str,
+            response: &str,
+        ) -> Result<()> {
+            self.sender
+                .send(format!("saved:{}:{}:{}", session_id, user_input, response))?;
+            Ok(())
+        }
+
+        async fn on_semantic_chunk(
+            &self,
+            session_id: i64,
+            chunk_id: i64,
+            chunk: &SemanticChunk,
+        ) -> Result<()> {
+            self.sender.send(format!(
+                "chunk:{}:{}:{}",
+                session_id, chunk_id, chunk.summary
+            ))?;
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_observer_receives_chat_turn_events() {
+        let (sender, receiver) = flume::unbounded();
+        let observers: Vec<Arc<dyn ChatObserver>> = vec![Arc::new(RecordingObserver { sender })];
+
+        let events = [
+            ChatEvent::PromptBuilt {
+                agent_name: "chat".to_string(),
+                prompt: "User: 你好".to_string(),
+            },
+            ChatEvent::Response {
+                agent_name: "chat".to_string(),
+                user_input: "你好".to_string(),
+                response: ChatResponse::new("你好呀".to_string(), 4, 3, 10),
+            },
+            ChatEvent::MemorySaved {
+                session_id: 7,
+                agent_name: "chat".to_string(),
+                user_input: "你好".to_string(),
+                response: "你好呀".to_string(),
+            },
+            ChatEvent::SemanticChunk {
+                session_id: 7,
+                chunk_id: 1,
+                chunk: SemanticChunk {
+                    id: Some(1),
+                    title: None,
+                    summary: "用户打招呼".to_string(),
+                    keywords: None,
+                    embedding: None,
+                    last_ref_ts: None,
+                    weight: 0.5,
+                },
+            },
+        ];
+        let mut received = Vec::new();
+        for event in events {
+            notify_observers(&observers, event);
+            // 每个事件在独立任务中分发，逐个等待以保证顺序
+            let payload = tokio::time::timeout(Duration::from_secs(1), receiver.recv_async())
+                .await
+                .unwrap()
+                .unwrap();
+            received.push(payload);
+        }
+
+        assert_eq!(
+            received,
+            vec![
+                "prompt:chat:User: 你好",
+                "response:chat:你好呀:7",
+                "saved:7:你好:你好呀",
+                "chunk:7:1:用户打招呼",
+            ]
+        );
+    }
+}
@@ -3,7 +3,7 @@
 //! 提供全局的 RWKV 服务实例，确保整个应用程序中只有一个 RWKV 推理引擎实例。
 //! 这样可以避免重复初始化，节省资源，并确保配置的一致性。
 
-use crate::core::config::KitConfig;
+use crate::core::config::{KitConfig, DEFAULT_STREAM_BUFFER};
 use crate::core::service::RwkvAgentKit as CoreService;
 use crate::rwkv::config::ModelConfig;
 use anyhow::Result;
@@ -44,6 +44,7 @@ pub async fn preload_global_rwkv_service_with_config(model_config: ModelConfig)
         model: model_config,
         agents: vec![],
         database: crate::db::DatabaseConfig::default(),
+        stream_buffer: DEFAULT_STREAM_BUFFER,
     };
 
     // 初始化核心服务
@@ -4,17 +4,22 @@ use ai00_core::{GenerateKind, GenerateRequest, ThreadRequest, Token};
 use flume::Sender;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 use web_rwkv::tokenizer::Tokenizer;
 
 use super::{
     error::{ErrorCode, RwkvError, RwkvResult},
     error_handler::ErrorHandler,
-    tools::{SharedToolRegistry, ToolRegistry},
+    observer::{notify_observers, ChatEvent, ChatObserver},
+    tools::{SharedToolRegistry, ToolError, ToolRegistry},
+    types::ChatResponse,
     KitConfig,
 };
 use crate::agent::prompt::PromptBuilder;
 use crate::agent::Agent;
 use crate::db::DatabaseManager;
+use crate::rwkv::config::ModelConfig;
+use crate::utils::{count_tokens, ConcurrencyLimiter};
 
 /// 主服务结构
 pub struct RwkvAgentKit {
@@ -26,6 +31,10 @@ pub struct RwkvAgentKit {
     pub database_manager: Option<Arc<DatabaseManager>>,
     /// 错误处理器
     pub error_handler: Arc<ErrorHandler>,
+    /// 对话生命周期观察者
+    pub observers: Vec<Arc<dyn ChatObserver>>,
+    /// 生成限流器，上限取 `config.model.max_concurrent_generations`
+    pub generation_limiter: ConcurrencyLimiter,
 }
 
 impl std::fmt::Debug for RwkvAgentKit {
@@ -39,6 +48,8 @@ impl std::fmt::Debug for RwkvAgentKit {
             .field("tools", &"SharedToolRegistry")
             .field("database_manager", &self.database_manager.is_some())
             .field("error_handler", &"ErrorHandler")
+            .field("observers", &self.observers.len())
+            .field("generation_limiter", &self.generation_limiter)
             .finish()
     }
 }
@@ -69,19 +80,7 @@ impl RwkvAgentKit {
         // 加载分词器
         let tokenizer = error_handler
             .execute_with_retry(
-                || async {
-                    let tokenizer_content = std::fs::read_to_string(&config.model.tokenizer_path)
-                        .map_err(|e| {
-                        RwkvError::new(ErrorCode::ModelLoadFailed, format!("分词器加载失败: {}", e))
-                    })?;
-
-                    Tokenizer::new(&tokenizer_content).map_err(|e| {
-                        RwkvError::new(
-                            ErrorCode::ModelLoadFailed,
-                            format!("分词器初始化失败: {}", e),
-                        )
-                    })
-                },
+                || async { load_tokenizer(&config.model.tokenizer_path) },
                 "tokenizer_loading",
             )
             .await?;
@@ -124,6 +123,17 @@ impl RwkvAgentKit {
 
         println!("✅ 模型加载完成，结果: {:?}", reload_result);
 
+        Self::with_sender(config, sender, Arc::new(tokenizer))
+    }
+
+    /// 使用已启动的 ai00-core 请求通道组装服务，不加载分词器与模型
+    ///
+    /// 推理请求都发往 `sender`，由调用方负责其另一端。
+    pub(crate) fn with_sender(
+        config: KitConfig,
+        sender: Sender<ThreadRequest>,
+        tokenizer: Arc<Tokenizer>,
+    ) -> RwkvResult<Self> {
         // 初始化工具注册表
         let tools = Arc::new(tokio::sync::RwLock::new(ToolRegistry::default()));
 
@@ -139,17 +149,44 @@ impl RwkvAgentKit {
             agents.insert(agent_config.name, agent);
         }
 
+        let generation_limiter =
+            ConcurrencyLimiter::new("generation", config.model.max_concurrent_generations);
+
         Ok(Self {
             config,
             sender,
             agents,
             tools,
-            tokenizer: Arc::new(tokenizer),
+            tokenizer,
             database_manager: None,
-            error_handler,
+            error_handler: Arc::new(ErrorHandler::default()),
+            observers: Vec::new(),
+            generation_limiter,
         })
     }
 
+    /// 切换到新的模型配置
+    ///
+    /// 先加载新分词器并按新配置重建智能体，再通过 ai00-core 重载模型；任一步失败都保留
+    /// 旧模型（ai00-core 已卸载旧模型时会重新加载旧配置）。
+    pub async fn reload_model(&mut self, new_config: ModelConfig) -> RwkvResult<()> {
+        let tokenizer = load_tokenizer(&new_config.tokenizer_path)?;
+
+        // 只替换各智能体的 state，保留其采样器与对话记忆
+        let agents: HashMap<String, Agent> = self
+            .agents
+            .iter()
+            .map(|(name, agent)| (name.clone(), agent.with_model(&new_config)))
+            .collect();
+
+        let loader = Ai00ModelLoader::new(self.sender.clone());
+        swap_model(&loader, &mut self.config.model, new_config).await?;
+
+        self.tokenizer = Arc::new(tokenizer);
+        self.agents = agents;
+        Ok(())
+    }
+
     /// 注册新的智能体
     pub fn register_agent(&mut self, agent_config: crate::agent::AgentConfig) -> RwkvResult<()> {
         let agent = Agent::new(agent_config.clone(), &self.config.model).map_err(|e| {
@@ -164,12 +201,12 @@ impl RwkvAgentKit {
 
     /// 注册工具
     pub async fn register_tool<T: crate::core::Tool + 'static>(&self, tool: T) -> RwkvResult<()> {
+        let name = tool.name();
         let mut tools = self.tools.write().await;
-        tools.register(tool);
-        println!(
-            "🔧 工具 '{}' 已注册",
-            tools.tools.keys().last().unwrap_or(&"unknown".to_string())
-        );
+        tools.register(tool).map_err(|e| {
+            RwkvError::new(ErrorCode::AgentToolError, format!("工具注册失败: {}", e))
+        })?;
+        log::info!("Tool '{}' registered", name);
         Ok(())
     }
 
@@ -180,28 +217,14 @@ impl RwkvAgentKit {
     }
 
     /// 执行工具
+    ///
+    /// 只有执行失败会按错误处理配置重试；工具不存在或参数不合法时立即返回原始的 [`ToolError`]。
     pub async fn execute_tool(
         &self,
         tool_name: &str,
         params: serde_json::Value,
-    ) -> RwkvResult<serde_json::Value> {
-        self.error_handler
-            .execute_with_retry(
-                || async {
-                    let tools = self.tools.read().await;
-                    tools
-                        .execute_tool(tool_name, params.clone())
-                        .await
-                        .map_err(|e| {
-                            RwkvError::new(
-                                ErrorCode::AgentToolError,
-                                format!("工具执行失败: {}", e),
-                            )
-                        })
-                },
-                &format!("tool_execution_{}", tool_name),
-            )
-            .await
+    ) -> Result<serde_json::Value, ToolError> {
+        execute_tool_with_retry(&self.error_handler, &self.tools, tool_name, params).await
     }
 
     /// 与指定智能体聊天
@@ -210,6 +233,12 @@ impl RwkvAgentKit {
             .await
     }
 
+    /// 与指定智能体聊天，同时返回 token 用量
+    pub async fn chat_with_usage(&self, agent_name: &str, user_input: &str) -> RwkvResult<ChatResponse> {
+        self.chat_with_usage_options(agent_name, user_input, None, None)
+            .await
+    }
+
     /// 与指定智能体聊天（可指定 BNF schema 和停止序列）
     pub async fn chat_with_options(
         &self,
@@ -218,16 +247,108 @@ impl RwkvAgentKit {
         bnf_schema: Option<String>,
         stop_sequences: Option<Vec<String>>,
     ) -> RwkvResult<String> {
+        self.chat_with_usage_options(agent_name, user_input, bnf_schema, stop_sequences)
+            .await
+            .map(|response| response.text)
+    }
+
+    /// 与指定智能体聊天并统计用量（可指定 BNF schema 和停止序列）
+    ///
+    /// 提示词 token 数按最终构建的提示词计算，生成 token 数按生成的原始内容计算。
+    pub async fn chat_with_usage_options(
+        &self,
+        agent_name: &str,
+        user_input: &str,
+        bnf_schema: Option<String>,
+        stop_sequences: Option<Vec<String>>,
+    ) -> RwkvResult<ChatResponse> {
         self.error_handler.execute_with_retry(
             || {
                 let bnf_schema = bnf_schema.clone();
                 let stop_sequences = stop_sequences.clone();
                 async move {
-                let agent = self.agents.get(agent_name)
-                    .ok_or_else(|| RwkvError::new(
-                        ErrorCode::AgentNotFound,
-                        format!("Agent '{}' not found", agent_name)
-                    ))?;
+                let started = Instant::now();
+                let (agent, generate_request) = self
+                    .prepare_generate_request(agent_name, user_input, bnf_schema, stop_sequences)
+                    .await?;
+                let prompt_tokens = count_tokens(Some(self.tokenizer.as_ref()), &generate_request.prompt);
+                let stop = generate_request.stop.clone();
+                // 收集完响应前一直占用生成许可
+                let _permit = self.acquire_generation_permit().await?;
+                let result_receiver = self.send_generate_request(generate_request).await?;
+
+        // 阶段 6: 收集响应结果，在第一个停止序列处截断
+        let result = collect_tokens(result_receiver, &stop).await;
+
+        // 阶段 7: 使用Agent自己的Memory系统保存对话
+        if agent.config.memory.enabled {
+            agent.memory().add_conversation(user_input.to_string(), result.clone()).await;
+        }
+
+        // 注意：数据库保存逻辑已移至 rwkv_agent_kit.rs 的 chat_with_memory 方法中
+        // 避免重复保存，这里不再直接保存到数据库
+        // if agent.config.save_conversations { ... } // 已注释掉以避免重复保存
+
+                let completion_tokens = count_tokens(Some(self.tokenizer.as_ref()), &result);
+                Ok(ChatResponse::new(
+                    result,
+                    prompt_tokens,
+                    completion_tokens,
+                    started.elapsed().as_millis() as u64,
+                ))
+            }
+            },
+            &format!("chat_{}", agent_name)
+        ).await
+    }
+
+    /// 与指定智能体进行流式聊天，逐段返回生成的内容
+    ///
+    /// 返回的接收端在生成结束（或出错）后关闭；若智能体启用了记忆，完整回复会在结束时写入其对话历史。
+    /// 通道容量为 `config.stream_buffer`，消费方过慢时转发任务会等待；丢弃接收端会中止生成。
+    pub async fn chat_stream(
+        &self,
+        agent_name: &str,
+        user_input: &str,
+        bnf_schema: Option<String>,
+        stop_sequences: Option<Vec<String>>,
+    ) -> RwkvResult<flume::Receiver<String>> {
+        let (agent, generate_request) = self
+            .prepare_generate_request(agent_name, user_input, bnf_schema, stop_sequences)
+            .await?;
+        let stop = generate_request.stop.clone();
+        let permit = self.acquire_generation_permit().await?;
+        let result_receiver = self.send_generate_request(generate_request).await?;
+
+        let (chunk_sender, chunk_receiver) = flume::bounded(self.config.stream_buffer.max(1));
+        let memory = agent.config.memory.enabled.then(|| agent.memory().clone());
+        let user_input = user_input.to_string();
+        tokio::spawn(async move {
+            let result = forward_stream_tokens(result_receiver, chunk_sender, stop).await;
+            // 生成结束后即归还许可，无需等待写入记忆
+            drop(permit);
+
+            if let Some(memory) = memory {
+                memory.add_conversation(user_input, result).await;
+            }
+        });
+
+        Ok(chunk_receiver)
+    }
+
+    /// 构建推理请求：组装提示词并注入历史、画像与工具信息
+    async fn prepare_generate_request(
+        &self,
+        agent_name: &str,
+        user_input: &str,
+        bnf_schema: Option<String>,
+        stop_sequences: Option<Vec<String>>,
+    ) -> RwkvResult<(&Agent, GenerateRequest)> {
+        let agent = self.agents.get(agent_name)
+            .ok_or_else(|| RwkvError::new(
+                ErrorCode::AgentNotFound,
+                format!("Agent '{}' not found", agent_name)
+            ))?;
 
         // 使用智能体的提示词构建器构建提示词
         let prompt_builder = agent.config.get_prompt_builder();
@@ -257,7 +378,13 @@ impl RwkvAgentKit {
         // 因为自定义 PromptBuilder 会在 build_prompt 中处理历史记录
         if agent.config.memory.enabled && agent.config.prompt_builder.is_none() {
             // 使用Agent自己的Memory系统获取历史记录
-            let history_text = agent.memory().get_history().await;
+            let history_text = agent
+                .memory()
+                .get_history_within_budget(
+                    agent.config.memory.context_token_budget,
+                    Some(self.tokenizer.as_ref()),
+                )
+                .await;
 
             if !history_text.is_empty() {
                 // 注入到 prompt
@@ -342,9 +469,18 @@ impl RwkvAgentKit {
         let tools_list = if available_tools.is_empty() { "无".to_string() } else { available_tools.join(", ") };
         prompt = prompt.replace("tool_call: [待规划]", &format!("tool_call: [可用工具: {}]", tools_list));
 
+        notify_observers(
+            &self.observers,
+            ChatEvent::PromptBuilt {
+                agent_name: agent_name.to_string(),
+                prompt: prompt.clone(),
+            },
+        );
+
         // 阶段 5: 推理请求
         // 应用传入的 bnf_schema 和 stop_sequences（如果提供），否则使用 agent 默认配置
         let final_stop_sequences = stop_sequences.unwrap_or_else(|| agent.config.inference_params.stop_sequences.clone());
+        let bnf_schema = bnf_schema.or_else(|| agent.config.inference_params.bnf_schema.clone());
 
         let generate_request = GenerateRequest {
             prompt: prompt.clone(),
@@ -353,7 +489,7 @@ impl RwkvAgentKit {
             stop: final_stop_sequences,
             bias: Arc::new(HashMap::new()),
             bnf_schema,
-            sampler: agent.sampler.clone(),
+            sampler: agent.request_sampler(self.config.model.seed),
             kind: GenerateKind::None,
             state: agent.state.clone(),
         };
@@ -369,8 +505,18 @@ impl RwkvAgentKit {
         println!("{}", prompt);
         println!("=== 提示词调试输出结束 ===\n");
 
+        Ok((agent, generate_request))
+    }
+
+    /// 将推理请求发送给 ai00-core，返回 token 接收端
+    ///
+    /// 通道容量为 `config.stream_buffer`，接收方跟不上时推理线程会等待，而不是无限堆积 token。
+    async fn send_generate_request(
+        &self,
+        generate_request: GenerateRequest,
+    ) -> RwkvResult<flume::Receiver<Token>> {
         // 发送推理请求
-        let (result_sender, result_receiver) = flume::unbounded();
+        let (result_sender, result_receiver) = flume::bounded(self.config.stream_buffer.max(1));
         let request = ThreadRequest::Generate {
             request: Box::new(generate_request),
             tokenizer: self.tokenizer.clone(),
@@ -383,37 +529,34 @@ impl RwkvAgentKit {
                 format!("Failed to send request: {}", e)
             ))?;
 
-        // 阶段 6: 收集响应结果
-        let mut result = String::new();
+        Ok(result_receiver)
+    }
 
-        while let Ok(token) = result_receiver.recv_async().await {
-            match &token {
-                Token::Content(content) => {
-                    result.push_str(content);
-                },
-                Token::Stop(_reason, _counter) => {
-                    break;
-                },
-                _ => {
-                    // 处理其他类型的token
-                }
-            }
-        }
+    /// 等待生成许可，超出 `max_concurrent_generations` 的请求在此排队
+    async fn acquire_generation_permit(
+        &self,
+    ) -> RwkvResult<Option<tokio::sync::OwnedSemaphorePermit>> {
+        self.generation_limiter.acquire().await.map_err(|e| {
+            RwkvError::new(
+                ErrorCode::ModelInferenceFailed,
+                format!("Failed to acquire generation permit: {}", e),
+            )
+        })
+    }
 
-        // 阶段 7: 使用Agent自己的Memory系统保存对话
-        if agent.config.memory.enabled {
-            agent.memory().add_conversation(user_input.to_string(), result.clone()).await;
-        }
+    /// 当前排队等待生成的请求数
+    pub fn generation_queue_depth(&self) -> usize {
+        self.generation_limiter.queue_depth()
+    }
 
-        // 注意：数据库保存逻辑已移至 rwkv_agent_kit.rs 的 chat_with_memory 方法中
-        // 避免重复保存，这里不再直接保存到数据库
-        // if agent.config.save_conversations { ... } // 已注释掉以避免重复保存
+    /// 获取已加载的 RWKV 分词器，可配合 `utils::count_tokens` 使用
+    pub fn tokenizer(&self) -> Arc<Tokenizer> {
+        self.tokenizer.clone()
+    }
 
-                Ok(result)
-            }
-            },
-            &format!("chat_{}", agent_name)
-        ).await
+    /// 添加对话生命周期观察者
+    pub fn add_observer(&mut self, observer: Arc<dyn ChatObserver>) {
+        self.observers.push(observer);
     }
 
     /// 设置数据库管理器
@@ -426,3 +569,480 @@ impl RwkvAgentKit {
         self.tools = tools;
     }
 }
+
+/// 从文件加载分词器
+fn load_tokenizer(path: &str) -> RwkvResult<Tokenizer> {
+    let tokenizer_content = std::fs::read_to_string(path).map_err(|e| {
+        RwkvError::new(ErrorCode::ModelLoadFailed, format!("分词器加载失败: {}", e))
+    })?;
+
+    Tokenizer::new(&tokenizer_content).map_err(|e| {
+        RwkvError::new(
+            ErrorCode::ModelLoadFailed,
+            format!("分词器初始化失败: {}", e),
+        )
+    })
+}
+
+/// 模型加载器
+#[async_trait::async_trait]
+pub trait ModelLoader: Send + Sync {
+    /// 按配置加载模型，成功返回后推理即使用新模型
+    async fn load(&self, config: &ModelConfig) -> RwkvResult<()>;
+}
+
+/// 通过 ai00-core 的重载请求加载模型
+pub struct Ai00ModelLoader {
+    sender: Sender<ThreadRequest>,
+}
+
+impl Ai00ModelLoader {
+    pub fn new(sender: Sender<ThreadRequest>) -> Self {
+        Self { sender }
+    }
+}
+
+#[async_trait::async_trait]
+impl ModelLoader for Ai00ModelLoader {
+    async fn load(&self, config: &ModelConfig) -> RwkvResult<()> {
+        let reload_request = config.clone().try_into().map_err(|e| {
+            RwkvError::new(
+                ErrorCode::ModelLoadFailed,
+                format!("Model config conversion failed: {}", e),
+            )
+        })?;
+
+        let (reload_sender, reload_receiver) = flume::unbounded();
+        let request = ThreadRequest::Reload {
+            request: Box::new(reload_request),
+            sender: Some(reload_sender),
+        };
+        self.sender.send_async(request).await.map_err(|e| {
+            RwkvError::new(
+                ErrorCode::ModelLoadFailed,
+                format!("Model reload request failed: {}", e),
+            )
+        })?;
+
+        let loaded = reload_receiver.recv_async().await.map_err(|e| {
+            RwkvError::new(
+                ErrorCode::ModelLoadFailed,
+                format!("Model reload response failed: {}", e),
+            )
+        })?;
+        if loaded {
+            Ok(())
+        } else {
+            Err(RwkvError::new(
+                ErrorCode::ModelLoadFailed,
+                format!("模型加载失败: {}", config.model_path),
+            ))
+        }
+    }
+}
+
+/// 收集推理结果，返回第一个停止序列之前的内容
+///
+/// 不依赖采样器自行停止：检测到停止序列后立即返回并释放 `result_receiver`，推理线程随之停止生成。
+async fn collect_tokens(result_receiver: flume::Receiver<Token>, stop: &[String]) -> String {
+    let mut result = String::new();
+    while let Ok(token) = result_receiver.recv_async().await {
+        match token {
+            Token::Content(content) => {
+                result.push_str(&content);
+                if let Some(end) = find_stop_sequence(&result, stop) {
+                    result.truncate(end);
+                    break;
+                }
+            }
+            Token::Stop(_reason, _counter) => break,
+            _ => {}
+        }
+    }
+    result
+}
+
+/// 执行工具，按 `error_handler` 的重试配置重试瞬时失败
+async fn execute_tool_with_retry(
+    error_handler: &ErrorHandler,
+    tools: &SharedToolRegistry,
+    tool_name: &str,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, ToolError> {
+    error_handler
+        .execute_with_retry_when(
+            || async {
+                let tools = tools.read().await;
+                tools.execute_tool(tool_name, params.clone()).await
+            },
+            ToolError::is_transient,
+            &format!("tool_execution_{}", tool_name),
+        )
+        .await
+}
+
+/// 将推理结果逐段转发给流式接收方，返回已生成的完整内容
+///
+/// 接收方被丢弃时立即返回并释放 `result_receiver`，推理线程检测到通道断开后停止生成。
+/// 与 [`collect_tokens`] 一样在第一个停止序列处截断；可能是停止序列开头的末尾内容会暂缓转发。
+async fn forward_stream_tokens(
+    result_receiver: flume::Receiver<Token>,
+    chunk_sender: flume::Sender<String>,
+    stop: Vec<String>,
+) -> String {
+    let mut result = String::new();
+    let mut forwarded = 0;
+    while let Ok(token) = result_receiver.recv_async().await {
+        match token {
+            Token::Content(content) => {
+                result.push_str(&content);
+                let stopped = find_stop_sequence(&result, &stop);
+                let end = match stopped {
+                    Some(end) => end,
+                    None => result.len() - pending_stop_prefix_len(&result, &stop),
+                };
+                if end > forwarded {
+                    let chunk = result[forwarded..end].to_string();
+                    forwarded = end;
+                    if chunk_sender.send_async(chunk).await.is_err() {
+                        // 调用方已放弃接收
+                        break;
+                    }
+                }
+                if let Some(end) = stopped {
+                    result.truncate(end);
+                    break;
+                }
+            }
+            Token::Stop(_reason, _counter) => break,
+            _ => {}
+        }
+    }
+    // 生成结束时暂缓的内容并未构成停止序列，补发给接收方
+    if result.len() > forwarded {
+        let _ = chunk_sender
+            .send_async(result[forwarded..].to_string())
+            .await;
+    }
+    result
+}
+
+/// 第一个停止序列在文本中的起始位置
+fn find_stop_sequence(text: &str, stop: &[String]) -> Option<usize> {
+    stop.iter()
+        .filter(|sequence| !sequence.is_empty())
+        .filter_map(|sequence| text.find(sequence.as_str()))
+        .min()
+}
+
+/// 文本末尾与某个停止序列开头重合的最大长度（字节）
+fn pending_stop_prefix_len(text: &str, stop: &[String]) -> usize {
+    stop.iter()
+        .filter_map(|sequence| {
+            (1..sequence.len())
+                .rev()
+                .filter(|&len| sequence.is_char_boundary(len))
+                .find(|&len| text.ends_with(&sequence[..len]))
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// 切换模型：新模型加载成功才替换 `active`，失败时重新加载旧配置以保证旧模型可用
+async fn swap_model(
+    loader: &dyn ModelLoader,
+    active: &mut ModelConfig,
+    new_config: ModelConfig,
+) -> RwkvResult<()> {
+    log::info!(
+        "Reloading model: {} -> {}",
+        active.model_path,
+        new_config.model_path
+    );
+
+    match loader.load(&new_config).await {
+        Ok(()) => {
+            log::info!("Model reloaded: {}", new_config.model_path);
+            *active = new_config;
+            Ok(())
+        }
+        Err(e) => {
+            log::warn!(
+                "Failed to load model {}, keeping {}: {}",
+                new_config.model_path,
+                active.model_path,
+                e
+            );
+            if let Err(restore) = loader.load(active).await {
+                log::error!(
+                    "Failed to restore model {}: {}",
+                    active.model_path,
+                    restore
+                );
+            }
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tools::ToolErrorKind;
+    use std::sync::Mutex;
+
+    /// 记录加载过的模型路径，路径以 `broken` 结尾时加载失败
+    #[derive(Default)]
+    struct StubLoader {
+        loaded: Mutex<Vec<String>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ModelLoader for StubLoader {
+        async fn load(&self, config: &ModelConfig) -> RwkvResult<()> {
+            if config.model_path.ends_with("broken") {
+                return Err(RwkvError::new(ErrorCode::ModelLoadFailed, "stub failure"));
+            }
+            self.loaded.lock().unwrap().push(config.model_path.clone());
+            Ok(())
+        }
+    }
+
+    fn model_config(path: &str) -> ModelConfig {
+        ModelConfig {
+            model_path: path.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_swap_model_is_atomic() {
+        let loader = StubLoader::default();
+        let mut active = model_config("old.st");
+
+        swap_model(&loader, &mut active, model_config("new.st"))
+            .await
+            .unwrap();
+        assert_eq!(active.model_path, "new.st");
+
+        let result = swap_model(&loader, &mut active, model_config("broken")).await;
+        assert!(result.is_err());
+        assert_eq!(active.model_path, "new.st");
+        // 失败后重新加载了之前的模型
+        assert_eq!(
+            *loader.loaded.lock().unwrap(),
+            vec!["new.st".to_string(), "new.st".to_string()]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_stream_backpressure_and_cancellation() {
+        // 与 send_generate_request 一样使用有界的 token 通道
+        let (token_sender, token_receiver) = flume::bounded::<Token>(4);
+        let (chunk_sender, chunk_receiver) = flume::bounded(4);
+
+        // 模拟推理线程：与 ai00 一样同步发送，通道满时阻塞，接收端断开后停止生成
+        let produced = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let producer = {
+            let produced = produced.clone();
+            tokio::task::spawn_blocking(move || {
+                while token_sender.send(Token::Content("x".to_string())).is_ok() {
+                    produced.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+            })
+        };
+        let forwarder = tokio::spawn(forward_stream_tokens(
+            token_receiver,
+            chunk_sender,
+            Vec::new(),
+        ));
+
+        // 慢速消费方：生成量受两个通道的容量限制
+        for received in 1..=5 {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            assert!(chunk_receiver.len() <= 4);
+            assert!(produced.load(std::sync::atomic::Ordering::SeqCst) <= received + 4 + 4 + 1);
+            chunk_receiver.recv_async().await.unwrap();
+        }
+
+        drop(chunk_receiver);
+        let forwarded = tokio::time::timeout(std::time::Duration::from_secs(1), forwarder)
+            .await
+            .expect("forwarder should stop once the receiver is dropped")
+            .unwrap();
+        assert!(forwarded.len() <= 5 + 4 + 1);
+
+        tokio::time::timeout(std::time::Duration::from_secs(1), producer)
+            .await
+            .expect("producer should stop once the forwarder exits")
+            .unwrap();
+    }
+
+    /// 统计调用次数的工具，按参数返回不同类别的错误
+    struct CountingTool(Arc<std::sync::atomic::AtomicUsize>);
+
+    #[async_trait::async_trait]
+    impl crate::core::tools::Tool for CountingTool {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        fn description(&self) -> &'static str {
+            "counts calls"
+        }
+
+        async fn execute(&self, params: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            match params["fail"].as_str() {
+                Some("missing") => Err(ToolError::NotFound("backend".to_string()).into()),
+                Some(_) => Err(anyhow::anyhow!("flaky")),
+                None => Ok(serde_json::json!("ok")),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_tool_retries_only_transient_errors() {
+        let handler = ErrorHandler::new(crate::core::error_handler::ErrorHandlerConfig {
+            max_retries: 2,
+            retry_delay_ms: 1,
+            ..Default::default()
+        });
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut registry = ToolRegistry::default();
+        registry.register(CountingTool(calls.clone())).unwrap();
+        let tools: SharedToolRegistry = Arc::new(tokio::sync::RwLock::new(registry));
+        let call_count = || calls.swap(0, std::sync::atomic::Ordering::SeqCst);
+
+        let not_found = execute_tool_with_retry(
+            &handler,
+            &tools,
+            "counting",
+            serde_json::json!({ "fail": "missing" }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(not_found.kind(), ToolErrorKind::NotFound);
+        assert_eq!(call_count(), 1);
+
+        let missing = execute_tool_with_retry(&handler, &tools, "missing", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.kind(), ToolErrorKind::NotFound);
+
+        let failed = execute_tool_with_retry(
+            &handler,
+            &tools,
+            "counting",
+            serde_json::json!({ "fail": "run" }),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(failed.kind(), ToolErrorKind::ExecutionFailed);
+        assert_eq!(call_count(), 3);
+    }
+
+    /// 按给定分段发送推理结果，模拟不理会停止序列的采样器
+    fn token_stream(chunks: &[&str]) -> flume::Receiver<Token> {
+        let (token_sender, token_receiver) = flume::unbounded();
+        for chunk in chunks {
+            token_sender
+                .send(Token::Content(chunk.to_string()))
+                .unwrap();
+        }
+        token_receiver
+    }
+
+    #[tokio::test]
+    async fn test_reply_is_cut_at_first_stop_sequence() {
+        let config = crate::agent::AgentConfig::default().with_stop_sequences(["\n\n"]);
+        let stop = &config.inference_params.stop_sequences;
+        // 停止序列跨越两段输出
+        let chunks = ["第一段。\n", "\n第二段。", "\n\n第三段。"];
+
+        let reply = collect_tokens(token_stream(&chunks), stop).await;
+        assert_eq!(reply, "第一段。");
+
+        let (chunk_sender, chunk_receiver) = flume::unbounded();
+        let reply = forward_stream_tokens(token_stream(&chunks), chunk_sender, stop.clone()).await;
+        assert_eq!(reply, "第一段。");
+        let streamed: String = chunk_receiver.drain().collect();
+        assert_eq!(streamed, "第一段。");
+
+        // 未出现停止序列时完整返回，暂缓的末尾内容在结束时补发
+        let (chunk_sender, chunk_receiver) = flume::unbounded();
+        let reply =
+            forward_stream_tokens(token_stream(&["答案\n"]), chunk_sender, stop.clone()).await;
+        assert_eq!(reply, "答案\n");
+        assert_eq!(chunk_receiver.drain().collect::<String>(), "答案\n");
+    }
+
+    /// 单字节词表的分词器，代替模型自带的分词器
+    fn byte_tokenizer() -> Arc<Tokenizer> {
+        let vocab: HashMap<u32, Vec<u8>> = (0..=255u8).map(|b| (b as u32 + 1, vec![b])).collect();
+        Arc::new(Tokenizer::new(&serde_json::to_string(&vocab).unwrap()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_generate_request_carries_agent_sampling_params() {
+        use crate::agent::{AgentConfig, InferenceParams};
+
+        let agent = |name: &str, inference_params: InferenceParams| AgentConfig {
+            name: name.to_string(),
+            inference_params,
+            ..Default::default()
+        };
+        let config = KitConfig {
+            agents: vec![
+                agent(
+                    "greedy",
+                    InferenceParams {
+                        max_tokens: 16,
+                        top_k: 1,
+                        stop_sequences: vec!["\n".to_string()],
+                        ..Default::default()
+                    },
+                ),
+                agent(
+                    "wide",
+                    InferenceParams {
+                        max_tokens: 64,
+                        temperature: 1.0,
+                        top_p: 1.0,
+                        stop_sequences: vec!["User:".to_string()],
+                        ..Default::default()
+                    },
+                ),
+            ],
+            ..Default::default()
+        };
+        let (sender, _receiver) = flume::unbounded();
+        let service = RwkvAgentKit::with_sender(config, sender, byte_tokenizer()).unwrap();
+
+        // 用请求里的采样器反复采样，观察其参数
+        let probs = [0.2, 0.3, 0.25, 0.25];
+        let sample = |request: GenerateRequest| async move {
+            let mut sampler = request.sampler.write().await;
+            (0..100)
+                .map(|_| sampler.sample(&probs))
+                .collect::<std::collections::HashSet<u32>>()
+        };
+
+        let (_, greedy) = service
+            .prepare_generate_request("greedy", "hi", None, None)
+            .await
+            .unwrap();
+        assert_eq!(greedy.max_tokens, 16);
+        assert_eq!(greedy.stop, vec!["\n".to_string()]);
+        // top_k = 1 时总是选概率最高的 token
+        assert_eq!(sample(greedy).await, [1].into());
+
+        let (_, wide) = service
+            .prepare_generate_request("wide", "hi", None, None)
+            .await
+            .unwrap();
+        assert_eq!(wide.max_tokens, 64);
+        assert_eq!(wide.stop, vec!["User:".to_string()]);
+        assert!(sample(wide).await.len() > 1);
+    }
+}
@@ -1,10 +1,59 @@
 //! 工具系统模块
 
-use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use thiserror::Error;
 use tokio::sync::RwLock;
 
+/// 工具注册与调用错误
+#[derive(Debug, Error)]
+pub enum ToolError {
+    /// 同名工具已注册
+    #[error("Tool '{0}' is already registered")]
+    DuplicateName(String),
+    /// 工具不存在
+    #[error("Tool '{0}' not found")]
+    NotFound(String),
+    /// 工具参数不合法
+    #[error("Invalid parameters for tool '{name}': {message}")]
+    InvalidParams { name: String, message: String },
+    /// 工具执行失败
+    #[error("Tool '{name}' failed: {source}")]
+    ExecutionFailed {
+        name: String,
+        #[source]
+        source: anyhow::Error,
+    },
+}
+
+/// 工具错误的类别，便于调用方（如工作流聚合器）分类统计
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToolErrorKind {
+    DuplicateName,
+    NotFound,
+    InvalidParams,
+    ExecutionFailed,
+}
+
+impl ToolError {
+    /// 错误类别
+    pub fn kind(&self) -> ToolErrorKind {
+        match self {
+            Self::DuplicateName(_) => ToolErrorKind::DuplicateName,
+            Self::NotFound(_) => ToolErrorKind::NotFound,
+            Self::InvalidParams { .. } => ToolErrorKind::InvalidParams,
+            Self::ExecutionFailed { .. } => ToolErrorKind::ExecutionFailed,
+        }
+    }
+
+    /// 是否为可能自行恢复的瞬时错误：只有执行失败值得重试，
+    /// 工具不存在或参数不合法时重试也不会成功
+    pub fn is_transient(&self) -> bool {
+        self.kind() == ToolErrorKind::ExecutionFailed
+    }
+}
+
 /// 工具注册表
 #[derive(Default)]
 pub struct ToolRegistry {
@@ -12,10 +61,20 @@ pub struct ToolRegistry {
 }
 
 impl ToolRegistry {
-    /// 注册新工具
-    pub fn register<T: Tool + 'static>(&mut self, tool: T) {
+    /// 注册新工具，同名工具已存在时返回 [`ToolError::DuplicateName`]
+    pub fn register<T: Tool + 'static>(&mut self, tool: T) -> Result<(), ToolError> {
+        let name = tool.name();
+        if self.tools.contains_key(name) {
+            return Err(ToolError::DuplicateName(name.to_string()));
+        }
+        self.tools.insert(name.to_string(), Box::new(tool));
+        Ok(())
+    }
+
+    /// 注册新工具并覆盖同名工具，返回是否发生了覆盖
+    pub fn register_or_replace<T: Tool + 'static>(&mut self, tool: T) -> bool {
         let name = tool.name().to_string();
-        self.tools.insert(name, Box::new(tool));
+        self.tools.insert(name, Box::new(tool)).is_some()
     }
 
     /// 获取工具列表 (名称)
@@ -36,11 +95,19 @@ impl ToolRegistry {
         &self,
         tool_name: &str,
         params: serde_json::Value,
-    ) -> Result<serde_json::Value> {
-        match self.tools.get(tool_name) {
-            Some(tool) => tool.execute(params).await,
-            None => Err(anyhow::anyhow!("Tool '{}' not found", tool_name)),
-        }
+    ) -> Result<serde_json::Value, ToolError> {
+        let tool = self
+            .tools
+            .get(tool_name)
+            .ok_or_else(|| ToolError::NotFound(tool_name.to_string()))?;
+        tool.execute(params).await.map_err(|e| match e.downcast::<ToolError>() {
+            // 工具自身可以返回更具体的错误（如参数不合法）
+            Ok(tool_error) => tool_error,
+            Err(source) => ToolError::ExecutionFailed {
+                name: tool_name.to_string(),
+                source,
+            },
+        })
     }
 
     /// 获取工具数量
@@ -62,8 +129,88 @@ impl std::fmt::Debug for ToolRegistry {
 pub trait Tool: Send + Sync {
     fn name(&self) -> &'static str;
     fn description(&self) -> &'static str;
-    async fn execute(&self, params: serde_json::Value) -> Result<serde_json::Value>;
+    /// 参数的 JSON Schema，默认接受任意对象
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({ "type": "object" })
+    }
+    async fn execute(&self, params: serde_json::Value) -> anyhow::Result<serde_json::Value>;
 }
 
 /// 共享工具注册表类型
 pub type SharedToolRegistry = Arc<RwLock<ToolRegistry>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedTool(&'static str);
+
+    #[async_trait::async_trait]
+    impl Tool for FixedTool {
+        fn name(&self) -> &'static str {
+            "fixed"
+        }
+
+        fn description(&self) -> &'static str {
+            self.0
+        }
+
+        async fn execute(&self, params: serde_json::Value) -> anyhow::Result<serde_json::Value> {
+            match params.get("fail").and_then(|v| v.as_str()) {
+                Some("params") => Err(ToolError::InvalidParams {
+                    name: "fixed".to_string(),
+                    message: "bad input".to_string(),
+                }
+                .into()),
+                Some(_) => Err(anyhow::anyhow!("boom")),
+                None => Ok(serde_json::json!(self.0)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_duplicate_registration() {
+        let mut registry = ToolRegistry::default();
+        registry.register(FixedTool("first")).unwrap();
+
+        let err = registry.register(FixedTool("second")).unwrap_err();
+        assert!(matches!(err, ToolError::DuplicateName(ref name) if name == "fixed"));
+        assert_eq!(err.kind(), ToolErrorKind::DuplicateName);
+        assert_eq!(registry.list_tool_descriptions()[0].1, "first");
+
+        assert!(registry.register_or_replace(FixedTool("second")));
+        assert_eq!(registry.count(), 1);
+        assert_eq!(registry.list_tool_descriptions()[0].1, "second");
+    }
+
+    #[tokio::test]
+    async fn test_execute_tool_errors_are_typed() {
+        let mut registry = ToolRegistry::default();
+        registry.register(FixedTool("ok")).unwrap();
+
+        let output = registry
+            .execute_tool("fixed", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert_eq!(output, serde_json::json!("ok"));
+
+        let missing = registry
+            .execute_tool("missing", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.kind(), ToolErrorKind::NotFound);
+
+        let invalid = registry
+            .execute_tool("fixed", serde_json::json!({ "fail": "params" }))
+            .await
+            .unwrap_err();
+        assert_eq!(invalid.kind(), ToolErrorKind::InvalidParams);
+
+        let failed = registry
+            .execute_tool("fixed", serde_json::json!({ "fail": "run" }))
+            .await
+            .unwrap_err();
+        assert_eq!(failed.kind(), ToolErrorKind::ExecutionFailed);
+        assert_eq!(failed.to_string(), "Tool 'fixed' failed: boom");
+    }
+}
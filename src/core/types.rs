@@ -181,9 +181,10 @@ pub struct Context {
 }
 
 /// 优先级枚举
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Priority {
     Low,
+    #[default]
     Normal,
     High,
     Critical,
@@ -211,7 +212,7 @@ pub struct Query {
 }
 
 /// 查询类型枚举
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum QueryType {
     /// 语义检索
     Semantic,
@@ -246,6 +247,10 @@ pub struct QueryFilters {
     pub language_filter: Option<String>,
     /// 情感过滤
     pub emotion_filter: Option<String>,
+    /// 偏好情感，与记忆情感一致的结果加分但不过滤其他结果
+    pub preferred_emotion: Option<String>,
+    /// 语义检索的最小相似度，优先于检索约束与 `vector.similarity_threshold`
+    pub min_similarity: Option<f32>,
     /// 置信度阈值
     pub confidence_threshold: Option<f32>,
     /// 自定义过滤器
@@ -253,22 +258,34 @@ pub struct QueryFilters {
 }
 
 /// 排序方式
+///
+/// 在相关性评分之后对结果做最终排序，排序键相同时按相关性降序。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SortBy {
     /// 按相关性排序
-    Relevance,
-    /// 按时间排序
-    Time,
+    Relevance(SortOrder),
+    /// 按创建时间排序
+    CreatedAt(SortOrder),
     /// 按重要性排序
-    Importance,
-    /// 按访问频率排序
-    AccessCount,
+    Importance(SortOrder),
+    /// 按访问次数排序
+    AccessCount(SortOrder),
     /// 按置信度排序
-    Confidence,
-    /// 自定义排序
+    Confidence(SortOrder),
+    /// 自定义排序，由调用方自行处理，检索结果保持相关性顺序
     Custom(String),
 }
 
+/// 排序方向
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// 升序
+    Asc,
+    /// 降序
+    #[default]
+    Desc,
+}
+
 /// 查询权重
 ///
 /// 定义不同检索维度的权重。
@@ -533,12 +550,6 @@ impl Default for QueryWeights {
     }
 }
 
-impl Default for Priority {
-    fn default() -> Self {
-        Self::Normal
-    }
-}
-
 // 辅助函数
 impl Default for MemoryMetadata {
     fn default() -> Self {
@@ -693,6 +704,34 @@ impl Connection {
     }
 }
 
+/// 带用量统计的对话回复
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ChatResponse {
+    /// 回复文本
+    pub text: String,
+    /// 提示词 token 数
+    pub prompt_tokens: usize,
+    /// 生成内容 token 数
+    pub completion_tokens: usize,
+    /// 总 token 数
+    pub total_tokens: usize,
+    /// 耗时（毫秒）
+    pub elapsed_ms: u64,
+}
+
+impl ChatResponse {
+    /// 创建回复，总 token 数为提示词与生成内容之和
+    pub fn new(text: String, prompt_tokens: usize, completion_tokens: usize, elapsed_ms: u64) -> Self {
+        Self {
+            text,
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+            elapsed_ms,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -772,4 +811,25 @@ mod tests {
         connection.update_strength(-0.5); // 负值
         assert_eq!(connection.strength, 0.0); // 应该被限制在0.0
     }
+
+    #[test]
+    fn test_chat_response_usage() {
+        use crate::utils::count_tokens;
+
+        let prompt = "User: 今天天气怎么样？\n\nAssistant: <think>";
+        let reply = "今天是晴天，适合出门散步。";
+        let response = ChatResponse::new(
+            reply.to_string(),
+            count_tokens(None, prompt),
+            count_tokens(None, reply),
+            12,
+        );
+
+        assert!(response.prompt_tokens > 0);
+        assert!(response.completion_tokens > 0);
+        assert_eq!(
+            response.total_tokens,
+            response.prompt_tokens + response.completion_tokens
+        );
+    }
 }
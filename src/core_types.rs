@@ -181,9 +181,10 @@ pub struct Context {
 }
 
 /// 优先级枚举
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 pub enum Priority {
     Low,
+    #[default]
     Normal,
     High,
     Critical,
@@ -211,7 +212,7 @@ pub struct Query {
 }
 
 /// 查询类型枚举
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum QueryType {
     /// 语义检索
     Semantic,
@@ -246,6 +247,10 @@ pub struct QueryFilters {
     pub language_filter: Option<String>,
     /// 情感过滤
     pub emotion_filter: Option<String>,
+    /// 偏好情感，与记忆情感一致的结果加分但不过滤其他结果
+    pub preferred_emotion: Option<String>,
+    /// 语义检索的最小相似度，优先于检索约束与 `vector.similarity_threshold`
+    pub min_similarity: Option<f32>,
     /// 置信度阈值
     pub confidence_threshold: Option<f32>,
     /// 自定义过滤器
@@ -253,22 +258,34 @@ pub struct QueryFilters {
 }
 
 /// 排序方式
+///
+/// 在相关性评分之后对结果做最终排序，排序键相同时按相关性降序。
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum SortBy {
     /// 按相关性排序
-    Relevance,
-    /// 按时间排序
-    Time,
+    Relevance(SortOrder),
+    /// 按创建时间排序
+    CreatedAt(SortOrder),
     /// 按重要性排序
-    Importance,
-    /// 按访问频率排序
-    AccessCount,
+    Importance(SortOrder),
+    /// 按访问次数排序
+    AccessCount(SortOrder),
     /// 按置信度排序
-    Confidence,
-    /// 自定义排序
+    Confidence(SortOrder),
+    /// 自定义排序，由调用方自行处理，检索结果保持相关性顺序
     Custom(String),
 }
 
+/// 排序方向
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    /// 升序
+    Asc,
+    /// 降序
+    #[default]
+    Desc,
+}
+
 /// 查询权重
 ///
 /// 定义不同检索维度的权重。
@@ -433,6 +450,23 @@ pub enum InteractionType {
     Browse,
 }
 
+/// 单条记忆的交互记录
+///
+/// 个性化检索据此学习用户偏好，并持久化到数据库以便重启后恢复。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractionRecord {
+    /// 被交互的记忆ID
+    pub memory_id: MemoryId,
+    /// 交互类型
+    pub interaction_type: InteractionType,
+    /// 时间戳
+    pub timestamp: DateTime<Utc>,
+    /// 用户反馈评分
+    pub feedback_score: Option<f32>,
+    /// 停留时长（毫秒）
+    pub dwell_time: Option<u64>,
+}
+
 /// 统计信息
 ///
 /// 系统运行统计数据。
@@ -533,12 +567,6 @@ impl Default for QueryWeights {
     }
 }
 
-impl Default for Priority {
-    fn default() -> Self {
-        Self::Normal
-    }
-}
-
 // 辅助函数
 impl Default for MemoryMetadata {
     fn default() -> Self {
@@ -693,6 +721,58 @@ impl Connection {
     }
 }
 
+impl ConnectionType {
+    /// 从图边类型字符串解析连接类型
+    ///
+    /// 边类型按 `{:?}` 格式存储（如 `Causal`、`Custom("k")`），基础类型名不区分大小写，
+    /// 无法识别的字符串视为自定义类型。
+    pub fn from_edge_type(edge_type: &str) -> Self {
+        match edge_type.to_ascii_lowercase().as_str() {
+            "semantic" => Self::Semantic,
+            "temporal" => Self::Temporal,
+            "causal" => Self::Causal,
+            "thematic" => Self::Thematic,
+            "hierarchical" => Self::Hierarchical,
+            "reference" => Self::Reference,
+            _ => {
+                let name = edge_type
+                    .strip_prefix("Custom(")
+                    .and_then(|rest| rest.strip_suffix(')'))
+                    .map(|inner| {
+                        serde_json::from_str::<String>(inner)
+                            .unwrap_or_else(|_| inner.trim_matches('"').to_string())
+                    })
+                    .unwrap_or_else(|| edge_type.to_string());
+                Self::Custom(name)
+            }
+        }
+    }
+}
+
+impl MemoryConnections {
+    /// 按连接类型把目标记忆加入对应的链接列表
+    ///
+    /// 层次与引用连接没有专门的列表，以类型名（`Hierarchical`、`Reference`）记入 `custom_links`。
+    pub fn add_link(&mut self, connection_type: &ConnectionType, target: MemoryId) {
+        let links = match connection_type {
+            ConnectionType::Semantic => &mut self.semantic_links,
+            ConnectionType::Temporal => &mut self.temporal_links,
+            ConnectionType::Causal => &mut self.causal_links,
+            ConnectionType::Thematic => &mut self.thematic_links,
+            ConnectionType::Hierarchical => self
+                .custom_links
+                .entry("Hierarchical".to_string())
+                .or_default(),
+            ConnectionType::Reference => self
+                .custom_links
+                .entry("Reference".to_string())
+                .or_default(),
+            ConnectionType::Custom(name) => self.custom_links.entry(name.clone()).or_default(),
+        };
+        links.push(target);
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -772,4 +852,25 @@ mod tests {
         connection.update_strength(-0.5); // 负值
         assert_eq!(connection.strength, 0.0); // 应该被限制在0.0
     }
+
+    #[test]
+    fn test_connection_type_edge_type_round_trip() {
+        for connection_type in [
+            ConnectionType::Semantic,
+            ConnectionType::Causal,
+            ConnectionType::Reference,
+            ConnectionType::Custom("引用\"原文\"".to_string()),
+        ] {
+            let edge_type = format!("{:?}", connection_type);
+            assert_eq!(ConnectionType::from_edge_type(&edge_type), connection_type);
+        }
+        assert_eq!(
+            ConnectionType::from_edge_type("causal"),
+            ConnectionType::Causal
+        );
+        assert_eq!(
+            ConnectionType::from_edge_type("supports"),
+            ConnectionType::Custom("supports".to_string())
+        );
+    }
 }
@@ -1,14 +1,23 @@
 //! 数据库模�?//!
 //! 本模块提供向量数据库和图数据库的统一接口，支持SQLite、PostgreSQL和MySQL�?//! 包含向量存储、图节点和边的管理、以及高效的查询功能�?
-use crate::config::{Config, DatabaseType};
+use crate::config::{Config, ConfigError, DatabaseType, DistanceMetric, VectorIndexType};
+use crate::core_types::{InteractionRecord, SortOrder};
 use crate::error::{MemoryError, Result};
+use crate::utils::VectorUtils;
+use crate::vector_index::{IndexState, IvfIndex};
 use chrono::{DateTime, Utc};
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
 
+use sqlx::query::Query;
+use sqlx::sqlite::{SqliteArguments, SqlitePoolOptions, SqliteRow};
 use sqlx::{Pool, Row, Sqlite};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
 use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -18,6 +27,28 @@ pub enum DatabasePool {
     Sqlite(Pool<Sqlite>),
 }
 
+/// 插入时的主键冲突处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnConflict {
+    /// 冲突时报错
+    Fail,
+    /// 冲突时更新已有记录
+    Update,
+    /// 冲突时保留已有记录
+    Ignore,
+}
+
+impl OnConflict {
+    /// 生成对应的 `ON CONFLICT` 子句，`updates` 为更新时的 SET 列表
+    fn clause(self, updates: &str) -> String {
+        match self {
+            OnConflict::Fail => String::new(),
+            OnConflict::Update => format!(" ON CONFLICT(id) DO UPDATE SET {}", updates),
+            OnConflict::Ignore => " ON CONFLICT(id) DO NOTHING".to_string(),
+        }
+    }
+}
+
 /// 向量数据结构
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Vector {
@@ -51,13 +82,54 @@ pub struct GraphEdge {
     pub updated_at: DateTime<Utc>,
 }
 
+/// 边属性中记录是否双向的键
+pub const BIDIRECTIONAL_PROPERTY: &str = "bidirectional";
+
+impl GraphEdge {
+    /// 是否双向边；未记录该属性的边（如旧数据）视为双向
+    pub fn is_bidirectional(&self) -> bool {
+        self.properties
+            .get(BIDIRECTIONAL_PROPERTY)
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true)
+    }
+
+    /// 能否从给定节点沿该边遍历：有向边只能从起点走向终点
+    pub fn traversable_from(&self, node_id: &str) -> bool {
+        self.from_node == node_id || (self.to_node == node_id && self.is_bidirectional())
+    }
+}
+
 /// 向量查询请求
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorQueryRequest {
     pub query_vector: Vec<f32>,
     pub limit: Option<usize>,
+    /// 最低相似度，含义取决于 `config.vector.distance_metric`（见 `VectorGraphDB::query_vectors`）
     pub threshold: Option<f32>,
     pub filters: Option<HashMap<String, serde_json::Value>>,
+    /// 标签筛选，在相似度排序与数量上限之前通过标签表完成
+    #[serde(default)]
+    pub tags: TagFilter,
+}
+
+/// 向量查询的标签筛选条件，通过 `memory_tags` 表在 SQL 中求值，不解析元数据
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagFilter {
+    /// 至少带有其中一个标签，为空时不限制
+    pub any_of: Vec<String>,
+    /// 必须带有全部标签
+    pub all_of: Vec<String>,
+    /// 带有其中任一标签即排除
+    pub none_of: Vec<String>,
+}
+
+impl TagFilter {
+    /// 是否没有任何标签条件
+    pub fn is_empty(&self) -> bool {
+        self.any_of.is_empty() && self.all_of.is_empty() && self.none_of.is_empty()
+    }
 }
 
 /// 向量查询结果
@@ -76,6 +148,20 @@ pub struct GraphQueryRequest {
     pub max_depth: Option<usize>,
     pub limit: Option<usize>,
     pub filters: Option<HashMap<String, serde_json::Value>>,
+    /// 遍历方式，默认为按跳数的广度优先
+    #[serde(default)]
+    pub traversal: TraversalMode,
+}
+
+/// 图遍历方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TraversalMode {
+    /// 广度优先，路径代价为跳数
+    #[default]
+    BreadthFirst,
+    /// 按边权重的最短路径（Dijkstra），每条边的代价为 `1 / weight`，
+    /// 因此权重高的链路优先；权重不大于 0 的边会被忽略
+    Weighted,
 }
 
 /// 图查询结果
@@ -84,6 +170,148 @@ pub struct GraphQueryResult {
     pub nodes: Vec<GraphNode>,
     pub edges: Vec<GraphEdge>,
     pub paths: Vec<Vec<String>>,
+    /// 与 `paths` 一一对应的路径总代价
+    #[serde(default)]
+    pub path_costs: Vec<f32>,
+    /// 遍历因节点、边预算（`graph.traversal.max_nodes`/`max_edges`/`max_explored_edges`）
+    /// 提前停止，结果只是部分结果
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// 元数据扫描条件，字段名为向量元数据的顶层键
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataCondition {
+    /// 字段等于给定值
+    Equals(String, serde_json::Value),
+    /// 数组字段包含给定字符串
+    Contains(String, String),
+    /// 数值字段不小于给定值
+    AtLeast(String, f64),
+    /// RFC 3339 时间字段位于闭区间内
+    Between(String, DateTime<Utc>, DateTime<Utc>),
+}
+
+/// 元数据扫描的排序字段
+#[derive(Debug, Clone, PartialEq)]
+pub enum MetadataSortKey {
+    /// 数值字段
+    Number(String),
+    /// RFC 3339 时间字段
+    Time(String),
+}
+
+/// 元数据扫描的绑定参数
+enum ScanArg {
+    Text(String),
+    Real(f64),
+    Int(i64),
+}
+
+/// 按顺序绑定元数据扫描参数
+fn bind_scan_args<'q>(
+    mut query: Query<'q, Sqlite, SqliteArguments<'q>>,
+    args: Vec<ScanArg>,
+) -> Query<'q, Sqlite, SqliteArguments<'q>> {
+    for arg in args {
+        query = match arg {
+            ScanArg::Text(value) => query.bind(value),
+            ScanArg::Real(value) => query.bind(value),
+            ScanArg::Int(value) => query.bind(value),
+        };
+    }
+    query
+}
+
+/// 图结构统计
+///
+/// 边按无向处理计算度数与连通分量，用于判断记忆图是否过于分散或过度连接。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphStats {
+    /// 节点数（包括只出现在边上的节点）
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub avg_degree: f32,
+    pub max_degree: usize,
+    /// 连通分量数，孤立节点各自算一个分量
+    pub connected_components: usize,
+    /// 边数与简单无向图最大边数之比
+    pub density: f32,
+    /// 度数最高的节点（按度数降序，同度数按ID升序）
+    pub top_degree_nodes: Vec<(String, usize)>,
+}
+
+/// 筛选未被软删除的向量行
+const NOT_DELETED_CONDITION: &str = "COALESCE(json_extract(metadata, '$.is_deleted'), 0) = 0";
+
+/// 标签/关键词规范化表：(表名, 列名, 元数据中的 JSON 路径)
+const TERM_TABLES: [(&str, &str, &str); 2] = [
+    ("memory_tags", "tag", "$.tags"),
+    ("memory_keywords", "keyword", "$.keywords"),
+];
+
+/// `GraphStats::top_degree_nodes` 记录的节点数
+const TOP_DEGREE_NODES: usize = 10;
+
+/// 未配置 `index_params.nlist` 时 IVF 索引的聚类数
+const DEFAULT_NLIST: usize = 100;
+
+/// 未配置 `search_params.nprobe` 时查询探测的聚类数
+const DEFAULT_NPROBE: usize = 10;
+
+/// 按ID批量读取向量时每条语句绑定的ID数
+const IDS_PER_QUERY: usize = 500;
+
+/// 单类缓存的命中统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheCategoryMetrics {
+    pub hits: u64,
+    pub misses: u64,
+    /// 命中率，尚无查询时为 0
+    pub hit_ratio: f32,
+}
+
+/// 内存缓存各分类的命中统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheMetrics {
+    pub vectors: CacheCategoryMetrics,
+    pub nodes: CacheCategoryMetrics,
+    pub edges: CacheCategoryMetrics,
+    pub query_cache: CacheCategoryMetrics,
+}
+
+/// 命中/未命中计数器
+#[derive(Debug, Default)]
+struct CacheCounters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CacheCounters {
+    fn record<T>(&self, found: Option<T>) -> Option<T> {
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    fn metrics(&self) -> CacheCategoryMetrics {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let misses = self.misses.load(Ordering::Relaxed);
+        let total = hits + misses;
+        CacheCategoryMetrics {
+            hits,
+            misses,
+            hit_ratio: if total == 0 {
+                0.0
+            } else {
+                hits as f32 / total as f32
+            },
+        }
+    }
 }
 
 /// 内存缓存
@@ -92,7 +320,13 @@ pub struct MemoryCache {
     vectors: Arc<RwLock<LruCache<String, Vector>>>,
     nodes: Arc<RwLock<LruCache<String, GraphNode>>>,
     edges: Arc<RwLock<LruCache<String, GraphEdge>>>,
-    query_cache: Arc<RwLock<LruCache<String, VectorQueryResult>>>,
+    query_cache: Arc<RwLock<LruCache<String, Vec<VectorQueryResult>>>>,
+    /// 查询缓存的代数，向量写入时递增，用于丢弃写入前算出的查询结果
+    query_generation: AtomicU64,
+    vector_counters: CacheCounters,
+    node_counters: CacheCounters,
+    edge_counters: CacheCounters,
+    query_counters: CacheCounters,
 }
 
 impl MemoryCache {
@@ -105,11 +339,17 @@ impl MemoryCache {
             nodes: Arc::new(RwLock::new(LruCache::new(cache_capacity))),
             edges: Arc::new(RwLock::new(LruCache::new(cache_capacity))),
             query_cache: Arc::new(RwLock::new(LruCache::new(cache_capacity))),
+            query_generation: AtomicU64::new(0),
+            vector_counters: CacheCounters::default(),
+            node_counters: CacheCounters::default(),
+            edge_counters: CacheCounters::default(),
+            query_counters: CacheCounters::default(),
         }
     }
 
     pub async fn get_vector(&self, id: &str) -> Option<Vector> {
-        self.vectors.read().await.peek(id).cloned()
+        let found = self.vectors.read().await.peek(id).cloned();
+        self.vector_counters.record(found)
     }
 
     pub async fn put_vector(&self, id: String, vector: Vector) {
@@ -117,7 +357,8 @@ impl MemoryCache {
     }
 
     pub async fn get_node(&self, id: &str) -> Option<GraphNode> {
-        self.nodes.read().await.peek(id).cloned()
+        let found = self.nodes.read().await.peek(id).cloned();
+        self.node_counters.record(found)
     }
 
     pub async fn put_node(&self, id: String, node: GraphNode) {
@@ -125,18 +366,53 @@ impl MemoryCache {
     }
 
     pub async fn get_edge(&self, id: &str) -> Option<GraphEdge> {
-        self.edges.read().await.peek(id).cloned()
+        let found = self.edges.read().await.peek(id).cloned();
+        self.edge_counters.record(found)
     }
 
     pub async fn put_edge(&self, id: String, edge: GraphEdge) {
         self.edges.write().await.put(id, edge);
     }
 
+    pub async fn remove_edge(&self, id: &str) {
+        self.edges.write().await.pop(id);
+    }
+
+    pub async fn get_query_result(&self, key: &str) -> Option<Vec<VectorQueryResult>> {
+        let found = self.query_cache.read().await.peek(key).cloned();
+        self.query_counters.record(found)
+    }
+
+    /// 当前查询缓存代数，查询开始前读取并传给 `put_query_result`
+    pub fn query_generation(&self) -> u64 {
+        self.query_generation.load(Ordering::Acquire)
+    }
+
+    /// 缓存查询结果；`generation` 之后有过向量写入时丢弃，避免缓存过期结果
+    pub async fn put_query_result(
+        &self,
+        key: String,
+        generation: u64,
+        results: Vec<VectorQueryResult>,
+    ) {
+        let mut query_cache = self.query_cache.write().await;
+        if self.query_generation.load(Ordering::Acquire) == generation {
+            query_cache.put(key, results);
+        }
+    }
+
+    /// 向量写入后使全部查询结果失效
+    pub async fn invalidate_queries(&self) {
+        let mut query_cache = self.query_cache.write().await;
+        self.query_generation.fetch_add(1, Ordering::AcqRel);
+        query_cache.clear();
+    }
+
     pub async fn clear(&self) {
         self.vectors.write().await.clear();
         self.nodes.write().await.clear();
         self.edges.write().await.clear();
-        self.query_cache.write().await.clear();
+        self.invalidate_queries().await;
     }
 
     pub async fn get_stats(&self) -> (usize, usize, usize, usize) {
@@ -146,6 +422,16 @@ impl MemoryCache {
         let query_cache_len = self.query_cache.read().await.len();
         (vectors_len, nodes_len, edges_len, query_cache_len)
     }
+
+    /// 获取各分类自创建以来的命中/未命中次数与命中率
+    pub fn cache_metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            vectors: self.vector_counters.metrics(),
+            nodes: self.node_counters.metrics(),
+            edges: self.edge_counters.metrics(),
+            query_cache: self.query_counters.metrics(),
+        }
+    }
 }
 
 /// 向量图数据库
@@ -154,26 +440,105 @@ pub struct VectorGraphDB {
     pool: DatabasePool,
     cache: MemoryCache,
     config: Config,
+    /// 含命名空间的表前缀
+    table_prefix: String,
+    /// IVF 索引，`config.vector.index_type` 为 IVF 时启用
+    vector_index: RwLock<Option<IvfIndex>>,
+    /// 本实例从向量表完整重建索引的次数
+    index_rebuilds: AtomicU64,
+    /// 向量写入在提交与更新索引期间持有读锁，持久化索引时持有写锁
+    index_writes: RwLock<()>,
 }
 
 impl VectorGraphDB {
     /// 创建新的数据库实例
     pub async fn new(config: Config) -> Result<Self> {
+        config
+            .validate()
+            .map_err(|errors| MemoryError::validation_error(ConfigError::join(&errors)))?;
+
         let pool = Self::create_pool(&config).await?;
-        let cache = MemoryCache::new(config.cache.lru_capacity);
+        Self::with_pool(pool, config).await
+    }
 
+    /// 使用已有连接池创建实例并初始化（命名空间的）表
+    async fn with_pool(pool: DatabasePool, config: Config) -> Result<Self> {
         let db = Self {
             pool,
-            cache,
+            cache: MemoryCache::new(config.cache.lru_capacity),
+            table_prefix: config.namespaced_table_prefix(),
             config,
+            vector_index: RwLock::new(None),
+            index_rebuilds: AtomicU64::new(0),
+            index_writes: RwLock::new(()),
         };
 
         // 初始化数据库表
         db.initialize_tables().await?;
+        if db.index_enabled() {
+            db.load_or_rebuild_index().await?;
+        }
 
         Ok(db)
     }
 
+    /// 创建共享同一连接池、但只访问指定命名空间数据的实例
+    pub async fn with_namespace(&self, namespace: impl Into<String>) -> Result<Self> {
+        let mut config = self.config.clone();
+        config.database.namespace = Some(namespace.into());
+        config
+            .validate()
+            .map_err(|errors| MemoryError::validation_error(ConfigError::join(&errors)))?;
+        Self::with_pool(self.pool.clone(), config).await
+    }
+
+    /// 当前命名空间
+    pub fn namespace(&self) -> Option<&str> {
+        self.config.database.namespace.as_deref()
+    }
+
+    /// 创建只读快照，快照存续期间的读取都看到创建时的数据
+    ///
+    /// 快照使用独立的单连接池，连接在建立时开启读事务，之后的所有查询都复用该连接。
+    /// 快照有自己的缓存，不会读到或写入原实例缓存中更新的数据。写入请使用原实例：
+    /// 非 WAL 模式下，快照释放前其他连接的提交会一直等待。
+    ///
+    /// 快照按原实例的连接参数新建连接，只适用于文件数据库：`sqlite::memory:` 这类内存数据库
+    /// 的新连接是一个空库，快照上的读取会因缺表而失败。
+    pub async fn read_snapshot(&self) -> Result<ReadSnapshot> {
+        let pool = match &self.pool {
+            DatabasePool::Sqlite(pool) => SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+                .after_connect(|conn, _| {
+                    Box::pin(async move {
+                        sqlx::query("BEGIN DEFERRED").execute(&mut *conn).await?;
+                        // 延迟事务在第一次读取时才取得快照，这里立即读取以固定快照时间点
+                        sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+                            .fetch_one(&mut *conn)
+                            .await?;
+                        Ok(())
+                    })
+                })
+                .connect_with((*pool.connect_options()).clone())
+                .await
+                .map_err(MemoryError::Database)?,
+        };
+
+        Ok(ReadSnapshot {
+            db: Self {
+                pool: DatabasePool::Sqlite(pool),
+                cache: MemoryCache::new(self.config.cache.lru_capacity),
+                config: self.config.clone(),
+                table_prefix: self.table_prefix.clone(),
+                vector_index: RwLock::new(None),
+                index_rebuilds: AtomicU64::new(0),
+                index_writes: RwLock::new(()),
+            },
+        })
+    }
+
     /// 创建数据库连接池
     async fn create_pool(config: &Config) -> Result<DatabasePool> {
         match config.database.database_type {
@@ -198,7 +563,7 @@ impl VectorGraphDB {
 
     /// 创建SQLite表
     async fn create_sqlite_tables(&self, pool: &Pool<Sqlite>) -> Result<()> {
-        let prefix = &self.config.database.table_prefix;
+        let prefix = &self.table_prefix;
 
         // 向量表
         sqlx::query(&format!(
@@ -256,15 +621,143 @@ impl VectorGraphDB {
         .await
         .map_err(MemoryError::Database)?;
 
+        // 交互记录表，用于在重启后恢复个性化历史
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}interactions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                memory_id TEXT NOT NULL,
+                interaction_type TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                feedback_score REAL,
+                dwell_time INTEGER
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
+        // 反馈队列表，待学习周期处理的反馈以 JSON 保存
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}feedback (
+                id TEXT PRIMARY KEY,
+                memory_id TEXT NOT NULL,
+                record TEXT NOT NULL,
+                processed INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
+        // 用户档案表，以 JSON 保存个性化偏好
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}user_profiles (
+                id TEXT PRIMARY KEY,
+                profile TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
+        // 统计表，保存跨重启累计的计数器
+        sqlx::query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}stats (
+                name TEXT PRIMARY KEY,
+                value INTEGER NOT NULL,
+                updated_at TEXT NOT NULL
+            )
+            "#,
+            prefix
+        ))
+        .execute(pool)
+        .await
+        .map_err(MemoryError::Database)?;
+
         // 创建索引
         self.create_sqlite_indexes(pool).await?;
+        self.create_sqlite_term_tables(pool).await?;
+
+        Ok(())
+    }
+
+    /// 创建标签/关键词规范化表，并用触发器与向量元数据保持同步
+    ///
+    /// 插入、更新元数据和删除向量时由 SQLite 触发器维护，所有写入路径都无需额外处理；
+    /// 表首次创建时从已有向量回填。
+    async fn create_sqlite_term_tables(&self, pool: &Pool<Sqlite>) -> Result<()> {
+        let prefix = &self.table_prefix;
+
+        for (table, column, path) in TERM_TABLES {
+            let exists: Option<String> = sqlx::query_scalar(
+                "SELECT name FROM sqlite_master WHERE type = 'table' AND name = ?",
+            )
+            .bind(format!("{}{}", prefix, table))
+            .fetch_optional(pool)
+            .await
+            .map_err(MemoryError::Database)?;
+
+            let insert_terms = format!(
+                "INSERT OR IGNORE INTO {prefix}{table} (memory_id, {column}) \
+                 SELECT NEW.id, value FROM json_each(NEW.metadata, '{path}') WHERE type = 'text';"
+            );
+            let delete_terms = format!("DELETE FROM {prefix}{table} WHERE memory_id = OLD.id;");
+            let statements = [
+                format!(
+                    "CREATE TABLE IF NOT EXISTS {prefix}{table} (\
+                     memory_id TEXT NOT NULL, {column} TEXT NOT NULL, PRIMARY KEY (memory_id, {column}))"
+                ),
+                format!("CREATE INDEX IF NOT EXISTS idx_{prefix}{table}_{column} ON {prefix}{table}({column})"),
+                format!(
+                    "CREATE TRIGGER IF NOT EXISTS {prefix}{table}_after_insert AFTER INSERT ON {prefix}vectors \
+                     BEGIN {insert_terms} END"
+                ),
+                format!(
+                    "CREATE TRIGGER IF NOT EXISTS {prefix}{table}_after_update AFTER UPDATE OF metadata ON {prefix}vectors \
+                     BEGIN {delete_terms} {insert_terms} END"
+                ),
+                format!(
+                    "CREATE TRIGGER IF NOT EXISTS {prefix}{table}_after_delete AFTER DELETE ON {prefix}vectors \
+                     BEGIN {delete_terms} END"
+                ),
+            ];
+            for sql in statements {
+                sqlx::query(&sql)
+                    .execute(pool)
+                    .await
+                    .map_err(MemoryError::Database)?;
+            }
+
+            if exists.is_none() {
+                sqlx::query(&format!(
+                    "INSERT OR IGNORE INTO {prefix}{table} (memory_id, {column}) \
+                     SELECT v.id, t.value FROM {prefix}vectors v, json_each(v.metadata, '{path}') t \
+                     WHERE t.type = 'text'"
+                ))
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
 
         Ok(())
     }
 
     /// 创建SQLite索引
     async fn create_sqlite_indexes(&self, pool: &Pool<Sqlite>) -> Result<()> {
-        let prefix = &self.config.database.table_prefix;
+        let prefix = &self.table_prefix;
 
         let indexes = vec![
             format!("CREATE INDEX IF NOT EXISTS idx_{prefix}vectors_created_at ON {prefix}vectors(created_at)"),
@@ -274,6 +767,8 @@ impl VectorGraphDB {
             format!("CREATE INDEX IF NOT EXISTS idx_{prefix}edges_to_node ON {prefix}graph_edges(to_node)"),
             format!("CREATE INDEX IF NOT EXISTS idx_{prefix}edges_type ON {prefix}graph_edges(edge_type)"),
             format!("CREATE INDEX IF NOT EXISTS idx_{prefix}edges_weight ON {prefix}graph_edges(weight)"),
+            format!("CREATE INDEX IF NOT EXISTS idx_{prefix}interactions_timestamp ON {prefix}interactions(timestamp)"),
+            format!("CREATE INDEX IF NOT EXISTS idx_{prefix}feedback_processed ON {prefix}feedback(processed, created_at)"),
         ];
 
         for index_sql in indexes {
@@ -286,26 +781,35 @@ impl VectorGraphDB {
         Ok(())
     }
 
-    /// 插入向量
+    /// 插入向量（ID已存在时报错）
     pub async fn insert_vector(&self, vector: &Vector) -> Result<()> {
-        let embedding_bytes = self.vector_to_bytes(&vector.embedding)?;
-        let metadata_json =
-            serde_json::to_string(&vector.metadata).map_err(MemoryError::Serialization)?;
+        let _writing = self.index_writes.read().await;
+        let vector = &*self.prepare_vector(vector);
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = self.vector_insert_sql(OnConflict::Fail);
+                self.write_vector(pool, &sql, vector).await?;
+            }
+        }
+
+        // 更新缓存
+        self.cache
+            .put_vector(vector.id.clone(), vector.clone())
+            .await;
+        self.cache.invalidate_queries().await;
+        self.index_vectors([vector]).await;
+
+        Ok(())
+    }
 
+    /// 插入或更新向量（ID已存在时覆盖嵌入、元数据和更新时间）
+    pub async fn upsert_vector(&self, vector: &Vector) -> Result<()> {
+        let _writing = self.index_writes.read().await;
+        let vector = &*self.prepare_vector(vector);
         match &self.pool {
             DatabasePool::Sqlite(pool) => {
-                sqlx::query(&format!(
-                    "INSERT INTO {}vectors (id, embedding, metadata, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
-                    self.config.database.table_prefix
-                ))
-                .bind(&vector.id)
-                .bind(&embedding_bytes)
-                .bind(&metadata_json)
-                .bind(vector.created_at.to_rfc3339())
-                .bind(vector.updated_at.to_rfc3339())
-                .execute(pool)
-                .await
-                .map_err(MemoryError::Database)?;
+                let sql = self.vector_insert_sql(OnConflict::Update);
+                self.write_vector(pool, &sql, vector).await?;
             }
         }
 
@@ -313,11 +817,16 @@ impl VectorGraphDB {
         self.cache
             .put_vector(vector.id.clone(), vector.clone())
             .await;
+        self.cache.invalidate_queries().await;
+        self.index_vectors([vector]).await;
 
         Ok(())
     }
 
     /// 查询向量
+    ///
+    /// 按 `config.vector.distance_metric` 计算相似度并按相似度降序返回，`threshold` 与相似度比较：
+    /// 余弦为 [-1, 1]，点积为原始点积，欧几里得/曼哈顿为 `1 / (1 + 距离)`。
     pub async fn query_vectors(
         &self,
         request: &VectorQueryRequest,
@@ -325,46 +834,270 @@ impl VectorGraphDB {
         let limit = request.limit.unwrap_or(10);
         let threshold = request.threshold.unwrap_or(0.0);
 
-        // 从数据库获取所有向量（在实际应用中应该使用向量索引）
-        let vectors = self.get_all_vectors().await?;
+        // 向量未变更时相同请求直接复用上次结果
+        let cache_key = serde_json::to_string(request).map_err(MemoryError::Serialization)?;
+        if let Some(results) = self.cache.get_query_result(&cache_key).await {
+            return Ok(results);
+        }
+        let generation = self.cache.query_generation();
 
-        let mut results = Vec::new();
+        // 标签条件由 SQL 先行筛选；启用 IVF 索引时只读取探测到的聚类中的向量
+        let vectors = if !request.tags.is_empty() {
+            self.get_vectors_by_tags(&request.tags).await?
+        } else if let Some(ids) = self.index_candidates(&request.query_vector).await? {
+            self.get_vectors_by_ids(&ids).await?
+        } else {
+            self.get_all_vectors().await?
+        };
+        let candidate_count = vectors.len();
+
+        // 维度不一致说明存储已损坏，直接报错而不是当作不相似
+        let mismatched: Vec<&Vector> = vectors
+            .iter()
+            .filter(|v| v.embedding.len() != request.query_vector.len())
+            .collect();
+        if let Some(first) = mismatched.first() {
+            log::error!(
+                "{} of {} stored vectors do not match query dimension {} (e.g. '{}' has {})",
+                mismatched.len(),
+                candidate_count,
+                request.query_vector.len(),
+                first.id,
+                first.embedding.len()
+            );
+            return Err(MemoryError::invalid_vector_dimension(
+                request.query_vector.len(),
+                first.embedding.len(),
+            ));
+        }
 
-        for vector in vectors {
-            let similarity = self.cosine_similarity(&request.query_vector, &vector.embedding);
+        let scorer = QueryScorer::new(
+            self.config.vector.distance_metric.clone(),
+            &request.query_vector,
+            self.config.vector.normalize_on_insert,
+        );
+        let filters = request.filters.as_ref();
 
-            if similarity >= threshold {
-                // 应用过滤器
-                if let Some(filters) = &request.filters {
-                    if !self.matches_filters(&vector.metadata, filters) {
-                        continue;
-                    }
+        #[cfg(feature = "parallel")]
+        let top = if vectors.len() >= PARALLEL_SCAN_MIN_CANDIDATES {
+            scan_parallel(vectors, &scorer, threshold, filters, limit)
+        } else {
+            scan_sequential(vectors, &scorer, threshold, filters, limit)
+        };
+        #[cfg(not(feature = "parallel"))]
+        let top = scan_sequential(vectors, &scorer, threshold, filters, limit);
+
+        if top.is_empty() && candidate_count > 0 {
+            log::debug!(
+                "No vector among {} candidates passed similarity threshold {}",
+                candidate_count,
+                threshold
+            );
+        }
+
+        let results: Vec<VectorQueryResult> = top
+            .into_iter()
+            .map(|candidate| VectorQueryResult {
+                vector: candidate.vector,
+                similarity: candidate.similarity,
+                distance: candidate.distance,
+            })
+            .collect();
+        self.cache
+            .put_query_result(cache_key, generation, results.clone())
+            .await;
+        Ok(results)
+    }
+
+    /// 是否启用 IVF 索引
+    fn index_enabled(&self) -> bool {
+        self.config.vector.index_type == VectorIndexType::IVF
+    }
+
+    /// 旁路文件路径，设置命名空间时文件名前加上命名空间
+    fn index_sidecar_path(&self) -> Option<PathBuf> {
+        let path = self.config.vector.index_path.as_ref()?;
+        Some(match (self.namespace(), path.file_name()) {
+            (Some(namespace), Some(file_name)) => {
+                path.with_file_name(format!("{}.{}", namespace, file_name.to_string_lossy()))
+            }
+            _ => path.clone(),
+        })
+    }
+
+    /// 向量表的行数与最大更新时间，用于判断旁路文件是否过期
+    async fn index_state(&self) -> Result<IndexState> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let (row_count, max_updated_at): (i64, Option<String>) = sqlx::query_as(&format!(
+                    "SELECT COUNT(*), MAX(updated_at) FROM {}vectors",
+                    self.table_prefix
+                ))
+                .fetch_one(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+                Ok(IndexState {
+                    row_count: row_count as u64,
+                    max_updated_at,
+                })
+            }
+        }
+    }
+
+    /// 加载旁路文件中的索引；文件缺失、无法读取、参数不符或与向量表不一致时从向量表重建
+    async fn load_or_rebuild_index(&self) -> Result<()> {
+        if let Some(path) = self.index_sidecar_path() {
+            let state = self.index_state().await?;
+            match IvfIndex::load(&path) {
+                Ok(Some((index, saved)))
+                    if saved == state
+                        && index.dimension() == self.config.vector.dimension
+                        && index.nlist() == self.index_nlist() =>
+                {
+                    log::info!(
+                        "Loaded vector index with {} vectors from {}",
+                        index.len(),
+                        path.display()
+                    );
+                    *self.vector_index.write().await = Some(index);
+                    return Ok(());
                 }
+                Ok(Some(_)) => {
+                    log::info!("Vector index at {} is stale, rebuilding", path.display())
+                }
+                Ok(None) => {}
+                Err(e) => log::warn!(
+                    "Failed to load vector index from {}: {}, rebuilding",
+                    path.display(),
+                    e
+                ),
+            }
+        }
+        self.rebuild_index().await
+    }
+
+    /// 从整个向量表重建索引；重建期间持有索引写锁，并发写入在重建完成后再更新索引
+    async fn rebuild_index(&self) -> Result<()> {
+        let mut slot = self.vector_index.write().await;
+        let vectors = self.get_all_vectors().await?;
+        let index = IvfIndex::build(
+            self.config.vector.dimension,
+            self.index_nlist(),
+            self.config.vector.distance_metric == DistanceMetric::Cosine,
+            vectors
+                .iter()
+                .map(|vector| (vector.id.as_str(), vector.embedding.as_slice())),
+        );
+        log::info!("Rebuilt vector index with {} vectors", index.len());
+        *slot = Some(index);
+        self.index_rebuilds.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
 
-                results.push(VectorQueryResult {
-                    vector,
-                    similarity,
-                    distance: 1.0 - similarity,
-                });
+    /// 把索引连同向量表当前状态写入 `config.vector.index_path`
+    ///
+    /// 持久化期间向量写入会等待，保证写出的状态与索引一致，因此可在运行中作为检查点调用。
+    /// 未启用 IVF 索引或未配置旁路文件路径时什么也不做。
+    pub async fn persist_index(&self) -> Result<()> {
+        let Some(path) = self.index_sidecar_path() else {
+            return Ok(());
+        };
+        let _writes = self.index_writes.write().await;
+        let index = self.vector_index.read().await;
+        if let Some(index) = index.as_ref() {
+            let state = self.index_state().await?;
+            index.save(&path, &state)?;
+            log::info!(
+                "Persisted vector index with {} vectors to {}",
+                index.len(),
+                path.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// 本实例从向量表完整重建索引的次数
+    pub fn index_rebuilds(&self) -> u64 {
+        self.index_rebuilds.load(Ordering::Relaxed)
+    }
+
+    fn index_nlist(&self) -> usize {
+        self.config
+            .vector
+            .index_params
+            .nlist
+            .unwrap_or(DEFAULT_NLIST)
+    }
+
+    /// 写入提交后同步更新索引
+    async fn index_vectors<'a>(&self, vectors: impl IntoIterator<Item = &'a Vector>) {
+        if !self.index_enabled() {
+            return;
+        }
+        if let Some(index) = self.vector_index.write().await.as_mut() {
+            for vector in vectors {
+                index.insert(&vector.id, &vector.embedding);
             }
         }
+    }
+
+    /// 用索引挑选候选向量ID，未启用索引时返回 `None`
+    async fn index_candidates(&self, query_vector: &[f32]) -> Result<Option<Vec<String>>> {
+        let index = self.vector_index.read().await;
+        let Some(index) = index.as_ref() else {
+            return Ok(None);
+        };
+        if query_vector.len() != index.dimension() {
+            return Err(MemoryError::invalid_vector_dimension(
+                index.dimension(),
+                query_vector.len(),
+            ));
+        }
+        let nprobe = self
+            .config
+            .vector
+            .search_params
+            .nprobe
+            .unwrap_or(DEFAULT_NPROBE);
+        Ok(Some(index.candidates(query_vector, nprobe)))
+    }
 
-        // 按相似度排序
-        results.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
-        results.truncate(limit);
+    /// 按ID批量读取向量，不存在的ID被忽略
+    pub async fn get_vectors_by_ids(&self, ids: &[String]) -> Result<Vec<Vector>> {
+        let mut vectors = Vec::with_capacity(ids.len());
 
-        Ok(results)
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                for chunk in ids.chunks(IDS_PER_QUERY) {
+                    let placeholders = vec!["?"; chunk.len()].join(", ");
+                    let sql = format!(
+                        "SELECT id, embedding, metadata, created_at, updated_at FROM {}vectors WHERE id IN ({})",
+                        self.table_prefix, placeholders
+                    );
+                    let mut query = sqlx::query(&sql);
+                    for id in chunk {
+                        query = query.bind(id);
+                    }
+                    let rows = query.fetch_all(pool).await.map_err(MemoryError::Database)?;
+                    for row in &rows {
+                        vectors.push(self.row_to_vector(row)?);
+                    }
+                }
+            }
+        }
+
+        Ok(vectors)
     }
 
     /// 获取所有向量
-    async fn get_all_vectors(&self) -> Result<Vec<Vector>> {
+    pub async fn get_all_vectors(&self) -> Result<Vec<Vector>> {
         let mut vectors = Vec::new();
 
         match &self.pool {
             DatabasePool::Sqlite(pool) => {
                 let rows = sqlx::query(&format!(
                     "SELECT id, embedding, metadata, created_at, updated_at FROM {}vectors",
-                    self.config.database.table_prefix
+                    self.table_prefix
                 ))
                 .fetch_all(pool)
                 .await
@@ -405,63 +1138,163 @@ impl VectorGraphDB {
         Ok(vectors)
     }
 
-    /// 插入图节点
-    pub async fn insert_node(&self, node: &GraphNode) -> Result<()> {
-        let properties_json =
-            serde_json::to_string(&node.properties).map_err(MemoryError::Serialization)?;
+    /// 获取满足标签筛选条件的向量
+    pub async fn get_vectors_by_tags(&self, filter: &TagFilter) -> Result<Vec<Vector>> {
+        let (sql, args) = self.tag_filter_query(filter);
 
         match &self.pool {
             DatabasePool::Sqlite(pool) => {
-                sqlx::query(&format!(
-                    "INSERT INTO {}graph_nodes (id, node_type, properties, created_at, updated_at) VALUES (?, ?, ?, ?, ?)",
-                    self.config.database.table_prefix
-                ))
-                .bind(&node.id)
-                .bind(&node.node_type)
-                .bind(&properties_json)
-                .bind(node.created_at.to_rfc3339())
-                .bind(node.updated_at.to_rfc3339())
-                .execute(pool)
-                .await
-                .map_err(MemoryError::Database)?;
+                let rows = bind_scan_args(sqlx::query(&sql), args)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(MemoryError::Database)?;
+                rows.iter().map(|row| self.row_to_vector(row)).collect()
             }
         }
+    }
 
-        // 更新缓存
-        self.cache.put_node(node.id.clone(), node.clone()).await;
+    /// 构建 `get_vectors_by_tags` 的 SQL 与参数，各条件连接标签表求值
+    fn tag_filter_query(&self, filter: &TagFilter) -> (String, Vec<ScanArg>) {
+        let tags_table = format!("{}memory_tags", self.table_prefix);
+        let placeholders = |count: usize| vec!["?"; count].join(", ");
+        let mut clauses = Vec::new();
+        let mut args = Vec::new();
+
+        if !filter.any_of.is_empty() {
+            clauses.push(format!(
+                "v.id IN (SELECT memory_id FROM {} WHERE tag IN ({}))",
+                tags_table,
+                placeholders(filter.any_of.len())
+            ));
+            args.extend(filter.any_of.iter().cloned().map(ScanArg::Text));
+        }
 
-        Ok(())
-    }
+        let mut all_of = filter.all_of.clone();
+        all_of.sort();
+        all_of.dedup();
+        if !all_of.is_empty() {
+            // (memory_id, tag) 为主键，命中数等于标签数即全部带有
+            clauses.push(format!(
+                "v.id IN (SELECT memory_id FROM {} WHERE tag IN ({}) \
+                 GROUP BY memory_id HAVING COUNT(*) = {})",
+                tags_table,
+                placeholders(all_of.len()),
+                all_of.len()
+            ));
+            args.extend(all_of.into_iter().map(ScanArg::Text));
+        }
 
-    /// 插入图边
-    pub async fn insert_edge(&self, edge: &GraphEdge) -> Result<()> {
-        let properties_json =
-            serde_json::to_string(&edge.properties).map_err(MemoryError::Serialization)?;
+        if !filter.none_of.is_empty() {
+            clauses.push(format!(
+                "NOT EXISTS (SELECT 1 FROM {} t WHERE t.memory_id = v.id AND t.tag IN ({}))",
+                tags_table,
+                placeholders(filter.none_of.len())
+            ));
+            args.extend(filter.none_of.iter().cloned().map(ScanArg::Text));
+        }
+
+        if clauses.is_empty() {
+            clauses.push("1 = 1".to_string());
+        }
+        let sql = format!(
+            "SELECT v.id, v.embedding, v.metadata, v.created_at, v.updated_at \
+             FROM {}vectors v WHERE {}",
+            self.table_prefix,
+            clauses.join(" AND ")
+        );
+        (sql, args)
+    }
 
+    /// 插入图节点（ID已存在时报错）
+    pub async fn insert_node(&self, node: &GraphNode) -> Result<()> {
         match &self.pool {
             DatabasePool::Sqlite(pool) => {
-                sqlx::query(&format!(
-                    "INSERT INTO {}graph_edges (id, from_node, to_node, edge_type, weight, properties, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
-                    self.config.database.table_prefix
+                let sql = self.node_insert_sql(OnConflict::Fail);
+                self.write_node(pool, &sql, node).await?;
+            }
+        }
+
+        // 更新缓存
+        self.cache.put_node(node.id.clone(), node.clone()).await;
+
+        Ok(())
+    }
+
+    /// 插入或更新图节点（ID已存在时覆盖类型、属性和更新时间）
+    pub async fn upsert_node(&self, node: &GraphNode) -> Result<()> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = self.node_insert_sql(OnConflict::Update);
+                self.write_node(pool, &sql, node).await?;
+            }
+        }
+
+        // 更新缓存
+        self.cache.put_node(node.id.clone(), node.clone()).await;
+
+        Ok(())
+    }
+
+    /// 插入图边
+    pub async fn insert_edge(&self, edge: &GraphEdge) -> Result<()> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = self.edge_insert_sql(OnConflict::Fail);
+                self.write_edge(pool, &sql, edge).await?;
+            }
+        }
+
+        // 更新缓存
+        self.cache.put_edge(edge.id.clone(), edge.clone()).await;
+
+        Ok(())
+    }
+
+    /// 插入图边；已存在相同起点、终点与类型的边时改为累加其权重（限制在 [0, 1]），
+    /// 返回实际写入的边ID
+    ///
+    /// 事务以累加的 UPDATE 开始，写锁在查找前就已持有，并发调用不会插入重复的边。
+    pub async fn upsert_edge_with_accumulation(&self, edge: &GraphEdge) -> Result<String> {
+        let existing: Option<String> = match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.table_prefix;
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                let existing: Option<String> = sqlx::query_scalar(&format!(
+                    "UPDATE {prefix}graph_edges SET weight = MIN(1.0, MAX(0.0, weight + ?)), updated_at = ? \
+                     WHERE id = (SELECT id FROM {prefix}graph_edges WHERE from_node = ? AND to_node = ? AND edge_type = ? ORDER BY created_at LIMIT 1) \
+                     RETURNING id"
                 ))
-                .bind(&edge.id)
+                .bind(edge.weight)
+                .bind(Utc::now().to_rfc3339())
                 .bind(&edge.from_node)
                 .bind(&edge.to_node)
                 .bind(&edge.edge_type)
-                .bind(edge.weight)
-                .bind(&properties_json)
-                .bind(edge.created_at.to_rfc3339())
-                .bind(edge.updated_at.to_rfc3339())
-                .execute(pool)
+                .fetch_optional(&mut *tx)
                 .await
                 .map_err(MemoryError::Database)?;
-            }
-        }
 
-        // 更新缓存
-        self.cache.put_edge(edge.id.clone(), edge.clone()).await;
+                if existing.is_none() {
+                    let sql = self.edge_insert_sql(OnConflict::Fail);
+                    self.write_edge(&mut *tx, &sql, edge).await?;
+                }
 
-        Ok(())
+                tx.commit().await.map_err(MemoryError::Database)?;
+                existing
+            }
+        };
+
+        match existing {
+            Some(edge_id) => {
+                // 使缓存失效，下次读取时从数据库加载
+                self.cache.remove_edge(&edge_id).await;
+                Ok(edge_id)
+            }
+            None => {
+                self.cache.put_edge(edge.id.clone(), edge.clone()).await;
+                Ok(edge.id.clone())
+            }
+        }
     }
 
     /// 查询图
@@ -469,21 +1302,49 @@ impl VectorGraphDB {
         let mut nodes = Vec::new();
         let mut edges = Vec::new();
         let mut paths = Vec::new();
+        let mut path_costs = Vec::new();
+        let mut truncated = false;
+        let max_depth = request.max_depth.unwrap_or(3);
 
         // 简单的图遍历实现（在实际应用中应该使用更高效的算法）
         for start_node in &request.start_nodes {
-            let (node_results, edge_results, path_results) = self
-                .traverse_graph(
-                    start_node,
-                    request.max_depth.unwrap_or(3),
-                    &request.edge_types,
-                    &request.filters,
-                )
-                .await?;
+            let (node_results, edge_results, path_results, partial) = match request.traversal {
+                TraversalMode::BreadthFirst => {
+                    let (node_results, edge_results, path_results, partial) = self
+                        .traverse_graph(
+                            start_node,
+                            max_depth,
+                            &request.edge_types,
+                            &request.filters,
+                        )
+                        .await?;
+                    let path_results = path_results
+                        .into_iter()
+                        .map(|path| {
+                            let hops = path.len().saturating_sub(1) as f32;
+                            (path, hops)
+                        })
+                        .collect::<Vec<_>>();
+                    (node_results, edge_results, path_results, partial)
+                }
+                TraversalMode::Weighted => {
+                    self.traverse_graph_weighted(
+                        start_node,
+                        max_depth,
+                        &request.edge_types,
+                        &request.filters,
+                    )
+                    .await?
+                }
+            };
 
+            truncated |= partial;
             nodes.extend(node_results);
             edges.extend(edge_results);
-            paths.extend(path_results);
+            for (path, cost) in path_results {
+                paths.push(path);
+                path_costs.push(cost);
+            }
         }
 
         // 去重
@@ -493,29 +1354,50 @@ impl VectorGraphDB {
         edges.sort_by(|a, b| a.id.cmp(&b.id));
         edges.dedup_by(|a, b| a.id == b.id);
 
+        // 多个起点的结果合并后同样受预算限制
+        let traversal_config = &self.config.graph.traversal;
+        if nodes.len() > traversal_config.max_nodes || edges.len() > traversal_config.max_edges {
+            nodes.truncate(traversal_config.max_nodes);
+            edges.truncate(traversal_config.max_edges);
+            truncated = true;
+        }
+
         // 应用限制
         if let Some(limit) = request.limit {
             nodes.truncate(limit);
             edges.truncate(limit);
             paths.truncate(limit);
+            path_costs.truncate(limit);
         }
 
         Ok(GraphQueryResult {
             nodes,
             edges,
             paths,
+            path_costs,
+            truncated,
         })
     }
 
     /// 图遍历
+    ///
+    /// 广度优先展开，每条路径单独记录已经过的节点，因此同一节点可以经由多条不同路径到达，
+    /// 而环路不会被重复展开。有向边只沿起点到终点的方向展开。检查的边数达到 `graph.traversal.max_explored_edges`，或收集的
+    /// 节点、边数达到 `max_nodes`/`max_edges` 后停止展开，并返回截断标记。
     async fn traverse_graph(
         &self,
         start_node: &str,
         max_depth: usize,
         edge_types: &Option<Vec<String>>,
         filters: &Option<HashMap<String, serde_json::Value>>,
-    ) -> Result<(Vec<GraphNode>, Vec<GraphEdge>, Vec<Vec<String>>)> {
-        let mut visited_nodes = std::collections::HashSet::new();
+    ) -> Result<(Vec<GraphNode>, Vec<GraphEdge>, Vec<Vec<String>>, bool)> {
+        let traversal_config = &self.config.graph.traversal;
+        let max_explored_edges = traversal_config.max_explored_edges;
+        let mut explored_edges = 0usize;
+        let mut truncated = false;
+        // 节点是否通过过滤器，每个节点只查询一次
+        let mut node_matches: HashMap<String, bool> = HashMap::new();
+        let mut seen_edges = std::collections::HashSet::new();
         let mut result_nodes = Vec::new();
         let mut result_edges = Vec::new();
         let mut paths = Vec::new();
@@ -523,40 +1405,77 @@ impl VectorGraphDB {
         let mut queue = std::collections::VecDeque::new();
         queue.push_back((start_node.to_string(), 0, vec![start_node.to_string()]));
 
-        while let Some((current_node, depth, path)) = queue.pop_front() {
-            if depth > max_depth || visited_nodes.contains(&current_node) {
+        'traversal: while let Some((current_node, depth, path)) = queue.pop_front() {
+            if depth > max_depth {
                 continue;
             }
 
-            visited_nodes.insert(current_node.clone());
-
-            // 获取当前节点
-            if let Ok(node) = self.get_node(&current_node).await {
-                // 应用过滤器
-                if let Some(filters) = filters {
-                    if !self.matches_filters(&node.properties, filters) {
-                        continue;
+            let matches = match node_matches.get(&current_node) {
+                Some(&matches) => matches,
+                None => {
+                    let mut matches = true;
+                    // 获取当前节点
+                    if let Ok(Some(node)) = self.get_node(&current_node).await {
+                        // 应用过滤器
+                        if let Some(filters) = filters {
+                            matches = Self::matches_filters(&node.properties, filters);
+                        }
+                        if matches {
+                            if result_nodes.len() >= traversal_config.max_nodes {
+                                truncated = true;
+                                break 'traversal;
+                            }
+                            result_nodes.push(node);
+                        }
                     }
+                    node_matches.insert(current_node.clone(), matches);
+                    matches
                 }
-                result_nodes.push(node);
+            };
+            if !matches {
+                continue;
+            }
+
+            if explored_edges >= max_explored_edges {
+                truncated = true;
+                continue;
             }
 
             // 获取相邻边
             let adjacent_edges = self.get_adjacent_edges(&current_node, edge_types).await?;
 
             for edge in adjacent_edges {
-                result_edges.push(edge.clone());
+                if explored_edges >= max_explored_edges {
+                    log::debug!(
+                        "Graph traversal from {} stopped after exploring {} edges",
+                        start_node,
+                        explored_edges
+                    );
+                    truncated = true;
+                    break;
+                }
+                explored_edges += 1;
 
                 let next_node = if edge.from_node == current_node {
-                    &edge.to_node
+                    edge.to_node.clone()
                 } else {
-                    &edge.from_node
+                    edge.from_node.clone()
                 };
 
-                if !visited_nodes.contains(next_node) {
+                if !seen_edges.contains(&edge.id) {
+                    if result_edges.len() >= traversal_config.max_edges {
+                        truncated = true;
+                        break 'traversal;
+                    }
+                    seen_edges.insert(edge.id.clone());
+                    result_edges.push(edge);
+                }
+
+                // 只避免当前路径上的环路，其他路径经过的节点仍可再次到达
+                if !path.contains(&next_node) {
                     let mut new_path = path.clone();
                     new_path.push(next_node.clone());
-                    queue.push_back((next_node.clone(), depth + 1, new_path.clone()));
+                    queue.push_back((next_node, depth + 1, new_path.clone()));
 
                     if depth < max_depth {
                         paths.push(new_path);
@@ -565,11 +1484,120 @@ impl VectorGraphDB {
             }
         }
 
-        Ok((result_nodes, result_edges, paths))
+        Ok((result_nodes, result_edges, paths, truncated))
+    }
+
+    /// 按边权重的最短路径遍历
+    ///
+    /// 从起点出发按累计代价（每条边 `1 / weight`）由小到大展开，只展开 `max_depth` 跳以内的节点，
+    /// 返回到达每个节点的最优路径及其代价（按代价升序）。收集的节点、边数达到
+    /// `graph.traversal.max_nodes`/`max_edges` 后停止展开，并返回截断标记。
+    async fn traverse_graph_weighted(
+        &self,
+        start_node: &str,
+        max_depth: usize,
+        edge_types: &Option<Vec<String>>,
+        filters: &Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<(
+        Vec<GraphNode>,
+        Vec<GraphEdge>,
+        Vec<(Vec<String>, f32)>,
+        bool,
+    )> {
+        let traversal_config = &self.config.graph.traversal;
+        let mut truncated = false;
+        let mut seen_edges = std::collections::HashSet::new();
+        let mut settled = std::collections::HashSet::new();
+        let mut best_costs: HashMap<String, f32> = HashMap::new();
+        let mut result_nodes = Vec::new();
+        let mut result_edges = Vec::new();
+        let mut paths = Vec::new();
+
+        let mut frontier = BinaryHeap::new();
+        best_costs.insert(start_node.to_string(), 0.0);
+        frontier.push(Reverse(WeightedFrontier {
+            cost: 0.0,
+            path: vec![start_node.to_string()],
+        }));
+
+        'traversal: while let Some(Reverse(WeightedFrontier { cost, path })) = frontier.pop() {
+            let current_node = path.last().cloned().unwrap_or_default();
+            if !settled.insert(current_node.clone()) {
+                continue;
+            }
+
+            if let Ok(Some(node)) = self.get_node(&current_node).await {
+                if let Some(filters) = filters {
+                    if !Self::matches_filters(&node.properties, filters) {
+                        continue;
+                    }
+                }
+                if result_nodes.len() >= traversal_config.max_nodes {
+                    truncated = true;
+                    break;
+                }
+                result_nodes.push(node);
+            }
+
+            if path.len() > 1 {
+                paths.push((path.clone(), cost));
+            }
+
+            let depth = path.len() - 1;
+            if depth >= max_depth {
+                continue;
+            }
+
+            let adjacent_edges = self.get_adjacent_edges(&current_node, edge_types).await?;
+
+            for edge in adjacent_edges {
+                if !seen_edges.contains(&edge.id) {
+                    if result_edges.len() >= traversal_config.max_edges {
+                        truncated = true;
+                        break 'traversal;
+                    }
+                    seen_edges.insert(edge.id.clone());
+                    result_edges.push(edge.clone());
+                }
+
+                if edge.weight <= 0.0 {
+                    continue;
+                }
+
+                let next_node = if edge.from_node == current_node {
+                    &edge.to_node
+                } else {
+                    &edge.from_node
+                };
+                if settled.contains(next_node) {
+                    continue;
+                }
+
+                let next_cost = cost + 1.0 / edge.weight;
+                if best_costs
+                    .get(next_node)
+                    .is_some_and(|&best| best <= next_cost)
+                {
+                    continue;
+                }
+                best_costs.insert(next_node.clone(), next_cost);
+
+                let mut new_path = path.clone();
+                new_path.push(next_node.clone());
+                frontier.push(Reverse(WeightedFrontier {
+                    cost: next_cost,
+                    path: new_path,
+                }));
+            }
+        }
+
+        Ok((result_nodes, result_edges, paths, truncated))
     }
 
     /// 更新向量
     pub async fn update_vector(&self, vector: &Vector) -> Result<()> {
+        let _writing = self.index_writes.read().await;
+        let vector = &*self.prepare_vector(vector);
         let embedding_bytes = self.vector_to_bytes(&vector.embedding)?;
         let metadata_json =
             serde_json::to_string(&vector.metadata).map_err(MemoryError::Serialization)?;
@@ -578,7 +1606,7 @@ impl VectorGraphDB {
             DatabasePool::Sqlite(pool) => {
                 sqlx::query(&format!(
                     "UPDATE {}vectors SET embedding = ?, metadata = ?, updated_at = ? WHERE id = ?",
-                    self.config.database.table_prefix
+                    self.table_prefix
                 ))
                 .bind(&embedding_bytes)
                 .bind(&metadata_json)
@@ -594,10 +1622,64 @@ impl VectorGraphDB {
         self.cache
             .put_vector(vector.id.clone(), vector.clone())
             .await;
+        self.cache.invalidate_queries().await;
+        self.index_vectors([vector]).await;
 
         Ok(())
     }
 
+    /// 批量更新向量（单个事务内完成），返回实际更新的行数
+    pub async fn update_vectors(&self, vectors: &[Vector]) -> Result<u64> {
+        if vectors.is_empty() {
+            return Ok(0);
+        }
+        let vectors: Vec<Cow<'_, Vector>> =
+            vectors.iter().map(|v| self.prepare_vector(v)).collect();
+        let _writing = self.index_writes.read().await;
+
+        let mut rows_affected = 0;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = format!(
+                    "UPDATE {}vectors SET embedding = ?, metadata = ?, updated_at = ? WHERE id = ?",
+                    self.table_prefix
+                );
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                for vector in &vectors {
+                    let embedding_bytes = self.vector_to_bytes(&vector.embedding)?;
+                    let metadata_json = serde_json::to_string(&vector.metadata)
+                        .map_err(MemoryError::Serialization)?;
+
+                    let result = sqlx::query(&sql)
+                        .bind(&embedding_bytes)
+                        .bind(&metadata_json)
+                        .bind(vector.updated_at.to_rfc3339())
+                        .bind(&vector.id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                    rows_affected += result.rows_affected();
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        // 更新缓存
+        self.index_vectors(vectors.iter().map(|vector| vector.as_ref()))
+            .await;
+        for vector in vectors {
+            self.cache
+                .put_vector(vector.id.clone(), vector.into_owned())
+                .await;
+        }
+        self.cache.invalidate_queries().await;
+
+        Ok(rows_affected)
+    }
+
     /// 更新节点
     pub async fn update_node(&self, node: &GraphNode) -> Result<()> {
         let properties_json =
@@ -607,7 +1689,7 @@ impl VectorGraphDB {
             DatabasePool::Sqlite(pool) => {
                 sqlx::query(&format!(
                     "UPDATE {}graph_nodes SET node_type = ?, properties = ?, updated_at = ? WHERE id = ?",
-                    self.config.database.table_prefix
+                    self.table_prefix
                 ))
                 .bind(&node.node_type)
                 .bind(&properties_json)
@@ -636,7 +1718,7 @@ impl VectorGraphDB {
             DatabasePool::Sqlite(pool) => {
                 let row = sqlx::query(&format!(
                     "SELECT id, embedding, metadata, created_at, updated_at FROM {}vectors WHERE id = ?",
-                    self.config.database.table_prefix
+                    self.table_prefix
                 ))
                 .bind(vector_id)
                 .fetch_one(pool)
@@ -681,64 +1763,65 @@ impl VectorGraphDB {
         }
     }
 
-    /// 获取节点
-    async fn get_node(&self, node_id: &str) -> Result<GraphNode> {
+    /// 获取节点，节点不存在时返回 `None`
+    pub async fn get_node(&self, node_id: &str) -> Result<Option<GraphNode>> {
         // 先检查缓存
         if let Some(node) = self.cache.get_node(node_id).await {
-            return Ok(node);
+            return Ok(Some(node));
         }
 
         match &self.pool {
             DatabasePool::Sqlite(pool) => {
                 let row = sqlx::query(&format!(
                     "SELECT id, node_type, properties, created_at, updated_at FROM {}graph_nodes WHERE id = ?",
-                    self.config.database.table_prefix
+                    self.table_prefix
                 ))
                 .bind(node_id)
-                .fetch_one(pool)
+                .fetch_optional(pool)
                 .await
                 .map_err(MemoryError::Database)?;
 
-                let id: String = row.get("id");
-                let node_type: String = row.get("node_type");
-                let properties_str: String = row.get("properties");
-                let created_at_str: String = row.get("created_at");
-                let updated_at_str: String = row.get("updated_at");
-
-                let properties: HashMap<String, serde_json::Value> =
-                    serde_json::from_str(&properties_str).map_err(MemoryError::Serialization)?;
-                let created_at = DateTime::parse_from_rfc3339(&created_at_str)
-                    .map_err(|e| MemoryError::Internal {
-                        message: format!("DateTime parse failed: {}", e),
-                    })?
-                    .with_timezone(&Utc);
-                let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
-                    .map_err(|e| MemoryError::Internal {
-                        message: format!("DateTime parse failed: {}", e),
-                    })?
-                    .with_timezone(&Utc);
-
-                let node = GraphNode {
-                    id,
-                    node_type,
-                    properties,
-                    created_at,
-                    updated_at,
+                let Some(row) = row else {
+                    return Ok(None);
                 };
+                let node = self.row_to_node(&row)?;
 
                 // 更新缓存
                 self.cache.put_node(node.id.clone(), node.clone()).await;
 
-                Ok(node)
+                Ok(Some(node))
             }
         }
     }
 
-    /// 获取相邻边
+    /// 获取可从节点出发遍历的边（有向边只包含以该节点为起点的边），节点不存在时返回 `None`
+    pub async fn get_edges_for_node(
+        &self,
+        node_id: &str,
+        edge_types: &Option<Vec<String>>,
+    ) -> Result<Option<Vec<GraphEdge>>> {
+        if self.get_node(node_id).await?.is_none() {
+            return Ok(None);
+        }
+        self.get_adjacent_edges(node_id, edge_types).await.map(Some)
+    }
+
+    /// 获取可从节点出发遍历的相邻边（有向边只包含以该节点为起点的边）
     async fn get_adjacent_edges(
         &self,
         node_id: &str,
         edge_types: &Option<Vec<String>>,
+    ) -> Result<Vec<GraphEdge>> {
+        let mut edges = self.get_incident_edges(node_id, edge_types).await?;
+        edges.retain(|edge| edge.traversable_from(node_id));
+        Ok(edges)
+    }
+
+    /// 获取与节点相连的所有边，不考虑方向
+    pub async fn get_incident_edges(
+        &self,
+        node_id: &str,
+        edge_types: &Option<Vec<String>>,
     ) -> Result<Vec<GraphEdge>> {
         let mut edges = Vec::new();
 
@@ -755,7 +1838,7 @@ impl VectorGraphDB {
             DatabasePool::Sqlite(pool) => {
                 let sql = format!(
                     "SELECT id, from_node, to_node, edge_type, weight, properties, created_at, updated_at FROM {}graph_edges WHERE (from_node = ? OR to_node = ?){}",
-                    self.config.database.table_prefix, type_filter
+                    self.table_prefix, type_filter
                 );
 
                 let mut query = sqlx::query(&sql).bind(node_id).bind(node_id);
@@ -769,39 +1852,7 @@ impl VectorGraphDB {
                 let rows = query.fetch_all(pool).await.map_err(MemoryError::Database)?;
 
                 for row in rows {
-                    let id: String = row.get("id");
-                    let from_node: String = row.get("from_node");
-                    let to_node: String = row.get("to_node");
-                    let edge_type: String = row.get("edge_type");
-                    let weight: f32 = row.get("weight");
-                    let properties_str: String = row.get("properties");
-                    let created_at_str: String = row.get("created_at");
-                    let updated_at_str: String = row.get("updated_at");
-
-                    let properties: HashMap<String, serde_json::Value> =
-                        serde_json::from_str(&properties_str)
-                            .map_err(MemoryError::Serialization)?;
-                    let created_at = DateTime::parse_from_rfc3339(&created_at_str)
-                        .map_err(|e| MemoryError::Internal {
-                            message: format!("DateTime parse failed: {}", e),
-                        })?
-                        .with_timezone(&Utc);
-                    let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
-                        .map_err(|e| MemoryError::Internal {
-                            message: format!("DateTime parse failed: {}", e),
-                        })?
-                        .with_timezone(&Utc);
-
-                    edges.push(GraphEdge {
-                        id,
-                        from_node,
-                        to_node,
-                        edge_type,
-                        weight,
-                        properties,
-                        created_at,
-                        updated_at,
-                    });
+                    edges.push(self.row_to_edge(&row)?);
                 }
             }
         }
@@ -809,67 +1860,1233 @@ impl VectorGraphDB {
         Ok(edges)
     }
 
-    /// 清除缓存
-    pub async fn clear_cache(&self) {
-        self.cache.clear().await;
-    }
+    /// 获取两端都在给定节点集合内的边
+    pub async fn get_edges_among(&self, node_ids: &[String]) -> Result<Vec<GraphEdge>> {
+        if node_ids.is_empty() {
+            return Ok(Vec::new());
+        }
 
-    /// 获取统计信息
-    pub async fn get_stats(&self) -> Result<(u64, u64, u64, (usize, usize, usize, usize))> {
-        let (vector_count, node_count, edge_count) = match &self.pool {
-            DatabasePool::Sqlite(pool) => {
-                let prefix = &self.config.database.table_prefix;
+        let placeholders = node_ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ");
+        let mut edges = Vec::new();
 
-                let vector_count: i64 =
-                    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}vectors", prefix))
-                        .fetch_one(pool)
-                        .await
-                        .map_err(MemoryError::Database)?;
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = format!(
+                    "SELECT id, from_node, to_node, edge_type, weight, properties, created_at, updated_at FROM {}graph_edges WHERE from_node IN ({}) AND to_node IN ({})",
+                    self.table_prefix, placeholders, placeholders
+                );
 
-                let node_count: i64 =
-                    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}graph_nodes", prefix))
-                        .fetch_one(pool)
-                        .await
-                        .map_err(MemoryError::Database)?;
+                let mut query = sqlx::query(&sql);
+                for _ in 0..2 {
+                    for node_id in node_ids {
+                        query = query.bind(node_id);
+                    }
+                }
 
-                let edge_count: i64 =
-                    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}graph_edges", prefix))
-                        .fetch_one(pool)
-                        .await
-                        .map_err(MemoryError::Database)?;
+                let rows = query.fetch_all(pool).await.map_err(MemoryError::Database)?;
 
-                (vector_count as u64, node_count as u64, edge_count as u64)
+                for row in rows {
+                    edges.push(self.row_to_edge(&row)?);
+                }
             }
-        };
-
-        let cache_stats = self.cache.get_stats().await;
+        }
 
-        Ok((vector_count, node_count, edge_count, cache_stats))
+        Ok(edges)
     }
 
-    // 辅助函数
-
-    /// 计算余弦相似度
-    fn cosine_similarity(&self, a: &[f32], b: &[f32]) -> f32 {
-        if a.len() != b.len() {
-            return 0.0;
+    /// 获取边
+    pub async fn get_edge(&self, edge_id: &str) -> Result<GraphEdge> {
+        // 先检查缓存
+        if let Some(edge) = self.cache.get_edge(edge_id).await {
+            return Ok(edge);
         }
 
-        let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-        let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-        let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let row = sqlx::query(&format!(
+                    "SELECT id, from_node, to_node, edge_type, weight, properties, created_at, updated_at FROM {}graph_edges WHERE id = ?",
+                    self.table_prefix
+                ))
+                .bind(edge_id)
+                .fetch_one(pool)
+                .await
+                .map_err(MemoryError::Database)?;
 
-        if norm_a == 0.0 || norm_b == 0.0 {
-            0.0
-        } else {
-            dot_product / (norm_a * norm_b)
+                let edge = self.row_to_edge(&row)?;
+
+                // 更新缓存
+                self.cache.put_edge(edge.id.clone(), edge.clone()).await;
+
+                Ok(edge)
+            }
         }
     }
 
-    /// 向量转字节
-    fn vector_to_bytes(&self, vector: &[f32]) -> Result<Vec<u8>> {
-        let mut bytes = Vec::with_capacity(vector.len() * 4);
-        for &value in vector {
+    /// 获取所有图边
+    pub async fn get_graph_edges(&self) -> Result<Vec<GraphEdge>> {
+        let mut edges = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT id, from_node, to_node, edge_type, weight, properties, created_at, updated_at FROM {}graph_edges",
+                    self.table_prefix
+                ))
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                for row in rows {
+                    edges.push(self.row_to_edge(&row)?);
+                }
+            }
+        }
+
+        Ok(edges)
+    }
+
+    /// 批量调整边权重（结果限制在 [0, 1]），返回实际更新的边ID
+    pub async fn adjust_edge_weights(&self, adjustments: &[(String, f32)]) -> Result<Vec<String>> {
+        let mut updated = Vec::new();
+        if adjustments.is_empty() {
+            return Ok(updated);
+        }
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = format!(
+                    "UPDATE {}graph_edges SET weight = MIN(1.0, MAX(0.0, weight + ?)), updated_at = ? WHERE id = ?",
+                    self.table_prefix
+                );
+                let now = Utc::now().to_rfc3339();
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                for (edge_id, delta) in adjustments {
+                    let result = sqlx::query(&sql)
+                        .bind(delta)
+                        .bind(&now)
+                        .bind(edge_id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                    if result.rows_affected() > 0 {
+                        updated.push(edge_id.clone());
+                    }
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        // 使缓存失效，下次读取时从数据库加载
+        for edge_id in &updated {
+            self.cache.remove_edge(edge_id).await;
+        }
+
+        Ok(updated)
+    }
+
+    /// 删除给定边中权重低于阈值的边，返回被删除的边ID
+    pub async fn delete_edges_below(
+        &self,
+        edge_ids: &[String],
+        threshold: f32,
+    ) -> Result<Vec<String>> {
+        let mut deleted = Vec::new();
+        if edge_ids.is_empty() {
+            return Ok(deleted);
+        }
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = format!(
+                    "DELETE FROM {}graph_edges WHERE id = ? AND weight < ?",
+                    self.table_prefix
+                );
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                for edge_id in edge_ids {
+                    let result = sqlx::query(&sql)
+                        .bind(edge_id)
+                        .bind(threshold)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                    if result.rows_affected() > 0 {
+                        deleted.push(edge_id.clone());
+                    }
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        for edge_id in &deleted {
+            self.cache.remove_edge(edge_id).await;
+        }
+
+        Ok(deleted)
+    }
+
+    /// 将源节点的所有边改接到目标节点
+    ///
+    /// 改接后端点与类型相同的平行边合并为一条并累加权重，源节点与目标节点之间的边（改接后成为自环）被删除。
+    /// 返回被删除的边ID。
+    pub async fn redirect_edges(&self, target: &str, sources: &[String]) -> Result<Vec<String>> {
+        let mut removed = Vec::new();
+        if sources.is_empty() {
+            return Ok(removed);
+        }
+
+        let mut involved: Vec<&str> = sources.iter().map(String::as_str).collect();
+        involved.push(target);
+        let placeholders = vec!["?"; involved.len()].join(", ");
+        let remap = |id: String| {
+            if sources.contains(&id) {
+                target.to_string()
+            } else {
+                id
+            }
+        };
+        let mut changed = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.table_prefix;
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                let sql = format!(
+                    "SELECT id, from_node, to_node, edge_type, weight, properties, created_at, updated_at FROM {}graph_edges WHERE from_node IN ({}) OR to_node IN ({}) ORDER BY created_at, id",
+                    prefix, placeholders, placeholders
+                );
+                let mut query = sqlx::query(&sql);
+                for id in involved.iter().chain(involved.iter()) {
+                    query = query.bind(*id);
+                }
+                let rows = query
+                    .fetch_all(&mut *tx)
+                    .await
+                    .map_err(MemoryError::Database)?;
+
+                let mut survivors: Vec<(GraphEdge, bool)> = Vec::new();
+                let mut index: HashMap<(String, String, String), usize> = HashMap::new();
+                for row in rows {
+                    let mut edge = self.row_to_edge(&row)?;
+                    let (from_node, to_node) =
+                        (remap(edge.from_node.clone()), remap(edge.to_node.clone()));
+                    let moved = from_node != edge.from_node || to_node != edge.to_node;
+                    edge.from_node = from_node;
+                    edge.to_node = to_node;
+
+                    if edge.from_node == edge.to_node {
+                        removed.push(edge.id);
+                        continue;
+                    }
+
+                    let key = (
+                        edge.from_node.clone(),
+                        edge.to_node.clone(),
+                        edge.edge_type.clone(),
+                    );
+                    match index.get(&key) {
+                        Some(&i) => {
+                            survivors[i].0.weight += edge.weight;
+                            survivors[i].1 = true;
+                            removed.push(edge.id);
+                        }
+                        None => {
+                            index.insert(key, survivors.len());
+                            survivors.push((edge, moved));
+                        }
+                    }
+                }
+
+                let delete_sql = format!("DELETE FROM {}graph_edges WHERE id = ?", prefix);
+                for edge_id in &removed {
+                    sqlx::query(&delete_sql)
+                        .bind(edge_id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                }
+
+                let update_sql = format!(
+                    "UPDATE {}graph_edges SET from_node = ?, to_node = ?, weight = ?, updated_at = ? WHERE id = ?",
+                    prefix
+                );
+                let now = Utc::now().to_rfc3339();
+                for (edge, _) in survivors.iter().filter(|(_, modified)| *modified) {
+                    sqlx::query(&update_sql)
+                        .bind(&edge.from_node)
+                        .bind(&edge.to_node)
+                        .bind(edge.weight)
+                        .bind(&now)
+                        .bind(&edge.id)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                    changed.push(edge.id.clone());
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        for edge_id in removed.iter().chain(changed.iter()) {
+            self.cache.remove_edge(edge_id).await;
+        }
+
+        Ok(removed)
+    }
+
+    /// 只按元数据筛选向量，不计算相似度
+    ///
+    /// 条件之间为“与”关系，自动排除软删除的向量；未指定排序时按创建时间降序，
+    /// 排序键相同时按ID升序。
+    pub async fn scan_vectors(
+        &self,
+        conditions: &[MetadataCondition],
+        order_by: Option<(MetadataSortKey, SortOrder)>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Vector>> {
+        let (sql, args) = self.scan_query(conditions, order_by);
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = bind_scan_args(sqlx::query(&sql), args)
+                    .bind(limit as i64)
+                    .bind(offset as i64)
+                    .fetch_all(pool)
+                    .await
+                    .map_err(MemoryError::Database)?;
+                rows.iter().map(|row| self.row_to_vector(row)).collect()
+            }
+        }
+    }
+
+    /// 构建 `scan_vectors` 的 SQL 与参数，末尾留有 LIMIT/OFFSET 两个占位符
+    fn scan_query(
+        &self,
+        conditions: &[MetadataCondition],
+        order_by: Option<(MetadataSortKey, SortOrder)>,
+    ) -> (String, Vec<ScanArg>) {
+        let mut clauses = vec![NOT_DELETED_CONDITION.to_string()];
+        let mut args = Vec::new();
+        for condition in conditions {
+            match condition {
+                MetadataCondition::Equals(key, value) => {
+                    clauses.push("json_extract(metadata, ?) = ?".to_string());
+                    args.push(ScanArg::Text(format!("$.{}", key)));
+                    args.push(match value {
+                        serde_json::Value::String(s) => ScanArg::Text(s.clone()),
+                        serde_json::Value::Bool(b) => ScanArg::Int(*b as i64),
+                        serde_json::Value::Number(n) => match n.as_i64() {
+                            Some(i) => ScanArg::Int(i),
+                            None => ScanArg::Real(n.as_f64().unwrap_or_default()),
+                        },
+                        other => ScanArg::Text(other.to_string()),
+                    });
+                }
+                MetadataCondition::Contains(key, value) => {
+                    let path = format!("$.{}", key);
+                    match TERM_TABLES
+                        .iter()
+                        .find(|(_, _, term_path)| *term_path == path)
+                    {
+                        // 标签与关键词走规范化表的索引
+                        Some((table, column, _)) => clauses.push(format!(
+                            "id IN (SELECT memory_id FROM {}{} WHERE {} = ?)",
+                            self.table_prefix, table, column
+                        )),
+                        None => {
+                            clauses.push(
+                                "EXISTS (SELECT 1 FROM json_each(metadata, ?) WHERE json_each.value = ?)"
+                                    .to_string(),
+                            );
+                            args.push(ScanArg::Text(path));
+                        }
+                    }
+                    args.push(ScanArg::Text(value.clone()));
+                }
+                MetadataCondition::AtLeast(key, min) => {
+                    clauses.push("json_extract(metadata, ?) >= ?".to_string());
+                    args.push(ScanArg::Text(format!("$.{}", key)));
+                    args.push(ScanArg::Real(*min));
+                }
+                MetadataCondition::Between(key, start, end) => {
+                    clauses.push(
+                        "julianday(json_extract(metadata, ?)) BETWEEN julianday(?) AND julianday(?)"
+                            .to_string(),
+                    );
+                    args.push(ScanArg::Text(format!("$.{}", key)));
+                    args.push(ScanArg::Text(start.to_rfc3339()));
+                    args.push(ScanArg::Text(end.to_rfc3339()));
+                }
+            }
+        }
+
+        let (sort_expr, sort_order) = match order_by {
+            Some((MetadataSortKey::Number(key), order)) => {
+                args.push(ScanArg::Text(format!("$.{}", key)));
+                ("json_extract(metadata, ?)", order)
+            }
+            Some((MetadataSortKey::Time(key), order)) => {
+                args.push(ScanArg::Text(format!("$.{}", key)));
+                ("julianday(json_extract(metadata, ?))", order)
+            }
+            None => ("julianday(created_at)", SortOrder::Desc),
+        };
+        let direction = match sort_order {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        };
+        let sql = format!(
+            "SELECT id, embedding, metadata, created_at, updated_at FROM {}vectors WHERE {} ORDER BY {} {}, id ASC LIMIT ? OFFSET ?",
+            self.table_prefix,
+            clauses.join(" AND "),
+            sort_expr,
+            direction
+        );
+        (sql, args)
+    }
+
+    /// 分页获取向量ID（按ID升序，`after` 为上一页最后一个ID）
+    pub async fn list_vector_ids(
+        &self,
+        after: Option<&str>,
+        page_size: usize,
+        include_deleted: bool,
+    ) -> Result<Vec<String>> {
+        let deleted_filter = if include_deleted {
+            String::new()
+        } else {
+            format!(" AND {}", NOT_DELETED_CONDITION)
+        };
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let ids: Vec<String> = sqlx::query_scalar(&format!(
+                    "SELECT id FROM {}vectors WHERE id > ?{} ORDER BY id LIMIT ?",
+                    self.table_prefix, deleted_filter
+                ))
+                .bind(after.unwrap_or(""))
+                .bind(page_size as i64)
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+                Ok(ids)
+            }
+        }
+    }
+
+    /// 分页获取边ID（按ID升序，`after` 为上一页最后一个ID）
+    pub async fn list_edge_ids(
+        &self,
+        after: Option<&str>,
+        page_size: usize,
+    ) -> Result<Vec<String>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let ids: Vec<String> = sqlx::query_scalar(&format!(
+                    "SELECT id FROM {}graph_edges WHERE id > ? ORDER BY id LIMIT ?",
+                    self.table_prefix
+                ))
+                .bind(after.unwrap_or(""))
+                .bind(page_size as i64)
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+                Ok(ids)
+            }
+        }
+    }
+
+    /// 写入一条交互记录
+    pub async fn insert_interaction(&self, record: &InteractionRecord) -> Result<()> {
+        let interaction_type =
+            serde_json::to_value(&record.interaction_type).map_err(MemoryError::Serialization)?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "INSERT INTO {}interactions (memory_id, interaction_type, timestamp, feedback_score, dwell_time) VALUES (?, ?, ?, ?, ?)",
+                    self.table_prefix
+                ))
+                .bind(&record.memory_id)
+                .bind(interaction_type.as_str().unwrap_or_default())
+                .bind(record.timestamp.to_rfc3339())
+                .bind(record.feedback_score)
+                .bind(record.dwell_time.map(|ms| ms as i64))
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 获取最近的 `limit` 条交互记录（按时间升序）
+    pub async fn get_recent_interactions(&self, limit: usize) -> Result<Vec<InteractionRecord>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT memory_id, interaction_type, timestamp, feedback_score, dwell_time FROM {}interactions ORDER BY timestamp DESC, id DESC LIMIT ?",
+                    self.table_prefix
+                ))
+                .bind(limit as i64)
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                let mut records = rows
+                    .iter()
+                    .map(|row| self.row_to_interaction(row))
+                    .collect::<Result<Vec<_>>>()?;
+                records.reverse();
+                Ok(records)
+            }
+        }
+    }
+
+    /// 获取时间范围 `[start, end]` 内的交互记录（按时间升序）
+    pub async fn get_interactions_between(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<InteractionRecord>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT memory_id, interaction_type, timestamp, feedback_score, dwell_time FROM {}interactions WHERE timestamp >= ? AND timestamp <= ? ORDER BY timestamp, id",
+                    self.table_prefix
+                ))
+                .bind(start.to_rfc3339())
+                .bind(end.to_rfc3339())
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                rows.iter()
+                    .map(|row| self.row_to_interaction(row))
+                    .collect()
+            }
+        }
+    }
+
+    /// 将一条反馈加入待处理队列
+    pub async fn insert_feedback(
+        &self,
+        id: &str,
+        memory_id: &str,
+        record: &serde_json::Value,
+    ) -> Result<()> {
+        let record_json = serde_json::to_string(record).map_err(MemoryError::Serialization)?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "INSERT INTO {}feedback (id, memory_id, record, processed, created_at) VALUES (?, ?, ?, 0, ?)",
+                    self.table_prefix
+                ))
+                .bind(id)
+                .bind(memory_id)
+                .bind(&record_json)
+                .bind(Utc::now().to_rfc3339())
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 按加入顺序获取最多 `limit` 条未处理的反馈，返回 `(ID, 反馈JSON)`
+    pub async fn get_unprocessed_feedback(
+        &self,
+        limit: usize,
+    ) -> Result<Vec<(String, serde_json::Value)>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT id, record FROM {}feedback WHERE processed = 0 ORDER BY created_at, rowid LIMIT ?",
+                    self.table_prefix
+                ))
+                .bind(limit as i64)
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                rows.iter()
+                    .map(|row| {
+                        let record: String = row.get("record");
+                        let record =
+                            serde_json::from_str(&record).map_err(MemoryError::Serialization)?;
+                        Ok((row.get("id"), record))
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// 将反馈标记为已处理
+    pub async fn mark_feedback_processed(&self, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = format!(
+                    "UPDATE {}feedback SET processed = 1 WHERE id IN ({})",
+                    self.table_prefix,
+                    ids.iter().map(|_| "?").collect::<Vec<_>>().join(", ")
+                );
+                let mut query = sqlx::query(&sql);
+                for id in ids {
+                    query = query.bind(id);
+                }
+                query.execute(pool).await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 保存累计统计计数（同名计数覆盖）
+    pub async fn save_stat_counters(&self, counters: &[(&str, u64)]) -> Result<()> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let sql = format!(
+                    "INSERT INTO {}stats (name, value, updated_at) VALUES (?, ?, ?) ON CONFLICT(name) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at",
+                    self.table_prefix
+                );
+                let now = Utc::now().to_rfc3339();
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+                for (name, value) in counters {
+                    sqlx::query(&sql)
+                        .bind(name)
+                        .bind(*value as i64)
+                        .bind(&now)
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                }
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 读取全部累计统计计数
+    pub async fn load_stat_counters(&self) -> Result<HashMap<String, u64>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows: Vec<(String, i64)> = sqlx::query_as(&format!(
+                    "SELECT name, value FROM {}stats",
+                    self.table_prefix
+                ))
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|(name, value)| (name, value.max(0) as u64))
+                    .collect())
+            }
+        }
+    }
+
+    /// 未删除记忆的平均重要性，没有记忆时为 0
+    pub async fn average_importance(&self) -> Result<f32> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let average: Option<f64> = sqlx::query_scalar(&format!(
+                    "SELECT AVG(json_extract(metadata, '$.importance')) FROM {}vectors WHERE {}",
+                    self.table_prefix, NOT_DELETED_CONDITION
+                ))
+                .fetch_one(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                Ok(average.unwrap_or_default() as f32)
+            }
+        }
+    }
+
+    /// 保存用户档案（已存在时覆盖）
+    pub async fn save_user_profile(&self, id: &str, profile: &serde_json::Value) -> Result<()> {
+        let profile_json = serde_json::to_string(profile).map_err(MemoryError::Serialization)?;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query(&format!(
+                    "INSERT INTO {}user_profiles (id, profile, updated_at) VALUES (?, ?, ?) ON CONFLICT(id) DO UPDATE SET profile = excluded.profile, updated_at = excluded.updated_at",
+                    self.table_prefix
+                ))
+                .bind(id)
+                .bind(&profile_json)
+                .bind(Utc::now().to_rfc3339())
+                .execute(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 读取用户档案，不存在时返回 `None`
+    pub async fn load_user_profile(&self, id: &str) -> Result<Option<serde_json::Value>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let profile: Option<String> = sqlx::query_scalar(&format!(
+                    "SELECT profile FROM {}user_profiles WHERE id = ?",
+                    self.table_prefix
+                ))
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                profile
+                    .map(|profile| serde_json::from_str(&profile))
+                    .transpose()
+                    .map_err(MemoryError::Serialization)
+            }
+        }
+    }
+
+    /// 分页获取节点ID（按ID升序，`after` 为上一页最后一个ID）
+    pub async fn list_node_ids(
+        &self,
+        after: Option<&str>,
+        page_size: usize,
+    ) -> Result<Vec<String>> {
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let ids: Vec<String> = sqlx::query_scalar(&format!(
+                    "SELECT id FROM {}graph_nodes WHERE id > ? ORDER BY id LIMIT ?",
+                    self.table_prefix
+                ))
+                .bind(after.unwrap_or(""))
+                .bind(page_size as i64)
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+                Ok(ids)
+            }
+        }
+    }
+
+    /// 获取所有图节点
+    pub async fn get_all_nodes(&self) -> Result<Vec<GraphNode>> {
+        let mut nodes = Vec::new();
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let rows = sqlx::query(&format!(
+                    "SELECT id, node_type, properties, created_at, updated_at FROM {}graph_nodes",
+                    self.table_prefix
+                ))
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                for row in rows {
+                    nodes.push(self.row_to_node(&row)?);
+                }
+            }
+        }
+
+        Ok(nodes)
+    }
+
+    /// 统计图结构：一次遍历边列表计算度数，并查集计算连通分量
+    pub async fn graph_stats(&self) -> Result<GraphStats> {
+        let (node_ids, endpoints) = match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.table_prefix;
+                let node_ids: Vec<String> =
+                    sqlx::query_scalar(&format!("SELECT id FROM {}graph_nodes", prefix))
+                        .fetch_all(pool)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                let endpoints: Vec<(String, String)> = sqlx::query_as(&format!(
+                    "SELECT from_node, to_node FROM {}graph_edges",
+                    prefix
+                ))
+                .fetch_all(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+                (node_ids, endpoints)
+            }
+        };
+
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut ids = Vec::new();
+        let mut index_of = |id: String| {
+            *index.entry(id).or_insert_with_key(|id| {
+                ids.push(id.clone());
+                ids.len() - 1
+            })
+        };
+        for id in node_ids {
+            index_of(id);
+        }
+        let edges: Vec<(usize, usize)> = endpoints
+            .into_iter()
+            .map(|(from, to)| (index_of(from), index_of(to)))
+            .collect();
+
+        let node_count = ids.len();
+        let edge_count = edges.len();
+        let mut degrees = vec![0usize; node_count];
+        let mut parents: Vec<usize> = (0..node_count).collect();
+        let mut connected_components = node_count;
+        for &(from, to) in &edges {
+            degrees[from] += 1;
+            degrees[to] += 1;
+            let (from_root, to_root) = (find_root(&mut parents, from), find_root(&mut parents, to));
+            if from_root != to_root {
+                parents[from_root] = to_root;
+                connected_components -= 1;
+            }
+        }
+
+        let mut top_degree_nodes: Vec<(String, usize)> = ids.into_iter().zip(degrees).collect();
+        let max_degree = top_degree_nodes
+            .iter()
+            .map(|(_, degree)| *degree)
+            .max()
+            .unwrap_or(0);
+        top_degree_nodes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_degree_nodes.truncate(TOP_DEGREE_NODES);
+
+        let (avg_degree, density) = if node_count == 0 {
+            (0.0, 0.0)
+        } else {
+            let n = node_count as f32;
+            let avg_degree = 2.0 * edge_count as f32 / n;
+            let density = if node_count > 1 {
+                2.0 * edge_count as f32 / (n * (n - 1.0))
+            } else {
+                0.0
+            };
+            (avg_degree, density)
+        };
+
+        Ok(GraphStats {
+            node_count,
+            edge_count,
+            avg_degree,
+            max_degree,
+            connected_components,
+            density,
+            top_degree_nodes,
+        })
+    }
+
+    /// 删除所有向量、节点和边
+    pub async fn clear_all(&self) -> Result<()> {
+        let _writing = self.index_writes.read().await;
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.table_prefix;
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                for table in ["graph_edges", "graph_nodes", "vectors"] {
+                    sqlx::query(&format!("DELETE FROM {}{}", prefix, table))
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(MemoryError::Database)?;
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        self.cache.clear().await;
+        if let Some(index) = self.vector_index.write().await.as_mut() {
+            index.clear();
+        }
+        Ok(())
+    }
+
+    /// 在单个事务内批量写入向量、节点和边
+    ///
+    /// `overwrite` 为 true 时覆盖ID冲突的记录，否则保留已有记录。返回实际写入的行数。
+    pub async fn import_records(
+        &self,
+        vectors: &[Vector],
+        nodes: &[GraphNode],
+        edges: &[GraphEdge],
+        overwrite: bool,
+    ) -> Result<u64> {
+        let on_conflict = if overwrite {
+            OnConflict::Update
+        } else {
+            OnConflict::Ignore
+        };
+        let mut rows_affected = 0;
+        let _writing = self.index_writes.read().await;
+
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                // 先写节点，保证边的外键可以引用到
+                let node_sql = self.node_insert_sql(on_conflict);
+                for node in nodes {
+                    rows_affected += self.write_node(&mut *tx, &node_sql, node).await?;
+                }
+
+                let vector_sql = self.vector_insert_sql(on_conflict);
+                for vector in vectors {
+                    rows_affected += self.write_vector(&mut *tx, &vector_sql, vector).await?;
+                }
+
+                let edge_sql = self.edge_insert_sql(on_conflict);
+                for edge in edges {
+                    rows_affected += self.write_edge(&mut *tx, &edge_sql, edge).await?;
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        // 覆盖写入可能使缓存过期；冲突时保留的是哪一行只有数据库知道，索引直接重建
+        self.cache.clear().await;
+        if self.index_enabled() {
+            self.rebuild_index().await?;
+        }
+
+        Ok(rows_affected)
+    }
+
+    /// 在单个事务中写入一条记忆的向量、节点与关联边
+    ///
+    /// 任一写入失败时整体回滚，不会留下只有向量或只有节点的半成品；缓存仅在提交成功后更新。
+    pub async fn create_memory_atomic(
+        &self,
+        vector: &Vector,
+        node: &GraphNode,
+        edges: &[GraphEdge],
+    ) -> Result<()> {
+        let vector = &*self.prepare_vector(vector);
+        let _writing = self.index_writes.read().await;
+        match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let mut tx = pool.begin().await.map_err(MemoryError::Database)?;
+
+                let vector_sql = self.vector_insert_sql(OnConflict::Fail);
+                self.write_vector(&mut *tx, &vector_sql, vector).await?;
+
+                let node_sql = self.node_insert_sql(OnConflict::Fail);
+                self.write_node(&mut *tx, &node_sql, node).await?;
+
+                let edge_sql = self.edge_insert_sql(OnConflict::Fail);
+                for edge in edges {
+                    self.write_edge(&mut *tx, &edge_sql, edge).await?;
+                }
+
+                tx.commit().await.map_err(MemoryError::Database)?;
+            }
+        }
+
+        self.cache
+            .put_vector(vector.id.clone(), vector.clone())
+            .await;
+        self.cache.invalidate_queries().await;
+        self.index_vectors([vector]).await;
+        self.cache.put_node(node.id.clone(), node.clone()).await;
+        for edge in edges {
+            self.cache.put_edge(edge.id.clone(), edge.clone()).await;
+        }
+
+        Ok(())
+    }
+
+    /// 清除缓存
+    pub async fn clear_cache(&self) {
+        self.cache.clear().await;
+    }
+
+    /// 获取内存缓存的命中统计
+    pub fn cache_metrics(&self) -> CacheMetrics {
+        self.cache.cache_metrics()
+    }
+
+    /// 获取统计信息：未删除的向量数、节点数、边数及缓存条目数
+    pub async fn get_stats(&self) -> Result<(u64, u64, u64, (usize, usize, usize, usize))> {
+        let (vector_count, node_count, edge_count) = match &self.pool {
+            DatabasePool::Sqlite(pool) => {
+                let prefix = &self.table_prefix;
+
+                // 软删除的记忆仍保留在表中，不计入总数
+                let vector_count: i64 = sqlx::query_scalar(&format!(
+                    "SELECT COUNT(*) FROM {}vectors WHERE {}",
+                    prefix, NOT_DELETED_CONDITION
+                ))
+                .fetch_one(pool)
+                .await
+                .map_err(MemoryError::Database)?;
+
+                let node_count: i64 =
+                    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}graph_nodes", prefix))
+                        .fetch_one(pool)
+                        .await
+                        .map_err(MemoryError::Database)?;
+
+                let edge_count: i64 =
+                    sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}graph_edges", prefix))
+                        .fetch_one(pool)
+                        .await
+                        .map_err(MemoryError::Database)?;
+
+                (vector_count as u64, node_count as u64, edge_count as u64)
+            }
+        };
+
+        let cache_stats = self.cache.get_stats().await;
+
+        Ok((vector_count, node_count, edge_count, cache_stats))
+    }
+
+    // 辅助函数
+
+    /// 构建向量插入语句
+    fn vector_insert_sql(&self, on_conflict: OnConflict) -> String {
+        format!(
+            "INSERT INTO {}vectors (id, embedding, metadata, created_at, updated_at) VALUES (?, ?, ?, ?, ?){}",
+            self.table_prefix,
+            on_conflict.clause("embedding = excluded.embedding, metadata = excluded.metadata, updated_at = excluded.updated_at")
+        )
+    }
+
+    /// 构建节点插入语句
+    fn node_insert_sql(&self, on_conflict: OnConflict) -> String {
+        format!(
+            "INSERT INTO {}graph_nodes (id, node_type, properties, created_at, updated_at) VALUES (?, ?, ?, ?, ?){}",
+            self.table_prefix,
+            on_conflict.clause("node_type = excluded.node_type, properties = excluded.properties, updated_at = excluded.updated_at")
+        )
+    }
+
+    /// 构建边插入语句
+    fn edge_insert_sql(&self, on_conflict: OnConflict) -> String {
+        format!(
+            "INSERT INTO {}graph_edges (id, from_node, to_node, edge_type, weight, properties, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?){}",
+            self.table_prefix,
+            on_conflict.clause("from_node = excluded.from_node, to_node = excluded.to_node, edge_type = excluded.edge_type, weight = excluded.weight, properties = excluded.properties, updated_at = excluded.updated_at")
+        )
+    }
+
+    /// 执行向量写入语句
+    async fn write_vector<'e, E>(&self, executor: E, sql: &str, vector: &Vector) -> Result<u64>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
+        let embedding_bytes = self.vector_to_bytes(&vector.embedding)?;
+        let metadata_json =
+            serde_json::to_string(&vector.metadata).map_err(MemoryError::Serialization)?;
+
+        let result = sqlx::query(sql)
+            .bind(&vector.id)
+            .bind(&embedding_bytes)
+            .bind(&metadata_json)
+            .bind(vector.created_at.to_rfc3339())
+            .bind(vector.updated_at.to_rfc3339())
+            .execute(executor)
+            .await
+            .map_err(MemoryError::Database)?;
+        Ok(result.rows_affected())
+    }
+
+    /// 执行节点写入语句
+    async fn write_node<'e, E>(&self, executor: E, sql: &str, node: &GraphNode) -> Result<u64>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
+        let properties_json =
+            serde_json::to_string(&node.properties).map_err(MemoryError::Serialization)?;
+
+        let result = sqlx::query(sql)
+            .bind(&node.id)
+            .bind(&node.node_type)
+            .bind(&properties_json)
+            .bind(node.created_at.to_rfc3339())
+            .bind(node.updated_at.to_rfc3339())
+            .execute(executor)
+            .await
+            .map_err(MemoryError::Database)?;
+        Ok(result.rows_affected())
+    }
+
+    /// 执行边写入语句
+    async fn write_edge<'e, E>(&self, executor: E, sql: &str, edge: &GraphEdge) -> Result<u64>
+    where
+        E: sqlx::Executor<'e, Database = Sqlite>,
+    {
+        let properties_json =
+            serde_json::to_string(&edge.properties).map_err(MemoryError::Serialization)?;
+
+        let result = sqlx::query(sql)
+            .bind(&edge.id)
+            .bind(&edge.from_node)
+            .bind(&edge.to_node)
+            .bind(&edge.edge_type)
+            .bind(edge.weight)
+            .bind(&properties_json)
+            .bind(edge.created_at.to_rfc3339())
+            .bind(edge.updated_at.to_rfc3339())
+            .execute(executor)
+            .await
+            .map_err(MemoryError::Database)?;
+        Ok(result.rows_affected())
+    }
+
+    /// 将查询行转换为向量
+    fn row_to_vector(&self, row: &SqliteRow) -> Result<Vector> {
+        let embedding_bytes: Vec<u8> = row.get("embedding");
+        let metadata_str: String = row.get("metadata");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+
+        let embedding = self.bytes_to_vector(&embedding_bytes)?;
+        let metadata: HashMap<String, serde_json::Value> =
+            serde_json::from_str(&metadata_str).map_err(MemoryError::Serialization)?;
+        let created_at = DateTime::parse_from_rfc3339(&created_at_str)
+            .map_err(|e| MemoryError::Internal {
+                message: format!("DateTime parse failed: {}", e),
+            })?
+            .with_timezone(&Utc);
+        let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
+            .map_err(|e| MemoryError::Internal {
+                message: format!("DateTime parse failed: {}", e),
+            })?
+            .with_timezone(&Utc);
+
+        Ok(Vector {
+            id: row.get("id"),
+            embedding,
+            metadata,
+            created_at,
+            updated_at,
+        })
+    }
+
+    /// 将查询行转换为图节点
+    fn row_to_node(&self, row: &SqliteRow) -> Result<GraphNode> {
+        let properties_str: String = row.get("properties");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+
+        let properties: HashMap<String, serde_json::Value> =
+            serde_json::from_str(&properties_str).map_err(MemoryError::Serialization)?;
+        let created_at = DateTime::parse_from_rfc3339(&created_at_str)
+            .map_err(|e| MemoryError::Internal {
+                message: format!("DateTime parse failed: {}", e),
+            })?
+            .with_timezone(&Utc);
+        let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
+            .map_err(|e| MemoryError::Internal {
+                message: format!("DateTime parse failed: {}", e),
+            })?
+            .with_timezone(&Utc);
+
+        Ok(GraphNode {
+            id: row.get("id"),
+            node_type: row.get("node_type"),
+            properties,
+            created_at,
+            updated_at,
+        })
+    }
+
+    /// 将查询行转换为图边
+    fn row_to_edge(&self, row: &SqliteRow) -> Result<GraphEdge> {
+        let properties_str: String = row.get("properties");
+        let created_at_str: String = row.get("created_at");
+        let updated_at_str: String = row.get("updated_at");
+
+        let properties: HashMap<String, serde_json::Value> =
+            serde_json::from_str(&properties_str).map_err(MemoryError::Serialization)?;
+        let created_at = DateTime::parse_from_rfc3339(&created_at_str)
+            .map_err(|e| MemoryError::Internal {
+                message: format!("DateTime parse failed: {}", e),
+            })?
+            .with_timezone(&Utc);
+        let updated_at = DateTime::parse_from_rfc3339(&updated_at_str)
+            .map_err(|e| MemoryError::Internal {
+                message: format!("DateTime parse failed: {}", e),
+            })?
+            .with_timezone(&Utc);
+
+        Ok(GraphEdge {
+            id: row.get("id"),
+            from_node: row.get("from_node"),
+            to_node: row.get("to_node"),
+            edge_type: row.get("edge_type"),
+            weight: row.get("weight"),
+            properties,
+            created_at,
+            updated_at,
+        })
+    }
+
+    fn row_to_interaction(&self, row: &SqliteRow) -> Result<InteractionRecord> {
+        let interaction_type: String = row.get("interaction_type");
+        let timestamp: String = row.get("timestamp");
+        let dwell_time: Option<i64> = row.get("dwell_time");
+
+        Ok(InteractionRecord {
+            memory_id: row.get("memory_id"),
+            interaction_type: serde_json::from_value(serde_json::Value::String(interaction_type))
+                .map_err(MemoryError::Serialization)?,
+            timestamp: DateTime::parse_from_rfc3339(&timestamp)
+                .map_err(|e| MemoryError::Internal {
+                    message: format!("DateTime parse failed: {}", e),
+                })?
+                .with_timezone(&Utc),
+            feedback_score: row.get("feedback_score"),
+            dwell_time: dwell_time.map(|ms| ms as u64),
+        })
+    }
+
+    /// 计算余弦相似度，维度不一致时返回 `InvalidVectorDimension`
+    pub fn cosine_similarity(&self, a: &[f32], b: &[f32]) -> Result<f32> {
+        if a.len() != b.len() {
+            return Err(MemoryError::invalid_vector_dimension(a.len(), b.len()));
+        }
+
+        let (dot_product, norm_a_sq, norm_b_sq) = VectorUtils::dot_and_norms(a, b);
+        Ok(VectorUtils::cosine_from_parts(
+            dot_product,
+            norm_a_sq,
+            norm_b_sq,
+        ))
+    }
+
+    /// 按配置预处理待写入的向量：开启 `normalize_on_insert` 时归一化为单位向量
+    fn prepare_vector<'a>(&self, vector: &'a Vector) -> Cow<'a, Vector> {
+        if !self.config.vector.normalize_on_insert {
+            return Cow::Borrowed(vector);
+        }
+        let mut normalized = vector.clone();
+        // 零向量无法归一化，保持原样
+        let _ = VectorUtils::normalize(&mut normalized.embedding);
+        Cow::Owned(normalized)
+    }
+
+    /// 向量转字节
+    fn vector_to_bytes(&self, vector: &[f32]) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(vector.len() * 4);
+        for &value in vector {
             bytes.extend_from_slice(&value.to_le_bytes());
         }
         Ok(bytes)
@@ -877,7 +3094,7 @@ impl VectorGraphDB {
 
     /// 字节转向量
     fn bytes_to_vector(&self, bytes: &[u8]) -> Result<Vec<f32>> {
-        if bytes.len() % 4 != 0 {
+        if !bytes.len().is_multiple_of(4) {
             return Err(MemoryError::InvalidVectorDimension {
                 expected: bytes.len() - (bytes.len() % 4),
                 actual: bytes.len(),
@@ -895,7 +3112,6 @@ impl VectorGraphDB {
 
     /// 检查过滤器匹配
     fn matches_filters(
-        &self,
         metadata: &HashMap<String, serde_json::Value>,
         filters: &HashMap<String, serde_json::Value>,
     ) -> bool {
@@ -908,8 +3124,285 @@ impl VectorGraphDB {
                 return false;
             }
         }
-        true
+        true
+    }
+}
+
+/// 数据库只读快照，由 [`VectorGraphDB::read_snapshot`] 创建
+///
+/// 解引用为 [`VectorGraphDB`]，丢弃时结束读事务并关闭快照连接。
+#[derive(Debug)]
+pub struct ReadSnapshot {
+    db: VectorGraphDB,
+}
+
+impl std::ops::Deref for ReadSnapshot {
+    type Target = VectorGraphDB;
+
+    fn deref(&self) -> &Self::Target {
+        &self.db
+    }
+}
+
+/// 加权遍历的待展开路径，按累计代价排序
+#[derive(Debug)]
+struct WeightedFrontier {
+    cost: f32,
+    path: Vec<String>,
+}
+
+impl Ord for WeightedFrontier {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.cost
+            .total_cmp(&other.cost)
+            .then_with(|| self.path.len().cmp(&other.path.len()))
+            .then_with(|| self.path.cmp(&other.path))
+    }
+}
+
+impl PartialOrd for WeightedFrontier {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for WeightedFrontier {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for WeightedFrontier {}
+
+/// 启用并行扫描的最小候选数量，候选较少时并行开销得不偿失
+#[cfg(feature = "parallel")]
+const PARALLEL_SCAN_MIN_CANDIDATES: usize = 2048;
+
+/// 向量查询评分器，查询向量的预处理（余弦度量下的归一化）只做一次
+#[derive(Debug)]
+struct QueryScorer {
+    metric: DistanceMetric,
+    query: Vec<f32>,
+    /// 候选向量在写入时已归一化，余弦相似度只需点积
+    prenormalized: bool,
+}
+
+impl QueryScorer {
+    fn new(metric: DistanceMetric, query: &[f32], prenormalized: bool) -> Self {
+        let mut query = query.to_vec();
+        if metric == DistanceMetric::Cosine {
+            let norm = query.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                query.iter_mut().for_each(|x| *x /= norm);
+            }
+        }
+        Self {
+            metric,
+            query,
+            prenormalized,
+        }
+    }
+
+    /// 计算 (相似度, 距离)，相似度越大越相关
+    ///
+    /// - `Cosine`: 相似度为余弦值，取值 [-1, 1]；距离为 `1 - 相似度`
+    /// - `DotProduct`: 相似度为点积（向量未归一化时无界）；距离为点积的相反数
+    /// - `Euclidean` / `Manhattan`: 距离为 L2 / L1 距离；相似度为 `1 / (1 + 距离)`，取值 (0, 1]
+    ///
+    /// 查询阈值始终与相似度比较。维度不一致时相似度为 0、距离为无穷大。
+    fn score(&self, candidate: &[f32]) -> (f32, f32) {
+        if self.query.len() != candidate.len() {
+            return (0.0, f32::INFINITY);
+        }
+
+        let query = &self.query;
+        match self.metric {
+            DistanceMetric::Cosine => {
+                let similarity = if self.prenormalized {
+                    VectorUtils::dot(query, candidate)
+                } else {
+                    // 查询向量已是单位向量，只需候选向量的范数
+                    let (dot, _, norm_sq) = VectorUtils::dot_and_norms(query, candidate);
+                    if norm_sq == 0.0 {
+                        0.0
+                    } else {
+                        dot / norm_sq.sqrt()
+                    }
+                };
+                (similarity, 1.0 - similarity)
+            }
+            DistanceMetric::DotProduct => {
+                let dot = VectorUtils::dot(query, candidate);
+                (dot, -dot)
+            }
+            DistanceMetric::Euclidean => {
+                let distance = query
+                    .iter()
+                    .zip(candidate)
+                    .map(|(x, y)| (x - y).powi(2))
+                    .sum::<f32>()
+                    .sqrt();
+                (1.0 / (1.0 + distance), distance)
+            }
+            DistanceMetric::Manhattan => {
+                let distance: f32 = query
+                    .iter()
+                    .zip(candidate)
+                    .map(|(x, y)| (x - y).abs())
+                    .sum();
+                (1.0 / (1.0 + distance), distance)
+            }
+        }
+    }
+
+    /// 评分并应用阈值与元数据过滤
+    fn evaluate(
+        &self,
+        vector: Vector,
+        threshold: f32,
+        filters: Option<&HashMap<String, serde_json::Value>>,
+    ) -> Option<ScoredVector> {
+        let (similarity, distance) = self.score(&vector.embedding);
+        if similarity < threshold {
+            return None;
+        }
+        if let Some(filters) = filters {
+            if !VectorGraphDB::matches_filters(&vector.metadata, filters) {
+                return None;
+            }
+        }
+        Some(ScoredVector {
+            similarity,
+            distance,
+            vector,
+        })
+    }
+}
+
+/// 已评分的候选向量，按相似度（相同时按ID升序）排序
+#[derive(Debug)]
+struct ScoredVector {
+    similarity: f32,
+    distance: f32,
+    vector: Vector,
+}
+
+impl Ord for ScoredVector {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.similarity
+            .total_cmp(&other.similarity)
+            .then_with(|| other.vector.id.cmp(&self.vector.id))
+    }
+}
+
+impl PartialOrd for ScoredVector {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for ScoredVector {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for ScoredVector {}
+
+/// 有界 top-k 堆（小顶堆，堆顶为当前最差的候选）
+#[derive(Debug)]
+struct TopK {
+    limit: usize,
+    heap: BinaryHeap<Reverse<ScoredVector>>,
+}
+
+impl TopK {
+    fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::with_capacity(limit.saturating_add(1).min(1024)),
+        }
+    }
+
+    fn push(&mut self, candidate: ScoredVector) {
+        if self.heap.len() < self.limit {
+            self.heap.push(Reverse(candidate));
+        } else if let Some(Reverse(worst)) = self.heap.peek() {
+            if candidate > *worst {
+                self.heap.pop();
+                self.heap.push(Reverse(candidate));
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn merge(mut self, other: TopK) -> TopK {
+        for Reverse(candidate) in other.heap {
+            self.push(candidate);
+        }
+        self
+    }
+
+    /// 按相似度降序输出
+    fn into_sorted_vec(self) -> Vec<ScoredVector> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(candidate)| candidate)
+            .collect()
+    }
+}
+
+/// 顺序扫描候选向量，返回相似度最高的 `limit` 个
+fn scan_sequential(
+    vectors: Vec<Vector>,
+    scorer: &QueryScorer,
+    threshold: f32,
+    filters: Option<&HashMap<String, serde_json::Value>>,
+    limit: usize,
+) -> Vec<ScoredVector> {
+    let mut top = TopK::new(limit);
+    for vector in vectors {
+        if let Some(candidate) = scorer.evaluate(vector, threshold, filters) {
+            top.push(candidate);
+        }
+    }
+    top.into_sorted_vec()
+}
+
+/// 在 rayon 线程池上并行扫描候选向量，各线程维护自己的 top-k 堆后再合并
+#[cfg(feature = "parallel")]
+fn scan_parallel(
+    vectors: Vec<Vector>,
+    scorer: &QueryScorer,
+    threshold: f32,
+    filters: Option<&HashMap<String, serde_json::Value>>,
+    limit: usize,
+) -> Vec<ScoredVector> {
+    use rayon::prelude::*;
+
+    vectors
+        .into_par_iter()
+        .fold(
+            || TopK::new(limit),
+            |mut top, vector| {
+                if let Some(candidate) = scorer.evaluate(vector, threshold, filters) {
+                    top.push(candidate);
+                }
+                top
+            },
+        )
+        .reduce(|| TopK::new(limit), TopK::merge)
+        .into_sorted_vec()
+}
+
+/// 并查集查找根节点（路径减半）
+fn find_root(parents: &mut [usize], mut node: usize) -> usize {
+    while parents[node] != node {
+        parents[node] = parents[parents[node]];
+        node = parents[node];
     }
+    node
 }
 
 #[cfg(test)]
@@ -918,8 +3411,13 @@ mod tests {
     use tempfile::NamedTempFile;
 
     async fn create_test_db() -> VectorGraphDB {
-        let temp_file = NamedTempFile::new().unwrap();
-        let db_url = format!("sqlite://{}", temp_file.path().display());
+        // 连接池比本函数活得久，临时文件不能在返回时被删除
+        let db_path = NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let db_url = format!("sqlite://{}", db_path.display());
 
         let config = Config {
             database: crate::config::DatabaseConfig {
@@ -953,6 +3451,7 @@ mod tests {
             limit: Some(10),
             threshold: Some(0.5),
             filters: None,
+            tags: TagFilter::default(),
         };
 
         let results = db.query_vectors(&query_request).await.unwrap();
@@ -960,6 +3459,228 @@ mod tests {
         assert_eq!(results[0].vector.id, "test_vector");
     }
 
+    #[tokio::test]
+    async fn test_tag_filter_uses_term_index() {
+        let db = create_test_db().await;
+
+        // 每 100 条带一个 rare 标签；labels 与 tags 内容相同但只能走 JSON 扫描
+        let vectors: Vec<Vector> = (0..3000)
+            .map(|i| {
+                let tags = if i % 100 == 0 {
+                    vec!["common", "rare"]
+                } else {
+                    vec!["common"]
+                };
+                let mut metadata = HashMap::new();
+                metadata.insert("tags".to_string(), serde_json::json!(tags));
+                metadata.insert("labels".to_string(), serde_json::json!(tags));
+                metadata.insert(
+                    "keywords".to_string(),
+                    serde_json::json!([format!("k{}", i)]),
+                );
+                Vector {
+                    id: format!("v{:04}", i),
+                    embedding: vec![0.1, 0.2, 0.3, 0.4],
+                    metadata,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                }
+            })
+            .collect();
+        db.import_records(&vectors, &[], &[], false).await.unwrap();
+
+        let indexed = [MetadataCondition::Contains(
+            "tags".to_string(),
+            "rare".to_string(),
+        )];
+        let scanned = [MetadataCondition::Contains(
+            "labels".to_string(),
+            "rare".to_string(),
+        )];
+        let ids = |found: Vec<Vector>| {
+            let mut ids: Vec<String> = found.into_iter().map(|v| v.id).collect();
+            ids.sort();
+            ids
+        };
+        let expected: Vec<String> = (0..3000)
+            .step_by(100)
+            .map(|i| format!("v{:04}", i))
+            .collect();
+        assert_eq!(
+            ids(db.scan_vectors(&indexed, None, 100, 0).await.unwrap()),
+            expected
+        );
+        assert_eq!(
+            ids(db.scan_vectors(&scanned, None, 100, 0).await.unwrap()),
+            expected
+        );
+        let keyword = [MetadataCondition::Contains(
+            "keywords".to_string(),
+            "k42".to_string(),
+        )];
+        assert_eq!(
+            ids(db.scan_vectors(&keyword, None, 10, 0).await.unwrap()),
+            vec!["v0042"]
+        );
+
+        // 查询计划使用标签索引
+        let DatabasePool::Sqlite(pool) = &db.pool;
+        let (sql, args) = db.scan_query(&indexed, None);
+        let plan: Vec<String> =
+            bind_scan_args(sqlx::query(&format!("EXPLAIN QUERY PLAN {}", sql)), args)
+                .bind(100i64)
+                .bind(0i64)
+                .fetch_all(pool)
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| row.get::<String, _>("detail"))
+                .collect();
+        assert!(
+            plan.iter()
+                .any(|detail| detail.contains(&format!("idx_{}memory_tags_tag", db.table_prefix))),
+            "{:?}",
+            plan
+        );
+
+        // 向量查询的标签条件同样连接标签表，而不是逐行解析元数据
+        let query = |tags: TagFilter| VectorQueryRequest {
+            query_vector: vec![0.1, 0.2, 0.3, 0.4],
+            limit: Some(100),
+            threshold: Some(-1.0),
+            filters: None,
+            tags,
+        };
+        let filter = TagFilter {
+            all_of: vec!["common".to_string(), "rare".to_string()],
+            ..Default::default()
+        };
+        let found: Vec<String> = db
+            .query_vectors(&query(filter.clone()))
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|result| result.vector.id)
+            .collect();
+        assert_eq!(
+            ids(db.get_vectors_by_tags(&filter).await.unwrap()),
+            expected
+        );
+        assert_eq!(found.len(), expected.len());
+        let excluded = TagFilter {
+            any_of: vec!["common".to_string()],
+            none_of: vec!["rare".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(db.get_vectors_by_tags(&excluded).await.unwrap().len(), 2970);
+
+        let (sql, args) = db.tag_filter_query(&filter);
+        let plan: Vec<String> =
+            bind_scan_args(sqlx::query(&format!("EXPLAIN QUERY PLAN {}", sql)), args)
+                .fetch_all(pool)
+                .await
+                .unwrap()
+                .iter()
+                .map(|row| row.get::<String, _>("detail"))
+                .collect();
+        assert!(
+            plan.iter()
+                .any(|detail| detail.contains(&format!("idx_{}memory_tags_tag", db.table_prefix))),
+            "{:?}",
+            plan
+        );
+        assert!(
+            !plan.iter().any(|detail| detail.contains("json_each")),
+            "{:?}",
+            plan
+        );
+
+        // 更新元数据与清空时同步
+        let mut updated = vectors[0].clone();
+        updated
+            .metadata
+            .insert("tags".to_string(), serde_json::json!(["common"]));
+        db.update_vector(&updated).await.unwrap();
+        let mut updated = vectors[1].clone();
+        updated
+            .metadata
+            .insert("tags".to_string(), serde_json::json!(["rare"]));
+        db.upsert_vector(&updated).await.unwrap();
+        let found = ids(db.scan_vectors(&indexed, None, 100, 0).await.unwrap());
+        assert_eq!(found.len(), 30);
+        assert!(found.contains(&"v0001".to_string()) && !found.contains(&"v0000".to_string()));
+
+        db.clear_all().await.unwrap();
+        let remaining: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {}memory_tags",
+            db.table_prefix
+        ))
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        assert_eq!(remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_isolate_vectors_and_nodes() {
+        let db = create_test_db().await;
+        let tenant_a = db.with_namespace("tenant_a").await.unwrap();
+        let tenant_b = db.with_namespace("tenant_b").await.unwrap();
+        assert_eq!(tenant_a.namespace(), Some("tenant_a"));
+        assert!(db.with_namespace("bad-name; DROP").await.is_err());
+
+        for (store, tenant) in [(&tenant_a, "tenant_a"), (&tenant_b, "tenant_b")] {
+            let mut metadata = HashMap::new();
+            metadata.insert("tenant".to_string(), serde_json::json!(tenant));
+            // 相同ID在不同命名空间中互不冲突
+            for id in ["shared", tenant] {
+                store
+                    .insert_vector(&Vector {
+                        id: id.to_string(),
+                        embedding: vec![0.1, 0.2, 0.3, 0.4],
+                        metadata: metadata.clone(),
+                        created_at: Utc::now(),
+                        updated_at: Utc::now(),
+                    })
+                    .await
+                    .unwrap();
+            }
+            store
+                .insert_node(&GraphNode {
+                    id: "shared".to_string(),
+                    node_type: tenant.to_string(),
+                    properties: HashMap::new(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let request = VectorQueryRequest {
+            query_vector: vec![0.1, 0.2, 0.3, 0.4],
+            limit: Some(10),
+            threshold: Some(0.0),
+            filters: None,
+            tags: TagFilter::default(),
+        };
+        let results = tenant_a.query_vectors(&request).await.unwrap();
+        let mut ids: Vec<&str> = results.iter().map(|r| r.vector.id.as_str()).collect();
+        ids.sort_unstable();
+        assert_eq!(ids, vec!["shared", "tenant_a"]);
+        assert!(results
+            .iter()
+            .all(|r| r.vector.metadata["tenant"] == "tenant_a"));
+
+        let nodes = tenant_b.get_all_nodes().await.unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].node_type, "tenant_b");
+
+        // 未设置命名空间的实例看不到任何命名空间的数据
+        assert!(db.query_vectors(&request).await.unwrap().is_empty());
+        assert!(db.get_all_nodes().await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_graph_operations() {
         let db = create_test_db().await;
@@ -1007,11 +3728,473 @@ mod tests {
             max_depth: Some(2),
             limit: Some(10),
             filters: None,
+            traversal: TraversalMode::BreadthFirst,
         };
 
         let results = db.query_graph(&query_request).await.unwrap();
         assert!(!results.nodes.is_empty());
         assert!(!results.edges.is_empty());
+        assert_eq!(results.path_costs.len(), results.paths.len());
+    }
+
+    #[tokio::test]
+    async fn test_get_node_returns_none_for_missing() {
+        let db = create_test_db().await;
+        let node = GraphNode {
+            id: "node1".to_string(),
+            node_type: "test".to_string(),
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        db.insert_node(&node).await.unwrap();
+
+        let hits_before = db.cache_metrics().nodes.hits;
+        let fetched = db.get_node("node1").await.unwrap().unwrap();
+        assert_eq!(fetched.id, "node1");
+        assert_eq!(db.cache_metrics().nodes.hits, hits_before + 1);
+        assert!(db.get_node("missing").await.unwrap().is_none());
+
+        let edges = db.get_edges_for_node("node1", &None).await.unwrap();
+        assert_eq!(edges.map(|edges| edges.len()), Some(0));
+        assert!(db
+            .get_edges_for_node("missing", &None)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_traversal_returns_all_paths_in_diamond() {
+        let db = create_test_db().await;
+
+        for id in ["source", "left", "right", "sink"] {
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "test".to_string(),
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        for (id, from, to) in [
+            ("e1", "source", "left"),
+            ("e2", "source", "right"),
+            ("e3", "left", "sink"),
+            ("e4", "right", "sink"),
+        ] {
+            db.insert_edge(&GraphEdge {
+                id: id.to_string(),
+                from_node: from.to_string(),
+                to_node: to.to_string(),
+                edge_type: "related".to_string(),
+                weight: 1.0,
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let result = db
+            .query_graph(&GraphQueryRequest {
+                start_nodes: vec!["source".to_string()],
+                edge_types: None,
+                max_depth: Some(3),
+                limit: None,
+                filters: None,
+                traversal: TraversalMode::BreadthFirst,
+            })
+            .await
+            .unwrap();
+
+        let mut sink_paths: Vec<_> = result
+            .paths
+            .iter()
+            .filter(|path| path.last().map(String::as_str) == Some("sink"))
+            .cloned()
+            .collect();
+        sink_paths.sort();
+        assert_eq!(
+            sink_paths,
+            vec![
+                vec!["source", "left", "sink"],
+                vec!["source", "right", "sink"],
+            ]
+        );
+        // 环路不会出现在同一条路径中
+        for path in &result.paths {
+            let unique: std::collections::HashSet<_> = path.iter().collect();
+            assert_eq!(unique.len(), path.len());
+        }
+        assert_eq!(result.nodes.len(), 4);
+        assert_eq!(result.edges.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_graph_stats_on_small_graph() {
+        let db = create_test_db().await;
+
+        // 星形 hub-a/b/c、链 x-y，以及孤立节点 lonely
+        for id in ["hub", "a", "b", "c", "x", "y", "lonely"] {
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "test".to_string(),
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+        for (id, from, to) in [
+            ("e1", "hub", "a"),
+            ("e2", "hub", "b"),
+            ("e3", "c", "hub"),
+            ("e4", "x", "y"),
+        ] {
+            db.insert_edge(&GraphEdge {
+                id: id.to_string(),
+                from_node: from.to_string(),
+                to_node: to.to_string(),
+                edge_type: "related".to_string(),
+                weight: 1.0,
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let stats = db.graph_stats().await.unwrap();
+        assert_eq!(stats.node_count, 7);
+        assert_eq!(stats.edge_count, 4);
+        assert_eq!(stats.connected_components, 3);
+        assert_eq!(stats.max_degree, 3);
+        assert_eq!(stats.top_degree_nodes[0], ("hub".to_string(), 3));
+        assert!((stats.avg_degree - 8.0 / 7.0).abs() < 1e-6);
+        assert!((stats.density - 8.0 / 42.0).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_directed_edges_are_followed_one_way() {
+        let db = create_test_db().await;
+
+        for id in ["cause", "effect", "peer"] {
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "test".to_string(),
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        for (id, from, to, edge_type, bidirectional) in [
+            ("e1", "cause", "effect", "Causal", false),
+            ("e2", "peer", "effect", "Semantic", true),
+        ] {
+            let mut properties = HashMap::new();
+            properties.insert(
+                BIDIRECTIONAL_PROPERTY.to_string(),
+                serde_json::Value::Bool(bidirectional),
+            );
+            db.insert_edge(&GraphEdge {
+                id: id.to_string(),
+                from_node: from.to_string(),
+                to_node: to.to_string(),
+                edge_type: edge_type.to_string(),
+                weight: 1.0,
+                properties,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let reached = |start: &str, traversal: TraversalMode| {
+            let request = GraphQueryRequest {
+                start_nodes: vec![start.to_string()],
+                edge_types: None,
+                max_depth: Some(3),
+                limit: None,
+                filters: None,
+                traversal,
+            };
+            let db = &db;
+            async move {
+                let result = db.query_graph(&request).await.unwrap();
+                let mut ids: Vec<String> = result.nodes.into_iter().map(|n| n.id).collect();
+                ids.sort();
+                ids
+            }
+        };
+
+        // 有向因果边只能从原因走向结果，双向边两端都可到达
+        for traversal in [TraversalMode::BreadthFirst, TraversalMode::Weighted] {
+            assert_eq!(reached("effect", traversal).await, vec!["effect", "peer"]);
+            assert_eq!(
+                reached("cause", traversal).await,
+                vec!["cause", "effect", "peer"]
+            );
+        }
+
+        // 不考虑方向时仍能取到所有相连的边
+        assert_eq!(
+            db.get_incident_edges("effect", &None).await.unwrap().len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_traversal_budget_truncates_star_graph() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.graph.traversal.max_nodes = 5;
+        config.graph.traversal.max_edges = 8;
+        let db = VectorGraphDB::new(config).await.unwrap();
+
+        let leaves: Vec<String> = (0..20).map(|i| format!("leaf{:02}", i)).collect();
+        for id in std::iter::once("hub").chain(leaves.iter().map(String::as_str)) {
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "test".to_string(),
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+        for leaf in &leaves {
+            db.insert_edge(&GraphEdge {
+                id: format!("hub-{}", leaf),
+                from_node: "hub".to_string(),
+                to_node: leaf.clone(),
+                edge_type: "related".to_string(),
+                weight: 1.0,
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        for traversal in [TraversalMode::BreadthFirst, TraversalMode::Weighted] {
+            let result = db
+                .query_graph(&GraphQueryRequest {
+                    start_nodes: vec!["hub".to_string()],
+                    edge_types: None,
+                    max_depth: Some(3),
+                    limit: None,
+                    filters: None,
+                    traversal,
+                })
+                .await
+                .unwrap();
+            assert!(result.truncated);
+            assert!(result.nodes.len() <= 5);
+            assert_eq!(result.edges.len(), 8);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_weighted_traversal_prefers_strong_path() {
+        let db = create_test_db().await;
+
+        for id in ["a", "b", "c", "d"] {
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "test".to_string(),
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        // 一条短而弱的路径 a-d，一条长而强的路径 a-b-c-d
+        for (id, from, to, weight) in [
+            ("weak", "a", "d", 0.1),
+            ("strong1", "a", "b", 0.9),
+            ("strong2", "b", "c", 0.9),
+            ("strong3", "c", "d", 0.9),
+        ] {
+            db.insert_edge(&GraphEdge {
+                id: id.to_string(),
+                from_node: from.to_string(),
+                to_node: to.to_string(),
+                edge_type: "related".to_string(),
+                weight,
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let path_to_d = |result: &GraphQueryResult| {
+            result
+                .paths
+                .iter()
+                .zip(&result.path_costs)
+                .find(|(path, _)| path.last().map(String::as_str) == Some("d"))
+                .map(|(path, cost)| (path.clone(), *cost))
+                .unwrap()
+        };
+
+        let mut request = GraphQueryRequest {
+            start_nodes: vec!["a".to_string()],
+            edge_types: None,
+            max_depth: Some(3),
+            limit: None,
+            filters: None,
+            traversal: TraversalMode::BreadthFirst,
+        };
+
+        let (path, cost) = path_to_d(&db.query_graph(&request).await.unwrap());
+        assert_eq!(path, vec!["a", "d"]);
+        assert_eq!(cost, 1.0);
+
+        request.traversal = TraversalMode::Weighted;
+        let (path, cost) = path_to_d(&db.query_graph(&request).await.unwrap());
+        assert_eq!(path, vec!["a", "b", "c", "d"]);
+        assert!((cost - 3.0 / 0.9).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_create_memory_atomic_rolls_back_on_node_failure() {
+        let db = create_test_db().await;
+
+        // 预先存在同ID节点，使节点写入失败
+        let node = GraphNode {
+            id: "memory1".to_string(),
+            node_type: "memory".to_string(),
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        db.insert_node(&node).await.unwrap();
+
+        let vector = Vector {
+            id: "memory1".to_string(),
+            embedding: vec![1.0, 0.0],
+            metadata: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+
+        assert!(db.create_memory_atomic(&vector, &node, &[]).await.is_err());
+        assert!(db.get_vector("memory1").await.is_err());
+        assert!(db.get_all_vectors().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_snapshot_ignores_concurrent_writes() {
+        // 快照需要按路径重新连接，临时文件要保留到测试结束
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let db = Arc::new(VectorGraphDB::new(config).await.unwrap());
+
+        let vector = |id: &str, embedding: Vec<f32>| Vector {
+            id: id.to_string(),
+            embedding,
+            metadata: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let node = |id: &str| GraphNode {
+            id: id.to_string(),
+            node_type: "memory".to_string(),
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let edge = |id: &str, from: &str, to: &str| GraphEdge {
+            id: id.to_string(),
+            from_node: from.to_string(),
+            to_node: to.to_string(),
+            edge_type: "related".to_string(),
+            weight: 1.0,
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        for id in ["a", "b"] {
+            db.insert_vector(&vector(id, vec![1.0, 0.0])).await.unwrap();
+            db.insert_node(&node(id)).await.unwrap();
+        }
+        db.insert_edge(&edge("ab", "a", "b")).await.unwrap();
+
+        let vector_request = VectorQueryRequest {
+            query_vector: vec![1.0, 0.0],
+            limit: Some(10),
+            threshold: Some(0.5),
+            filters: None,
+            tags: TagFilter::default(),
+        };
+        let graph_request = GraphQueryRequest {
+            start_nodes: vec!["a".to_string()],
+            edge_types: None,
+            max_depth: Some(3),
+            limit: None,
+            filters: None,
+            traversal: TraversalMode::BreadthFirst,
+        };
+        let ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+
+        let snapshot = db.read_snapshot().await.unwrap();
+        let seeds = snapshot.query_vectors(&vector_request).await.unwrap();
+        assert_eq!(seeds.len(), 2);
+
+        // 多步读取之间插入一次并发写入
+        let writer = tokio::spawn({
+            let db = db.clone();
+            async move {
+                db.insert_vector(&vector("c", vec![1.0, 0.0]))
+                    .await
+                    .unwrap();
+                db.insert_node(&node("c")).await.unwrap();
+                db.insert_edge(&edge("bc", "b", "c")).await.unwrap();
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let graph = snapshot.query_graph(&graph_request).await.unwrap();
+        assert!(graph.nodes.iter().all(|n| n.id != "c"));
+        let edges = snapshot.get_edges_among(&ids).await.unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].id, "ab");
+        let seeds = snapshot.query_vectors(&vector_request).await.unwrap();
+        assert!(seeds.iter().all(|r| r.vector.id != "c"));
+
+        drop(snapshot);
+        writer.await.unwrap();
+        assert_eq!(db.get_edges_among(&ids).await.unwrap().len(), 2);
+        assert_eq!(db.query_vectors(&vector_request).await.unwrap().len(), 3);
     }
 
     #[tokio::test]
@@ -1022,8 +4205,168 @@ mod tests {
         let vec2 = vec![1.0, 0.0, 0.0];
         let vec3 = vec![0.0, 1.0, 0.0];
 
-        assert!((db.cosine_similarity(&vec1, &vec2) - 1.0).abs() < 1e-6);
-        assert!((db.cosine_similarity(&vec1, &vec3) - 0.0).abs() < 1e-6);
+        assert!((db.cosine_similarity(&vec1, &vec2).unwrap() - 1.0).abs() < 1e-6);
+        assert!((db.cosine_similarity(&vec1, &vec3).unwrap() - 0.0).abs() < 1e-6);
+        assert!(matches!(
+            db.cosine_similarity(&vec1, &[1.0, 0.0]),
+            Err(MemoryError::InvalidVectorDimension {
+                expected: 3,
+                actual: 2
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_query_vectors_respects_distance_metric() {
+        let candidates = [
+            ("large", vec![10.0, 5.0]),
+            ("near", vec![1.0, 0.1]),
+            ("aligned", vec![0.5, 0.0]),
+        ];
+        let expectations = [
+            (DistanceMetric::Cosine, ["aligned", "near", "large"]),
+            (DistanceMetric::DotProduct, ["large", "near", "aligned"]),
+            (DistanceMetric::Euclidean, ["near", "aligned", "large"]),
+            (DistanceMetric::Manhattan, ["near", "aligned", "large"]),
+        ];
+
+        for (metric, expected) in expectations {
+            let temp_file = NamedTempFile::new().unwrap();
+            let mut config = Config {
+                database: crate::config::DatabaseConfig {
+                    url: format!("sqlite://{}", temp_file.path().display()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            config.vector.distance_metric = metric.clone();
+            let db = VectorGraphDB::new(config).await.unwrap();
+
+            for (id, embedding) in &candidates {
+                db.insert_vector(&Vector {
+                    id: id.to_string(),
+                    embedding: embedding.clone(),
+                    metadata: HashMap::new(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+            }
+
+            let results = db
+                .query_vectors(&VectorQueryRequest {
+                    query_vector: vec![1.0, 0.0],
+                    limit: Some(10),
+                    threshold: Some(f32::MIN),
+                    filters: None,
+                    tags: TagFilter::default(),
+                })
+                .await
+                .unwrap();
+
+            let order: Vec<&str> = results.iter().map(|r| r.vector.id.as_str()).collect();
+            assert_eq!(order, expected, "unexpected order for {:?}", metric);
+            for pair in results.windows(2) {
+                assert!(pair[0].distance <= pair[1].distance);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_normalize_on_insert_preserves_cosine_ranking() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.vector.normalize_on_insert = true;
+        let db = VectorGraphDB::new(config).await.unwrap();
+
+        for (id, embedding) in [("large", vec![10.0, 5.0]), ("aligned", vec![0.5, 0.0])] {
+            db.insert_vector(&Vector {
+                id: id.to_string(),
+                embedding,
+                metadata: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let stored = db.get_vector("large").await.unwrap();
+        let norm: f32 = stored.embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-6);
+
+        let results = db
+            .query_vectors(&VectorQueryRequest {
+                query_vector: vec![2.0, 0.0],
+                limit: Some(10),
+                threshold: Some(f32::MIN),
+                filters: None,
+                tags: TagFilter::default(),
+            })
+            .await
+            .unwrap();
+        let order: Vec<&str> = results.iter().map(|r| r.vector.id.as_str()).collect();
+        assert_eq!(order, ["aligned", "large"]);
+        assert!((results[0].similarity - 1.0).abs() < 1e-6);
+        assert!((results[1].similarity - 10.0 / 125f32.sqrt()).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_scan_matches_sequential() {
+        // 线性同余生成器，保证数据可复现
+        let mut seed: u64 = 42;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+        };
+
+        let dimension = 64;
+        let vectors: Vec<Vector> = (0..20_000)
+            .map(|i| Vector {
+                id: format!("vector_{:05}", i),
+                embedding: (0..dimension).map(|_| next()).collect(),
+                metadata: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .collect();
+        let query: Vec<f32> = (0..dimension).map(|_| next()).collect();
+
+        for metric in [DistanceMetric::Cosine, DistanceMetric::Euclidean] {
+            let scorer = QueryScorer::new(metric, &query, false);
+
+            let sequential_start = std::time::Instant::now();
+            let sequential = scan_sequential(vectors.clone(), &scorer, f32::MIN, None, 50);
+            let sequential_elapsed = sequential_start.elapsed();
+
+            let parallel_start = std::time::Instant::now();
+            let parallel = scan_parallel(vectors.clone(), &scorer, f32::MIN, None, 50);
+            let parallel_elapsed = parallel_start.elapsed();
+
+            println!(
+                "{:?}: sequential {:?}, parallel {:?}",
+                scorer.metric, sequential_elapsed, parallel_elapsed
+            );
+
+            assert_eq!(sequential.len(), 50);
+            let ids = |results: &[ScoredVector]| {
+                results
+                    .iter()
+                    .map(|c| (c.vector.id.clone(), c.similarity))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(ids(&parallel), ids(&sequential));
+        }
     }
 
     #[tokio::test]
@@ -1036,4 +4379,227 @@ mod tests {
 
         assert_eq!(original_vector, restored_vector);
     }
+
+    #[tokio::test]
+    async fn test_cache_metrics_report_hit_ratios() {
+        let cache = MemoryCache::new(10);
+        let vector = Vector {
+            id: "v1".to_string(),
+            embedding: vec![0.1, 0.2],
+            metadata: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        cache.put_vector("v1".to_string(), vector).await;
+
+        // 向量缓存：3次命中，1次未命中
+        for _ in 0..3 {
+            assert!(cache.get_vector("v1").await.is_some());
+        }
+        assert!(cache.get_vector("missing").await.is_none());
+        // 节点缓存：2次未命中
+        assert!(cache.get_node("n1").await.is_none());
+        assert!(cache.get_node("n2").await.is_none());
+
+        let metrics = cache.cache_metrics();
+        assert_eq!((metrics.vectors.hits, metrics.vectors.misses), (3, 1));
+        assert!((metrics.vectors.hit_ratio - 0.75).abs() < 1e-6);
+        assert_eq!((metrics.nodes.hits, metrics.nodes.misses), (0, 2));
+        assert_eq!(metrics.nodes.hit_ratio, 0.0);
+        assert_eq!(metrics.edges, CacheCategoryMetrics::default());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_edge_upserts_accumulate_into_one_edge() {
+        let db = create_test_db().await;
+        for id in ["from", "to"] {
+            db.insert_node(&GraphNode {
+                id: id.to_string(),
+                node_type: "memory".to_string(),
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let upserts = (0..10).map(|i| {
+            let edge = GraphEdge {
+                id: format!("edge{}", i),
+                from_node: "from".to_string(),
+                to_node: "to".to_string(),
+                edge_type: "semantic".to_string(),
+                weight: 0.05,
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            let db = &db;
+            async move { db.upsert_edge_with_accumulation(&edge).await }
+        });
+        let ids: Vec<String> = futures::future::join_all(upserts)
+            .await
+            .into_iter()
+            .map(|result| result.unwrap())
+            .collect();
+        assert!(ids.iter().all(|id| *id == ids[0]));
+
+        let edges = db.get_graph_edges().await.unwrap();
+        assert_eq!(edges.len(), 1);
+        assert!((edges[0].weight - 0.5).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_persisted_index_is_reloaded_without_rebuild() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!(
+                    "sqlite://{}?mode=rwc",
+                    dir.path().join("index.db").display()
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.vector.dimension = 4;
+        config.vector.index_type = VectorIndexType::IVF;
+        config.vector.index_params.nlist = Some(4);
+        config.vector.search_params.nprobe = Some(2);
+        config.vector.index_path = Some(dir.path().join("index.ivf"));
+
+        let vector = |i: usize| Vector {
+            id: format!("v{:02}", i),
+            embedding: vec![
+                (i % 4) as f32 + 1.0,
+                (i % 3) as f32,
+                (i % 5) as f32 * 0.5,
+                i as f32 * 0.1,
+            ],
+            metadata: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let request = VectorQueryRequest {
+            query_vector: vec![2.0, 1.0, 0.5, 0.3],
+            limit: Some(5),
+            threshold: None,
+            filters: None,
+            tags: TagFilter::default(),
+        };
+        let ranked = |results: Vec<VectorQueryResult>| -> Vec<(String, f32)> {
+            results
+                .into_iter()
+                .map(|result| (result.vector.id, result.similarity))
+                .collect()
+        };
+
+        // 首次启动没有旁路文件，从空表构建一次
+        let db = VectorGraphDB::new(config.clone()).await.unwrap();
+        assert_eq!(db.index_rebuilds(), 1);
+        for i in 0..40 {
+            db.insert_vector(&vector(i)).await.unwrap();
+        }
+        let expected = ranked(db.query_vectors(&request).await.unwrap());
+        assert_eq!(expected.len(), 5);
+        db.persist_index().await.unwrap();
+        drop(db);
+
+        // 表未变化：直接加载旁路文件，结果一致
+        let db = VectorGraphDB::new(config.clone()).await.unwrap();
+        assert_eq!(db.index_rebuilds(), 0);
+        assert_eq!(ranked(db.query_vectors(&request).await.unwrap()), expected);
+
+        // 写入后未持久化，旁路文件过期，下次启动重建
+        db.insert_vector(&vector(40)).await.unwrap();
+        drop(db);
+        let db = VectorGraphDB::new(config).await.unwrap();
+        assert_eq!(db.index_rebuilds(), 1);
+        assert_eq!(db.query_vectors(&request).await.unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_query_is_served_from_cache_until_vectors_change() {
+        let db = create_test_db().await;
+        let vector = |id: &str, embedding: Vec<f32>| Vector {
+            id: id.to_string(),
+            embedding,
+            metadata: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let request = VectorQueryRequest {
+            query_vector: vec![1.0, 0.0, 0.0, 0.0],
+            limit: Some(10),
+            threshold: None,
+            filters: None,
+            tags: TagFilter::default(),
+        };
+        db.insert_vector(&vector("a", vec![1.0, 0.0, 0.0, 0.0]))
+            .await
+            .unwrap();
+
+        let first = db.query_vectors(&request).await.unwrap();
+        let second = db.query_vectors(&request).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        let metrics = db.cache_metrics().query_cache;
+        assert_eq!((metrics.hits, metrics.misses), (1, 1));
+
+        // 写入向量后缓存失效，新向量出现在结果中
+        db.insert_vector(&vector("b", vec![0.0, 1.0, 0.0, 0.0]))
+            .await
+            .unwrap();
+        let after_write = db.query_vectors(&request).await.unwrap();
+        assert_eq!(after_write.len(), 2);
+        assert_eq!(db.cache_metrics().query_cache.misses, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stale_query_result_is_not_cached() {
+        let cache = MemoryCache::new(10);
+        let generation = cache.query_generation();
+        cache.invalidate_queries().await;
+        cache
+            .put_query_result("q".to_string(), generation, Vec::new())
+            .await;
+        assert!(cache.get_query_result("q").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_query_reports_dimension_mismatch() {
+        let db = create_test_db().await;
+        for (id, embedding) in [
+            ("good", vec![0.1, 0.2, 0.3, 0.4]),
+            ("corrupt", vec![0.1, 0.2, 0.3]),
+        ] {
+            db.insert_vector(&Vector {
+                id: id.to_string(),
+                embedding,
+                metadata: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            })
+            .await
+            .unwrap();
+        }
+
+        let result = db
+            .query_vectors(&VectorQueryRequest {
+                query_vector: vec![0.1, 0.2, 0.3, 0.4],
+                limit: Some(10),
+                threshold: None,
+                filters: None,
+                tags: TagFilter::default(),
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(MemoryError::InvalidVectorDimension {
+                expected: 4,
+                actual: 3
+            })
+        ));
+    }
 }
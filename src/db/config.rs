@@ -28,6 +28,25 @@ pub struct DatabaseConfig {
     pub timeout: u64,
     /// 是否自动创建表
     pub auto_create_tables: bool,
+    /// 连接池最大连接数
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+    /// 从连接池获取连接的超时时间（秒）
+    #[serde(default = "default_acquire_timeout_secs")]
+    pub acquire_timeout_secs: u64,
+    /// 空闲连接的回收时间（秒，0 表示不回收）
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+    /// 写操作遇到 SQLITE_BUSY 等瞬时错误时的最大重试次数（0 表示不重试）
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    /// 首次重试前的退避时间（毫秒），之后每次翻倍并附加随机抖动
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// 命名空间：会话、记忆事件、语义片段与画像特征只在同一命名空间内可见，
+    /// `None` 表示默认命名空间。与记忆系统的 `database.namespace` 取值规则相同
+    #[serde(default)]
+    pub namespace: Option<String>,
 }
 
 impl Default for DatabaseConfig {
@@ -38,10 +57,36 @@ impl Default for DatabaseConfig {
             pool_size: 10,
             timeout: 30,
             auto_create_tables: true,
+            max_connections: default_max_connections(),
+            acquire_timeout_secs: default_acquire_timeout_secs(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+            max_retries: default_max_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+            namespace: None,
         }
     }
 }
 
+fn default_max_connections() -> u32 {
+    10
+}
+
+fn default_acquire_timeout_secs() -> u64 {
+    30
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    600
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    20
+}
+
 impl DatabaseConfig {
     /// 创建SQLite配置
     pub fn sqlite<P: Into<PathBuf>>(path: P) -> Self {
@@ -60,4 +105,30 @@ impl DatabaseConfig {
             ..Default::default()
         }
     }
+
+    /// 设置连接池大小与超时
+    pub fn with_pool(
+        mut self,
+        max_connections: u32,
+        acquire_timeout_secs: u64,
+        idle_timeout_secs: u64,
+    ) -> Self {
+        self.max_connections = max_connections;
+        self.acquire_timeout_secs = acquire_timeout_secs;
+        self.idle_timeout_secs = idle_timeout_secs;
+        self
+    }
+
+    /// 设置瞬时错误的重试次数与初始退避时间
+    pub fn with_retry(mut self, max_retries: u32, retry_backoff_ms: u64) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff_ms = retry_backoff_ms;
+        self
+    }
+
+    /// 设置命名空间
+    pub fn with_namespace<S: Into<String>>(mut self, namespace: S) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
 }
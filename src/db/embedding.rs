@@ -2,85 +2,387 @@
 //! 基于 model2vec-rs 提供快速文本嵌入功能
 
 use anyhow::Result;
+use async_trait::async_trait;
+use lru::LruCache;
 use model2vec_rs::model::StaticModel;
+use sha2::{Digest, Sha256};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+use crate::telemetry;
+use crate::utils::{ConcurrencyLimiter, VectorUtils};
+
+/// 编码函数：输入一批文本，按顺序返回对应的嵌入向量
+pub type EncodeFn = Box<dyn Fn(&[String]) -> Vec<Vec<f32>> + Send>;
+
+/// 默认嵌入缓存容量，与 `config.vector.embedding_cache_size` 的默认值一致
+pub const DEFAULT_EMBEDDING_CACHE_SIZE: usize = 1024;
+
+/// 嵌入缓存统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmbeddingCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// 嵌入向量的 LRU 缓存
+///
+/// 键为 (模型 ID, 归一化文本) 的 SHA-256 摘要，不同模型的嵌入互不复用。
+/// 内部自带锁，可以在多个服务实例之间通过 `Arc` 共享。
+#[derive(Debug)]
+pub struct EmbeddingCache {
+    entries: std::sync::Mutex<Option<LruCache<[u8; 32], Vec<f32>>>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EmbeddingCache {
+    /// 创建容量为 `capacity` 的缓存，容量为 0 时禁用缓存
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: std::sync::Mutex::new(NonZeroUsize::new(capacity).map(LruCache::new)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// 计算缓存键：首尾空白去除、内部空白折叠后的文本连同模型 ID 一起哈希
+    pub fn key(model_id: &str, text: &str) -> [u8; 32] {
+        let normalized = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut hasher = Sha256::new();
+        hasher.update(model_id.as_bytes());
+        hasher.update([0u8]);
+        hasher.update(normalized.as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// 查找缓存，并记录命中/未命中
+    pub fn get(&self, key: &[u8; 32]) -> Option<Vec<f32>> {
+        let found = self
+            .entries
+            .lock()
+            .ok()
+            .and_then(|mut entries| entries.as_mut()?.get(key).cloned());
+        let counter = if found.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        found
+    }
+
+    /// 写入缓存
+    pub fn put(&self, key: [u8; 32], embedding: Vec<f32>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            if let Some(entries) = entries.as_mut() {
+                entries.put(key, embedding);
+            }
+        }
+    }
+
+    /// 获取统计信息
+    pub fn stats(&self) -> EmbeddingCacheStats {
+        EmbeddingCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self
+                .entries
+                .lock()
+                .ok()
+                .and_then(|entries| entries.as_ref().map(LruCache::len))
+                .unwrap_or(0),
+        }
+    }
+}
+
+impl Default for EmbeddingCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_EMBEDDING_CACHE_SIZE)
+    }
+}
+
+/// 嵌入后端
+///
+/// 默认使用 model2vec 的 [`StaticModelEmbedder`]；接入外部嵌入服务（OpenAI、Triton、
+/// candle 上的 bge 等）时实现此 trait，并通过 [`EmbeddingService::with_embedder`] 注入。
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    /// 编码一批文本，按顺序返回对应的嵌入向量
+    async fn encode(&self, texts: &[String]) -> Result<Vec<Vec<f32>>>;
+
+    /// 嵌入向量维度
+    fn dimension(&self) -> usize;
+}
+
+/// 基于 model2vec `StaticModel` 的默认嵌入后端
+///
+/// 模型只读且可并发使用，编码在阻塞线程池中执行，不占用异步运行时的工作线程。
+pub struct StaticModelEmbedder {
+    model: Arc<StaticModel>,
+    dimension: usize,
+}
+
+impl StaticModelEmbedder {
+    /// 从本地路径或 HuggingFace 仓库加载模型
+    pub fn load(model_path: &str) -> Result<Self> {
+        let model = StaticModel::from_pretrained(model_path, None, None, None)
+            .map_err(|e| anyhow::anyhow!("Failed to load model2vec model: {}", e))?;
+
+        // 检测嵌入维度
+        let dimension = model
+            .encode(&["test".to_string()])
+            .first()
+            .map(Vec::len)
+            .unwrap_or(0);
+
+        Ok(Self {
+            model: Arc::new(model),
+            dimension,
+        })
+    }
+}
+
+#[async_trait]
+impl Embedder for StaticModelEmbedder {
+    async fn encode(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let model = self.model.clone();
+        let texts = texts.to_vec();
+        Ok(tokio::task::spawn_blocking(move || model.encode(&texts)).await?)
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+/// 包装同步编码函数的嵌入后端，编码函数在阻塞线程池中调用
+struct EncodeFnEmbedder {
+    encoder: Arc<std::sync::Mutex<EncodeFn>>,
+    dimension: usize,
+}
+
+#[async_trait]
+impl Embedder for EncodeFnEmbedder {
+    async fn encode(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let encoder = self.encoder.clone();
+        let texts = texts.to_vec();
+        tokio::task::spawn_blocking(move || {
+            let encoder = encoder
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Failed to acquire encoder lock: {}", e))?;
+            Ok(encoder(&texts))
+        })
+        .await?
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
 /// 嵌入模型服务
 pub struct EmbeddingService {
-    model: Arc<Mutex<Option<StaticModel>>>,
+    embedder: Option<Box<dyn Embedder>>,
     model_path: String,
-    embedding_dim: usize,
+    cache: Arc<EmbeddingCache>,
+    limiter: ConcurrencyLimiter,
+}
+
+impl std::fmt::Debug for EmbeddingService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EmbeddingService")
+            .field("model_path", &self.model_path)
+            .field("embedding_dim", &self.embedding_dim())
+            .field("initialized", &self.embedder.is_some())
+            .finish()
+    }
 }
 
 impl EmbeddingService {
     /// 创建新的嵌入服务实例
     pub fn new(model_path: String) -> Self {
         Self {
-            model: Arc::new(Mutex::new(None)),
+            embedder: None,
             model_path,
-            embedding_dim: 0,
+            cache: Arc::new(EmbeddingCache::default()),
+            limiter: ConcurrencyLimiter::new("embedding", None),
+        }
+    }
+
+    /// 使用自定义嵌入后端创建已就绪的服务，`model_id` 参与缓存键
+    pub fn with_embedder(model_id: impl Into<String>, embedder: Box<dyn Embedder>) -> Self {
+        Self {
+            embedder: Some(embedder),
+            model_path: model_id.into(),
+            cache: Arc::new(EmbeddingCache::default()),
+            limiter: ConcurrencyLimiter::new("embedding", None),
+        }
+    }
+
+    /// 使用自定义编码函数创建已就绪的服务，`model_id` 参与缓存键
+    pub fn with_encoder(model_id: impl Into<String>, encoder: EncodeFn) -> Self {
+        let dimension = encoder(&["test".to_string()])
+            .first()
+            .map(Vec::len)
+            .unwrap_or(0);
+        let embedder = EncodeFnEmbedder {
+            encoder: Arc::new(std::sync::Mutex::new(encoder)),
+            dimension,
+        };
+        Self::with_embedder(model_id, Box::new(embedder))
+    }
+
+    /// 设置嵌入缓存容量（通常取 `config.vector.embedding_cache_size`）
+    pub fn with_cache_size(mut self, capacity: usize) -> Self {
+        self.cache = Arc::new(EmbeddingCache::new(capacity));
+        self
+    }
+
+    /// 按向量配置设置缓存容量（`embedding_cache_size`）与并发上限（`max_concurrent_embeddings`）
+    pub fn with_vector_config(self, vector: &crate::config::VectorConfig) -> Self {
+        let service = self.with_cache_size(vector.embedding_cache_size);
+        match vector.max_concurrent_embeddings {
+            Some(limit) => service.with_max_concurrency(limit),
+            None => service,
         }
     }
 
+    /// 使用共享的嵌入缓存
+    pub fn with_cache(mut self, cache: Arc<EmbeddingCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// 限制本服务同时进行的编码数，超出的请求排队等待
+    /// （通常取 `config.vector.max_concurrent_embeddings`）
+    pub fn with_max_concurrency(self, limit: usize) -> Self {
+        self.with_limiter(ConcurrencyLimiter::new("embedding", Some(limit)))
+    }
+
+    /// 使用共享的限流器，与其他嵌入服务合计并发上限
+    pub fn with_limiter(mut self, limiter: ConcurrencyLimiter) -> Self {
+        self.limiter = limiter;
+        self
+    }
+
+    /// 同时进行的编码数上限，`None` 表示不限制
+    pub fn max_concurrency(&self) -> Option<usize> {
+        self.limiter.limit()
+    }
+
+    /// 当前排队等待编码的请求数
+    pub fn queue_depth(&self) -> usize {
+        self.limiter.queue_depth()
+    }
+
+    /// 模型 ID（模型路径）
+    pub fn model_id(&self) -> &str {
+        &self.model_path
+    }
+
+    /// 嵌入缓存统计
+    pub fn cache_stats(&self) -> EmbeddingCacheStats {
+        self.cache.stats()
+    }
+
     /// 初始化模型（延迟加载）
     pub async fn initialize(&mut self) -> Result<()> {
-        let mut model_guard = self.model.lock().await;
-
-        if model_guard.is_some() {
+        if self.embedder.is_some() {
             return Ok(());
         }
 
         log::info!("Loading model2vec model from: {}", self.model_path);
 
-        // 加载 model2vec 模型
-        let static_model = StaticModel::from_pretrained(self.model_path.clone(), None, None, None)
-            .map_err(|e| anyhow::anyhow!("Failed to load model2vec model: {}", e))?;
-
-        // 检测嵌入维度
-        let test_embedding = static_model.encode(&["test".to_string()]);
-
-        self.embedding_dim = test_embedding.first().map(|emb| emb.len()).unwrap_or(0);
+        let embedder = StaticModelEmbedder::load(&self.model_path)?;
 
         log::info!(
             "Model2vec model loaded successfully, embedding dimension: {}",
-            self.embedding_dim
+            embedder.dimension()
         );
 
-        *model_guard = Some(static_model);
+        self.embedder = Some(Box::new(embedder));
         Ok(())
     }
 
     /// 编码文本为嵌入向量
     pub async fn encode(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
-        let model_guard = self.model.lock().await;
-
-        let model = model_guard
+        let embedder = self
+            .embedder
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("Model not initialized"))?;
 
         log::debug!("Encoding {} texts", texts.len());
 
         let inputs: Vec<String> = texts.iter().map(|s| s.to_string()).collect();
-        let embeddings = model.encode(&inputs);
+        let _permit = self.limiter.acquire().await?;
+        let embeddings = embedder.encode(&inputs).await?;
+        telemetry::record_embeddings_generated(embeddings.len());
 
         log::debug!("Successfully encoded {} embeddings", embeddings.len());
 
         Ok(embeddings)
     }
 
-    /// 编码单个文本
+    /// 编码单个文本，相同模型下相同文本的结果直接取自缓存
     pub async fn encode_single(&self, text: &str) -> Result<Vec<f32>> {
-        let embeddings = self.encode(&[text]).await?;
-        embeddings
+        let key = EmbeddingCache::key(&self.model_path, text);
+        if let Some(embedding) = self.cache.get(&key) {
+            return Ok(embedding);
+        }
+
+        let embedding = self
+            .encode(&[text])
+            .await?
             .into_iter()
             .next()
-            .ok_or_else(|| anyhow::anyhow!("No embedding returned"))
+            .ok_or_else(|| anyhow::anyhow!("No embedding returned"))?;
+        self.cache.put(key, embedding.clone());
+        Ok(embedding)
+    }
+
+    /// 批量编码文本，结果与输入一一对应
+    ///
+    /// 已缓存的文本直接复用，其余文本在一次模型调用中完成编码。
+    pub async fn encode_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let keys: Vec<[u8; 32]> = texts
+            .iter()
+            .map(|text| EmbeddingCache::key(&self.model_path, text))
+            .collect();
+        let mut embeddings: Vec<Option<Vec<f32>>> =
+            keys.iter().map(|key| self.cache.get(key)).collect();
+
+        let missing: Vec<usize> = (0..texts.len())
+            .filter(|&index| embeddings[index].is_none())
+            .collect();
+        if !missing.is_empty() {
+            let inputs: Vec<&str> = missing.iter().map(|&index| texts[index].as_str()).collect();
+            let encoded = self.encode(&inputs).await?;
+            if encoded.len() != missing.len() {
+                return Err(anyhow::anyhow!(
+                    "Expected {} embeddings, got {}",
+                    missing.len(),
+                    encoded.len()
+                ));
+            }
+            for (index, embedding) in missing.into_iter().zip(encoded) {
+                self.cache.put(keys[index], embedding.clone());
+                embeddings[index] = Some(embedding);
+            }
+        }
+
+        Ok(embeddings.into_iter().flatten().collect())
     }
 
     /// 获取嵌入维度
     pub fn embedding_dim(&self) -> usize {
-        self.embedding_dim
+        self.embedder
+            .as_ref()
+            .map(|embedder| embedder.dimension())
+            .unwrap_or(0)
     }
 
     /// 计算余弦相似度
@@ -89,15 +391,8 @@ impl EmbeddingService {
             return 0.0;
         }
 
-        let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-        let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-        let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
-
-        if norm_a == 0.0 || norm_b == 0.0 {
-            return 0.0;
-        }
-
-        dot_product / (norm_a * norm_b)
+        let (dot_product, norm_a_sq, norm_b_sq) = VectorUtils::dot_and_norms(a, b);
+        VectorUtils::cosine_from_parts(dot_product, norm_a_sq, norm_b_sq)
     }
 
     /// 序列化嵌入向量为字节
@@ -131,7 +426,12 @@ pub async fn initialize_global_embedding_service(model_path: Option<String>) ->
     let path = model_path.unwrap_or_else(|| "./qwen3-model2vec".to_string());
     let mut service = EmbeddingService::new(path);
     service.initialize().await?;
+    set_global_embedding_service(service)
+}
 
+/// 使用已就绪的服务（例如通过 [`EmbeddingService::with_embedder`] 注入的自定义后端）
+/// 初始化全局嵌入服务
+pub fn set_global_embedding_service(service: EmbeddingService) -> Result<()> {
     EMBEDDING_SERVICE
         .set(Arc::new(Mutex::new(service)))
         .map_err(|_| anyhow::anyhow!("Global embedding service already initialized"))?;
@@ -150,3 +450,199 @@ pub fn get_global_embedding_service() -> Result<Arc<Mutex<EmbeddingService>>> {
 pub fn is_global_embedding_service_initialized() -> bool {
     EMBEDDING_SERVICE.get().is_some()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// 以文本长度与首字符构造确定性向量，并统计调用次数
+    fn counting_encoder(calls: Arc<AtomicUsize>) -> EncodeFn {
+        Box::new(move |inputs: &[String]| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            inputs
+                .iter()
+                .map(|text| {
+                    let first = text.chars().next().map(|c| c as u32 as f32).unwrap_or(0.0);
+                    vec![text.len() as f32, first, 1.0]
+                })
+                .collect()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_encode_single_uses_cache() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = EmbeddingService::with_encoder("model-a", counting_encoder(calls.clone()));
+        let calls_after_probe = calls.load(Ordering::SeqCst);
+
+        let first = service.encode_single("hello world").await.unwrap();
+        let second = service.encode_single("  hello   world ").await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(calls.load(Ordering::SeqCst), calls_after_probe + 1);
+        let stats = service.cache_stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.entries, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_is_keyed_by_model_id() {
+        let cache = Arc::new(EmbeddingCache::new(16));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let model_a = EmbeddingService::with_encoder("model-a", counting_encoder(calls.clone()))
+            .with_cache(cache.clone());
+        let model_b = EmbeddingService::with_encoder("model-b", counting_encoder(calls.clone()))
+            .with_cache(cache.clone());
+
+        model_a.encode_single("shared text").await.unwrap();
+        model_b.encode_single("shared text").await.unwrap();
+        model_a.encode_single("shared text").await.unwrap();
+
+        let stats = cache.stats();
+        assert_eq!(stats.misses, 2);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.entries, 2);
+    }
+
+    #[tokio::test]
+    async fn test_encode_batch_matches_encode_single() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let batched = EmbeddingService::with_encoder("model-a", counting_encoder(calls.clone()));
+        let single = EmbeddingService::with_encoder("model-a", counting_encoder(calls.clone()));
+        let texts = vec!["alpha".to_string(), "bravo charlie".to_string()];
+
+        let calls_before = calls.load(Ordering::SeqCst);
+        let embeddings = batched.encode_batch(&texts).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), calls_before + 1);
+
+        let expected = vec![
+            single.encode_single(&texts[0]).await.unwrap(),
+            single.encode_single(&texts[1]).await.unwrap(),
+        ];
+        assert_eq!(embeddings, expected);
+
+        // 部分命中缓存时仍保持输入顺序
+        let mixed = vec![texts[1].clone(), "delta".to_string(), texts[0].clone()];
+        let embeddings = batched.encode_batch(&mixed).await.unwrap();
+        assert_eq!(embeddings[0], expected[1]);
+        assert_eq!(embeddings[1], single.encode_single("delta").await.unwrap());
+        assert_eq!(embeddings[2], expected[0]);
+
+        assert!(batched.encode_batch(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_zero_capacity_disables_cache() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let service = EmbeddingService::with_encoder("model-a", counting_encoder(calls.clone()))
+            .with_cache_size(0);
+        let calls_after_probe = calls.load(Ordering::SeqCst);
+
+        service.encode_single("hello").await.unwrap();
+        service.encode_single("hello").await.unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), calls_after_probe + 2);
+        assert_eq!(service.cache_stats().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_vector_config_sets_cache_size() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let vector = crate::config::VectorConfig {
+            embedding_cache_size: 2,
+            ..Default::default()
+        };
+        let service = EmbeddingService::with_encoder("model-a", counting_encoder(calls))
+            .with_vector_config(&vector);
+
+        for text in ["alpha", "bravo", "charlie", "alpha"] {
+            service.encode_single(text).await.unwrap();
+        }
+
+        // 容量为 2 时 alpha 在 charlie 写入后被淘汰，再次编码不会命中
+        let stats = service.cache_stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.hits, 0);
+        assert_eq!(stats.misses, 4);
+    }
+
+    /// 记录同时进行的编码数峰值的嵌入后端
+    struct SlowEmbedder {
+        active: AtomicUsize,
+        peak: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl Embedder for SlowEmbedder {
+        async fn encode(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+            let active = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(active, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            self.active.fetch_sub(1, Ordering::SeqCst);
+            Ok(texts.iter().map(|text| vec![text.len() as f32]).collect())
+        }
+
+        fn dimension(&self) -> usize {
+            1
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_concurrency_queues_excess_requests() {
+        let peak = Arc::new(AtomicUsize::new(0));
+        let embedder = SlowEmbedder {
+            active: AtomicUsize::new(0),
+            peak: peak.clone(),
+        };
+        let service = Arc::new(
+            EmbeddingService::with_embedder("model-a", Box::new(embedder))
+                .with_cache_size(0)
+                .with_max_concurrency(2),
+        );
+
+        let tasks: Vec<_> = (0..8)
+            .map(|index| {
+                let service = service.clone();
+                tokio::spawn(async move { service.encode_single(&format!("text {}", index)).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        assert_eq!(service.queue_depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_vector_config_sets_max_concurrency() {
+        let peak = Arc::new(AtomicUsize::new(0));
+        let embedder = SlowEmbedder {
+            active: AtomicUsize::new(0),
+            peak: peak.clone(),
+        };
+        let vector = crate::config::VectorConfig {
+            max_concurrent_embeddings: Some(3),
+            ..Default::default()
+        };
+        let service = Arc::new(
+            EmbeddingService::with_embedder("model-a", Box::new(embedder))
+                .with_vector_config(&vector),
+        );
+        assert_eq!(service.max_concurrency(), Some(3));
+
+        let tasks: Vec<_> = (0..8)
+            .map(|index| {
+                let service = service.clone();
+                tokio::spawn(async move { service.encode_single(&format!("text {}", index)).await })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap().unwrap();
+        }
+
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+}
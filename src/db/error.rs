@@ -0,0 +1,146 @@
+//! 数据库错误类型
+
+use thiserror::Error;
+
+/// SQLite 主错误码：数据库文件被占用
+pub(crate) const SQLITE_BUSY: i32 = 5;
+/// SQLite 主错误码：表被同一连接中的其他操作锁定
+pub(crate) const SQLITE_LOCKED: i32 = 6;
+/// SQLite 主错误码：违反约束（唯一、外键、非空等）
+const SQLITE_CONSTRAINT: i32 = 19;
+
+/// 数据库层错误
+///
+/// 每个变体携带可读的错误信息，调用方按变体区分处理方式（如 `Busy` 可重试）。
+#[derive(Error, Debug)]
+pub enum DbError {
+    /// 记录不存在
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    /// 违反约束
+    #[error("Constraint violation: {0}")]
+    Constraint(String),
+
+    /// 连接失败或连接池已关闭
+    #[error("Connection error: {0}")]
+    Connection(String),
+
+    /// 数据库被占用或锁定、连接池获取超时
+    #[error("Database busy: {0}")]
+    Busy(String),
+
+    /// 序列化/反序列化或列解码失败
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+
+    /// 其他错误
+    #[error("Database error: {0}")]
+    Other(String),
+}
+
+impl DbError {
+    /// 创建记录不存在错误
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
+    }
+
+    /// 为错误附加上下文，保留原有的错误类别
+    pub fn with_context(context: impl std::fmt::Display, error: impl Into<DbError>) -> Self {
+        match error.into() {
+            Self::NotFound(msg) => Self::NotFound(format!("{}: {}", context, msg)),
+            Self::Constraint(msg) => Self::Constraint(format!("{}: {}", context, msg)),
+            Self::Connection(msg) => Self::Connection(format!("{}: {}", context, msg)),
+            Self::Busy(msg) => Self::Busy(format!("{}: {}", context, msg)),
+            Self::Serialization(msg) => Self::Serialization(format!("{}: {}", context, msg)),
+            Self::Other(msg) => Self::Other(format!("{}: {}", context, msg)),
+        }
+    }
+
+    /// 错误信息（不含类别前缀）
+    pub fn message(&self) -> &str {
+        match self {
+            Self::NotFound(msg)
+            | Self::Constraint(msg)
+            | Self::Connection(msg)
+            | Self::Busy(msg)
+            | Self::Serialization(msg)
+            | Self::Other(msg) => msg,
+        }
+    }
+}
+
+/// 取 SQLite 主错误码（扩展码的低 8 位）
+pub(crate) fn sqlite_primary_code(error: &dyn sqlx::error::DatabaseError) -> Option<i32> {
+    error
+        .code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .map(|code| code & 0xff)
+}
+
+impl From<sqlx::Error> for DbError {
+    fn from(error: sqlx::Error) -> Self {
+        let message = error.to_string();
+        match &error {
+            sqlx::Error::RowNotFound => Self::NotFound(message),
+            sqlx::Error::Database(db_error) => match sqlite_primary_code(db_error.as_ref()) {
+                Some(SQLITE_CONSTRAINT) => Self::Constraint(message),
+                Some(SQLITE_BUSY | SQLITE_LOCKED) => Self::Busy(message),
+                _ => Self::Other(message),
+            },
+            sqlx::Error::PoolTimedOut => Self::Busy(message),
+            sqlx::Error::PoolClosed
+            | sqlx::Error::WorkerCrashed
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_)
+            | sqlx::Error::Configuration(_)
+            | sqlx::Error::Protocol(_) => Self::Connection(message),
+            sqlx::Error::Decode(_)
+            | sqlx::Error::ColumnDecode { .. }
+            | sqlx::Error::ColumnNotFound(_)
+            | sqlx::Error::TypeNotFound { .. } => Self::Serialization(message),
+            _ => Self::Other(message),
+        }
+    }
+}
+
+impl From<serde_json::Error> for DbError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Serialization(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for DbError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Other(error.to_string())
+    }
+}
+
+impl From<String> for DbError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+
+impl From<&str> for DbError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_keeps_error_kind() {
+        let error = DbError::with_context("Failed to get session", sqlx::Error::RowNotFound);
+        assert!(matches!(error, DbError::NotFound(_)));
+        assert!(error.message().starts_with("Failed to get session: "));
+
+        let error = DbError::with_context("Failed to insert", sqlx::Error::PoolTimedOut);
+        assert!(matches!(error, DbError::Busy(_)));
+        let error = DbError::with_context("Failed to connect", sqlx::Error::PoolClosed);
+        assert!(matches!(error, DbError::Connection(_)));
+    }
+}
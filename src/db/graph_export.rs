@@ -0,0 +1,208 @@
+//! 知识图谱导出
+//! 将 graph_nodes/graph_edges 导出为 GraphML（Gephi）或 DOT（Graphviz）文本
+
+use super::{GraphEdge, GraphNode};
+use std::fmt::Write;
+
+/// 图谱导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphExportFormat {
+    /// GraphML，可直接导入 Gephi
+    GraphMl,
+    /// Graphviz DOT
+    Dot,
+}
+
+/// 按指定格式渲染图谱
+pub fn render_graph(nodes: &[GraphNode], edges: &[GraphEdge], format: GraphExportFormat) -> String {
+    match format {
+        GraphExportFormat::GraphMl => render_graphml(nodes, edges),
+        GraphExportFormat::Dot => render_dot(nodes, edges),
+    }
+}
+
+/// 节点在导出文本中的ID
+fn node_key(id: Option<i64>) -> String {
+    format!("n{}", id.unwrap_or_default())
+}
+
+/// 转义 DOT 双引号字符串中的特殊字符
+fn escape_dot(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// 转义 XML 文本与属性值
+fn escape_xml(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_dot(nodes: &[GraphNode], edges: &[GraphEdge]) -> String {
+    let mut out = String::from("digraph knowledge_graph {\n");
+    for node in nodes {
+        let _ = writeln!(
+            out,
+            "  {} [label=\"{}\", entity_type=\"{}\", entity_name=\"{}\"];",
+            node_key(node.id),
+            escape_dot(&node.entity_name),
+            escape_dot(&node.entity_type),
+            escape_dot(&node.entity_name)
+        );
+    }
+    for edge in edges {
+        let _ = writeln!(
+            out,
+            "  {} -> {} [label=\"{}\", relation_type=\"{}\", weight={}];",
+            node_key(Some(edge.from_node)),
+            node_key(Some(edge.to_node)),
+            escape_dot(&edge.relation_type),
+            escape_dot(&edge.relation_type),
+            edge.weight
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_graphml(nodes: &[GraphNode], edges: &[GraphEdge]) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"entity_type\" for=\"node\" attr.name=\"entity_type\" attr.type=\"string\"/>\n",
+        "  <key id=\"entity_name\" for=\"node\" attr.name=\"entity_name\" attr.type=\"string\"/>\n",
+        "  <key id=\"relation_type\" for=\"edge\" attr.name=\"relation_type\" attr.type=\"string\"/>\n",
+        "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"double\"/>\n",
+        "  <graph id=\"knowledge_graph\" edgedefault=\"directed\">\n",
+    ));
+    for node in nodes {
+        let _ = writeln!(
+            out,
+            "    <node id=\"{}\"><data key=\"entity_type\">{}</data><data key=\"entity_name\">{}</data></node>",
+            node_key(node.id),
+            escape_xml(&node.entity_type),
+            escape_xml(&node.entity_name)
+        );
+    }
+    for edge in edges {
+        let _ = writeln!(
+            out,
+            "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"><data key=\"relation_type\">{}</data><data key=\"weight\">{}</data></edge>",
+            edge.id.unwrap_or_default(),
+            node_key(Some(edge.from_node)),
+            node_key(Some(edge.to_node)),
+            escape_xml(&edge.relation_type),
+            edge.weight
+        );
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{DatabaseConfig, DatabaseManager};
+
+    /// 简单校验 DOT 结构：引号成对、语句以分号结尾、花括号闭合
+    fn assert_valid_dot(dot: &str) {
+        let lines: Vec<&str> = dot.lines().collect();
+        assert!(lines.first().unwrap().starts_with("digraph "));
+        assert!(lines.first().unwrap().ends_with('{'));
+        assert_eq!(*lines.last().unwrap(), "}");
+        for line in &lines[1..lines.len() - 1] {
+            assert!(line.ends_with("];"), "bad statement: {}", line);
+            let mut in_string = false;
+            let mut chars = line.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' if in_string => {
+                        chars.next();
+                    }
+                    '"' => in_string = !in_string,
+                    _ => {}
+                }
+            }
+            assert!(!in_string, "unterminated string: {}", line);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_graph_as_dot() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let manager = DatabaseManager::new(DatabaseConfig {
+            db_path: temp_dir.path().join("graph.db"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let alice = manager
+            .upsert_graph_node(GraphNode {
+                id: None,
+                entity_type: "person".to_string(),
+                entity_name: "Alice \"Ally\" Chen".to_string(),
+            })
+            .await
+            .unwrap();
+        let city = manager
+            .upsert_graph_node(GraphNode {
+                id: None,
+                entity_type: "location".to_string(),
+                entity_name: "杭州".to_string(),
+            })
+            .await
+            .unwrap();
+        manager
+            .upsert_graph_edge(GraphEdge {
+                id: None,
+                from_node: alice,
+                to_node: city,
+                relation_type: "lives_in".to_string(),
+                weight: 0.8,
+            })
+            .await
+            .unwrap();
+
+        let dot = manager.export_graph(GraphExportFormat::Dot).await.unwrap();
+        assert_valid_dot(&dot);
+        assert!(dot.contains(&format!(
+            "n{} [label=\"Alice \\\"Ally\\\" Chen\", entity_type=\"person\"",
+            alice
+        )));
+        assert!(dot.contains(&format!(
+            "n{} [label=\"杭州\", entity_type=\"location\"",
+            city
+        )));
+        assert!(dot.contains(&format!(
+            "n{} -> n{} [label=\"lives_in\", relation_type=\"lives_in\", weight=0.8];",
+            alice, city
+        )));
+
+        let graphml = manager
+            .export_graph(GraphExportFormat::GraphMl)
+            .await
+            .unwrap();
+        assert!(graphml.contains("Alice &quot;Ally&quot; Chen"));
+        assert!(graphml.contains(&format!("source=\"n{}\" target=\"n{}\"", alice, city)));
+    }
+}
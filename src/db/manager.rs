@@ -5,8 +5,8 @@ use super::config::{DatabaseConfig, DatabaseType};
 use super::embedding::{get_global_embedding_service, EmbeddingService};
 use super::sqlite::SqliteDatabase;
 use super::{
-    Database, DbResult, GraphEdge, GraphNode, MemoryEvent, PersonaProfile, PersonaTrait,
-    SemanticChunk,
+    CheckpointMode, Database, DbResult, EdgeDirection, GraphEdge, GraphExportFormat, GraphNode,
+    MemoryEvent, PersonaDecayStats, PersonaProfile, PersonaTrait, SemanticChunk,
 };
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -22,6 +22,8 @@ enum DatabaseInstance {
 pub struct DatabaseManager {
     database: Arc<Mutex<DatabaseInstance>>,
     config: DatabaseConfig,
+    /// 专用嵌入服务，未设置时使用全局嵌入服务
+    embedding_service: Option<Arc<Mutex<EmbeddingService>>>,
 }
 
 impl DatabaseManager {
@@ -45,9 +47,38 @@ impl DatabaseManager {
         Ok(Self {
             database: Arc::new(Mutex::new(database)),
             config,
+            embedding_service: None,
         })
     }
 
+    /// 使用专用的嵌入服务（例如自定义嵌入后端），代替全局嵌入服务
+    pub fn with_embedding_service(mut self, service: EmbeddingService) -> Self {
+        self.embedding_service = Some(Arc::new(Mutex::new(service)));
+        self
+    }
+
+    /// 创建共享同一数据库连接池、但只访问指定命名空间会话与记忆事件的管理器
+    pub async fn with_namespace<S: Into<String>>(&self, namespace: S) -> Self {
+        let namespace = namespace.into();
+        let database = match &*self.database.lock().await {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                DatabaseInstance::Sqlite(sqlite_db.with_namespace(namespace.clone()))
+            }
+        };
+        Self {
+            database: Arc::new(Mutex::new(database)),
+            config: self.config.clone().with_namespace(namespace),
+            embedding_service: self.embedding_service.clone(),
+        }
+    }
+
+    /// 获取写入与检索使用的嵌入服务：优先专用服务，其次全局服务
+    pub fn embedding_service(&self) -> Option<Arc<Mutex<EmbeddingService>>> {
+        self.embedding_service
+            .clone()
+            .or_else(|| get_global_embedding_service().ok())
+    }
+
     /// 获取数据库配置
     pub fn config(&self) -> &DatabaseConfig {
         &self.config
@@ -69,6 +100,22 @@ impl DatabaseManager {
         }
     }
 
+    /// 执行 WAL 检查点
+    pub async fn checkpoint(&self, mode: CheckpointMode) -> DbResult<()> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => sqlite_db.checkpoint(mode).await,
+        }
+    }
+
+    /// 优化数据库，vacuum 为 true 时同时执行 VACUUM
+    pub async fn optimize(&self, vacuum: bool) -> DbResult<()> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => sqlite_db.optimize(vacuum).await,
+        }
+    }
+
     /// 获取SQLite数据库实例（如果是SQLite类型）
     pub async fn as_sqlite(&self) -> Option<SqliteDatabase> {
         match self.config.db_type {
@@ -105,6 +152,9 @@ impl DatabaseManager {
     }
 
     /// 打开会话
+    ///
+    /// 会同时关闭其他活跃会话：这是自动解析会话（`get_active_session`）的默认路径的设计，
+    /// 需要并发对话时请使用 [`DatabaseManager::create_session`]。
     pub async fn open_session(&self, agent_name: &str, title: Option<&str>) -> DbResult<i64> {
         let db = self.database.lock().await;
         match &*db {
@@ -112,6 +162,18 @@ impl DatabaseManager {
         }
     }
 
+    /// 新建显式会话，不影响当前活跃会话
+    ///
+    /// 多个对话并发进行时，为每个对话创建独立会话并显式传入会话ID，避免轮次交错写入同一会话。
+    pub async fn create_session(&self, agent_name: &str, title: Option<&str>) -> DbResult<i64> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                sqlite_db.create_session(agent_name, title).await
+            }
+        }
+    }
+
     /// 关闭活跃会话
     pub async fn close_active_session(&self) -> DbResult<()> {
         let db = self.database.lock().await;
@@ -138,6 +200,14 @@ impl DatabaseManager {
         }
     }
 
+    /// 获取会话标题
+    pub async fn get_session_title(&self, session_id: i64) -> DbResult<Option<String>> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => sqlite_db.get_session_title(session_id).await,
+        }
+    }
+
     /// 插入记忆事件
     pub async fn insert_memory_event(&self, event: MemoryEvent) -> DbResult<i64> {
         let db = self.database.lock().await;
@@ -154,6 +224,19 @@ impl DatabaseManager {
         }
     }
 
+    /// 列出会话中尚未聚合为语义片段的记忆事件
+    pub async fn list_unsummarized_memory_events(
+        &self,
+        session_id: i64,
+    ) -> DbResult<Vec<(i64, MemoryEvent)>> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                sqlite_db.list_unsummarized_memory_events(session_id).await
+            }
+        }
+    }
+
     /// 清理所有记忆事件（用于调试）
     pub async fn clear_all_memory_events(&self) -> DbResult<()> {
         let db = self.database.lock().await;
@@ -162,6 +245,43 @@ impl DatabaseManager {
         }
     }
 
+    /// 删除单条记忆事件，返回是否存在
+    pub async fn delete_memory_event(&self, id: i64) -> DbResult<bool> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => sqlite_db.delete_memory_event(id).await,
+        }
+    }
+
+    /// 删除会话下的全部记忆事件，返回删除条数
+    pub async fn delete_session_events(&self, session_id: i64) -> DbResult<u64> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                sqlite_db.delete_session_events(session_id).await
+            }
+        }
+    }
+
+    /// 修改记忆事件文本；嵌入服务可用时重新生成向量，否则清除旧向量
+    pub async fn update_memory_event_text(&self, id: i64, new_text: &str) -> DbResult<bool> {
+        let mut embedding = None;
+        if let Some(svc) = self.embedding_service() {
+            if let Ok(emb) = svc.lock().await.encode_single(new_text).await {
+                embedding = EmbeddingService::serialize_embedding(&emb).ok();
+            }
+        }
+
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                sqlite_db
+                    .update_memory_event_text(id, new_text, embedding)
+                    .await
+            }
+        }
+    }
+
     /// 插入语义片段
     pub async fn insert_semantic_chunk(&self, chunk: SemanticChunk) -> DbResult<i64> {
         let db = self.database.lock().await;
@@ -220,6 +340,40 @@ impl DatabaseManager {
         }
     }
 
+    /// 导出图谱为 GraphML 或 DOT 文本
+    pub async fn export_graph(&self, format: GraphExportFormat) -> DbResult<String> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => sqlite_db.export_graph(format).await,
+        }
+    }
+
+    /// 获取与节点相连的边，按权重降序
+    pub async fn get_edges_for_node(
+        &self,
+        node_id: i64,
+        direction: EdgeDirection,
+    ) -> DbResult<Vec<GraphEdge>> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                sqlite_db.get_edges_for_node(node_id, direction).await
+            }
+        }
+    }
+
+    /// 获取权重最高的 `top_k` 个邻居节点
+    pub async fn get_neighbors(
+        &self,
+        node_id: i64,
+        top_k: usize,
+    ) -> DbResult<Vec<(GraphNode, GraphEdge)>> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => sqlite_db.get_neighbors(node_id, top_k).await,
+        }
+    }
+
     /// 根据实体获取节点
     pub async fn get_node_by_entity(
         &self,
@@ -247,6 +401,22 @@ impl DatabaseManager {
         }
     }
 
+    /// 清理低权重或长期未引用的语义片段，返回删除的片段数
+    pub async fn prune_semantic_chunks(
+        &self,
+        min_weight: f32,
+        older_than: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> DbResult<u64> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                sqlite_db
+                    .prune_semantic_chunks(min_weight, older_than)
+                    .await
+            }
+        }
+    }
+
     /// 根据片段ID获取映射
     pub async fn get_chunk_mappings_by_chunk_id(
         &self,
@@ -273,6 +443,16 @@ impl DatabaseManager {
         }
     }
 
+    /// 获取语义片段的来源记忆事件
+    pub async fn get_memory_events_for_chunk(&self, chunk_id: i64) -> DbResult<Vec<MemoryEvent>> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                sqlite_db.get_memory_events_for_chunk(chunk_id).await
+            }
+        }
+    }
+
     /// 累积边权重版本的 upsert_graph_edge
     pub async fn upsert_graph_edge_with_accumulation(&self, edge: GraphEdge) -> DbResult<i64> {
         let db = self.database.lock().await;
@@ -341,6 +521,24 @@ impl DatabaseManager {
             }
         }
     }
+
+    /// 按半衰期衰减画像特征，删除置信度低于 min_confidence 的特征
+    pub async fn decay_persona_traits(
+        &self,
+        agent_name: &str,
+        half_life_days: f32,
+        min_confidence: f32,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> DbResult<PersonaDecayStats> {
+        let db = self.database.lock().await;
+        match &*db {
+            DatabaseInstance::Sqlite(sqlite_db) => {
+                sqlite_db
+                    .decay_persona_traits(agent_name, half_life_days, min_confidence, now)
+                    .await
+            }
+        }
+    }
 }
 
 /// 数据库统计信息
@@ -383,7 +581,7 @@ impl DatabaseManager {
         };
 
         let mut query_embedding: Option<Vec<f32>> = None;
-        if let Ok(svc) = get_global_embedding_service() {
+        if let Some(svc) = self.embedding_service() {
             if let Ok(emb) = svc.lock().await.encode_single(query).await {
                 query_embedding = Some(emb);
             }
@@ -494,3 +692,546 @@ impl DatabaseManager {
         Ok(out)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DbError;
+
+    async fn create_test_manager(dir: &tempfile::TempDir) -> DatabaseManager {
+        DatabaseManager::new(DatabaseConfig {
+            db_path: dir.path().join("manager.db"),
+            ..Default::default()
+        })
+        .await
+        .unwrap()
+    }
+
+    async fn add_node(manager: &DatabaseManager, name: &str) -> i64 {
+        manager
+            .upsert_graph_node(GraphNode {
+                id: None,
+                entity_type: "topic".to_string(),
+                entity_name: name.to_string(),
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn add_edge(manager: &DatabaseManager, from: i64, to: i64, relation: &str, weight: f32) {
+        manager
+            .upsert_graph_edge(GraphEdge {
+                id: None,
+                from_node: from,
+                to_node: to,
+                relation_type: relation.to_string(),
+                weight,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_isolate_sessions_and_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+        let tenant_a = manager.with_namespace("tenant_a").await;
+        let tenant_b = manager.with_namespace("tenant_b").await;
+
+        let session_a = tenant_a.open_session("chat", Some("a")).await.unwrap();
+        let session_b = tenant_b.open_session("chat", Some("b")).await.unwrap();
+        for (store, session_id, text) in [
+            (&tenant_a, session_a, "tenant a secret"),
+            (&tenant_b, session_b, "tenant b secret"),
+        ] {
+            store
+                .insert_memory_event(MemoryEvent {
+                    session_id,
+                    agent_name: "chat".to_string(),
+                    role: "user".to_string(),
+                    text: text.to_string(),
+                    topic: None,
+                    sentiment: None,
+                    importance: None,
+                    decay: 1.0,
+                    embedding: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        // 打开 b 的会话不会关闭 a 的活跃会话
+        assert_eq!(
+            tenant_a.get_active_session().await.unwrap(),
+            Some(session_a)
+        );
+        assert_eq!(
+            tenant_b.get_active_session().await.unwrap(),
+            Some(session_b)
+        );
+        assert_eq!(manager.get_active_session().await.unwrap(), None);
+
+        let events = tenant_a.list_memory_events(session_a).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].text, "tenant a secret");
+        assert!(tenant_a
+            .list_memory_events(session_b)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            tenant_a.get_session_title(session_b).await,
+            Err(DbError::NotFound(_))
+        ));
+
+        tenant_b.close_active_session().await.unwrap();
+        assert_eq!(
+            tenant_a.get_active_session().await.unwrap(),
+            Some(session_a)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_isolate_chunks_and_persona_traits() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+        let tenant_a = manager.with_namespace("tenant_a").await;
+        let tenant_b = manager.with_namespace("tenant_b").await;
+
+        tenant_a
+            .insert_semantic_chunk(SemanticChunk {
+                id: None,
+                title: None,
+                summary: "secret recipe".to_string(),
+                keywords: None,
+                embedding: None,
+                last_ref_ts: None,
+                weight: 1.0,
+            })
+            .await
+            .unwrap();
+        // 两个租户的同名特征互不覆盖
+        for (store, value) in [(&tenant_a, "noodles"), (&tenant_b, "rice")] {
+            store
+                .upsert_persona_trait(PersonaTrait {
+                    id: None,
+                    agent_name: "chat".to_string(),
+                    trait_type: "preference".to_string(),
+                    trait_key: "recipe".to_string(),
+                    trait_value: value.to_string(),
+                    confidence: 0.8,
+                    stability: 0.5,
+                    last_seen: None,
+                    source_event_id: None,
+                })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(tenant_a.list_semantic_chunks(None).await.unwrap().len(), 1);
+        assert!(tenant_b
+            .list_semantic_chunks(None)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(tenant_b.prune_semantic_chunks(2.0, None).await.unwrap(), 0);
+
+        let traits_b = tenant_b
+            .list_persona_traits("chat", None, None)
+            .await
+            .unwrap();
+        assert_eq!(traits_b.len(), 1);
+        assert_eq!(traits_b[0].trait_value, "rice");
+
+        let context = tenant_b
+            .retrieve_memories("chat", "recipe", 5, 24.0, 0.0, 2000)
+            .await
+            .unwrap();
+        assert!(context.contains("rice"));
+        assert!(!context.contains("noodles"));
+        assert!(!context.contains("secret recipe"));
+    }
+
+    #[tokio::test]
+    async fn test_edges_for_node_and_neighbors() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+
+        let hub = add_node(&manager, "hub").await;
+        let a = add_node(&manager, "a").await;
+        let b = add_node(&manager, "b").await;
+        let c = add_node(&manager, "c").await;
+        let unrelated = add_node(&manager, "unrelated").await;
+        add_edge(&manager, hub, a, "likes", 0.3).await;
+        add_edge(&manager, hub, b, "likes", 0.9).await;
+        add_edge(&manager, c, hub, "mentions", 0.6).await;
+        add_edge(&manager, hub, a, "mentions", 0.5).await;
+        add_edge(&manager, a, unrelated, "likes", 1.0).await;
+
+        let outgoing = manager
+            .get_edges_for_node(hub, EdgeDirection::Outgoing)
+            .await
+            .unwrap();
+        assert_eq!(outgoing.len(), 3);
+        assert!(outgoing.iter().all(|e| e.from_node == hub));
+        let weights: Vec<f32> = outgoing.iter().map(|e| e.weight).collect();
+        assert_eq!(weights, vec![0.9, 0.5, 0.3]);
+
+        let incoming = manager
+            .get_edges_for_node(hub, EdgeDirection::Incoming)
+            .await
+            .unwrap();
+        assert_eq!(incoming.len(), 1);
+        assert_eq!(incoming[0].from_node, c);
+
+        let both = manager
+            .get_edges_for_node(hub, EdgeDirection::Both)
+            .await
+            .unwrap();
+        assert_eq!(both.len(), 4);
+
+        // 邻居按最高边权重排序，同一邻居只出现一次
+        let neighbors = manager.get_neighbors(hub, 10).await.unwrap();
+        let names: Vec<&str> = neighbors
+            .iter()
+            .map(|(node, _)| node.entity_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["b", "c", "a"]);
+        assert_eq!(neighbors[2].1.weight, 0.5);
+
+        let top = manager.get_neighbors(hub, 2).await.unwrap();
+        assert_eq!(top.len(), 2);
+    }
+
+    async fn add_trait(manager: &DatabaseManager, key: &str, confidence: f32) {
+        manager
+            .upsert_persona_trait(PersonaTrait {
+                id: None,
+                agent_name: "chat".to_string(),
+                trait_type: "preference".to_string(),
+                trait_key: key.to_string(),
+                trait_value: format!("{} value", key),
+                confidence,
+                stability: 0.6,
+                last_seen: None,
+                source_event_id: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_decay_persona_traits() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+
+        add_trait(&manager, "old", 0.9).await;
+        add_trait(&manager, "faded", 0.3).await;
+        add_trait(&manager, "fresh", 0.8).await;
+        {
+            let db = manager.database.lock().await;
+            let DatabaseInstance::Sqlite(sqlite_db) = &*db;
+            sqlite_db
+                .execute("UPDATE persona_traits SET last_seen = datetime('now', '-90 days') WHERE trait_key IN ('old', 'faded')")
+                .await
+                .unwrap();
+        }
+
+        let now = chrono::Utc::now();
+        let stats = manager
+            .decay_persona_traits("chat", 30.0, 0.1, now)
+            .await
+            .unwrap();
+        assert_eq!(stats.pruned, 1);
+
+        let traits = manager
+            .list_persona_traits("chat", None, None)
+            .await
+            .unwrap();
+        let find = |key: &str| traits.iter().find(|t| t.trait_key == key);
+        // 三个半衰期后 0.9 -> 0.1125，0.3 -> 0.0375 低于下限被删除
+        let old = find("old").unwrap();
+        assert!((old.confidence - 0.1125).abs() < 1e-3);
+        assert!((old.stability - 0.075).abs() < 1e-3);
+        assert!(find("faded").is_none());
+        let fresh = find("fresh").unwrap();
+        assert!((fresh.confidence - 0.8).abs() < 1e-3);
+        assert!((fresh.stability - 0.6).abs() < 1e-3);
+
+        // 同一时刻再次衰减不会叠加
+        manager
+            .decay_persona_traits("chat", 30.0, 0.1, now)
+            .await
+            .unwrap();
+        let traits = manager
+            .list_persona_traits("chat", Some("preference"), None)
+            .await
+            .unwrap();
+        let old = traits.iter().find(|t| t.trait_key == "old").unwrap();
+        assert!((old.confidence - 0.1125).abs() < 1e-3);
+
+        assert!(manager
+            .decay_persona_traits("chat", 0.0, 0.1, now)
+            .await
+            .is_err());
+    }
+
+    async fn add_event(manager: &DatabaseManager, session_id: i64, text: &str) -> i64 {
+        manager
+            .insert_memory_event(MemoryEvent {
+                session_id,
+                agent_name: "chat".to_string(),
+                role: "user".to_string(),
+                text: text.to_string(),
+                topic: None,
+                sentiment: None,
+                importance: None,
+                decay: 1.0,
+                embedding: Some(vec![1, 2, 3, 4]),
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn event_texts(manager: &DatabaseManager, session_id: i64) -> Vec<String> {
+        manager
+            .list_memory_events(session_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.text)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_delete_memory_event() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+        let session_id = manager.create_session("chat", None).await.unwrap();
+        add_event(&manager, session_id, "保留").await;
+        let secret = add_event(&manager, session_id, "我的密码是123").await;
+        manager
+            .upsert_persona_trait(PersonaTrait {
+                id: None,
+                agent_name: "chat".to_string(),
+                trait_type: "attribute".to_string(),
+                trait_key: "password".to_string(),
+                trait_value: "123".to_string(),
+                confidence: 0.9,
+                stability: 0.1,
+                last_seen: None,
+                source_event_id: Some(secret),
+            })
+            .await
+            .unwrap();
+
+        assert!(manager.delete_memory_event(secret).await.unwrap());
+        assert!(!manager.delete_memory_event(secret).await.unwrap());
+        assert_eq!(event_texts(&manager, session_id).await, vec!["保留"]);
+        // 引用该事件的画像特征保留，但来源被清空
+        let traits = manager
+            .list_persona_traits("chat", None, None)
+            .await
+            .unwrap();
+        assert_eq!(traits[0].source_event_id, None);
+    }
+
+    #[tokio::test]
+    async fn test_delete_session_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+        let first = manager.create_session("chat", None).await.unwrap();
+        let second = manager.create_session("chat", None).await.unwrap();
+        add_event(&manager, first, "a").await;
+        add_event(&manager, first, "b").await;
+        add_event(&manager, second, "c").await;
+
+        assert_eq!(manager.delete_session_events(first).await.unwrap(), 2);
+        assert!(event_texts(&manager, first).await.is_empty());
+        assert_eq!(event_texts(&manager, second).await, vec!["c"]);
+    }
+
+    #[tokio::test]
+    async fn test_update_memory_event_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+        let session_id = manager.create_session("chat", None).await.unwrap();
+        let id = add_event(&manager, session_id, "我住在北京").await;
+
+        assert!(manager
+            .update_memory_event_text(id, "我住在上海")
+            .await
+            .unwrap());
+        assert!(!manager.update_memory_event_text(-1, "x").await.unwrap());
+
+        let events = manager.list_memory_events(session_id).await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].text, "我住在上海");
+        // 旧向量不再对应新文本，不能原样保留
+        assert_ne!(events[0].embedding, Some(vec![1, 2, 3, 4]));
+    }
+
+    #[tokio::test]
+    async fn test_missing_session_is_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+
+        let error = manager.get_session_title(42).await.unwrap_err();
+        assert!(matches!(error, DbError::NotFound(_)), "{:?}", error);
+        let error = manager.upsert_session_title(42, "标题").await.unwrap_err();
+        assert!(matches!(error, DbError::NotFound(_)), "{:?}", error);
+
+        let session_id = manager.create_session("chat", None).await.unwrap();
+        manager
+            .upsert_session_title(session_id, "标题")
+            .await
+            .unwrap();
+        assert_eq!(
+            manager
+                .get_session_title(session_id)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("标题")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_memory_events_for_chunk() {
+        let dir = tempfile::tempdir().unwrap();
+        let manager = create_test_manager(&dir).await;
+        let session_id = manager.create_session("chat", None).await.unwrap();
+        add_event(&manager, session_id, "无关").await;
+        let first = add_event(&manager, session_id, "我在学 Rust").await;
+        let second = add_event(&manager, session_id, "想写一个数据库").await;
+
+        let chunk_id = manager
+            .insert_semantic_chunk(SemanticChunk {
+                id: None,
+                title: None,
+                summary: "用户在学习 Rust".to_string(),
+                keywords: None,
+                embedding: None,
+                last_ref_ts: None,
+                weight: 0.7,
+            })
+            .await
+            .unwrap();
+        manager
+            .insert_semantic_chunk_mapping(crate::db::SemanticChunkMapping {
+                id: None,
+                chunk_id,
+                session_id,
+                memory_event_ids: serde_json::to_string(&[first, second]).unwrap(),
+                created_ts: None,
+            })
+            .await
+            .unwrap();
+
+        let texts: Vec<String> = manager
+            .get_memory_events_for_chunk(chunk_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.text)
+            .collect();
+        assert_eq!(texts, vec!["我在学 Rust", "想写一个数据库"]);
+        assert!(manager
+            .get_memory_events_for_chunk(chunk_id + 1)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    /// 按是否与水果相关返回确定性向量，并统计调用次数
+    struct MockEmbedder {
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl crate::db::embedding::Embedder for MockEmbedder {
+        async fn encode(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    if text.contains("apple") || text.contains("fruit") {
+                        vec![1.0, 0.0]
+                    } else {
+                        vec![0.0, 1.0]
+                    }
+                })
+                .collect())
+        }
+
+        fn dimension(&self) -> usize {
+            2
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_embedder_is_used_for_storage_and_retrieval() {
+        let dir = tempfile::tempdir().unwrap();
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let embedder = MockEmbedder {
+            calls: calls.clone(),
+        };
+        let manager = create_test_manager(&dir)
+            .await
+            .with_embedding_service(EmbeddingService::with_embedder("mock", Box::new(embedder)));
+        assert_eq!(
+            manager
+                .embedding_service()
+                .unwrap()
+                .lock()
+                .await
+                .embedding_dim(),
+            2
+        );
+
+        let session_id = manager.open_session("chat", None).await.unwrap();
+        let mut ids = Vec::new();
+        // 先写入无关事件：若退回词重叠度，它会因顺序在前而排第一
+        for text in ["my car broke down", "I baked a pie"] {
+            let event = MemoryEvent {
+                session_id,
+                agent_name: "chat".to_string(),
+                role: "user".to_string(),
+                text: text.to_string(),
+                topic: None,
+                sentiment: None,
+                importance: None,
+                decay: 1.0,
+                embedding: None,
+            };
+            ids.push(manager.insert_memory_event(event).await.unwrap());
+        }
+        assert!(manager
+            .update_memory_event_text(ids[1], "I baked an apple pie")
+            .await
+            .unwrap());
+
+        let events = manager.list_memory_events(session_id).await.unwrap();
+        let stored = events
+            .iter()
+            .find(|e| e.text == "I baked an apple pie")
+            .and_then(|e| e.embedding.as_ref())
+            .unwrap();
+        assert_eq!(
+            EmbeddingService::deserialize_embedding(stored).unwrap(),
+            vec![1.0, 0.0]
+        );
+
+        let calls_before = calls.load(std::sync::atomic::Ordering::SeqCst);
+        let context = manager
+            .retrieve_memories("chat", "fruit", 1, 24.0, 0.0, 1000)
+            .await
+            .unwrap();
+        assert!(context.contains("apple pie"));
+        assert!(!context.contains("car"));
+        assert_eq!(
+            calls.load(std::sync::atomic::Ordering::SeqCst),
+            calls_before + 1
+        );
+    }
+}
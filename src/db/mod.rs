@@ -3,6 +3,8 @@
 
 pub mod config;
 pub mod embedding;
+pub mod error;
+pub mod graph_export;
 pub mod manager;
 pub mod performance;
 pub mod query_optimizer;
@@ -10,13 +12,15 @@ pub mod sqlite;
 
 // 重新导出主要类型
 pub use config::{DatabaseConfig, DatabaseType};
+pub use error::DbError;
+pub use graph_export::GraphExportFormat;
 pub use manager::DatabaseManager;
 pub use performance::*;
 pub use query_optimizer::*;
 pub use sqlite::SqliteDatabase;
 
 /// 数据库操作结果类型
-pub type DbResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+pub type DbResult<T> = Result<T, DbError>;
 
 /// 记忆事件（替代 conversations 表的最小可用记录）
 #[derive(Debug, Clone)]
@@ -72,6 +76,39 @@ pub struct GraphEdge {
     pub weight: f32,           // 关系权重
 }
 
+/// 查询节点关系时的边方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeDirection {
+    /// 从该节点出发的边
+    Outgoing,
+    /// 指向该节点的边
+    Incoming,
+    /// 两个方向
+    Both,
+}
+
+/// WAL 检查点模式（对应 `PRAGMA wal_checkpoint`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckpointMode {
+    /// 不等待读写，尽可能写回
+    Passive,
+    /// 等待写入结束后全部写回
+    Full,
+    /// 全部写回并将 WAL 文件截断为 0
+    Truncate,
+}
+
+impl CheckpointMode {
+    /// 对应的 PRAGMA 参数
+    pub fn as_pragma_arg(&self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
+
 /// 用户画像配置文件（聚合信息）
 #[derive(Debug, Clone)]
 pub struct PersonaProfile {
@@ -95,6 +132,15 @@ pub struct PersonaTrait {
     pub source_event_id: Option<i64>, // 来源 memory_events.id
 }
 
+/// 画像特征衰减结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PersonaDecayStats {
+    /// 被衰减的特征数量
+    pub decayed: usize,
+    /// 置信度低于下限而被删除的特征数量
+    pub pruned: usize,
+}
+
 /// 数据库操作trait
 #[async_trait::async_trait]
 pub trait Database: Send + Sync + std::fmt::Debug {
@@ -119,26 +165,67 @@ pub trait Database: Send + Sync + std::fmt::Debug {
         Err("Backup not supported".into())
     }
 
+    /// WAL 检查点
+    async fn checkpoint(&self, mode: CheckpointMode) -> DbResult<()> {
+        let _ = mode; // 默认空实现
+        Ok(())
+    }
+
+    /// 优化数据库，vacuum 为 true 时同时整理碎片
+    async fn optimize(&self, vacuum: bool) -> DbResult<()> {
+        let _ = vacuum; // 默认空实现
+        Ok(())
+    }
+
     /// 转换为SQLite数据库实例（仅限SQLite实现）
     async fn as_sqlite(&self) -> DbResult<Option<&dyn std::any::Any>> {
         Ok(None)
     }
 
     // 会话管理（单用户，多Agent）
+    /// 打开新会话并关闭其他活跃会话（仅用于自动解析会话的默认路径）
     async fn open_session(&self, agent_name: &str, title: Option<&str>) -> DbResult<i64>;
+    /// 新建会话但不改变其他会话的活跃状态，供调用方显式指定会话
+    async fn create_session(&self, agent_name: &str, title: Option<&str>) -> DbResult<i64>;
     async fn close_active_session(&self) -> DbResult<()>;
     async fn get_active_session(&self) -> DbResult<Option<i64>>;
+    /// 修改会话标题，会话不存在时返回 [`DbError::NotFound`]
     async fn upsert_session_title(&self, session_id: i64, title: &str) -> DbResult<()>;
+    /// 获取会话标题，会话不存在（或不属于当前命名空间）时返回 [`DbError::NotFound`]
+    async fn get_session_title(&self, session_id: i64) -> DbResult<Option<String>>;
 
     // 记忆事件（替代原 save_conversation）
     async fn insert_memory_event(&self, event: MemoryEvent) -> DbResult<i64>;
     async fn list_memory_events(&self, session_id: i64) -> DbResult<Vec<MemoryEvent>>;
+    /// 列出会话中尚未被任何语义片段映射引用的记忆事件（附带事件ID），按时间升序
+    async fn list_unsummarized_memory_events(
+        &self,
+        session_id: i64,
+    ) -> DbResult<Vec<(i64, MemoryEvent)>>;
     async fn clear_all_memory_events(&self) -> DbResult<()>; // 清理所有记忆事件（调试用）
+    /// 删除单条记忆事件，返回是否存在
+    async fn delete_memory_event(&self, id: i64) -> DbResult<bool>;
+    /// 删除会话下的全部记忆事件，返回删除条数
+    async fn delete_session_events(&self, session_id: i64) -> DbResult<u64>;
+    /// 修改记忆事件文本并替换其向量（None 表示清除旧向量），返回是否存在
+    async fn update_memory_event_text(
+        &self,
+        id: i64,
+        new_text: &str,
+        embedding: Option<Vec<u8>>,
+    ) -> DbResult<bool>;
 
     // 阶段3: 长期语义片段与图谱
     async fn insert_semantic_chunk(&self, chunk: SemanticChunk) -> DbResult<i64>;
     async fn list_semantic_chunks(&self, limit: Option<i32>) -> DbResult<Vec<SemanticChunk>>;
     async fn update_semantic_chunk_ref_time(&self, chunk_id: i64) -> DbResult<()>;
+    /// 删除权重低于 `min_weight` 或（给定 `older_than` 时）在该时间之后未再被引用的语义片段，
+    /// 同时删除它们的映射，返回删除的片段数
+    async fn prune_semantic_chunks(
+        &self,
+        min_weight: f32,
+        older_than: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> DbResult<u64>;
 
     // 语义片段映射管理
     async fn insert_semantic_chunk_mapping(&self, mapping: SemanticChunkMapping) -> DbResult<i64>;
@@ -150,6 +237,8 @@ pub trait Database: Send + Sync + std::fmt::Debug {
         &self,
         session_id: i64,
     ) -> DbResult<Vec<SemanticChunkMapping>>;
+    /// 按映射中记录的事件ID取回语义片段的来源记忆事件，按时间升序
+    async fn get_memory_events_for_chunk(&self, chunk_id: i64) -> DbResult<Vec<MemoryEvent>>;
 
     // 图谱管理
     async fn upsert_graph_node(&self, node: GraphNode) -> DbResult<i64>;
@@ -158,11 +247,29 @@ pub trait Database: Send + Sync + std::fmt::Debug {
     async fn upsert_graph_edge_with_accumulation(&self, edge: GraphEdge) -> DbResult<i64>;
     async fn get_graph_nodes(&self) -> DbResult<Vec<GraphNode>>;
     async fn get_graph_edges(&self) -> DbResult<Vec<GraphEdge>>;
+    /// 获取与节点相连的边，按权重降序
+    async fn get_edges_for_node(
+        &self,
+        node_id: i64,
+        direction: EdgeDirection,
+    ) -> DbResult<Vec<GraphEdge>>;
+    /// 获取权重最高的 `top_k` 个邻居节点（两个方向），每个邻居附带权重最高的那条边
+    async fn get_neighbors(
+        &self,
+        node_id: i64,
+        top_k: usize,
+    ) -> DbResult<Vec<(GraphNode, GraphEdge)>>;
     async fn get_node_by_entity(
         &self,
         entity_type: &str,
         entity_name: &str,
     ) -> DbResult<Option<GraphNode>>;
+    /// 导出整个图谱（GraphML 或 DOT），节点带 entity_type/entity_name，边带 relation_type/weight
+    async fn export_graph(&self, format: GraphExportFormat) -> DbResult<String> {
+        let nodes = self.get_graph_nodes().await?;
+        let edges = self.get_graph_edges().await?;
+        Ok(graph_export::render_graph(&nodes, &edges, format))
+    }
 
     // 阶段5: 画像/Persona 管理
     async fn upsert_persona_profile(&self, profile: PersonaProfile) -> DbResult<i64>;
@@ -180,4 +287,12 @@ pub trait Database: Send + Sync + std::fmt::Debug {
         query: &str,
         top_k: usize,
     ) -> DbResult<Vec<PersonaTrait>>;
+    /// 按半衰期衰减画像特征的 confidence/stability，并删除置信度低于 min_confidence 的特征
+    async fn decay_persona_traits(
+        &self,
+        agent_name: &str,
+        half_life_days: f32,
+        min_confidence: f32,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> DbResult<PersonaDecayStats>;
 }
@@ -743,8 +743,12 @@ mod tests {
     use tempfile::NamedTempFile;
 
     async fn setup_test_db() -> SqlitePool {
-        let temp_file = NamedTempFile::new().unwrap();
-        let database_url = format!("sqlite:{}", temp_file.path().to_str().unwrap());
+        let db_path = NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let database_url = format!("sqlite:{}", db_path.to_str().unwrap());
 
         let pool = SqlitePool::connect(&database_url).await.unwrap();
 
@@ -1,17 +1,62 @@
 //! SQLite数据库实现 - 使用 sqlx
 
 use super::config::DatabaseConfig;
-use super::{Database, DbResult, MemoryEvent};
+use super::error::{sqlite_primary_code, SQLITE_BUSY, SQLITE_LOCKED};
+use super::{Database, DbError, DbResult, MemoryEvent};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
 use sqlx::Row;
-use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, SqlitePool};
+use sqlx::{ConnectOptions, SqlitePool};
+use std::future::Future;
 use std::str::FromStr;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+/// 判断是否为可重试的瞬时错误：SQLITE_BUSY / SQLITE_LOCKED（含扩展码）及连接池获取超时
+fn is_transient_error(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(db_error) => sqlite_primary_code(db_error.as_ref())
+            .is_some_and(|code| matches!(code, SQLITE_BUSY | SQLITE_LOCKED)),
+        sqlx::Error::PoolTimedOut => true,
+        _ => false,
+    }
+}
+
+/// 执行 `op`，遇到瞬时错误时按指数退避并附加随机抖动重试，最多重试 `max_retries` 次
+async fn retry_with_backoff<T, F, Fut>(
+    max_retries: u32,
+    backoff_ms: u64,
+    mut op: F,
+) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(e) if attempt < max_retries && is_transient_error(&e) => {
+                let delay = backoff_ms.saturating_mul(1 << attempt.min(16));
+                let jitter = fastrand::u64(0..=delay / 2);
+                attempt += 1;
+                log::debug!(
+                    "Transient database error, retry {}/{} in {}ms: {}",
+                    attempt,
+                    max_retries,
+                    delay + jitter,
+                    e
+                );
+                tokio::time::sleep(Duration::from_millis(delay + jitter)).await;
+            }
+            result => return result,
+        }
+    }
+}
 
 /// SQLite数据库实现
 #[derive(Debug, Clone)]
 pub struct SqliteDatabase {
-    pool: Arc<Mutex<Option<SqlitePool>>>,
+    pool: Arc<OnceCell<SqlitePool>>,
     config: DatabaseConfig,
 }
 
@@ -19,29 +64,51 @@ impl SqliteDatabase {
     /// 创建新的SQLite数据库实例
     pub fn new(config: DatabaseConfig) -> DbResult<Self> {
         Ok(Self {
-            pool: Arc::new(Mutex::new(None)),
+            pool: Arc::new(OnceCell::new()),
             config,
         })
     }
 
-    /// 获取数据库连接池
+    /// 获取数据库连接池（首次调用时创建，之后无锁复用）
     async fn get_pool(&self) -> DbResult<SqlitePool> {
-        let mut pool_guard = self.pool.lock().await;
+        let pool = self.pool.get_or_try_init(|| self.connect()).await?;
+        Ok(pool.clone())
+    }
 
-        if let Some(ref pool) = *pool_guard {
-            return Ok(pool.clone());
+    /// 创建共享同一连接池、但只访问指定命名空间数据的实例
+    pub fn with_namespace<S: Into<String>>(&self, namespace: S) -> Self {
+        Self {
+            pool: self.pool.clone(),
+            config: self.config.clone().with_namespace(namespace),
         }
+    }
+
+    /// 当前命名空间，默认命名空间为空字符串
+    fn namespace(&self) -> &str {
+        self.config.namespace.as_deref().unwrap_or("")
+    }
+
+    /// 执行写操作，瞬时错误按配置的次数与退避时间重试
+    async fn with_retry<T, F, Fut>(&self, op: F) -> Result<T, sqlx::Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, sqlx::Error>>,
+    {
+        retry_with_backoff(self.config.max_retries, self.config.retry_backoff_ms, op).await
+    }
 
+    /// 按配置创建连接池
+    async fn connect(&self) -> DbResult<SqlitePool> {
         // 确保数据目录存在
         if let Some(parent) = self.config.db_path.parent() {
             std::fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create directory: {}", e))?;
+                .map_err(|e| DbError::with_context("Failed to create directory", e))?;
         }
 
         // 配置 SQLite 连接选项，启用调试模式
         let options =
             SqliteConnectOptions::from_str(&format!("sqlite:{}", self.config.db_path.display()))
-                .map_err(|e| format!("Failed to parse database URL: {}", e))?
+                .map_err(|e| DbError::with_context("Failed to parse database URL", e))?
                 .create_if_missing(true)
                 .pragma("journal_mode", "WAL")
                 .pragma("synchronous", "NORMAL")
@@ -50,11 +117,18 @@ impl SqliteDatabase {
                 .pragma("temp_store", "MEMORY")
                 .log_statements(log::LevelFilter::Debug); // 启用 SQL 语句调试日志
 
-        let pool = SqlitePool::connect_with(options)
+        let idle_timeout = match self.config.idle_timeout_secs {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        let pool = SqlitePoolOptions::new()
+            .max_connections(self.config.max_connections.max(1))
+            .acquire_timeout(Duration::from_secs(self.config.acquire_timeout_secs))
+            .idle_timeout(idle_timeout)
+            .connect_with(options)
             .await
-            .map_err(|e| format!("Failed to connect to database: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to connect to database", e))?;
 
-        *pool_guard = Some(pool.clone());
         Ok(pool)
     }
 
@@ -67,7 +141,7 @@ impl SqliteDatabase {
         let result = sqlx::query(sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to execute SQL: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to execute SQL", e))?;
 
         log::debug!(
             "SQL execution result: {} rows affected",
@@ -86,7 +160,7 @@ impl SqliteDatabase {
         let rows = sqlx::query(sql)
             .fetch_all(&pool)
             .await
-            .map_err(|e| format!("Failed to query: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to query", e))?;
 
         log::debug!("Query returned {} rows", rows.len());
 
@@ -108,30 +182,31 @@ impl SqliteDatabase {
                 started_at DATETIME DEFAULT CURRENT_TIMESTAMP,
                 ended_at DATETIME,
                 is_active BOOLEAN NOT NULL DEFAULT 1,
-                metadata TEXT
+                metadata TEXT,
+                namespace TEXT NOT NULL DEFAULT ''
             )
         "#;
         log::debug!("Creating sessions table...");
         sqlx::query(create_sessions_sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create sessions table: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create sessions table", e))?;
 
         // 会话表索引
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_agent_name ON sessions(agent_name)")
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create sessions agent_name index: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create sessions agent_name index", e))?;
 
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_started_at ON sessions(started_at)")
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create sessions started_at index: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create sessions started_at index", e))?;
 
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_sessions_is_active ON sessions(is_active)")
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create sessions is_active index: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create sessions is_active index", e))?;
 
         // 阶段2: 记忆事件表（替代conversations）
         let create_memory_events_sql = r#"
@@ -147,6 +222,7 @@ impl SqliteDatabase {
                 importance REAL,
                 decay REAL DEFAULT 1.0,
                 embedding BLOB,
+                namespace TEXT NOT NULL DEFAULT '',
                 FOREIGN KEY (session_id) REFERENCES sessions(id)
             )
         "#;
@@ -154,7 +230,7 @@ impl SqliteDatabase {
         sqlx::query(create_memory_events_sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create memory_events table: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create memory_events table", e))?;
 
         // 记忆事件表索引
         sqlx::query(
@@ -162,19 +238,19 @@ impl SqliteDatabase {
         )
         .execute(&pool)
         .await
-        .map_err(|e| format!("Failed to create memory_events session index: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to create memory_events session index", e))?;
 
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_memory_events_ts ON memory_events(ts)")
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create memory_events ts index: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create memory_events ts index", e))?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_memory_events_agent ON memory_events(agent_name)",
         )
         .execute(&pool)
         .await
-        .map_err(|e| format!("Failed to create memory_events agent index: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to create memory_events agent index", e))?;
 
         // 阶段3: 语义片段与图谱
         let create_semantic_chunks_sql = r#"
@@ -185,21 +261,22 @@ impl SqliteDatabase {
                 keywords TEXT,
                 embedding BLOB,
                 last_ref_ts DATETIME,
-                weight REAL DEFAULT 1.0
+                weight REAL DEFAULT 1.0,
+                namespace TEXT NOT NULL DEFAULT ''
             )
         "#;
         log::debug!("Creating semantic_chunks table...");
         sqlx::query(create_semantic_chunks_sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create semantic_chunks table: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create semantic_chunks table", e))?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_semantic_chunks_weight ON semantic_chunks(weight)",
         )
         .execute(&pool)
         .await
-        .map_err(|e| format!("Failed to create semantic_chunks weight index: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to create semantic_chunks weight index", e))?;
 
         let create_graph_nodes_sql = r#"
             CREATE TABLE IF NOT EXISTS graph_nodes (
@@ -212,12 +289,12 @@ impl SqliteDatabase {
         sqlx::query(create_graph_nodes_sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create graph_nodes table: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create graph_nodes table", e))?;
 
         sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_graph_nodes_unique ON graph_nodes(entity_type, entity_name)")
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create graph_nodes unique index: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create graph_nodes unique index", e))?;
 
         let create_graph_edges_sql = r#"
             CREATE TABLE IF NOT EXISTS graph_edges (
@@ -234,14 +311,55 @@ impl SqliteDatabase {
         sqlx::query(create_graph_edges_sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create graph_edges table: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create graph_edges table", e))?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_graph_edges_from_to ON graph_edges(from_node, to_node)",
         )
         .execute(&pool)
         .await
-        .map_err(|e| format!("Failed to create graph_edges from_to index: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to create graph_edges from_to index", e))?;
+
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_graph_edges_to ON graph_edges(to_node)")
+            .execute(&pool)
+            .await
+            .map_err(|e| DbError::with_context("Failed to create graph_edges to index", e))?;
+
+        // 旧库可能存在重复边：权重合并到最早的一条后删除其余，再建立唯一索引
+        sqlx::query(
+            r#"
+            UPDATE graph_edges SET weight = (
+                SELECT SUM(COALESCE(g.weight, 0)) FROM graph_edges g
+                WHERE g.from_node = graph_edges.from_node
+                  AND g.to_node = graph_edges.to_node
+                  AND g.relation_type = graph_edges.relation_type
+            )
+            WHERE id IN (
+                SELECT MIN(id) FROM graph_edges
+                GROUP BY from_node, to_node, relation_type
+                HAVING COUNT(*) > 1
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| DbError::with_context("Failed to merge duplicate graph_edges", e))?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM graph_edges WHERE id NOT IN (
+                SELECT MIN(id) FROM graph_edges GROUP BY from_node, to_node, relation_type
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| DbError::with_context("Failed to delete duplicate graph_edges", e))?;
+
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_graph_edges_unique ON graph_edges(from_node, to_node, relation_type)")
+            .execute(&pool)
+            .await
+            .map_err(|e| DbError::with_context("Failed to create graph_edges unique index", e))?;
 
         // 语义片段映射表
         let create_semantic_chunk_mappings_sql = r#"
@@ -259,17 +377,19 @@ impl SqliteDatabase {
         sqlx::query(create_semantic_chunk_mappings_sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create semantic_chunk_mappings table: {}", e))?;
+            .map_err(|e| {
+                DbError::with_context("Failed to create semantic_chunk_mappings table", e)
+            })?;
 
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_semantic_chunk_mappings_chunk_id ON semantic_chunk_mappings(chunk_id)")
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create semantic_chunk_mappings chunk_id index: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create semantic_chunk_mappings chunk_id index", e))?;
 
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_semantic_chunk_mappings_session_id ON semantic_chunk_mappings(session_id)")
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create semantic_chunk_mappings session_id index: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create semantic_chunk_mappings session_id index", e))?;
 
         // 阶段5: 画像/Persona 表（正式路径，单用户多Agent设计）
         let create_persona_profiles_sql = r#"
@@ -284,61 +404,168 @@ impl SqliteDatabase {
         sqlx::query(create_persona_profiles_sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create persona_profiles table: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create persona_profiles table", e))?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_persona_profiles_agent ON persona_profiles(agent_name)",
         )
         .execute(&pool)
         .await
-        .map_err(|e| format!("Failed to create persona_profiles agent index: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to create persona_profiles agent index", e))?;
 
-        let create_persona_traits_sql = r#"
-            CREATE TABLE IF NOT EXISTS persona_traits (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                agent_name TEXT NOT NULL,
-                trait_type TEXT,
-                trait_key TEXT,
-                trait_value TEXT,
-                confidence REAL DEFAULT 0.5,
-                stability REAL DEFAULT 0.0,
-                last_seen DATETIME,
-                source_event_id INTEGER,
-                UNIQUE(agent_name, trait_type, trait_key),
-                FOREIGN KEY(source_event_id) REFERENCES memory_events(id)
-            )
-        "#;
         log::debug!("Creating persona_traits table...");
-        sqlx::query(create_persona_traits_sql)
+        sqlx::query(&Self::persona_traits_schema("persona_traits"))
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to create persona_traits table: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create persona_traits table", e))?;
+
+        // 旧数据库迁移：补充 last_decayed 与命名空间列
+        Self::ensure_column(&pool, "persona_traits", "last_decayed", "DATETIME").await?;
+        Self::migrate_persona_traits_namespace(&pool).await?;
+        Self::ensure_column(
+            &pool,
+            "semantic_chunks",
+            "namespace",
+            "TEXT NOT NULL DEFAULT ''",
+        )
+        .await?;
+        Self::ensure_column(&pool, "sessions", "namespace", "TEXT NOT NULL DEFAULT ''").await?;
+        Self::ensure_column(
+            &pool,
+            "memory_events",
+            "namespace",
+            "TEXT NOT NULL DEFAULT ''",
+        )
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_sessions_namespace ON sessions(namespace, is_active)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| DbError::with_context("Failed to create sessions namespace index", e))?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_memory_events_namespace ON memory_events(namespace)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| DbError::with_context("Failed to create memory_events namespace index", e))?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_semantic_chunks_namespace ON semantic_chunks(namespace)",
+        )
+        .execute(&pool)
+        .await
+        .map_err(|e| DbError::with_context("Failed to create semantic_chunks namespace index", e))?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_persona_traits_agent ON persona_traits(agent_name)",
         )
         .execute(&pool)
         .await
-        .map_err(|e| format!("Failed to create persona_traits agent index: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to create persona_traits agent index", e))?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_persona_traits_type ON persona_traits(trait_type)",
         )
         .execute(&pool)
         .await
-        .map_err(|e| format!("Failed to create persona_traits type index: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to create persona_traits type index", e))?;
 
         sqlx::query(
             "CREATE INDEX IF NOT EXISTS idx_persona_traits_last_seen ON persona_traits(last_seen)",
         )
         .execute(&pool)
         .await
-        .map_err(|e| format!("Failed to create persona_traits last_seen index: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to create persona_traits last_seen index", e))?;
 
         log::info!("Database tables created successfully");
 
         Ok(())
     }
+
+    /// 表中缺少指定列时追加该列（用于旧数据库迁移）
+    async fn ensure_column(
+        pool: &SqlitePool,
+        table: &str,
+        column: &str,
+        definition: &str,
+    ) -> DbResult<()> {
+        if !Self::has_column(pool, table, column).await? {
+            sqlx::query(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))
+            .execute(pool)
+            .await
+            .map_err(|e| DbError::with_context(format!("Failed to add {}.{}", table, column), e))?;
+        }
+        Ok(())
+    }
+
+    async fn has_column(pool: &SqlitePool, table: &str, column: &str) -> DbResult<bool> {
+        let row = sqlx::query("SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2")
+            .bind(table)
+            .bind(column)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| {
+                DbError::with_context(format!("Failed to inspect {} columns", table), e)
+            })?;
+        Ok(row.is_some())
+    }
+
+    /// persona_traits 建表语句，特征在同一命名空间内按 (智能体, 类型, 键) 唯一
+    fn persona_traits_schema(table: &str) -> String {
+        format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {} (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                agent_name TEXT NOT NULL,
+                trait_type TEXT,
+                trait_key TEXT,
+                trait_value TEXT,
+                confidence REAL DEFAULT 0.5,
+                stability REAL DEFAULT 0.0,
+                last_seen DATETIME,
+                last_decayed DATETIME,
+                source_event_id INTEGER,
+                namespace TEXT NOT NULL DEFAULT '',
+                UNIQUE(namespace, agent_name, trait_type, trait_key),
+                FOREIGN KEY(source_event_id) REFERENCES memory_events(id)
+            )
+        "#,
+            table
+        )
+    }
+
+    /// 旧版 persona_traits 的唯一约束不含命名空间，无法用 ALTER TABLE 修改，
+    /// 只能按新结构重建表并拷回数据（原有特征归入默认命名空间）
+    async fn migrate_persona_traits_namespace(pool: &SqlitePool) -> DbResult<()> {
+        if Self::has_column(pool, "persona_traits", "namespace").await? {
+            return Ok(());
+        }
+        log::info!("Migrating persona_traits to namespaced unique key...");
+        let mut tx = pool.begin().await?;
+        sqlx::query(&Self::persona_traits_schema("persona_traits_migrated"))
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO persona_traits_migrated \
+                (id, agent_name, trait_type, trait_key, trait_value, confidence, stability, last_seen, last_decayed, source_event_id) \
+             SELECT id, agent_name, trait_type, trait_key, trait_value, confidence, stability, last_seen, last_decayed, source_event_id \
+             FROM persona_traits",
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DROP TABLE persona_traits")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("ALTER TABLE persona_traits_migrated RENAME TO persona_traits")
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| DbError::with_context("Failed to migrate persona_traits", e))?;
+        tx.commit().await?;
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -366,8 +593,7 @@ impl Database for SqliteDatabase {
     async fn close(&mut self) -> DbResult<()> {
         log::info!("Closing database connection...");
 
-        let mut pool_guard = self.pool.lock().await;
-        if let Some(pool) = pool_guard.take() {
+        if let Some(pool) = self.pool.get() {
             pool.close().await;
             log::info!("Database connection closed");
         }
@@ -378,59 +604,109 @@ impl Database for SqliteDatabase {
     // 阶段1: 单用户会话管理（移除user_id，新增agent_name参数）
     async fn open_session(&self, agent_name: &str, title: Option<&str>) -> DbResult<i64> {
         let pool = self.get_pool().await?;
-        // 关闭已有活跃会话（单用户，所以关闭所有活跃会话）
-        sqlx::query("UPDATE sessions SET is_active=0, ended_at=datetime('now') WHERE is_active=1")
+        // 关闭已有活跃会话（单用户，所以关闭命名空间内所有活跃会话）
+        self.with_retry(|| {
+            sqlx::query(
+                "UPDATE sessions SET is_active=0, ended_at=datetime('now') WHERE is_active=1 AND namespace=?1",
+            )
+            .bind(self.namespace())
             .execute(&pool)
-            .await
-            .map_err(|e| format!("Failed to close previous active session: {}", e))?;
+        })
+        .await
+        .map_err(|e| DbError::with_context("Failed to close previous active session", e))?;
         // 新建会话
-        let res = sqlx::query("INSERT INTO sessions (agent_name, title, started_at, is_active) VALUES (?1, ?2, datetime('now'), 1)")
-            .bind(agent_name)
-            .bind(title.unwrap_or(""))
-            .execute(&pool)
+        let res = self
+            .with_retry(|| {
+                sqlx::query("INSERT INTO sessions (agent_name, title, started_at, is_active, namespace) VALUES (?1, ?2, datetime('now'), 1, ?3)")
+                    .bind(agent_name)
+                    .bind(title.unwrap_or(""))
+                    .bind(self.namespace())
+                    .execute(&pool)
+            })
+            .await
+            .map_err(|e| DbError::with_context("Failed to open session", e))?;
+        Ok(res.last_insert_rowid())
+    }
+
+    async fn create_session(&self, agent_name: &str, title: Option<&str>) -> DbResult<i64> {
+        let pool = self.get_pool().await?;
+        // 显式会话不参与活跃会话的自动解析，也不会关闭其他会话
+        let res = self
+            .with_retry(|| {
+                sqlx::query("INSERT INTO sessions (agent_name, title, started_at, is_active, namespace) VALUES (?1, ?2, datetime('now'), 0, ?3)")
+                    .bind(agent_name)
+                    .bind(title.unwrap_or(""))
+                    .bind(self.namespace())
+                    .execute(&pool)
+            })
             .await
-            .map_err(|e| format!("Failed to open session: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to create session", e))?;
         Ok(res.last_insert_rowid())
     }
 
     async fn close_active_session(&self) -> DbResult<()> {
         let pool = self.get_pool().await?;
-        sqlx::query("UPDATE sessions SET is_active=0, ended_at=datetime('now') WHERE is_active=1")
+        self.with_retry(|| {
+            sqlx::query(
+                "UPDATE sessions SET is_active=0, ended_at=datetime('now') WHERE is_active=1 AND namespace=?1",
+            )
+            .bind(self.namespace())
             .execute(&pool)
-            .await
-            .map_err(|e| format!("Failed to close active session: {}", e))?;
+        })
+        .await
+        .map_err(|e| DbError::with_context("Failed to close active session", e))?;
         Ok(())
     }
 
     async fn get_active_session(&self) -> DbResult<Option<i64>> {
         let pool = self.get_pool().await?;
         let row = sqlx::query(
-            "SELECT id FROM sessions WHERE is_active=1 ORDER BY started_at DESC LIMIT 1",
+            "SELECT id FROM sessions WHERE is_active=1 AND namespace=?1 ORDER BY started_at DESC LIMIT 1",
         )
+        .bind(self.namespace())
         .fetch_optional(&pool)
         .await
-        .map_err(|e| format!("Failed to get active session: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to get active session", e))?;
         Ok(row.map(|r| r.get::<i64, _>("id")))
     }
 
     async fn upsert_session_title(&self, session_id: i64, title: &str) -> DbResult<()> {
         let pool = self.get_pool().await?;
-        sqlx::query("UPDATE sessions SET title=?1 WHERE id=?2")
-            .bind(title)
-            .bind(session_id)
-            .execute(&pool)
+        let result = self
+            .with_retry(|| {
+                sqlx::query("UPDATE sessions SET title=?1 WHERE id=?2 AND namespace=?3")
+                    .bind(title)
+                    .bind(session_id)
+                    .bind(self.namespace())
+                    .execute(&pool)
+            })
             .await
-            .map_err(|e| format!("Failed to update session title: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to update session title", e))?;
+        if result.rows_affected() == 0 {
+            return Err(DbError::not_found(format!("session {}", session_id)));
+        }
         Ok(())
     }
 
+    async fn get_session_title(&self, session_id: i64) -> DbResult<Option<String>> {
+        let pool = self.get_pool().await?;
+        let row = sqlx::query("SELECT title FROM sessions WHERE id=?1 AND namespace=?2")
+            .bind(session_id)
+            .bind(self.namespace())
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| DbError::with_context("Failed to get session title", e))?
+            .ok_or_else(|| DbError::not_found(format!("session {}", session_id)))?;
+        Ok(row.get::<Option<String>, _>("title"))
+    }
+
     // 阶段2: 记忆事件管理（替代save_conversation）
     async fn insert_memory_event(&self, event: MemoryEvent) -> DbResult<i64> {
         let pool = self.get_pool().await?;
 
         let sql = r#"
-            INSERT INTO memory_events (session_id, agent_name, role, text, topic, sentiment, importance, decay, embedding, ts)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, datetime('now'))
+            INSERT INTO memory_events (session_id, agent_name, role, text, topic, sentiment, importance, decay, embedding, namespace, ts)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, datetime('now'))
         "#;
 
         log::debug!(
@@ -439,19 +715,23 @@ impl Database for SqliteDatabase {
             event.role
         );
 
-        let result = sqlx::query(sql)
-            .bind(event.session_id)
-            .bind(&event.agent_name)
-            .bind(&event.role)
-            .bind(&event.text)
-            .bind(&event.topic)
-            .bind(event.sentiment)
-            .bind(event.importance)
-            .bind(event.decay)
-            .bind(&event.embedding)
-            .execute(&pool)
+        let result = self
+            .with_retry(|| {
+                sqlx::query(sql)
+                    .bind(event.session_id)
+                    .bind(&event.agent_name)
+                    .bind(&event.role)
+                    .bind(&event.text)
+                    .bind(&event.topic)
+                    .bind(event.sentiment)
+                    .bind(event.importance)
+                    .bind(event.decay)
+                    .bind(&event.embedding)
+                    .bind(self.namespace())
+                    .execute(&pool)
+            })
             .await
-            .map_err(|e| format!("Failed to insert memory event: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to insert memory event", e))?;
 
         log::info!(
             "Memory event saved successfully, ID: {}",
@@ -467,13 +747,14 @@ impl Database for SqliteDatabase {
         let rows = sqlx::query(r#"
             SELECT session_id, agent_name, role, text, topic, sentiment, importance, decay, embedding
             FROM memory_events 
-            WHERE session_id = ?1 
+            WHERE session_id = ?1 AND namespace = ?2
             ORDER BY ts ASC
         "#)
             .bind(session_id)
+            .bind(self.namespace())
             .fetch_all(&pool)
             .await
-            .map_err(|e| format!("Failed to list memory events: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to list memory events", e))?;
 
         let mut events = Vec::new();
         for row in rows {
@@ -493,34 +774,184 @@ impl Database for SqliteDatabase {
         Ok(events)
     }
 
-    async fn clear_all_memory_events(&self) -> DbResult<()> {
+    async fn list_unsummarized_memory_events(
+        &self,
+        session_id: i64,
+    ) -> DbResult<Vec<(i64, MemoryEvent)>> {
         let pool = self.get_pool().await?;
 
-        sqlx::query("DELETE FROM memory_events")
-            .execute(&pool)
+        // memory_event_ids 为 JSON 数组，借助 json_each 排除已被映射引用的事件
+        let rows = sqlx::query(r#"
+            SELECT id, session_id, agent_name, role, text, topic, sentiment, importance, decay, embedding
+            FROM memory_events e
+            WHERE e.session_id = ?1
+              AND e.namespace = ?2
+              AND NOT EXISTS (
+                  SELECT 1 FROM semantic_chunk_mappings m, json_each(m.memory_event_ids) j
+                  WHERE m.session_id = ?1 AND j.value = e.id
+              )
+            ORDER BY ts ASC, id ASC
+        "#)
+            .bind(session_id)
+            .bind(self.namespace())
+            .fetch_all(&pool)
             .await
-            .map_err(|e| format!("Failed to clear all memory events: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to list unsummarized memory events", e))?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            events.push((
+                row.get("id"),
+                MemoryEvent {
+                    session_id: row.get("session_id"),
+                    agent_name: row.get("agent_name"),
+                    role: row.get("role"),
+                    text: row.get("text"),
+                    topic: row.get("topic"),
+                    sentiment: row.get("sentiment"),
+                    importance: row.get("importance"),
+                    decay: row.get("decay"),
+                    embedding: row.get("embedding"),
+                },
+            ));
+        }
+
+        Ok(events)
+    }
+
+    async fn clear_all_memory_events(&self) -> DbResult<()> {
+        let pool = self.get_pool().await?;
+
+        self.with_retry(|| {
+            sqlx::query("DELETE FROM memory_events WHERE namespace = ?1")
+                .bind(self.namespace())
+                .execute(&pool)
+        })
+        .await
+        .map_err(|e| DbError::with_context("Failed to clear all memory events", e))?;
 
         log::info!("Cleared all memory events from database");
         Ok(())
     }
 
+    async fn delete_memory_event(&self, id: i64) -> DbResult<bool> {
+        let pool = self.get_pool().await?;
+        let namespace = self.namespace();
+
+        // 整个事务作为一次尝试重试
+        let deleted = self
+            .with_retry(|| {
+                let pool = &pool;
+                async move {
+                    let mut tx = pool.begin().await?;
+                    // 解除画像特征对该事件的引用，避免外键约束失败
+                    sqlx::query(
+                        "UPDATE persona_traits SET source_event_id = NULL \
+                         WHERE source_event_id IN (SELECT id FROM memory_events WHERE id = ?1 AND namespace = ?2)",
+                    )
+                    .bind(id)
+                    .bind(namespace)
+                    .execute(&mut *tx)
+                    .await?;
+                    let result =
+                        sqlx::query("DELETE FROM memory_events WHERE id = ?1 AND namespace = ?2")
+                            .bind(id)
+                            .bind(namespace)
+                            .execute(&mut *tx)
+                            .await?;
+                    tx.commit().await?;
+                    Ok(result.rows_affected())
+                }
+            })
+            .await
+            .map_err(|e| DbError::with_context("Failed to delete memory event", e))?;
+
+        log::info!("Deleted memory event {}", id);
+        Ok(deleted > 0)
+    }
+
+    async fn delete_session_events(&self, session_id: i64) -> DbResult<u64> {
+        let pool = self.get_pool().await?;
+        let namespace = self.namespace();
+
+        let deleted = self
+            .with_retry(|| {
+                let pool = &pool;
+                async move {
+                    let mut tx = pool.begin().await?;
+                    sqlx::query(
+                        "UPDATE persona_traits SET source_event_id = NULL \
+                         WHERE source_event_id IN (SELECT id FROM memory_events WHERE session_id = ?1 AND namespace = ?2)",
+                    )
+                    .bind(session_id)
+                    .bind(namespace)
+                    .execute(&mut *tx)
+                    .await?;
+                    let result = sqlx::query(
+                        "DELETE FROM memory_events WHERE session_id = ?1 AND namespace = ?2",
+                    )
+                    .bind(session_id)
+                    .bind(namespace)
+                    .execute(&mut *tx)
+                    .await?;
+                    tx.commit().await?;
+                    Ok(result.rows_affected())
+                }
+            })
+            .await
+            .map_err(|e| DbError::with_context("Failed to delete session events", e))?;
+
+        log::info!(
+            "Deleted {} memory events from session {}",
+            deleted,
+            session_id
+        );
+        Ok(deleted)
+    }
+
+    async fn update_memory_event_text(
+        &self,
+        id: i64,
+        new_text: &str,
+        embedding: Option<Vec<u8>>,
+    ) -> DbResult<bool> {
+        let pool = self.get_pool().await?;
+        let result = self
+            .with_retry(|| {
+                sqlx::query(
+                    "UPDATE memory_events SET text = ?1, embedding = ?2 WHERE id = ?3 AND namespace = ?4",
+                )
+                .bind(new_text)
+                .bind(&embedding)
+                .bind(id)
+                .bind(self.namespace())
+                .execute(&pool)
+            })
+            .await
+            .map_err(|e| DbError::with_context("Failed to update memory event", e))?;
+        Ok(result.rows_affected() > 0)
+    }
+
     // 阶段3: 语义片段 DAO
     async fn insert_semantic_chunk(&self, chunk: super::SemanticChunk) -> DbResult<i64> {
         let pool = self.get_pool().await?;
         let sql = r#"
-            INSERT INTO semantic_chunks (title, summary, keywords, embedding, last_ref_ts, weight)
-            VALUES (?1, ?2, ?3, ?4, datetime('now'), COALESCE(?5, 1.0))
+            INSERT INTO semantic_chunks (title, summary, keywords, embedding, last_ref_ts, weight, namespace)
+            VALUES (?1, ?2, ?3, ?4, datetime('now'), COALESCE(?5, 1.0), ?6)
         "#;
-        let result = sqlx::query(sql)
-            .bind(&chunk.title)
-            .bind(&chunk.summary)
-            .bind(&chunk.keywords)
-            .bind(&chunk.embedding)
-            .bind(chunk.weight)
-            .execute(&pool)
+        let result = self
+            .with_retry(|| {
+                sqlx::query(sql)
+                    .bind(&chunk.title)
+                    .bind(&chunk.summary)
+                    .bind(&chunk.keywords)
+                    .bind(&chunk.embedding)
+                    .bind(chunk.weight)
+                    .bind(self.namespace())
+                    .execute(&pool)
+            })
             .await
-            .map_err(|e| format!("Failed to insert semantic_chunk: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to insert semantic_chunk", e))?;
         Ok(result.last_insert_rowid())
     }
 
@@ -530,18 +961,18 @@ impl Database for SqliteDatabase {
     ) -> DbResult<Vec<super::SemanticChunk>> {
         let pool = self.get_pool().await?;
         let sql = if limit.is_some() {
-            "SELECT id, title, summary, keywords, embedding, last_ref_ts, weight FROM semantic_chunks ORDER BY (last_ref_ts IS NULL), last_ref_ts DESC, id DESC LIMIT ?1"
+            "SELECT id, title, summary, keywords, embedding, last_ref_ts, weight FROM semantic_chunks WHERE namespace = ?1 ORDER BY (last_ref_ts IS NULL), last_ref_ts DESC, id DESC LIMIT ?2"
         } else {
-            "SELECT id, title, summary, keywords, embedding, last_ref_ts, weight FROM semantic_chunks ORDER BY (last_ref_ts IS NULL), last_ref_ts DESC, id DESC"
+            "SELECT id, title, summary, keywords, embedding, last_ref_ts, weight FROM semantic_chunks WHERE namespace = ?1 ORDER BY (last_ref_ts IS NULL), last_ref_ts DESC, id DESC"
         };
-        let mut q = sqlx::query(sql);
+        let mut q = sqlx::query(sql).bind(self.namespace());
         if let Some(lim) = limit {
             q = q.bind(lim);
         }
         let rows = q
             .fetch_all(&pool)
             .await
-            .map_err(|e| format!("Failed to list semantic_chunks: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to list semantic_chunks", e))?;
         let mut chunks = Vec::new();
         for row in rows {
             chunks.push(super::SemanticChunk {
@@ -559,60 +990,112 @@ impl Database for SqliteDatabase {
 
     async fn update_semantic_chunk_ref_time(&self, chunk_id: i64) -> DbResult<()> {
         let pool = self.get_pool().await?;
-        sqlx::query("UPDATE semantic_chunks SET last_ref_ts=datetime('now') WHERE id=?1")
+        self.with_retry(|| {
+            sqlx::query(
+                "UPDATE semantic_chunks SET last_ref_ts=datetime('now') WHERE id=?1 AND namespace=?2",
+            )
             .bind(chunk_id)
-            .execute(&pool)
-            .await
-            .map_err(|e| format!("Failed to update semantic_chunk ref time: {}", e))?;
+            .bind(self.namespace())
+                .execute(&pool)
+        })
+        .await
+        .map_err(|e| DbError::with_context("Failed to update semantic_chunk ref time", e))?;
         Ok(())
     }
 
+    async fn prune_semantic_chunks(
+        &self,
+        min_weight: f32,
+        older_than: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> DbResult<u64> {
+        let pool = self.get_pool().await?;
+        let cutoff = older_than.map(|ts| ts.format("%Y-%m-%d %H:%M:%S").to_string());
+        // last_ref_ts 与 datetime('now') 同为 "YYYY-MM-DD HH:MM:SS"，可直接按字符串比较
+        let condition = "namespace = ?3 AND (weight < ?1 OR (?2 IS NOT NULL AND (last_ref_ts IS NULL OR last_ref_ts < ?2)))";
+
+        let namespace = self.namespace();
+        let cutoff = &cutoff;
+
+        let pruned = self
+            .with_retry(|| {
+                let pool = &pool;
+                async move {
+                    let mut tx = pool.begin().await?;
+                    // 先删除映射，避免留下指向已删除片段的记录
+                    sqlx::query(&format!(
+                        "DELETE FROM semantic_chunk_mappings WHERE chunk_id IN (SELECT id FROM semantic_chunks WHERE {})",
+                        condition
+                    ))
+                    .bind(min_weight)
+                    .bind(cutoff)
+                    .bind(namespace)
+                    .execute(&mut *tx)
+                    .await?;
+                    let result =
+                        sqlx::query(&format!("DELETE FROM semantic_chunks WHERE {}", condition))
+                            .bind(min_weight)
+                            .bind(cutoff)
+                            .bind(namespace)
+                            .execute(&mut *tx)
+                            .await?;
+                    tx.commit().await?;
+                    Ok(result.rows_affected())
+                }
+            })
+            .await
+            .map_err(|e| DbError::with_context("Failed to prune semantic_chunks", e))?;
+
+        log::debug!("Pruned {} semantic chunks", pruned);
+        Ok(pruned)
+    }
+
     // 阶段3: 图谱 DAO
     async fn upsert_graph_node(&self, node: super::GraphNode) -> DbResult<i64> {
         let pool = self.get_pool().await?;
-        // 尝试查找
-        if let Some(existing) = self
-            .get_node_by_entity(&node.entity_type, &node.entity_name)
-            .await?
-        {
-            return Ok(existing.id.unwrap());
-        }
-        // 插入
-        let res = sqlx::query("INSERT INTO graph_nodes (entity_type, entity_name) VALUES (?1, ?2)")
-            .bind(&node.entity_type)
-            .bind(&node.entity_name)
-            .execute(&pool)
+        // 节点按 (entity_type, entity_name) 唯一，已存在时返回其ID
+        let row = self
+            .with_retry(|| {
+                sqlx::query(
+                    r#"
+                    INSERT INTO graph_nodes (entity_type, entity_name)
+                    VALUES (?1, ?2)
+                    ON CONFLICT(entity_type, entity_name)
+                    DO UPDATE SET entity_name = graph_nodes.entity_name
+                    RETURNING id
+                    "#,
+                )
+                .bind(&node.entity_type)
+                .bind(&node.entity_name)
+                .fetch_one(&pool)
+            })
             .await
-            .map_err(|e| format!("Failed to insert graph_node: {}", e))?;
-        Ok(res.last_insert_rowid())
+            .map_err(|e| DbError::with_context("Failed to upsert graph_node", e))?;
+        Ok(row.get::<i64, _>("id"))
     }
 
     async fn upsert_graph_edge(&self, edge: super::GraphEdge) -> DbResult<i64> {
         let pool = self.get_pool().await?;
-        // 边不做严格唯一约束（允许多次强化），但可尝试查重
-        let row = sqlx::query(
-            "SELECT id FROM graph_edges WHERE from_node=?1 AND to_node=?2 AND relation_type=?3",
-        )
-        .bind(edge.from_node)
-        .bind(edge.to_node)
-        .bind(&edge.relation_type)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| format!("Failed to query graph_edge: {}", e))?;
-        if let Some(r) = row {
-            return Ok(r.get::<i64, _>("id"));
-        }
-        let res = sqlx::query(
-            "INSERT INTO graph_edges (from_node, to_node, relation_type, weight) VALUES (?1, ?2, ?3, COALESCE(?4, 1.0))"
-        )
-        .bind(edge.from_node)
-        .bind(edge.to_node)
-        .bind(&edge.relation_type)
-        .bind(edge.weight)
-        .execute(&pool)
-        .await
-        .map_err(|e| format!("Failed to insert graph_edge: {}", e))?;
-        Ok(res.last_insert_rowid())
+        // 边按 (from_node, to_node, relation_type) 唯一，已存在时保留原权重并返回其ID
+        let row = self
+            .with_retry(|| {
+                sqlx::query(
+                    r#"
+                    INSERT INTO graph_edges (from_node, to_node, relation_type, weight)
+                    VALUES (?1, ?2, ?3, COALESCE(?4, 1.0))
+                    ON CONFLICT(from_node, to_node, relation_type)
+                    DO UPDATE SET weight = graph_edges.weight
+                    RETURNING id
+                    "#,
+                )
+                .bind(edge.from_node)
+                .bind(edge.to_node)
+                .bind(&edge.relation_type)
+                .bind(edge.weight)
+                .fetch_one(&pool)
+            })
+            .await
+            .map_err(|e| DbError::with_context("Failed to upsert graph_edge", e))?;
+        Ok(row.get::<i64, _>("id"))
     }
 
     async fn get_graph_nodes(&self) -> DbResult<Vec<super::GraphNode>> {
@@ -620,7 +1103,7 @@ impl Database for SqliteDatabase {
         let rows = sqlx::query("SELECT id, entity_type, entity_name FROM graph_nodes")
             .fetch_all(&pool)
             .await
-            .map_err(|e| format!("Failed to list graph_nodes: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to list graph_nodes", e))?;
         let mut nodes = Vec::new();
         for row in rows {
             nodes.push(super::GraphNode {
@@ -638,7 +1121,40 @@ impl Database for SqliteDatabase {
             sqlx::query("SELECT id, from_node, to_node, relation_type, weight FROM graph_edges")
                 .fetch_all(&pool)
                 .await
-                .map_err(|e| format!("Failed to list graph_edges: {}", e))?;
+                .map_err(|e| DbError::with_context("Failed to list graph_edges", e))?;
+        let mut edges = Vec::new();
+        for row in rows {
+            edges.push(super::GraphEdge {
+                id: row.get("id"),
+                from_node: row.get("from_node"),
+                to_node: row.get("to_node"),
+                relation_type: row.get("relation_type"),
+                weight: row.get("weight"),
+            });
+        }
+        Ok(edges)
+    }
+
+    async fn get_edges_for_node(
+        &self,
+        node_id: i64,
+        direction: super::EdgeDirection,
+    ) -> DbResult<Vec<super::GraphEdge>> {
+        let pool = self.get_pool().await?;
+        let condition = match direction {
+            super::EdgeDirection::Outgoing => "from_node = ?1",
+            super::EdgeDirection::Incoming => "to_node = ?1",
+            super::EdgeDirection::Both => "from_node = ?1 OR to_node = ?1",
+        };
+        let sql = format!(
+            "SELECT id, from_node, to_node, relation_type, weight FROM graph_edges WHERE {} ORDER BY weight DESC, id",
+            condition
+        );
+        let rows = sqlx::query(&sql)
+            .bind(node_id)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| DbError::with_context("Failed to list graph_edges for node", e))?;
         let mut edges = Vec::new();
         for row in rows {
             edges.push(super::GraphEdge {
@@ -652,6 +1168,54 @@ impl Database for SqliteDatabase {
         Ok(edges)
     }
 
+    async fn get_neighbors(
+        &self,
+        node_id: i64,
+        top_k: usize,
+    ) -> DbResult<Vec<(super::GraphNode, super::GraphEdge)>> {
+        let pool = self.get_pool().await?;
+        let rows = sqlx::query(
+            "SELECT e.id, e.from_node, e.to_node, e.relation_type, e.weight, \
+                    n.id AS node_id, n.entity_type, n.entity_name \
+             FROM graph_edges e \
+             JOIN graph_nodes n ON n.id = CASE WHEN e.from_node = ?1 THEN e.to_node ELSE e.from_node END \
+             WHERE e.from_node = ?1 OR e.to_node = ?1 \
+             ORDER BY e.weight DESC, e.id",
+        )
+        .bind(node_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| DbError::with_context("Failed to list graph neighbors", e))?;
+
+        // 同一邻居可能有多条边，只保留权重最高的一条
+        let mut seen = std::collections::HashSet::new();
+        let mut neighbors = Vec::new();
+        for row in rows {
+            if neighbors.len() >= top_k {
+                break;
+            }
+            let neighbor_id: i64 = row.get("node_id");
+            if !seen.insert(neighbor_id) {
+                continue;
+            }
+            neighbors.push((
+                super::GraphNode {
+                    id: Some(neighbor_id),
+                    entity_type: row.get("entity_type"),
+                    entity_name: row.get("entity_name"),
+                },
+                super::GraphEdge {
+                    id: row.get("id"),
+                    from_node: row.get("from_node"),
+                    to_node: row.get("to_node"),
+                    relation_type: row.get("relation_type"),
+                    weight: row.get("weight"),
+                },
+            ));
+        }
+        Ok(neighbors)
+    }
+
     async fn get_node_by_entity(
         &self,
         entity_type: &str,
@@ -714,16 +1278,17 @@ impl Database for SqliteDatabase {
 
     async fn upsert_persona_trait(&self, trait_item: super::PersonaTrait) -> DbResult<i64> {
         let pool = self.get_pool().await?;
-        // Upsert on (agent_name, trait_type, trait_key)
-        sqlx::query(
-            "INSERT INTO persona_traits (agent_name, trait_type, trait_key, trait_value, confidence, stability, last_seen, source_event_id) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'), ?7) \
-             ON CONFLICT(agent_name, trait_type, trait_key) DO UPDATE SET \
+        // Upsert on (namespace, agent_name, trait_type, trait_key)
+        let row = sqlx::query(
+            "INSERT INTO persona_traits (agent_name, trait_type, trait_key, trait_value, confidence, stability, last_seen, source_event_id, namespace) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'), ?7, ?8) \
+             ON CONFLICT(namespace, agent_name, trait_type, trait_key) DO UPDATE SET \
                 trait_value=excluded.trait_value, \
                 confidence=excluded.confidence, \
                 stability=excluded.stability, \
                 last_seen=excluded.last_seen, \
-                source_event_id=excluded.source_event_id"
+                source_event_id=excluded.source_event_id \
+             RETURNING id"
         )
         .bind(&trait_item.agent_name)
         .bind(&trait_item.trait_type)
@@ -732,15 +1297,9 @@ impl Database for SqliteDatabase {
         .bind(trait_item.confidence)
         .bind(trait_item.stability)
         .bind(trait_item.source_event_id)
-        .execute(&pool)
+        .bind(self.namespace())
+        .fetch_one(&pool)
         .await?;
-
-        let row = sqlx::query("SELECT id FROM persona_traits WHERE agent_name = ?1 AND trait_type = ?2 AND trait_key = ?3")
-            .bind(&trait_item.agent_name)
-            .bind(&trait_item.trait_type)
-            .bind(&trait_item.trait_key)
-            .fetch_one(&pool)
-            .await?;
         Ok(row.get::<i64, _>("id"))
     }
 
@@ -751,15 +1310,15 @@ impl Database for SqliteDatabase {
         top_k: Option<usize>,
     ) -> DbResult<Vec<super::PersonaTrait>> {
         let pool = self.get_pool().await?;
-        let mut query = String::from("SELECT id, agent_name, trait_type, trait_key, trait_value, confidence, stability, last_seen, source_event_id FROM persona_traits WHERE agent_name = ?1");
+        let mut query = String::from("SELECT id, agent_name, trait_type, trait_key, trait_value, confidence, stability, last_seen, source_event_id FROM persona_traits WHERE agent_name = ?1 AND namespace = ?2");
         if trait_type.is_some() {
-            query.push_str(" AND trait_type = ?2");
+            query.push_str(" AND trait_type = ?3");
         }
         query.push_str(" ORDER BY stability DESC, confidence DESC, last_seen DESC");
         if let Some(k) = top_k {
             query.push_str(&format!(" LIMIT {}", k));
         }
-        let mut q = sqlx::query(&query).bind(agent_name);
+        let mut q = sqlx::query(&query).bind(agent_name).bind(self.namespace());
         if let Some(t) = trait_type {
             q = q.bind(t);
         }
@@ -792,12 +1351,13 @@ impl Database for SqliteDatabase {
         let like = format!("%{}%", query);
         let rows = sqlx::query(
             "SELECT id, agent_name, trait_type, trait_key, trait_value, confidence, stability, last_seen, source_event_id \
-             FROM persona_traits WHERE agent_name = ?1 AND (trait_key LIKE ?2 OR trait_value LIKE ?2) \
+             FROM persona_traits WHERE agent_name = ?1 AND namespace = ?4 AND (trait_key LIKE ?2 OR trait_value LIKE ?2) \
              ORDER BY stability DESC, confidence DESC, last_seen DESC LIMIT ?3"
         )
         .bind(agent_name)
         .bind(like)
         .bind(top_k as i64)
+        .bind(self.namespace())
         .fetch_all(&pool)
         .await?;
         let mut res = Vec::new();
@@ -817,21 +1377,110 @@ impl Database for SqliteDatabase {
         Ok(res)
     }
 
+    async fn decay_persona_traits(
+        &self,
+        agent_name: &str,
+        half_life_days: f32,
+        min_confidence: f32,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> DbResult<super::PersonaDecayStats> {
+        if half_life_days <= 0.0 || half_life_days.is_nan() {
+            return Err(format!("Invalid persona decay half-life: {}", half_life_days).into());
+        }
+
+        let pool = self.get_pool().await?;
+        let namespace = self.namespace();
+        let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
+        let now_str = &now_str;
+
+        let stats = self
+            .with_retry(|| {
+                let pool = &pool;
+                async move {
+                    let mut tx = pool.begin().await?;
+                    let rows = sqlx::query(
+                        "SELECT id, confidence, stability, last_seen, last_decayed FROM persona_traits WHERE agent_name = ?1 AND namespace = ?2",
+                    )
+                    .bind(agent_name)
+                    .bind(namespace)
+                    .fetch_all(&mut *tx)
+                    .await?;
+
+                    let mut stats = super::PersonaDecayStats::default();
+                    for row in rows {
+                        let id: i64 = row.get("id");
+                        let last_seen: Option<String> = row.get("last_seen");
+                        let last_decayed: Option<String> = row.get("last_decayed");
+                        // 从最近一次观察或衰减开始计算，重复调用不会叠加衰减
+                        let since = [last_seen, last_decayed]
+                            .iter()
+                            .flatten()
+                            .filter_map(|ts| parse_sqlite_datetime(ts))
+                            .max();
+                        let Some(since) = since else {
+                            continue;
+                        };
+                        let age_days = (now - since).num_seconds() as f64 / 86_400.0;
+                        if age_days <= 0.0 {
+                            continue;
+                        }
+
+                        let factor = 0.5_f64.powf(age_days / half_life_days as f64) as f32;
+                        let confidence: f32 = row.get::<f32, _>("confidence") * factor;
+                        let stability: f32 = row.get::<f32, _>("stability") * factor;
+                        if confidence < min_confidence {
+                            sqlx::query("DELETE FROM persona_traits WHERE id = ?1")
+                                .bind(id)
+                                .execute(&mut *tx)
+                                .await?;
+                            stats.pruned += 1;
+                        } else {
+                            sqlx::query(
+                                "UPDATE persona_traits SET confidence = ?1, stability = ?2, last_decayed = ?3 WHERE id = ?4",
+                            )
+                            .bind(confidence)
+                            .bind(stability)
+                            .bind(now_str)
+                            .bind(id)
+                            .execute(&mut *tx)
+                            .await?;
+                            stats.decayed += 1;
+                        }
+                    }
+                    tx.commit().await?;
+                    Ok(stats)
+                }
+            })
+            .await
+            .map_err(|e| DbError::with_context("Failed to decay persona traits", e))?;
+
+        log::debug!(
+            "Decayed persona traits for {}: {} decayed, {} pruned",
+            agent_name,
+            stats.decayed,
+            stats.pruned
+        );
+        Ok(stats)
+    }
+
     // 语义片段映射管理
     async fn insert_semantic_chunk_mapping(
         &self,
         mapping: super::SemanticChunkMapping,
     ) -> DbResult<i64> {
         let pool = self.get_pool().await?;
-        let res = sqlx::query(
-            "INSERT INTO semantic_chunk_mappings (chunk_id, session_id, memory_event_ids) VALUES (?1, ?2, ?3)"
-        )
-        .bind(mapping.chunk_id)
-        .bind(mapping.session_id)
-        .bind(&mapping.memory_event_ids)
-        .execute(&pool)
-        .await
-        .map_err(|e| format!("Failed to insert semantic_chunk_mapping: {}", e))?;
+        let res = self
+            .with_retry(|| {
+                sqlx::query(
+                    "INSERT INTO semantic_chunk_mappings (chunk_id, session_id, memory_event_ids) VALUES (?1, ?2, ?3)"
+                )
+                .bind(mapping.chunk_id)
+                .bind(mapping.session_id)
+                .bind(&mapping.memory_event_ids)
+                .execute(&pool)
+            })
+            .await
+            .map_err(|e| DbError::with_context("Failed to insert semantic_chunk_mapping", e))?;
         Ok(res.last_insert_rowid())
     }
 
@@ -846,7 +1495,7 @@ impl Database for SqliteDatabase {
         .bind(chunk_id)
         .fetch_all(&pool)
         .await
-        .map_err(|e| format!("Failed to get chunk mappings by chunk_id: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to get chunk mappings by chunk_id", e))?;
 
         let mut mappings = Vec::new();
         for row in rows {
@@ -872,7 +1521,7 @@ impl Database for SqliteDatabase {
         .bind(session_id)
         .fetch_all(&pool)
         .await
-        .map_err(|e| format!("Failed to get chunk mappings by session_id: {}", e))?;
+        .map_err(|e| DbError::with_context("Failed to get chunk mappings by session_id", e))?;
 
         let mut mappings = Vec::new();
         for row in rows {
@@ -887,49 +1536,68 @@ impl Database for SqliteDatabase {
         Ok(mappings)
     }
 
-    /// 累积边权重版本的 upsert_graph_edge
-    async fn upsert_graph_edge_with_accumulation(&self, edge: super::GraphEdge) -> DbResult<i64> {
+    async fn get_memory_events_for_chunk(&self, chunk_id: i64) -> DbResult<Vec<MemoryEvent>> {
         let pool = self.get_pool().await?;
 
-        // 查找现有边
-        let existing_row = sqlx::query(
-            "SELECT id, weight FROM graph_edges WHERE from_node=?1 AND to_node=?2 AND relation_type=?3"
-        )
-        .bind(edge.from_node)
-        .bind(edge.to_node)
-        .bind(&edge.relation_type)
-        .fetch_optional(&pool)
-        .await
-        .map_err(|e| format!("Failed to query graph_edge for accumulation: {}", e))?;
+        let rows = sqlx::query(r#"
+            SELECT session_id, agent_name, role, text, topic, sentiment, importance, decay, embedding
+            FROM memory_events
+            WHERE id IN (
+                SELECT j.value FROM semantic_chunk_mappings m, json_each(m.memory_event_ids) j
+                WHERE m.chunk_id = ?1
+            )
+            AND namespace = ?2
+            ORDER BY ts ASC, id ASC
+        "#)
+            .bind(chunk_id)
+            .bind(self.namespace())
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| DbError::with_context("Failed to get memory events for chunk", e))?;
 
-        if let Some(row) = existing_row {
-            // 累积权重
-            let existing_id: i64 = row.get("id");
-            let existing_weight: f32 = row.get("weight");
-            let new_weight = existing_weight + edge.weight;
+        let mut events = Vec::new();
+        for row in rows {
+            events.push(MemoryEvent {
+                session_id: row.get("session_id"),
+                agent_name: row.get("agent_name"),
+                role: row.get("role"),
+                text: row.get("text"),
+                topic: row.get("topic"),
+                sentiment: row.get("sentiment"),
+                importance: row.get("importance"),
+                decay: row.get("decay"),
+                embedding: row.get("embedding"),
+            });
+        }
 
-            sqlx::query("UPDATE graph_edges SET weight = ?1 WHERE id = ?2")
-                .bind(new_weight)
-                .bind(existing_id)
-                .execute(&pool)
-                .await
-                .map_err(|e| format!("Failed to update graph_edge weight: {}", e))?;
+        Ok(events)
+    }
 
-            Ok(existing_id)
-        } else {
-            // 插入新边
-            let res = sqlx::query(
-                "INSERT INTO graph_edges (from_node, to_node, relation_type, weight) VALUES (?1, ?2, ?3, ?4)"
-            )
-            .bind(edge.from_node)
-            .bind(edge.to_node)
-            .bind(&edge.relation_type)
-            .bind(edge.weight)
-            .execute(&pool)
+    /// 累积边权重版本的 upsert_graph_edge
+    ///
+    /// 单条 `INSERT ... ON CONFLICT DO UPDATE` 完成，并发累积同一条边不会丢失更新。
+    async fn upsert_graph_edge_with_accumulation(&self, edge: super::GraphEdge) -> DbResult<i64> {
+        let pool = self.get_pool().await?;
+        let row = self
+            .with_retry(|| {
+                sqlx::query(
+                    r#"
+                    INSERT INTO graph_edges (from_node, to_node, relation_type, weight)
+                    VALUES (?1, ?2, ?3, ?4)
+                    ON CONFLICT(from_node, to_node, relation_type)
+                    DO UPDATE SET weight = COALESCE(graph_edges.weight, 0) + excluded.weight
+                    RETURNING id
+                    "#,
+                )
+                .bind(edge.from_node)
+                .bind(edge.to_node)
+                .bind(&edge.relation_type)
+                .bind(edge.weight)
+                .fetch_one(&pool)
+            })
             .await
-            .map_err(|e| format!("Failed to insert graph_edge: {}", e))?;
-            Ok(res.last_insert_rowid())
-        }
+            .map_err(|e| DbError::with_context("Failed to accumulate graph_edge weight", e))?;
+        Ok(row.get::<i64, _>("id"))
     }
 
     async fn migrate(&mut self) -> DbResult<()> {
@@ -941,7 +1609,7 @@ impl Database for SqliteDatabase {
         let path = std::path::Path::new(backup_path);
         if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+                .map_err(|e| DbError::with_context("Failed to create backup directory", e))?;
         }
         // Use SQLite VACUUM INTO to create a consistent backup even with WAL
         let pool = self.get_pool().await?;
@@ -951,10 +1619,505 @@ impl Database for SqliteDatabase {
         sqlx::query(&sql)
             .execute(&pool)
             .await
-            .map_err(|e| format!("Failed to backup database: {}", e))?;
+            .map_err(|e| DbError::with_context("Failed to backup database", e))?;
+        Ok(())
+    }
+
+    async fn checkpoint(&self, mode: super::CheckpointMode) -> DbResult<()> {
+        let pool = self.get_pool().await?;
+        let sql = format!("PRAGMA wal_checkpoint({})", mode.as_pragma_arg());
+        let row = sqlx::query(&sql)
+            .fetch_one(&pool)
+            .await
+            .map_err(|e| DbError::with_context("Failed to checkpoint WAL", e))?;
+        let busy: i64 = row.get(0);
+        let log_frames: i64 = row.get(1);
+        let checkpointed: i64 = row.get(2);
+        if busy != 0 {
+            log::warn!(
+                "WAL checkpoint ({:?}) blocked by active connections: {}/{} frames checkpointed",
+                mode,
+                checkpointed,
+                log_frames
+            );
+        } else {
+            log::info!(
+                "WAL checkpoint ({:?}) completed: {}/{} frames checkpointed",
+                mode,
+                checkpointed,
+                log_frames
+            );
+        }
         Ok(())
     }
+
+    async fn optimize(&self, vacuum: bool) -> DbResult<()> {
+        let pool = self.get_pool().await?;
+        sqlx::query("PRAGMA optimize")
+            .execute(&pool)
+            .await
+            .map_err(|e| DbError::with_context("Failed to optimize database", e))?;
+        if vacuum {
+            log::info!("Vacuuming SQLite database...");
+            sqlx::query("VACUUM")
+                .execute(&pool)
+                .await
+                .map_err(|e| DbError::with_context("Failed to vacuum database", e))?;
+        }
+        Ok(())
+    }
+
     async fn as_sqlite(&self) -> DbResult<Option<&dyn std::any::Any>> {
         Ok(Some(self as &dyn std::any::Any))
     }
 }
+
+/// 解析 SQLite datetime('now') 写入的 UTC 时间（兼容 RFC3339）
+fn parse_sqlite_datetime(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .ok()
+        .or_else(|| {
+            chrono::DateTime::parse_from_rfc3339(value)
+                .map(|dt| dt.with_timezone(&chrono::Utc))
+                .ok()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{
+        CheckpointMode, GraphEdge, GraphNode, PersonaTrait, SemanticChunk, SemanticChunkMapping,
+    };
+
+    #[tokio::test]
+    async fn test_retry_recovers_from_transient_errors() {
+        let mut attempts = 0;
+        let result = retry_with_backoff(3, 1, || {
+            attempts += 1;
+            let outcome = if attempts <= 2 {
+                Err(sqlx::Error::PoolTimedOut)
+            } else {
+                Ok(attempts)
+            };
+            async move { outcome }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 3);
+
+        // 超过重试次数后返回最后一次的错误
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_with_backoff(2, 1, || {
+            attempts += 1;
+            async { Err(sqlx::Error::PoolTimedOut) }
+        })
+        .await;
+        assert!(matches!(result, Err(sqlx::Error::PoolTimedOut)));
+        assert_eq!(attempts, 3);
+
+        // 非瞬时错误不重试
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_with_backoff(3, 1, || {
+            attempts += 1;
+            async { Err(sqlx::Error::RowNotFound) }
+        })
+        .await;
+        assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_waits_out_a_held_write_lock() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let dir = tempfile::tempdir().unwrap();
+        let options = SqliteConnectOptions::from_str(&format!(
+            "sqlite:{}",
+            dir.path().join("busy.db").display()
+        ))
+        .unwrap()
+        .create_if_missing(true)
+        .pragma("journal_mode", "WAL")
+        .busy_timeout(Duration::ZERO);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(2)
+            .connect_with(options)
+            .await
+            .unwrap();
+        sqlx::query("CREATE TABLE items (value INTEGER)")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // 另一个连接持有写锁，此时写入立即得到 SQLITE_BUSY
+        let mut holder = pool.acquire().await.unwrap();
+        sqlx::query("BEGIN IMMEDIATE")
+            .execute(&mut *holder)
+            .await
+            .unwrap();
+        let insert = || sqlx::query("INSERT INTO items (value) VALUES (1)").execute(&pool);
+        let error = retry_with_backoff(0, 1, insert).await.unwrap_err();
+        let sqlx::Error::Database(db_error) = &error else {
+            panic!("expected a database error, got {:?}", error);
+        };
+        assert_eq!(sqlite_primary_code(db_error.as_ref()), Some(SQLITE_BUSY));
+
+        let release = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            sqlx::query("COMMIT").execute(&mut *holder).await.unwrap();
+        });
+        let busy_attempts = AtomicUsize::new(0);
+        retry_with_backoff(20, 10, || {
+            let (pool, busy_attempts) = (&pool, &busy_attempts);
+            async move {
+                let result = sqlx::query("INSERT INTO items (value) VALUES (1)")
+                    .execute(pool)
+                    .await;
+                if result.as_ref().is_err_and(is_transient_error) {
+                    busy_attempts.fetch_add(1, Ordering::SeqCst);
+                }
+                result
+            }
+        })
+        .await
+        .unwrap();
+        release.await.unwrap();
+
+        assert!(busy_attempts.load(Ordering::SeqCst) >= 1);
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM items")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_reads_share_pool() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = DatabaseConfig {
+            db_path: dir.path().join("pool.db"),
+            ..Default::default()
+        }
+        .with_pool(4, 5, 60);
+        let mut db = SqliteDatabase::new(config).unwrap();
+        db.initialize().await.unwrap();
+        db.open_session("chat", None).await.unwrap();
+
+        let db = Arc::new(db);
+        let reads = (0..64).map(|_| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                db.query_raw("SELECT id FROM sessions WHERE agent_name = 'chat'")
+                    .await
+                    .map(|rows| rows.len())
+                    .map_err(|e| e.to_string())
+            })
+        });
+        let results =
+            tokio::time::timeout(Duration::from_secs(10), futures::future::join_all(reads))
+                .await
+                .expect("concurrent reads stalled");
+
+        for result in results {
+            assert_eq!(result.unwrap().unwrap(), 1);
+        }
+        // 所有读取复用同一个连接池，且不超过配置的最大连接数
+        assert!(db.get_pool().await.unwrap().size() <= 4);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_truncate_shrinks_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = SqliteDatabase::new(DatabaseConfig {
+            db_path: dir.path().join("wal.db"),
+            ..Default::default()
+        })
+        .unwrap();
+        db.initialize().await.unwrap();
+
+        for i in 0..200 {
+            db.upsert_graph_node(GraphNode {
+                id: None,
+                entity_type: "topic".to_string(),
+                entity_name: format!("topic-{}", i),
+            })
+            .await
+            .unwrap();
+        }
+
+        let wal_path = dir.path().join("wal.db-wal");
+        let wal_size = || std::fs::metadata(&wal_path).map(|m| m.len()).unwrap_or(0);
+        let before = wal_size();
+        assert!(before > 0);
+
+        db.checkpoint(CheckpointMode::Truncate).await.unwrap();
+        assert!(wal_size() < before);
+        assert_eq!(wal_size(), 0);
+
+        db.optimize(true).await.unwrap();
+        assert_eq!(db.get_graph_nodes().await.unwrap().len(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_edge_accumulation_is_atomic() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = SqliteDatabase::new(
+            DatabaseConfig {
+                db_path: dir.path().join("edges.db"),
+                ..Default::default()
+            }
+            .with_pool(8, 5, 60),
+        )
+        .unwrap();
+        db.initialize().await.unwrap();
+
+        let mut node_ids = Vec::new();
+        for name in ["a", "b"] {
+            let id = db
+                .upsert_graph_node(GraphNode {
+                    id: None,
+                    entity_type: "concept".to_string(),
+                    entity_name: name.to_string(),
+                })
+                .await
+                .unwrap();
+            node_ids.push(id);
+        }
+
+        let db = Arc::new(db);
+        let tasks = (0..50).map(|_| {
+            let db = db.clone();
+            let (from_node, to_node) = (node_ids[0], node_ids[1]);
+            tokio::spawn(async move {
+                db.upsert_graph_edge_with_accumulation(GraphEdge {
+                    id: None,
+                    from_node,
+                    to_node,
+                    relation_type: "co_occurs".to_string(),
+                    weight: 0.5,
+                })
+                .await
+                .map_err(|e| e.to_string())
+            })
+        });
+        let ids: Vec<i64> = futures::future::join_all(tasks)
+            .await
+            .into_iter()
+            .map(|result| result.unwrap().unwrap())
+            .collect();
+        assert!(ids.iter().all(|id| *id == ids[0]));
+
+        let edges = db.get_graph_edges().await.unwrap();
+        assert_eq!(edges.len(), 1);
+        assert!((edges[0].weight - 25.0).abs() < 1e-4);
+    }
+
+    #[tokio::test]
+    async fn test_upserts_return_existing_ids() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = SqliteDatabase::new(
+            DatabaseConfig {
+                db_path: dir.path().join("upserts.db"),
+                ..Default::default()
+            }
+            .with_pool(8, 5, 60),
+        )
+        .unwrap();
+        db.initialize().await.unwrap();
+
+        let db = Arc::new(db);
+        let tasks = (0..20).map(|_| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                db.upsert_graph_node(GraphNode {
+                    id: None,
+                    entity_type: "person".to_string(),
+                    entity_name: "小明".to_string(),
+                })
+                .await
+                .map_err(|e| e.to_string())
+            })
+        });
+        let ids: Vec<i64> = futures::future::join_all(tasks)
+            .await
+            .into_iter()
+            .map(|result| result.unwrap().unwrap())
+            .collect();
+        assert!(ids.iter().all(|id| *id == ids[0]));
+        assert_eq!(db.get_graph_nodes().await.unwrap().len(), 1);
+
+        let persona_trait = |value: &str| PersonaTrait {
+            id: None,
+            agent_name: "chat".to_string(),
+            trait_type: "preference".to_string(),
+            trait_key: "food".to_string(),
+            trait_value: value.to_string(),
+            confidence: 0.5,
+            stability: 0.0,
+            last_seen: None,
+            source_event_id: None,
+        };
+        let first = db
+            .upsert_persona_trait(persona_trait("noodles"))
+            .await
+            .unwrap();
+        let second = db
+            .upsert_persona_trait(persona_trait("rice"))
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        let traits = db.list_persona_traits("chat", None, None).await.unwrap();
+        assert_eq!(traits.len(), 1);
+        assert_eq!(traits[0].id, Some(first));
+        assert_eq!(traits[0].trait_value, "rice");
+    }
+
+    #[tokio::test]
+    async fn test_persona_traits_migrate_to_namespaced_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("legacy.db");
+        let pool = SqlitePool::connect_with(
+            SqliteConnectOptions::new()
+                .filename(&db_path)
+                .create_if_missing(true),
+        )
+        .await
+        .unwrap();
+        // 旧版表结构：唯一约束不含命名空间
+        sqlx::query(
+            "CREATE TABLE persona_traits (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                agent_name TEXT NOT NULL,
+                trait_type TEXT,
+                trait_key TEXT,
+                trait_value TEXT,
+                confidence REAL DEFAULT 0.5,
+                stability REAL DEFAULT 0.0,
+                last_seen DATETIME,
+                source_event_id INTEGER,
+                UNIQUE(agent_name, trait_type, trait_key)
+            )",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO persona_traits (agent_name, trait_type, trait_key, trait_value) \
+             VALUES ('chat', 'preference', 'food', 'noodles')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        pool.close().await;
+
+        let mut db = SqliteDatabase::new(DatabaseConfig {
+            db_path,
+            ..Default::default()
+        })
+        .unwrap();
+        db.initialize().await.unwrap();
+
+        let legacy = db.list_persona_traits("chat", None, None).await.unwrap();
+        assert_eq!(legacy.len(), 1);
+        assert_eq!(legacy[0].trait_value, "noodles");
+
+        let tenant = db.with_namespace("tenant_a");
+        tenant
+            .upsert_persona_trait(PersonaTrait {
+                id: None,
+                agent_name: "chat".to_string(),
+                trait_type: "preference".to_string(),
+                trait_key: "food".to_string(),
+                trait_value: "rice".to_string(),
+                confidence: 0.5,
+                stability: 0.0,
+                last_seen: None,
+                source_event_id: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(
+            db.list_persona_traits("chat", None, None).await.unwrap()[0].trait_value,
+            "noodles"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_prune_semantic_chunks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = SqliteDatabase::new(DatabaseConfig {
+            db_path: dir.path().join("prune.db"),
+            ..Default::default()
+        })
+        .unwrap();
+        db.initialize().await.unwrap();
+        let session_id = db.create_session("chat", None).await.unwrap();
+
+        // (摘要, 权重, 最后引用时间)
+        let chunks = [
+            ("heavy-fresh", 0.9, "2024-06-01 00:00:00"),
+            ("light-fresh", 0.1, "2024-06-01 00:00:00"),
+            ("heavy-stale", 0.9, "2023-01-01 00:00:00"),
+        ];
+        let mut ids = Vec::new();
+        for (summary, weight, last_ref_ts) in chunks {
+            let chunk_id = db
+                .insert_semantic_chunk(SemanticChunk {
+                    id: None,
+                    title: None,
+                    summary: summary.to_string(),
+                    keywords: None,
+                    embedding: None,
+                    last_ref_ts: None,
+                    weight,
+                })
+                .await
+                .unwrap();
+            db.execute(&format!(
+                "UPDATE semantic_chunks SET last_ref_ts = '{}' WHERE id = {}",
+                last_ref_ts, chunk_id
+            ))
+            .await
+            .unwrap();
+            db.insert_semantic_chunk_mapping(SemanticChunkMapping {
+                id: None,
+                chunk_id,
+                session_id,
+                memory_event_ids: "[]".to_string(),
+                created_ts: None,
+            })
+            .await
+            .unwrap();
+            ids.push(chunk_id);
+        }
+
+        // 只按权重清理
+        assert_eq!(db.prune_semantic_chunks(0.5, None).await.unwrap(), 1);
+        let cutoff = chrono::NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_utc();
+        // 再按最后引用时间清理
+        assert_eq!(
+            db.prune_semantic_chunks(0.5, Some(cutoff)).await.unwrap(),
+            1
+        );
+
+        let remaining: Vec<String> = db
+            .list_semantic_chunks(None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|c| c.summary)
+            .collect();
+        assert_eq!(remaining, vec!["heavy-fresh"]);
+        let mappings = db
+            .get_chunk_mappings_by_session_id(session_id)
+            .await
+            .unwrap();
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].chunk_id, ids[0]);
+    }
+}
@@ -0,0 +1,93 @@
+//! 实体分类模块
+//!
+//! 知识图谱更新时为记忆触发词推断实体类型，并据此推断共现实体之间的关系类型。
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// 支持的实体类型
+pub const ENTITY_TYPES: &[&str] = &[
+    "person",
+    "organization",
+    "location",
+    "preference",
+    "task",
+    "concept",
+    "entity",
+];
+
+/// 实体分类器
+///
+/// 默认使用 [`RuleBasedEntityClassifier`]；需要更高准确率时可换用基于智能体的
+/// `agents::LlmEntityClassifier`。
+#[async_trait]
+pub trait EntityClassifier: Send + Sync + std::fmt::Debug {
+    /// 为每个触发词返回实体类型，顺序与输入一致
+    async fn classify(&self, triggers: &[String]) -> Result<Vec<String>>;
+}
+
+/// 基于规则的实体分类器
+///
+/// 依赖大小写与关键词，中文人名、机构名没有大小写，通常只能归为 `concept`。
+#[derive(Debug, Clone, Default)]
+pub struct RuleBasedEntityClassifier;
+
+impl RuleBasedEntityClassifier {
+    /// 推断单个实体的类型
+    pub fn classify_one(&self, entity_name: &str) -> String {
+        if entity_name.chars().any(|c| c.is_uppercase()) && entity_name.len() > 1 {
+            // 首字母大写，可能是人名、地名或组织
+            if entity_name.contains("公司")
+                || entity_name.contains("Corp")
+                || entity_name.contains("Ltd")
+            {
+                "organization".to_string()
+            } else if entity_name.len() <= 5
+                && entity_name
+                    .chars()
+                    .all(|c| c.is_alphabetic() || c.is_whitespace())
+            {
+                "person".to_string() // 短的字母组合，可能是人名
+            } else {
+                "entity".to_string() // 通用实体
+            }
+        } else if entity_name.ends_with("喜欢")
+            || entity_name.ends_with("偏好")
+            || entity_name.contains("prefer")
+        {
+            "preference".to_string()
+        } else if entity_name.ends_with("任务")
+            || entity_name.ends_with("task")
+            || entity_name.contains("目标")
+        {
+            "task".to_string()
+        } else {
+            "concept".to_string() // 概念或其他
+        }
+    }
+}
+
+#[async_trait]
+impl EntityClassifier for RuleBasedEntityClassifier {
+    async fn classify(&self, triggers: &[String]) -> Result<Vec<String>> {
+        Ok(triggers
+            .iter()
+            .map(|trigger| self.classify_one(trigger))
+            .collect())
+    }
+}
+
+/// 根据两个实体的类型推断关系类型
+pub fn infer_relation_type(type1: &str, type2: &str) -> String {
+    match (type1, type2) {
+        ("person", "organization") | ("organization", "person") => "works_at".to_string(),
+        ("person", "location") | ("location", "person") => "located_in".to_string(),
+        ("person", "preference") | ("preference", "person") => "prefers".to_string(),
+        ("person", "task") | ("task", "person") => "assigned_to".to_string(),
+        ("task", "concept") | ("concept", "task") => "relates_to".to_string(),
+        ("person", "person") => "knows".to_string(),
+        ("organization", "organization") => "partners_with".to_string(),
+        ("preference", "preference") => "similar_to".to_string(),
+        _ => "co_occurs".to_string(), // 默认共现关系
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! 定义了RWKV-Agent-Kit库中使用的所有错误类型和结果类型。
 
+use crate::db::DbError;
 use thiserror::Error;
 
 /// RWKV-Agent-Kit库的统一结果类型
@@ -14,6 +15,10 @@ pub enum MemoryError {
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
+    /// 数据库层（`db` 模块）错误
+    #[error("Storage error: {0}")]
+    Storage(#[from] DbError),
+
     /// 序列化/反序列化错误
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -216,7 +221,7 @@ impl MemoryError {
 
     /// 检查是否为数据库错误
     pub fn is_database_error(&self) -> bool {
-        matches!(self, Self::Database(_))
+        matches!(self, Self::Database(_) | Self::Storage(_))
     }
 
     /// 检查是否为序列化错误
@@ -237,7 +242,8 @@ impl MemoryError {
     /// 获取错误的严重程度
     pub fn severity(&self) -> ErrorSeverity {
         match self {
-            Self::Database(_) | Self::Io(_) => ErrorSeverity::Critical,
+            Self::Storage(DbError::NotFound(_)) => ErrorSeverity::Warning,
+            Self::Database(_) | Self::Storage(_) | Self::Io(_) => ErrorSeverity::Critical,
             Self::MemoryNotFound { .. }
             | Self::InvalidQuery { .. }
             | Self::ValidationError { .. } => ErrorSeverity::Warning,
@@ -139,7 +139,7 @@ impl MemorySystemExample {
             },
             limit: Some(10),
             offset: None,
-            sort_by: Some(SortBy::Relevance),
+            sort_by: Some(SortBy::Relevance(SortOrder::Desc)),
             weights: QueryWeights {
                 semantic_weight: 0.4,
                 temporal_weight: 0.2,
@@ -195,7 +195,7 @@ impl MemorySystemExample {
             },
             limit: Some(5),
             offset: None,
-            sort_by: Some(SortBy::Relevance),
+            sort_by: Some(SortBy::Relevance(SortOrder::Desc)),
             weights: QueryWeights {
                 semantic_weight: 0.4,
                 temporal_weight: 0.2,
@@ -387,7 +387,7 @@ impl MemorySystemExample {
             },
             limit: Some(10),
             offset: None,
-            sort_by: Some(SortBy::Relevance),
+            sort_by: Some(SortBy::Relevance(SortOrder::Desc)),
             weights: QueryWeights {
                 semantic_weight: 0.4,
                 temporal_weight: 0.2,
@@ -516,7 +516,7 @@ impl MemorySystemExample {
             },
             limit: Some(100),
             offset: None,
-            sort_by: Some(SortBy::Relevance),
+            sort_by: Some(SortBy::Relevance(SortOrder::Desc)),
             weights: QueryWeights {
                 semantic_weight: 0.4,
                 temporal_weight: 0.2,
@@ -687,7 +687,7 @@ pub async fn benchmark() -> Result<()> {
         },
         limit: Some(50),
         offset: None,
-        sort_by: Some(SortBy::Relevance),
+        sort_by: Some(SortBy::Relevance(SortOrder::Desc)),
         weights: QueryWeights {
             semantic_weight: 0.4,
             temporal_weight: 0.2,
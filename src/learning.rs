@@ -4,15 +4,31 @@
 //! 用户偏好学习等。
 
 use crate::config::Config;
-use crate::core_types::{Connection, ConnectionId, MemoryId};
-use crate::database::VectorGraphDB;
-use crate::error::Result;
-use chrono::{DateTime, Duration, Utc};
-use log::{debug, info};
+use crate::core_types::{Connection, ConnectionId, ConnectionType, MemoryId};
+use crate::database::{GraphEdge, VectorGraphDB};
+use crate::error::{MemoryError, Result};
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+/// 分页读取ID时每页的大小
+const ID_PAGE_SIZE: usize = 500;
+
+/// 单个学习周期最多处理的记忆/连接数，其余的由后续周期接着处理
+const MAX_IDS_PER_CYCLE: usize = 10_000;
+
+/// 单个学习周期最多处理的待处理反馈数
+const PENDING_FEEDBACK_BATCH_SIZE: usize = 100;
+
+/// 访问模式检测中，相邻交互间隔超过该值即视为新会话
+const ACCESS_SESSION_GAP: Duration = Duration::minutes(30);
+
+/// 时间模式中活跃时段的交互次数至少为峰值的比例
+const TEMPORAL_PEAK_RATIO: f32 = 0.5;
 
 /// 学习引擎
 ///
@@ -36,6 +52,21 @@ pub struct LearningEngine {
     feedback_processor: FeedbackProcessor,
     /// 学习统计
     stats: Arc<RwLock<LearningStats>>,
+    /// 学习周期互斥锁，保证周期不重叠
+    cycle_lock: Mutex<()>,
+    /// 跨周期的分页游标
+    cursors: Mutex<CycleCursors>,
+    /// 单个学习周期最多处理的记忆/连接数
+    max_ids_per_cycle: usize,
+    /// 调度器关闭信号
+    shutdown_tx: watch::Sender<bool>,
+}
+
+/// 学习周期的分页游标，记录上个周期处理到的最后一个ID
+#[derive(Debug, Default)]
+struct CycleCursors {
+    memory: Option<MemoryId>,
+    connection: Option<ConnectionId>,
 }
 
 /// 重要性学习器
@@ -56,6 +87,8 @@ pub struct ImportanceLearner {
     temporal_weight: f32,
     /// 连接权重
     connection_weight: f32,
+    /// 反馈历史记录（与反馈处理器共享）
+    feedback_history: Arc<RwLock<VecDeque<FeedbackRecord>>>,
 }
 
 /// 连接学习器
@@ -106,6 +139,8 @@ pub struct PatternDetector {
     semantic_similarity_threshold: f32,
     /// 模式缓存
     pattern_cache: Arc<RwLock<HashMap<String, DetectedPattern>>>,
+    /// 读取持久化交互记录的数据库
+    db: Arc<VectorGraphDB>,
 }
 
 /// 反馈处理器
@@ -122,6 +157,8 @@ pub struct FeedbackProcessor {
     explicit_feedback_weight: f32,
     /// 反馈历史记录
     feedback_history: Arc<RwLock<VecDeque<FeedbackRecord>>>,
+    /// 持久化待处理反馈的数据库
+    db: Arc<VectorGraphDB>,
 }
 
 /// 学习统计信息
@@ -307,11 +344,12 @@ pub struct LearningResult {
 impl LearningEngine {
     /// 创建新的学习引擎
     pub async fn new(db: Arc<VectorGraphDB>, config: Config) -> Result<Self> {
-        let importance_learner = ImportanceLearner::new(&config);
+        let feedback_processor = FeedbackProcessor::new(&config, db.clone());
+        let importance_learner =
+            ImportanceLearner::new(&config, feedback_processor.feedback_history.clone());
         let connection_learner = ConnectionLearner::new(&config);
         let preference_learner = PreferenceLearner::new(&config);
-        let pattern_detector = PatternDetector::new(&config);
-        let feedback_processor = FeedbackProcessor::new(&config);
+        let pattern_detector = PatternDetector::new(&config, db.clone());
 
         Ok(Self {
             db,
@@ -322,6 +360,10 @@ impl LearningEngine {
             pattern_detector,
             feedback_processor,
             stats: Arc::new(RwLock::new(LearningStats::default())),
+            cycle_lock: Mutex::new(()),
+            cursors: Mutex::new(CycleCursors::default()),
+            max_ids_per_cycle: MAX_IDS_PER_CYCLE,
+            shutdown_tx: watch::channel(false).0,
         })
     }
 
@@ -392,8 +434,8 @@ impl LearningEngine {
     ) -> Result<LearningResult> {
         debug!("Adjusting importance for {} memories", memory_ids.len());
 
-        let mut changes_made = 0;
         let mut details = HashMap::new();
+        let mut updated_vectors = Vec::new();
 
         for memory_id in memory_ids {
             let adjustment = self
@@ -401,20 +443,53 @@ impl LearningEngine {
                 .calculate_adjustment(&memory_id, &trigger, &self.db)
                 .await?;
 
-            if adjustment.abs() > 0.01 {
-                // 只有显著变化才应用
-                // TODO: 实际更新数据库中的重要性
-                changes_made += 1;
-                details.insert(
-                    memory_id.clone(),
-                    serde_json::json!({
-                        "adjustment": adjustment,
-                        "trigger": format!("{:?}", trigger)
-                    }),
-                );
+            // 只有显著变化才应用
+            if adjustment.abs() <= 0.01 {
+                continue;
             }
+
+            let mut vector = match self.db.get_vector(&memory_id).await {
+                Ok(vector) => vector,
+                Err(MemoryError::Database(sqlx::Error::RowNotFound)) => {
+                    debug!(
+                        "Memory {} not found, skipping importance adjustment",
+                        memory_id
+                    );
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+
+            let old_importance = vector
+                .metadata
+                .get("importance")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(0.5) as f32;
+            let new_importance = (old_importance + adjustment).clamp(0.0, 1.0);
+            if (new_importance - old_importance).abs() < f32::EPSILON {
+                continue;
+            }
+
+            vector
+                .metadata
+                .insert("importance".to_string(), serde_json::json!(new_importance));
+            vector.updated_at = Utc::now();
+
+            details.insert(
+                memory_id.clone(),
+                serde_json::json!({
+                    "adjustment": adjustment,
+                    "old_importance": old_importance,
+                    "new_importance": new_importance,
+                    "trigger": format!("{:?}", trigger)
+                }),
+            );
+            updated_vectors.push(vector);
         }
 
+        // 同一任务内的更新批量写回
+        let changes_made = self.db.update_vectors(&updated_vectors).await? as u32;
+
         // 更新统计信息
         self.update_stats(|stats| {
             stats.importance_adjustments += changes_made as u64;
@@ -443,19 +518,26 @@ impl LearningEngine {
             evolution_type
         );
 
-        let mut changes_made = 0;
+        let changes_made: u32;
         let mut details = HashMap::new();
 
         match evolution_type {
             ConnectionEvolutionType::Strengthen => {
+                let mut adjustments = Vec::new();
                 for connection_id in connection_ids {
                     let strengthening = self
                         .connection_learner
                         .calculate_strengthening(&connection_id)
                         .await?;
                     if strengthening > 0.0 {
-                        // TODO: 实际更新连接强度
-                        changes_made += 1;
+                        adjustments.push((connection_id, strengthening));
+                    }
+                }
+
+                let updated = self.db.adjust_edge_weights(&adjustments).await?;
+                changes_made = updated.len() as u32;
+                for (connection_id, strengthening) in adjustments {
+                    if updated.contains(&connection_id) {
                         details.insert(
                             connection_id,
                             serde_json::json!({"strengthening": strengthening}),
@@ -469,15 +551,22 @@ impl LearningEngine {
                 .await;
             }
             ConnectionEvolutionType::Weaken => {
+                let mut adjustments = Vec::new();
                 for connection_id in connection_ids {
                     let weakening = self
                         .connection_learner
                         .calculate_weakening(&connection_id)
                         .await?;
                     if weakening > 0.0 {
-                        // TODO: 实际更新连接强度
-                        changes_made += 1;
-                        details.insert(connection_id, serde_json::json!({"weakening": weakening}));
+                        adjustments.push((connection_id, -weakening));
+                    }
+                }
+
+                let updated = self.db.adjust_edge_weights(&adjustments).await?;
+                changes_made = updated.len() as u32;
+                for (connection_id, delta) in adjustments {
+                    if updated.contains(&connection_id) {
+                        details.insert(connection_id, serde_json::json!({"weakening": -delta}));
                     }
                 }
 
@@ -489,8 +578,22 @@ impl LearningEngine {
             ConnectionEvolutionType::Create => {
                 let new_connections = self
                     .connection_learner
-                    .discover_new_connections(&connection_ids)
+                    .discover_new_connections(&connection_ids, &self.db)
                     .await?;
+
+                for connection in &new_connections {
+                    let edge = GraphEdge {
+                        id: connection.id.clone(),
+                        from_node: connection.from_memory.clone(),
+                        to_node: connection.to_memory.clone(),
+                        edge_type: format!("{:?}", connection.connection_type),
+                        weight: connection.strength,
+                        properties: connection.properties.clone(),
+                        created_at: connection.created_at,
+                        updated_at: connection.updated_at,
+                    };
+                    self.db.insert_edge(&edge).await?;
+                }
                 changes_made = new_connections.len() as u32;
 
                 for (i, connection) in new_connections.iter().enumerate() {
@@ -513,7 +616,7 @@ impl LearningEngine {
             ConnectionEvolutionType::Prune => {
                 let pruned_connections = self
                     .connection_learner
-                    .prune_weak_connections(&connection_ids)
+                    .prune_weak_connections(&connection_ids, &self.db)
                     .await?;
                 changes_made = pruned_connections.len() as u32;
 
@@ -675,20 +778,21 @@ impl LearningEngine {
 
     /// 执行定期学习循环
     pub async fn run_learning_cycle(&self) -> Result<Vec<LearningResult>> {
+        let _guard = self.cycle_lock.lock().await;
         info!("Starting learning cycle");
 
         let mut results = Vec::new();
 
         // 1. 重要性衰减
         let importance_task = LearningTask::ImportanceAdjustment {
-            memory_ids: self.get_all_memory_ids().await?,
+            memory_ids: self.next_memory_batch().await?,
             trigger: ImportanceTrigger::TimeDecay,
         };
         results.push(self.execute_learning_task(importance_task).await?);
 
         // 2. 连接演化
         let connection_task = LearningTask::ConnectionEvolution {
-            connection_ids: self.get_all_connection_ids().await?,
+            connection_ids: self.next_connection_batch().await?,
             evolution_type: ConnectionEvolutionType::Weaken,
         };
         results.push(self.execute_learning_task(connection_task).await?);
@@ -705,17 +809,112 @@ impl LearningEngine {
         };
         results.push(self.execute_learning_task(pattern_task).await?);
 
-        // 4. 处理积累的反馈
-        let feedback_batch = self.feedback_processor.get_pending_feedback().await?;
-        if !feedback_batch.is_empty() {
+        // 4. 处理积累的反馈，成功后标记为已处理
+        let pending = self.feedback_processor.get_pending_feedback().await?;
+        if !pending.is_empty() {
+            let (feedback_ids, feedback_batch): (Vec<_>, Vec<_>) = pending.into_iter().unzip();
             let feedback_task = LearningTask::FeedbackProcessing { feedback_batch };
             results.push(self.execute_learning_task(feedback_task).await?);
+            self.feedback_processor
+                .mark_processed(&feedback_ids)
+                .await?;
         }
 
         info!("Learning cycle completed with {} tasks", results.len());
         Ok(results)
     }
 
+    /// 启动后台学习调度器，每隔 `interval`（附加最多10%的随机抖动）执行一次学习周期
+    pub fn spawn_scheduler(self: Arc<Self>, interval: std::time::Duration) -> JoinHandle<()> {
+        let mut shutdown_rx = self.shutdown_tx.subscribe();
+
+        tokio::spawn(async move {
+            info!("Learning scheduler started with interval {:?}", interval);
+
+            while !*shutdown_rx.borrow() {
+                let delay = interval + Self::scheduler_jitter(interval);
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = shutdown_rx.changed() => continue,
+                }
+
+                match self.run_learning_cycle().await {
+                    Ok(results) => {
+                        for result in &results {
+                            info!(
+                                "Learning task {} finished: success={}, changes={}, time={}ms",
+                                result.task_type,
+                                result.success,
+                                result.changes_made,
+                                result.execution_time.num_milliseconds()
+                            );
+                        }
+                    }
+                    Err(e) => warn!("Learning cycle failed: {}", e),
+                }
+                if let Err(e) = self.checkpoint_index().await {
+                    warn!("Vector index checkpoint failed: {}", e);
+                }
+            }
+
+            info!("Learning scheduler stopped");
+        })
+    }
+
+    /// 按配置的 `learning_interval_hours` 启动调度器；学习未启用时返回 None
+    pub fn spawn_configured_scheduler(self: Arc<Self>) -> Option<JoinHandle<()>> {
+        if !self.config.learning.enabled {
+            return None;
+        }
+        let hours = self.config.learning.learning_interval_hours.max(1);
+        Some(self.spawn_scheduler(std::time::Duration::from_secs(hours * 3600)))
+    }
+
+    /// 通知后台调度器停止
+    pub fn shutdown(&self) {
+        self.shutdown_tx.send_replace(true);
+    }
+
+    /// 把向量索引写入旁路文件，调度器在每个学习周期后调用；未配置 `vector.index_path` 时什么也不做
+    pub async fn checkpoint_index(&self) -> Result<()> {
+        self.db.persist_index().await
+    }
+
+    /// 处理并清空所有待处理的反馈，返回处理的条数
+    pub async fn flush_pending_feedback(&self) -> Result<usize> {
+        let _guard = self.cycle_lock.lock().await;
+        let mut flushed = 0;
+
+        loop {
+            let pending = self.feedback_processor.get_pending_feedback().await?;
+            if pending.is_empty() {
+                break;
+            }
+            let (feedback_ids, feedback_batch): (Vec<_>, Vec<_>) = pending.into_iter().unzip();
+            self.process_feedback_batch(feedback_batch).await?;
+            self.feedback_processor
+                .mark_processed(&feedback_ids)
+                .await?;
+            flushed += feedback_ids.len();
+        }
+
+        Ok(flushed)
+    }
+
+    /// 停止调度器并等待其退出（包括进行中的学习周期），随后刷新待处理反馈
+    pub async fn stop_and_flush(&self, scheduler: Option<JoinHandle<()>>) -> Result<usize> {
+        self.shutdown();
+        if let Some(handle) = scheduler {
+            if let Err(e) = handle.await {
+                warn!("Learning scheduler terminated abnormally: {}", e);
+            }
+        }
+
+        let flushed = self.flush_pending_feedback().await?;
+        info!("Flushed {} pending feedback records", flushed);
+        Ok(flushed)
+    }
+
     /// 获取学习统计信息
     pub async fn get_stats(&self) -> LearningStats {
         self.stats.read().await.clone()
@@ -746,21 +945,106 @@ impl LearningEngine {
         updater(&mut stats);
     }
 
-    async fn get_all_memory_ids(&self) -> Result<Vec<MemoryId>> {
-        // TODO: 从数据库获取所有记忆ID
-        Ok(Vec::new())
+    fn scheduler_jitter(interval: std::time::Duration) -> std::time::Duration {
+        let fraction = (uuid::Uuid::new_v4().as_u128() % 1000) as f64 / 1000.0;
+        interval.mul_f64(fraction * 0.1)
     }
 
-    async fn get_all_connection_ids(&self) -> Result<Vec<ConnectionId>> {
-        // TODO: 从数据库获取所有连接ID
-        Ok(Vec::new())
+    /// 分页获取未删除的记忆ID，最多返回 `limit` 个
+    async fn get_all_memory_ids(
+        &self,
+        after: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<MemoryId>> {
+        let mut ids: Vec<MemoryId> = Vec::new();
+        loop {
+            let page_size = Self::next_page_size(ids.len(), limit);
+            if page_size == 0 {
+                break;
+            }
+            let cursor = ids.last().map(String::as_str).or(after);
+            let page = self.db.list_vector_ids(cursor, page_size, false).await?;
+            let exhausted = page.len() < page_size;
+            ids.extend(page);
+            if exhausted {
+                break;
+            }
+        }
+        Ok(ids)
+    }
+
+    /// 分页获取连接ID，最多返回 `limit` 个
+    async fn get_all_connection_ids(
+        &self,
+        after: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<ConnectionId>> {
+        let mut ids: Vec<ConnectionId> = Vec::new();
+        loop {
+            let page_size = Self::next_page_size(ids.len(), limit);
+            if page_size == 0 {
+                break;
+            }
+            let cursor = ids.last().map(String::as_str).or(after);
+            let page = self.db.list_edge_ids(cursor, page_size).await?;
+            let exhausted = page.len() < page_size;
+            ids.extend(page);
+            if exhausted {
+                break;
+            }
+        }
+        Ok(ids)
+    }
+
+    /// 从上个周期停下的位置取下一批记忆ID，取到末尾后下个周期从头开始
+    async fn next_memory_batch(&self) -> Result<Vec<MemoryId>> {
+        let mut cursors = self.cursors.lock().await;
+        let limit = Some(self.max_ids_per_cycle);
+        let mut ids = self
+            .get_all_memory_ids(cursors.memory.as_deref(), limit)
+            .await?;
+        if ids.is_empty() && cursors.memory.is_some() {
+            ids = self.get_all_memory_ids(None, limit).await?;
+        }
+        cursors.memory = Self::advance_cursor(&ids, self.max_ids_per_cycle);
+        Ok(ids)
+    }
+
+    /// 从上个周期停下的位置取下一批连接ID，取到末尾后下个周期从头开始
+    async fn next_connection_batch(&self) -> Result<Vec<ConnectionId>> {
+        let mut cursors = self.cursors.lock().await;
+        let limit = Some(self.max_ids_per_cycle);
+        let mut ids = self
+            .get_all_connection_ids(cursors.connection.as_deref(), limit)
+            .await?;
+        if ids.is_empty() && cursors.connection.is_some() {
+            ids = self.get_all_connection_ids(None, limit).await?;
+        }
+        cursors.connection = Self::advance_cursor(&ids, self.max_ids_per_cycle);
+        Ok(ids)
+    }
+
+    /// 取满一批时记下最后一个ID，否则说明已到末尾
+    fn advance_cursor(ids: &[String], batch_size: usize) -> Option<String> {
+        if ids.len() < batch_size {
+            None
+        } else {
+            ids.last().cloned()
+        }
+    }
+
+    fn next_page_size(fetched: usize, limit: Option<usize>) -> usize {
+        match limit {
+            Some(limit) => ID_PAGE_SIZE.min(limit.saturating_sub(fetched)),
+            None => ID_PAGE_SIZE,
+        }
     }
 }
 
 // 实现各个学习组件
 
 impl ImportanceLearner {
-    fn new(config: &Config) -> Self {
+    fn new(config: &Config, feedback_history: Arc<RwLock<VecDeque<FeedbackRecord>>>) -> Self {
         Self {
             learning_rate: config.learning.learning_rate,
             decay_factor: config.learning.importance_decay_factor,
@@ -769,6 +1053,7 @@ impl ImportanceLearner {
             feedback_weight: 0.4,
             temporal_weight: 0.2,
             connection_weight: 0.1,
+            feedback_history,
         }
     }
 
@@ -811,9 +1096,21 @@ impl ImportanceLearner {
         Ok(0)
     }
 
-    async fn get_recent_feedback_score(&self, _memory_id: &MemoryId) -> Result<f32> {
-        // TODO: 实现反馈分数获取
-        Ok(0.0)
+    async fn get_recent_feedback_score(&self, memory_id: &MemoryId) -> Result<f32> {
+        // 取最近7天内该记忆反馈分数的平均值
+        let since = Utc::now() - Duration::days(7);
+        let history = self.feedback_history.read().await;
+        let scores: Vec<f32> = history
+            .iter()
+            .filter(|f| &f.memory_id == memory_id && f.timestamp >= since)
+            .map(|f| f.score)
+            .collect();
+
+        if scores.is_empty() {
+            Ok(0.0)
+        } else {
+            Ok(scores.iter().sum::<f32>() / scores.len() as f32)
+        }
     }
 
     async fn get_average_connection_strength(&self, _memory_id: &MemoryId) -> Result<f32> {
@@ -850,27 +1147,78 @@ impl ConnectionLearner {
         Ok(self.weakening_rate)
     }
 
+    /// 基于传递关系发现新连接：A-B 与 B-C 足够强时建议创建 A-C
     async fn discover_new_connections(
         &self,
-        _connection_ids: &[ConnectionId],
+        connection_ids: &[ConnectionId],
+        db: &VectorGraphDB,
     ) -> Result<Vec<Connection>> {
-        // TODO: 发现新连接
-        Ok(Vec::new())
+        let mut seeds = Vec::new();
+        for connection_id in connection_ids {
+            match db.get_edge(connection_id).await {
+                Ok(edge) => seeds.push(edge),
+                Err(MemoryError::Database(sqlx::Error::RowNotFound)) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut existing: HashSet<(String, String)> = db
+            .get_graph_edges()
+            .await?
+            .into_iter()
+            .map(|edge| (edge.from_node, edge.to_node))
+            .collect();
+
+        let mut new_connections = Vec::new();
+        for first in &seeds {
+            for second in &seeds {
+                if first.id == second.id || first.to_node != second.from_node {
+                    continue;
+                }
+                let (from, to) = (&first.from_node, &second.to_node);
+                if from == to
+                    || existing.contains(&(from.clone(), to.clone()))
+                    || existing.contains(&(to.clone(), from.clone()))
+                {
+                    continue;
+                }
+
+                let strength = first.weight * second.weight;
+                if strength < self.creation_threshold {
+                    continue;
+                }
+
+                let now = Utc::now();
+                let mut properties = HashMap::new();
+                properties.insert(
+                    "discovered_via".to_string(),
+                    serde_json::json!([first.id, second.id]),
+                );
+                new_connections.push(Connection {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    from_memory: from.clone(),
+                    to_memory: to.clone(),
+                    connection_type: ConnectionType::Semantic,
+                    strength,
+                    created_at: now,
+                    updated_at: now,
+                    properties,
+                    bidirectional: false,
+                });
+                existing.insert((from.clone(), to.clone()));
+            }
+        }
+
+        Ok(new_connections)
     }
 
     async fn prune_weak_connections(
         &self,
         connection_ids: &[ConnectionId],
+        db: &VectorGraphDB,
     ) -> Result<Vec<ConnectionId>> {
-        // TODO: 修剪弱连接
-        let mut pruned = Vec::new();
-        for connection_id in connection_ids {
-            // 简化逻辑：随机修剪一些连接
-            if connection_id.len() % 3 == 0 {
-                pruned.push(connection_id.clone());
-            }
-        }
-        Ok(pruned)
+        db.delete_edges_below(connection_ids, self.pruning_threshold)
+            .await
     }
 }
 
@@ -916,12 +1264,13 @@ impl PreferenceLearner {
 }
 
 impl PatternDetector {
-    fn new(_config: &Config) -> Self {
+    fn new(_config: &Config, db: Arc<VectorGraphDB>) -> Self {
         Self {
             min_pattern_frequency: 3,
             temporal_window: Duration::days(7),
             semantic_similarity_threshold: 0.8,
             pattern_cache: Arc::new(RwLock::new(HashMap::new())),
+            db,
         }
     }
 
@@ -940,12 +1289,71 @@ impl PatternDetector {
         }
     }
 
+    /// 访问模式：在同一会话中被一起访问的记忆对
+    ///
+    /// 相邻交互间隔不超过 `ACCESS_SESSION_GAP` 的记录视为同一会话。共同出现的会话数
+    /// 达到 `min_pattern_frequency` 的记忆对生成一个模式，置信度为共同出现的会话数
+    /// 占两者中较少出现一方会话数的比例。
     async fn detect_access_patterns(
         &self,
-        _data_window: &(DateTime<Utc>, DateTime<Utc>),
+        data_window: &(DateTime<Utc>, DateTime<Utc>),
     ) -> Result<Vec<DetectedPattern>> {
-        // TODO: 实现访问模式检测
-        Ok(Vec::new())
+        let interactions = self
+            .db
+            .get_interactions_between(data_window.0, data_window.1)
+            .await?;
+
+        // 按时间间隔切分会话
+        let mut sessions: Vec<(DateTime<Utc>, HashSet<MemoryId>)> = Vec::new();
+        let mut last_timestamp: Option<DateTime<Utc>> = None;
+        for interaction in &interactions {
+            let same_session = last_timestamp
+                .is_some_and(|last| interaction.timestamp - last <= ACCESS_SESSION_GAP);
+            if !same_session {
+                sessions.push((interaction.timestamp, HashSet::new()));
+            }
+            if let Some((_, memories)) = sessions.last_mut() {
+                memories.insert(interaction.memory_id.clone());
+            }
+            last_timestamp = Some(interaction.timestamp);
+        }
+
+        let mut memory_sessions: HashMap<&MemoryId, u32> = HashMap::new();
+        let mut pair_sessions: HashMap<(&MemoryId, &MemoryId), Vec<DateTime<Utc>>> = HashMap::new();
+        for (started_at, memories) in &sessions {
+            let mut memories: Vec<&MemoryId> = memories.iter().collect();
+            memories.sort();
+            for (i, first) in memories.iter().enumerate() {
+                *memory_sessions.entry(*first).or_insert(0) += 1;
+                for second in &memories[i + 1..] {
+                    pair_sessions
+                        .entry((*first, *second))
+                        .or_default()
+                        .push(*started_at);
+                }
+            }
+        }
+
+        let mut patterns: Vec<DetectedPattern> = pair_sessions
+            .into_iter()
+            .filter(|(_, seen_at)| seen_at.len() as u32 >= self.min_pattern_frequency)
+            .map(|((first, second), seen_at)| {
+                let frequency = seen_at.len() as u32;
+                let base = memory_sessions[first].min(memory_sessions[second]);
+                DetectedPattern {
+                    pattern_id: format!("access:{}:{}", first, second),
+                    pattern_type: PatternType::AccessPattern,
+                    frequency,
+                    confidence: frequency as f32 / base.max(1) as f32,
+                    elements: vec![first.clone(), second.clone()],
+                    temporal_signature: None,
+                    first_detected: seen_at[0],
+                    last_seen: seen_at[seen_at.len() - 1],
+                }
+            })
+            .collect();
+        patterns.sort_by(|a, b| a.pattern_id.cmp(&b.pattern_id));
+        Ok(patterns)
     }
 
     async fn detect_query_patterns(
@@ -964,12 +1372,77 @@ impl PatternDetector {
         Ok(Vec::new())
     }
 
+    /// 时间模式：交互集中的小时与星期（UTC）
+    ///
+    /// 交互次数达到 `min_pattern_frequency` 且不低于峰值 `TEMPORAL_PEAK_RATIO` 倍的
+    /// 小时与星期被视为活跃时段，置信度为落在活跃小时内的交互占比。
     async fn detect_temporal_patterns(
         &self,
-        _data_window: &(DateTime<Utc>, DateTime<Utc>),
+        data_window: &(DateTime<Utc>, DateTime<Utc>),
     ) -> Result<Vec<DetectedPattern>> {
-        // TODO: 实现时间模式检测
-        Ok(Vec::new())
+        let interactions = self
+            .db
+            .get_interactions_between(data_window.0, data_window.1)
+            .await?;
+        if interactions.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut hour_counts = [0u32; 24];
+        let mut day_counts = [0u32; 7];
+        for interaction in &interactions {
+            hour_counts[interaction.timestamp.hour() as usize] += 1;
+            day_counts[interaction.timestamp.weekday().num_days_from_monday() as usize] += 1;
+        }
+
+        let active = |counts: &[u32]| -> Vec<u8> {
+            let peak = counts.iter().copied().max().unwrap_or(0) as f32;
+            counts
+                .iter()
+                .enumerate()
+                .filter(|&(_, &count)| {
+                    count >= self.min_pattern_frequency
+                        && count as f32 >= peak * TEMPORAL_PEAK_RATIO
+                })
+                .map(|(index, _)| index as u8)
+                .collect()
+        };
+        let preferred_hours = active(&hour_counts);
+        if preferred_hours.is_empty() {
+            return Ok(Vec::new());
+        }
+        let preferred_days = active(&day_counts);
+
+        let frequency: u32 = preferred_hours
+            .iter()
+            .map(|&hour| hour_counts[hour as usize])
+            .sum();
+        let total = interactions.len() as f32;
+        let frequency_distribution = hour_counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(hour, &count)| (format!("hour_{}", hour), count as f32 / total))
+            .collect();
+
+        Ok(vec![DetectedPattern {
+            pattern_id: "temporal:active_hours".to_string(),
+            pattern_type: PatternType::TemporalPattern,
+            frequency,
+            confidence: frequency as f32 / total,
+            elements: preferred_hours
+                .iter()
+                .map(|hour| format!("hour_{}", hour))
+                .collect(),
+            temporal_signature: Some(TemporalSignature {
+                preferred_hours,
+                preferred_days,
+                session_patterns: Vec::new(),
+                frequency_distribution,
+            }),
+            first_detected: interactions[0].timestamp,
+            last_seen: interactions[interactions.len() - 1].timestamp,
+        }])
     }
 
     async fn detect_connection_patterns(
@@ -1003,13 +1476,14 @@ struct FeedbackProcessingResult {
 }
 
 impl FeedbackProcessor {
-    fn new(_config: &Config) -> Self {
+    fn new(_config: &Config, db: Arc<VectorGraphDB>) -> Self {
         Self {
             positive_threshold: 0.6,
             negative_threshold: 0.4,
             implicit_feedback_weight: 0.3,
             explicit_feedback_weight: 1.0,
             feedback_history: Arc::new(RwLock::new(VecDeque::new())),
+            db,
         }
     }
 
@@ -1041,46 +1515,84 @@ impl FeedbackProcessor {
         Ok(FeedbackProcessingResult { applied, impact })
     }
 
+    /// 将反馈写入持久化队列，等待学习周期处理
     async fn queue_feedback(&self, feedback: FeedbackRecord) -> Result<()> {
-        let mut history = self.feedback_history.write().await;
-        history.push_back(feedback);
-        Ok(())
+        let id = uuid::Uuid::new_v4().to_string();
+        self.db
+            .insert_feedback(&id, &feedback.memory_id, &serde_json::to_value(&feedback)?)
+            .await
     }
 
-    async fn get_pending_feedback(&self) -> Result<Vec<FeedbackRecord>> {
-        let history = self.feedback_history.read().await;
-        // 返回最近的隐式反馈进行批处理
-        Ok(history
-            .iter()
-            .filter(|f| !matches!(f.feedback_type, FeedbackType::Explicit))
-            .take(100)
-            .cloned()
-            .collect())
+    /// 按加入顺序读取未处理的反馈，返回 `(反馈ID, 反馈)`
+    async fn get_pending_feedback(&self) -> Result<Vec<(String, FeedbackRecord)>> {
+        self.db
+            .get_unprocessed_feedback(PENDING_FEEDBACK_BATCH_SIZE)
+            .await?
+            .into_iter()
+            .map(|(id, record)| -> Result<(String, FeedbackRecord)> {
+                Ok((id, serde_json::from_value(record)?))
+            })
+            .collect()
+    }
+
+    /// 将已处理的反馈从待处理队列中移除
+    async fn mark_processed(&self, ids: &[String]) -> Result<()> {
+        self.db.mark_feedback_processed(ids).await
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::database::VectorGraphDB;
+    use crate::core_types::{InteractionRecord, InteractionType};
+    use crate::database::{GraphNode, VectorGraphDB};
     use tempfile::NamedTempFile;
 
     async fn create_test_learning_engine() -> LearningEngine {
-        let temp_file = NamedTempFile::new().unwrap();
-        let db_url = format!("sqlite://{}", temp_file.path().display());
+        create_test_learning_engine_with(|_| {}).await
+    }
 
-        let config = Config {
+    async fn create_test_learning_engine_with<F>(customize: F) -> LearningEngine
+    where
+        F: FnOnce(&mut Config),
+    {
+        let db_path = NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let db_url = format!("sqlite://{}", db_path.display());
+
+        let mut config = Config {
             database: crate::config::DatabaseConfig {
                 url: db_url,
                 ..Default::default()
             },
             ..Default::default()
         };
+        customize(&mut config);
 
         let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
         LearningEngine::new(db, config).await.unwrap()
     }
 
+    fn create_test_feedback(memory_id: &str, score: f32) -> FeedbackRecord {
+        FeedbackRecord {
+            memory_id: memory_id.to_string(),
+            feedback_type: FeedbackType::Explicit,
+            score,
+            context: FeedbackContext {
+                query: "测试查询".to_string(),
+                result_position: 0,
+                session_id: "session_1".to_string(),
+                device_type: None,
+                time_of_day: 14,
+                day_of_week: 1,
+            },
+            timestamp: Utc::now(),
+        }
+    }
+
     #[tokio::test]
     async fn test_importance_adjustment() {
         let engine = create_test_learning_engine().await;
@@ -1095,6 +1607,349 @@ mod tests {
         assert!(result.success);
     }
 
+    #[tokio::test]
+    async fn test_user_feedback_increases_stored_importance() {
+        let engine = create_test_learning_engine_with(|config| {
+            config.learning.learning_rate = 0.5;
+        })
+        .await;
+
+        let mut metadata = HashMap::new();
+        metadata.insert("importance".to_string(), serde_json::json!(0.5));
+        let vector = crate::database::Vector {
+            id: "memory_fb".to_string(),
+            embedding: vec![0.1, 0.2, 0.3],
+            metadata,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        engine.db.insert_vector(&vector).await.unwrap();
+
+        engine
+            .record_feedback(create_test_feedback("memory_fb", 0.8))
+            .await
+            .unwrap();
+
+        let task = LearningTask::ImportanceAdjustment {
+            memory_ids: vec!["memory_fb".to_string(), "missing_memory".to_string()],
+            trigger: ImportanceTrigger::UserFeedback,
+        };
+        let result = engine.execute_learning_task(task).await.unwrap();
+        assert_eq!(result.changes_made, 1);
+
+        // 绕过缓存，确认数据库中的值已更新
+        engine.db.clear_cache().await;
+        let stored = engine.db.get_vector("memory_fb").await.unwrap();
+        let importance = stored.metadata["importance"].as_f64().unwrap();
+        assert!(importance > 0.5);
+        assert!(importance <= 1.0);
+    }
+
+    async fn insert_test_edge(
+        engine: &LearningEngine,
+        id: &str,
+        from: &str,
+        to: &str,
+        weight: f32,
+    ) {
+        // 边的两端受外键约束，需要先有对应节点
+        for node_id in [from, to] {
+            let node = GraphNode {
+                id: node_id.to_string(),
+                node_type: "memory".to_string(),
+                properties: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            engine.db.upsert_node(&node).await.unwrap();
+        }
+        let edge = GraphEdge {
+            id: id.to_string(),
+            from_node: from.to_string(),
+            to_node: to.to_string(),
+            edge_type: "Semantic".to_string(),
+            weight,
+            properties: HashMap::new(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        engine.db.insert_edge(&edge).await.unwrap();
+    }
+
+    async fn evolve(
+        engine: &LearningEngine,
+        ids: &[&str],
+        evolution_type: ConnectionEvolutionType,
+    ) -> LearningResult {
+        let task = LearningTask::ConnectionEvolution {
+            connection_ids: ids.iter().map(|id| id.to_string()).collect(),
+            evolution_type,
+        };
+        engine.execute_learning_task(task).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_strengthen_connection_persists_weight() {
+        let engine = create_test_learning_engine().await;
+        insert_test_edge(&engine, "edge_s", "a", "b", 0.5).await;
+        insert_test_edge(&engine, "edge_max", "a", "c", 0.95).await;
+
+        let result = evolve(
+            &engine,
+            &["edge_s", "edge_max", "missing"],
+            ConnectionEvolutionType::Strengthen,
+        )
+        .await;
+        assert_eq!(result.changes_made, 2);
+
+        engine.db.clear_cache().await;
+        let edge = engine.db.get_edge("edge_s").await.unwrap();
+        assert!((edge.weight - 0.6).abs() < 1e-5);
+        let edge = engine.db.get_edge("edge_max").await.unwrap();
+        assert!((edge.weight - 1.0).abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_weaken_connection_persists_weight() {
+        let engine = create_test_learning_engine().await;
+        insert_test_edge(&engine, "edge_w", "a", "b", 0.5).await;
+        insert_test_edge(&engine, "edge_min", "a", "c", 0.02).await;
+
+        let result = evolve(
+            &engine,
+            &["edge_w", "edge_min"],
+            ConnectionEvolutionType::Weaken,
+        )
+        .await;
+        assert_eq!(result.changes_made, 2);
+
+        engine.db.clear_cache().await;
+        let edge = engine.db.get_edge("edge_w").await.unwrap();
+        assert!((edge.weight - 0.45).abs() < 1e-5);
+        let edge = engine.db.get_edge("edge_min").await.unwrap();
+        assert!(edge.weight.abs() < 1e-5);
+    }
+
+    #[tokio::test]
+    async fn test_prune_removes_weak_connections() {
+        let engine = create_test_learning_engine().await;
+        insert_test_edge(&engine, "edge_weak", "a", "b", 0.05).await;
+        insert_test_edge(&engine, "edge_strong", "a", "c", 0.8).await;
+
+        let result = evolve(
+            &engine,
+            &["edge_weak", "edge_strong"],
+            ConnectionEvolutionType::Prune,
+        )
+        .await;
+        assert_eq!(result.changes_made, 1);
+
+        let edges = engine.db.get_graph_edges().await.unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].id, "edge_strong");
+    }
+
+    #[tokio::test]
+    async fn test_create_inserts_discovered_connections() {
+        let engine = create_test_learning_engine().await;
+        insert_test_edge(&engine, "edge_ab", "a", "b", 0.9).await;
+        insert_test_edge(&engine, "edge_bc", "b", "c", 0.9).await;
+
+        let result = evolve(
+            &engine,
+            &["edge_ab", "edge_bc"],
+            ConnectionEvolutionType::Create,
+        )
+        .await;
+        assert_eq!(result.changes_made, 1);
+
+        let edges = engine.db.get_graph_edges().await.unwrap();
+        assert_eq!(edges.len(), 3);
+        let created = edges
+            .iter()
+            .find(|e| e.from_node == "a" && e.to_node == "c")
+            .unwrap();
+        assert!((created.weight - 0.81).abs() < 1e-5);
+
+        // 已存在的连接不会重复创建
+        let result = evolve(
+            &engine,
+            &["edge_ab", "edge_bc"],
+            ConnectionEvolutionType::Create,
+        )
+        .await;
+        assert_eq!(result.changes_made, 0);
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_runs_repeatedly_until_shutdown() {
+        let engine = Arc::new(create_test_learning_engine().await);
+
+        let handle = engine
+            .clone()
+            .spawn_scheduler(std::time::Duration::from_millis(10));
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        engine.shutdown();
+        tokio::time::timeout(std::time::Duration::from_secs(5), handle)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // 每个学习周期至少执行3个任务
+        let stats = engine.get_stats().await;
+        assert!(stats.total_learning_cycles >= 6);
+
+        // 关闭后不再执行新的周期
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        assert_eq!(
+            engine.get_stats().await.total_learning_cycles,
+            stats.total_learning_cycles
+        );
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_checkpoints_vector_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_path = dir.path().join("vectors.ivf");
+        let engine = Arc::new(
+            create_test_learning_engine_with(|config| {
+                config.vector.dimension = 4;
+                config.vector.index_type = crate::config::VectorIndexType::IVF;
+                config.vector.index_params.nlist = Some(2);
+                config.vector.index_path = Some(index_path.clone());
+            })
+            .await,
+        );
+        for i in 0..8 {
+            engine
+                .db
+                .insert_vector(&crate::database::Vector {
+                    id: format!("v{}", i),
+                    embedding: vec![i as f32, 1.0, 0.0, 0.5],
+                    metadata: HashMap::new(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let handle = engine
+            .clone()
+            .spawn_scheduler(std::time::Duration::from_millis(10));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        engine.stop_and_flush(Some(handle)).await.unwrap();
+        assert!(index_path.exists());
+
+        // 检查点之后没有写入，重新打开时直接加载索引
+        let reopened = VectorGraphDB::new(engine.config.clone()).await.unwrap();
+        assert_eq!(reopened.index_rebuilds(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stop_and_flush_awaits_scheduler_and_flushes_feedback() {
+        let engine = Arc::new(create_test_learning_engine().await);
+        let handle = engine
+            .clone()
+            .spawn_scheduler(std::time::Duration::from_secs(3600));
+
+        for memory_id in ["memory_1", "memory_2", "memory_3"] {
+            let feedback = FeedbackRecord {
+                feedback_type: FeedbackType::Click,
+                ..create_test_feedback(memory_id, 0.7)
+            };
+            engine.record_feedback(feedback).await.unwrap();
+        }
+
+        let flushed = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            engine.stop_and_flush(Some(handle)),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(flushed, 3);
+        assert_eq!(engine.get_stats().await.feedback_processed, 3);
+        assert!(engine
+            .feedback_processor
+            .get_pending_feedback()
+            .await
+            .unwrap()
+            .is_empty());
+        // 调度器已退出，不会再执行学习周期
+        assert_eq!(engine.get_stats().await.total_learning_cycles, 0);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_ids_match_inserted_rows() {
+        let engine = create_test_learning_engine().await;
+
+        for i in 0..5 {
+            let mut metadata = HashMap::new();
+            metadata.insert("is_deleted".to_string(), serde_json::json!(i == 4));
+            let vector = crate::database::Vector {
+                id: format!("memory_{}", i),
+                embedding: vec![0.1, 0.2],
+                metadata,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            engine.db.insert_vector(&vector).await.unwrap();
+        }
+        insert_test_edge(&engine, "edge_1", "memory_0", "memory_1", 0.5).await;
+        insert_test_edge(&engine, "edge_2", "memory_1", "memory_2", 0.5).await;
+
+        let memory_ids = engine.get_all_memory_ids(None, None).await.unwrap();
+        assert_eq!(
+            memory_ids,
+            vec!["memory_0", "memory_1", "memory_2", "memory_3"]
+        );
+
+        let limited = engine.get_all_memory_ids(None, Some(2)).await.unwrap();
+        assert_eq!(limited, vec!["memory_0", "memory_1"]);
+        let after = engine
+            .get_all_memory_ids(Some("memory_1"), None)
+            .await
+            .unwrap();
+        assert_eq!(after, vec!["memory_2", "memory_3"]);
+
+        let connection_ids = engine.get_all_connection_ids(None, None).await.unwrap();
+        assert_eq!(connection_ids, vec!["edge_1", "edge_2"]);
+    }
+
+    #[tokio::test]
+    async fn test_learning_cycles_page_past_the_cap() {
+        let mut engine = create_test_learning_engine().await;
+        engine.max_ids_per_cycle = 3;
+
+        for i in 0..7 {
+            let vector = crate::database::Vector {
+                id: format!("memory_{}", i),
+                embedding: vec![0.1, 0.2],
+                metadata: HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            };
+            engine.db.insert_vector(&vector).await.unwrap();
+        }
+
+        engine.run_learning_cycle().await.unwrap();
+        assert_eq!(
+            engine.cursors.lock().await.memory.as_deref(),
+            Some("memory_2")
+        );
+
+        // 后续批次接着上个周期的位置，到末尾后回到开头
+        let mut batches = Vec::new();
+        for _ in 0..3 {
+            batches.push(engine.next_memory_batch().await.unwrap());
+        }
+        assert_eq!(batches[0], vec!["memory_3", "memory_4", "memory_5"]);
+        assert_eq!(batches[1], vec!["memory_6"]);
+        assert_eq!(batches[2], vec!["memory_0", "memory_1", "memory_2"]);
+    }
+
     #[tokio::test]
     async fn test_pattern_detection() {
         let engine = create_test_learning_engine().await;
@@ -1110,6 +1965,67 @@ mod tests {
         assert!(result.success);
     }
 
+    #[tokio::test]
+    async fn test_detects_temporal_and_access_patterns_from_interactions() {
+        let engine = create_test_learning_engine().await;
+
+        let record = |memory_id: &str, timestamp: DateTime<Utc>| InteractionRecord {
+            memory_id: memory_id.to_string(),
+            interaction_type: InteractionType::Browse,
+            timestamp,
+            feedback_score: None,
+            dwell_time: None,
+        };
+        // 连续四天下午两点先后访问 a 和 b，另有一次凌晨三点的零散访问
+        let now = Utc::now();
+        for days_ago in 1..=4 {
+            let afternoon = (now - Duration::days(days_ago))
+                .date_naive()
+                .and_hms_opt(14, 0, 0)
+                .unwrap()
+                .and_utc();
+            engine
+                .db
+                .insert_interaction(&record("a", afternoon))
+                .await
+                .unwrap();
+            engine
+                .db
+                .insert_interaction(&record("b", afternoon + Duration::minutes(5)))
+                .await
+                .unwrap();
+        }
+        let night = (now - Duration::days(2))
+            .date_naive()
+            .and_hms_opt(3, 0, 0)
+            .unwrap()
+            .and_utc();
+        engine
+            .db
+            .insert_interaction(&record("c", night))
+            .await
+            .unwrap();
+
+        let task = LearningTask::PatternDetection {
+            data_window: (now - Duration::days(7), now),
+            pattern_types: vec![PatternType::TemporalPattern, PatternType::AccessPattern],
+        };
+        let result = engine.execute_learning_task(task).await.unwrap();
+        assert_eq!(result.changes_made, 2);
+
+        let cache = engine.pattern_detector.pattern_cache.read().await;
+        let temporal = &cache["temporal:active_hours"];
+        let signature = temporal.temporal_signature.as_ref().unwrap();
+        assert_eq!(signature.preferred_hours, vec![14]);
+        assert_eq!(temporal.frequency, 8);
+        assert!((temporal.confidence - 8.0 / 9.0).abs() < 1e-6);
+
+        let access = &cache["access:a:b"];
+        assert_eq!(access.frequency, 4);
+        assert_eq!(access.elements, vec!["a", "b"]);
+        assert!((access.confidence - 1.0).abs() < 1e-6);
+    }
+
     #[tokio::test]
     async fn test_feedback_processing() {
         let engine = create_test_learning_engine().await;
@@ -1140,6 +2056,53 @@ mod tests {
         assert!(result.success);
     }
 
+    #[tokio::test]
+    async fn test_pending_feedback_survives_restart() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        {
+            let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+            let engine = LearningEngine::new(db, config.clone()).await.unwrap();
+            for (memory_id, feedback_type) in [
+                ("memory_1", FeedbackType::Click),
+                ("memory_2", FeedbackType::DwellTime),
+            ] {
+                let feedback = FeedbackRecord {
+                    feedback_type,
+                    ..create_test_feedback(memory_id, 0.7)
+                };
+                engine.record_feedback(feedback).await.unwrap();
+            }
+        }
+
+        // 模拟重启：在同一个数据库上重新创建学习引擎
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let engine = LearningEngine::new(db, config).await.unwrap();
+        let pending = engine
+            .feedback_processor
+            .get_pending_feedback()
+            .await
+            .unwrap();
+        let memory_ids: Vec<_> = pending.iter().map(|(_, f)| f.memory_id.as_str()).collect();
+        assert_eq!(memory_ids, vec!["memory_1", "memory_2"]);
+
+        engine.run_learning_cycle().await.unwrap();
+        assert_eq!(engine.get_stats().await.feedback_processed, 2);
+        assert!(engine
+            .feedback_processor
+            .get_pending_feedback()
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
     #[tokio::test]
     async fn test_learning_cycle() {
         let engine = create_test_learning_engine().await;
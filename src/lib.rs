@@ -72,21 +72,29 @@
 //!
 //! ## 配置
 //!
-//! 系统支持通过配置文件或环境变量进行配置：
+//! 系统支持通过配置文件或环境变量进行配置（见 `Config::from_toml_path` 与
+//! `Config::from_env`），缺失的键使用默认值：
 //!
 //! ```toml
-//! [memory]
-//! database_url = "sqlite:memory.db"
-//! cache_size = 1000
+//! [database]
+//! url = "sqlite:memory.db"
+//!
+//! [cache]
+//! lru_capacity = 1000
+//!
+//! [vector]
+//! similarity_threshold = 0.7
 //!
 //! [retrieval]
-//! semantic_threshold = 0.7
 //! max_results = 50
 //!
 //! [learning]
 //! learning_rate = 0.01
-//! decay_rate = 0.001
+//! importance_decay_factor = 0.95
 //! ```
+//!
+//! 环境变量以 `RWKV_AGENT_KIT` 为前缀、以 `__` 分隔层级，并覆盖文件中的值，
+//! 例如 `RWKV_AGENT_KIT__VECTOR__SIMILARITY_THRESHOLD=0.8`。
 
 pub mod agent;
 pub mod agents;
@@ -95,22 +103,35 @@ pub mod core;
 pub mod core_types;
 pub mod database;
 pub mod db;
+pub mod entity;
 pub mod error;
 pub mod learning;
 pub mod memory;
 pub mod retrieval;
 pub mod rwkv;
 pub mod rwkv_agent_kit;
+pub mod telemetry;
 pub mod utils;
+pub mod vector_index;
 
 #[cfg(feature = "examples")]
 pub mod examples;
 
+#[cfg(feature = "server")]
+pub mod server;
+
+#[cfg(feature = "mcp")]
+pub mod mcp;
+
 // 重新导出核心类型（避免命名冲突）
 pub use database::VectorGraphDB;
 pub use error::{MemoryError, Result};
 pub use memory::MemoryManager;
 pub use rwkv_agent_kit::{RwkvAgentKit, RwkvAgentKitBuilder, RwkvAgentKitConfig};
+#[cfg(feature = "metrics")]
+pub use telemetry::metrics_handle;
+#[cfg(feature = "tracing")]
+pub use telemetry::init_tracing;
 
 /// 预导入模块，包含最常用的类型和函数
 pub mod prelude {
@@ -168,4 +189,15 @@ mod tests {
         let result = VectorGraphDB::new(config).await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_invalid_config_rejected() {
+        use crate::prelude::*;
+
+        let mut config = crate::config::Config::default();
+        config.database.url = "sqlite::memory:".to_string();
+        config.vector.similarity_threshold = 2.0;
+        let err = VectorGraphDB::new(config).await.unwrap_err();
+        assert!(err.to_string().contains("vector.similarity_threshold"));
+    }
 }
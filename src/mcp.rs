@@ -0,0 +1,274 @@
+//! MCP（Model Context Protocol）工具服务
+//!
+//! 启用 `mcp` 特性后可用，将 [`ToolRegistry`](crate::core::tools::ToolRegistry) 中注册的工具
+//! 以 MCP 工具的形式暴露给外部 LLM 客户端。消息为按行分隔的 JSON-RPC 2.0，
+//! 支持 `initialize`、`ping`、`tools/list` 与 `tools/call`。
+
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+use crate::core::tools::{SharedToolRegistry, ToolError};
+use crate::error::Result;
+
+/// 服务端声明的 MCP 协议版本
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// JSON-RPC 错误码
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// MCP 传输层：从 `reader` 逐行读取请求，向 `writer` 逐行写出响应
+pub struct McpTransport<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl McpTransport<BufReader<tokio::io::Stdin>, tokio::io::Stdout> {
+    /// 基于标准输入输出的传输
+    pub fn stdio() -> Self {
+        Self::new(tokio::io::stdin(), tokio::io::stdout())
+    }
+}
+
+impl<R: AsyncRead + Unpin, W: AsyncWrite + Unpin> McpTransport<BufReader<R>, W> {
+    /// 基于任意异步读写流的传输
+    pub fn new(reader: R, writer: W) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            writer,
+        }
+    }
+}
+
+/// 在给定传输上提供 MCP 工具服务，直到对端关闭输入
+pub async fn serve_tools<R, W>(
+    registry: SharedToolRegistry,
+    transport: McpTransport<R, W>,
+) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let McpTransport { reader, mut writer } = transport;
+    let mut lines = reader.lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = handle_message(&registry, &line).await {
+            let mut encoded = serde_json::to_string(&response)?;
+            encoded.push('\n');
+            writer.write_all(encoded.as_bytes()).await?;
+            writer.flush().await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// 处理一条 JSON-RPC 消息；通知（无 `id`）不返回响应
+async fn handle_message(registry: &SharedToolRegistry, line: &str) -> Option<Value> {
+    let message: Value = match serde_json::from_str(line) {
+        Ok(message) => message,
+        Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string())),
+    };
+
+    let id = message.get("id").cloned();
+    let Some(method) = message.get("method").and_then(Value::as_str) else {
+        return Some(error_response(
+            id.unwrap_or(Value::Null),
+            INVALID_REQUEST,
+            "missing method".to_string(),
+        ));
+    };
+    let id = id?;
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => Ok(json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": crate::NAME, "version": crate::VERSION },
+        })),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(list_tools(registry).await),
+        "tools/call" => call_tool(registry, &params).await,
+        other => Err((METHOD_NOT_FOUND, format!("Method not found: {}", other))),
+    };
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, message),
+    })
+}
+
+/// `tools/list`：按名称排序列出所有注册的工具
+async fn list_tools(registry: &SharedToolRegistry) -> Value {
+    let registry = registry.read().await;
+    let mut tools: Vec<Value> = registry
+        .tools
+        .values()
+        .map(|tool| {
+            json!({
+                "name": tool.name(),
+                "description": tool.description(),
+                "inputSchema": tool.input_schema(),
+            })
+        })
+        .collect();
+    tools.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    json!({ "tools": tools })
+}
+
+/// `tools/call`：调度到注册表执行
+///
+/// 未知工具按协议返回 JSON-RPC 错误；工具自身的执行错误以 `isError: true` 的结果返回，
+/// 以便客户端模型看到错误信息。
+async fn call_tool(
+    registry: &SharedToolRegistry,
+    params: &Value,
+) -> std::result::Result<Value, (i64, String)> {
+    let name = params
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| (INVALID_PARAMS, "missing tool name".to_string()))?;
+    let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+    let registry = registry.read().await;
+    Ok(match registry.execute_tool(name, arguments).await {
+        Ok(output) => {
+            let text = match output {
+                Value::String(text) => text,
+                other => other.to_string(),
+            };
+            json!({ "content": [{ "type": "text", "text": text }], "isError": false })
+        }
+        Err(ToolError::NotFound(name)) => {
+            return Err((INVALID_PARAMS, format!("Unknown tool: {}", name)))
+        }
+        Err(e) => json!({
+            "content": [{ "type": "text", "text": e.to_string() }],
+            "isError": true,
+        }),
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::tools::{Tool, ToolRegistry};
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    struct EchoTool;
+
+    #[async_trait::async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn description(&self) -> &'static str {
+            "Echo the given text"
+        }
+
+        fn input_schema(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": { "text": { "type": "string" } },
+                "required": ["text"],
+            })
+        }
+
+        async fn execute(&self, params: Value) -> anyhow::Result<Value> {
+            match params.get("text").and_then(Value::as_str) {
+                Some(text) => Ok(Value::String(text.to_string())),
+                None => Err(anyhow::anyhow!("missing text")),
+            }
+        }
+    }
+
+    async fn next_response<R: AsyncBufRead + Unpin>(
+        responses: &mut tokio::io::Lines<R>,
+    ) -> Value {
+        let line = responses.next_line().await.unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tools_list_and_call() {
+        let mut registry = ToolRegistry::default();
+        registry.register(EchoTool).unwrap();
+        let registry = Arc::new(RwLock::new(registry));
+
+        let (client, server) = tokio::io::duplex(4096);
+        let (server_read, server_write) = tokio::io::split(server);
+        let server = tokio::spawn(serve_tools(
+            registry,
+            McpTransport::new(server_read, server_write),
+        ));
+
+        let (client_read, mut client_write) = tokio::io::split(client);
+        let mut responses = BufReader::new(client_read).lines();
+
+        for message in [
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "tools/list" }),
+            json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+            json!({
+                "jsonrpc": "2.0", "id": 2, "method": "tools/call",
+                "params": { "name": "echo", "arguments": { "text": "hello" } },
+            }),
+            json!({
+                "jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                "params": { "name": "echo", "arguments": {} },
+            }),
+            json!({
+                "jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                "params": { "name": "missing" },
+            }),
+        ] {
+            let line = format!("{}\n", message);
+            client_write.write_all(line.as_bytes()).await.unwrap();
+        }
+
+        let listed = next_response(&mut responses).await;
+        assert_eq!(listed["id"], 1);
+        let tools = listed["result"]["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["name"], "echo");
+        assert_eq!(tools[0]["description"], "Echo the given text");
+        assert_eq!(tools[0]["inputSchema"]["required"], json!(["text"]));
+
+        // 通知没有响应，下一条即为 id 2
+        let called = next_response(&mut responses).await;
+        assert_eq!(called["id"], 2);
+        assert_eq!(called["result"]["isError"], false);
+        assert_eq!(called["result"]["content"][0]["text"], "hello");
+
+        let failed = next_response(&mut responses).await;
+        assert_eq!(failed["id"], 3);
+        assert_eq!(failed["result"]["isError"], true);
+        assert_eq!(
+            failed["result"]["content"][0]["text"],
+            "Tool 'echo' failed: missing text"
+        );
+
+        let unknown = next_response(&mut responses).await;
+        assert_eq!(unknown["id"], 4);
+        assert_eq!(unknown["error"]["code"], INVALID_PARAMS);
+
+        client_write.shutdown().await.unwrap();
+        server.await.unwrap().unwrap();
+    }
+}
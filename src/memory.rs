@@ -6,19 +6,128 @@
 use crate::config::Config;
 use crate::core_types::{
     Connection, ConnectionId, ConnectionType, Context, EvolutionTrigger, Interaction,
-    InteractionType, Memory, MemoryAttributes, MemoryConnections, MemoryId, MemoryMetadata,
-    MemoryType, Priority, Query, QueryFilters, QueryType, QueryWeights, UpdateType,
+    InteractionRecord, Memory, MemoryAttributes, MemoryConnections, MemoryId, MemoryMetadata,
+    MemoryType, Priority, Query, QueryFilters, QueryType, QueryWeights, SortBy, UpdateType,
 };
 use crate::database::{
-    GraphEdge, GraphNode, GraphQueryRequest, Vector, VectorGraphDB, VectorQueryRequest,
+    GraphEdge, GraphNode, GraphQueryRequest, MetadataCondition, MetadataSortKey, TagFilter,
+    TraversalMode, Vector, VectorGraphDB, VectorQueryRequest, BIDIRECTIONAL_PROPERTY,
 };
+use crate::db::embedding::{Embedder, EmbeddingService};
 use crate::error::{MemoryError, Result};
+use crate::retrieval::{
+    apply_emotion_boost, resolve_similarity_threshold, sort_results, DetailedExplanation,
+    HippoRAGRetriever, RetrievalCache, RetrievalConstraints, RetrievalContext, RetrievalStrategy,
+};
+use crate::telemetry;
+use crate::utils::{chunk_text, TextUtils, TimeUtils, VectorUtils};
+use async_trait::async_trait;
 use chrono::{DateTime, Duration, Utc};
 use log::{debug, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// 记忆嵌入使用的模型，同时作为嵌入缓存键中的模型 ID
+const MEMORY_EMBEDDING_MODEL: &str = "minishlab/potion-multilingual-128M";
+
+/// 导入时分页读取已有ID的页大小
+const IMPORT_ID_PAGE_SIZE: usize = 1000;
+
+/// 累计统计计数写回数据库的最小间隔（秒）
+const STATS_PERSIST_INTERVAL_SECS: i64 = 60;
+
+/// 重新计算重要性时访问次数的饱和尺度，因子为 1 - exp(-访问次数/尺度)
+const IMPORTANCE_ACCESS_SCALE: f32 = 10.0;
+
+/// 重新计算重要性时连接度数的饱和尺度，因子为 1 - exp(-度数/尺度)
+const IMPORTANCE_DEGREE_SCALE: f32 = 5.0;
+
+/// 文档切分时每个片段的最大 token 数
+const DOCUMENT_CHUNK_MAX_TOKENS: usize = 256;
+
+/// 文档相邻片段之间重叠的 token 数
+const DOCUMENT_CHUNK_OVERLAP: usize = 32;
+
+/// 中文因果标记词
+const ZH_CAUSAL_MARKERS: &[&str] = &["因为", "所以", "导致", "引起", "造成", "由于", "结果"];
+
+/// 英文因果标记词（按单词边界匹配）
+const EN_CAUSAL_MARKERS: &[&str] = &[
+    "because",
+    "so",
+    "therefore",
+    "thus",
+    "hence",
+    "since",
+    "due to",
+    "caused",
+    "causes",
+    "cause",
+    "leads to",
+    "led to",
+    "lead to",
+    "results in",
+    "resulted in",
+    "as a result",
+];
+
+/// 中文主题切分时作为分隔的虚词
+const ZH_THEME_STOP_CHARS: &[char] = &[
+    '的', '了', '是', '在', '和', '与', '或', '也', '就', '都', '而', '着', '把', '被', '我', '你',
+    '他', '她', '它', '们', '这', '那', '吗', '呢', '吧', '啊',
+];
+
+/// 英文主题提取的停用词
+const EN_THEME_STOPWORDS: &[&str] = &[
+    "the", "and", "that", "this", "with", "from", "have", "has", "had", "were", "was", "are",
+    "been", "will", "would", "could", "should", "about", "into", "than", "then", "them", "they",
+    "their", "there", "what", "when", "which", "while", "because", "since",
+];
+
+/// 主题提取返回的最大主题数
+const MAX_THEMES: usize = 10;
+
+/// 英文主题词的最小长度
+const MIN_EN_THEME_LEN: usize = 4;
+
+/// 中文片段整体作为主题的最大长度，更长的片段按字二元组切分
+const MAX_CJK_THEME_LEN: usize = 4;
+
+/// 属性提取器
+///
+/// 通常由 LLM 智能体实现（见 `agents::AgentAttributeExtractor`），返回包含
+/// `keywords`、`tags`、`context`、`emotion`、`importance` 字段的 JSON 回复。
+/// 回复无法解析时记忆管理器会退回到内置的简单提取。
+#[async_trait]
+pub trait AttributeExtractor: Send + Sync + std::fmt::Debug {
+    /// 提取记忆属性，返回模型的原始 JSON 回复
+    async fn extract(&self, content: &str, context: &Context) -> Result<String>;
+}
+
+/// 属性提取器返回的 JSON 结构
+#[derive(Debug, Deserialize)]
+struct ExtractedAttributes {
+    #[serde(default)]
+    keywords: Vec<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    context: Option<String>,
+    #[serde(default)]
+    emotion: Option<String>,
+    #[serde(default)]
+    importance: Option<f32>,
+}
+
+/// 按重要性权重 `w` 混合语义相似度与记忆重要性：`sim * (1 - w) + importance * w`
+///
+/// `w = 0` 时为纯语义排序。
+fn blend_importance(similarity: f32, importance: f32, weight: f32) -> f32 {
+    similarity * (1.0 - weight) + importance * weight
+}
 
 /// 记忆管理器
 ///
@@ -32,10 +141,36 @@ pub struct MemoryManager {
     evolution_engine: EvolutionEngine,
     retrieval_engine: RetrievalEngine,
     stats: Arc<RwLock<MemoryStats>>,
+    /// 累计计数最近一次写回数据库的时间
+    stats_persisted_at: Arc<RwLock<DateTime<Utc>>>,
+    /// 关联的检索缓存，记忆写入时使其失效
+    retrieval_cache: Option<Arc<RwLock<RetrievalCache>>>,
+    /// 可选的属性提取器，未设置时使用简单提取
+    attribute_extractor: Option<Arc<dyn AttributeExtractor>>,
+    /// 执行配置检索策略的检索器，仅在配置了 `strategy_overrides` 或 `default_strategy` 时创建
+    strategy_retriever: Option<RwLock<HippoRAGRetriever>>,
+    /// 生成记忆嵌入的服务，默认使用进程内共享的嵌入模型，限流器按本实例的配置创建
+    embedding_service: Arc<EmbeddingService>,
+}
+
+/// 以进程内共享的嵌入模型编码文本的嵌入后端
+struct SharedModelEmbedder {
+    dimension: usize,
+}
+
+#[async_trait]
+impl Embedder for SharedModelEmbedder {
+    async fn encode(&self, texts: &[String]) -> anyhow::Result<Vec<Vec<f32>>> {
+        Ok(MemoryManager::encode_with_shared_model(texts.to_vec()).await?)
+    }
+
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
 }
 
 /// 记忆统计信息
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct MemoryStats {
     pub total_memories: u64,
     pub total_connections: u64,
@@ -46,6 +181,61 @@ pub struct MemoryStats {
     pub last_updated: DateTime<Utc>,
 }
 
+/// 当前导出格式版本
+pub const MEMORY_EXPORT_VERSION: u32 = 1;
+
+/// 记忆导出数据
+///
+/// 包含全部向量、图节点、图边与统计信息，可序列化为 JSON 用于备份或迁移。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryExport {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub vectors: Vec<Vector>,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub stats: MemoryStats,
+}
+
+impl MemoryExport {
+    /// 序列化为 JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string_pretty(self).map_err(MemoryError::Serialization)
+    }
+
+    /// 从 JSON 解析，拒绝高于当前版本的导出数据
+    pub fn from_json(json: &str) -> Result<Self> {
+        let export: Self = serde_json::from_str(json).map_err(MemoryError::Serialization)?;
+        if export.version > MEMORY_EXPORT_VERSION {
+            return Err(MemoryError::validation_error(format!(
+                "Unsupported export version {} (max {})",
+                export.version, MEMORY_EXPORT_VERSION
+            )));
+        }
+        Ok(export)
+    }
+}
+
+/// 导入方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportMode {
+    /// 清空现有数据后导入，统计信息取自导出数据
+    Replace,
+    /// 与现有数据合并，按给定策略处理ID冲突
+    Merge(IdConflict),
+}
+
+/// 合并导入时的ID冲突处理策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdConflict {
+    /// 保留已有记录，跳过导入记录
+    Skip,
+    /// 用导入记录覆盖已有记录
+    Overwrite,
+    /// 为导入记录分配新ID
+    Rename,
+}
+
 /// 链接分析器
 ///
 /// 实现A-Mem的Zettelkasten链接机制，自动发现和建立记忆之间的连接。
@@ -66,8 +256,14 @@ pub struct LinkAnalyzer {
 ///
 /// 基于多种因素计算记忆的重要性评分。
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct ImportanceCalculator {
+    /// 基础重要性
+    base_importance: f32,
+    /// 各优先级的偏移：Low / Normal / High / Critical
+    low_priority_offset: f32,
+    normal_priority_offset: f32,
+    high_priority_offset: f32,
+    critical_priority_offset: f32,
     /// 访问权重
     access_weight: f32,
     /// 时间权重
@@ -118,16 +314,6 @@ pub struct PersonalizationVector {
     pub last_updated: DateTime<Utc>,
 }
 
-/// 交互记录
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InteractionRecord {
-    pub memory_id: MemoryId,
-    pub interaction_type: InteractionType,
-    pub timestamp: DateTime<Utc>,
-    pub feedback_score: Option<f32>,
-    pub dwell_time: Option<u64>, // 毫秒
-}
-
 /// PageRank配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PageRankConfig {
@@ -143,6 +329,9 @@ pub struct RetrievalResult {
     pub memory: Memory,
     pub relevance_score: f32,
     pub explanation: RetrievalExplanation,
+    /// 完整解释（分数分解、推理路径与备选结果），仅由 HippoRAG 检索器生成
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detailed_explanation: Option<DetailedExplanation>,
 }
 
 /// 检索解释
@@ -156,6 +345,86 @@ pub struct RetrievalExplanation {
     pub reasoning: String,
 }
 
+impl RetrievalExplanation {
+    /// 合并同一记忆在另一种检索策略中的解释：各分量取最大值，推理说明依次拼接，
+    /// 连接路径取并集
+    pub fn merge(&mut self, other: &RetrievalExplanation) {
+        self.semantic_score = self.semantic_score.max(other.semantic_score);
+        self.temporal_score = self.temporal_score.max(other.temporal_score);
+        self.importance_score = self.importance_score.max(other.importance_score);
+        self.personalization_score = self.personalization_score.max(other.personalization_score);
+
+        for path in &other.connection_paths {
+            if !self.connection_paths.contains(path) {
+                self.connection_paths.push(path.clone());
+            }
+        }
+
+        if !other.reasoning.is_empty() && !self.reasoning.split("; ").any(|r| r == other.reasoning)
+        {
+            if self.reasoning.is_empty() {
+                self.reasoning = other.reasoning.clone();
+            } else {
+                self.reasoning = format!("{}; {}", self.reasoning, other.reasoning);
+            }
+        }
+    }
+}
+
+/// 各评分分量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ComponentScores {
+    pub semantic: f32,
+    pub temporal: f32,
+    pub importance: f32,
+    pub personalization: f32,
+}
+
+/// 候选记忆的评分解释（见 `MemoryManager::explain_retrieval`）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateExplanation {
+    pub memory_id: MemoryId,
+    /// 排名，从 1 开始
+    pub rank: usize,
+    /// 各分量的原始分数（未加权）
+    pub component_scores: ComponentScores,
+    /// 各分量按查询权重加权后的贡献
+    pub weighted_scores: ComponentScores,
+    /// 最终融合分数，即 `RetrievalResult::relevance_score`
+    pub final_score: f32,
+    pub reasoning: String,
+    /// HippoRAG 检索器给出的完整分数分解（仅配置了检索策略时存在）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detailed_explanation: Option<DetailedExplanation>,
+}
+
+impl CandidateExplanation {
+    fn new(rank: usize, result: RetrievalResult, weights: &QueryWeights) -> Self {
+        let explanation = result.explanation;
+        let component_scores = ComponentScores {
+            semantic: explanation.semantic_score,
+            temporal: explanation.temporal_score,
+            importance: explanation.importance_score,
+            personalization: explanation.personalization_score,
+        };
+        let weighted_scores = ComponentScores {
+            semantic: component_scores.semantic * weights.semantic_weight,
+            temporal: component_scores.temporal * weights.temporal_weight,
+            importance: component_scores.importance * weights.importance_weight,
+            personalization: component_scores.personalization * weights.personalization_weight,
+        };
+        Self {
+            memory_id: result.memory.id,
+            rank,
+            component_scores,
+            weighted_scores,
+            final_score: result.relevance_score,
+            reasoning: explanation.reasoning,
+            detailed_explanation: result.detailed_explanation,
+        }
+    }
+}
+
 /// 记忆创建请求
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateMemoryRequest {
@@ -180,6 +449,16 @@ impl MemoryManager {
         let importance_calculator = ImportanceCalculator::new(&config);
         let evolution_engine = EvolutionEngine::new(&config);
         let retrieval_engine = RetrievalEngine::new(&config).await?;
+        let embedding_service = Arc::new(Self::default_embedding_service(&config));
+        let strategy_retriever = if config.retrieval.has_configured_strategies() {
+            let mut retriever = HippoRAGRetriever::new(db.clone(), config.clone()).await?;
+            retriever.set_embedding_service(embedding_service.clone());
+            Some(RwLock::new(retriever))
+        } else {
+            None
+        };
+        let stats = Self::load_stats(&db).await?;
+        telemetry::set_memories_total(stats.total_memories);
 
         Ok(Self {
             db,
@@ -188,10 +467,38 @@ impl MemoryManager {
             importance_calculator,
             evolution_engine,
             retrieval_engine,
-            stats: Arc::new(RwLock::new(MemoryStats::default())),
+            stats: Arc::new(RwLock::new(stats)),
+            stats_persisted_at: Arc::new(RwLock::new(Utc::now())),
+            retrieval_cache: None,
+            attribute_extractor: None,
+            strategy_retriever,
+            embedding_service,
         })
     }
 
+    /// 关联检索缓存（见 `HippoRAGRetriever::cache_handle`），记忆变更时自动失效
+    pub fn attach_retrieval_cache(&mut self, cache: Arc<RwLock<RetrievalCache>>) {
+        self.retrieval_cache = Some(cache);
+    }
+
+    /// 设置属性提取器，新记忆的关键词、标签等属性将由其提取
+    pub fn set_attribute_extractor(&mut self, extractor: Arc<dyn AttributeExtractor>) {
+        self.attribute_extractor = Some(extractor);
+    }
+
+    /// 替换生成记忆嵌入的服务（例如接入自定义嵌入后端），策略检索器随之切换
+    pub fn set_embedding_service(&mut self, service: Arc<EmbeddingService>) {
+        if let Some(retriever) = &mut self.strategy_retriever {
+            retriever.get_mut().set_embedding_service(service.clone());
+        }
+        self.embedding_service = service;
+    }
+
+    /// 生成记忆嵌入的服务
+    pub fn embedding_service(&self) -> &Arc<EmbeddingService> {
+        &self.embedding_service
+    }
+
     /// 创建新记忆（从请求）
     pub async fn create_memory_from_request(&self, request: CreateMemoryRequest) -> Result<Memory> {
         info!(
@@ -201,10 +508,33 @@ impl MemoryManager {
 
         // 生成嵌入向量
         let embedding = self.generate_embedding(&request.content).await?;
+        self.create_memory_with_embedding(request, embedding).await
+    }
+
+    /// 批量创建记忆
+    ///
+    /// 嵌入向量按 `vector.embed_batch_size` 分批生成，其余流程与
+    /// [`MemoryManager::create_memory_from_request`] 相同。返回顺序与请求一致。
+    pub async fn create_memories(&self, requests: Vec<CreateMemoryRequest>) -> Result<Vec<Memory>> {
+        let contents: Vec<String> = requests.iter().map(|r| r.content.clone()).collect();
+        let embeddings = self.generate_embeddings_batched(&contents).await?;
+
+        let mut memories = Vec::with_capacity(requests.len());
+        for (request, embedding) in requests.into_iter().zip(embeddings) {
+            memories.push(self.create_memory_with_embedding(request, embedding).await?);
+        }
+        Ok(memories)
+    }
 
+    /// 使用已生成的嵌入向量创建记忆
+    async fn create_memory_with_embedding(
+        &self,
+        request: CreateMemoryRequest,
+        embedding: Vec<f32>,
+    ) -> Result<Memory> {
         // 提取属性
-        let attributes = if let Some(attrs) = request.attributes {
-            attrs
+        let (attributes, estimated_importance) = if let Some(attrs) = request.attributes {
+            (attrs, None)
         } else {
             self.extract_attributes(&request.content, &request.context)
                 .await?
@@ -214,13 +544,16 @@ impl MemoryManager {
         let memory_type = MemoryType::Knowledge; // 默认类型，可以根据内容分析来确定
         let mut memory = Memory::new(request.content, memory_type, embedding, attributes);
 
-        // 计算初始重要性
-        memory.attributes.importance = self
+        // 计算初始重要性，提取器给出估计时取两者平均
+        let importance = self
             .importance_calculator
             .calculate_initial_importance(&memory, &request.context)
             .await?;
+        memory.attributes.importance = match estimated_importance {
+            Some(estimate) => ((importance + estimate) / 2.0).clamp(0.0, 1.0),
+            None => importance,
+        };
 
-        // 存储到向量数据库
         let vector = Vector {
             id: memory.id.clone(),
             embedding: memory.embedding.clone(),
@@ -228,9 +561,7 @@ impl MemoryManager {
             created_at: memory.metadata.created_at,
             updated_at: memory.metadata.updated_at,
         };
-        self.db.insert_vector(&vector).await?;
 
-        // 存储到图数据库
         let node = GraphNode {
             id: memory.id.clone(),
             node_type: "memory".to_string(),
@@ -238,7 +569,6 @@ impl MemoryManager {
             created_at: memory.metadata.created_at,
             updated_at: memory.metadata.updated_at,
         };
-        self.db.insert_node(&node).await?;
 
         // 自动发现连接
         let connections = self
@@ -247,6 +577,7 @@ impl MemoryManager {
             .await?;
 
         // 添加强制连接
+        let mut edges = Vec::new();
         if let Some(forced_connections) = request.force_connections {
             for target_id in forced_connections {
                 let connection = Connection::new(
@@ -255,23 +586,33 @@ impl MemoryManager {
                     ConnectionType::Custom("forced".to_string()),
                     1.0,
                 );
-                self.create_connection(&connection).await?;
+                edges.push(Self::connection_to_edge(&connection));
             }
         }
 
-        // 创建发现的连接
+        // 添加发现的连接
+        for connection in &connections {
+            edges.push(Self::connection_to_edge(connection));
+        }
+
+        // 向量、节点与连接在同一事务中写入，任一失败都会整体回滚
+        self.db.create_memory_atomic(&vector, &node, &edges).await?;
+
         for connection in connections {
-            self.create_connection(&connection).await?;
             memory
                 .connections
                 .semantic_links
                 .push(connection.to_memory.clone());
         }
 
+        // 新记忆可能出现在任何查询的结果中，清空检索缓存
+        self.invalidate_retrieval_cache(None).await;
+
         // 更新统计信息
         self.update_stats(|stats| {
             stats.total_memories += 1;
             stats.creation_count += 1;
+            stats.total_connections += edges.len() as u64;
             stats.last_updated = Utc::now();
         })
         .await;
@@ -280,10 +621,70 @@ impl MemoryManager {
         Ok(memory)
     }
 
+    /// 将长文档切分后逐段创建记忆
+    ///
+    /// 每个片段继承 `base_attributes`（并在自定义属性中记录 `chunk_index` 与
+    /// `chunk_count`），相邻片段之间建立时间与主题连接。返回按文档顺序排列的记忆。
+    pub async fn create_memories_from_document(
+        &self,
+        text: &str,
+        base_attributes: MemoryAttributes,
+    ) -> Result<Vec<Memory>> {
+        let chunks = chunk_text(text, DOCUMENT_CHUNK_MAX_TOKENS, DOCUMENT_CHUNK_OVERLAP);
+        let chunk_count = chunks.len();
+        info!("Creating {} memories from document", chunk_count);
+
+        let embeddings = self.generate_embeddings_batched(&chunks).await?;
+        let mut memories: Vec<Memory> = Vec::with_capacity(chunk_count);
+        for (index, (content, embedding)) in chunks.into_iter().zip(embeddings).enumerate() {
+            let mut attributes = base_attributes.clone();
+            attributes
+                .custom_attributes
+                .insert("chunk_index".to_string(), serde_json::json!(index));
+            attributes
+                .custom_attributes
+                .insert("chunk_count".to_string(), serde_json::json!(chunk_count));
+
+            let request = CreateMemoryRequest {
+                content,
+                context: Context::default(),
+                attributes: Some(attributes),
+                force_connections: None,
+            };
+            let mut memory = self.create_memory_with_embedding(request, embedding).await?;
+
+            if let Some(previous) = memories.last_mut() {
+                for connection_type in [ConnectionType::Temporal, ConnectionType::Thematic] {
+                    let mut connection = Connection::new(
+                        previous.id.clone(),
+                        memory.id.clone(),
+                        connection_type,
+                        1.0,
+                    );
+                    // 相邻片段互相链接，两个方向都可遍历
+                    connection.bidirectional = true;
+                    self.create_connection(&connection).await?;
+                }
+                previous.connections.temporal_links.push(memory.id.clone());
+                previous.connections.thematic_links.push(memory.id.clone());
+                memory.connections.temporal_links.push(previous.id.clone());
+                memory.connections.thematic_links.push(previous.id.clone());
+            }
+
+            memories.push(memory);
+        }
+
+        Ok(memories)
+    }
+
     /// 创建新记忆（从Memory对象）
+    ///
+    /// 以 upsert 方式写入：对已存在的ID重复调用会覆盖为最新内容，而不是报错。
     pub async fn create_memory(&self, memory: &Memory) -> Result<()> {
         info!("Creating memory with ID: {}", memory.id);
 
+        let existed = self.db.get_vector(&memory.id).await.is_ok();
+
         // 存储到向量数据库
         let vector = Vector {
             id: memory.id.clone(),
@@ -292,7 +693,7 @@ impl MemoryManager {
             created_at: memory.metadata.created_at,
             updated_at: memory.metadata.updated_at,
         };
-        self.db.insert_vector(&vector).await?;
+        self.db.upsert_vector(&vector).await?;
 
         // 存储到图数据库
         let node = GraphNode {
@@ -302,12 +703,17 @@ impl MemoryManager {
             created_at: memory.metadata.created_at,
             updated_at: memory.metadata.updated_at,
         };
-        self.db.insert_node(&node).await?;
+        self.db.upsert_node(&node).await?;
 
-        // 更新统计信息
+        // 新记忆可能出现在任何查询的结果中，清空检索缓存
+        self.invalidate_retrieval_cache(None).await;
+
+        // 更新统计信息（重复写入同一ID不计为新记忆）
         self.update_stats(|stats| {
-            stats.total_memories += 1;
-            stats.creation_count += 1;
+            if !existed {
+                stats.total_memories += 1;
+                stats.creation_count += 1;
+            }
             stats.last_updated = Utc::now();
         })
         .await;
@@ -317,6 +723,11 @@ impl MemoryManager {
     }
 
     /// 检索记忆
+    #[tracing::instrument(
+        name = "retrieve_memories",
+        skip_all,
+        fields(query.text = %query.text, query.query_type = ?query.query_type)
+    )]
     pub async fn retrieve_memories(
         &self,
         query: &Query,
@@ -324,17 +735,10 @@ impl MemoryManager {
     ) -> Result<Vec<RetrievalResult>> {
         debug!("Retrieving memories for query: {}", query.text);
 
-        let results = match query.query_type {
-            QueryType::Semantic => self.semantic_retrieval(query, context).await?,
-            QueryType::Temporal => self.temporal_retrieval(query, context).await?,
-            QueryType::Causal => self.causal_retrieval(query, context).await?,
-            QueryType::Thematic => self.thematic_retrieval(query, context).await?,
-            QueryType::Mixed => self.mixed_retrieval(query, context).await?,
-            QueryType::GraphTraversal => self.graph_traversal_retrieval(query, context).await?,
-            QueryType::PersonalizedPageRank => {
-                self.personalized_pagerank_retrieval(query, context).await?
-            }
-        };
+        let started = std::time::Instant::now();
+        let results = self.rank_candidates(query, context, false).await?;
+
+        telemetry::record_retrieval(&format!("{:?}", query.query_type), started.elapsed());
 
         // 更新统计信息
         self.update_stats(|stats| {
@@ -347,7 +751,123 @@ impl MemoryManager {
         Ok(results)
     }
 
+    /// 只解释不检索：返回排名靠前的候选及其各分量原始分数、加权贡献与最终分数
+    ///
+    /// 与 `retrieve_memories` 使用相同的评分流程，但不读写检索缓存、不记录交互，也不更新统计信息。
+    pub async fn explain_retrieval(
+        &self,
+        query: &Query,
+        context: &Context,
+    ) -> Result<Vec<CandidateExplanation>> {
+        let limit = query.limit.unwrap_or(self.config.retrieval.max_results);
+        let results = self.rank_candidates(query, context, true).await?;
+        Ok(results
+            .into_iter()
+            .take(limit)
+            .enumerate()
+            .map(|(i, result)| CandidateExplanation::new(i + 1, result, &query.weights))
+            .collect())
+    }
+
+    /// 按查询类型（或配置的检索策略）计算候选结果并排序
+    ///
+    /// `dry_run` 为真时配置的检索策略不读写缓存、不记录交互。
+    async fn rank_candidates(
+        &self,
+        query: &Query,
+        context: &Context,
+        dry_run: bool,
+    ) -> Result<Vec<RetrievalResult>> {
+        let mut results = match self.config.retrieval.strategy_for(&query.query_type) {
+            // 检索器内部已按偏好情感加分并排序
+            Some(strategy) => {
+                self.strategy_retrieval(query, context, strategy, dry_run)
+                    .await?
+            }
+            None => {
+                let mut results = match query.query_type {
+                    QueryType::Semantic => self.semantic_retrieval(query, context).await?,
+                    QueryType::Temporal => self.temporal_retrieval(query, context).await?,
+                    QueryType::Causal => self.causal_retrieval(query, context).await?,
+                    QueryType::Thematic => self.thematic_retrieval(query, context).await?,
+                    QueryType::Mixed => self.mixed_retrieval(query, context).await?,
+                    QueryType::GraphTraversal => {
+                        self.graph_traversal_retrieval(query, context).await?
+                    }
+                    QueryType::PersonalizedPageRank => {
+                        self.personalized_pagerank_retrieval(query, context).await?
+                    }
+                };
+
+                // 偏好情感加分后重新排序
+                if let Some(preferred_emotion) = &query.filters.preferred_emotion {
+                    apply_emotion_boost(
+                        &mut results,
+                        preferred_emotion,
+                        self.config.retrieval.emotion_match_boost,
+                    );
+                    results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+                }
+                results
+            }
+        };
+
+        if let Some(sort_by) = &query.sort_by {
+            sort_results(&mut results, sort_by);
+        }
+
+        Ok(results)
+    }
+
+    /// 按配置的检索策略，通过 HippoRAG 检索器执行检索
+    #[tracing::instrument(skip_all, fields(strategy = ?strategy))]
+    async fn strategy_retrieval(
+        &self,
+        query: &Query,
+        context: &Context,
+        strategy: &RetrievalStrategy,
+        dry_run: bool,
+    ) -> Result<Vec<RetrievalResult>> {
+        let retriever = self
+            .strategy_retriever
+            .as_ref()
+            .ok_or_else(|| MemoryError::Internal {
+                message: "Strategy retriever is not initialized".to_string(),
+            })?;
+
+        let retrieval_context = RetrievalContext {
+            session_id: context.session_id.clone(),
+            current_topic: context.current_topic.clone(),
+            recent_queries: Vec::new(),
+            time_window: context.time_window,
+            priority: context.priority.clone(),
+            constraints: RetrievalConstraints {
+                max_results: query.limit,
+                min_relevance: None,
+                required_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                time_range: None,
+                source_filter: None,
+            },
+        };
+
+        if dry_run {
+            retriever
+                .read()
+                .await
+                .retrieve_uncached(query, &retrieval_context, strategy)
+                .await
+        } else {
+            retriever
+                .write()
+                .await
+                .retrieve(query, &retrieval_context, strategy)
+                .await
+        }
+    }
+
     /// 语义检索
+    #[tracing::instrument(skip_all)]
     async fn semantic_retrieval(
         &self,
         query: &Query,
@@ -358,41 +878,65 @@ impl MemoryManager {
         let vector_request = VectorQueryRequest {
             query_vector: query_embedding,
             limit: query.limit,
-            threshold: Some(self.config.vector.similarity_threshold),
+            threshold: Some(resolve_similarity_threshold(
+                &query.filters,
+                None,
+                self.config.vector.similarity_threshold,
+            )),
             filters: self.query_filters_to_metadata_filters(&query.filters)?,
+            tags: TagFilter {
+                any_of: query.filters.tags.clone().unwrap_or_default(),
+                ..Default::default()
+            },
         };
 
         let vector_results = self.db.query_vectors(&vector_request).await?;
+        let importance_weight = self.config.retrieval.fusion_weights.importance_weight;
 
         let mut results = Vec::new();
         for vector_result in vector_results {
-            let memory = self.metadata_to_memory(&vector_result.vector.metadata)?;
+            let mut memory = self.metadata_to_memory(&vector_result.vector.metadata)?;
+            memory.embedding = vector_result.vector.embedding;
 
-            // 应用查询过滤器
-            if !memory.matches_filters(&query.filters) {
+            // 跳过已软删除的记忆，并应用查询过滤器
+            if memory.metadata.is_deleted || !memory.matches_filters(&query.filters) {
                 continue;
             }
 
+            let importance = memory.attributes.importance;
+            let relevance_score =
+                blend_importance(vector_result.similarity, importance, importance_weight);
+            let reasoning = if importance_weight > 0.0 {
+                format!(
+                    "Semantic similarity: {:.3}, importance: {:.3} (weight {:.2})",
+                    vector_result.similarity, importance, importance_weight
+                )
+            } else {
+                format!("Semantic similarity: {:.3}", vector_result.similarity)
+            };
             let explanation = RetrievalExplanation {
                 semantic_score: vector_result.similarity,
                 temporal_score: 0.0,
-                importance_score: memory.attributes.importance,
+                importance_score: importance,
                 personalization_score: 0.0,
                 connection_paths: Vec::new(),
-                reasoning: format!("Semantic similarity: {:.3}", vector_result.similarity),
+                reasoning,
             };
 
             results.push(RetrievalResult {
                 memory,
-                relevance_score: vector_result.similarity,
+                relevance_score,
                 explanation,
+                detailed_explanation: None,
             });
         }
 
+        results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
         Ok(results)
     }
 
     /// 时间检索
+    #[tracing::instrument(skip_all)]
     async fn temporal_retrieval(
         &self,
         query: &Query,
@@ -405,11 +949,17 @@ impl MemoryManager {
 
         // 首先进行语义检索
         let mut semantic_results = self.semantic_retrieval(query, context).await?;
+        if self.config.retrieval.temporal_hard_window {
+            semantic_results.retain(|result| {
+                let created_at = result.memory.metadata.created_at;
+                created_at >= time_window.0 && created_at <= time_window.1
+            });
+        }
 
         // 根据时间相关性重新评分
         for result in &mut semantic_results {
             let time_score = self
-                .calculate_temporal_relevance(&result.memory, &time_window, &query.text)
+                .calculate_temporal_relevance(&result.memory, &time_window)
                 .await?;
 
             result.explanation.temporal_score = time_score;
@@ -425,13 +975,15 @@ impl MemoryManager {
     }
 
     /// 因果检索
+    #[tracing::instrument(skip_all)]
     async fn causal_retrieval(
         &self,
         query: &Query,
         context: &Context,
     ) -> Result<Vec<RetrievalResult>> {
         // 基于因果关系的检索实现
-        let causal_keywords = self.extract_causal_keywords(&query.text)?;
+        let language = Self::query_language(query);
+        let causal_keywords = self.extract_causal_keywords(&query.text, &language)?;
 
         let graph_request = GraphQueryRequest {
             start_nodes: context.recent_memories.clone(),
@@ -439,13 +991,14 @@ impl MemoryManager {
             max_depth: Some(3),
             limit: query.limit,
             filters: self.query_filters_to_graph_filters(&query.filters)?,
+            traversal: TraversalMode::BreadthFirst,
         };
 
         let graph_results = self.db.query_graph(&graph_request).await?;
 
         let mut results = Vec::new();
         for node in graph_results.nodes {
-            if let Ok(memory) = self.properties_to_memory(&node.properties) {
+            if let Ok(memory) = self.properties_to_memory(&node.properties).await {
                 let causal_score = self
                     .calculate_causal_relevance(&memory, &causal_keywords)
                     .await?;
@@ -464,6 +1017,7 @@ impl MemoryManager {
                         memory,
                         relevance_score: causal_score,
                         explanation,
+                        detailed_explanation: None,
                     });
                 }
             }
@@ -474,12 +1028,14 @@ impl MemoryManager {
     }
 
     /// 主题检索
+    #[tracing::instrument(skip_all)]
     async fn thematic_retrieval(
         &self,
         query: &Query,
         _context: &Context,
     ) -> Result<Vec<RetrievalResult>> {
-        let themes = self.extract_themes(&query.text)?;
+        let language = Self::query_language(query);
+        let themes = self.extract_themes(&query.text, &language)?;
 
         let graph_request = GraphQueryRequest {
             start_nodes: Vec::new(),
@@ -487,13 +1043,14 @@ impl MemoryManager {
             max_depth: Some(2),
             limit: query.limit,
             filters: self.create_theme_filters(&themes)?,
+            traversal: TraversalMode::BreadthFirst,
         };
 
         let graph_results = self.db.query_graph(&graph_request).await?;
 
         let mut results = Vec::new();
         for node in graph_results.nodes {
-            if let Ok(memory) = self.properties_to_memory(&node.properties) {
+            if let Ok(memory) = self.properties_to_memory(&node.properties).await {
                 let theme_score = self.calculate_thematic_relevance(&memory, &themes).await?;
 
                 let explanation = RetrievalExplanation {
@@ -509,6 +1066,7 @@ impl MemoryManager {
                     memory,
                     relevance_score: theme_score,
                     explanation,
+                    detailed_explanation: None,
                 });
             }
         }
@@ -518,6 +1076,7 @@ impl MemoryManager {
     }
 
     /// 混合检索
+    #[tracing::instrument(skip_all)]
     async fn mixed_retrieval(
         &self,
         query: &Query,
@@ -540,6 +1099,7 @@ impl MemoryManager {
     }
 
     /// 图遍历检索
+    #[tracing::instrument(skip_all)]
     async fn graph_traversal_retrieval(
         &self,
         query: &Query,
@@ -563,13 +1123,14 @@ impl MemoryManager {
             max_depth: Some(self.config.graph.traversal.max_depth),
             limit: query.limit,
             filters: self.query_filters_to_graph_filters(&query.filters)?,
+            traversal: TraversalMode::BreadthFirst,
         };
 
         let graph_results = self.db.query_graph(&graph_request).await?;
 
         let mut results = Vec::new();
         for (i, node) in graph_results.nodes.iter().enumerate() {
-            if let Ok(memory) = self.properties_to_memory(&node.properties) {
+            if let Ok(memory) = self.properties_to_memory(&node.properties).await {
                 let path_score = 1.0 / (i as f32 + 1.0); // 距离越近分数越高
 
                 let explanation = RetrievalExplanation {
@@ -585,6 +1146,7 @@ impl MemoryManager {
                     memory,
                     relevance_score: path_score,
                     explanation,
+                    detailed_explanation: None,
                 });
             }
         }
@@ -593,6 +1155,7 @@ impl MemoryManager {
     }
 
     /// 个性化PageRank检索
+    #[tracing::instrument(skip_all)]
     async fn personalized_pagerank_retrieval(
         &self,
         query: &Query,
@@ -629,6 +1192,7 @@ impl MemoryManager {
                 memory: semantic_result.memory,
                 relevance_score: combined_score,
                 explanation,
+                detailed_explanation: None,
             });
         }
 
@@ -681,6 +1245,9 @@ impl MemoryManager {
         // 保存到数据库
         self.save_memory(&memory).await?;
 
+        // 内容变化可能影响任意查询的结果，清空检索缓存
+        self.invalidate_retrieval_cache(None).await;
+
         info!("Successfully updated memory: {}", request.memory_id);
         Ok(memory)
     }
@@ -692,6 +1259,10 @@ impl MemoryManager {
         if soft_delete {
             // 软删除：标记为已删除
             let mut memory = self.get_memory(memory_id).await?;
+            if memory.metadata.is_deleted {
+                debug!("Memory {} is already deleted", memory_id);
+                return Ok(());
+            }
             memory.metadata.is_deleted = true;
             memory.metadata.deleted_at = Some(Utc::now());
             self.save_memory(&memory).await?;
@@ -703,6 +1274,8 @@ impl MemoryManager {
             });
         }
 
+        self.invalidate_retrieval_cache(Some(memory_id)).await;
+
         // 更新统计信息
         self.update_stats(|stats| {
             if stats.total_memories > 0 {
@@ -716,373 +1289,1160 @@ impl MemoryManager {
         Ok(())
     }
 
-    /// 演化记忆
-    pub async fn evolve_memories(&self, trigger: EvolutionTrigger) -> Result<u64> {
-        info!("Starting memory evolution with trigger: {:?}", trigger);
-
-        let evolved_count = self.evolution_engine.evolve(&trigger, &self.db).await?;
-
-        // 更新统计信息
-        self.update_stats(|stats| {
-            stats.evolution_count += evolved_count;
-            stats.last_updated = Utc::now();
-        })
-        .await;
-
-        info!("Evolution completed. {} memories evolved", evolved_count);
-        Ok(evolved_count)
-    }
+    /// 导出全部记忆数据
+    pub async fn export_memories(&self) -> Result<MemoryExport> {
+        let export = MemoryExport {
+            version: MEMORY_EXPORT_VERSION,
+            exported_at: Utc::now(),
+            vectors: self.db.get_all_vectors().await?,
+            nodes: self.db.get_all_nodes().await?,
+            edges: self.db.get_graph_edges().await?,
+            stats: self.get_stats().await,
+        };
 
-    /// 获取记忆统计信息
-    pub async fn get_stats(&self) -> MemoryStats {
-        self.stats.read().await.clone()
+        info!(
+            "Exported {} vectors, {} nodes, {} edges",
+            export.vectors.len(),
+            export.nodes.len(),
+            export.edges.len()
+        );
+        Ok(export)
     }
 
-    // 私有辅助方法
-
-    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        use model2vec_rs::model::StaticModel;
-        use std::sync::OnceLock;
-        use std::sync::{Arc, Mutex};
-
-        // 使用静态变量缓存模型，避免重复加载
-        static MODEL: OnceLock<Option<Arc<Mutex<StaticModel>>>> = OnceLock::new();
+    /// 导入记忆数据
+    pub async fn import_memories(&self, export: MemoryExport, mode: ImportMode) -> Result<()> {
+        if export.version > MEMORY_EXPORT_VERSION {
+            return Err(MemoryError::validation_error(format!(
+                "Unsupported export version {} (max {})",
+                export.version, MEMORY_EXPORT_VERSION
+            )));
+        }
 
-        let model = MODEL
-            .get_or_init(|| {
-                match StaticModel::from_pretrained(
-                    "minishlab/potion-multilingual-128M", // 使用多语言模型
-                    None,                                 // 无需 HF token
-                    None,                                 // 使用模型默认的归一化设置
-                    None,                                 // 无子文件夹
-                ) {
-                    Ok(m) => Some(Arc::new(Mutex::new(m))),
-                    Err(_) => None,
+        let MemoryExport {
+            mut vectors,
+            mut nodes,
+            mut edges,
+            stats: imported_stats,
+            ..
+        } = export;
+
+        match mode {
+            ImportMode::Replace => {
+                self.db.clear_all().await?;
+                self.db
+                    .import_records(&vectors, &nodes, &edges, false)
+                    .await?;
+                self.update_stats(|stats| *stats = imported_stats).await;
+            }
+            ImportMode::Merge(conflict) => {
+                let existing_memories = self.existing_memory_ids().await?;
+                let existing_edges = self.existing_edge_ids().await?;
+
+                if conflict == IdConflict::Rename {
+                    Self::rename_conflicting_ids(
+                        &mut vectors,
+                        &mut nodes,
+                        &mut edges,
+                        &existing_memories,
+                        &existing_edges,
+                    );
                 }
-            })
-            .as_ref()
-            .ok_or_else(|| MemoryError::Internal {
-                message: "Failed to load embedding model".to_string(),
-            })?;
 
-        let model_guard = model.lock().map_err(|e| MemoryError::Internal {
-            message: format!("Failed to acquire model lock: {}", e),
-        })?;
+                let new_memories = vectors
+                    .iter()
+                    .filter(|v| !existing_memories.contains(&v.id))
+                    .filter(|v| v.metadata.get("is_deleted") != Some(&serde_json::json!(true)))
+                    .count() as u64;
+                let new_edges = edges
+                    .iter()
+                    .filter(|e| !existing_edges.contains(&e.id))
+                    .count() as u64;
 
-        // 生成嵌入向量
-        let sentences = vec![text.to_string()];
-        let embeddings = model_guard.encode(&sentences);
+                self.db
+                    .import_records(&vectors, &nodes, &edges, conflict == IdConflict::Overwrite)
+                    .await?;
 
-        if embeddings.is_empty() {
-            return Err(MemoryError::Internal {
-                message: "Failed to generate embedding: empty result".to_string(),
-            });
+                self.update_stats(|stats| {
+                    stats.total_memories += new_memories;
+                    stats.total_connections += new_edges;
+                    stats.retrieval_count += imported_stats.retrieval_count;
+                    stats.creation_count += imported_stats.creation_count;
+                    stats.evolution_count += imported_stats.evolution_count;
+                    stats.last_updated = Utc::now();
+                })
+                .await;
+            }
         }
 
-        Ok(embeddings[0].clone())
+        self.invalidate_retrieval_cache(None).await;
+
+        info!(
+            "Imported {} vectors, {} nodes, {} edges ({:?})",
+            vectors.len(),
+            nodes.len(),
+            edges.len(),
+            mode
+        );
+        Ok(())
     }
 
-    async fn extract_attributes(
+    /// 合并重复记忆
+    ///
+    /// 保留主记忆，将重复记忆的所有边改接到主记忆（平行边合并并累加权重），
+    /// 合并关键词与标签、取最大重要性，并软删除重复记忆。
+    pub async fn merge_memories(
         &self,
-        content: &str,
-        context: &Context,
-    ) -> Result<MemoryAttributes> {
-        // 简单的属性提取实现
-        let words: Vec<&str> = content.split_whitespace().collect();
-        let keywords = words.iter().take(5).map(|s| s.to_string()).collect();
+        primary: &MemoryId,
+        duplicates: &[MemoryId],
+    ) -> Result<Memory> {
+        info!(
+            "Merging {} duplicates into memory: {}",
+            duplicates.len(),
+            primary
+        );
 
-        Ok(MemoryAttributes {
-            keywords,
-            tags: Vec::new(),
-            context: context.current_topic.clone().unwrap_or_default(),
-            importance: 0.5,
-            emotion: None,
-            source: None,
-            confidence: 1.0,
-            language: Some("zh".to_string()),
-            custom_attributes: HashMap::new(),
-        })
-    }
+        let mut duplicate_ids: Vec<MemoryId> = Vec::new();
+        for id in duplicates {
+            if id != primary && !duplicate_ids.contains(id) {
+                duplicate_ids.push(id.clone());
+            }
+        }
 
-    pub async fn create_connection(&self, connection: &Connection) -> Result<()> {
-        let edge = GraphEdge {
-            id: connection.id.clone(),
-            from_node: connection.from_memory.clone(),
-            to_node: connection.to_memory.clone(),
-            edge_type: format!("{:?}", connection.connection_type),
-            weight: connection.strength,
-            properties: connection.properties.clone(),
-            created_at: connection.created_at,
-            updated_at: connection.updated_at,
-        };
+        let mut merged = self.get_memory(primary).await?;
+        for id in &duplicate_ids {
+            let duplicate = self.get_memory(id).await?;
+            for keyword in duplicate.attributes.keywords {
+                if !merged.attributes.keywords.contains(&keyword) {
+                    merged.attributes.keywords.push(keyword);
+                }
+            }
+            for tag in duplicate.attributes.tags {
+                if !merged.attributes.tags.contains(&tag) {
+                    merged.attributes.tags.push(tag);
+                }
+            }
+            merged.attributes.importance = merged
+                .attributes
+                .importance
+                .max(duplicate.attributes.importance);
+        }
 
-        self.db.insert_edge(&edge).await?;
+        let removed_edges = self.db.redirect_edges(primary, &duplicate_ids).await?;
 
-        // 更新统计信息
+        merged.metadata.updated_at = Utc::now();
+        merged.metadata.version += 1;
+        self.save_memory(&merged).await?;
+
+        for id in &duplicate_ids {
+            self.delete_memory(id, true).await?;
+        }
+
+        let removed_count = removed_edges.len() as u64;
         self.update_stats(|stats| {
-            stats.total_connections += 1;
+            stats.total_connections = stats.total_connections.saturating_sub(removed_count);
             stats.last_updated = Utc::now();
         })
         .await;
+        self.invalidate_retrieval_cache(None).await;
 
-        Ok(())
-    }
-
-    async fn remove_connection(&self, connection_id: &ConnectionId) -> Result<()> {
-        // TODO: 实现连接删除逻辑
-        warn!("Connection removal not implemented: {}", connection_id);
-        Ok(())
+        info!("Successfully merged duplicates into memory: {}", primary);
+        self.get_memory(primary).await
     }
 
-    async fn get_memory(&self, memory_id: &MemoryId) -> Result<Memory> {
-        let vector = self.db.get_vector(memory_id).await.map_err(|e| {
-            if let MemoryError::Database(_) = e {
-                MemoryError::MemoryNotFound {
-                    id: memory_id.clone(),
-                }
-            } else {
-                e
-            }
-        })?;
-
-        let mut memory = self.metadata_to_memory(&vector.metadata)?;
-        memory.embedding = vector.embedding;
-
-        // 可选: 加载连接
-        memory.connections.semantic_links = self
-            .get_connections(memory_id)
+    /// 查找疑似重复的记忆
+    ///
+    /// 两两比较未删除记忆的嵌入余弦相似度（O(n²)），相似度不低于阈值的记忆被归入同一簇。
+    /// 返回包含至少两条记忆的簇，簇内按ID排序。
+    pub async fn find_duplicate_candidates(&self, threshold: f32) -> Result<Vec<Vec<MemoryId>>> {
+        let vectors: Vec<Vector> = self
+            .db
+            .get_all_vectors()
             .await?
             .into_iter()
-            .map(|c| c.to_memory)
+            .filter(|v| v.metadata.get("is_deleted") != Some(&serde_json::json!(true)))
             .collect();
 
-        Ok(memory)
-    }
+        // 并查集
+        let mut parent: Vec<usize> = (0..vectors.len()).collect();
+        fn find(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
 
-    async fn save_memory(&self, memory: &Memory) -> Result<()> {
-        let vector = Vector {
-            id: memory.id.clone(),
-            embedding: memory.embedding.clone(),
-            metadata: self.memory_to_metadata(memory)?,
-            created_at: memory.metadata.created_at,
-            updated_at: memory.metadata.updated_at,
-        };
-        self.db.update_vector(&vector).await?;
+        for i in 0..vectors.len() {
+            for j in (i + 1)..vectors.len() {
+                let similarity =
+                    VectorUtils::cosine_similarity(&vectors[i].embedding, &vectors[j].embedding)
+                        .unwrap_or(0.0);
+                if similarity >= threshold {
+                    let (root_i, root_j) = (find(&mut parent, i), find(&mut parent, j));
+                    if root_i != root_j {
+                        parent[root_j] = root_i;
+                    }
+                }
+            }
+        }
 
-        let node = GraphNode {
-            id: memory.id.clone(),
-            node_type: "memory".to_string(),
-            properties: self.memory_to_properties(memory)?,
-            created_at: memory.metadata.created_at,
-            updated_at: memory.metadata.updated_at,
-        };
-        self.db.update_node(&node).await?;
+        let mut clusters: HashMap<usize, Vec<MemoryId>> = HashMap::new();
+        for (i, vector) in vectors.iter().enumerate() {
+            let root = find(&mut parent, i);
+            clusters.entry(root).or_default().push(vector.id.clone());
+        }
 
-        Ok(())
+        let mut clusters: Vec<Vec<MemoryId>> = clusters
+            .into_values()
+            .filter(|cluster| cluster.len() > 1)
+            .map(|mut cluster| {
+                cluster.sort();
+                cluster
+            })
+            .collect();
+        clusters.sort();
+
+        Ok(clusters)
     }
 
-    /// 记录用户交互
-    pub async fn record_interaction(&self, interaction: &Interaction) -> Result<()> {
-        debug!("Recording interaction: {:?}", interaction.interaction_type);
+    /// 根据累积信号重新计算记忆的重要性并保存
+    ///
+    /// 综合访问次数、最后访问时间、连接度数、反馈评分（`custom_metadata["feedback_score"]`）
+    /// 与内容长度，返回新的重要性。
+    pub async fn recalculate_importance(&self, memory_id: &MemoryId) -> Result<f32> {
+        let mut memory = self.get_memory(memory_id).await?;
+        let degree = self.get_connections(memory_id).await?.len();
+        let tau = Duration::seconds((self.config.retrieval.temporal_tau_days * 86_400.0) as i64);
+
+        let importance =
+            self.importance_calculator
+                .recalculate_importance(&memory, degree, Utc::now(), tau);
+        memory.attributes.importance = importance;
+        self.save_memory(&memory).await?;
+        self.invalidate_retrieval_cache(Some(memory_id)).await;
 
-        // 更新记忆的访问统计
-        for memory_id in &interaction.retrieved_memories {
-            // TODO: 更新记忆的访问计数和最后访问时间
-            info!("Updated access stats for memory: {}", memory_id);
+        debug!(
+            "Recalculated importance of {}: {:.3}",
+            memory_id, importance
+        );
+        Ok(importance)
+    }
+
+    /// 重新计算所有未删除记忆的重要性，返回更新的记忆数
+    pub async fn recalculate_all_importance(&self) -> Result<usize> {
+        let mut updated = 0;
+        let mut after: Option<String> = None;
+        loop {
+            let page = self
+                .db
+                .list_vector_ids(after.as_deref(), IMPORT_ID_PAGE_SIZE, false)
+                .await?;
+            after = page.last().cloned();
+            for memory_id in &page {
+                self.recalculate_importance(memory_id).await?;
+                updated += 1;
+            }
+            if after.is_none() {
+                break;
+            }
         }
 
+        info!("Recalculated importance of {} memories", updated);
+        Ok(updated)
+    }
+
+    /// 演化记忆，返回重要性被调整的记忆数
+    ///
+    /// - `TimeDecay`：按距上次更新或访问的时长衰减所有记忆的重要性
+    /// - `UserFeedback`：按反馈评分（[0, 1]，0.5 为中性）调整该记忆的重要性，并记入
+    ///   `custom_metadata["feedback_score"]`；记忆不存在时返回 0
+    /// - `NewMemoryAdded` / `AccessPatternChanged` / `BatchUpdate`：重新计算相应记忆的重要性
+    /// - `SystemMaintenance`：重新计算全部记忆的重要性
+    /// - `ConnectionStrengthChanged`：暂不支持，返回 `EvolutionFailed`
+    pub async fn evolve_memories(&self, trigger: EvolutionTrigger) -> Result<u64> {
+        info!("Starting memory evolution with trigger: {:?}", trigger);
+
+        let evolved_count = match &trigger {
+            EvolutionTrigger::TimeDecay => self.apply_time_decay(Utc::now()).await?,
+            EvolutionTrigger::UserFeedback(memory_id, score) => {
+                self.apply_feedback(memory_id, *score).await?
+            }
+            EvolutionTrigger::NewMemoryAdded(memory_id)
+            | EvolutionTrigger::AccessPatternChanged(memory_id) => {
+                self.recalculate_existing(std::slice::from_ref(memory_id))
+                    .await?
+            }
+            EvolutionTrigger::BatchUpdate(memory_ids) => {
+                self.recalculate_existing(memory_ids).await?
+            }
+            EvolutionTrigger::SystemMaintenance => self.recalculate_all_importance().await? as u64,
+            EvolutionTrigger::ConnectionStrengthChanged(_) => {
+                return Err(MemoryError::evolution_failed(format!(
+                    "Unsupported evolution trigger: {:?}",
+                    trigger
+                )));
+            }
+        };
+
         // 更新统计信息
         self.update_stats(|stats| {
+            stats.evolution_count += evolved_count;
             stats.last_updated = Utc::now();
         })
         .await;
 
-        Ok(())
+        info!("Evolution completed. {} memories evolved", evolved_count);
+        Ok(evolved_count)
     }
 
-    /// 获取记忆的连接
-    pub async fn get_connections(&self, memory_id: &MemoryId) -> Result<Vec<Connection>> {
-        debug!("Getting connections for memory: {}", memory_id);
+    /// 衰减所有未删除记忆的重要性，返回实际降低的记忆数
+    async fn apply_time_decay(&self, now: DateTime<Utc>) -> Result<u64> {
+        let mut decayed = 0;
+        let mut after: Option<String> = None;
+        loop {
+            let page = self
+                .db
+                .list_vector_ids(after.as_deref(), IMPORT_ID_PAGE_SIZE, false)
+                .await?;
+            after = page.last().cloned();
+            for memory_id in &page {
+                let Some(mut memory) = self.find_live_memory(memory_id).await? else {
+                    continue;
+                };
+                if let Some(importance) = self.evolution_engine.decayed_importance(&memory, now) {
+                    memory.attributes.importance = importance;
+                    memory.metadata.updated_at = now;
+                    self.save_memory(&memory).await?;
+                    decayed += 1;
+                }
+            }
+            if after.is_none() {
+                break;
+            }
+        }
 
-        let graph_request = GraphQueryRequest {
-            start_nodes: vec![memory_id.clone()],
-            edge_types: None,
-            max_depth: Some(1),
-            limit: Some(100),
-            filters: None,
+        if decayed > 0 {
+            self.invalidate_retrieval_cache(None).await;
+        }
+        Ok(decayed)
+    }
+
+    /// 按反馈调整单条记忆的重要性，记忆存在时返回 1
+    async fn apply_feedback(&self, memory_id: &MemoryId, score: f32) -> Result<u64> {
+        let Some(mut memory) = self.find_live_memory(memory_id).await? else {
+            return Ok(0);
         };
 
-        let graph_results = self.db.query_graph(&graph_request).await?;
+        let score = score.clamp(0.0, 1.0);
+        memory.attributes.importance = self
+            .evolution_engine
+            .feedback_importance(memory.attributes.importance, score);
+        memory
+            .metadata
+            .custom_metadata
+            .insert("feedback_score".to_string(), serde_json::json!(score));
+        memory.metadata.updated_at = Utc::now();
+        self.save_memory(&memory).await?;
+        self.invalidate_retrieval_cache(Some(memory_id)).await;
+        Ok(1)
+    }
 
-        let mut connections = Vec::new();
-        for edge in graph_results.edges {
-            let connection = Connection {
-                id: edge.id,
-                from_memory: edge.from_node,
-                to_memory: edge.to_node,
-                connection_type: ConnectionType::Semantic, // TODO: 从edge.edge_type解析
-                strength: edge.weight,
-                created_at: edge.created_at,
-                updated_at: edge.updated_at,
-                properties: edge.properties,
-                bidirectional: false,
-            };
-            connections.push(connection);
+    /// 重新计算给定记忆中仍存在者的重要性，返回更新数
+    async fn recalculate_existing(&self, memory_ids: &[MemoryId]) -> Result<u64> {
+        let mut updated = 0;
+        for memory_id in memory_ids {
+            if self.find_live_memory(memory_id).await?.is_some() {
+                self.recalculate_importance(memory_id).await?;
+                updated += 1;
+            }
         }
+        Ok(updated)
+    }
 
-        Ok(connections)
+    /// 获取未删除的记忆，不存在时返回 `None`
+    async fn find_live_memory(&self, memory_id: &MemoryId) -> Result<Option<Memory>> {
+        match self.get_memory(memory_id).await {
+            Ok(memory) if !memory.metadata.is_deleted => Ok(Some(memory)),
+            Ok(_) => Ok(None),
+            Err(e) if e.is_memory_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
     }
 
-    async fn update_stats<F>(&self, updater: F)
-    where
-        F: FnOnce(&mut MemoryStats),
-    {
-        let mut stats = self.stats.write().await;
-        updater(&mut stats);
+    /// 获取记忆统计信息
+    pub async fn get_stats(&self) -> MemoryStats {
+        self.stats.read().await.clone()
     }
 
-    // 转换方法
+    /// 立即把累计计数（检索、创建、演化次数）写回数据库
+    ///
+    /// 统计更新时至多每分钟自动写回一次，关闭前可手动调用以免丢失最近的计数。
+    pub async fn persist_stats(&self) -> Result<()> {
+        let stats = self.stats.read().await.clone();
+        *self.stats_persisted_at.write().await = Utc::now();
+        self.db
+            .save_stat_counters(&[
+                ("retrieval_count", stats.retrieval_count),
+                ("creation_count", stats.creation_count),
+                ("evolution_count", stats.evolution_count),
+            ])
+            .await
+    }
 
-    fn memory_to_metadata(&self, memory: &Memory) -> Result<HashMap<String, serde_json::Value>> {
-        let mut metadata = HashMap::new();
+    // 私有辅助方法
 
-        // 基本信息
-        metadata.insert(
-            "id".to_string(),
-            serde_json::Value::String(memory.id.clone()),
-        );
-        metadata.insert(
-            "content".to_string(),
-            serde_json::Value::String(memory.content.clone()),
-        );
-        metadata.insert(
-            "memory_type".to_string(),
-            serde_json::Value::String(format!("{:?}", memory.memory_type)),
-        );
+    /// 获取进程内共享的嵌入模型（首次调用时加载）
+    ///
+    /// 模型只加载一次并使用其默认的归一化设置；`vector.normalize` 要求归一化时
+    /// 由 [`MemoryManager::generate_embeddings`] 在 Rust 侧做 L2 归一化。
+    fn embedding_model() -> Result<&'static model2vec_rs::model::StaticModel> {
+        use model2vec_rs::model::StaticModel;
+        use std::sync::OnceLock;
 
-        // 嵌入向量
-        let embedding_array: Vec<serde_json::Value> = memory
-            .embedding
-            .iter()
-            .map(|&f| serde_json::Value::Number(serde_json::Number::from_f64(f as f64).unwrap()))
-            .collect();
-        metadata.insert(
-            "embedding".to_string(),
-            serde_json::Value::Array(embedding_array),
-        );
+        static MODEL: OnceLock<Option<StaticModel>> = OnceLock::new();
+        MODEL
+            .get_or_init(|| {
+                StaticModel::from_pretrained(
+                    MEMORY_EMBEDDING_MODEL, // 使用多语言模型
+                    None,                   // 无需 HF token
+                    None,                   // 使用模型默认的归一化设置
+                    None,                   // 无子文件夹
+                )
+                .ok()
+            })
+            .as_ref()
+            .ok_or_else(|| MemoryError::Internal {
+                message: "Failed to load embedding model".to_string(),
+            })
+    }
 
-        // 属性
-        metadata.insert(
-            "importance".to_string(),
-            serde_json::Value::Number(
-                serde_json::Number::from_f64(memory.attributes.importance as f64).unwrap(),
-            ),
-        );
-        metadata.insert(
-            "keywords".to_string(),
-            serde_json::Value::Array(
-                memory
-                    .attributes
-                    .keywords
-                    .iter()
-                    .map(|k| serde_json::Value::String(k.clone()))
-                    .collect(),
-            ),
-        );
-        metadata.insert(
-            "tags".to_string(),
-            serde_json::Value::Array(
-                memory
-                    .attributes
-                    .tags
-                    .iter()
-                    .map(|t| serde_json::Value::String(t.clone()))
-                    .collect(),
-            ),
-        );
-        metadata.insert(
-            "context".to_string(),
-            serde_json::Value::String(memory.attributes.context.clone()),
-        );
-        metadata.insert(
-            "confidence".to_string(),
-            serde_json::Value::Number(
-                serde_json::Number::from_f64(memory.attributes.confidence as f64).unwrap(),
-            ),
-        );
+    /// 在阻塞线程池中用共享嵌入模型编码文本
+    ///
+    /// 模型可以并发只读使用，各批次互不等待，也不会占用异步运行时的工作线程。
+    async fn encode_with_shared_model(texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        tokio::task::spawn_blocking(move || {
+            Self::embedding_model().map(|model| model.encode(&texts))
+        })
+        .await
+        .map_err(|e| MemoryError::Internal {
+            message: format!("Embedding task failed: {}", e),
+        })?
+    }
 
-        if let Some(ref source) = memory.attributes.source {
-            metadata.insert(
-                "source".to_string(),
-                serde_json::Value::String(source.to_string()),
-            );
+    /// 按向量配置创建使用共享嵌入模型的嵌入服务
+    pub(crate) fn default_embedding_service(config: &Config) -> EmbeddingService {
+        EmbeddingService::with_embedder(
+            MEMORY_EMBEDDING_MODEL,
+            Box::new(SharedModelEmbedder {
+                dimension: config.vector.dimension,
+            }),
+        )
+        .with_vector_config(&config.vector)
+    }
+
+    pub async fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+        self.generate_embeddings(&[text.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| MemoryError::Internal {
+                message: "Failed to generate embedding: empty result".to_string(),
+            })
+    }
+
+    /// 批量生成嵌入向量，结果与输入一一对应
+    ///
+    /// 通过嵌入服务的 `encode_batch` 编码，已缓存的文本不会重复调用模型。
+    /// `vector.normalize` 要求归一化时返回 L2 归一化后的向量。
+    pub async fn generate_embeddings(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
         }
 
-        if let Some(ref language) = memory.attributes.language {
-            metadata.insert(
-                "language".to_string(),
-                serde_json::Value::String(language.to_string()),
-            );
+        let mut embeddings = self
+            .embedding_service
+            .encode_batch(texts)
+            .await
+            .map_err(|e| MemoryError::Internal {
+                message: format!("Failed to generate embeddings: {}", e),
+            })?;
+        if embeddings.len() != texts.len() {
+            return Err(MemoryError::Internal {
+                message: format!(
+                    "Failed to generate embeddings: expected {}, got {}",
+                    texts.len(),
+                    embeddings.len()
+                ),
+            });
         }
 
-        // 元数据
-        metadata.insert(
-            "created_at".to_string(),
-            serde_json::Value::String(memory.metadata.created_at.to_rfc3339()),
-        );
-        metadata.insert(
-            "updated_at".to_string(),
-            serde_json::Value::String(memory.metadata.updated_at.to_rfc3339()),
-        );
-        metadata.insert(
-            "access_count".to_string(),
-            serde_json::Value::Number(serde_json::Number::from(memory.metadata.access_count)),
-        );
-        metadata.insert(
-            "last_accessed".to_string(),
-            serde_json::Value::String(memory.metadata.last_accessed.to_rfc3339()),
-        );
-        metadata.insert(
-            "version".to_string(),
-            serde_json::Value::Number(serde_json::Number::from(memory.metadata.version)),
-        );
+        if self.config.vector.normalizes_embeddings() {
+            for embedding in &mut embeddings {
+                // 零向量无法归一化，保持原样
+                let _ = VectorUtils::normalize(embedding);
+            }
+        }
 
-        metadata.insert(
-            "is_deleted".to_string(),
-            serde_json::Value::Bool(memory.metadata.is_deleted),
-        );
+        Ok(embeddings)
+    }
 
-        Ok(metadata)
+    /// 按配置的批大小与并发数生成嵌入向量，结果与输入一一对应
+    async fn generate_embeddings_batched(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        Self::embed_in_batches(
+            texts,
+            self.config.vector.embed_batch_size,
+            self.config.vector.embed_concurrency,
+            |batch| async move { self.generate_embeddings(&batch).await },
+        )
+        .await
     }
 
-    fn memory_to_properties(&self, memory: &Memory) -> Result<HashMap<String, serde_json::Value>> {
-        self.memory_to_metadata(memory)
+    /// 将文本切分为大小为 `batch_size` 的批次，最多 `concurrency` 个批次同时调用 `embed`，
+    /// 按输入顺序拼接结果
+    async fn embed_in_batches<F, Fut>(
+        texts: &[String],
+        batch_size: usize,
+        concurrency: usize,
+        embed: F,
+    ) -> Result<Vec<Vec<f32>>>
+    where
+        F: Fn(Vec<String>) -> Fut,
+        Fut: std::future::Future<Output = Result<Vec<Vec<f32>>>>,
+    {
+        let semaphore = &tokio::sync::Semaphore::new(concurrency.max(1));
+        let embed = &embed;
+        let batches = texts.chunks(batch_size.max(1)).map(|batch| async move {
+            let _permit = semaphore
+                .acquire()
+                .await
+                .map_err(|e| MemoryError::Internal {
+                    message: format!("Embedding semaphore closed: {}", e),
+                })?;
+            embed(batch.to_vec()).await
+        });
+
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in futures::future::try_join_all(batches).await? {
+            embeddings.extend(batch);
+        }
+        Ok(embeddings)
     }
 
-    fn metadata_to_memory(&self, metadata: &HashMap<String, serde_json::Value>) -> Result<Memory> {
-        // 从元数据重构Memory对象
-        let id = metadata
-            .get("id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| MemoryError::Internal {
-                message: "Missing memory id in metadata".to_string(),
-            })?
-            .to_string();
+    /// 提取属性，返回属性及提取器给出的重要性估计
+    ///
+    /// 设置了属性提取器时优先使用其结果，提取失败或回复无法解析时退回到简单提取。
+    async fn extract_attributes(
+        &self,
+        content: &str,
+        context: &Context,
+    ) -> Result<(MemoryAttributes, Option<f32>)> {
+        if let Some(extractor) = &self.attribute_extractor {
+            match extractor.extract(content, context).await {
+                Ok(reply) => match Self::parse_extracted_attributes(&reply, context) {
+                    Some(extracted) => return Ok(extracted),
+                    None => warn!(
+                        "Failed to parse extracted attributes, falling back: {}",
+                        reply
+                    ),
+                },
+                Err(e) => warn!("Attribute extraction failed, falling back: {}", e),
+            }
+        }
 
-        let content = metadata
-            .get("content")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| MemoryError::Internal {
-                message: "Missing content in metadata".to_string(),
-            })?
-            .to_string();
+        // 简单的属性提取实现
+        let words: Vec<&str> = content.split_whitespace().collect();
+        let keywords = words.iter().take(5).map(|s| s.to_string()).collect();
 
-        let memory_type_str = metadata
-            .get("memory_type")
-            .and_then(|v| v.as_str())
-            .unwrap_or("Knowledge");
-        let memory_type = match memory_type_str {
-            "Knowledge" => MemoryType::Knowledge,
+        Ok((
+            MemoryAttributes {
+                keywords,
+                tags: Vec::new(),
+                context: context.current_topic.clone().unwrap_or_default(),
+                importance: 0.5,
+                emotion: None,
+                source: None,
+                confidence: 1.0,
+                language: Some("zh".to_string()),
+                custom_attributes: HashMap::new(),
+            },
+            None,
+        ))
+    }
+
+    /// 解析属性提取器的 JSON 回复，允许回复中包含 JSON 以外的文字
+    fn parse_extracted_attributes(
+        reply: &str,
+        context: &Context,
+    ) -> Option<(MemoryAttributes, Option<f32>)> {
+        let start = reply.find('{')?;
+        let end = reply.rfind('}')?;
+        if end < start {
+            return None;
+        }
+
+        let extracted: ExtractedAttributes = serde_json::from_str(&reply[start..=end]).ok()?;
+        let keywords: Vec<String> = extracted
+            .keywords
+            .into_iter()
+            .map(|keyword| keyword.trim().to_string())
+            .filter(|keyword| !keyword.is_empty())
+            .collect();
+        if keywords.is_empty() {
+            return None;
+        }
+
+        let importance = extracted
+            .importance
+            .filter(|importance| importance.is_finite())
+            .map(|importance| importance.clamp(0.0, 1.0));
+
+        Some((
+            MemoryAttributes {
+                keywords,
+                tags: extracted.tags,
+                context: extracted
+                    .context
+                    .filter(|context| !context.is_empty())
+                    .or_else(|| context.current_topic.clone())
+                    .unwrap_or_default(),
+                importance: importance.unwrap_or(0.5),
+                emotion: extracted.emotion.filter(|emotion| !emotion.is_empty()),
+                source: None,
+                confidence: 0.8,
+                language: Some("zh".to_string()),
+                custom_attributes: HashMap::new(),
+            },
+            importance,
+        ))
+    }
+
+    fn connection_to_edge(connection: &Connection) -> GraphEdge {
+        let mut properties = connection.properties.clone();
+        properties.insert(
+            BIDIRECTIONAL_PROPERTY.to_string(),
+            serde_json::Value::Bool(connection.bidirectional),
+        );
+        GraphEdge {
+            id: connection.id.clone(),
+            from_node: connection.from_memory.clone(),
+            to_node: connection.to_memory.clone(),
+            // 以 `{:?}` 格式存储，由 `ConnectionType::from_edge_type` 解析回连接类型
+            edge_type: format!("{:?}", connection.connection_type),
+            weight: connection.strength,
+            properties,
+            created_at: connection.created_at,
+            updated_at: connection.updated_at,
+        }
+    }
+
+    /// 创建连接；相同起点、终点与类型的连接已存在时累加其强度，而不是插入重复的边
+    pub async fn create_connection(&self, connection: &Connection) -> Result<()> {
+        let edge = Self::connection_to_edge(connection);
+
+        let edge_id = self.db.upsert_edge_with_accumulation(&edge).await?;
+        let inserted = edge_id == edge.id;
+
+        // 更新统计信息
+        self.update_stats(|stats| {
+            if inserted {
+                stats.total_connections += 1;
+            }
+            stats.last_updated = Utc::now();
+        })
+        .await;
+
+        Ok(())
+    }
+
+    async fn remove_connection(&self, connection_id: &ConnectionId) -> Result<()> {
+        // TODO: 实现连接删除逻辑
+        warn!("Connection removal not implemented: {}", connection_id);
+        Ok(())
+    }
+
+    /// 获取记忆，并按连接类型从已存储的边重建各类链接
+    pub async fn get_memory(&self, memory_id: &MemoryId) -> Result<Memory> {
+        let vector = self.db.get_vector(memory_id).await.map_err(|e| {
+            if let MemoryError::Database(_) = e {
+                MemoryError::MemoryNotFound {
+                    id: memory_id.clone(),
+                }
+            } else {
+                e
+            }
+        })?;
+
+        let mut memory = self.metadata_to_memory(&vector.metadata)?;
+        memory.embedding = vector.embedding;
+
+        // 按连接类型从已存储的边重建各类链接
+        for connection in self.get_connections(memory_id).await? {
+            let target = if &connection.from_memory == memory_id {
+                connection.to_memory
+            } else {
+                connection.from_memory
+            };
+            memory
+                .connections
+                .add_link(&connection.connection_type, target);
+        }
+
+        Ok(memory)
+    }
+
+    /// 只按过滤条件列出记忆，不生成查询向量也不计算相似度
+    ///
+    /// 支持标签、时间范围、重要性/置信度阈值、来源、语言与情感过滤，`custom_filters`
+    /// 按元数据顶层字段逐一相等比较（例如 `memory_type` 为 `"Task"`）；`preferred_emotion`
+    /// 与 `min_similarity` 依赖检索评分，这里忽略。未指定排序或按相关性、自定义排序时按创建
+    /// 时间降序。返回的记忆不含链接，需要时用 [`MemoryManager::get_memory`] 获取完整记忆。
+    pub async fn list_memories(
+        &self,
+        filters: QueryFilters,
+        sort: Option<SortBy>,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Memory>> {
+        let mut conditions = Vec::new();
+        for tag in filters.tags.unwrap_or_default() {
+            conditions.push(MetadataCondition::Contains("tags".to_string(), tag));
+        }
+        if let Some((start, end)) = filters.time_range {
+            conditions.push(MetadataCondition::Between(
+                "created_at".to_string(),
+                start,
+                end,
+            ));
+        }
+        if let Some(threshold) = filters.importance_threshold {
+            conditions.push(MetadataCondition::AtLeast(
+                "importance".to_string(),
+                threshold as f64,
+            ));
+        }
+        if let Some(threshold) = filters.confidence_threshold {
+            conditions.push(MetadataCondition::AtLeast(
+                "confidence".to_string(),
+                threshold as f64,
+            ));
+        }
+        for (key, value) in [
+            ("source", filters.source_filter),
+            ("language", filters.language_filter),
+            ("emotion", filters.emotion_filter),
+        ] {
+            if let Some(value) = value {
+                conditions.push(MetadataCondition::Equals(
+                    key.to_string(),
+                    serde_json::Value::String(value),
+                ));
+            }
+        }
+        for (key, value) in filters.custom_filters {
+            conditions.push(MetadataCondition::Equals(key, value));
+        }
+
+        let order_by = match sort {
+            Some(SortBy::CreatedAt(order)) => {
+                Some((MetadataSortKey::Time("created_at".to_string()), order))
+            }
+            Some(SortBy::Importance(order)) => {
+                Some((MetadataSortKey::Number("importance".to_string()), order))
+            }
+            Some(SortBy::AccessCount(order)) => {
+                Some((MetadataSortKey::Number("access_count".to_string()), order))
+            }
+            Some(SortBy::Confidence(order)) => {
+                Some((MetadataSortKey::Number("confidence".to_string()), order))
+            }
+            Some(SortBy::Relevance(_)) | Some(SortBy::Custom(_)) | None => None,
+        };
+
+        let vectors = self
+            .db
+            .scan_vectors(&conditions, order_by, limit, offset)
+            .await?;
+        vectors
+            .into_iter()
+            .map(|vector| {
+                let mut memory = self.metadata_to_memory(&vector.metadata)?;
+                memory.embedding = vector.embedding;
+                Ok(memory)
+            })
+            .collect()
+    }
+
+    /// 已存在的记忆ID（向量与节点）
+    async fn existing_memory_ids(&self) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+
+        let mut after: Option<String> = None;
+        loop {
+            let page = self
+                .db
+                .list_vector_ids(after.as_deref(), IMPORT_ID_PAGE_SIZE, true)
+                .await?;
+            after = page.last().cloned();
+            ids.extend(page);
+            if after.is_none() {
+                break;
+            }
+        }
+
+        let mut after: Option<String> = None;
+        loop {
+            let page = self
+                .db
+                .list_node_ids(after.as_deref(), IMPORT_ID_PAGE_SIZE)
+                .await?;
+            after = page.last().cloned();
+            ids.extend(page);
+            if after.is_none() {
+                break;
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// 已存在的边ID
+    async fn existing_edge_ids(&self) -> Result<HashSet<String>> {
+        let mut ids = HashSet::new();
+        let mut after: Option<String> = None;
+        loop {
+            let page = self
+                .db
+                .list_edge_ids(after.as_deref(), IMPORT_ID_PAGE_SIZE)
+                .await?;
+            after = page.last().cloned();
+            ids.extend(page);
+            if after.is_none() {
+                break;
+            }
+        }
+        Ok(ids)
+    }
+
+    /// 为与已有记录冲突的导入记录分配新ID，并同步更新边的端点
+    fn rename_conflicting_ids(
+        vectors: &mut [Vector],
+        nodes: &mut [GraphNode],
+        edges: &mut [GraphEdge],
+        existing_memories: &HashSet<String>,
+        existing_edges: &HashSet<String>,
+    ) {
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        let ids = vectors
+            .iter()
+            .map(|v| &v.id)
+            .chain(nodes.iter().map(|n| &n.id));
+        for id in ids {
+            if existing_memories.contains(id) && !renamed.contains_key(id) {
+                renamed.insert(id.clone(), Uuid::new_v4().to_string());
+            }
+        }
+
+        let rename_record = |id: &mut String, fields: &mut HashMap<String, serde_json::Value>| {
+            if let Some(new_id) = renamed.get(id.as_str()) {
+                if fields.get("id").and_then(|v| v.as_str()) == Some(id.as_str()) {
+                    fields.insert("id".to_string(), serde_json::Value::String(new_id.clone()));
+                }
+                *id = new_id.clone();
+            }
+        };
+        for vector in vectors.iter_mut() {
+            rename_record(&mut vector.id, &mut vector.metadata);
+        }
+        for node in nodes.iter_mut() {
+            rename_record(&mut node.id, &mut node.properties);
+        }
+
+        for edge in edges.iter_mut() {
+            if existing_edges.contains(&edge.id) {
+                edge.id = Uuid::new_v4().to_string();
+            }
+            if let Some(new_id) = renamed.get(&edge.from_node) {
+                edge.from_node = new_id.clone();
+            }
+            if let Some(new_id) = renamed.get(&edge.to_node) {
+                edge.to_node = new_id.clone();
+            }
+        }
+    }
+
+    /// 使检索缓存失效：指定记忆ID时只移除包含该记忆的结果，否则全部清空
+    async fn invalidate_retrieval_cache(&self, memory_id: Option<&MemoryId>) {
+        let strategy_cache = match &self.strategy_retriever {
+            Some(retriever) => Some(retriever.read().await.cache_handle()),
+            None => None,
+        };
+        for cache in self.retrieval_cache.iter().chain(strategy_cache.iter()) {
+            let mut cache = cache.write().await;
+            match memory_id {
+                Some(memory_id) => cache.invalidate_memory(memory_id),
+                None => cache.invalidate_all(),
+            }
+        }
+    }
+
+    async fn save_memory(&self, memory: &Memory) -> Result<()> {
+        let vector = Vector {
+            id: memory.id.clone(),
+            embedding: memory.embedding.clone(),
+            metadata: self.memory_to_metadata(memory)?,
+            created_at: memory.metadata.created_at,
+            updated_at: memory.metadata.updated_at,
+        };
+        self.db.update_vector(&vector).await?;
+
+        let node = GraphNode {
+            id: memory.id.clone(),
+            node_type: "memory".to_string(),
+            properties: self.memory_to_properties(memory)?,
+            created_at: memory.metadata.created_at,
+            updated_at: memory.metadata.updated_at,
+        };
+        self.db.update_node(&node).await?;
+
+        Ok(())
+    }
+
+    /// 记录用户交互
+    pub async fn record_interaction(&self, interaction: &Interaction) -> Result<()> {
+        debug!("Recording interaction: {:?}", interaction.interaction_type);
+
+        // 更新记忆的访问统计
+        for memory_id in &interaction.retrieved_memories {
+            // TODO: 更新记忆的访问计数和最后访问时间
+            info!("Updated access stats for memory: {}", memory_id);
+        }
+
+        // 更新统计信息
+        self.update_stats(|stats| {
+            stats.last_updated = Utc::now();
+        })
+        .await;
+
+        Ok(())
+    }
+
+    /// 获取记忆的连接
+    pub async fn get_connections(&self, memory_id: &MemoryId) -> Result<Vec<Connection>> {
+        debug!("Getting connections for memory: {}", memory_id);
+
+        let edges = self.db.get_incident_edges(memory_id, &None).await?;
+
+        let mut connections = Vec::new();
+        for edge in edges {
+            let bidirectional = edge.is_bidirectional();
+            let mut properties = edge.properties;
+            properties.remove(BIDIRECTIONAL_PROPERTY);
+            let connection = Connection {
+                id: edge.id,
+                from_memory: edge.from_node,
+                to_memory: edge.to_node,
+                connection_type: ConnectionType::from_edge_type(&edge.edge_type),
+                strength: edge.weight,
+                created_at: edge.created_at,
+                updated_at: edge.updated_at,
+                properties,
+                bidirectional,
+            };
+            connections.push(connection);
+        }
+
+        Ok(connections)
+    }
+
+    async fn update_stats<F>(&self, updater: F)
+    where
+        F: FnOnce(&mut MemoryStats),
+    {
+        {
+            let mut stats = self.stats.write().await;
+            updater(&mut stats);
+            telemetry::set_memories_total(stats.total_memories);
+        }
+
+        let persisted_at = *self.stats_persisted_at.read().await;
+        if Utc::now() - persisted_at >= Duration::seconds(STATS_PERSIST_INTERVAL_SECS) {
+            if let Err(e) = self.persist_stats().await {
+                warn!("Failed to persist memory stats: {}", e);
+            }
+        }
+    }
+
+    /// 从数据库恢复统计：总数按现有记录计算，累计计数取上次写回的值
+    async fn load_stats(db: &VectorGraphDB) -> Result<MemoryStats> {
+        let (total_memories, _, total_connections, _) = db.get_stats().await?;
+        let counters = db.load_stat_counters().await?;
+        let counter = |name: &str| counters.get(name).copied().unwrap_or(0);
+
+        Ok(MemoryStats {
+            total_memories,
+            total_connections,
+            average_importance: db.average_importance().await?,
+            retrieval_count: counter("retrieval_count"),
+            creation_count: counter("creation_count"),
+            evolution_count: counter("evolution_count"),
+            last_updated: Utc::now(),
+        })
+    }
+
+    // 转换方法
+
+    fn memory_to_metadata(&self, memory: &Memory) -> Result<HashMap<String, serde_json::Value>> {
+        let mut metadata = HashMap::new();
+
+        // 基本信息
+        metadata.insert(
+            "id".to_string(),
+            serde_json::Value::String(memory.id.clone()),
+        );
+        metadata.insert(
+            "content".to_string(),
+            serde_json::Value::String(memory.content.clone()),
+        );
+        metadata.insert(
+            "memory_type".to_string(),
+            serde_json::Value::String(format!("{:?}", memory.memory_type)),
+        );
+
+        // 嵌入向量只保存在向量行的二进制列中，不写入元数据
+
+        // 属性
+        metadata.insert(
+            "importance".to_string(),
+            serde_json::Value::Number(
+                serde_json::Number::from_f64(memory.attributes.importance as f64).unwrap(),
+            ),
+        );
+        metadata.insert(
+            "keywords".to_string(),
+            serde_json::Value::Array(
+                memory
+                    .attributes
+                    .keywords
+                    .iter()
+                    .map(|k| serde_json::Value::String(k.clone()))
+                    .collect(),
+            ),
+        );
+        metadata.insert(
+            "tags".to_string(),
+            serde_json::Value::Array(
+                memory
+                    .attributes
+                    .tags
+                    .iter()
+                    .map(|t| serde_json::Value::String(t.clone()))
+                    .collect(),
+            ),
+        );
+        metadata.insert(
+            "context".to_string(),
+            serde_json::Value::String(memory.attributes.context.clone()),
+        );
+        metadata.insert(
+            "confidence".to_string(),
+            serde_json::Value::Number(
+                serde_json::Number::from_f64(memory.attributes.confidence as f64).unwrap(),
+            ),
+        );
+
+        if let Some(ref source) = memory.attributes.source {
+            metadata.insert(
+                "source".to_string(),
+                serde_json::Value::String(source.to_string()),
+            );
+        }
+
+        if let Some(ref language) = memory.attributes.language {
+            metadata.insert(
+                "language".to_string(),
+                serde_json::Value::String(language.to_string()),
+            );
+        }
+
+        if let Some(ref emotion) = memory.attributes.emotion {
+            metadata.insert(
+                "emotion".to_string(),
+                serde_json::Value::String(emotion.to_string()),
+            );
+        }
+
+        // 元数据
+        metadata.insert(
+            "created_at".to_string(),
+            serde_json::Value::String(memory.metadata.created_at.to_rfc3339()),
+        );
+        metadata.insert(
+            "updated_at".to_string(),
+            serde_json::Value::String(memory.metadata.updated_at.to_rfc3339()),
+        );
+        metadata.insert(
+            "access_count".to_string(),
+            serde_json::Value::Number(serde_json::Number::from(memory.metadata.access_count)),
+        );
+        metadata.insert(
+            "last_accessed".to_string(),
+            serde_json::Value::String(memory.metadata.last_accessed.to_rfc3339()),
+        );
+        metadata.insert(
+            "version".to_string(),
+            serde_json::Value::Number(serde_json::Number::from(memory.metadata.version)),
+        );
+
+        metadata.insert(
+            "is_deleted".to_string(),
+            serde_json::Value::Bool(memory.metadata.is_deleted),
+        );
+
+        if !memory.metadata.custom_metadata.is_empty() {
+            metadata.insert(
+                "custom_metadata".to_string(),
+                serde_json::Value::Object(
+                    memory
+                        .metadata
+                        .custom_metadata
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect(),
+                ),
+            );
+        }
+
+        Ok(metadata)
+    }
+
+    fn memory_to_properties(&self, memory: &Memory) -> Result<HashMap<String, serde_json::Value>> {
+        self.memory_to_metadata(memory)
+    }
+
+    fn metadata_to_memory(&self, metadata: &HashMap<String, serde_json::Value>) -> Result<Memory> {
+        // 从元数据重构Memory对象
+        let id = metadata
+            .get("id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| MemoryError::Internal {
+                message: "Missing memory id in metadata".to_string(),
+            })?
+            .to_string();
+
+        let content = metadata
+            .get("content")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| MemoryError::Internal {
+                message: "Missing content in metadata".to_string(),
+            })?
+            .to_string();
+
+        let memory_type_str = metadata
+            .get("memory_type")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Knowledge");
+        let memory_type = match memory_type_str {
+            "Knowledge" => MemoryType::Knowledge,
             "Event" => MemoryType::Event,
             "Task" => MemoryType::Task,
             "Conversation" => MemoryType::Conversation,
@@ -1093,483 +2453,2016 @@ impl MemoryManager {
             _ => MemoryType::Knowledge,
         };
 
-        let embedding = metadata
-            .get("embedding")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_f64().map(|f| f as f32))
-                    .collect()
+        let importance = metadata
+            .get("importance")
+            .and_then(|v| v.as_f64())
+            .unwrap_or(0.5) as f32;
+
+        let keywords = metadata
+            .get("keywords")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let tags = metadata
+            .get("tags")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let created_at = metadata
+            .get("created_at")
+            .and_then(|v| v.as_str())
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now);
+
+        let updated_at = metadata
+            .get("updated_at")
+            .and_then(|v| v.as_str())
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or_else(Utc::now);
+
+        let attributes = MemoryAttributes {
+            keywords,
+            tags,
+            context: metadata
+                .get("context")
+                .and_then(|v| v.as_str())
+                .unwrap_or("")
+                .to_string(),
+            importance,
+            emotion: metadata
+                .get("emotion")
+                .and_then(|v| v.as_str())
+                .map(|s: &str| s.to_string()),
+            source: metadata
+                .get("source")
+                .and_then(|v| v.as_str())
+                .map(|s: &str| s.to_string()),
+            confidence: metadata
+                .get("confidence")
+                .and_then(|v| v.as_f64())
+                .unwrap_or(1.0) as f32,
+            language: metadata
+                .get("language")
+                .and_then(|v| v.as_str())
+                .map(|s: &str| s.to_string()),
+            custom_attributes: HashMap::new(),
+        };
+
+        let metadata_obj = MemoryMetadata {
+            created_at,
+            updated_at,
+            access_count: metadata
+                .get("access_count")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(0),
+            last_accessed: metadata
+                .get("last_accessed")
+                .and_then(|v| v.as_str())
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                .map(|dt| dt.with_timezone(&Utc))
+                .unwrap_or_else(Utc::now),
+            version: metadata
+                .get("version")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(1) as u32,
+
+            is_deleted: metadata
+                .get("is_deleted")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false),
+            deleted_at: None,
+            custom_metadata: metadata
+                .get("custom_metadata")
+                .and_then(|v| v.as_object())
+                .map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+                .unwrap_or_default(),
+        };
+
+        let connections = MemoryConnections {
+            semantic_links: Vec::new(),
+            temporal_links: Vec::new(),
+            causal_links: Vec::new(),
+            thematic_links: Vec::new(),
+            custom_links: HashMap::new(),
+        };
+
+        // 元数据不含嵌入向量，需要时由调用方从向量行填充
+        Ok(Memory {
+            id,
+            content,
+            memory_type,
+            embedding: Vec::new(),
+            attributes,
+            connections,
+            metadata: metadata_obj,
+        })
+    }
+
+    /// 从图节点属性重构记忆，嵌入向量从同 id 的向量行读取
+    async fn properties_to_memory(
+        &self,
+        properties: &HashMap<String, serde_json::Value>,
+    ) -> Result<Memory> {
+        let mut memory = self.metadata_to_memory(properties)?;
+        memory.embedding = self.db.get_vector(&memory.id).await?.embedding;
+        Ok(memory)
+    }
+
+    fn query_filters_to_metadata_filters(
+        &self,
+        _filters: &QueryFilters,
+    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
+        // TODO: 实现查询过滤器转换
+        Ok(None)
+    }
+
+    fn query_filters_to_graph_filters(
+        &self,
+        filters: &QueryFilters,
+    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
+        self.query_filters_to_metadata_filters(filters)
+    }
+
+    /// 时间相关性：以时间窗口终点为参照的指数衰减 exp(-age/τ)
+    ///
+    /// 分数随记忆年龄连续下降，窗口外的记忆仍保留较小的非零分数；
+    /// 是否按窗口硬过滤由 `config.retrieval.temporal_hard_window` 决定。
+    async fn calculate_temporal_relevance(
+        &self,
+        memory: &Memory,
+        time_window: &(DateTime<Utc>, DateTime<Utc>),
+    ) -> Result<f32> {
+        let tau = Duration::seconds((self.config.retrieval.temporal_tau_days * 86_400.0) as i64);
+        Ok(TimeUtils::recency_decay(
+            memory.metadata.created_at,
+            time_window.1,
+            tau,
+        ))
+    }
+
+    /// 按语言提取因果标记词
+    fn extract_causal_keywords(&self, text: &str, language: &str) -> Result<Vec<String>> {
+        let keywords = match language {
+            lang if lang.starts_with("zh") => ZH_CAUSAL_MARKERS
+                .iter()
+                .filter(|marker| text.contains(*marker))
+                .map(|s| s.to_string())
+                .collect(),
+            _ => {
+                // 以空格包围的归一化文本，保证按单词边界匹配
+                let normalized: String = text
+                    .to_lowercase()
+                    .chars()
+                    .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+                    .collect();
+                let words: Vec<&str> = normalized.split_whitespace().collect();
+                let padded = format!(" {} ", words.join(" "));
+                EN_CAUSAL_MARKERS
+                    .iter()
+                    .filter(|marker| padded.contains(&format!(" {} ", marker)))
+                    .map(|s| s.to_string())
+                    .collect()
+            }
+        };
+        Ok(keywords)
+    }
+
+    async fn calculate_causal_relevance(
+        &self,
+        memory: &Memory,
+        keywords: &[String],
+    ) -> Result<f32> {
+        let content_lower = memory.content.to_lowercase();
+        let matches = keywords
+            .iter()
+            .filter(|k| content_lower.contains(&k.to_lowercase()))
+            .count();
+        Ok(matches as f32 / keywords.len().max(1) as f32)
+    }
+
+    /// 按语言提取主题词
+    fn extract_themes(&self, text: &str, language: &str) -> Result<Vec<String>> {
+        let custom_stopwords: HashSet<String> = self
+            .config
+            .retrieval
+            .theme_stopwords
+            .iter()
+            .filter(|(lang, _)| language.starts_with(lang.as_str()))
+            .flat_map(|(_, words)| words.iter().map(|w| w.to_lowercase()))
+            .collect();
+
+        let candidates: Vec<String> = match language {
+            lang if lang.starts_with("zh") => {
+                // 中文没有空格分词，先去掉因果标记词，再按标点和虚词切分
+                let stripped = ZH_CAUSAL_MARKERS
+                    .iter()
+                    .fold(text.to_string(), |acc, marker| acc.replace(marker, " "));
+                stripped
+                    .split(|c: char| !c.is_alphanumeric() || ZH_THEME_STOP_CHARS.contains(&c))
+                    .flat_map(Self::cjk_theme_terms)
+                    .filter(|w| !custom_stopwords.contains(w))
+                    .collect()
+            }
+            _ => text
+                .split_whitespace()
+                .map(|w| {
+                    w.trim_matches(|c: char| !c.is_alphanumeric())
+                        .to_lowercase()
+                })
+                .filter(|w| {
+                    w.chars().count() >= MIN_EN_THEME_LEN
+                        && !EN_THEME_STOPWORDS.contains(&w.as_str())
+                        && !custom_stopwords.contains(w)
+                })
+                .collect(),
+        };
+
+        // 按出现频率选取主题，频率相同时保留先出现的
+        let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+        for (position, word) in candidates.iter().enumerate() {
+            counts.entry(word.as_str()).or_insert((0, position)).0 += 1;
+        }
+        let mut ranked: Vec<(&str, (usize, usize))> = counts.into_iter().collect();
+        ranked.sort_by(|a, b| b.1 .0.cmp(&a.1 .0).then(a.1 .1.cmp(&b.1 .1)));
+
+        Ok(ranked
+            .into_iter()
+            .take(MAX_THEMES)
+            .map(|(word, _)| word.to_string())
+            .collect())
+    }
+
+    /// 中文片段的主题候选：短片段整体保留，过长的片段（通常是未能切开的短语）退化为字二元组
+    fn cjk_theme_terms(segment: &str) -> Vec<String> {
+        let chars: Vec<char> = segment.chars().collect();
+        if chars.len() < 2 {
+            Vec::new()
+        } else if chars.len() <= MAX_CJK_THEME_LEN {
+            vec![segment.to_string()]
+        } else {
+            chars.windows(2).map(|w| w.iter().collect()).collect()
+        }
+    }
+
+    /// 确定查询语言：优先使用语言过滤条件，否则根据文本检测，默认中文
+    fn query_language(query: &Query) -> String {
+        query
+            .filters
+            .language_filter
+            .clone()
+            .or_else(|| TextUtils::detect_language(&query.text))
+            .unwrap_or_else(|| "zh".to_string())
+    }
+
+    fn create_theme_filters(
+        &self,
+        themes: &[String],
+    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
+        let mut filters = HashMap::new();
+        filters.insert(
+            "themes".to_string(),
+            serde_json::Value::Array(
+                themes
+                    .iter()
+                    .map(|t| serde_json::Value::String(t.clone()))
+                    .collect(),
+            ),
+        );
+        Ok(Some(filters))
+    }
+
+    async fn calculate_thematic_relevance(
+        &self,
+        memory: &Memory,
+        themes: &[String],
+    ) -> Result<f32> {
+        let content_lower = memory.content.to_lowercase();
+        let matches = themes
+            .iter()
+            .filter(|theme| content_lower.contains(&theme.to_lowercase()))
+            .count();
+        Ok(matches as f32 / themes.len().max(1) as f32)
+    }
+
+    async fn fuse_retrieval_results(
+        &self,
+        results_list: Vec<Vec<RetrievalResult>>,
+        weights: &QueryWeights,
+    ) -> Result<Vec<RetrievalResult>> {
+        let mut fused_results = HashMap::new();
+
+        for (i, results) in results_list.iter().enumerate() {
+            let weight = match i {
+                0 => weights.semantic_weight,
+                1 => weights.temporal_weight,
+                2 => weights.importance_weight,
+                _ => 0.1,
+            };
+
+            for result in results {
+                let entry = fused_results
+                    .entry(result.memory.id.clone())
+                    .or_insert_with(|| RetrievalResult {
+                        relevance_score: 0.0,
+                        ..result.clone()
+                    });
+                entry.relevance_score += result.relevance_score * weight;
+                entry.explanation.merge(&result.explanation);
+            }
+        }
+
+        let mut final_results: Vec<RetrievalResult> = fused_results.into_values().collect();
+        final_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
+
+        Ok(final_results)
+    }
+
+    async fn compute_personalized_pagerank(
+        &self,
+        personalization: &PersonalizationVector,
+        query: &str,
+    ) -> Result<HashMap<MemoryId, f32>> {
+        // 简化的PageRank实现
+        let mut scores = HashMap::new();
+
+        // 基于用户偏好计算分数
+        for (preference, weight) in &personalization.preferences {
+            if query.contains(preference) {
+                scores.insert(format!("memory_{}", preference), *weight);
+            }
+        }
+
+        Ok(scores)
+    }
+}
+
+// 实现各个组件
+
+impl LinkAnalyzer {
+    fn new(config: &Config) -> Self {
+        Self {
+            semantic_threshold: config.vector.similarity_threshold,
+            temporal_window: Duration::hours(24),
+            causal_patterns: vec!["因为".to_string(), "所以".to_string(), "导致".to_string()],
+            thematic_keywords: HashMap::new(),
+        }
+    }
+
+    async fn discover_connections(
+        &self,
+        memory: &Memory,
+        context: &Context,
+    ) -> Result<Vec<Connection>> {
+        let mut connections = Vec::new();
+
+        // 基于最近记忆发现连接
+        for recent_memory_id in &context.recent_memories {
+            if recent_memory_id != &memory.id {
+                let mut connection = Connection::new(
+                    memory.id.clone(),
+                    recent_memory_id.clone(),
+                    ConnectionType::Temporal,
+                    0.7,
+                );
+                // 时间上的邻近没有方向
+                connection.bidirectional = true;
+                connections.push(connection);
+            }
+        }
+
+        Ok(connections)
+    }
+}
+
+impl ImportanceCalculator {
+    fn new(config: &Config) -> Self {
+        let importance = &config.learning.importance;
+        Self {
+            base_importance: importance.base_importance,
+            low_priority_offset: importance.low_priority_offset,
+            normal_priority_offset: importance.normal_priority_offset,
+            high_priority_offset: importance.high_priority_offset,
+            critical_priority_offset: importance.critical_priority_offset,
+            access_weight: importance.access_weight,
+            recency_weight: importance.recency_weight,
+            connection_weight: importance.connection_weight,
+            feedback_weight: importance.feedback_weight,
+            content_weight: importance.content_weight,
+        }
+    }
+
+    async fn calculate_initial_importance(
+        &self,
+        memory: &Memory,
+        context: &Context,
+    ) -> Result<f32> {
+        let mut importance = self.base_importance;
+
+        // 基于内容长度调整
+        let content_factor = (memory.content.len() as f32 / 1000.0).min(1.0);
+        importance += content_factor * self.content_weight;
+
+        // 基于上下文优先级调整
+        importance += match context.priority {
+            Priority::Critical => self.critical_priority_offset,
+            Priority::High => self.high_priority_offset,
+            Priority::Normal => self.normal_priority_offset,
+            Priority::Low => self.low_priority_offset,
+        };
+
+        Ok(importance.clamp(0.0, 1.0))
+    }
+
+    /// 按累积信号重新计算重要性：各因子归一化到 [0, 1] 后按权重加权平均
+    ///
+    /// 时间因子为最后访问时间相对 `now` 的指数衰减 exp(-age/τ)，没有反馈时反馈因子取中性值 0.5。
+    fn recalculate_importance(
+        &self,
+        memory: &Memory,
+        degree: usize,
+        now: DateTime<Utc>,
+        tau: Duration,
+    ) -> f32 {
+        let access_factor =
+            1.0 - (-(memory.metadata.access_count as f32) / IMPORTANCE_ACCESS_SCALE).exp();
+        let recency_factor = TimeUtils::recency_decay(memory.metadata.last_accessed, now, tau);
+        let connection_factor = 1.0 - (-(degree as f32) / IMPORTANCE_DEGREE_SCALE).exp();
+        let feedback_factor = memory
+            .metadata
+            .custom_metadata
+            .get("feedback_score")
+            .and_then(|v| v.as_f64())
+            .map(|score| (score as f32).clamp(0.0, 1.0))
+            .unwrap_or(0.5);
+        let content_factor = (memory.content.len() as f32 / 1000.0).min(1.0);
+
+        let total_weight = self.access_weight
+            + self.recency_weight
+            + self.connection_weight
+            + self.feedback_weight
+            + self.content_weight;
+        if total_weight <= 0.0 {
+            return memory.attributes.importance;
+        }
+
+        let weighted = access_factor * self.access_weight
+            + recency_factor * self.recency_weight
+            + connection_factor * self.connection_weight
+            + feedback_factor * self.feedback_weight
+            + content_factor * self.content_weight;
+        (weighted / total_weight).clamp(0.0, 1.0)
+    }
+}
+
+impl EvolutionEngine {
+    fn new(config: &Config) -> Self {
+        Self {
+            decay_factor: config.learning.importance_decay_factor,
+            reinforcement_factor: 1.05,
+            pruning_threshold: 0.1,
+            evolution_interval: Duration::hours(config.learning.learning_interval_hours as i64),
+        }
+    }
+
+    /// 按距上次更新或访问的时长衰减重要性：每经过一个演化间隔乘一次衰减因子
+    ///
+    /// 不足一个间隔或重要性没有降低时返回 `None`。
+    fn decayed_importance(&self, memory: &Memory, now: DateTime<Utc>) -> Option<f32> {
+        let since = memory
+            .metadata
+            .last_accessed
+            .max(memory.metadata.updated_at);
+        let interval = self.evolution_interval.num_seconds().max(1) as f32;
+        let periods = (now - since).num_seconds() as f32 / interval;
+        if periods < 1.0 {
+            return None;
+        }
+
+        let current = memory.attributes.importance;
+        let importance = (current * self.decay_factor.powf(periods)).clamp(0.0, 1.0);
+        (importance < current).then_some(importance)
+    }
+
+    /// 按反馈评分调整重要性：正反馈按强化因子放大，负反馈按衰减因子缩小
+    fn feedback_importance(&self, importance: f32, score: f32) -> f32 {
+        let strength = (score - 0.5) * 2.0;
+        let factor = if strength >= 0.0 {
+            1.0 + (self.reinforcement_factor - 1.0) * strength
+        } else {
+            1.0 + (1.0 - self.decay_factor) * strength
+        };
+        (importance * factor).clamp(0.0, 1.0)
+    }
+}
+
+impl RetrievalEngine {
+    async fn new(config: &Config) -> Result<Self> {
+        Ok(Self {
+            pagerank_config: PageRankConfig {
+                damping_factor: config.graph.pagerank.damping_factor,
+                max_iterations: config.graph.pagerank.max_iterations,
+                convergence_threshold: config.graph.pagerank.convergence_threshold,
+                personalization_weight: config.graph.pagerank.personalization_weight,
+            },
+            fusion_weights: QueryWeights::default(),
+            personalization_cache: Arc::new(RwLock::new(PersonalizationVector::default())),
+        })
+    }
+
+    async fn get_personalization_vector(&self) -> Result<PersonalizationVector> {
+        let cache = self.personalization_cache.read().await;
+        Ok(cache.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core_types::{SortBy, SortOrder};
+    use crate::database::VectorGraphDB;
+    use tempfile::NamedTempFile;
+
+    async fn create_test_memory_manager() -> MemoryManager {
+        let db_path = NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let db_url = format!("sqlite://{}", db_path.display());
+
+        let config = Config {
+            database: crate::config::DatabaseConfig {
+                url: db_url,
+                ..Default::default()
+            },
+            vector: crate::config::VectorConfig {
+                similarity_threshold: 0.0, // 设置为0.0以便测试
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        MemoryManager::new(db, config).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_create_memory() {
+        let manager = create_test_memory_manager().await;
+
+        let request = CreateMemoryRequest {
+            content: "这是一个测试记忆".to_string(),
+            context: Context::default(),
+            attributes: None,
+            force_connections: None,
+        };
+
+        let memory = manager.create_memory_from_request(request).await.unwrap();
+        assert_eq!(memory.content, "这是一个测试记忆");
+        assert!(!memory.id.is_empty());
+        assert!(!memory.embedding.is_empty());
+    }
+
+    #[derive(Debug)]
+    struct StubAttributeExtractor {
+        reply: String,
+    }
+
+    #[async_trait]
+    impl AttributeExtractor for StubAttributeExtractor {
+        async fn extract(&self, _content: &str, _context: &Context) -> Result<String> {
+            Ok(self.reply.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_attribute_extractor_handles_chinese_content() {
+        let content = "我明天要去北京参加人工智能大会，有点紧张。";
+        let request = || CreateMemoryRequest {
+            content: content.to_string(),
+            context: Context::default(),
+            attributes: None,
+            force_connections: None,
+        };
+
+        // 不设置提取器时按空白切分，整句只得到一个关键词
+        let manager = create_test_memory_manager().await;
+        let memory = manager.create_memory_from_request(request()).await.unwrap();
+        assert_eq!(memory.attributes.keywords, vec![content.to_string()]);
+
+        let mut manager = create_test_memory_manager().await;
+        manager.set_attribute_extractor(Arc::new(StubAttributeExtractor {
+            reply: r#"```json
+{"keywords": ["北京", "人工智能大会"], "tags": ["出行"], "context": "参加会议", "emotion": "紧张", "importance": 0.9}
+```"#
+                .to_string(),
+        }));
+        let memory = manager.create_memory_from_request(request()).await.unwrap();
+        assert_eq!(memory.attributes.keywords, vec!["北京", "人工智能大会"]);
+        assert_eq!(memory.attributes.tags, vec!["出行"]);
+        assert_eq!(memory.attributes.context, "参加会议");
+        assert_eq!(memory.attributes.emotion.as_deref(), Some("紧张"));
+        assert!(memory.attributes.importance > 0.5);
+
+        // 回复无法解析时退回简单提取
+        let mut manager = create_test_memory_manager().await;
+        manager.set_attribute_extractor(Arc::new(StubAttributeExtractor {
+            reply: "抱歉，我无法完成".to_string(),
+        }));
+        let memory = manager.create_memory_from_request(request()).await.unwrap();
+        assert_eq!(memory.attributes.keywords, vec![content.to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_semantic_retrieval() {
+        let manager = create_test_memory_manager().await;
+
+        // 创建测试记忆
+        let request = CreateMemoryRequest {
+            content: "人工智能的发展历程".to_string(),
+            context: Context::default(),
+            attributes: None,
+            force_connections: None,
+        };
+        manager.create_memory_from_request(request).await.unwrap();
+
+        // 执行检索
+        let query = Query {
+            text: "AI发展".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+
+        let results = manager
+            .retrieve_memories(&query, &Context::default())
+            .await
+            .unwrap();
+        assert!(!results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_importance_breaks_semantic_ties_when_weighted() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            vector: crate::config::VectorConfig {
+                similarity_threshold: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.retrieval.fusion_weights.importance_weight = 0.5;
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let content = "周末去爬山";
+        let embedding = manager.generate_embedding(content).await.unwrap();
+        let mut ids = Vec::new();
+        for importance in [0.2, 0.9] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Event,
+                embedding.clone(),
+                MemoryAttributes {
+                    importance,
+                    ..Default::default()
+                },
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let query = Query {
+            text: content.to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let results = manager
+            .retrieve_memories(&query, &Context::default())
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].memory.id, ids[1]);
+        let top = &results[0];
+        let expected = top.explanation.semantic_score * 0.5 + 0.9 * 0.5;
+        assert!((top.relevance_score - expected).abs() < 1e-6);
+        assert!(top.explanation.reasoning.contains("importance"));
+
+        // w = 0 时退化为纯语义相似度
+        assert_eq!(blend_importance(0.8, 0.9, 0.0), 0.8);
+    }
+
+    #[tokio::test]
+    async fn test_strategy_override_runs_hipporag_for_semantic_queries() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            vector: crate::config::VectorConfig {
+                similarity_threshold: 0.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let hipporag = RetrievalStrategy::HippoRAG {
+            use_pagerank: false,
+            use_personalization: false,
+            fusion_method: crate::retrieval::FusionMethod::LinearWeighted,
+        };
+        config
+            .retrieval
+            .strategy_overrides
+            .insert(QueryType::Semantic, hipporag);
+
+        // 嵌套混合策略无法执行，校验时报告
+        let mut invalid = config.clone();
+        invalid.retrieval.default_strategy = Some(RetrievalStrategy::Hybrid {
+            strategies: vec![RetrievalStrategy::Hybrid {
+                strategies: Vec::new(),
+                weights: Vec::new(),
+            }],
+            weights: vec![1.0],
+        });
+        let errors = invalid.validate().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "retrieval.default_strategy");
+        assert!(config.validate().is_ok());
+
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
+        manager
+            .create_memory_from_request(CreateMemoryRequest {
+                content: "人工智能的发展历程".to_string(),
+                context: Context::default(),
+                attributes: None,
+                force_connections: None,
             })
-            .unwrap_or_else(|| vec![0.0; self.config.vector.dimension]);
+            .await
+            .unwrap();
 
-        let importance = metadata
-            .get("importance")
-            .and_then(|v| v.as_f64())
-            .unwrap_or(0.5) as f32;
+        let query = Query {
+            text: "人工智能的发展历程".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let results = manager
+            .retrieve_memories(&query, &Context::default())
+            .await
+            .unwrap();
 
-        let keywords = metadata
-            .get("keywords")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
+        assert!(!results.is_empty());
+        for result in &results {
+            let details = result.detailed_explanation.as_ref().unwrap();
+            assert!(matches!(
+                details.strategy_used,
+                RetrievalStrategy::HippoRAG { .. }
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_explain_retrieval_reports_components_without_side_effects() {
+        let manager = create_test_memory_manager().await;
+        let memory = manager
+            .create_memory_from_request(CreateMemoryRequest {
+                content: "昨天讨论了检索权重的调优".to_string(),
+                context: Context::default(),
+                attributes: None,
+                force_connections: None,
             })
-            .unwrap_or_default();
+            .await
+            .unwrap();
+
+        let query = Query {
+            text: "检索权重的调优".to_string(),
+            query_type: QueryType::Temporal,
+            filters: QueryFilters::default(),
+            limit: Some(5),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let explanations = manager
+            .explain_retrieval(&query, &Context::default())
+            .await
+            .unwrap();
+
+        let candidate = explanations
+            .iter()
+            .find(|c| c.memory_id == memory.id)
+            .unwrap();
+        assert_eq!(candidate.rank, 1);
+        assert!(candidate.component_scores.semantic > 0.0);
+        assert!(candidate.component_scores.temporal > 0.0);
+        assert!(
+            (candidate.weighted_scores.semantic
+                - candidate.component_scores.semantic * query.weights.semantic_weight)
+                .abs()
+                < 1e-6
+        );
+        let blended = candidate.weighted_scores.semantic + candidate.weighted_scores.temporal;
+        assert!((candidate.final_score - blended).abs() < 1e-6);
+
+        // 只解释不计入检索统计
+        assert_eq!(manager.get_stats().await.retrieval_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_emotion_round_trip_and_filter() {
+        let manager = create_test_memory_manager().await;
+
+        let mut ids = Vec::new();
+        let samples = [("今天去公园野餐", "开心"), ("今天去公园散步", "难过")];
+        for (content, emotion) in samples {
+            let request = CreateMemoryRequest {
+                content: content.to_string(),
+                context: Context::default(),
+                attributes: Some(MemoryAttributes {
+                    emotion: Some(emotion.to_string()),
+                    ..Default::default()
+                }),
+                force_connections: None,
+            };
+            let memory = manager.create_memory_from_request(request).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let stored = manager.get_memory(&ids[0]).await.unwrap();
+        assert_eq!(stored.attributes.emotion.as_deref(), Some("开心"));
+
+        let query = |filters: QueryFilters| Query {
+            text: "今天去公园".to_string(),
+            query_type: QueryType::Semantic,
+            filters,
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let manager = &manager;
+        let retrieve = |query: Query| async move {
+            manager
+                .retrieve_memories(&query, &Context::default())
+                .await
+                .unwrap()
+        };
+        let score_of = |results: &[RetrievalResult], id: &MemoryId| {
+            results
+                .iter()
+                .find(|r| &r.memory.id == id)
+                .map(|r| r.relevance_score)
+                .unwrap()
+        };
+
+        let all = retrieve(query(QueryFilters::default())).await;
+        assert_eq!(all.len(), 2);
+
+        let happy = retrieve(query(QueryFilters {
+            emotion_filter: Some("开心".to_string()),
+            ..Default::default()
+        }))
+        .await;
+        assert_eq!(happy.len(), 1);
+        assert_eq!(happy[0].memory.id, ids[0]);
+
+        // 偏好情感只加分，不过滤
+        let preferred = retrieve(query(QueryFilters {
+            preferred_emotion: Some("难过".to_string()),
+            ..Default::default()
+        }))
+        .await;
+        assert_eq!(preferred.len(), 2);
+        let boost = manager.config.retrieval.emotion_match_boost;
+        assert!((score_of(&preferred, &ids[1]) - score_of(&all, &ids[1]) - boost).abs() < 1e-6);
+        assert_eq!(score_of(&preferred, &ids[0]), score_of(&all, &ids[0]));
+    }
+
+    #[tokio::test]
+    async fn test_sort_by_reorders_results_independently_of_relevance() {
+        let manager = create_test_memory_manager().await;
+
+        let mut ids = Vec::new();
+        let samples = [
+            ("今天去公园散步", 0.2),
+            ("昨天在家看书", 0.9),
+            ("今天去公园野餐", 0.5),
+        ];
+        for (content, importance) in samples {
+            let request = CreateMemoryRequest {
+                content: content.to_string(),
+                context: Context::default(),
+                attributes: Some(MemoryAttributes {
+                    importance,
+                    ..Default::default()
+                }),
+                force_connections: None,
+            };
+            let memory = manager.create_memory_from_request(request).await.unwrap();
+            ids.push(memory.id);
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        let manager = &manager;
+        let retrieve = |sort_by: Option<SortBy>| async move {
+            let query = Query {
+                text: "今天去公园".to_string(),
+                query_type: QueryType::Semantic,
+                filters: QueryFilters::default(),
+                limit: Some(10),
+                offset: None,
+                sort_by,
+                weights: QueryWeights::default(),
+            };
+            manager
+                .retrieve_memories(&query, &Context::default())
+                .await
+                .unwrap()
+        };
+        let ids_of = |results: &[RetrievalResult]| {
+            results
+                .iter()
+                .map(|r| r.memory.id.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let by_relevance = retrieve(None).await;
+        assert_eq!(by_relevance.len(), 3);
+        assert!(by_relevance
+            .windows(2)
+            .all(|w| w[0].relevance_score >= w[1].relevance_score));
+
+        let newest_first = retrieve(Some(SortBy::CreatedAt(SortOrder::Desc))).await;
+        let expected: Vec<_> = ids.iter().rev().cloned().collect();
+        assert_eq!(ids_of(&newest_first), expected);
+
+        let oldest_first = retrieve(Some(SortBy::CreatedAt(SortOrder::Asc))).await;
+        assert_eq!(ids_of(&oldest_first), ids);
+
+        let by_importance = retrieve(Some(SortBy::Importance(SortOrder::Desc))).await;
+        let importance = |r: &RetrievalResult| r.memory.attributes.importance;
+        assert!(by_importance
+            .windows(2)
+            .all(|w| importance(&w[0]) >= importance(&w[1])));
+    }
+
+    #[tokio::test]
+    async fn test_list_memories_filters_without_query_vector() {
+        let manager = create_test_memory_manager().await;
+
+        // (类型, 标签, 重要性, 创建于几天前)
+        let specs = [
+            (MemoryType::Task, vec!["urgent"], 0.9, 1),
+            (MemoryType::Task, vec!["urgent", "work"], 0.5, 2),
+            (MemoryType::Task, vec!["someday"], 0.8, 1),
+            (MemoryType::Event, vec!["urgent"], 0.7, 1),
+            (MemoryType::Task, vec!["urgent"], 0.6, 20),
+        ];
+        let mut ids = Vec::new();
+        for (index, (memory_type, tags, importance, days_ago)) in specs.into_iter().enumerate() {
+            let mut memory = Memory::new(
+                format!("记忆 {}", index),
+                memory_type,
+                vec![0.1; 4],
+                MemoryAttributes {
+                    tags: tags.into_iter().map(String::from).collect(),
+                    importance,
+                    ..Default::default()
+                },
+            );
+            memory.metadata.created_at = Utc::now() - Duration::days(days_ago);
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
+
+        let mut filters = QueryFilters {
+            tags: Some(vec!["urgent".to_string()]),
+            time_range: Some((Utc::now() - Duration::days(7), Utc::now())),
+            ..Default::default()
+        };
+        filters
+            .custom_filters
+            .insert("memory_type".to_string(), serde_json::json!("Task"));
+
+        let listed = manager
+            .list_memories(
+                filters.clone(),
+                Some(SortBy::Importance(SortOrder::Asc)),
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        let listed_ids: Vec<_> = listed.iter().map(|m| m.id.clone()).collect();
+        assert_eq!(listed_ids, vec![ids[1].clone(), ids[0].clone()]);
+
+        let page = manager
+            .list_memories(filters, Some(SortBy::Importance(SortOrder::Asc)), 1, 1)
+            .await
+            .unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].id, ids[0]);
+    }
+
+    #[tokio::test]
+    async fn test_get_memory_groups_links_by_connection_type() {
+        let manager = create_test_memory_manager().await;
+
+        let mut memories = Vec::new();
+        for content in ["源记忆", "语义", "时间", "因果", "主题", "自定义"] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes::default(),
+            );
+            manager.create_memory(&memory).await.unwrap();
+            memories.push(memory.id);
+        }
+        let connection_types = [
+            ConnectionType::Semantic,
+            ConnectionType::Temporal,
+            ConnectionType::Causal,
+            ConnectionType::Thematic,
+            ConnectionType::Custom("supports".to_string()),
+        ];
+        for (target, connection_type) in memories[1..].iter().zip(connection_types) {
+            manager
+                .create_connection(&Connection::new(
+                    memories[0].clone(),
+                    target.clone(),
+                    connection_type,
+                    0.5,
+                ))
+                .await
+                .unwrap();
+        }
+
+        let connections = manager.get_memory(&memories[0]).await.unwrap().connections;
+        assert_eq!(connections.semantic_links, vec![memories[1].clone()]);
+        assert_eq!(connections.temporal_links, vec![memories[2].clone()]);
+        assert_eq!(connections.causal_links, vec![memories[3].clone()]);
+        assert_eq!(connections.thematic_links, vec![memories[4].clone()]);
+        assert_eq!(connections.custom_links.len(), 1);
+        assert_eq!(
+            connections.custom_links["supports"],
+            vec![memories[5].clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_initial_importance_uses_configured_priority_offsets() {
+        let mut config = Config::default();
+        config.learning.importance = crate::config::ImportanceConfig {
+            base_importance: 0.2,
+            critical_priority_offset: 0.45,
+            content_weight: 0.0,
+            access_weight: 0.7,
+            ..Default::default()
+        };
+        let calculator = ImportanceCalculator::new(&config);
+        assert_eq!(calculator.access_weight, 0.7);
+        assert_eq!(calculator.content_weight, 0.0);
+
+        let memory = Memory::new(
+            "紧急事项".to_string(),
+            MemoryType::Task,
+            vec![0.1; 4],
+            MemoryAttributes::default(),
+        );
+        let context = |priority| Context {
+            priority,
+            ..Default::default()
+        };
+        let critical = calculator
+            .calculate_initial_importance(&memory, &context(Priority::Critical))
+            .await
+            .unwrap();
+        assert!((critical - 0.65).abs() < 1e-6);
+        let normal = calculator
+            .calculate_initial_importance(&memory, &context(Priority::Normal))
+            .await
+            .unwrap();
+        assert!((normal - 0.2).abs() < 1e-6);
+
+        // 超出范围时限制在 [0, 1]
+        config.learning.importance.critical_priority_offset = 1.0;
+        let boosted = ImportanceCalculator::new(&config)
+            .calculate_initial_importance(&memory, &context(Priority::Critical))
+            .await
+            .unwrap();
+        assert_eq!(boosted, 1.0);
+
+        config.learning.importance.base_importance = 1.5;
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recalculate_importance_rises_with_access_and_degree() {
+        let manager = create_test_memory_manager().await;
+
+        let mut memories = Vec::new();
+        for content in ["冷门记忆", "热门记忆", "邻居一", "邻居二", "邻居三"] {
+            let mut memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes::default(),
+            );
+            if content == "热门记忆" {
+                memory.metadata.access_count = 50;
+            }
+            manager.create_memory(&memory).await.unwrap();
+            memories.push(memory.id);
+        }
+        for neighbor in &memories[2..] {
+            manager
+                .create_connection(&Connection::new(
+                    memories[1].clone(),
+                    neighbor.clone(),
+                    ConnectionType::Semantic,
+                    0.5,
+                ))
+                .await
+                .unwrap();
+        }
+
+        let cold = manager.recalculate_importance(&memories[0]).await.unwrap();
+        let hot = manager.recalculate_importance(&memories[1]).await.unwrap();
+        assert!(hot > cold);
+        let stored = manager.get_memory(&memories[1]).await.unwrap();
+        assert_eq!(stored.attributes.importance, hot);
 
-        let tags = metadata
-            .get("tags")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
+        assert_eq!(manager.recalculate_all_importance().await.unwrap(), 5);
+    }
 
-        let created_at = metadata
-            .get("created_at")
-            .and_then(|v| v.as_str())
-            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(Utc::now);
+    #[tokio::test]
+    async fn test_fusion_merges_explanations_across_strategies() {
+        let manager = create_test_memory_manager().await;
+        let memory = Memory::new(
+            "融合测试".to_string(),
+            MemoryType::Knowledge,
+            vec![0.1, 0.2, 0.3],
+            MemoryAttributes::default(),
+        );
+        let result = |relevance_score: f32, explanation: RetrievalExplanation| RetrievalResult {
+            memory: memory.clone(),
+            relevance_score,
+            explanation,
+            detailed_explanation: None,
+        };
+        let semantic = result(
+            0.8,
+            RetrievalExplanation {
+                semantic_score: 0.8,
+                temporal_score: 0.0,
+                importance_score: 0.5,
+                personalization_score: 0.0,
+                connection_paths: Vec::new(),
+                reasoning: "Semantic similarity: 0.800".to_string(),
+            },
+        );
+        let temporal = result(
+            0.6,
+            RetrievalExplanation {
+                semantic_score: 0.0,
+                temporal_score: 0.6,
+                importance_score: 0.5,
+                personalization_score: 0.0,
+                connection_paths: vec![vec![memory.id.clone()]],
+                reasoning: "Temporal relevance: 0.600".to_string(),
+            },
+        );
 
-        let updated_at = metadata
-            .get("updated_at")
-            .and_then(|v| v.as_str())
-            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or_else(Utc::now);
+        let weights = QueryWeights::default();
+        let fused = manager
+            .fuse_retrieval_results(vec![vec![semantic], vec![temporal]], &weights)
+            .await
+            .unwrap();
 
-        let attributes = MemoryAttributes {
-            keywords,
-            tags,
-            context: metadata
-                .get("context")
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string(),
-            importance,
-            emotion: None,
-            source: metadata
-                .get("source")
-                .and_then(|v| v.as_str())
-                .map(|s: &str| s.to_string()),
-            confidence: metadata
-                .get("confidence")
-                .and_then(|v| v.as_f64())
-                .unwrap_or(1.0) as f32,
-            language: metadata
-                .get("language")
-                .and_then(|v| v.as_str())
-                .map(|s: &str| s.to_string()),
-            custom_attributes: HashMap::new(),
+        assert_eq!(fused.len(), 1);
+        let explanation = &fused[0].explanation;
+        assert_eq!(explanation.semantic_score, 0.8);
+        assert_eq!(explanation.temporal_score, 0.6);
+        assert_eq!(explanation.connection_paths, vec![vec![memory.id.clone()]]);
+        assert!(explanation.reasoning.contains("Semantic similarity"));
+        assert!(explanation.reasoning.contains("Temporal relevance"));
+        let expected = 0.8 * weights.semantic_weight + 0.6 * weights.temporal_weight;
+        assert!((fused[0].relevance_score - expected).abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn test_default_threshold_keeps_relevant_results() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
         };
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db, config).await.unwrap();
 
-        let metadata_obj = MemoryMetadata {
-            created_at,
-            updated_at,
-            access_count: metadata
-                .get("access_count")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(0),
-            last_accessed: metadata
-                .get("last_accessed")
-                .and_then(|v| v.as_str())
-                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-                .map(|dt| dt.with_timezone(&Utc))
-                .unwrap_or_else(Utc::now),
-            version: metadata
-                .get("version")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(1) as u32,
+        let request = CreateMemoryRequest {
+            content: "今天天气很好，适合去公园散步".to_string(),
+            context: Context::default(),
+            attributes: None,
+            force_connections: None,
+        };
+        manager.create_memory_from_request(request).await.unwrap();
 
-            is_deleted: metadata
-                .get("is_deleted")
-                .and_then(|v| v.as_bool())
-                .unwrap_or(false),
-            deleted_at: None,
-            custom_metadata: HashMap::new(),
+        let query = |filters: QueryFilters| Query {
+            text: "今天天气很好".to_string(),
+            query_type: QueryType::Semantic,
+            filters,
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
         };
+        let results = manager
+            .retrieve_memories(&query(QueryFilters::default()), &Context::default())
+            .await
+            .unwrap();
+        assert!(!results.is_empty());
 
-        let connections = MemoryConnections {
-            semantic_links: Vec::new(),
-            temporal_links: Vec::new(),
-            causal_links: Vec::new(),
-            thematic_links: Vec::new(),
-            custom_links: HashMap::new(),
+        // 查询级阈值优先于配置默认值
+        let strict = QueryFilters {
+            min_similarity: Some(1.01),
+            ..Default::default()
         };
+        let results = manager
+            .retrieve_memories(&query(strict), &Context::default())
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+    }
 
-        Ok(Memory {
-            id,
-            content,
-            memory_type,
-            embedding,
-            attributes,
-            connections,
-            metadata: metadata_obj,
+    #[tokio::test]
+    async fn test_normalized_embeddings_have_unit_norm() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.vector.normalize = Some(true);
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let manager = MemoryManager::new(db.clone(), config).await.unwrap();
+
+        let requests = [
+            "今天天气很好，适合去公园散步",
+            "明天要交项目报告",
+            "我喜欢喝绿茶",
+        ]
+        .into_iter()
+        .map(|content| CreateMemoryRequest {
+            content: content.to_string(),
+            context: Context::default(),
+            attributes: None,
+            force_connections: None,
         })
-    }
+        .collect();
+        manager.create_memories(requests).await.unwrap();
+
+        let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let stored = db.get_all_vectors().await.unwrap();
+        assert_eq!(stored.len(), 3);
+        let query_embedding = manager.generate_embedding("今天天气很好").await.unwrap();
+        assert!((norm(&query_embedding) - 1.0).abs() < 1e-4);
+        for vector in &stored {
+            assert!((norm(&vector.embedding) - 1.0).abs() < 1e-4);
+            // 单位向量的余弦相似度等于点积
+            let cosine =
+                VectorUtils::cosine_similarity(&query_embedding, &vector.embedding).unwrap();
+            let dot = VectorUtils::dot(&query_embedding, &vector.embedding);
+            assert!((cosine - dot).abs() < 1e-4);
+            assert!((-1.0..=1.0).contains(&cosine));
+        }
 
-    fn properties_to_memory(
-        &self,
-        properties: &HashMap<String, serde_json::Value>,
-    ) -> Result<Memory> {
-        self.metadata_to_memory(properties)
+        let query = Query {
+            text: "今天天气很好".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters {
+                min_similarity: Some(-1.0),
+                ..Default::default()
+            },
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let results = manager
+            .retrieve_memories(&query, &Context::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results
+            .iter()
+            .all(|r| (-1.0..=1.0).contains(&r.explanation.semantic_score)));
     }
 
-    fn query_filters_to_metadata_filters(
-        &self,
-        _filters: &QueryFilters,
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        // TODO: 实现查询过滤器转换
-        Ok(None)
-    }
+    #[tokio::test]
+    async fn test_repeated_connection_accumulates_strength() {
+        let manager = create_test_memory_manager().await;
 
-    fn query_filters_to_graph_filters(
-        &self,
-        filters: &QueryFilters,
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        self.query_filters_to_metadata_filters(filters)
+        let mut memories = Vec::new();
+        for content in ["起点", "终点"] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes::default(),
+            );
+            manager.create_memory(&memory).await.unwrap();
+            memories.push(memory.id);
+        }
+        for strength in [0.3, 0.4] {
+            manager
+                .create_connection(&Connection::new(
+                    memories[0].clone(),
+                    memories[1].clone(),
+                    ConnectionType::Semantic,
+                    strength,
+                ))
+                .await
+                .unwrap();
+        }
+
+        let connections = manager.get_connections(&memories[0]).await.unwrap();
+        assert_eq!(connections.len(), 1);
+        assert!((connections[0].strength - 0.7).abs() < 1e-6);
+        assert_eq!(manager.db.get_graph_edges().await.unwrap().len(), 1);
+        assert_eq!(manager.get_stats().await.total_connections, 1);
     }
 
-    async fn calculate_temporal_relevance(
-        &self,
-        memory: &Memory,
-        time_window: &(DateTime<Utc>, DateTime<Utc>),
-        _query: &str,
-    ) -> Result<f32> {
-        // 简单的时间相关性计算
-        let memory_time = memory.metadata.created_at;
-        if memory_time >= time_window.0 && memory_time <= time_window.1 {
-            let window_duration = time_window.1 - time_window.0;
-            let memory_offset = memory_time - time_window.0;
-            Ok(1.0 - (memory_offset.num_seconds() as f32 / window_duration.num_seconds() as f32))
-        } else {
-            Ok(0.0)
-        }
+    #[tokio::test]
+    async fn test_bulk_creation_encodes_through_embedding_service() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut manager = create_test_memory_manager().await;
+        manager.config.vector.embed_batch_size = 2;
+        // 断言编码器的原始输出
+        manager.config.vector.normalize = Some(false);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let encoder_calls = calls.clone();
+        let service = EmbeddingService::with_encoder(
+            "test-encoder",
+            Box::new(move |inputs: &[String]| {
+                encoder_calls.fetch_add(1, Ordering::SeqCst);
+                inputs
+                    .iter()
+                    .map(|text| vec![text.chars().count() as f32, 1.0, 0.5])
+                    .collect()
+            }),
+        );
+        manager.set_embedding_service(Arc::new(service));
+        // 创建服务时会探测一次维度
+        calls.store(0, Ordering::SeqCst);
+
+        let requests = || {
+            ["一", "二二", "三三三"]
+                .into_iter()
+                .map(|content| CreateMemoryRequest {
+                    content: content.to_string(),
+                    context: Context::default(),
+                    attributes: None,
+                    force_connections: None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let memories = manager.create_memories(requests()).await.unwrap();
+        let lengths: Vec<f32> = memories.iter().map(|m| m.embedding[0]).collect();
+        assert_eq!(lengths, vec![1.0, 2.0, 3.0]);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // 相同内容再次创建时嵌入取自服务缓存
+        manager.create_memories(requests()).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let chunks = manager
+            .create_memories_from_document("一段很短的文档", MemoryAttributes::default())
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].embedding, vec![7.0, 1.0, 0.5]);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+        assert_eq!(manager.embedding_service().cache_stats().entries, 4);
     }
 
-    fn extract_causal_keywords(&self, text: &str) -> Result<Vec<String>> {
-        let causal_words = vec!["因为", "所以", "导致", "引起", "造成", "由于", "结果"];
-        let keywords = causal_words
-            .into_iter()
-            .filter(|word| text.contains(word))
-            .map(|s| s.to_string())
-            .collect();
-        Ok(keywords)
+    #[tokio::test]
+    async fn test_embedding_runs_off_the_async_runtime() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let mut manager = create_test_memory_manager().await;
+        manager.set_embedding_service(Arc::new(EmbeddingService::with_encoder(
+            "slow-encoder",
+            Box::new(|inputs: &[String]| {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                inputs.iter().map(|_| vec![1.0, 0.0, 0.0]).collect()
+            }),
+        )));
+
+        // 单线程运行时：编码若阻塞工作线程，计时任务在编码期间无法推进
+        let ticks = Arc::new(AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = ticks.clone();
+            async move {
+                loop {
+                    tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        });
+        tokio::task::yield_now().await;
+
+        let texts: Vec<String> = (0..4).map(|i| format!("文本{}", i)).collect();
+        let embeddings = manager.generate_embeddings_batched(&texts).await.unwrap();
+        ticker.abort();
+
+        assert_eq!(embeddings.len(), 4);
+        assert!(ticks.load(Ordering::SeqCst) > 0);
     }
 
-    async fn calculate_causal_relevance(
-        &self,
-        memory: &Memory,
-        keywords: &[String],
-    ) -> Result<f32> {
-        let content_lower = memory.content.to_lowercase();
-        let matches = keywords
-            .iter()
-            .filter(|k| content_lower.contains(&k.to_lowercase()))
-            .count();
-        Ok(matches as f32 / keywords.len().max(1) as f32)
+    #[tokio::test]
+    async fn test_embedding_limit_is_per_manager() {
+        let db = create_test_memory_manager().await.db.clone();
+        let manager_with_limit = |limit| {
+            let mut config = Config::default();
+            config.vector.max_concurrent_embeddings = limit;
+            MemoryManager::new(db.clone(), config)
+        };
+
+        let first = manager_with_limit(Some(1)).await.unwrap();
+        let second = manager_with_limit(Some(4)).await.unwrap();
+        let unlimited = manager_with_limit(None).await.unwrap();
+
+        assert_eq!(first.embedding_service().max_concurrency(), Some(1));
+        assert_eq!(second.embedding_service().max_concurrency(), Some(4));
+        assert_eq!(unlimited.embedding_service().max_concurrency(), None);
     }
 
-    fn extract_themes(&self, text: &str) -> Result<Vec<String>> {
-        // 简单的主题提取
-        let words: Vec<String> = text
-            .split_whitespace()
-            .filter(|w| w.len() > 3)
-            .take(10)
-            .map(|s| s.to_string())
-            .collect();
-        Ok(words)
+    #[tokio::test]
+    async fn test_normalize_setting_controls_generated_embeddings() {
+        let mut manager = create_test_memory_manager().await;
+        manager.set_embedding_service(Arc::new(EmbeddingService::with_encoder(
+            "test-encoder",
+            Box::new(|inputs: &[String]| inputs.iter().map(|_| vec![3.0, 4.0, 0.0]).collect()),
+        )));
+
+        // 默认按余弦度量归一化
+        let normalized = manager.generate_embedding("原始向量").await.unwrap();
+        assert_eq!(normalized, vec![0.6, 0.8, 0.0]);
+
+        manager.config.vector.normalize = Some(false);
+        let raw = manager.generate_embedding("原始向量").await.unwrap();
+        assert_eq!(raw, vec![3.0, 4.0, 0.0]);
+
+        // 非余弦度量默认保持模型输出，显式开启时仍归一化
+        manager.config.vector.normalize = None;
+        manager.config.vector.distance_metric = crate::config::DistanceMetric::DotProduct;
+        let raw = manager.generate_embedding("原始向量").await.unwrap();
+        assert_eq!(raw, vec![3.0, 4.0, 0.0]);
+
+        manager.config.vector.normalize = Some(true);
+        let normalized = manager.generate_embedding("原始向量").await.unwrap();
+        assert_eq!(normalized, vec![0.6, 0.8, 0.0]);
     }
 
-    fn create_theme_filters(
-        &self,
-        themes: &[String],
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        let mut filters = HashMap::new();
-        filters.insert(
-            "themes".to_string(),
-            serde_json::Value::Array(
-                themes
+    #[tokio::test]
+    async fn test_embed_in_batches_bounds_concurrency_and_keeps_order() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let texts: Vec<String> = (0..1000).map(|i| i.to_string()).collect();
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let max_batch = AtomicUsize::new(0);
+
+        let embeddings = MemoryManager::embed_in_batches(&texts, 16, 3, |batch| {
+            let (in_flight, max_in_flight, max_batch) = (&in_flight, &max_in_flight, &max_batch);
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                max_batch.fetch_max(batch.len(), Ordering::SeqCst);
+                tokio::task::yield_now().await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(batch
                     .iter()
-                    .map(|t| serde_json::Value::String(t.clone()))
-                    .collect(),
-            ),
-        );
-        Ok(Some(filters))
+                    .map(|t| vec![t.parse::<f32>().unwrap()])
+                    .collect())
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(embeddings.len(), 1000);
+        assert!(embeddings.iter().enumerate().all(|(i, e)| e[0] == i as f32));
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+        assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+        assert_eq!(max_batch.load(Ordering::SeqCst), 16);
     }
 
-    async fn calculate_thematic_relevance(
-        &self,
-        memory: &Memory,
-        themes: &[String],
-    ) -> Result<f32> {
-        let content_lower = memory.content.to_lowercase();
-        let matches = themes
-            .iter()
-            .filter(|theme| content_lower.contains(&theme.to_lowercase()))
-            .count();
-        Ok(matches as f32 / themes.len().max(1) as f32)
+    #[tokio::test]
+    async fn test_themes_rank_by_frequency_and_skip_stopwords() {
+        let mut manager = create_test_memory_manager().await;
+        manager
+            .config
+            .retrieval
+            .theme_stopwords
+            .insert("en".to_string(), vec!["weekend".to_string()]);
+
+        // "rain" 出现得更早，但频率更高的词排在前面
+        let text = "About the weekend: there was rain, then hiking and camping, \
+                    hiking and camping and hiking.";
+        let themes = manager.extract_themes(text, "en").unwrap();
+        assert_eq!(themes, vec!["hiking", "camping", "rain"]);
+
+        // 没有虚词分隔的长中文片段退化为二元组，重复出现的二元组排在前面
+        let themes = manager
+            .extract_themes("机器学习模型，机器学习方法", "zh")
+            .unwrap();
+        assert_eq!(themes[..3], ["机器", "器学", "学习"]);
     }
 
-    async fn fuse_retrieval_results(
-        &self,
-        results_list: Vec<Vec<RetrievalResult>>,
-        weights: &QueryWeights,
-    ) -> Result<Vec<RetrievalResult>> {
-        let mut fused_results = HashMap::new();
+    #[tokio::test]
+    async fn test_causal_keywords_follow_query_language() {
+        let manager = create_test_memory_manager().await;
+
+        let mut query = Query {
+            text: "因为下雨，所以比赛取消了".to_string(),
+            query_type: QueryType::Causal,
+            filters: QueryFilters::default(),
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let language = MemoryManager::query_language(&query);
+        assert_eq!(language, "zh");
+        let keywords = manager
+            .extract_causal_keywords(&query.text, &language)
+            .unwrap();
+        assert_eq!(keywords, vec!["因为".to_string(), "所以".to_string()]);
+        let themes = manager.extract_themes(&query.text, &language).unwrap();
+        assert!(themes.contains(&"比赛取消".to_string()));
+        assert!(!themes.iter().any(|t| t.contains("因为")));
+
+        query.text = "The match was cancelled because it rained, so we went home.".to_string();
+        let language = MemoryManager::query_language(&query);
+        assert_eq!(language, "en");
+        let keywords = manager
+            .extract_causal_keywords(&query.text, &language)
+            .unwrap();
+        assert_eq!(keywords, vec!["because".to_string(), "so".to_string()]);
+        let themes = manager.extract_themes(&query.text, &language).unwrap();
+        assert!(themes.contains(&"cancelled".to_string()));
+        assert!(!themes.contains(&"because".to_string()));
+
+        query.filters.language_filter = Some("zh".to_string());
+        assert_eq!(MemoryManager::query_language(&query), "zh");
+    }
 
-        for (i, results) in results_list.iter().enumerate() {
-            let weight = match i {
-                0 => weights.semantic_weight,
-                1 => weights.temporal_weight,
-                2 => weights.importance_weight,
-                _ => 0.1,
-            };
+    #[tokio::test]
+    async fn test_memory_stats() {
+        let manager = create_test_memory_manager().await;
 
-            for result in results {
-                let entry = fused_results
-                    .entry(result.memory.id.clone())
-                    .or_insert_with(|| result.clone());
-                entry.relevance_score += result.relevance_score * weight;
-            }
-        }
+        let initial_stats = manager.get_stats().await;
+        assert_eq!(initial_stats.total_memories, 0);
 
-        let mut final_results: Vec<RetrievalResult> = fused_results.into_values().collect();
-        final_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
+        // 创建记忆
+        let request = CreateMemoryRequest {
+            content: "测试统计".to_string(),
+            context: Context::default(),
+            attributes: None,
+            force_connections: None,
+        };
+        manager.create_memory_from_request(request).await.unwrap();
 
-        Ok(final_results)
+        let updated_stats = manager.get_stats().await;
+        assert_eq!(updated_stats.total_memories, 1);
+        assert_eq!(updated_stats.creation_count, 1);
     }
 
-    async fn compute_personalized_pagerank(
-        &self,
-        personalization: &PersonalizationVector,
-        query: &str,
-    ) -> Result<HashMap<MemoryId, f32>> {
-        // 简化的PageRank实现
-        let mut scores = HashMap::new();
-
-        // 基于用户偏好计算分数
-        for (preference, weight) in &personalization.preferences {
-            if query.contains(preference) {
-                scores.insert(format!("memory_{}", preference), *weight);
-            }
+    #[tokio::test]
+    async fn test_stats_survive_manager_restart() {
+        let manager = create_test_memory_manager().await;
+        for content in ["重启前一", "重启前二", "重启前三"] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes {
+                    importance: 0.6,
+                    ..Default::default()
+                },
+            );
+            manager.create_memory(&memory).await.unwrap();
         }
+        manager.persist_stats().await.unwrap();
 
-        Ok(scores)
+        // 在同一数据库上重新创建管理器
+        let restarted = MemoryManager::new(manager.db.clone(), manager.config.clone())
+            .await
+            .unwrap();
+        let stats = restarted.get_stats().await;
+        assert_eq!(stats.total_memories, 3);
+        assert_eq!(stats.creation_count, 3);
+        assert!((stats.average_importance - 0.6).abs() < 1e-6);
     }
-}
 
-// 实现各个组件
+    #[tokio::test]
+    async fn test_time_decay_lowers_old_memories() {
+        let manager = create_test_memory_manager().await;
 
-impl LinkAnalyzer {
-    fn new(config: &Config) -> Self {
-        Self {
-            semantic_threshold: config.vector.similarity_threshold,
-            temporal_window: Duration::hours(24),
-            causal_patterns: vec!["因为".to_string(), "所以".to_string(), "导致".to_string()],
-            thematic_keywords: HashMap::new(),
+        let mut ids = Vec::new();
+        for (content, days_ago) in [("旧记忆一", 10), ("旧记忆二", 3), ("新记忆", 0)] {
+            let mut memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes {
+                    importance: 0.8,
+                    ..Default::default()
+                },
+            );
+            let at = Utc::now() - Duration::days(days_ago);
+            memory.metadata.updated_at = at;
+            memory.metadata.last_accessed = at;
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
         }
+
+        let decayed = manager
+            .evolve_memories(EvolutionTrigger::TimeDecay)
+            .await
+            .unwrap();
+        assert_eq!(decayed, 2);
+        let importance = |memory: Memory| memory.attributes.importance;
+        let oldest = importance(manager.get_memory(&ids[0]).await.unwrap());
+        let older = importance(manager.get_memory(&ids[1]).await.unwrap());
+        assert!(oldest < older && older < 0.8);
+        assert_eq!(importance(manager.get_memory(&ids[2]).await.unwrap()), 0.8);
+        assert_eq!(manager.get_stats().await.evolution_count, 2);
+
+        // 刚衰减过的记忆不会重复衰减
+        let again = manager
+            .evolve_memories(EvolutionTrigger::TimeDecay)
+            .await
+            .unwrap();
+        assert_eq!(again, 0);
     }
 
-    async fn discover_connections(
-        &self,
-        memory: &Memory,
-        context: &Context,
-    ) -> Result<Vec<Connection>> {
-        let mut connections = Vec::new();
+    #[tokio::test]
+    async fn test_user_feedback_adjusts_existing_memory_only() {
+        let manager = create_test_memory_manager().await;
+        let memory = Memory::new(
+            "反馈记忆".to_string(),
+            MemoryType::Knowledge,
+            vec![0.1, 0.2, 0.3],
+            MemoryAttributes {
+                importance: 0.5,
+                ..Default::default()
+            },
+        );
+        manager.create_memory(&memory).await.unwrap();
+
+        let trigger = EvolutionTrigger::UserFeedback(memory.id.clone(), 1.0);
+        assert_eq!(manager.evolve_memories(trigger).await.unwrap(), 1);
+        let updated = manager.get_memory(&memory.id).await.unwrap();
+        assert!(updated.attributes.importance > 0.5);
+        assert_eq!(
+            updated.metadata.custom_metadata["feedback_score"],
+            serde_json::json!(1.0)
+        );
 
-        // 基于最近记忆发现连接
-        for recent_memory_id in &context.recent_memories {
-            if recent_memory_id != &memory.id {
-                let connection = Connection::new(
-                    memory.id.clone(),
-                    recent_memory_id.clone(),
-                    ConnectionType::Temporal,
-                    0.7,
-                );
-                connections.push(connection);
-            }
-        }
+        let missing = EvolutionTrigger::UserFeedback("missing".to_string(), 1.0);
+        assert_eq!(manager.evolve_memories(missing).await.unwrap(), 0);
 
-        Ok(connections)
+        let unsupported = EvolutionTrigger::ConnectionStrengthChanged("edge".to_string());
+        assert!(manager.evolve_memories(unsupported).await.is_err());
     }
-}
 
-impl ImportanceCalculator {
-    fn new(_config: &Config) -> Self {
-        Self {
-            access_weight: 0.3,
-            recency_weight: 0.2,
-            connection_weight: 0.2,
-            feedback_weight: 0.2,
-            content_weight: 0.1,
-        }
-    }
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let source = create_test_memory_manager().await;
+
+        let first = Memory::new(
+            "导出测试一".to_string(),
+            MemoryType::Knowledge,
+            vec![0.1, 0.2, 0.3],
+            MemoryAttributes::default(),
+        );
+        let second = Memory::new(
+            "导出测试二".to_string(),
+            MemoryType::Knowledge,
+            vec![0.3, 0.2, 0.1],
+            MemoryAttributes::default(),
+        );
+        source.create_memory(&first).await.unwrap();
+        source.create_memory(&second).await.unwrap();
+        source
+            .create_connection(&Connection::new(
+                first.id.clone(),
+                second.id.clone(),
+                ConnectionType::Semantic,
+                0.6,
+            ))
+            .await
+            .unwrap();
 
-    async fn calculate_initial_importance(
-        &self,
-        memory: &Memory,
-        context: &Context,
-    ) -> Result<f32> {
-        let mut importance: f32 = 0.5; // 基础重要性
+        let json = source.export_memories().await.unwrap().to_json().unwrap();
+        let export = MemoryExport::from_json(&json).unwrap();
+        assert_eq!(export.version, MEMORY_EXPORT_VERSION);
+        assert_eq!(export.vectors.len(), 2);
+        assert_eq!(export.edges.len(), 1);
 
-        // 基于内容长度调整
-        let content_factor = (memory.content.len() as f32 / 1000.0).min(1.0);
-        importance += content_factor * self.content_weight;
+        let target = create_test_memory_manager().await;
+        target
+            .import_memories(export, ImportMode::Replace)
+            .await
+            .unwrap();
 
-        // 基于上下文优先级调整
-        match context.priority {
-            Priority::Critical => importance += 0.3,
-            Priority::High => importance += 0.2,
-            Priority::Normal => {}
-            Priority::Low => importance -= 0.1,
-        }
+        assert_eq!(target.get_stats().await, source.get_stats().await);
 
-        Ok(importance.clamp(0.0, 1.0))
+        let (source_export, target_export) = (
+            source.export_memories().await.unwrap(),
+            target.export_memories().await.unwrap(),
+        );
+        let sorted = |mut vectors: Vec<Vector>| {
+            vectors.sort_by(|a, b| a.id.cmp(&b.id));
+            vectors
+        };
+        assert_eq!(sorted(target_export.vectors), sorted(source_export.vectors));
+        assert_eq!(target_export.edges, source_export.edges);
     }
-}
 
-impl EvolutionEngine {
-    fn new(config: &Config) -> Self {
-        Self {
-            decay_factor: config.learning.importance_decay_factor,
-            reinforcement_factor: 1.05,
-            pruning_threshold: 0.1,
-            evolution_interval: Duration::hours(config.learning.learning_interval_hours as i64),
+    #[tokio::test]
+    async fn test_import_merge_renames_conflicting_ids() {
+        let manager = create_test_memory_manager().await;
+        let memory = Memory::new(
+            "冲突测试".to_string(),
+            MemoryType::Knowledge,
+            vec![0.5, 0.5],
+            MemoryAttributes::default(),
+        );
+        manager.create_memory(&memory).await.unwrap();
+        let export = manager.export_memories().await.unwrap();
+
+        manager
+            .import_memories(export.clone(), ImportMode::Merge(IdConflict::Skip))
+            .await
+            .unwrap();
+        assert_eq!(manager.db.get_all_vectors().await.unwrap().len(), 1);
+
+        manager
+            .import_memories(export, ImportMode::Merge(IdConflict::Rename))
+            .await
+            .unwrap();
+        let vectors = manager.db.get_all_vectors().await.unwrap();
+        assert_eq!(vectors.len(), 2);
+        for vector in &vectors {
+            assert_eq!(vector.metadata["id"], serde_json::json!(vector.id));
         }
+        assert_eq!(manager.get_stats().await.total_memories, 2);
     }
 
-    async fn evolve(&self, trigger: &EvolutionTrigger, _db: &VectorGraphDB) -> Result<u64> {
-        match trigger {
-            EvolutionTrigger::TimeDecay => {
-                // 实现时间衰减逻辑
-                Ok(0)
-            }
-            EvolutionTrigger::UserFeedback(_memory_id, _score) => {
-                // 实现用户反馈处理逻辑
-                Ok(1)
-            }
-            _ => Ok(0),
+    #[tokio::test]
+    async fn test_merge_memories_repoints_edges() {
+        let manager = create_test_memory_manager().await;
+
+        let mut memories = Vec::new();
+        for (content, keyword) in [
+            ("主记忆", "a"),
+            ("重复记忆", "b"),
+            ("邻居一", "c"),
+            ("邻居二", "d"),
+        ] {
+            let attributes = MemoryAttributes {
+                keywords: vec![keyword.to_string()],
+                ..Default::default()
+            };
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                attributes,
+            );
+            manager.create_memory(&memory).await.unwrap();
+            memories.push(memory);
         }
-    }
-}
+        let (primary, duplicate, first, second) = (
+            &memories[0].id,
+            &memories[1].id,
+            &memories[2].id,
+            &memories[3].id,
+        );
 
-impl RetrievalEngine {
-    async fn new(config: &Config) -> Result<Self> {
-        Ok(Self {
-            pagerank_config: PageRankConfig {
-                damping_factor: config.graph.pagerank.damping_factor,
-                max_iterations: config.graph.pagerank.max_iterations,
-                convergence_threshold: config.graph.pagerank.convergence_threshold,
-                personalization_weight: config.graph.pagerank.personalization_weight,
-            },
-            fusion_weights: QueryWeights::default(),
-            personalization_cache: Arc::new(RwLock::new(PersonalizationVector::default())),
-        })
-    }
+        for (from, to, strength) in [
+            (primary, first, 0.5),
+            (duplicate, second, 0.4),
+            (duplicate, first, 0.3),
+        ] {
+            manager
+                .create_connection(&Connection::new(
+                    from.clone(),
+                    to.clone(),
+                    ConnectionType::Semantic,
+                    strength,
+                ))
+                .await
+                .unwrap();
+        }
 
-    async fn get_personalization_vector(&self) -> Result<PersonalizationVector> {
-        let cache = self.personalization_cache.read().await;
-        Ok(cache.clone())
+        let merged = manager
+            .merge_memories(primary, std::slice::from_ref(duplicate))
+            .await
+            .unwrap();
+        assert!(merged.attributes.keywords.contains(&"a".to_string()));
+        assert!(merged.attributes.keywords.contains(&"b".to_string()));
+
+        let edges = manager.db.get_graph_edges().await.unwrap();
+        assert_eq!(edges.len(), 2);
+        assert!(edges.iter().all(|e| e.from_node == *primary));
+        assert!(edges.iter().all(|e| e.to_node != *duplicate));
+        let to_first = edges.iter().find(|e| e.to_node == *first).unwrap();
+        assert!((to_first.weight - 0.8).abs() < 1e-6);
+        assert!(edges.iter().any(|e| e.to_node == *second));
+
+        let duplicate_vector = manager.db.get_vector(duplicate).await.unwrap();
+        assert_eq!(
+            duplicate_vector.metadata["is_deleted"],
+            serde_json::json!(true)
+        );
+        let clusters = manager.find_duplicate_candidates(0.99).await.unwrap();
+        assert!(clusters.iter().all(|c| !c.contains(duplicate)));
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].len(), 3);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::database::VectorGraphDB;
-    use tempfile::NamedTempFile;
+    #[tokio::test]
+    async fn test_create_memory_is_idempotent() {
+        let manager = create_test_memory_manager().await;
 
-    async fn create_test_memory_manager() -> MemoryManager {
-        let temp_file = NamedTempFile::new().unwrap();
-        let db_url = format!("sqlite://{}", temp_file.path().display());
+        let mut memory = Memory::new(
+            "初始内容".to_string(),
+            MemoryType::Knowledge,
+            vec![0.1, 0.2, 0.3],
+            MemoryAttributes::default(),
+        );
+        manager.create_memory(&memory).await.unwrap();
 
-        let config = Config {
-            database: crate::config::DatabaseConfig {
-                url: db_url,
-                ..Default::default()
-            },
-            vector: crate::config::VectorConfig {
-                similarity_threshold: 0.0, // 设置为0.0以便测试
-                ..Default::default()
-            },
-            ..Default::default()
-        };
+        memory.content = "更新后的内容".to_string();
+        memory.embedding = vec![0.3, 0.2, 0.1];
+        manager.create_memory(&memory).await.unwrap();
 
-        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
-        MemoryManager::new(db, config).await.unwrap()
+        manager.db.clear_cache().await;
+        let stored = manager.get_memory(&memory.id).await.unwrap();
+        assert_eq!(stored.content, "更新后的内容");
+        assert_eq!(stored.embedding, vec![0.3, 0.2, 0.1]);
+        assert_eq!(manager.get_stats().await.total_memories, 1);
+
+        // 严格插入仍然在ID冲突时报错
+        let vector = manager.db.get_vector(&memory.id).await.unwrap();
+        assert!(manager.db.insert_vector(&vector).await.is_err());
     }
 
     #[tokio::test]
-    async fn test_create_memory() {
+    async fn test_soft_delete_is_idempotent_and_matches_db_count() {
         let manager = create_test_memory_manager().await;
+        let mut ids = Vec::new();
+        for content in ["第一条记忆", "第二条记忆"] {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes::default(),
+            );
+            manager.create_memory(&memory).await.unwrap();
+            ids.push(memory.id);
+        }
 
-        let request = CreateMemoryRequest {
-            content: "这是一个测试记忆".to_string(),
-            context: Context::default(),
-            attributes: None,
-            force_connections: None,
-        };
+        manager.delete_memory(&ids[0], true).await.unwrap();
+        manager.delete_memory(&ids[0], true).await.unwrap();
 
-        let memory = manager.create_memory_from_request(request).await.unwrap();
-        assert_eq!(memory.content, "这是一个测试记忆");
-        assert!(!memory.id.is_empty());
-        assert!(!memory.embedding.is_empty());
+        assert_eq!(manager.get_stats().await.total_memories, 1);
+        let (db_count, _, _, _) = manager.db.get_stats().await.unwrap();
+        assert_eq!(db_count, 1);
     }
 
     #[tokio::test]
-    async fn test_semantic_retrieval() {
+    async fn test_embedding_is_not_duplicated_into_metadata() {
         let manager = create_test_memory_manager().await;
+        let memory = Memory::new(
+            "嵌入向量只存在向量列中".to_string(),
+            MemoryType::Knowledge,
+            vec![0.1, -0.5, 0.25],
+            MemoryAttributes::default(),
+        );
+        manager.create_memory(&memory).await.unwrap();
+        manager.db.clear_cache().await;
 
-        // 创建测试记忆
-        let request = CreateMemoryRequest {
-            content: "人工智能的发展历程".to_string(),
-            context: Context::default(),
-            attributes: None,
-            force_connections: None,
-        };
-        manager.create_memory_from_request(request).await.unwrap();
+        let vector = manager.db.get_vector(&memory.id).await.unwrap();
+        assert!(!vector.metadata.contains_key("embedding"));
+        assert_eq!(vector.embedding, memory.embedding);
 
-        // 执行检索
-        let query = Query {
-            text: "AI发展".to_string(),
-            query_type: QueryType::Semantic,
-            filters: QueryFilters::default(),
-            limit: Some(10),
-            offset: None,
-            sort_by: None,
-            weights: QueryWeights::default(),
-        };
+        let stored = manager.get_memory(&memory.id).await.unwrap();
+        assert_eq!(stored.embedding, memory.embedding);
 
-        let results = manager
-            .retrieve_memories(&query, &Context::default())
+        // 图检索路径从节点属性重构记忆时同样读取向量行
+        let node = manager.db.get_node(&memory.id).await.unwrap().unwrap();
+        let from_node = manager
+            .properties_to_memory(&node.properties)
             .await
             .unwrap();
-        assert!(!results.is_empty());
+        assert_eq!(from_node.embedding, memory.embedding);
+    }
+
+    #[cfg(feature = "metrics")]
+    fn metric_total(rendered: &str, name: &str) -> f64 {
+        rendered
+            .lines()
+            .filter(|line| {
+                line.strip_prefix(name)
+                    .is_some_and(|rest| rest.starts_with(' ') || rest.starts_with('{'))
+            })
+            .filter_map(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+            .sum()
     }
 
+    #[cfg(feature = "metrics")]
     #[tokio::test]
-    async fn test_memory_stats() {
+    async fn test_retrievals_advance_metrics() {
+        let handle = crate::telemetry::metrics_handle();
         let manager = create_test_memory_manager().await;
 
-        let initial_stats = manager.get_stats().await;
-        assert_eq!(initial_stats.total_memories, 0);
-
-        // 创建记忆
         let request = CreateMemoryRequest {
-            content: "测试统计".to_string(),
+            content: "指标测试记忆".to_string(),
             context: Context::default(),
             attributes: None,
             force_connections: None,
         };
         manager.create_memory_from_request(request).await.unwrap();
 
-        let updated_stats = manager.get_stats().await;
-        assert_eq!(updated_stats.total_memories, 1);
-        assert_eq!(updated_stats.creation_count, 1);
+        let before = handle.render();
+        let cache_before = manager.embedding_service().cache_stats();
+        let query = Query {
+            text: "指标".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(5),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        for _ in 0..2 {
+            manager
+                .retrieve_memories(&query, &Context::default())
+                .await
+                .unwrap();
+        }
+        let after = handle.render();
+        let cache_after = manager.embedding_service().cache_stats();
+
+        assert!(
+            metric_total(&after, "retrievals_total")
+                >= metric_total(&before, "retrievals_total") + 2.0
+        );
+        // 查询文本只在第一次编码，第二次命中嵌入缓存
+        assert_eq!(cache_after.misses, cache_before.misses + 1);
+        assert_eq!(cache_after.hits, cache_before.hits + 1);
+        assert!(after.contains("retrieval_latency_seconds"));
+        assert!(after.contains("memories_total"));
     }
 }
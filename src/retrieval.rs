@@ -4,21 +4,31 @@
 
 use crate::config::Config;
 use crate::core_types::{
-    InteractionType, Memory, MemoryAttributes, MemoryConnections, MemoryId, MemoryMetadata,
-    MemoryType, Priority, Query,
+    InteractionRecord, InteractionType, Memory, MemoryAttributes, MemoryConnections, MemoryId,
+    MemoryMetadata, MemoryType, Priority, Query, QueryFilters, SortBy, SortOrder,
 };
-use crate::database::{GraphQueryRequest, VectorGraphDB, VectorQueryRequest};
-use crate::error::{MemoryError, Result};
-use crate::memory::{
-    InteractionRecord, PersonalizationVector, RetrievalExplanation, RetrievalResult,
+use crate::database::{
+    GraphQueryRequest, TagFilter, TraversalMode, VectorGraphDB, VectorQueryRequest,
 };
+use crate::db::embedding::EmbeddingService;
+use crate::error::{MemoryError, Result};
+use crate::memory::{MemoryManager, PersonalizationVector, RetrievalExplanation, RetrievalResult};
+use crate::telemetry;
+use crate::utils::{HashUtils, TimeUtils, VectorUtils};
 use chrono::{DateTime, Duration, Utc};
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::Future;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
+/// 持久化用户档案使用的ID（当前只维护一个本地用户档案）
+const USER_PROFILE_ID: &str = "default";
+
+/// 偏好权重衰减到该值以下时移除
+const MIN_PREFERENCE_WEIGHT: f32 = 1e-3;
+
 /// HippoRAG检索引擎
 ///
 /// 实现神经生物学启发的检索算法，包括个性化PageRank和多模态融合。
@@ -30,6 +40,8 @@ pub struct HippoRAGRetriever {
     fusion_engine: FusionEngine,
     personalization_manager: PersonalizationManager,
     cache: Arc<RwLock<RetrievalCache>>,
+    /// 生成查询嵌入的服务，与记忆写入使用同一模型
+    embedding_service: Arc<EmbeddingService>,
 }
 
 /// PageRank引擎
@@ -134,7 +146,6 @@ pub struct InteractionTracker {
 
 /// 偏好学习器
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub struct RetrievalPreferenceLearner {
     /// 学习率
     learning_rate: f32,
@@ -167,7 +178,7 @@ pub struct CachedResult {
 }
 
 /// 检索策略
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum RetrievalStrategy {
     /// 纯语义检索
     Semantic,
@@ -191,7 +202,7 @@ pub enum RetrievalStrategy {
 }
 
 /// 融合方法
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum FusionMethod {
     /// 线性加权融合
     LinearWeighted,
@@ -269,7 +280,17 @@ impl HippoRAGRetriever {
     pub async fn new(db: Arc<VectorGraphDB>, config: Config) -> Result<Self> {
         let pagerank_engine = PageRankEngine::new(&config);
         let fusion_engine = FusionEngine::new(&config);
-        let personalization_manager = PersonalizationManager::new(&config)?;
+        let mut personalization_manager = PersonalizationManager::new(&config)?;
+
+        // 从数据库恢复用户档案和最近的交互历史
+        if let Some(profile) = db.load_user_profile(USER_PROFILE_ID).await? {
+            personalization_manager.restore_profile(serde_json::from_value(profile)?);
+        }
+        let history = db
+            .get_recent_interactions(config.learning.max_interaction_history)
+            .await?;
+        personalization_manager.restore_history(history);
+        let embedding_service = Arc::new(MemoryManager::default_embedding_service(&config));
 
         Ok(Self {
             db,
@@ -278,10 +299,21 @@ impl HippoRAGRetriever {
             fusion_engine,
             personalization_manager,
             cache: Arc::new(RwLock::new(RetrievalCache::new(1000))),
+            embedding_service,
         })
     }
 
+    /// 替换生成查询嵌入的服务，与记忆管理器共用时并发上限合并计算
+    pub fn set_embedding_service(&mut self, service: Arc<EmbeddingService>) {
+        self.embedding_service = service;
+    }
+
     /// 执行HippoRAG检索
+    #[tracing::instrument(
+        name = "retrieve_memories",
+        skip_all,
+        fields(query.text = %query.text, query.query_type = ?query.query_type, strategy = ?strategy)
+    )]
     pub async fn retrieve(
         &mut self,
         query: &Query,
@@ -291,16 +323,47 @@ impl HippoRAGRetriever {
         info!("Starting HippoRAG retrieval with strategy: {:?}", strategy);
 
         // 检查缓存
-        if let Some(cached) = self.check_cache(query, context).await? {
+        if let Some(cached) = self.check_cache(query, context, strategy).await? {
             debug!("Returning cached results");
             return Ok(cached);
         }
 
-        let results = match strategy {
-            RetrievalStrategy::Semantic => self.semantic_retrieval(query, context).await?,
-            RetrievalStrategy::Structural => self.structural_retrieval(query, context).await?,
-            RetrievalStrategy::Temporal => self.temporal_retrieval(query, context).await?,
-            RetrievalStrategy::Personalized => self.personalized_retrieval(query, context).await?,
+        let results = self.retrieve_uncached(query, context, strategy).await?;
+
+        // 缓存结果
+        self.cache_results(query, context, strategy, &results)
+            .await?;
+
+        // 记录交互
+        self.record_interaction(query, &results).await?;
+
+        info!("Retrieved {} results", results.len());
+        Ok(results)
+    }
+
+    /// 按策略计算并约束检索结果，不读写缓存、不记录交互
+    ///
+    /// 供 `retrieve` 与只解释不检索的场景（见 `MemoryManager::explain_retrieval`）共用。
+    pub async fn retrieve_uncached(
+        &self,
+        query: &Query,
+        context: &RetrievalContext,
+        strategy: &RetrievalStrategy,
+    ) -> Result<Vec<RetrievalResult>> {
+        let candidates = match strategy {
+            RetrievalStrategy::Semantic => {
+                self.semantic_retrieval(&self.db, query, context).await?
+            }
+            RetrievalStrategy::Structural => {
+                self.structural_retrieval(&self.db, query, context).await?
+            }
+            RetrievalStrategy::Temporal => {
+                self.temporal_retrieval(&self.db, query, context).await?
+            }
+            RetrievalStrategy::Personalized => {
+                self.personalized_retrieval(&self.db, query, context)
+                    .await?
+            }
             RetrievalStrategy::Hybrid {
                 strategies,
                 weights,
@@ -324,19 +387,14 @@ impl HippoRAGRetriever {
             }
         };
 
-        // 缓存结果
-        self.cache_results(query, context, &results).await?;
-
-        // 记录交互
-        self.record_interaction(query, &results).await?;
-
-        info!("Retrieved {} results", results.len());
-        Ok(results)
+        Ok(self.apply_constraints(query, context, candidates))
     }
 
     /// 语义检索
+    #[tracing::instrument(skip_all)]
     async fn semantic_retrieval(
         &self,
+        db: &VectorGraphDB,
         query: &Query,
         context: &RetrievalContext,
     ) -> Result<Vec<RetrievalResult>> {
@@ -349,12 +407,22 @@ impl HippoRAGRetriever {
         let vector_request = VectorQueryRequest {
             query_vector: query_embedding,
             limit: Some(context.constraints.max_results.unwrap_or(50)),
-            threshold: context.constraints.min_relevance,
-            filters: self.build_metadata_filters(&context.constraints)?,
+            threshold: Some(resolve_similarity_threshold(
+                &query.filters,
+                Some(&context.constraints),
+                self.config.vector.similarity_threshold,
+            )),
+            filters: None,
+            // 标签约束在数量上限之前由 SQL 筛选，被排除的记忆不会占用名额
+            tags: TagFilter {
+                all_of: context.constraints.required_tags.clone(),
+                none_of: context.constraints.excluded_tags.clone(),
+                ..Default::default()
+            },
         };
 
         // 执行向量检索
-        let vector_results = self.db.query_vectors(&vector_request).await?;
+        let vector_results = db.query_vectors(&vector_request).await?;
 
         // 转换为检索结果
         let mut results = Vec::new();
@@ -364,7 +432,7 @@ impl HippoRAGRetriever {
             // 保存需要的值，避免移动后访问
             let importance_score = memory.attributes.importance;
 
-            let _explanation = DetailedExplanation {
+            let detailed_explanation = DetailedExplanation {
                 strategy_used: RetrievalStrategy::Semantic,
                 score_breakdown: ScoreBreakdown {
                     semantic_score: vector_result.similarity,
@@ -396,15 +464,19 @@ impl HippoRAGRetriever {
                     connection_paths: Vec::new(),
                     reasoning: format!("Semantic similarity: {:.3}", vector_result.similarity),
                 },
+                detailed_explanation: Some(detailed_explanation),
             });
         }
 
+        tracing::debug!(candidates = results.len(), "semantic candidates");
         Ok(results)
     }
 
     /// 结构化检索（基于图遍历）
+    #[tracing::instrument(skip_all)]
     async fn structural_retrieval(
         &self,
+        db: &VectorGraphDB,
         query: &Query,
         context: &RetrievalContext,
     ) -> Result<Vec<RetrievalResult>> {
@@ -417,7 +489,7 @@ impl HippoRAGRetriever {
                 .await?
         } else {
             // 使用语义检索找到初始节点
-            let semantic_results = self.semantic_retrieval(query, context).await?;
+            let semantic_results = self.semantic_retrieval(db, query, context).await?;
             semantic_results
                 .into_iter()
                 .take(5)
@@ -435,11 +507,12 @@ impl HippoRAGRetriever {
             edge_types: None, // 考虑所有边类型
             max_depth: Some(self.config.graph.traversal.max_depth),
             limit: Some(context.constraints.max_results.unwrap_or(50)),
-            filters: self.build_graph_filters(&context.constraints)?,
+            filters: None,
+            traversal: TraversalMode::BreadthFirst,
         };
 
         // 执行图遍历
-        let graph_results = self.db.query_graph(&graph_request).await?;
+        let graph_results = db.query_graph(&graph_request).await?;
 
         // 计算结构化分数
         let mut results = Vec::new();
@@ -452,7 +525,7 @@ impl HippoRAGRetriever {
             // 保存需要的值，避免移动后访问
             let importance_score = memory.attributes.importance;
 
-            let _explanation = DetailedExplanation {
+            let detailed_explanation = DetailedExplanation {
                 strategy_used: RetrievalStrategy::Structural,
                 score_breakdown: ScoreBreakdown {
                     semantic_score: 0.0,
@@ -484,35 +557,46 @@ impl HippoRAGRetriever {
                     connection_paths: graph_results.paths.clone(),
                     reasoning: format!("Structural relevance: {:.3}", structural_score),
                 },
+                detailed_explanation: Some(detailed_explanation),
             });
         }
 
         // 按分数排序
         results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
 
+        tracing::debug!(candidates = results.len(), "structural candidates");
         Ok(results)
     }
 
     /// 时间感知检索
+    #[tracing::instrument(skip_all)]
     async fn temporal_retrieval(
         &self,
+        db: &VectorGraphDB,
         query: &Query,
         context: &RetrievalContext,
     ) -> Result<Vec<RetrievalResult>> {
         debug!("Performing temporal retrieval");
 
         // 首先执行语义检索获取候选
-        let mut semantic_results = self.semantic_retrieval(query, context).await?;
+        let mut semantic_results = self.semantic_retrieval(db, query, context).await?;
 
         // 获取时间窗口
         let time_window = context
             .time_window
             .unwrap_or((Utc::now() - Duration::days(30), Utc::now()));
 
+        if self.config.retrieval.temporal_hard_window {
+            semantic_results.retain(|result| {
+                let created_at = result.memory.metadata.created_at;
+                created_at >= time_window.0 && created_at <= time_window.1
+            });
+        }
+
         // 重新计算时间相关性分数
         for result in &mut semantic_results {
             let temporal_score = self
-                .calculate_temporal_relevance(&result.memory, &time_window, &query.text)
+                .calculate_temporal_relevance(&result.memory, &time_window)
                 .await?;
 
             // 融合语义和时间分数
@@ -525,17 +609,32 @@ impl HippoRAGRetriever {
                 "Temporal-semantic fusion: semantic={:.3}, temporal={:.3}",
                 result.explanation.semantic_score, temporal_score
             );
+            if let Some(details) = &mut result.detailed_explanation {
+                details.strategy_used = RetrievalStrategy::Temporal;
+                details.score_breakdown.temporal_score = temporal_score;
+                details.score_breakdown.final_score = fused_score;
+                details.reasoning_path.push(ReasoningStep {
+                    step_type: "temporal_decay".to_string(),
+                    description: format!("Recency score: {:.3}", temporal_score),
+                    score_impact: temporal_score,
+                    confidence: 0.8,
+                });
+                details.confidence = fused_score;
+            }
         }
 
         // 重新排序
         semantic_results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
 
+        tracing::debug!(candidates = semantic_results.len(), "temporal candidates");
         Ok(semantic_results)
     }
 
     /// 个性化检索
+    #[tracing::instrument(skip_all)]
     async fn personalized_retrieval(
         &self,
+        db: &VectorGraphDB,
         query: &Query,
         context: &RetrievalContext,
     ) -> Result<Vec<RetrievalResult>> {
@@ -547,11 +646,11 @@ impl HippoRAGRetriever {
         // 执行个性化PageRank
         let pagerank_scores = self
             .pagerank_engine
-            .compute_personalized_pagerank(&user_profile, &query.text, &self.db)
+            .compute_personalized_pagerank(&user_profile, &query.text, db)
             .await?;
 
         // 获取语义候选
-        let semantic_results = self.semantic_retrieval(query, context).await?;
+        let semantic_results = self.semantic_retrieval(db, query, context).await?;
 
         // 融合个性化分数
         let mut personalized_results = Vec::new();
@@ -572,7 +671,7 @@ impl HippoRAGRetriever {
             let importance_score = semantic_result.memory.attributes.importance;
             let semantic_score = semantic_result.explanation.semantic_score;
 
-            let _explanation = DetailedExplanation {
+            let detailed_explanation = DetailedExplanation {
                 strategy_used: RetrievalStrategy::Personalized,
                 score_breakdown: ScoreBreakdown {
                     semantic_score,
@@ -618,6 +717,7 @@ impl HippoRAGRetriever {
                         semantic_score, pagerank_score, personalization_score
                     ),
                 },
+                detailed_explanation: Some(detailed_explanation),
             });
         }
 
@@ -625,10 +725,15 @@ impl HippoRAGRetriever {
         personalized_results
             .sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
 
+        tracing::debug!(
+            candidates = personalized_results.len(),
+            "personalized candidates"
+        );
         Ok(personalized_results)
     }
 
     /// 混合检索
+    #[tracing::instrument(skip_all)]
     pub async fn hybrid_retrieval(
         &self,
         query: &Query,
@@ -649,30 +754,49 @@ impl HippoRAGRetriever {
 
         // 执行各种检索策略
         let mut all_results = Vec::new();
+        let mut breakdowns = HashMap::new();
         for (strategy, weight) in strategies.iter().zip(weights.iter()) {
             let strategy_results = match strategy {
-                RetrievalStrategy::Semantic => self.semantic_retrieval(query, context).await?,
-                RetrievalStrategy::Structural => self.structural_retrieval(query, context).await?,
-                RetrievalStrategy::Temporal => self.temporal_retrieval(query, context).await?,
+                RetrievalStrategy::Semantic => {
+                    self.semantic_retrieval(&self.db, query, context).await?
+                }
+                RetrievalStrategy::Structural => {
+                    self.structural_retrieval(&self.db, query, context).await?
+                }
+                RetrievalStrategy::Temporal => {
+                    self.temporal_retrieval(&self.db, query, context).await?
+                }
                 RetrievalStrategy::Personalized => {
-                    self.personalized_retrieval(query, context).await?
+                    self.personalized_retrieval(&self.db, query, context)
+                        .await?
                 }
                 _ => continue, // 跳过嵌套的混合策略
             };
 
+            record_component_scores(&mut breakdowns, strategy, &strategy_results);
             all_results.push((strategy_results, *weight));
         }
 
         // 融合结果
-        let fused_results = self
+        let mut fused_results = self
             .fusion_engine
             .fuse_results(all_results, FusionMethod::LinearWeighted)
             .await?;
+        attach_fused_explanations(
+            &mut fused_results,
+            &RetrievalStrategy::Hybrid {
+                strategies: strategies.to_vec(),
+                weights: weights.to_vec(),
+            },
+            breakdowns,
+        );
 
+        tracing::debug!(candidates = fused_results.len(), "hybrid fusion completed");
         Ok(fused_results)
     }
 
     /// HippoRAG完整检索
+    #[tracing::instrument(skip_all)]
     pub async fn hippocampus_retrieval(
         &self,
         query: &Query,
@@ -683,10 +807,56 @@ impl HippoRAGRetriever {
     ) -> Result<Vec<RetrievalResult>> {
         info!("Performing full HippoRAG retrieval");
 
-        // 第一阶段：多模态检索
-        let semantic_results = self.semantic_retrieval(query, context).await?;
-        let structural_results = self.structural_retrieval(query, context).await?;
-        let temporal_results = self.temporal_retrieval(query, context).await?;
+        // 多个阶段分别查询向量、图与边，启用快照时都读取同一份数据，不受并发写入影响
+        let snapshot = if self.config.retrieval.snapshot_reads {
+            Some(self.db.read_snapshot().await?)
+        } else {
+            None
+        };
+        let db: &VectorGraphDB = snapshot.as_deref().unwrap_or(&self.db);
+
+        // 第一、二阶段：多模态检索与个性化（如果启用），各组件相互独立，并发执行
+        let (semantic_results, structural_results, temporal_results, personalized_results) =
+            gather_component_results(
+                self.semantic_retrieval(db, query, context),
+                self.structural_retrieval(db, query, context),
+                self.temporal_retrieval(db, query, context),
+                use_personalization.then(|| self.personalized_retrieval(db, query, context)),
+            )
+            .await?;
+
+        tracing::debug!(
+            semantic = semantic_results.len(),
+            structural = structural_results.len(),
+            temporal = temporal_results.len(),
+            personalized = personalized_results.as_ref().map(Vec::len),
+            "component retrievals completed"
+        );
+
+        // 融合前记录各组件分数，用于生成分数分解
+        let mut breakdowns = HashMap::new();
+        record_component_scores(
+            &mut breakdowns,
+            &RetrievalStrategy::Semantic,
+            &semantic_results,
+        );
+        record_component_scores(
+            &mut breakdowns,
+            &RetrievalStrategy::Structural,
+            &structural_results,
+        );
+        record_component_scores(
+            &mut breakdowns,
+            &RetrievalStrategy::Temporal,
+            &temporal_results,
+        );
+        if let Some(personalized_results) = &personalized_results {
+            record_component_scores(
+                &mut breakdowns,
+                &RetrievalStrategy::Personalized,
+                personalized_results,
+            );
+        }
 
         let mut all_results = vec![
             (semantic_results, self.fusion_engine.semantic_weight),
@@ -694,9 +864,7 @@ impl HippoRAGRetriever {
             (temporal_results, self.fusion_engine.temporal_weight),
         ];
 
-        // 第二阶段：个性化（如果启用）
-        if use_personalization {
-            let personalized_results = self.personalized_retrieval(query, context).await?;
+        if let Some(personalized_results) = personalized_results {
             all_results.push((
                 personalized_results,
                 self.fusion_engine.personalization_weight,
@@ -708,20 +876,42 @@ impl HippoRAGRetriever {
             .fusion_engine
             .fuse_results(all_results, fusion_method.clone())
             .await?;
+        attach_fused_explanations(
+            &mut fused_results,
+            &RetrievalStrategy::HippoRAG {
+                use_pagerank,
+                use_personalization,
+                fusion_method: fusion_method.clone(),
+            },
+            breakdowns,
+        );
 
         // 第四阶段：PageRank重排序（如果启用）
         if use_pagerank {
             fused_results = self
-                .apply_pagerank_reranking(fused_results, query, context)
+                .apply_pagerank_reranking(db, fused_results, query, context)
                 .await?;
         }
 
         // 第五阶段：后处理和解释生成
         for result in &mut fused_results {
+            tracing::trace!(
+                memory_id = %result.memory.id,
+                semantic = result.explanation.semantic_score,
+                temporal = result.explanation.temporal_score,
+                importance = result.explanation.importance_score,
+                personalization = result.explanation.personalization_score,
+                final_score = result.relevance_score,
+                "score breakdown"
+            );
+            let structural_score = result
+                .detailed_explanation
+                .as_ref()
+                .map_or(0.0, |details| details.score_breakdown.structural_score);
             result.explanation.reasoning = format!(
                 "HippoRAG retrieval: semantic={:.3}, structural={:.3}, temporal={:.3}, personalization={:.3}",
                 result.explanation.semantic_score,
-                0.0, // structural score would be computed
+                structural_score,
                 result.explanation.temporal_score,
                 result.explanation.personalization_score
             );
@@ -730,9 +920,78 @@ impl HippoRAGRetriever {
         Ok(fused_results)
     }
 
+    /// 按约束筛选最终结果：相关性阈值、排除/必需标签、情感过滤与数量上限（`query.limit`，
+    /// 缺省为 `max_results`）
+    ///
+    /// 筛选前先对情感与 `query.filters.preferred_emotion` 一致的候选加分。
+    /// 被排除的候选按分数降序记录为备选结果，最多 `config.retrieval.max_alternatives` 个，
+    /// 附加到每个保留结果的完整解释中。
+    fn apply_constraints(
+        &self,
+        query: &Query,
+        context: &RetrievalContext,
+        mut candidates: Vec<RetrievalResult>,
+    ) -> Vec<RetrievalResult> {
+        let constraints = &context.constraints;
+        let limit = query.limit.or(constraints.max_results);
+        if let Some(preferred_emotion) = &query.filters.preferred_emotion {
+            apply_emotion_boost(
+                &mut candidates,
+                preferred_emotion,
+                self.config.retrieval.emotion_match_boost,
+            );
+        }
+        candidates.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+
+        let mut results = Vec::new();
+        let mut alternatives = Vec::new();
+        for candidate in candidates {
+            let tags = &candidate.memory.attributes.tags;
+            let reason_excluded = if let Some(min_relevance) = constraints
+                .min_relevance
+                .filter(|min_relevance| candidate.relevance_score < *min_relevance)
+            {
+                Some(format!("below threshold {}", min_relevance))
+            } else if let Some(tag) = constraints.excluded_tags.iter().find(|t| tags.contains(t)) {
+                Some(format!("excluded by tag '{}'", tag))
+            } else if let Some(tag) = constraints.required_tags.iter().find(|t| !tags.contains(t)) {
+                Some(format!("missing required tag '{}'", tag))
+            } else if let Some(emotion) =
+                query.filters.emotion_filter.as_ref().filter(|emotion| {
+                    candidate.memory.attributes.emotion.as_ref() != Some(*emotion)
+                })
+            {
+                Some(format!("emotion is not '{}'", emotion))
+            } else {
+                limit
+                    .filter(|limit| results.len() >= *limit)
+                    .map(|limit| format!("beyond limit {}", limit))
+            };
+
+            match reason_excluded {
+                Some(reason_excluded) => alternatives.push(AlternativeResult {
+                    memory_id: candidate.memory.id.clone(),
+                    score: candidate.relevance_score,
+                    reason_excluded,
+                }),
+                None => results.push(candidate),
+            }
+        }
+
+        alternatives.truncate(self.config.retrieval.max_alternatives);
+        for result in &mut results {
+            if let Some(details) = &mut result.detailed_explanation {
+                details.alternatives = alternatives.clone();
+            }
+        }
+        results
+    }
+
     /// 应用PageRank重排序
+    #[tracing::instrument(skip_all)]
     async fn apply_pagerank_reranking(
         &self,
+        db: &VectorGraphDB,
         mut results: Vec<RetrievalResult>,
         query: &Query,
         _context: &RetrievalContext,
@@ -741,22 +1000,32 @@ impl HippoRAGRetriever {
 
         // 构建子图
         let memory_ids: Vec<MemoryId> = results.iter().map(|r| r.memory.id.clone()).collect();
-        let _subgraph = self.build_subgraph(&memory_ids).await?;
+        let subgraph = self.build_subgraph(db, &memory_ids).await?;
 
-        // 计算PageRank分数
+        // 计算PageRank分数：子图中有边时以结构分数为基础，再叠加个性化分数
+        let mut pagerank_scores = if subgraph.values().any(|neighbors| !neighbors.is_empty()) {
+            self.pagerank_engine
+                .compute_standard_pagerank(&subgraph)
+                .await?
+        } else {
+            HashMap::new()
+        };
         let user_profile = self.personalization_manager.get_user_profile()?;
-        let pagerank_scores = self
+        let personalized_scores = self
             .pagerank_engine
-            .compute_personalized_pagerank(&user_profile, &query.text, &self.db)
+            .compute_personalized_pagerank(&user_profile, &query.text, db)
             .await?;
-
-        // 重新计算分数
-        for result in &mut results {
-            if let Some(pagerank_score) = pagerank_scores.get(&result.memory.id) {
-                result.relevance_score = result.relevance_score * 0.7 + pagerank_score * 0.3;
-            }
+        for (memory_id, score) in personalized_scores {
+            *pagerank_scores.entry(memory_id).or_insert(0.0) +=
+                score * self.pagerank_engine.personalization_weight;
         }
 
+        blend_pagerank_scores(
+            &mut results,
+            &pagerank_scores,
+            self.config.graph.pagerank.rerank_alpha,
+        );
+
         // 重新排序
         results.sort_by(|a, b| b.relevance_score.partial_cmp(&a.relevance_score).unwrap());
 
@@ -766,60 +1035,38 @@ impl HippoRAGRetriever {
     // 辅助方法
 
     async fn generate_query_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        use model2vec_rs::model::StaticModel;
-        use std::sync::OnceLock;
-
-        // 使用静态变量缓存模型，避免重复加载
-        static MODEL: OnceLock<Option<StaticModel>> = OnceLock::new();
-
-        let model = MODEL.get_or_init(|| {
-            match StaticModel::from_pretrained(
-                "minishlab/potion-multilingual-128M", // 使用多语言模型
-                None,                                 // 无需 HF token
-                None,                                 // 使用模型默认的归一化设置
-                None,                                 // 无子文件夹
-            ) {
-                Ok(m) => Some(m),
-                Err(e) => {
-                    eprintln!("Failed to load embedding model: {}", e);
-                    None
-                }
-            }
-        });
-
-        if let Some(model) = model {
-            // 生成嵌入向量
-            let sentences = vec![text.to_string()];
-            let embeddings = model.encode(&sentences);
-
-            if embeddings.is_empty() {
-                return Err(MemoryError::Internal {
-                    message: "Failed to generate embedding: empty result".to_string(),
-                });
-            }
-
-            Ok(embeddings[0].clone())
-        } else {
-            Err(MemoryError::Internal {
-                message: "Embedding model not initialized".to_string(),
-            })
+        // 与记忆写入共用模型与归一化设置，查询向量和存储向量可直接比较
+        let mut embedding = self
+            .embedding_service
+            .encode_single(text)
+            .await
+            .map_err(|e| MemoryError::Internal {
+                message: format!("Failed to generate embedding: {}", e),
+            })?;
+        if self.config.vector.normalizes_embeddings() {
+            // 零向量无法归一化，保持原样
+            let _ = VectorUtils::normalize(&mut embedding);
         }
+        Ok(embedding)
     }
 
     async fn check_cache(
         &self,
         query: &Query,
         context: &RetrievalContext,
+        strategy: &RetrievalStrategy,
     ) -> Result<Option<Vec<RetrievalResult>>> {
-        let cache_key = self.generate_cache_key(query, context)?;
+        let cache_key = self.generate_cache_key(query, context, strategy)?;
         let cache = self.cache.read().await;
 
         if let Some(cached) = cache.query_cache.get(&cache_key) {
             if Utc::now() - cached.timestamp < cached.ttl {
+                telemetry::record_cache_lookup(true);
                 return Ok(Some(cached.results.clone()));
             }
         }
 
+        telemetry::record_cache_lookup(false);
         Ok(None)
     }
 
@@ -827,13 +1074,14 @@ impl HippoRAGRetriever {
         &self,
         query: &Query,
         context: &RetrievalContext,
+        strategy: &RetrievalStrategy,
         results: &[RetrievalResult],
     ) -> Result<()> {
-        let cache_key = self.generate_cache_key(query, context)?;
+        let cache_key = self.generate_cache_key(query, context, strategy)?;
         let cached_result = CachedResult {
             results: results.to_vec(),
             timestamp: Utc::now(),
-            ttl: Duration::minutes(30),
+            ttl: Duration::seconds(self.config.retrieval.cache_ttl_seconds as i64),
         };
 
         let mut cache = self.cache.write().await;
@@ -845,16 +1093,42 @@ impl HippoRAGRetriever {
         Ok(())
     }
 
-    fn generate_cache_key(&self, query: &Query, _context: &RetrievalContext) -> Result<String> {
-        use std::collections::hash_map::DefaultHasher;
-        use std::hash::{Hash, Hasher};
-
-        let mut hasher = DefaultHasher::new();
-        query.text.hash(&mut hasher);
-        format!("{:?}", query.query_type).hash(&mut hasher);
+    /// 生成检索缓存键：对查询及上下文的规范化序列化取 SHA-256，跨进程和 Rust 版本保持稳定
+    fn generate_cache_key(
+        &self,
+        query: &Query,
+        context: &RetrievalContext,
+        strategy: &RetrievalStrategy,
+    ) -> Result<String> {
+        // 不同查询参数、策略、约束和时间窗口的结果不同，不能共用缓存
         // 单用户系统，不需要user_id
+        // serde_json 的对象键有序，HashMap 字段的序列化结果与迭代顺序无关
+        let canonical = serde_json::json!({
+            "query": serde_json::to_value(query)?,
+            "strategy": serde_json::to_value(strategy)?,
+            "constraints": serde_json::to_value(&context.constraints)?,
+            "time_window": serde_json::to_value(context.time_window)?,
+        });
 
-        Ok(format!("query_{:x}", hasher.finish()))
+        Ok(format!(
+            "query_{}",
+            HashUtils::hash_string(&canonical.to_string())
+        ))
+    }
+
+    /// 获取检索缓存句柄，供记忆管理器在写入时使缓存失效
+    pub fn cache_handle(&self) -> Arc<RwLock<RetrievalCache>> {
+        self.cache.clone()
+    }
+
+    /// 清空检索缓存
+    pub async fn invalidate_cache(&self) {
+        self.cache.write().await.invalidate_all();
+    }
+
+    /// 使包含指定记忆的缓存结果失效
+    pub async fn invalidate_memory(&self, memory_id: &MemoryId) {
+        self.cache.write().await.invalidate_memory(memory_id);
     }
 
     async fn record_interaction(
@@ -872,6 +1146,7 @@ impl HippoRAGRetriever {
                 dwell_time: None,
             };
 
+            self.db.insert_interaction(&interaction_record).await?;
             self.personalization_manager
                 .record_interaction(interaction_record)?;
         }
@@ -879,6 +1154,37 @@ impl HippoRAGRetriever {
         Ok(())
     }
 
+    /// 根据用户对某条记忆的反馈更新偏好
+    ///
+    /// 反馈会作为交互记录保存；达到强化阈值的正反馈会强化该记忆的标签与关键词，
+    /// 并将更新后的用户档案写入数据库。
+    pub async fn update_from_interaction(&mut self, memory: &Memory, feedback: f32) -> Result<()> {
+        let interaction_record = InteractionRecord {
+            memory_id: memory.id.clone(),
+            interaction_type: InteractionType::Feedback,
+            timestamp: Utc::now(),
+            feedback_score: Some(feedback),
+            dwell_time: None,
+        };
+        self.db.insert_interaction(&interaction_record).await?;
+        self.personalization_manager
+            .record_interaction(interaction_record)?;
+
+        if self
+            .personalization_manager
+            .update_from_interaction(memory, feedback)
+        {
+            let mut profile = self.personalization_manager.get_user_profile()?;
+            // 交互历史单独保存在交互记录表中
+            profile.interaction_history.clear();
+            self.db
+                .save_user_profile(USER_PROFILE_ID, &serde_json::to_value(&profile)?)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     // 转换方法
 
     fn vector_to_memory(&self, vector: &crate::database::Vector) -> Result<Memory> {
@@ -1119,52 +1425,6 @@ impl HippoRAGRetriever {
         })
     }
 
-    fn build_metadata_filters(
-        &self,
-        constraints: &RetrievalConstraints,
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        let mut filters = HashMap::new();
-
-        if !constraints.required_tags.is_empty() {
-            filters.insert(
-                "required_tags".to_string(),
-                serde_json::Value::Array(
-                    constraints
-                        .required_tags
-                        .iter()
-                        .map(|t| serde_json::Value::String(t.clone()))
-                        .collect(),
-                ),
-            );
-        }
-
-        if !constraints.excluded_tags.is_empty() {
-            filters.insert(
-                "excluded_tags".to_string(),
-                serde_json::Value::Array(
-                    constraints
-                        .excluded_tags
-                        .iter()
-                        .map(|t| serde_json::Value::String(t.clone()))
-                        .collect(),
-                ),
-            );
-        }
-
-        if filters.is_empty() {
-            Ok(None)
-        } else {
-            Ok(Some(filters))
-        }
-    }
-
-    fn build_graph_filters(
-        &self,
-        constraints: &RetrievalConstraints,
-    ) -> Result<Option<HashMap<String, serde_json::Value>>> {
-        self.build_metadata_filters(constraints)
-    }
-
     async fn find_seed_nodes_from_queries(&self, _queries: &[String]) -> Result<Vec<MemoryId>> {
         // 简化实现：返回空向量
         Ok(Vec::new())
@@ -1175,20 +1435,21 @@ impl HippoRAGRetriever {
         Ok(1.0 / (position as f32 + 1.0))
     }
 
+    /// 时间相关性：以时间窗口终点为参照的指数衰减 exp(-age/τ)
+    ///
+    /// 分数随记忆年龄连续下降，窗口外的记忆仍保留较小的非零分数；
+    /// 是否按窗口硬过滤由 `config.retrieval.temporal_hard_window` 决定。
     async fn calculate_temporal_relevance(
         &self,
         memory: &Memory,
         time_window: &(DateTime<Utc>, DateTime<Utc>),
-        _query: &str,
     ) -> Result<f32> {
-        let memory_time = memory.metadata.created_at;
-        if memory_time >= time_window.0 && memory_time <= time_window.1 {
-            let window_duration = time_window.1 - time_window.0;
-            let memory_offset = memory_time - time_window.0;
-            Ok(1.0 - (memory_offset.num_seconds() as f32 / window_duration.num_seconds() as f32))
-        } else {
-            Ok(0.0)
-        }
+        let tau = Duration::seconds((self.config.retrieval.temporal_tau_days * 86_400.0) as i64);
+        Ok(TimeUtils::recency_decay(
+            memory.metadata.created_at,
+            time_window.1,
+            tau,
+        ))
     }
 
     async fn calculate_personalization_score(
@@ -1198,13 +1459,17 @@ impl HippoRAGRetriever {
     ) -> Result<f32> {
         let mut score = 0.0;
 
-        // 基于用户偏好计算分数
+        // 基于用户偏好计算分数：偏好出现在内容中，或与标签、关键词一致
+        let content = memory.content.to_lowercase();
         for (preference, weight) in &user_profile.preferences {
-            if memory
-                .content
-                .to_lowercase()
-                .contains(&preference.to_lowercase())
-            {
+            let preference = preference.to_lowercase();
+            let matches_feature = memory
+                .attributes
+                .tags
+                .iter()
+                .chain(&memory.attributes.keywords)
+                .any(|feature| feature.to_lowercase() == preference);
+            if matches_feature || content.contains(&preference) {
                 score += weight;
             }
         }
@@ -1224,19 +1489,255 @@ impl HippoRAGRetriever {
         Ok(score.min(1.0))
     }
 
+    /// 构建给定记忆之间的子图邻接表
+    ///
+    /// 只包含两端都在集合内的边；PageRank 衡量结构中心性，边按无向处理，
+    /// 每个节点的邻居不重复。
     async fn build_subgraph(
         &self,
+        db: &VectorGraphDB,
         memory_ids: &[MemoryId],
     ) -> Result<HashMap<MemoryId, Vec<MemoryId>>> {
-        // 简化的子图构建
-        let mut subgraph = HashMap::new();
-        for id in memory_ids {
-            subgraph.insert(id.clone(), Vec::new());
+        let mut subgraph: HashMap<MemoryId, Vec<MemoryId>> = memory_ids
+            .iter()
+            .map(|id| (id.clone(), Vec::new()))
+            .collect();
+
+        for edge in db.get_edges_among(memory_ids).await? {
+            if edge.from_node == edge.to_node {
+                continue;
+            }
+            for (node, neighbor) in [
+                (&edge.from_node, &edge.to_node),
+                (&edge.to_node, &edge.from_node),
+            ] {
+                if let Some(neighbors) = subgraph.get_mut(node) {
+                    if !neighbors.contains(neighbor) {
+                        neighbors.push(neighbor.clone());
+                    }
+                }
+            }
         }
+
         Ok(subgraph)
     }
 }
 
+/// 解析语义检索的相似度阈值：查询过滤器 > 检索约束 > 配置默认值
+pub(crate) fn resolve_similarity_threshold(
+    filters: &QueryFilters,
+    constraints: Option<&RetrievalConstraints>,
+    default: f32,
+) -> f32 {
+    filters
+        .min_similarity
+        .or_else(|| constraints.and_then(|constraints| constraints.min_relevance))
+        .unwrap_or(default)
+}
+
+/// 情感匹配加分：记忆情感与 `preferred_emotion` 一致的结果相关性分数加上 `boost`
+pub(crate) fn apply_emotion_boost(
+    results: &mut [RetrievalResult],
+    preferred_emotion: &str,
+    boost: f32,
+) {
+    for result in results {
+        if result.memory.attributes.emotion.as_deref() != Some(preferred_emotion) {
+            continue;
+        }
+        result.relevance_score += boost;
+        if let Some(details) = &mut result.detailed_explanation {
+            details.score_breakdown.final_score = result.relevance_score;
+            details.reasoning_path.push(ReasoningStep {
+                step_type: "emotion_match".to_string(),
+                description: format!("Emotion matches '{}'", preferred_emotion),
+                score_impact: boost,
+                confidence: 1.0,
+            });
+        }
+    }
+}
+
+/// 将 PageRank 分数混入相关性分数：`alpha * relevance + (1 - alpha) * pagerank`
+///
+/// PageRank 分数先按结果集中的最大值归一化到 0..1，与相关性分数同一尺度；
+/// 没有 PageRank 分数的结果按 0 计。`alpha` 为 1.0 时相关性分数不变。
+pub(crate) fn blend_pagerank_scores(
+    results: &mut [RetrievalResult],
+    pagerank_scores: &HashMap<MemoryId, f32>,
+    alpha: f32,
+) {
+    let max_score = results
+        .iter()
+        .filter_map(|r| pagerank_scores.get(&r.memory.id))
+        .fold(0.0f32, |max, &score| max.max(score));
+    if max_score <= 0.0 {
+        return;
+    }
+
+    for result in results {
+        let pagerank_score = pagerank_scores
+            .get(&result.memory.id)
+            .map_or(0.0, |score| score / max_score);
+        result.relevance_score = result.relevance_score * alpha + pagerank_score * (1.0 - alpha);
+        if let Some(details) = &mut result.detailed_explanation {
+            details.score_breakdown.final_score = result.relevance_score;
+            details.reasoning_path.push(ReasoningStep {
+                step_type: "pagerank".to_string(),
+                description: format!("PageRank reranking: {:.3}", pagerank_score),
+                score_impact: pagerank_score,
+                confidence: 0.9,
+            });
+            details.confidence = result.relevance_score;
+        }
+    }
+}
+
+/// 按 `sort_by` 对结果做最终排序；排序键相同时按相关性降序
+pub(crate) fn sort_results(results: &mut [RetrievalResult], sort_by: &SortBy) {
+    let order = match sort_by {
+        SortBy::Relevance(order)
+        | SortBy::CreatedAt(order)
+        | SortBy::Importance(order)
+        | SortBy::AccessCount(order)
+        | SortBy::Confidence(order) => *order,
+        SortBy::Custom(_) => return,
+    };
+    let key = |result: &RetrievalResult| -> f64 {
+        match sort_by {
+            SortBy::Relevance(_) => result.relevance_score as f64,
+            SortBy::CreatedAt(_) => result.memory.metadata.created_at.timestamp_micros() as f64,
+            SortBy::Importance(_) => result.memory.attributes.importance as f64,
+            SortBy::AccessCount(_) => result.memory.metadata.access_count as f64,
+            SortBy::Confidence(_) => result.memory.attributes.confidence as f64,
+            SortBy::Custom(_) => 0.0,
+        }
+    };
+
+    results.sort_by(|a, b| {
+        let ordering = key(a).total_cmp(&key(b));
+        let ordering = match order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        };
+        ordering.then_with(|| b.relevance_score.total_cmp(&a.relevance_score))
+    });
+}
+
+/// 记录某个组件检索结果中的分数，按记忆ID累积到融合前的分数分解中
+fn record_component_scores(
+    breakdowns: &mut HashMap<MemoryId, ScoreBreakdown>,
+    strategy: &RetrievalStrategy,
+    results: &[RetrievalResult],
+) {
+    for result in results {
+        let breakdown = breakdowns
+            .entry(result.memory.id.clone())
+            .or_insert_with(|| ScoreBreakdown {
+                semantic_score: 0.0,
+                temporal_score: 0.0,
+                structural_score: 0.0,
+                importance_score: result.explanation.importance_score,
+                personalization_score: 0.0,
+                final_score: 0.0,
+                normalization_factor: 1.0,
+            });
+        breakdown.semantic_score = breakdown
+            .semantic_score
+            .max(result.explanation.semantic_score);
+        match strategy {
+            RetrievalStrategy::Structural => breakdown.structural_score = result.relevance_score,
+            RetrievalStrategy::Temporal => {
+                breakdown.temporal_score = result.explanation.temporal_score
+            }
+            RetrievalStrategy::Personalized => {
+                breakdown.personalization_score = result.explanation.personalization_score
+            }
+            _ => {}
+        }
+    }
+}
+
+/// 为融合后的结果生成完整解释，分数分解取自融合前各组件的分数
+fn attach_fused_explanations(
+    results: &mut [RetrievalResult],
+    strategy: &RetrievalStrategy,
+    mut breakdowns: HashMap<MemoryId, ScoreBreakdown>,
+) {
+    for result in results {
+        let Some(mut score_breakdown) = breakdowns.remove(&result.memory.id) else {
+            continue;
+        };
+        score_breakdown.final_score = result.relevance_score;
+
+        let components = [
+            ("semantic_matching", score_breakdown.semantic_score, 0.9),
+            ("graph_traversal", score_breakdown.structural_score, 0.8),
+            ("temporal_decay", score_breakdown.temporal_score, 0.8),
+            (
+                "personalization",
+                score_breakdown.personalization_score,
+                0.85,
+            ),
+        ];
+        let mut reasoning_path: Vec<ReasoningStep> = components
+            .into_iter()
+            .filter(|(_, score, _)| *score > 0.0)
+            .map(|(step_type, score, confidence)| ReasoningStep {
+                step_type: step_type.to_string(),
+                description: format!("{} score: {:.3}", step_type, score),
+                score_impact: score,
+                confidence,
+            })
+            .collect();
+        reasoning_path.push(ReasoningStep {
+            step_type: "fusion".to_string(),
+            description: format!("Fused score: {:.3}", result.relevance_score),
+            score_impact: result.relevance_score,
+            confidence: 1.0,
+        });
+
+        result.detailed_explanation = Some(DetailedExplanation {
+            strategy_used: strategy.clone(),
+            score_breakdown,
+            reasoning_path,
+            confidence: result.relevance_score,
+            alternatives: Vec::new(),
+        });
+    }
+}
+
+/// 组件检索结果：语义、结构、时间、个性化（可选）
+type ComponentResults = (
+    Vec<RetrievalResult>,
+    Vec<RetrievalResult>,
+    Vec<RetrievalResult>,
+    Option<Vec<RetrievalResult>>,
+);
+
+/// 并发执行各组件检索，总耗时由最慢的组件决定
+async fn gather_component_results<S, T, P, Q>(
+    semantic: S,
+    structural: T,
+    temporal: P,
+    personalized: Option<Q>,
+) -> Result<ComponentResults>
+where
+    S: Future<Output = Result<Vec<RetrievalResult>>>,
+    T: Future<Output = Result<Vec<RetrievalResult>>>,
+    P: Future<Output = Result<Vec<RetrievalResult>>>,
+    Q: Future<Output = Result<Vec<RetrievalResult>>>,
+{
+    let personalized = async move {
+        match personalized {
+            Some(future) => future.await.map(Some),
+            None => Ok(None),
+        }
+    };
+
+    tokio::try_join!(semantic, structural, temporal, personalized)
+}
+
 // 实现各个组件
 
 impl PageRankEngine {
@@ -1268,7 +1769,6 @@ impl PageRankEngine {
         Ok(scores)
     }
 
-    #[allow(dead_code)]
     async fn compute_standard_pagerank(
         &self,
         graph: &HashMap<MemoryId, Vec<MemoryId>>,
@@ -1355,8 +1855,12 @@ impl FusionEngine {
             for result in results {
                 let entry = fused_results
                     .entry(result.memory.id.clone())
-                    .or_insert_with(|| result.clone());
+                    .or_insert_with(|| RetrievalResult {
+                        relevance_score: 0.0,
+                        ..result.clone()
+                    });
                 entry.relevance_score += result.relevance_score * weight;
+                entry.explanation.merge(&result.explanation);
             }
         }
 
@@ -1408,13 +1912,40 @@ impl PersonalizationManager {
         self.user_profile.interaction_history.push(interaction);
         self.user_profile.updated_at = Utc::now();
 
-        // 限制历史记录长度
-        if self.user_profile.interaction_history.len() > 1000 {
-            self.user_profile.interaction_history.drain(0..500);
-        }
+        self.truncate_history();
 
         Ok(())
     }
+
+    /// 根据反馈更新偏好，返回偏好是否发生变化
+    fn update_from_interaction(&mut self, memory: &Memory, feedback: f32) -> bool {
+        self.preference_learner
+            .update_from_interaction(&mut self.user_profile, memory, feedback)
+    }
+
+    /// 用持久化的档案恢复偏好，保留当前的交互历史
+    fn restore_profile(&mut self, profile: UserProfile) {
+        let history = std::mem::take(&mut self.user_profile.interaction_history);
+        self.user_profile = UserProfile {
+            interaction_history: history,
+            ..profile
+        };
+    }
+
+    /// 用持久化的交互记录（按时间升序）恢复历史
+    fn restore_history(&mut self, history: Vec<InteractionRecord>) {
+        self.user_profile.interaction_history = history;
+        self.truncate_history();
+    }
+
+    /// 限制历史记录长度，只保留最近的 `max_interaction_history` 条
+    fn truncate_history(&mut self) {
+        let max_history = self.interaction_tracker.max_history_size;
+        let history = &mut self.user_profile.interaction_history;
+        if history.len() > max_history {
+            history.drain(0..history.len() - max_history);
+        }
+    }
 }
 
 impl InteractionTracker {
@@ -1434,6 +1965,49 @@ impl RetrievalPreferenceLearner {
             reinforcement_threshold: 0.7,
         }
     }
+
+    /// 根据一次反馈更新偏好权重
+    ///
+    /// 反馈达到强化阈值时，记忆的标签与关键词权重按 `w += learning_rate * feedback * (1 - w)`
+    /// 增长，其余偏好按衰减因子衰减，过小的权重被移除。返回偏好是否发生变化。
+    fn update_from_interaction(
+        &self,
+        profile: &mut UserProfile,
+        memory: &Memory,
+        feedback: f32,
+    ) -> bool {
+        if feedback < self.reinforcement_threshold {
+            return false;
+        }
+
+        let features: HashSet<String> = memory
+            .attributes
+            .tags
+            .iter()
+            .chain(&memory.attributes.keywords)
+            .map(|feature| feature.trim().to_lowercase())
+            .filter(|feature| !feature.is_empty())
+            .collect();
+        if features.is_empty() {
+            return false;
+        }
+
+        for (preference, weight) in profile.preferences.iter_mut() {
+            if !features.contains(preference) {
+                *weight *= self.decay_factor;
+            }
+        }
+        profile
+            .preferences
+            .retain(|_, weight| *weight >= MIN_PREFERENCE_WEIGHT);
+
+        for feature in features {
+            let weight = profile.preferences.entry(feature).or_insert(0.0);
+            *weight += self.learning_rate * feedback * (1.0 - *weight);
+        }
+        profile.updated_at = Utc::now();
+        true
+    }
 }
 
 impl RetrievalCache {
@@ -1446,6 +2020,20 @@ impl RetrievalCache {
         }
     }
 
+    /// 清空所有查询缓存
+    pub fn invalidate_all(&mut self) {
+        self.query_cache.clear();
+        self.pagerank_cache.clear();
+    }
+
+    /// 移除结果中包含指定记忆的缓存条目
+    pub fn invalidate_memory(&mut self, memory_id: &MemoryId) {
+        self.query_cache
+            .retain(|_, cached| !cached.results.iter().any(|r| &r.memory.id == memory_id));
+        self.pagerank_cache
+            .retain(|_, scores| !scores.contains_key(memory_id));
+    }
+
     fn cleanup_expired(&mut self) {
         let now = Utc::now();
         self.query_cache
@@ -1478,13 +2066,17 @@ impl RetrievalCache {
 mod tests {
     use super::*;
     use crate::core_types::{Interaction, InteractionType, QueryFilters, QueryType, QueryWeights};
-    use crate::database::VectorGraphDB;
+    use crate::database::{GraphEdge, GraphNode, VectorGraphDB};
     use tempfile::NamedTempFile;
     use uuid::Uuid;
 
     async fn create_test_retriever() -> HippoRAGRetriever {
-        let temp_file = NamedTempFile::new().unwrap();
-        let db_url = format!("sqlite://{}", temp_file.path().display());
+        let db_path = NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let db_url = format!("sqlite://{}", db_path.display());
 
         let config = Config {
             database: crate::config::DatabaseConfig {
@@ -1529,7 +2121,7 @@ mod tests {
         };
 
         let results = retriever
-            .semantic_retrieval(&query, &context)
+            .semantic_retrieval(&retriever.db, &query, &context)
             .await
             .unwrap();
         // 由于没有实际数据，结果应该为空
@@ -1582,23 +2174,553 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_personalization_manager() {
-        let config = Config::default();
-        let mut manager = PersonalizationManager::new(&config).unwrap();
-
-        let interaction = Interaction {
-            id: format!("interaction_{}", Uuid::new_v4()),
-            user_id: "system".to_string(),
-            session_id: None,
-            query: "test query".to_string(),
-            retrieved_memories: vec!["memory_1".to_string()],
-            user_feedback: Some(0.8),
-            interaction_type: InteractionType::Query,
-            timestamp: Utc::now(),
-            response_time_ms: Some(5000),
-            additional_info: HashMap::new(),
+    async fn test_cache_invalidated_after_memory_insert() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
         };
-
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let retriever = HippoRAGRetriever::new(db.clone(), config.clone())
+            .await
+            .unwrap();
+        let mut manager = crate::memory::MemoryManager::new(db, config).await.unwrap();
+        manager.attach_retrieval_cache(retriever.cache_handle());
+
+        let query = Query {
+            text: "缓存失效".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(5),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let context = RetrievalContext {
+            session_id: None,
+            current_topic: None,
+            recent_queries: Vec::new(),
+            time_window: None,
+            priority: Priority::Normal,
+            constraints: RetrievalConstraints {
+                max_results: Some(5),
+                min_relevance: None,
+                required_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                time_range: None,
+                source_filter: None,
+            },
+        };
+        let strategy = RetrievalStrategy::Semantic;
+
+        retriever
+            .cache_results(&query, &context, &strategy, &[])
+            .await
+            .unwrap();
+        assert!(retriever
+            .check_cache(&query, &context, &strategy)
+            .await
+            .unwrap()
+            .is_some());
+        // 不同策略不共用缓存
+        assert!(retriever
+            .check_cache(&query, &context, &RetrievalStrategy::Temporal)
+            .await
+            .unwrap()
+            .is_none());
+
+        let memory = Memory::new(
+            "新写入的记忆".to_string(),
+            MemoryType::Knowledge,
+            vec![0.1, 0.2, 0.3],
+            MemoryAttributes::default(),
+        );
+        manager.create_memory(&memory).await.unwrap();
+
+        assert!(retriever
+            .check_cache(&query, &context, &strategy)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_temporal_relevance_decays_smoothly() {
+        let retriever = create_test_retriever().await;
+        let now = Utc::now();
+        let time_window = (now - Duration::days(30), now);
+
+        let score_at = |age: Duration| {
+            let mut memory = Memory::new(
+                "记忆".to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes::default(),
+            );
+            memory.metadata.created_at = now - age;
+            let retriever = &retriever;
+            async move {
+                retriever
+                    .calculate_temporal_relevance(&memory, &time_window)
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let ages = [
+            Duration::zero(),
+            Duration::days(1),
+            Duration::days(10),
+            Duration::days(30) - Duration::seconds(1),
+            Duration::days(30) + Duration::seconds(1),
+            Duration::days(365),
+        ];
+        let mut scores = Vec::new();
+        for age in ages {
+            scores.push(score_at(age).await);
+        }
+
+        assert!((scores[0] - 1.0).abs() < 1e-6);
+        for pair in scores.windows(2) {
+            assert!(
+                pair[0] > pair[1],
+                "scores must decrease with age: {:?}",
+                scores
+            );
+        }
+        // 旧窗口边界两侧的分数连续，窗口外仍有非零分数
+        assert!((scores[3] - scores[4]).abs() < 1e-4);
+        assert!(scores[4] > 0.0);
+        assert!(scores[5] > 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_hipporag_results_carry_json_explanation() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let mut retriever = HippoRAGRetriever::new(db.clone(), config.clone())
+            .await
+            .unwrap();
+        let manager = crate::memory::MemoryManager::new(db, config).await.unwrap();
+
+        let embedding = retriever
+            .generate_query_embedding("检索解释")
+            .await
+            .unwrap();
+        let memory = Memory::new(
+            "检索解释".to_string(),
+            MemoryType::Knowledge,
+            embedding,
+            MemoryAttributes::default(),
+        );
+        manager.create_memory(&memory).await.unwrap();
+
+        let query = Query {
+            text: "检索解释".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(5),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let context = RetrievalContext {
+            session_id: None,
+            current_topic: None,
+            recent_queries: Vec::new(),
+            time_window: None,
+            priority: Priority::Normal,
+            constraints: RetrievalConstraints {
+                max_results: Some(5),
+                min_relevance: Some(0.0),
+                required_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                time_range: None,
+                source_filter: None,
+            },
+        };
+        let strategy = RetrievalStrategy::HippoRAG {
+            use_pagerank: false,
+            use_personalization: false,
+            fusion_method: FusionMethod::LinearWeighted,
+        };
+
+        let results = retriever
+            .retrieve(&query, &context, &strategy)
+            .await
+            .unwrap();
+        let result = results.iter().find(|r| r.memory.id == memory.id).unwrap();
+
+        let json = serde_json::to_value(result).unwrap();
+        let details = &json["detailed_explanation"];
+        assert!(details["strategy_used"]["HippoRAG"].is_object());
+        let breakdown = &details["score_breakdown"];
+        for field in [
+            "semantic_score",
+            "temporal_score",
+            "structural_score",
+            "importance_score",
+            "personalization_score",
+            "final_score",
+            "normalization_factor",
+        ] {
+            assert!(breakdown[field].is_number(), "missing {}", field);
+        }
+        assert!(breakdown["semantic_score"].as_f64().unwrap() > 0.9);
+        assert!(breakdown["temporal_score"].as_f64().unwrap() > 0.0);
+        let steps: Vec<&str> = details["reasoning_path"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|step| step["step_type"].as_str().unwrap())
+            .collect();
+        assert!(steps.contains(&"semantic_matching"));
+        assert_eq!(steps.last(), Some(&"fusion"));
+        assert!(details["alternatives"].is_array());
+    }
+
+    #[tokio::test]
+    async fn test_candidates_beyond_limit_become_alternatives() {
+        let retriever = create_test_retriever().await;
+        let candidate = |content: &str, score: f32, tags: &[&str]| {
+            let memory = Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes {
+                    tags: tags.iter().map(|t| t.to_string()).collect(),
+                    ..Default::default()
+                },
+            );
+            RetrievalResult {
+                memory,
+                relevance_score: score,
+                explanation: RetrievalExplanation {
+                    semantic_score: score,
+                    temporal_score: 0.0,
+                    importance_score: 0.5,
+                    personalization_score: 0.0,
+                    connection_paths: Vec::new(),
+                    reasoning: String::new(),
+                },
+                detailed_explanation: Some(DetailedExplanation {
+                    strategy_used: RetrievalStrategy::Semantic,
+                    score_breakdown: ScoreBreakdown {
+                        semantic_score: score,
+                        temporal_score: 0.0,
+                        structural_score: 0.0,
+                        importance_score: 0.5,
+                        personalization_score: 0.0,
+                        final_score: score,
+                        normalization_factor: 1.0,
+                    },
+                    reasoning_path: Vec::new(),
+                    confidence: score,
+                    alternatives: Vec::new(),
+                }),
+            }
+        };
+        let candidates = vec![
+            candidate("a", 0.95, &[]),
+            candidate("archived", 0.9, &["archive"]),
+            candidate("b", 0.85, &[]),
+            candidate("c", 0.8, &[]),
+            candidate("d", 0.75, &[]),
+            candidate("weak", 0.2, &[]),
+        ];
+        let ids: Vec<MemoryId> = candidates.iter().map(|c| c.memory.id.clone()).collect();
+
+        let query = Query {
+            text: "备选结果".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(2),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let context = RetrievalContext {
+            session_id: None,
+            current_topic: None,
+            recent_queries: Vec::new(),
+            time_window: None,
+            priority: Priority::Normal,
+            constraints: RetrievalConstraints {
+                max_results: Some(10),
+                min_relevance: Some(0.5),
+                required_tags: Vec::new(),
+                excluded_tags: vec!["archive".to_string()],
+                time_range: None,
+                source_filter: None,
+            },
+        };
+
+        let results = retriever.apply_constraints(&query, &context, candidates);
+        let kept: Vec<&str> = results.iter().map(|r| r.memory.content.as_str()).collect();
+        assert_eq!(kept, vec!["a", "b"]);
+
+        let alternatives = &results[0]
+            .detailed_explanation
+            .as_ref()
+            .unwrap()
+            .alternatives;
+        let excluded: Vec<(&str, &str)> = alternatives
+            .iter()
+            .map(|a| (a.memory_id.as_str(), a.reason_excluded.as_str()))
+            .collect();
+        assert_eq!(
+            excluded,
+            vec![
+                (ids[1].as_str(), "excluded by tag 'archive'"),
+                (ids[3].as_str(), "beyond limit 2"),
+                (ids[4].as_str(), "beyond limit 2"),
+                (ids[5].as_str(), "below threshold 0.5"),
+            ]
+        );
+        assert_eq!(alternatives[1].score, 0.8);
+    }
+
+    #[tokio::test]
+    async fn test_excluded_tags_do_not_consume_semantic_limit() {
+        let mut retriever = create_test_retriever().await;
+        retriever.set_embedding_service(Arc::new(EmbeddingService::with_encoder(
+            "test-encoder",
+            Box::new(|inputs: &[String]| inputs.iter().map(|_| vec![1.0, 0.0, 0.0]).collect()),
+        )));
+        let db = retriever.db.clone();
+
+        // 被排除的记忆与查询最相似，若先截断再排除，结果会被它们占满
+        let memory = |content: &str, embedding: Vec<f32>, tags: &[&str]| {
+            Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                embedding,
+                MemoryAttributes {
+                    tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                    ..Default::default()
+                },
+            )
+        };
+        let mut memories: Vec<Memory> = (0..3)
+            .map(|i| {
+                memory(
+                    &format!("archived {}", i),
+                    vec![1.0, 0.0, 0.0],
+                    &["archive"],
+                )
+            })
+            .collect();
+        memories.push(memory("kept 0", vec![0.9, 0.1, 0.0], &[]));
+        memories.push(memory("kept 1", vec![0.8, 0.2, 0.0], &["misc"]));
+        let manager = MemoryManager::new(db.clone(), retriever.config.clone())
+            .await
+            .unwrap();
+        for memory in &memories {
+            manager.create_memory(memory).await.unwrap();
+        }
+
+        let query = Query {
+            text: "查询".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(2),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let context = RetrievalContext {
+            session_id: None,
+            current_topic: None,
+            recent_queries: Vec::new(),
+            time_window: None,
+            priority: Priority::Normal,
+            constraints: RetrievalConstraints {
+                max_results: Some(2),
+                min_relevance: None,
+                required_tags: Vec::new(),
+                excluded_tags: vec!["archive".to_string()],
+                time_range: None,
+                source_filter: None,
+            },
+        };
+
+        let results = retriever
+            .semantic_retrieval(&db, &query, &context)
+            .await
+            .unwrap();
+        let contents: Vec<&str> = results.iter().map(|r| r.memory.content.as_str()).collect();
+        assert_eq!(contents, vec!["kept 0", "kept 1"]);
+    }
+
+    #[tokio::test]
+    async fn test_component_retrievals_run_concurrently() {
+        async fn delayed(ms: u64) -> Result<Vec<RetrievalResult>> {
+            tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+            Ok(Vec::new())
+        }
+
+        let start = std::time::Instant::now();
+        let (_, _, _, personalized) =
+            gather_component_results(delayed(100), delayed(100), delayed(100), Some(delayed(150)))
+                .await
+                .unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(personalized.is_some());
+        // 顺序执行需要 450ms，并发执行应接近最慢组件的 150ms
+        assert!(elapsed >= std::time::Duration::from_millis(150));
+        assert!(elapsed < std::time::Duration::from_millis(300));
+
+        let (_, _, _, personalized) = gather_component_results(
+            delayed(10),
+            delayed(10),
+            delayed(10),
+            None::<std::future::Ready<Result<Vec<RetrievalResult>>>>,
+        )
+        .await
+        .unwrap();
+        assert!(personalized.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_subgraph_uses_real_edges_and_ranks_hub_first() {
+        let retriever = create_test_retriever().await;
+
+        for id in ["hub", "leaf1", "leaf2", "leaf3", "outsider"] {
+            retriever
+                .db
+                .insert_node(&GraphNode {
+                    id: id.to_string(),
+                    node_type: "memory".to_string(),
+                    properties: HashMap::new(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+        for (id, from, to) in [
+            ("e1", "hub", "leaf1"),
+            ("e2", "leaf2", "hub"),
+            ("e3", "hub", "leaf3"),
+            ("e4", "leaf1", "outsider"),
+        ] {
+            retriever
+                .db
+                .insert_edge(&GraphEdge {
+                    id: id.to_string(),
+                    from_node: from.to_string(),
+                    to_node: to.to_string(),
+                    edge_type: "Semantic".to_string(),
+                    weight: 1.0,
+                    properties: HashMap::new(),
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        let ids: Vec<MemoryId> = ["hub", "leaf1", "leaf2", "leaf3"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+        let subgraph = retriever.build_subgraph(&retriever.db, &ids).await.unwrap();
+
+        assert_eq!(subgraph.len(), 4);
+        let mut hub_neighbors = subgraph["hub"].clone();
+        hub_neighbors.sort();
+        assert_eq!(hub_neighbors, vec!["leaf1", "leaf2", "leaf3"]);
+        // 集合外的节点不会出现在子图中
+        assert_eq!(subgraph["leaf1"], vec!["hub"]);
+        assert_eq!(subgraph["leaf2"], vec!["hub"]);
+
+        let scores = retriever
+            .pagerank_engine
+            .compute_standard_pagerank(&subgraph)
+            .await
+            .unwrap();
+        for leaf in ["leaf1", "leaf2", "leaf3"] {
+            assert!(scores["hub"] > scores[leaf]);
+        }
+    }
+
+    #[test]
+    fn test_pagerank_blend_follows_rerank_alpha() {
+        let candidate = |content: &str, score: f32| RetrievalResult {
+            memory: Memory::new(
+                content.to_string(),
+                MemoryType::Knowledge,
+                vec![0.1, 0.2, 0.3],
+                MemoryAttributes::default(),
+            ),
+            relevance_score: score,
+            explanation: RetrievalExplanation {
+                semantic_score: score,
+                temporal_score: 0.0,
+                importance_score: 0.5,
+                personalization_score: 0.0,
+                connection_paths: Vec::new(),
+                reasoning: String::new(),
+            },
+            detailed_explanation: None,
+        };
+        let relevant = candidate("relevant", 0.9);
+        let central = candidate("central", 0.6);
+        // 原始 PageRank 分数很小，归一化后 central 为 1.0，relevant 为 0.25
+        let pagerank_scores = HashMap::from([
+            (relevant.memory.id.clone(), 0.01),
+            (central.memory.id.clone(), 0.04),
+        ]);
+
+        let rank = |alpha: f32| {
+            let mut results = vec![relevant.clone(), central.clone()];
+            blend_pagerank_scores(&mut results, &pagerank_scores, alpha);
+            results.sort_by(|a, b| b.relevance_score.total_cmp(&a.relevance_score));
+            results
+        };
+
+        // alpha 为 1.0 时忽略 PageRank
+        let ignored = rank(1.0);
+        assert_eq!(ignored[0].memory.content, "relevant");
+        assert!((ignored[0].relevance_score - 0.9).abs() < 1e-6);
+        assert_eq!(rank(0.9)[0].memory.content, "relevant");
+
+        // PageRank 占比增大后中心节点排到前面
+        let blended = rank(0.5);
+        assert_eq!(blended[0].memory.content, "central");
+        assert!((blended[0].relevance_score - 0.8).abs() < 1e-6);
+        assert_eq!(rank(0.0)[0].memory.content, "central");
+    }
+
+    #[tokio::test]
+    async fn test_personalization_manager() {
+        let config = Config::default();
+        let mut manager = PersonalizationManager::new(&config).unwrap();
+
+        let interaction = Interaction {
+            id: format!("interaction_{}", Uuid::new_v4()),
+            user_id: "system".to_string(),
+            session_id: None,
+            query: "test query".to_string(),
+            retrieved_memories: vec!["memory_1".to_string()],
+            user_feedback: Some(0.8),
+            interaction_type: InteractionType::Query,
+            timestamp: Utc::now(),
+            response_time_ms: Some(5000),
+            additional_info: HashMap::new(),
+        };
+
         let interaction_record = InteractionRecord {
             memory_id: "test_memory".to_string(),
             interaction_type: interaction.interaction_type,
@@ -1612,4 +2734,337 @@ mod tests {
         let profile = manager.get_user_profile().unwrap();
         assert_eq!(profile.interaction_history.len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_interaction_history_survives_restart() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let mut config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.learning.max_interaction_history = 2;
+
+        let query = Query {
+            text: "test query".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let results: Vec<RetrievalResult> = ["first", "second", "third"]
+            .iter()
+            .map(|content| RetrievalResult {
+                memory: Memory::new(
+                    content.to_string(),
+                    MemoryType::Knowledge,
+                    vec![0.1, 0.2, 0.3],
+                    MemoryAttributes::default(),
+                ),
+                relevance_score: 0.5,
+                explanation: RetrievalExplanation {
+                    semantic_score: 0.5,
+                    temporal_score: 0.0,
+                    importance_score: 0.5,
+                    personalization_score: 0.0,
+                    connection_paths: Vec::new(),
+                    reasoning: String::new(),
+                },
+                detailed_explanation: None,
+            })
+            .collect();
+
+        {
+            let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+            let mut retriever = HippoRAGRetriever::new(db, config.clone()).await.unwrap();
+            retriever
+                .record_interaction(&query, &results)
+                .await
+                .unwrap();
+            let history = retriever
+                .personalization_manager
+                .get_user_profile()
+                .unwrap()
+                .interaction_history;
+            assert_eq!(history.len(), 2);
+        }
+
+        // 模拟重启：在同一个数据库上重新创建检索器
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let retriever = HippoRAGRetriever::new(db, config).await.unwrap();
+        let history = retriever
+            .personalization_manager
+            .get_user_profile()
+            .unwrap()
+            .interaction_history;
+
+        // 只恢复最近的 max_interaction_history 条，按时间升序
+        let restored: Vec<_> = history.iter().map(|r| r.memory_id.clone()).collect();
+        assert_eq!(
+            restored,
+            vec![results[1].memory.id.clone(), results[2].memory.id.clone()]
+        );
+        assert!(history
+            .iter()
+            .all(|r| r.interaction_type == InteractionType::Query));
+    }
+
+    #[tokio::test]
+    async fn test_positive_feedback_learns_tag_preference() {
+        let temp_file = NamedTempFile::new().unwrap();
+        let config = Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", temp_file.path().display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let db = Arc::new(VectorGraphDB::new(config.clone()).await.unwrap());
+        let mut retriever = HippoRAGRetriever::new(db.clone(), config.clone())
+            .await
+            .unwrap();
+        let manager = crate::memory::MemoryManager::new(db.clone(), config.clone())
+            .await
+            .unwrap();
+
+        // 内容与嵌入相同，只有标签不同，语义分数相等
+        let embedding = retriever
+            .generate_query_embedding("学习编程语言")
+            .await
+            .unwrap();
+        let tagged = |tag: &str| {
+            Memory::new(
+                "学习编程语言".to_string(),
+                MemoryType::Knowledge,
+                embedding.clone(),
+                MemoryAttributes {
+                    tags: vec![tag.to_string()],
+                    ..Default::default()
+                },
+            )
+        };
+        let rust_memory = tagged("rust");
+        let python_memory = tagged("python");
+        manager.create_memory(&rust_memory).await.unwrap();
+        manager.create_memory(&python_memory).await.unwrap();
+
+        let query = Query {
+            text: "学习编程语言".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(5),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let context = RetrievalContext {
+            session_id: None,
+            current_topic: None,
+            recent_queries: Vec::new(),
+            time_window: None,
+            priority: Priority::Normal,
+            constraints: RetrievalConstraints {
+                max_results: Some(5),
+                min_relevance: Some(0.0),
+                required_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                time_range: None,
+                source_filter: None,
+            },
+        };
+        let score_of = |results: &[RetrievalResult], id: &MemoryId| {
+            results
+                .iter()
+                .find(|r| &r.memory.id == id)
+                .map(|r| r.relevance_score)
+                .unwrap()
+        };
+
+        let before = retriever
+            .personalized_retrieval(&retriever.db, &query, &context)
+            .await
+            .unwrap();
+        assert_eq!(
+            score_of(&before, &rust_memory.id),
+            score_of(&before, &python_memory.id)
+        );
+
+        // 低于强化阈值的反馈不会改变偏好
+        retriever
+            .update_from_interaction(&python_memory, 0.2)
+            .await
+            .unwrap();
+        retriever
+            .update_from_interaction(&rust_memory, 0.9)
+            .await
+            .unwrap();
+        let profile = retriever
+            .personalization_manager
+            .get_user_profile()
+            .unwrap();
+        assert!(profile.preferences["rust"] > 0.0);
+        assert!(!profile.preferences.contains_key("python"));
+
+        let after = retriever
+            .personalized_retrieval(&retriever.db, &query, &context)
+            .await
+            .unwrap();
+        assert_eq!(after[0].memory.id, rust_memory.id);
+        assert!(score_of(&after, &rust_memory.id) > score_of(&after, &python_memory.id));
+
+        // 偏好随档案一起持久化
+        let restarted = HippoRAGRetriever::new(db, config).await.unwrap();
+        let restored = restarted
+            .personalization_manager
+            .get_user_profile()
+            .unwrap();
+        assert_eq!(restored.preferences, profile.preferences);
+        assert_eq!(restored.interaction_history.len(), 2);
+    }
+
+    /// 记录每个新建 span 及其父 span 名称
+    #[derive(Clone, Default)]
+    struct SpanCapture(Arc<std::sync::Mutex<Vec<(String, Option<String>)>>>);
+
+    impl<S> tracing_subscriber::Layer<S> for SpanCapture
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            _attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let span = ctx.span(id).expect("span should be registered");
+            let parent = span.parent().map(|parent| parent.name().to_string());
+            self.0
+                .lock()
+                .unwrap()
+                .push((span.name().to_string(), parent));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hipporag_query_emits_span_tree() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let capture = SpanCapture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let mut retriever = create_test_retriever().await;
+        let query = Query {
+            text: "追踪测试".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(5),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let context = RetrievalContext {
+            session_id: None,
+            current_topic: None,
+            recent_queries: Vec::new(),
+            time_window: None,
+            priority: Priority::Normal,
+            constraints: RetrievalConstraints {
+                max_results: Some(5),
+                min_relevance: None,
+                required_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                time_range: None,
+                source_filter: None,
+            },
+        };
+        let strategy = RetrievalStrategy::HippoRAG {
+            use_pagerank: false,
+            use_personalization: false,
+            fusion_method: FusionMethod::LinearWeighted,
+        };
+
+        retriever
+            .retrieve(&query, &context, &strategy)
+            .await
+            .unwrap();
+
+        let spans = capture.0.lock().unwrap().clone();
+        let has_span = |name: &str, parent: Option<&str>| {
+            spans
+                .iter()
+                .any(|(n, p)| n == name && p.as_deref() == parent)
+        };
+
+        assert!(has_span("retrieve_memories", None));
+        assert!(has_span("hippocampus_retrieval", Some("retrieve_memories")));
+        for component in [
+            "semantic_retrieval",
+            "structural_retrieval",
+            "temporal_retrieval",
+        ] {
+            assert!(
+                has_span(component, Some("hippocampus_retrieval")),
+                "missing span {}",
+                component
+            );
+        }
+        assert!(!spans.iter().any(|(n, _)| n == "personalized_retrieval"));
+    }
+
+    #[tokio::test]
+    async fn test_cache_key_is_stable_and_covers_constraints() {
+        let query = Query {
+            text: "人工智能".to_string(),
+            query_type: QueryType::Semantic,
+            filters: QueryFilters::default(),
+            limit: Some(10),
+            offset: None,
+            sort_by: None,
+            weights: QueryWeights::default(),
+        };
+        let context = RetrievalContext {
+            session_id: None,
+            current_topic: None,
+            recent_queries: Vec::new(),
+            time_window: None,
+            priority: Priority::Normal,
+            constraints: RetrievalConstraints {
+                max_results: Some(10),
+                min_relevance: Some(0.5),
+                required_tags: Vec::new(),
+                excluded_tags: Vec::new(),
+                time_range: None,
+                source_filter: None,
+            },
+        };
+        let strategy = RetrievalStrategy::Semantic;
+
+        // 模拟重启：两个独立的检索器实例生成相同的键
+        let key = create_test_retriever()
+            .await
+            .generate_cache_key(&query, &context, &strategy)
+            .unwrap();
+        let retriever = create_test_retriever().await;
+        assert_eq!(
+            retriever
+                .generate_cache_key(&query, &context, &strategy)
+                .unwrap(),
+            key
+        );
+        assert_eq!(key.len(), "query_".len() + 64);
+
+        let mut constrained = context.clone();
+        constrained.constraints.required_tags = vec!["ai".to_string()];
+        assert_ne!(
+            retriever
+                .generate_cache_key(&query, &constrained, &strategy)
+                .unwrap(),
+            key
+        );
+    }
 }
@@ -37,6 +37,12 @@ pub struct ModelConfig {
     pub bnf: Option<BnfConfig>,
     /// 适配器选项 ("auto", "economical", 或数字)
     pub adapter: Option<String>,
+    /// 采样随机种子，作为未设置种子的智能体的默认值
+    ///
+    /// 用于可复现的测试；temperature 为 0 的贪心解码最为确定。
+    pub seed: Option<u64>,
+    /// 同时进行的生成请求数上限，超出的请求排队等待，`None` 表示不限制
+    pub max_concurrent_generations: Option<usize>,
 }
 
 /// LoRA配置
@@ -85,6 +91,8 @@ impl Default for ModelConfig {
             embed_device: None,
             bnf: None,
             adapter: None,
+            seed: None,
+            max_concurrent_generations: None,
         }
     }
 }
@@ -7,27 +7,35 @@
 
 use anyhow::Result;
 use chrono;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 
 use crate::{
-    agent::AgentConfig,
+    agent::{AgentConfig, MemoryConfig},
+    config::{ConfigError, VectorConfig},
     core::{
         error_handler::ErrorHandler,
+        observer::{notify_observers, ChatEvent, ChatObserver},
         rwkv_singleton::{
             get_global_rwkv_service, is_global_rwkv_service_initialized,
             preload_global_rwkv_service_with_config,
         },
         service::RwkvAgentKit as CoreService,
-        tools::{SharedToolRegistry, ToolRegistry},
+        tools::{SharedToolRegistry, ToolError, ToolRegistry},
+        types::ChatResponse,
     },
     db::{
         embedding::{
-            get_global_embedding_service, initialize_global_embedding_service, EmbeddingService,
+            is_global_embedding_service_initialized, set_global_embedding_service, EmbeddingService,
         },
-        DatabaseConfig, DatabaseManager,
+        CheckpointMode, DatabaseConfig, DatabaseManager, MemoryEvent, PersonaDecayStats,
     },
+    entity::{infer_relation_type, EntityClassifier, RuleBasedEntityClassifier},
+    learning::LearningEngine,
     rwkv::config::{BnfConfig, ModelConfig},
 };
 
@@ -52,8 +60,22 @@ pub struct RwkvAgentKit {
     pub agent_configs: HashMap<String, AgentConfig>,
     /// 错误处理器
     pub error_handler: Arc<ErrorHandler>,
+    /// 对话生命周期观察者
+    pub observers: Vec<Arc<dyn ChatObserver>>,
+    /// 学习引擎（可选）
+    pub learning_engine: Option<Arc<LearningEngine>>,
+    /// 知识图谱更新使用的实体分类器（默认基于规则）
+    pub entity_classifier: Arc<dyn EntityClassifier>,
+    /// 学习调度器任务
+    learning_scheduler: Option<JoinHandle<()>>,
 }
 
+/// 关闭时等待刷新完成的默认时长，超时后强制关闭
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 自动生成的会话标题最大字符数
+const MAX_SESSION_TITLE_CHARS: usize = 32;
+
 /// RWKV Agent Kit 配置
 #[derive(Debug, Clone)]
 #[derive(Default)]
@@ -64,6 +86,139 @@ pub struct RwkvAgentKitConfig {
     pub database: DatabaseConfig,
     /// 智能体配置列表
     pub agents: Vec<AgentConfig>,
+    /// 路由结果无法识别时使用的智能体（默认 `chat`）
+    pub default_route_agent: Option<String>,
+    /// 结束会话时是否用对话总结智能体自动生成会话标题
+    pub auto_title_sessions: bool,
+    /// 流式对话通道的缓冲容量（`None` 使用默认值）
+    pub stream_buffer: Option<usize>,
+    /// 向量与嵌入配置，用于初始化全局嵌入服务
+    pub vector: VectorConfig,
+}
+
+impl RwkvAgentKitConfig {
+    /// 验证配置
+    ///
+    /// 检查模型路径、精度、数据库参数与智能体配置，一次性返回所有错误。
+    pub fn validate(&self) -> std::result::Result<(), Vec<ConfigError>> {
+        let mut errors = Vec::new();
+
+        if self.model.model_path.trim().is_empty() {
+            errors.push(ConfigError::new(
+                "model.model_path",
+                format!("{:?}", self.model.model_path),
+                "model path cannot be empty",
+            ));
+        }
+        if self.model.tokenizer_path.trim().is_empty() {
+            errors.push(ConfigError::new(
+                "model.tokenizer_path",
+                format!("{:?}", self.model.tokenizer_path),
+                "tokenizer path cannot be empty",
+            ));
+        }
+        if !matches!(self.model.precision.to_lowercase().as_str(), "fp16" | "fp32") {
+            errors.push(ConfigError::new(
+                "model.precision",
+                format!("{:?}", self.model.precision),
+                "must be \"fp16\" or \"fp32\"",
+            ));
+        }
+        if let Some(quant_type) = &self.model.quant_type {
+            if !matches!(
+                quant_type.to_lowercase().as_str(),
+                "none" | "int8" | "nf4" | "sf4"
+            ) {
+                errors.push(ConfigError::new(
+                    "model.quant_type",
+                    format!("{:?}", quant_type),
+                    "must be one of \"none\", \"int8\", \"nf4\", \"sf4\"",
+                ));
+            }
+        }
+        if self.model.token_chunk_size == Some(0) {
+            errors.push(ConfigError::new(
+                "model.token_chunk_size",
+                0,
+                "must be greater than 0",
+            ));
+        }
+        if self.model.max_batch == Some(0) {
+            errors.push(ConfigError::new(
+                "model.max_batch",
+                0,
+                "must be greater than 0",
+            ));
+        }
+        if self.model.max_concurrent_generations == Some(0) {
+            errors.push(ConfigError::new(
+                "model.max_concurrent_generations",
+                0,
+                "must be greater than 0",
+            ));
+        }
+        if self.stream_buffer == Some(0) {
+            errors.push(ConfigError::new(
+                "stream_buffer",
+                0,
+                "must be greater than 0",
+            ));
+        }
+
+        if self.database.db_path.as_os_str().is_empty() {
+            errors.push(ConfigError::new(
+                "database.db_path",
+                "\"\"",
+                "database path cannot be empty",
+            ));
+        }
+        if self.database.max_connections == 0 {
+            errors.push(ConfigError::new(
+                "database.max_connections",
+                0,
+                "must be greater than 0",
+            ));
+        }
+        if let Some(namespace) = &self.database.namespace {
+            if !crate::config::is_valid_namespace(namespace) {
+                errors.push(ConfigError::new(
+                    "database.namespace",
+                    format!("{:?}", namespace),
+                    "must be non-empty and contain only ASCII letters, digits or '_'",
+                ));
+            }
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for (index, agent) in self.agents.iter().enumerate() {
+            if agent.name.trim().is_empty() {
+                errors.push(ConfigError::new(
+                    format!("agents[{}].name", index),
+                    format!("{:?}", agent.name),
+                    "agent name cannot be empty",
+                ));
+            } else if !seen.insert(agent.name.as_str()) {
+                errors.push(ConfigError::new(
+                    format!("agents[{}].name", index),
+                    format!("{:?}", agent.name),
+                    "duplicate agent name",
+                ));
+            }
+            if let Err(e) = agent.inference_params.validate() {
+                errors.push(ConfigError::new(
+                    format!("agents[{}].inference_params", index),
+                    format!("{:?}", agent.name),
+                    e.to_string(),
+                ));
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
 }
 
 
@@ -92,8 +247,7 @@ impl RwkvAgentKit {
         // 2.5 初始化嵌入服务（仅在至少有一个 agent 开启记忆时）
         let memory_enabled = config.agents.iter().any(|a| a.memory.enabled);
         if memory_enabled {
-            // 目前使用默认路径，可扩展为从配置传入
-            if let Err(e) = initialize_global_embedding_service(None).await {
+            if let Err(e) = initialize_embedding_service(&config.vector).await {
                 eprintln!("⚠️ 嵌入服务初始化失败，将退回词重叠检索: {}", e);
             } else {
                 println!("✅ 嵌入服务已初始化");
@@ -105,9 +259,23 @@ impl RwkvAgentKit {
         if !is_global_rwkv_service_initialized() {
             preload_global_rwkv_service_with_config(config.model.clone()).await?;
         }
-        let core_service_arc = get_global_rwkv_service()?;
+        let core_service = get_global_rwkv_service()?;
+        println!("✅ 全局 RWKV 服务初始化完成");
+
+        let kit = Self::with_core_service(core_service, database_manager, config).await?;
+        println!("🎉 RWKV Agent Kit 启动完成！");
+        Ok(kit)
+    }
 
-        // 注册智能体到全局服务
+    /// 使用已有的核心服务与数据库管理器组装实例
+    ///
+    /// 配置中的智能体注册到 `core_service`，不初始化嵌入服务与全局 RWKV 服务。
+    pub(crate) async fn with_core_service(
+        core_service_arc: Arc<RwLock<CoreService>>,
+        database_manager: DatabaseManager,
+        config: RwkvAgentKitConfig,
+    ) -> Result<Self> {
+        // 注册智能体到核心服务
         {
             let mut core_service_write = core_service_arc.write().await;
             for agent_config in &config.agents {
@@ -115,11 +283,13 @@ impl RwkvAgentKit {
             }
             // 注入数据库管理器，供核心服务进行记忆检索
             core_service_write.set_database_manager(Arc::new(database_manager.clone()));
+            if let Some(stream_buffer) = config.stream_buffer {
+                core_service_write.config.stream_buffer = stream_buffer;
+            }
         }
 
         // 获取核心服务的克隆用于存储
         let core_service = core_service_arc.clone();
-        println!("✅ 全局 RWKV 服务初始化完成");
 
         // 7. 初始化工具注册表（共享）
         let tools: SharedToolRegistry = Arc::new(tokio::sync::RwLock::new(ToolRegistry::default()));
@@ -130,8 +300,6 @@ impl RwkvAgentKit {
             core_service_write.set_tools(tools.clone());
         }
 
-        println!("🎉 RWKV Agent Kit 启动完成！");
-
         // 8. 初始化智能体配置映射
         let mut agent_configs = HashMap::new();
         for agent_config in &config.agents {
@@ -179,6 +347,10 @@ impl RwkvAgentKit {
             config,
             agent_configs,
             error_handler,
+            observers: Vec::new(),
+            learning_engine: None,
+            entity_classifier: Arc::new(RuleBasedEntityClassifier),
+            learning_scheduler: None,
         })
     }
 
@@ -197,9 +369,167 @@ impl RwkvAgentKit {
         Ok(())
     }
 
+    /// 添加对话生命周期观察者
+    ///
+    /// 观察者同时注册到核心服务，以便接收提示词构建事件。
+    pub async fn add_observer(&mut self, observer: Arc<dyn ChatObserver>) {
+        self.core_service
+            .write()
+            .await
+            .add_observer(observer.clone());
+        self.observers.push(observer);
+    }
+
+    /// 获取基于智能体的记忆属性提取器
+    ///
+    /// 未注册 `attribute_extractor` 智能体时会先注册，返回值可传给 `MemoryManager::set_attribute_extractor`。
+    pub async fn attribute_extractor(
+        &mut self,
+    ) -> Result<Arc<crate::agents::AgentAttributeExtractor>> {
+        use crate::agents::attribute_extractor::{
+            create_attribute_extractor_config, ATTRIBUTE_EXTRACTOR_AGENT,
+        };
+
+        if !self.agent_configs.contains_key(ATTRIBUTE_EXTRACTOR_AGENT) {
+            self.register_agent(create_attribute_extractor_config())
+                .await?;
+        }
+
+        Ok(Arc::new(crate::agents::AgentAttributeExtractor::new(
+            self.core_service.clone(),
+            ATTRIBUTE_EXTRACTOR_AGENT,
+        )))
+    }
+
+    /// 设置知识图谱更新使用的实体分类器
+    pub fn set_entity_classifier(&mut self, classifier: Arc<dyn EntityClassifier>) {
+        self.entity_classifier = classifier;
+    }
+
+    /// 改用基于智能体的实体分类器
+    ///
+    /// 未注册 `entity_classifier` 智能体时会先注册；分类更准确，但每次图谱更新多一次推理。
+    pub async fn use_llm_entity_classifier(&mut self) -> Result<()> {
+        use crate::agents::entity_classifier::{
+            create_entity_classifier_config, LlmEntityClassifier, ENTITY_CLASSIFIER_AGENT,
+        };
+
+        if !self.agent_configs.contains_key(ENTITY_CLASSIFIER_AGENT) {
+            self.register_agent(create_entity_classifier_config())
+                .await?;
+        }
+
+        self.entity_classifier = Arc::new(LlmEntityClassifier::from_agent(
+            self.core_service.clone(),
+            ENTITY_CLASSIFIER_AGENT,
+        ));
+        Ok(())
+    }
+
     /// 与指定智能体进行对话
+    ///
+    /// 对话写入当前活跃会话（没有时自动打开一个）；并发对话请使用 [`RwkvAgentKit::chat_in_session`]。
     pub async fn chat(&mut self, agent_name: &str, user_input: &str) -> Result<String> {
-        self.chat_with_memory(agent_name, user_input, true).await
+        Ok(self.chat_with_usage(agent_name, user_input).await?.text)
+    }
+
+    /// 与指定智能体进行对话，并返回本轮的 token 用量与耗时
+    pub async fn chat_with_usage(
+        &mut self,
+        agent_name: &str,
+        user_input: &str,
+    ) -> Result<ChatResponse> {
+        self.chat_with_memory(agent_name, user_input, true, None)
+            .await
+    }
+
+    /// 按智能体的记忆配置衰减画像特征，删除置信度过低的特征
+    pub async fn decay_persona_traits(&self, agent_name: &str) -> Result<PersonaDecayStats> {
+        let memory_config = Self::agent_memory_config(&self.agent_configs, agent_name);
+        self.database_manager
+            .decay_persona_traits(
+                agent_name,
+                memory_config.persona_decay_half_life_days,
+                memory_config.persona_min_confidence,
+                chrono::Utc::now(),
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to decay persona traits: {}", e))
+    }
+
+    /// 新建显式会话，不影响当前活跃会话
+    pub async fn create_session(&self, agent_name: &str, title: Option<&str>) -> Result<i64> {
+        self.database_manager
+            .create_session(agent_name, title)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to create session: {}", e))
+    }
+
+    /// 与指定智能体进行对话，并将轮次写入指定会话
+    pub async fn chat_in_session(
+        &mut self,
+        agent_name: &str,
+        user_input: &str,
+        session_id: i64,
+    ) -> Result<String> {
+        Ok(self
+            .chat_with_memory(agent_name, user_input, true, Some(session_id))
+            .await?
+            .text)
+    }
+
+    /// 使用路由智能体判断输入应交给哪个智能体
+    ///
+    /// 回复受 KBNF schema 约束为 [`RouterDecision`] 的 JSON；模型给出未注册的智能体时
+    /// 返回 `default_route_agent`（默认 `chat`），置信度为 0。
+    pub async fn route(&mut self, user_input: &str) -> Result<crate::agents::RouterDecision> {
+        use crate::agent::prompt::PromptBuilderInstance;
+        use crate::agents::{create_router_config, RouterDecision, RouterPromptBuilder};
+
+        let mut available_agents: Vec<String> = self
+            .agent_configs
+            .keys()
+            .filter(|name| name.as_str() != "router")
+            .cloned()
+            .collect();
+        available_agents.sort();
+        let default_agent = self
+            .config
+            .default_route_agent
+            .clone()
+            .unwrap_or_else(|| "chat".to_string());
+
+        // 临时以决策格式注册路由智能体，结束后恢复原配置
+        let original_config = self.agent_configs.get("router").cloned();
+        let mut decision_config = original_config.clone().unwrap_or_else(create_router_config);
+        decision_config.prompt_builder = Some(Arc::new(PromptBuilderInstance::Router(
+            RouterPromptBuilder::for_decision(available_agents.clone()),
+        )));
+        {
+            let mut core_service = self.core_service.write().await;
+            core_service.register_agent(decision_config)?;
+        }
+
+        let reply = self
+            .chat_no_memory_with_options(
+                "router",
+                user_input,
+                Some(RouterDecision::schema(&available_agents)),
+                None,
+            )
+            .await;
+
+        {
+            let mut core_service = self.core_service.write().await;
+            match original_config {
+                Some(config) => core_service.register_agent(config)?,
+                None => {
+                    core_service.agents.remove("router");
+                }
+            }
+        }
+
+        RouterDecision::parse(&reply?, &available_agents, &default_agent)
     }
 
     /// 与 chat 智能体进行对话，并指定昵称
@@ -231,7 +561,10 @@ impl RwkvAgentKit {
         }
 
         // 进行对话
-        let result = self.chat_with_memory("chat", user_input, true).await;
+        let result = self
+            .chat_with_memory("chat", user_input, true, None)
+            .await
+            .map(|response| response.text);
 
         // 恢复原始配置
         {
@@ -243,14 +576,14 @@ impl RwkvAgentKit {
     }
 
     /// 与指定智能体进行对话（不存储记忆，用于内部处理）
-    pub async fn chat_no_memory(&mut self, agent_name: &str, user_input: &str) -> Result<String> {
+    pub async fn chat_no_memory(&self, agent_name: &str, user_input: &str) -> Result<String> {
         self.chat_no_memory_with_options(agent_name, user_input, None, None)
             .await
     }
 
     /// 与指定智能体进行对话（不存储记忆，用于内部处理，可选 bnf_schema 和 stop）
     pub async fn chat_no_memory_with_options(
-        &mut self,
+        &self,
         agent_name: &str,
         user_input: &str,
         bnf_schema: Option<String>,
@@ -269,15 +602,84 @@ impl RwkvAgentKit {
         Ok(response)
     }
 
+    /// 与指定智能体进行流式对话（不存储到数据库），逐段返回生成内容
+    pub async fn chat_stream(
+        &self,
+        agent_name: &str,
+        user_input: &str,
+    ) -> Result<flume::Receiver<String>> {
+        let core_service = self.core_service.read().await;
+        let receiver = core_service
+            .chat_stream(agent_name, user_input, None, None)
+            .await?;
+        Ok(receiver)
+    }
+
+    /// 与指定智能体进行流式对话，生成结束后将完整轮次写入指定会话
+    ///
+    /// 只需要共享引用，多个会话的流式对话可以同时进行而不会互相写入对方的会话。
+    /// 通道与核心服务使用相同的缓冲容量，消费方过慢时生成随之放缓；丢弃接收端会中止生成，
+    /// 已生成的部分仍写入会话。
+    pub async fn chat_stream_in_session(
+        &self,
+        agent_name: &str,
+        user_input: &str,
+        session_id: i64,
+    ) -> Result<flume::Receiver<String>> {
+        let upstream = self.chat_stream(agent_name, user_input).await?;
+        let should_save = self
+            .agent_configs
+            .get(agent_name)
+            .map(|config| config.save_conversations)
+            .unwrap_or(true);
+
+        let stream_buffer = self.core_service.read().await.config.stream_buffer;
+        let (sender, receiver) = flume::bounded(stream_buffer.max(1));
+        let database_manager = self.database_manager.clone();
+        let observers = self.observers.clone();
+        let agent_name = agent_name.to_string();
+        let user_input = user_input.to_string();
+        tokio::spawn(async move {
+            let response = relay_stream(upstream, sender).await;
+
+            if should_save && !response.trim().is_empty() {
+                let turn_event_ids = Self::record_turn(
+                    &database_manager,
+                    session_id,
+                    &agent_name,
+                    &user_input,
+                    response.trim(),
+                )
+                .await;
+                if turn_event_ids.is_some() {
+                    notify_observers(
+                        &observers,
+                        ChatEvent::MemorySaved {
+                            session_id,
+                            agent_name,
+                            user_input,
+                            response: response.trim().to_string(),
+                        },
+                    );
+                }
+            }
+        });
+
+        Ok(receiver)
+    }
+
     /// 与指定智能体进行对话的内部实现
+    ///
+    /// `session_id` 为 `None` 时使用当前活跃会话（没有时自动打开一个）。
     async fn chat_with_memory(
         &mut self,
         agent_name: &str,
         user_input: &str,
         store_memory: bool,
-    ) -> Result<String> {
+        session_id: Option<i64>,
+    ) -> Result<ChatResponse> {
         // 使用核心服务进行对话
-        let response = {
+        let mut usage = {
             let core_service = self.core_service.read().await;
 
             // 调试：显示发送给LLM的完整提示词 - 已禁用
@@ -302,8 +704,20 @@ impl RwkvAgentKit {
             //     }
             // }
 
-            core_service.chat(agent_name, user_input).await?
+            core_service.chat_with_usage(agent_name, user_input).await?
         };
+        let response = std::mem::take(&mut usage.text);
+        notify_observers(
+            &self.observers,
+            ChatEvent::Response {
+                agent_name: agent_name.to_string(),
+                user_input: user_input.to_string(),
+                response: ChatResponse {
+                    text: response.trim().to_string(),
+                    ..usage.clone()
+                },
+            },
+        );
 
         // 调试：显示AI的完整回复内容
         println!("\n🤖 ===== AI完整回复 =====");
@@ -322,123 +736,178 @@ impl RwkvAgentKit {
             // 检查回复是否为空，如果为空则不保存
             if response.trim().is_empty() {
                 println!("⚠️ AI回复为空，跳过保存到数据库");
-                return Ok(response);
+                usage.text = response;
+                return Ok(usage);
             }
 
-            // 获取或创建活跃会话
-            let session_id = match self.database_manager.get_active_session().await {
-                Ok(Some(id)) => id,
-                _ => {
-                    // 打开一个新的会话，标题可设为时间戳或agent_name
-                    let title = format!(
-                        "{} 会话 @ {}",
-                        agent_name,
-                        chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-                    );
-                    match self
-                        .database_manager
-                        .open_session(agent_name, Some(&title))
-                        .await
-                    {
-                        Ok(id) => id,
-                        Err(e) => {
-                            eprintln!("⚠️ 打开会话失败: {}", e);
-                            -1
+            // 使用指定会话，否则获取或创建活跃会话
+            let session_id = match session_id {
+                Some(id) => id,
+                None => match self.database_manager.get_active_session().await {
+                    Ok(Some(id)) => id,
+                    _ => {
+                        // 打开一个新的会话，标题可设为时间戳或agent_name
+                        let title = format!(
+                            "{} 会话 @ {}",
+                            agent_name,
+                            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+                        );
+                        match self
+                            .database_manager
+                            .open_session(agent_name, Some(&title))
+                            .await
+                        {
+                            Ok(id) => id,
+                            Err(e) => {
+                                eprintln!("⚠️ 打开会话失败: {}", e);
+                                -1
+                            }
                         }
                     }
-                }
+                },
             };
 
             if session_id >= 0 {
-                use crate::db::MemoryEvent;
-                // 可用的嵌入服务（如果初始化成功）
-                let embedding_service = get_global_embedding_service().ok();
-
-                // 先写入用户消息
-                let mut user_event = MemoryEvent {
-                    session_id,
-                    agent_name: agent_name.to_string(),
-                    role: "user".to_string(),
-                    text: user_input.to_string(),
-                    topic: None,
-                    sentiment: None,
-                    importance: None,
-                    decay: 1.0,
-                    embedding: None,
-                };
-                if let Some(svc) = embedding_service.as_ref() {
-                    if let Ok(emb) = svc.lock().await.encode_single(user_input).await {
-                        if let Ok(bytes) = EmbeddingService::serialize_embedding(&emb) {
-                            user_event.embedding = Some(bytes);
-                        }
-                    }
-                }
-                // 记录用户事件ID，供画像提取来源引用
-                #[allow(unused_variables)] // 预留功能，后续启用
-                let mut _last_user_event_id: Option<i64> = None;
-                #[allow(unused_assignments)] // 预留功能，后续启用
-                match self.database_manager.insert_memory_event(user_event).await {
-                    Ok(id) => {
-                        _last_user_event_id = Some(id);
-                    }
-                    Err(e) => {
-                        eprintln!("⚠️ 保存用户消息失败: {}", e);
-                    }
-                }
-
-                // 再写入助手回复
-                let mut assistant_event = MemoryEvent {
+                let turn_event_ids = Self::record_turn(
+                    &self.database_manager,
                     session_id,
-                    agent_name: agent_name.to_string(),
-                    role: "assistant".to_string(),
-                    text: response.clone(),
-                    topic: None,
-                    sentiment: None,
-                    importance: None,
-                    decay: 1.0,
-                    embedding: None,
-                };
-                if let Some(svc) = embedding_service.as_ref() {
-                    if let Ok(emb) = svc.lock().await.encode_single(&response).await {
-                        if let Ok(bytes) = EmbeddingService::serialize_embedding(&emb) {
-                            assistant_event.embedding = Some(bytes);
-                        }
-                    }
-                }
-                if let Err(e) = self
-                    .database_manager
-                    .insert_memory_event(assistant_event)
-                    .await
-                {
-                    eprintln!("⚠️ 保存助手回复失败: {}", e);
-                } else {
-                    println!("💾 对话轮次已保存到 memory_events");
+                    agent_name,
+                    user_input,
+                    &response,
+                )
+                .await;
+                if turn_event_ids.is_some() {
+                    notify_observers(
+                        &self.observers,
+                        ChatEvent::MemorySaved {
+                            session_id,
+                            agent_name: agent_name.to_string(),
+                            user_input: user_input.to_string(),
+                            response: response.clone(),
+                        },
+                    );
                 }
 
                 // === 阶段3：语义片段聚合 ===
-                // 在成功保存事件后，检查是否需要创建语义片段
-                // 暂时禁用语义片段功能
-                // if let Err(e) = self.trigger_semantic_aggregation(session_id, user_input, &response).await {
-                //     eprintln!("⚠️ 语义聚合失败: {}", e);
+                // 启用批量聚合时，累计足够轮次后在后台合并总结
+                let memory_config = Self::agent_memory_config(&self.agent_configs, agent_name);
+                if turn_event_ids.is_some() && memory_config.semantic_batch_turns > 0 {
+                    self.spawn_semantic_aggregation(session_id, memory_config);
+                }
+                // 逐轮聚合暂时禁用
+                // if let Some((user_event_id, assistant_event_id)) = turn_event_ids {
+                //     if let Err(e) = self.trigger_semantic_aggregation(session_id, agent_name, user_input, &response, &[user_event_id, assistant_event_id]).await {
+                //         eprintln!("⚠️ 语义聚合失败: {}", e);
+                //     }
                 // }
 
                 // 画像提取功能已移除
             }
         }
 
-        Ok(response
+        usage.text = response
             .trim_start_matches(|c: char| c.is_whitespace())
             .trim_end_matches(|c: char| c.is_whitespace())
-            .to_string())
+            .to_string();
+        Ok(usage)
+    }
+
+    /// 将一轮对话（用户消息与助手回复）写入指定会话的 memory_events
+    ///
+    /// 两条消息都写入成功时返回它们的事件ID `(用户消息, 助手回复)`，失败只记录日志。
+    async fn record_turn(
+        database_manager: &DatabaseManager,
+        session_id: i64,
+        agent_name: &str,
+        user_input: &str,
+        response: &str,
+    ) -> Option<(i64, i64)> {
+        // 可用的嵌入服务（如果初始化成功）
+        let embedding_service = database_manager.embedding_service();
+
+        // 先写入用户消息
+        let mut user_event = MemoryEvent {
+            session_id,
+            agent_name: agent_name.to_string(),
+            role: "user".to_string(),
+            text: user_input.to_string(),
+            topic: None,
+            sentiment: None,
+            importance: None,
+            decay: 1.0,
+            embedding: None,
+        };
+        if let Some(svc) = embedding_service.as_ref() {
+            if let Ok(emb) = svc.lock().await.encode_single(user_input).await {
+                if let Ok(bytes) = EmbeddingService::serialize_embedding(&emb) {
+                    user_event.embedding = Some(bytes);
+                }
+            }
+        }
+        let user_event_id = match database_manager.insert_memory_event(user_event).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                eprintln!("⚠️ 保存用户消息失败: {}", e);
+                None
+            }
+        };
+
+        // 再写入助手回复
+        let mut assistant_event = MemoryEvent {
+            session_id,
+            agent_name: agent_name.to_string(),
+            role: "assistant".to_string(),
+            text: response.to_string(),
+            topic: None,
+            sentiment: None,
+            importance: None,
+            decay: 1.0,
+            embedding: None,
+        };
+        if let Some(svc) = embedding_service.as_ref() {
+            if let Ok(emb) = svc.lock().await.encode_single(response).await {
+                if let Ok(bytes) = EmbeddingService::serialize_embedding(&emb) {
+                    assistant_event.embedding = Some(bytes);
+                }
+            }
+        }
+        match database_manager.insert_memory_event(assistant_event).await {
+            Ok(assistant_event_id) => {
+                println!("💾 对话轮次已保存到 memory_events");
+                user_event_id.map(|user_event_id| (user_event_id, assistant_event_id))
+            }
+            Err(e) => {
+                eprintln!("⚠️ 保存助手回复失败: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 获取指定智能体的记忆策略
+    ///
+    /// 未注册的智能体使用 [`MemoryConfig::default`]（阈值 5、不更新知识图谱）。
+    fn agent_memory_config(
+        agent_configs: &HashMap<String, AgentConfig>,
+        agent_name: &str,
+    ) -> MemoryConfig {
+        agent_configs
+            .get(agent_name)
+            .map(|config| config.memory.clone())
+            .unwrap_or_default()
     }
 
     /// 触发语义聚合：使用 ConversationSummarizer 分析当前对话轮次
+    ///
+    /// 阈值与图谱更新策略取自产生该轮对话的智能体 `agent_name` 的记忆配置；
+    /// `memory_event_ids` 为该轮对话写入的事件ID，会记录到语义片段映射中。
     #[allow(dead_code)] // 预留功能，后续启用
     async fn trigger_semantic_aggregation(
         &mut self,
         session_id: i64,
+        agent_name: &str,
         user_input: &str,
         assistant_response: &str,
+        memory_event_ids: &[i64],
     ) -> Result<()> {
         // 1. 构建对话历史（当前轮次）
         let conversation_history =
@@ -457,7 +926,7 @@ impl RwkvAgentKit {
         };
 
         // 3. 解析 JSON 响应
-        let parsed_summary = match self.parse_summarizer_response(&summarizer_result) {
+        let parsed_summary = match Self::parse_summarizer_response(&summarizer_result) {
             Ok(summary) => summary,
             Err(e) => {
                 eprintln!("⚠️ ConversationSummarizer 响应解析失败: {}", e);
@@ -466,16 +935,11 @@ impl RwkvAgentKit {
             }
         };
 
-        // 4. 获取配置化阈值（从任一智能体的记忆配置中获取，默认为5）
-        let semantic_threshold = self
-            .agent_configs
-            .values()
-            .find(|config| config.memory.enabled)
-            .map(|config| config.memory.semantic_chunk_threshold)
-            .unwrap_or(5);
+        // 4. 获取当前智能体的记忆策略
+        let memory_config = Self::agent_memory_config(&self.agent_configs, agent_name);
 
         // 检查重要性阈值（importance_score >= threshold 才创建语义片段）
-        if parsed_summary.importance_score < semantic_threshold {
+        if !parsed_summary.meets_threshold(&memory_config) {
             println!(
                 "📊 对话重要性较低 ({}), 跳过语义片段创建",
                 parsed_summary.importance_score
@@ -484,29 +948,81 @@ impl RwkvAgentKit {
         }
 
         // 5. 创建语义片段
-        use crate::db::SemanticChunk;
-        let embedding_service = get_global_embedding_service().ok();
+        match Self::store_semantic_chunk(
+            &self.database_manager,
+            &self.observers,
+            session_id,
+            &parsed_summary,
+            memory_event_ids,
+        )
+        .await
+        {
+            Ok(_) => {
+                // 6. 提取实体（memory_triggers）但暂不更新图谱：按阶段目标仅实现工作记忆与语义记忆
+                if !parsed_summary.memory_triggers.is_empty() {
+                    // 根据配置决定是否更新知识图谱
+                    if memory_config.enable_graph_updates {
+                        if let Err(e) = Self::update_knowledge_graph(
+                            &self.database_manager,
+                            self.entity_classifier.as_ref(),
+                            &memory_config,
+                            &parsed_summary.memory_triggers,
+                            (parsed_summary.importance_score as f32) / 10.0,
+                        )
+                        .await
+                        {
+                            eprintln!("⚠️ 知识图谱更新失败: {}", e);
+                        }
+                    } else {
+                        // 知识图谱更新已禁用（情景/过程记忆暂不实现）
+                        log::debug!(
+                            "Skip knowledge graph update. Triggers = {:?}, importance = {}",
+                            parsed_summary.memory_triggers,
+                            parsed_summary.importance_score
+                        );
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("⚠️ 语义片段保存失败: {}", e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 保存语义片段（附带嵌入向量）及其到来源事件的映射，返回片段ID
+    ///
+    /// 映射写入失败只记录日志，不影响已创建的片段。
+    async fn store_semantic_chunk(
+        database_manager: &DatabaseManager,
+        observers: &[Arc<dyn ChatObserver>],
+        session_id: i64,
+        summary: &ConversationSummary,
+        memory_event_ids: &[i64],
+    ) -> Result<i64> {
+        use crate::db::{SemanticChunk, SemanticChunkMapping};
 
         let mut semantic_chunk = SemanticChunk {
             id: None,
-            title: if parsed_summary.user_intent_summary.is_empty() {
+            title: if summary.user_intent_summary.is_empty() {
                 None
             } else {
-                Some(parsed_summary.user_intent_summary.clone())
+                Some(summary.user_intent_summary.clone())
             },
-            summary: parsed_summary.summary.clone(),
-            keywords: if parsed_summary.memory_triggers.is_empty() {
+            summary: summary.summary.clone(),
+            keywords: if summary.memory_triggers.is_empty() {
                 None
             } else {
-                Some(parsed_summary.memory_triggers.join(", "))
+                Some(summary.memory_triggers.join(", "))
             },
             embedding: None,
             last_ref_ts: None, // 将由数据库自动设置为当前时间
-            weight: (parsed_summary.importance_score as f32) / 10.0, // 归一化到 0.0-1.0
+            weight: (summary.importance_score as f32) / 10.0, // 归一化到 0.0-1.0
         };
 
-        // 6. 为语义片段生成嵌入向量
-        if let Some(svc) = embedding_service.as_ref() {
+        // 为语义片段生成嵌入向量
+        if let Some(svc) = database_manager.embedding_service().as_ref() {
             if let Ok(emb) = svc
                 .lock()
                 .await
@@ -519,113 +1035,270 @@ impl RwkvAgentKit {
             }
         }
 
-        // 7. 保存语义片段到数据库
-        match self
-            .database_manager
-            .insert_semantic_chunk(semantic_chunk)
+        let chunk_id = database_manager
+            .insert_semantic_chunk(semantic_chunk.clone())
+            .await?;
+        println!(
+            "✨ 语义片段已创建 (ID: {}, 重要性: {})",
+            chunk_id, summary.importance_score
+        );
+        semantic_chunk.id = Some(chunk_id);
+        notify_observers(
+            observers,
+            ChatEvent::SemanticChunk {
+                session_id,
+                chunk_id,
+                chunk: semantic_chunk,
+            },
+        );
+
+        let mapping = SemanticChunkMapping {
+            id: None,
+            chunk_id,
+            session_id,
+            memory_event_ids: serde_json::to_string(memory_event_ids)?,
+            created_ts: None, // 由数据库自动设置
+        };
+        if let Err(e) = database_manager
+            .insert_semantic_chunk_mapping(mapping)
             .await
         {
-            Ok(chunk_id) => {
-                println!(
-                    "✨ 语义片段已创建 (ID: {}, 重要性: {})",
-                    chunk_id, parsed_summary.importance_score
-                );
-
-                // 7.5. 创建语义片段映射表记录
-                use crate::db::SemanticChunkMapping;
-                let mapping = SemanticChunkMapping {
-                    id: None,
-                    chunk_id,
-                    session_id,
-                    memory_event_ids: "[]".to_string(), // 可扩展为实际记录相关事件ID
-                    created_ts: None,                   // 由数据库自动设置
-                };
+            eprintln!("⚠️ 语义片段映射创建失败: {}", e);
+        }
 
-                if let Err(e) = self
-                    .database_manager
-                    .insert_semantic_chunk_mapping(mapping)
-                    .await
-                {
-                    eprintln!("⚠️ 语义片段映射创建失败: {}", e);
-                }
+        Ok(chunk_id)
+    }
 
-                // 8. 提取实体（memory_triggers）但暂不更新图谱：按阶段目标仅实现工作记忆与语义记忆
-                if !parsed_summary.memory_triggers.is_empty() {
-                    // 根据配置决定是否更新知识图谱
-                    let enable_graph_updates = self
-                        .agent_configs
-                        .values()
-                        .find(|config| config.memory.enabled)
-                        .map(|config| config.memory.enable_graph_updates)
-                        .unwrap_or(false);
-
-                    if enable_graph_updates {
-                        if let Err(e) = self
-                            .update_knowledge_graph(
-                                &parsed_summary.memory_triggers,
-                                (parsed_summary.importance_score as f32) / 10.0,
-                            )
-                            .await
-                        {
-                            eprintln!("⚠️ 知识图谱更新失败: {}", e);
-                        }
-                    } else {
-                        // 知识图谱更新已禁用（情景/过程记忆暂不实现）
-                        log::debug!(
-                            "Skip knowledge graph update. Triggers = {:?}, importance = {}",
-                            parsed_summary.memory_triggers,
-                            parsed_summary.importance_score
-                        );
-                    }
-                }
+    /// 在后台任务中对会话执行批量语义聚合，不阻塞当前对话
+    fn spawn_semantic_aggregation(&self, session_id: i64, memory_config: MemoryConfig) {
+        let core_service = self.core_service.clone();
+        let database_manager = self.database_manager.clone();
+        let observers = self.observers.clone();
+        let entity_classifier = self.entity_classifier.clone();
+        tokio::spawn(async move {
+            let summarize = |conversation_history: String| async move {
+                let core_service = core_service.read().await;
+                Ok(core_service
+                    .chat_with_options("conversation_summarizer", &conversation_history, None, None)
+                    .await?)
+            };
+            if let Err(e) = Self::aggregate_session_batch(
+                &database_manager,
+                &observers,
+                entity_classifier.as_ref(),
+                session_id,
+                &memory_config,
+                summarize,
+            )
+            .await
+            {
+                eprintln!("⚠️ 批量语义聚合失败: {}", e);
             }
-            Err(e) => {
-                eprintln!("⚠️ 语义片段保存失败: {}", e);
+        });
+    }
+
+    /// 批量语义聚合：会话中未聚合的轮次数达到 `semantic_batch_turns` 的整数倍时，
+    /// 把这些轮次合并交给 `summarize` 总结为一个语义片段，并在映射中记录来源事件ID
+    ///
+    /// 重要性未达到阈值时不创建片段，这些轮次会并入下一批一起总结；启用 `enable_graph_updates`
+    /// 时再用 `entity_classifier` 把片段的触发词写入知识图谱。返回新建片段的ID。
+    async fn aggregate_session_batch<F, Fut>(
+        database_manager: &DatabaseManager,
+        observers: &[Arc<dyn ChatObserver>],
+        entity_classifier: &dyn EntityClassifier,
+        session_id: i64,
+        memory_config: &MemoryConfig,
+        summarize: F,
+    ) -> Result<Option<i64>>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: std::future::Future<Output = Result<String>>,
+    {
+        let batch_turns = memory_config.semantic_batch_turns;
+        if batch_turns == 0 {
+            return Ok(None);
+        }
+
+        let pending = database_manager
+            .list_unsummarized_memory_events(session_id)
+            .await?;
+        // 每轮对话以一条用户消息开始
+        let turns = pending
+            .iter()
+            .filter(|(_, event)| event.role == "user")
+            .count();
+        if turns == 0 || turns % batch_turns != 0 {
+            return Ok(None);
+        }
+
+        let conversation_history =
+            Self::format_conversation_history(pending.iter().map(|(_, event)| event));
+        let response = summarize(conversation_history).await?;
+        let summary = Self::parse_summarizer_response(&response)?;
+        if !summary.meets_threshold(memory_config) {
+            println!(
+                "📊 会话 {} 的 {} 轮对话重要性较低 ({}), 并入下一批聚合",
+                session_id, turns, summary.importance_score
+            );
+            return Ok(None);
+        }
+
+        let event_ids: Vec<i64> = pending.iter().map(|(id, _)| *id).collect();
+        let chunk_id = Self::store_semantic_chunk(
+            database_manager,
+            observers,
+            session_id,
+            &summary,
+            &event_ids,
+        )
+        .await?;
+
+        if memory_config.enable_graph_updates && !summary.memory_triggers.is_empty() {
+            if let Err(e) = Self::update_knowledge_graph(
+                database_manager,
+                entity_classifier,
+                memory_config,
+                &summary.memory_triggers,
+                (summary.importance_score as f32) / 10.0,
+            )
+            .await
+            {
+                eprintln!("⚠️ 知识图谱更新失败: {}", e);
             }
         }
+        Ok(Some(chunk_id))
+    }
+
+    /// 把记忆事件拼接为交给总结智能体的对话文本
+    fn format_conversation_history<'a>(events: impl Iterator<Item = &'a MemoryEvent>) -> String {
+        events
+            .map(|event| {
+                let speaker = if event.role == "user" {
+                    "User"
+                } else {
+                    "Assistant"
+                };
+                format!("{}: {}", speaker, event.text)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// 结束当前活跃会话
+    ///
+    /// 启用 `auto_title_sessions` 时，关闭前先用对话总结智能体为会话生成简洁标题；
+    /// 标题生成失败不会阻止会话关闭。
+    pub async fn end_session(&self) -> Result<()> {
+        let session_id = match self.database_manager.get_active_session().await? {
+            Some(id) => id,
+            None => return Ok(()),
+        };
 
+        if self.config.auto_title_sessions {
+            let core_service = &self.core_service;
+            let summarize = |conversation_history: String| async move {
+                let core_service = core_service.read().await;
+                Ok(core_service
+                    .chat_with_options("conversation_summarizer", &conversation_history, None, None)
+                    .await?)
+            };
+            if let Err(e) = Self::title_session(&self.database_manager, session_id, summarize).await
+            {
+                eprintln!("⚠️ 会话 {} 标题生成失败: {}", session_id, e);
+            }
+        }
+
+        self.database_manager.close_active_session().await?;
         Ok(())
     }
 
-    /// 解析 ConversationSummarizer 的 JSON 响应
-    #[allow(dead_code)] // 预留功能，后续启用
-    fn parse_summarizer_response(&self, response: &str) -> Result<ConversationSummary> {
-        // 先用与画像提取相同的鲁棒提取逻辑，截取第一个完整 JSON 对象
-        let json_str_owned = extract_json_object(response).unwrap_or_else(|| response.to_string());
-        let json_str = json_str_owned.as_str();
-
-        // 解析 JSON
-        let parsed: serde_json::Value =
-            serde_json::from_str(json_str).map_err(|e| anyhow::anyhow!("JSON 解析失败: {}", e))?;
-
-        // 提取字段
-        let importance_score = parsed
-            .get("importance_score")
-            .and_then(|v| v.as_i64())
-            .unwrap_or(0) as i32;
-
-        let user_intent_summary = parsed
-            .get("user_intent_summary")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
+    /// 总结会话内容并写入会话标题，返回新标题
+    ///
+    /// 空会话或总结结果为空时不修改标题，返回 `None`。
+    async fn title_session<F, Fut>(
+        database_manager: &DatabaseManager,
+        session_id: i64,
+        summarize: F,
+    ) -> Result<Option<String>>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: std::future::Future<Output = Result<String>>,
+    {
+        let events = database_manager.list_memory_events(session_id).await?;
+        if events.is_empty() {
+            return Ok(None);
+        }
 
-        let summary = parsed
-            .get("summary")
-            .and_then(|v| v.as_str())
+        let response = summarize(Self::format_conversation_history(events.iter())).await?;
+        let summary = Self::parse_summarizer_response(&response)?;
+        let source = if summary.user_intent_summary.trim().is_empty() {
+            &summary.summary
+        } else {
+            &summary.user_intent_summary
+        };
+        let title: String = source
+            .lines()
+            .next()
             .unwrap_or("")
-            .to_string();
+            .trim()
+            .chars()
+            .take(MAX_SESSION_TITLE_CHARS)
+            .collect();
+        if title.is_empty() {
+            return Ok(None);
+        }
 
-        let memory_triggers = parsed
-            .get("memory_triggers")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str())
-                    .map(|s| s.to_string())
-                    .collect()
+        database_manager
+            .upsert_session_title(session_id, &title)
+            .await?;
+        Ok(Some(title))
+    }
+
+    /// 解析 ConversationSummarizer 的 JSON 响应
+    ///
+    /// 四个字段都必须存在且类型正确，否则返回 [`SummaryParseError`]，不会退回空的默认值。
+    fn parse_summarizer_response(response: &str) -> Result<ConversationSummary> {
+        // 先截取第一个完整 JSON 对象，兼容未启用语法约束时模型输出的多余文本
+        let json_str = extract_json_object(response).unwrap_or_else(|| response.to_string());
+        let parsed: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&json_str)
+            .map_err(|e| SummaryParseError::InvalidJson(e.to_string()))?;
+
+        let field = |name: &'static str| {
+            parsed
+                .get(name)
+                .ok_or(SummaryParseError::MissingField(name))
+        };
+        let invalid =
+            |name: &'static str, value: &serde_json::Value| SummaryParseError::InvalidField {
+                field: name,
+                value: value.to_string(),
+            };
+        let string_field = |name: &'static str| {
+            let value = field(name)?;
+            value
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| invalid(name, value))
+        };
+
+        let value = field("importance_score")?;
+        let importance_score = value
+            .as_i64()
+            .filter(|score| (0..=10).contains(score))
+            .ok_or_else(|| invalid("importance_score", value))?
+            as i32;
+        let user_intent_summary = string_field("user_intent_summary")?;
+        let summary = string_field("summary")?;
+        let value = field("memory_triggers")?;
+        let memory_triggers = value
+            .as_array()
+            .and_then(|triggers| {
+                triggers
+                    .iter()
+                    .map(|trigger| trigger.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
             })
-            .unwrap_or_default();
+            .ok_or_else(|| invalid("memory_triggers", value))?;
 
         Ok(ConversationSummary {
             importance_score,
@@ -636,148 +1309,99 @@ impl RwkvAgentKit {
     }
 
     /// 更新知识图谱：基于 memory_triggers 创建节点和共现边
-    #[allow(dead_code)] // 预留功能，后续启用
     async fn update_knowledge_graph(
-        &mut self,
+        database_manager: &DatabaseManager,
+        entity_classifier: &dyn EntityClassifier,
+        memory_config: &MemoryConfig,
         memory_triggers: &[String],
         base_edge_weight: f32,
     ) -> Result<()> {
         use crate::db::{GraphEdge, GraphNode};
 
-        // 获取配置化的参数
-        let memory_config = self
-            .agent_configs
-            .values()
-            .find(|config| config.memory.enabled)
-            .map(|config| config.memory.clone())
-            .unwrap_or_default();
-
         let cooccur_weight_divisor = memory_config.cooccur_weight_divisor;
         let min_edge_weight = memory_config.min_edge_weight;
         let max_edge_weight = memory_config.max_edge_weight;
         let enable_weight_accumulation = memory_config.enable_weight_accumulation;
 
-        // 1) 逐个触发词 upsert 节点，并记录 node_id
-        let mut nodes: Vec<(String, i64)> = Vec::new();
-        for trigger in memory_triggers {
-            // 扩展的实体类型推断（基于规则）
-            let entity_type = self.infer_entity_type(trigger);
+        // 1) 分类触发词，分类失败时退回规则推断
+        let entity_types = match entity_classifier.classify(memory_triggers).await {
+            Ok(types) if types.len() == memory_triggers.len() => types,
+            result => {
+                if let Err(e) = result {
+                    eprintln!("⚠️ 实体分类失败，改用规则推断: {}", e);
+                }
+                RuleBasedEntityClassifier.classify(memory_triggers).await?
+            }
+        };
 
+        // 2) 逐个触发词 upsert 节点，按触发词位置记录 node_id 与实体类型
+        let mut nodes: Vec<Option<(i64, String)>> = Vec::with_capacity(memory_triggers.len());
+        for (trigger, entity_type) in memory_triggers.iter().zip(entity_types) {
             let node = GraphNode {
                 id: None,
-                entity_type,
+                entity_type: entity_type.clone(),
                 entity_name: trigger.clone(),
             };
 
-            match self.database_manager.upsert_graph_node(node).await {
+            match database_manager.upsert_graph_node(node).await {
                 Ok(node_id) => {
-                    nodes.push((trigger.clone(), node_id));
+                    nodes.push(Some((node_id, entity_type)));
                 }
                 Err(e) => {
                     eprintln!("⚠️ 图节点创建失败 {}: {}", trigger, e);
+                    nodes.push(None);
                 }
             }
         }
 
-        // 2) 在同一语义片段中将所有触发词两两连边（无向边使用有序 from->to 避免重复）
-        for i in 0..nodes.len() {
-            for j in (i + 1)..nodes.len() {
-                let id1 = nodes[i].1;
-                let id2 = nodes[j].1;
-                let (from_node, to_node) = if id1 <= id2 { (id1, id2) } else { (id2, id1) };
-
-                // 配置化的共现边权重计算
-                let cooccur_weight = (base_edge_weight / cooccur_weight_divisor)
-                    .clamp(min_edge_weight, max_edge_weight);
-
-                // 根据实体类型推断更具体的关系类型
-                let relation_type = self.infer_relation_type(&nodes[i].0, &nodes[j].0);
-
-                let edge = GraphEdge {
-                    id: None,
-                    from_node,
-                    to_node,
-                    relation_type,
-                    weight: cooccur_weight,
-                };
+        // 3) 共现窗口内的触发词两两连边（无向边使用有序 from->to 避免重复）
+        for (i, j, proximity) in cooccurrence_pairs(nodes.len(), memory_config.cooccur_window) {
+            let (Some((id1, type1)), Some((id2, type2))) = (&nodes[i], &nodes[j]) else {
+                continue;
+            };
+            let (from_node, to_node) = ((*id1).min(*id2), (*id1).max(*id2));
 
-                // 根据配置选择使用累积权重或直接插入
-                let edge_result = if enable_weight_accumulation {
-                    self.database_manager
-                        .upsert_graph_edge_with_accumulation(edge)
-                        .await
-                } else {
-                    self.database_manager.upsert_graph_edge(edge).await
-                };
+            // 配置化的共现边权重计算，按邻近度衰减
+            let cooccur_weight = (base_edge_weight / cooccur_weight_divisor * proximity)
+                .clamp(min_edge_weight, max_edge_weight);
 
-                match edge_result {
-                    Ok(edge_id) => {
-                        println!(
-                            "🔗 图边已创建/更新: {} <-> {} (ID: {}, 权重: {:.3})",
-                            nodes[i].0, nodes[j].0, edge_id, cooccur_weight
-                        );
-                    }
-                    Err(e) => {
-                        eprintln!("⚠️ 图边创建失败 {}-{}: {}", nodes[i].0, nodes[j].0, e);
-                    }
-                }
-            }
-        }
+            // 根据实体类型推断更具体的关系类型
+            let relation_type = infer_relation_type(type1, type2);
 
-        Ok(())
-    }
+            let edge = GraphEdge {
+                id: None,
+                from_node,
+                to_node,
+                relation_type,
+                weight: cooccur_weight,
+            };
 
-    /// 推断实体类型（可扩展为更复杂的NER或LLM分类）
-    #[allow(dead_code)] // 预留功能，后续启用
-    fn infer_entity_type(&self, entity_name: &str) -> String {
-        // 基于规则的简单推断
-        if entity_name.chars().any(|c| c.is_uppercase()) && entity_name.len() > 1 {
-            // 首字母大写，可能是人名、地名或组织
-            if entity_name.contains("公司")
-                || entity_name.contains("Corp")
-                || entity_name.contains("Ltd")
-            {
-                "organization".to_string()
-            } else if entity_name.len() <= 5
-                && entity_name
-                    .chars()
-                    .all(|c| c.is_alphabetic() || c.is_whitespace())
-            {
-                "person".to_string() // 短的字母组合，可能是人名
+            // 根据配置选择使用累积权重或直接插入
+            let edge_result = if enable_weight_accumulation {
+                database_manager
+                    .upsert_graph_edge_with_accumulation(edge)
+                    .await
             } else {
-                "entity".to_string() // 通用实体
+                database_manager.upsert_graph_edge(edge).await
+            };
+
+            match edge_result {
+                Ok(edge_id) => {
+                    println!(
+                        "🔗 图边已创建/更新: {} <-> {} (ID: {}, 权重: {:.3})",
+                        memory_triggers[i], memory_triggers[j], edge_id, cooccur_weight
+                    );
+                }
+                Err(e) => {
+                    eprintln!(
+                        "⚠️ 图边创建失败 {}-{}: {}",
+                        memory_triggers[i], memory_triggers[j], e
+                    );
+                }
             }
-        } else if entity_name.ends_with("喜欢")
-            || entity_name.ends_with("偏好")
-            || entity_name.contains("prefer")
-        {
-            "preference".to_string()
-        } else if entity_name.ends_with("任务")
-            || entity_name.ends_with("task")
-            || entity_name.contains("目标")
-        {
-            "task".to_string()
-        } else {
-            "concept".to_string() // 概念或其他
         }
-    }
 
-    /// 推断关系类型（基于实体类型和上下文）
-    #[allow(dead_code)] // 预留功能，后续启用
-    fn infer_relation_type(&self, entity1: &str, entity2: &str) -> String {
-        let type1 = self.infer_entity_type(entity1);
-        let type2 = self.infer_entity_type(entity2);
-
-        match (type1.as_str(), type2.as_str()) {
-            ("person", "organization") | ("organization", "person") => "works_at".to_string(),
-            ("person", "preference") | ("preference", "person") => "prefers".to_string(),
-            ("person", "task") | ("task", "person") => "assigned_to".to_string(),
-            ("task", "concept") | ("concept", "task") => "relates_to".to_string(),
-            ("person", "person") => "knows".to_string(),
-            ("organization", "organization") => "partners_with".to_string(),
-            ("preference", "preference") => "similar_to".to_string(),
-            _ => "co_occurs".to_string(), // 默认共现关系
-        }
+        Ok(())
     }
 
     /// 获取智能体列表
@@ -786,6 +1410,20 @@ impl RwkvAgentKit {
         core_service.agents.keys().cloned().collect()
     }
 
+    /// 在不重建工具包的情况下切换模型
+    ///
+    /// 持有核心服务写锁完成切换，进行中的对话结束后才会生效；新模型无法加载时保留旧模型并返回错误。
+    pub async fn reload_model(&self, new_config: ModelConfig) -> Result<()> {
+        let mut core_service = self.core_service.write().await;
+        core_service.reload_model(new_config).await?;
+        Ok(())
+    }
+
+    /// 获取已加载的 RWKV 分词器
+    pub async fn tokenizer(&self) -> Arc<web_rwkv::tokenizer::Tokenizer> {
+        self.core_service.read().await.tokenizer()
+    }
+
     /// 获取系统统计信息
     pub async fn get_stats(&self) -> Result<HashMap<String, usize>> {
         let mut stats = HashMap::new();
@@ -801,33 +1439,141 @@ impl RwkvAgentKit {
     }
 
     /// 添加工具到工具注册表
-    pub async fn register_tool<T: crate::core::tools::Tool + 'static>(&self, tool: T) {
-        let mut tools = self.tools.write().await;
-        let name = tool.name().to_string();
-        tools.register(tool);
-        println!("🔧 工具 '{}' 已注册 (wrapper)", name);
+    ///
+    /// 同名工具已存在时返回 [`ToolError::DuplicateName`]；需要覆盖时使用
+    /// [`RwkvAgentKit::register_or_replace_tool`]。
+    pub async fn register_tool<T: crate::core::tools::Tool + 'static>(
+        &self,
+        tool: T,
+    ) -> std::result::Result<(), ToolError> {
+        let name = tool.name();
+        self.tools.write().await.register(tool)?;
+        log::info!("Tool '{}' registered", name);
+        Ok(())
+    }
+
+    /// 添加工具到工具注册表并覆盖同名工具，返回是否发生了覆盖
+    pub async fn register_or_replace_tool<T: crate::core::tools::Tool + 'static>(
+        &self,
+        tool: T,
+    ) -> bool {
+        let name = tool.name();
+        let replaced = self.tools.write().await.register_or_replace(tool);
+        if replaced {
+            log::warn!("Tool '{}' replaced an existing registration", name);
+        } else {
+            log::info!("Tool '{}' registered", name);
+        }
+        replaced
+    }
+
+    /// 检查各子系统的实际健康状态：数据库连接、嵌入模型与 RWKV 模型
+    pub async fn health(&self) -> HealthReport {
+        HealthReport {
+            database: database_health(&self.database_manager).await,
+            embedding_model: if is_global_embedding_service_initialized() {
+                HealthStatus::healthy()
+            } else {
+                HealthStatus::unhealthy("embedding service is not initialized")
+            },
+            rwkv_model: if is_global_rwkv_service_initialized() {
+                HealthStatus::healthy()
+            } else {
+                HealthStatus::unhealthy("RWKV service is not initialized")
+            },
+        }
     }
 
     /// 获取数据库统计信息
     pub async fn get_database_stats(&self) -> Result<HashMap<String, String>> {
         let mut stats = HashMap::new();
+        let healthy = database_health(&self.database_manager).await.healthy;
 
         // SQLite 统计
-        stats.insert("sqlite_status".to_string(), "connected".to_string());
-
-        // 向量数据库统计
-        stats.insert("vector_db_status".to_string(), "active".to_string());
+        let sqlite_status = if healthy { "connected" } else { "disconnected" };
+        stats.insert("sqlite_status".to_string(), sqlite_status.to_string());
 
-        // 图数据库统计
-        stats.insert("graph_db_status".to_string(), "active".to_string());
+        // 向量、图数据库与 SQLite 共用同一连接
+        let store_status = if healthy { "active" } else { "unavailable" };
+        stats.insert("vector_db_status".to_string(), store_status.to_string());
+        stats.insert("graph_db_status".to_string(), store_status.to_string());
 
         Ok(stats)
     }
 
     /// 优雅关闭所有服务
     pub async fn shutdown(self) -> Result<()> {
+        self.shutdown_with_timeout(DEFAULT_SHUTDOWN_TIMEOUT).await
+    }
+
+    /// 挂载学习引擎，并按其配置启动后台学习调度器
+    pub fn attach_learning_engine(&mut self, engine: Arc<LearningEngine>) {
+        if let Some(previous) = self.learning_engine.take() {
+            previous.shutdown();
+        }
+        self.learning_scheduler = engine.clone().spawn_configured_scheduler();
+        self.learning_engine = Some(engine);
+    }
+
+    /// 协作式关闭：停止学习调度器、刷新待处理反馈、持久化向量索引、执行 WAL 检查点后关闭数据库。
+    /// 超过 `timeout` 仍未完成时强制关闭，并记录未刷新的部分。
+    pub async fn shutdown_with_timeout(mut self, timeout: Duration) -> Result<()> {
         println!("🛑 正在关闭 RWKV Agent Kit...");
 
+        let scheduler = self.learning_scheduler.take();
+        let scheduler_abort = scheduler.as_ref().map(|handle| handle.abort_handle());
+        let learning_engine = self.learning_engine.take();
+
+        let mut unflushed = Vec::new();
+        if learning_engine.is_some() {
+            unflushed.push("learning feedback");
+            unflushed.push("vector index");
+        }
+        unflushed.push("WAL checkpoint");
+
+        let graceful = async {
+            if let Some(engine) = &learning_engine {
+                engine
+                    .stop_and_flush(scheduler)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Learning flush error: {}", e))?;
+                unflushed.retain(|stage| *stage != "learning feedback");
+
+                engine
+                    .checkpoint_index()
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Vector index persist error: {}", e))?;
+                unflushed.retain(|stage| *stage != "vector index");
+            }
+
+            self.database_manager
+                .checkpoint(CheckpointMode::Truncate)
+                .await
+                .map_err(|e| anyhow::anyhow!("Database checkpoint error: {}", e))?;
+            unflushed.retain(|stage| *stage != "WAL checkpoint");
+            Ok::<(), anyhow::Error>(())
+        };
+
+        let outcome = tokio::time::timeout(timeout, graceful).await;
+        match outcome {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => log::warn!(
+                "Graceful shutdown failed: {}; not flushed: {:?}",
+                e,
+                unflushed
+            ),
+            Err(_) => {
+                if let Some(abort) = scheduler_abort {
+                    abort.abort();
+                }
+                log::warn!(
+                    "Graceful shutdown timed out after {:?}, forcing close; not flushed: {:?}",
+                    timeout,
+                    unflushed
+                );
+            }
+        }
+
         // 关闭数据库连接
         self.database_manager
             .close()
@@ -839,6 +1585,77 @@ impl RwkvAgentKit {
     }
 }
 
+/// 子系统健康状态
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthStatus {
+    pub healthy: bool,
+    /// 不健康时的错误说明
+    pub detail: Option<String>,
+}
+
+impl HealthStatus {
+    /// 健康
+    pub fn healthy() -> Self {
+        Self {
+            healthy: true,
+            detail: None,
+        }
+    }
+
+    /// 不健康，并附带错误说明
+    pub fn unhealthy(detail: impl Into<String>) -> Self {
+        Self {
+            healthy: false,
+            detail: Some(detail.into()),
+        }
+    }
+}
+
+/// [`RwkvAgentKit::health`] 返回的健康报告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HealthReport {
+    pub database: HealthStatus,
+    pub embedding_model: HealthStatus,
+    pub rwkv_model: HealthStatus,
+}
+
+impl HealthReport {
+    /// 所有子系统是否都健康
+    pub fn is_healthy(&self) -> bool {
+        self.database.healthy && self.embedding_model.healthy && self.rwkv_model.healthy
+    }
+}
+
+/// 通过 `Database::health_check` 检查数据库连接
+async fn database_health(database_manager: &DatabaseManager) -> HealthStatus {
+    match database_manager.health_check().await {
+        Ok(true) => HealthStatus::healthy(),
+        Ok(false) => HealthStatus::unhealthy("database health check query failed"),
+        Err(e) => HealthStatus::unhealthy(e.to_string()),
+    }
+}
+
+/// 使用默认模型路径初始化全局嵌入服务，缓存容量取自向量配置
+async fn initialize_embedding_service(vector: &VectorConfig) -> Result<()> {
+    let mut service = EmbeddingService::default().with_vector_config(vector);
+    service.initialize().await?;
+    set_global_embedding_service(service)
+}
+
+/// 将上游分段转发给调用方，返回已转发的完整内容
+///
+/// 调用方丢弃接收端时立即停止并释放上游，生成随之中止；已生成的部分仍会返回给调用者保存。
+async fn relay_stream(upstream: flume::Receiver<String>, sender: flume::Sender<String>) -> String {
+    let mut response = String::new();
+    while let Ok(chunk) = upstream.recv_async().await {
+        response.push_str(&chunk);
+        if sender.send_async(chunk).await.is_err() {
+            break;
+        }
+    }
+    response
+}
+
 /// 便捷的构建器模式
 pub struct RwkvAgentKitBuilder {
     config: RwkvAgentKitConfig,
@@ -924,6 +1741,30 @@ impl RwkvAgentKitBuilder {
         self
     }
 
+    /// 设置路由结果无法识别时使用的智能体
+    pub fn default_route_agent<A: Into<String>>(mut self, agent: A) -> Self {
+        self.config.default_route_agent = Some(agent.into());
+        self
+    }
+
+    /// 设置结束会话时是否自动生成会话标题
+    pub fn auto_title_sessions(mut self, enabled: bool) -> Self {
+        self.config.auto_title_sessions = enabled;
+        self
+    }
+
+    /// 设置流式对话通道的缓冲容量
+    pub fn stream_buffer(mut self, buffer: usize) -> Self {
+        self.config.stream_buffer = Some(buffer);
+        self
+    }
+
+    /// 设置向量与嵌入配置
+    pub fn vector_config(mut self, vector: VectorConfig) -> Self {
+        self.config.vector = vector;
+        self
+    }
+
     /// 添加默认智能体（主要agent和对话总结智能体）
     pub fn with_default_agents(mut self) -> Self {
         use crate::agents::{
@@ -948,6 +1789,9 @@ impl RwkvAgentKitBuilder {
 
     /// 构建 RWKV Agent Kit 实例
     pub async fn build(self) -> Result<RwkvAgentKit> {
+        self.config.validate().map_err(|errors| {
+            anyhow::anyhow!("Invalid configuration: {}", ConfigError::join(&errors))
+        })?;
         RwkvAgentKit::new(self.config).await
     }
 }
@@ -971,10 +1815,466 @@ mod tests {
         assert_eq!(builder.config.model.model_path, "/path/to/model");
         assert_eq!(builder.config.model.tokenizer_path, "/path/to/tokenizer");
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_relay_stream_stops_when_receiver_is_dropped() {
+        let (upstream_sender, upstream) = flume::bounded(2);
+        let (sender, receiver) = flume::bounded(2);
+        // 上游生成方在下游断开前一直阻塞发送
+        let producer = tokio::task::spawn_blocking(move || {
+            let mut produced = 0usize;
+            while upstream_sender.send("x".to_string()).is_ok() {
+                produced += 1;
+            }
+            produced
+        });
+        let relay = tokio::spawn(relay_stream(upstream, sender));
+
+        assert_eq!(receiver.recv_async().await.unwrap(), "x");
+        drop(receiver);
+
+        let response = tokio::time::timeout(Duration::from_secs(1), relay)
+            .await
+            .expect("relay should stop once the receiver is dropped")
+            .unwrap();
+        assert!(response.len() <= 1 + 2 + 1);
+        let produced = tokio::time::timeout(Duration::from_secs(1), producer)
+            .await
+            .expect("upstream should be released once the relay exits")
+            .unwrap();
+        assert!(produced <= 1 + 2 + 1 + 2);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        let builder = RwkvAgentKitBuilder::new().with_default_agents();
+        assert!(builder.config.validate().is_ok());
+
+        let mut config = builder.config.clone();
+        config.model.model_path = String::new();
+        config.model.precision = "fp8".to_string();
+        config.agents.push(config.agents[0].clone());
+
+        let errors = config.validate().unwrap_err();
+        let fields: Vec<&str> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(fields, vec!["model.model_path", "model.precision", "agents[2].name"]);
+        assert_eq!(errors[1].value, "\"fp8\"");
+    }
+
+    #[test]
+    fn test_cooccur_window_limits_edges_to_nearby_triggers() {
+        let pairs = cooccurrence_pairs(5, 2);
+        let edges: Vec<(usize, usize)> = pairs.iter().map(|(i, j, _)| (*i, *j)).collect();
+        assert_eq!(
+            edges,
+            vec![(0, 1), (0, 2), (1, 2), (1, 3), (2, 3), (2, 4), (3, 4)]
+        );
+        // 相邻触发词权重最高，窗口边缘减半
+        assert_eq!(pairs[0].2, 1.0);
+        assert_eq!(pairs[1].2, 0.5);
+
+        // 窗口为 0 时退回完全图 C(5,2)
+        assert_eq!(cooccurrence_pairs(5, 0).len(), 10);
+    }
+
+    #[test]
+    fn test_summarizer_output_is_validated() {
+        use crate::agents::conversation_summarizer::{
+            create_conversation_summarizer_config, CONVERSATION_SUMMARY_BNF,
+        };
+
+        let config = create_conversation_summarizer_config();
+        assert_eq!(
+            config.inference_params.bnf_schema.as_deref(),
+            Some(CONVERSATION_SUMMARY_BNF)
+        );
+
+        // 语法约束下的输出（字段顺序固定，允许空白与换行）
+        let constrained = r#"{"importance_score": 8,
+"user_intent_summary": "计划去北京", "summary": "用户说 \"你好\"", "memory_triggers": ["北京", "会议"]}"#;
+        let summary = RwkvAgentKit::parse_summarizer_response(constrained).unwrap();
+        assert_eq!(summary.importance_score, 8);
+        assert_eq!(summary.summary, "用户说 \"你好\"");
+        assert_eq!(summary.memory_triggers, vec!["北京", "会议"]);
+
+        let error = |response: &str| {
+            RwkvAgentKit::parse_summarizer_response(response)
+                .unwrap_err()
+                .downcast::<SummaryParseError>()
+                .unwrap()
+        };
+        assert!(matches!(
+            error("好的，这段对话主要讨论了行程安排。"),
+            SummaryParseError::InvalidJson(_)
+        ));
+        assert!(matches!(
+            error("{}"),
+            SummaryParseError::MissingField("importance_score")
+        ));
+        assert!(matches!(
+            error(
+                r#"{"importance_score": 11, "user_intent_summary": "", "summary": "", "memory_triggers": []}"#
+            ),
+            SummaryParseError::InvalidField {
+                field: "importance_score",
+                ..
+            }
+        ));
+        assert!(matches!(
+            error(
+                r#"{"importance_score": 3, "user_intent_summary": "", "summary": "", "memory_triggers": [1]}"#
+            ),
+            SummaryParseError::InvalidField {
+                field: "memory_triggers",
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_semantic_threshold_is_per_agent() {
+        let mut agent_configs = HashMap::new();
+        for (name, threshold) in [("strict", 8), ("lenient", 3)] {
+            let mut config = AgentConfig {
+                name: name.to_string(),
+                ..Default::default()
+            };
+            config.memory = MemoryConfig {
+                semantic_chunk_threshold: threshold,
+                ..MemoryConfig::enabled()
+            };
+            agent_configs.insert(name.to_string(), config);
+        }
+        let summary = ConversationSummary {
+            importance_score: 5,
+            user_intent_summary: String::new(),
+            summary: String::new(),
+            memory_triggers: Vec::new(),
+        };
+
+        let strict = RwkvAgentKit::agent_memory_config(&agent_configs, "strict");
+        let lenient = RwkvAgentKit::agent_memory_config(&agent_configs, "lenient");
+        assert_eq!(strict.semantic_chunk_threshold, 8);
+        assert_eq!(lenient.semantic_chunk_threshold, 3);
+        assert!(!summary.meets_threshold(&strict));
+        assert!(summary.meets_threshold(&lenient));
+
+        // 未注册的智能体回退到默认策略
+        let fallback = RwkvAgentKit::agent_memory_config(&agent_configs, "unknown");
+        assert_eq!(fallback.semantic_chunk_threshold, 5);
+        assert!(!fallback.enable_graph_updates);
+        assert!(summary.meets_threshold(&fallback));
+    }
+
+    #[tokio::test]
+    async fn test_build_rejects_invalid_config() {
+        let err = RwkvAgentKitBuilder::new()
+            .model_path("")
+            .build()
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("model.model_path"));
+    }
+
+    #[tokio::test]
+    async fn test_interleaved_turns_stay_in_their_sessions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let database_manager = DatabaseManager::new(DatabaseConfig {
+            db_path: temp_dir.path().join("sessions.db"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        // 默认路径的活跃会话不应受显式会话影响
+        let active = database_manager.open_session("chat", None).await.unwrap();
+        let first = database_manager
+            .create_session("chat", Some("first"))
+            .await
+            .unwrap();
+        let second = database_manager
+            .create_session("helper", Some("second"))
+            .await
+            .unwrap();
+        assert_eq!(
+            database_manager.get_active_session().await.unwrap(),
+            Some(active)
+        );
+
+        let run = |session_id: i64, agent_name: &'static str, tag: &'static str| {
+            let database_manager = database_manager.clone();
+            async move {
+                for turn in 0..3 {
+                    RwkvAgentKit::record_turn(
+                        &database_manager,
+                        session_id,
+                        agent_name,
+                        &format!("{} question {}", tag, turn),
+                        &format!("{} answer {}", tag, turn),
+                    )
+                    .await;
+                    tokio::task::yield_now().await;
+                }
+            }
+        };
+        tokio::join!(run(first, "chat", "first"), run(second, "helper", "second"));
+
+        for (session_id, tag) in [(first, "first"), (second, "second")] {
+            let events = database_manager
+                .list_memory_events(session_id)
+                .await
+                .unwrap();
+            assert_eq!(events.len(), 6);
+            assert!(events.iter().all(|event| event.session_id == session_id));
+            assert!(events.iter().all(|event| event.text.starts_with(tag)));
+        }
+        assert!(database_manager
+            .list_memory_events(active)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_batch_aggregation_creates_one_chunk_per_batch() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let database_manager = DatabaseManager::new(DatabaseConfig {
+            db_path: temp_dir.path().join("aggregation.db"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let session_id = database_manager.open_session("chat", None).await.unwrap();
+        let memory_config = MemoryConfig::enabled().with_semantic_batch_turns(3);
+
+        let summarized = std::sync::Mutex::new(Vec::new());
+        let mut created = Vec::new();
+        let mut turn_event_ids = Vec::new();
+        for turn in 0..4 {
+            let (user_event_id, assistant_event_id) = RwkvAgentKit::record_turn(
+                &database_manager,
+                session_id,
+                "chat",
+                &format!("question {}", turn),
+                &format!("answer {}", turn),
+            )
+            .await
+            .unwrap();
+            turn_event_ids.push(user_event_id);
+            turn_event_ids.push(assistant_event_id);
+            let chunk_id = RwkvAgentKit::aggregate_session_batch(
+                &database_manager,
+                &[],
+                &RuleBasedEntityClassifier,
+                session_id,
+                &memory_config,
+                |history: String| {
+                    summarized.lock().unwrap().push(history);
+                    async {
+                        Ok(r#"{"importance_score": 7, "user_intent_summary": "学习", "summary": "用户连续提问", "memory_triggers": ["Rust"]}"#.to_string())
+                    }
+                },
+            )
+            .await
+            .unwrap();
+            created.push(chunk_id);
+        }
+
+        // 只有第 3 轮触发聚合，且三轮对话一起总结
+        assert_eq!(created.iter().flatten().count(), 1);
+        assert!(created[2].is_some());
+        let summarized = summarized.into_inner().unwrap();
+        assert_eq!(summarized.len(), 1);
+        assert!(summarized[0].contains("User: question 0"));
+        assert!(summarized[0].contains("Assistant: answer 2"));
+
+        let chunks = database_manager.list_semantic_chunks(None).await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        let mappings = database_manager
+            .get_chunk_mappings_by_session_id(session_id)
+            .await
+            .unwrap();
+        assert_eq!(mappings.len(), 1);
+        assert_eq!(mappings[0].chunk_id, created[2].unwrap());
+        let event_ids: Vec<i64> = serde_json::from_str(&mappings[0].memory_event_ids).unwrap();
+        assert_eq!(event_ids, turn_event_ids[..6]);
+        let sources = database_manager
+            .get_memory_events_for_chunk(created[2].unwrap())
+            .await
+            .unwrap();
+        assert_eq!(sources.len(), 6);
+        assert_eq!(sources[0].text, "question 0");
+        assert_eq!(sources[5].text, "answer 2");
+
+        // 第 4 轮尚未聚合
+        let pending = database_manager
+            .list_unsummarized_memory_events(session_id)
+            .await
+            .unwrap();
+        let pending_ids: Vec<i64> = pending.iter().map(|(id, _)| *id).collect();
+        assert_eq!(pending_ids, turn_event_ids[6..]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_aggregation_updates_graph_through_classifier() {
+        #[derive(Debug)]
+        struct PersonClassifier;
+
+        #[async_trait::async_trait]
+        impl EntityClassifier for PersonClassifier {
+            async fn classify(&self, triggers: &[String]) -> Result<Vec<String>> {
+                Ok(vec!["person".to_string(); triggers.len()])
+            }
+        }
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let database_manager = DatabaseManager::new(DatabaseConfig {
+            db_path: temp_dir.path().join("graph.db"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let session_id = database_manager.open_session("chat", None).await.unwrap();
+        let memory_config = MemoryConfig::enabled()
+            .with_semantic_batch_turns(1)
+            .with_graph_updates(true);
+
+        RwkvAgentKit::record_turn(&database_manager, session_id, "chat", "我和小明", "好的")
+            .await
+            .unwrap();
+        let chunk_id = RwkvAgentKit::aggregate_session_batch(
+            &database_manager,
+            &[],
+            &PersonClassifier,
+            session_id,
+            &memory_config,
+            |_| async {
+                Ok(r#"{"importance_score": 8, "user_intent_summary": "介绍朋友", "summary": "用户提到小红和小明", "memory_triggers": ["小红", "小明"]}"#.to_string())
+            },
+        )
+        .await
+        .unwrap();
+        assert!(chunk_id.is_some());
+
+        // 节点类型来自注入的分类器，关系类型随之推断
+        let nodes = database_manager.get_graph_nodes().await.unwrap();
+        assert_eq!(nodes.len(), 2);
+        assert!(nodes.iter().all(|node| node.entity_type == "person"));
+        let edges = database_manager.get_graph_edges().await.unwrap();
+        assert_eq!(edges.len(), 1);
+        assert_eq!(edges[0].relation_type, "knows");
+    }
+
+    #[tokio::test]
+    async fn test_ended_session_gets_summarized_title() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let database_manager = DatabaseManager::new(DatabaseConfig {
+            db_path: temp_dir.path().join("title.db"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let raw_title = format!(
+            "chat 会话 @ {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
+        );
+        let session_id = database_manager
+            .open_session("chat", Some(&raw_title))
+            .await
+            .unwrap();
+        for turn in 0..3 {
+            RwkvAgentKit::record_turn(
+                &database_manager,
+                session_id,
+                "chat",
+                &format!("如何学习 Rust 第 {} 步", turn),
+                &format!("回答 {}", turn),
+            )
+            .await
+            .unwrap();
+        }
+
+        let title = RwkvAgentKit::title_session(&database_manager, session_id, |history| {
+            assert!(history.contains("User: 如何学习 Rust 第 0 步"));
+            async {
+                Ok(r#"{"importance_score": 6, "user_intent_summary": "学习 Rust 的入门路线", "summary": "用户询问学习步骤", "memory_triggers": []}"#.to_string())
+            }
+        })
+        .await
+        .unwrap();
+        database_manager.close_active_session().await.unwrap();
+
+        assert_eq!(title.as_deref(), Some("学习 Rust 的入门路线"));
+        let stored = database_manager
+            .get_session_title(session_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_ne!(stored, raw_title);
+        assert_eq!(stored, "学习 Rust 的入门路线");
+        assert!(database_manager
+            .get_active_session()
+            .await
+            .unwrap()
+            .is_none());
+
+        // 空会话不调用总结，保留原标题
+        let empty_id = database_manager
+            .open_session("chat", Some(&raw_title))
+            .await
+            .unwrap();
+        let title = RwkvAgentKit::title_session(&database_manager, empty_id, |_| async {
+            Err(anyhow::anyhow!("empty sessions must not be summarized"))
+        })
+        .await
+        .unwrap();
+        assert!(title.is_none());
+        assert_eq!(
+            database_manager
+                .get_session_title(empty_id)
+                .await
+                .unwrap()
+                .as_deref(),
+            Some(raw_title.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_closed_database_reports_unhealthy() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let database_manager = DatabaseManager::new(DatabaseConfig {
+            db_path: temp_dir.path().join("health.db"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            database_health(&database_manager).await,
+            HealthStatus::healthy()
+        );
+
+        database_manager.close().await.unwrap();
+        let status = database_health(&database_manager).await;
+        assert!(!status.healthy);
+        assert!(status.detail.is_some());
+    }
+}
+
+/// 总结智能体的输出无法解析为总结 JSON
+#[derive(Debug, thiserror::Error)]
+pub enum SummaryParseError {
+    /// 输出中没有有效的 JSON 对象
+    #[error("总结输出不是有效的 JSON 对象: {0}")]
+    InvalidJson(String),
+    /// 缺少必需字段
+    #[error("总结输出缺少字段 `{0}`")]
+    MissingField(&'static str),
+    /// 字段类型或取值不合法
+    #[error("总结字段 `{field}` 取值无效: {value}")]
+    InvalidField { field: &'static str, value: String },
 }
 
 /// 对话总结
-#[allow(dead_code)] // 预留功能，后续启用
 #[derive(Debug, Clone)]
 struct ConversationSummary {
     importance_score: i32,
@@ -983,9 +2283,34 @@ struct ConversationSummary {
     memory_triggers: Vec<String>,
 }
 
+impl ConversationSummary {
+    /// 重要性是否达到该记忆策略创建语义片段的阈值
+    fn meets_threshold(&self, memory_config: &MemoryConfig) -> bool {
+        self.importance_score >= memory_config.semantic_chunk_threshold
+    }
+}
+
+/// 计算共现窗口内的触发词位置对及其邻近度
+///
+/// 位置相距 `d <= window` 的触发词才连边，邻近度为 `1 - (d - 1) / window`（相邻为 1）；
+/// `window` 为 0 时两两全部连边，邻近度均为 1。
+fn cooccurrence_pairs(len: usize, window: usize) -> Vec<(usize, usize, f32)> {
+    let mut pairs = Vec::new();
+    for i in 0..len {
+        for j in (i + 1)..len {
+            let distance = j - i;
+            if window == 0 {
+                pairs.push((i, j, 1.0));
+            } else if distance <= window {
+                pairs.push((i, j, 1.0 - (distance - 1) as f32 / window as f32));
+            }
+        }
+    }
+    pairs
+}
+
 /// 从文本中提取第一个完整的 JSON 对象（尽量用于解析 {"traits": ...}）
-#[allow(dead_code)] // 预留功能，后续启用
-fn extract_json_object(s: &str) -> Option<String> {
+pub(crate) fn extract_json_object(s: &str) -> Option<String> {
     // 首先尝试直接解析整个字符串
     if serde_json::from_str::<serde_json::Value>(s.trim()).is_ok() {
         return Some(s.trim().to_string());
@@ -0,0 +1,788 @@
+//! OpenAI 兼容的 HTTP 服务
+//!
+//! 启用 `server` 特性后可用，提供 `POST /v1/chat/completions`（支持 `stream: true` 的 SSE 输出）
+//! 与 `GET /v1/models`。请求中的 `model` 字段对应智能体名称。
+//!
+//! [`memory_router`] 另外提供记忆的增删改查与检索接口：`POST /memories`、
+//! `GET/PUT/DELETE /memories/{id}` 与 `POST /search`，可与 [`router`] 合并后一起启动。
+
+use async_trait::async_trait;
+use axum::{
+    extract::{rejection::JsonRejection, Path, State},
+    http::StatusCode,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Json, Router,
+};
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::RwLock;
+
+use crate::core_types::{Context, Memory, Query, UpdateType};
+use crate::error::MemoryError;
+use crate::memory::{CreateMemoryRequest, MemoryManager, RetrievalResult, UpdateMemoryRequest};
+use crate::rwkv_agent_kit::RwkvAgentKit;
+
+/// 服务所需的聊天后端
+#[async_trait]
+pub trait ChatBackend: Send + Sync {
+    /// 可用的智能体名称
+    async fn list_agents(&self) -> Vec<String>;
+
+    /// 一次性返回完整回复
+    async fn chat(&self, agent_name: &str, user_input: &str) -> anyhow::Result<String>;
+
+    /// 逐段返回回复内容
+    async fn chat_stream(
+        &self,
+        agent_name: &str,
+        user_input: &str,
+    ) -> anyhow::Result<flume::Receiver<String>>;
+}
+
+/// 所有路由只取读锁，请求之间可以并发；两种模式都不把对话写入会话
+#[async_trait]
+impl ChatBackend for RwLock<RwkvAgentKit> {
+    async fn list_agents(&self) -> Vec<String> {
+        self.read().await.list_agents().await
+    }
+
+    async fn chat(&self, agent_name: &str, user_input: &str) -> anyhow::Result<String> {
+        self.read()
+            .await
+            .chat_no_memory(agent_name, user_input)
+            .await
+    }
+
+    async fn chat_stream(
+        &self,
+        agent_name: &str,
+        user_input: &str,
+    ) -> anyhow::Result<flume::Receiver<String>> {
+        self.read().await.chat_stream(agent_name, user_input).await
+    }
+}
+
+/// 共享的后端句柄
+pub type SharedChatBackend = Arc<dyn ChatBackend>;
+
+/// 在指定地址上启动服务，直到服务退出
+pub async fn serve(kit: RwkvAgentKit, addr: SocketAddr) -> anyhow::Result<()> {
+    let listener = TcpListener::bind(addr).await?;
+    serve_with_listener(Arc::new(RwLock::new(kit)), listener).await
+}
+
+/// 使用已绑定的监听器和任意后端启动服务
+pub async fn serve_with_listener(
+    backend: SharedChatBackend,
+    listener: TcpListener,
+) -> anyhow::Result<()> {
+    axum::serve(listener, router(backend)).await?;
+    Ok(())
+}
+
+/// 构建服务路由
+pub fn router(backend: SharedChatBackend) -> Router {
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/models", get(list_models))
+        .with_state(backend)
+}
+
+/// 构建记忆增删改查与检索的路由
+pub fn memory_router(manager: Arc<MemoryManager>) -> Router {
+    Router::new()
+        .route("/memories", post(create_memory))
+        .route(
+            "/memories/{id}",
+            get(get_memory).put(update_memory).delete(delete_memory),
+        )
+        .route("/search", post(search_memories))
+        .with_state(manager)
+}
+
+/// 聊天消息
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+/// `POST /v1/chat/completions` 请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatCompletionRequest {
+    /// 智能体名称
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub stream: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionResponse {
+    id: String,
+    object: &'static str,
+    created: i64,
+    model: String,
+    choices: Vec<ChatCompletionChoice>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionChoice {
+    index: u32,
+    message: ChatMessage,
+    finish_reason: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionChunk {
+    id: String,
+    object: &'static str,
+    created: i64,
+    model: String,
+    choices: Vec<ChatCompletionChunkChoice>,
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionChunkChoice {
+    index: u32,
+    delta: ChatDelta,
+    finish_reason: Option<&'static str>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct ChatDelta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+/// `PUT /memories/{id}` 请求体
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateMemoryBody {
+    pub updates: Vec<UpdateType>,
+    pub context: Option<Context>,
+}
+
+#[derive(Debug, Serialize)]
+struct ModelList {
+    object: &'static str,
+    data: Vec<ModelInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct ModelInfo {
+    id: String,
+    object: &'static str,
+    created: i64,
+    owned_by: &'static str,
+}
+
+/// OpenAI 风格的错误响应
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    error_type: &'static str,
+    code: Option<&'static str>,
+    message: String,
+}
+
+impl ApiError {
+    fn invalid_request(message: impl Into<String>) -> Self {
+        Self {
+            status: StatusCode::BAD_REQUEST,
+            error_type: "invalid_request_error",
+            code: None,
+            message: message.into(),
+        }
+    }
+
+    fn model_not_found(model: &str) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            error_type: "invalid_request_error",
+            code: Some("model_not_found"),
+            message: format!("The model '{}' does not exist", model),
+        }
+    }
+
+    fn memory_not_found(id: &str) -> Self {
+        Self {
+            status: StatusCode::NOT_FOUND,
+            error_type: "invalid_request_error",
+            code: Some("memory_not_found"),
+            message: format!("Memory with id '{}' not found", id),
+        }
+    }
+
+    fn internal(error: anyhow::Error) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            error_type: "server_error",
+            code: None,
+            message: error.to_string(),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({
+            "error": {
+                "message": self.message,
+                "type": self.error_type,
+                "param": null,
+                "code": self.code,
+            }
+        });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+impl From<MemoryError> for ApiError {
+    fn from(error: MemoryError) -> Self {
+        match error {
+            MemoryError::MemoryNotFound { id } => Self::memory_not_found(&id),
+            MemoryError::InvalidQuery { .. }
+            | MemoryError::ValidationError { .. }
+            | MemoryError::InvalidVectorDimension { .. } => {
+                Self::invalid_request(error.to_string())
+            }
+            MemoryError::PermissionDenied { .. } => Self {
+                status: StatusCode::FORBIDDEN,
+                ..Self::invalid_request(error.to_string())
+            },
+            MemoryError::Timeout { .. } => Self {
+                status: StatusCode::GATEWAY_TIMEOUT,
+                ..Self::internal(error.into())
+            },
+            error => Self::internal(error.into()),
+        }
+    }
+}
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        Self {
+            status: rejection.status(),
+            ..Self::invalid_request(rejection.body_text())
+        }
+    }
+}
+
+async fn list_models(State(backend): State<SharedChatBackend>) -> Json<ModelList> {
+    let data = backend
+        .list_agents()
+        .await
+        .into_iter()
+        .map(|id| ModelInfo {
+            id,
+            object: "model",
+            created: 0,
+            owned_by: "rwkv-agent-kit",
+        })
+        .collect();
+
+    Json(ModelList {
+        object: "list",
+        data,
+    })
+}
+
+async fn chat_completions(
+    State(backend): State<SharedChatBackend>,
+    payload: Result<Json<ChatCompletionRequest>, JsonRejection>,
+) -> Result<Response, ApiError> {
+    let Json(request) = payload?;
+
+    // 智能体自行维护提示词模板与对话历史，这里只转发最后一条用户消息
+    let user_input = request
+        .messages
+        .iter()
+        .rev()
+        .find(|message| message.role == "user")
+        .map(|message| message.content.clone())
+        .ok_or_else(|| ApiError::invalid_request("'messages' must contain a user message"))?;
+
+    if !backend.list_agents().await.contains(&request.model) {
+        return Err(ApiError::model_not_found(&request.model));
+    }
+
+    let id = format!("chatcmpl-{}", uuid::Uuid::new_v4().simple());
+    let created = chrono::Utc::now().timestamp();
+
+    if !request.stream {
+        let content = backend
+            .chat(&request.model, &user_input)
+            .await
+            .map_err(ApiError::internal)?;
+
+        let response = ChatCompletionResponse {
+            id,
+            object: "chat.completion",
+            created,
+            model: request.model,
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatMessage {
+                    role: "assistant".to_string(),
+                    content,
+                },
+                finish_reason: "stop",
+            }],
+        };
+        return Ok(Json(response).into_response());
+    }
+
+    let receiver = backend
+        .chat_stream(&request.model, &user_input)
+        .await
+        .map_err(ApiError::internal)?;
+
+    let model = request.model;
+    let chunk = move |delta: ChatDelta, finish_reason: Option<&'static str>| ChatCompletionChunk {
+        id: id.clone(),
+        object: "chat.completion.chunk",
+        created,
+        model: model.clone(),
+        choices: vec![ChatCompletionChunkChoice {
+            index: 0,
+            delta,
+            finish_reason,
+        }],
+    };
+
+    let first = chunk(
+        ChatDelta {
+            role: Some("assistant"),
+            content: None,
+        },
+        None,
+    );
+    let last = chunk(ChatDelta::default(), Some("stop"));
+    let contents = receiver.into_stream().map(move |content| {
+        chunk(
+            ChatDelta {
+                role: None,
+                content: Some(content),
+            },
+            None,
+        )
+    });
+
+    let events = stream::once(async move { first })
+        .chain(contents)
+        .chain(stream::once(async move { last }))
+        .map(|chunk| Event::default().json_data(chunk))
+        .chain(stream::once(async {
+            Ok::<_, axum::Error>(Event::default().data("[DONE]"))
+        }));
+
+    Ok(Sse::new(events)
+        .keep_alive(KeepAlive::default())
+        .into_response())
+}
+
+async fn create_memory(
+    State(manager): State<Arc<MemoryManager>>,
+    payload: Result<Json<CreateMemoryRequest>, JsonRejection>,
+) -> Result<(StatusCode, Json<Memory>), ApiError> {
+    let Json(request) = payload?;
+    if request.content.trim().is_empty() {
+        return Err(ApiError::invalid_request("'content' must not be empty"));
+    }
+
+    let memory = manager.create_memory_from_request(request).await?;
+    Ok((StatusCode::CREATED, Json(memory)))
+}
+
+async fn get_memory(
+    State(manager): State<Arc<MemoryManager>>,
+    Path(id): Path<String>,
+) -> Result<Json<Memory>, ApiError> {
+    let memory = manager.get_memory(&id).await?;
+    if memory.metadata.is_deleted {
+        return Err(ApiError::memory_not_found(&id));
+    }
+    Ok(Json(memory))
+}
+
+async fn update_memory(
+    State(manager): State<Arc<MemoryManager>>,
+    Path(id): Path<String>,
+    payload: Result<Json<UpdateMemoryBody>, JsonRejection>,
+) -> Result<Json<Memory>, ApiError> {
+    let Json(body) = payload?;
+    if body.updates.is_empty() {
+        return Err(ApiError::invalid_request("'updates' must not be empty"));
+    }
+    if manager.get_memory(&id).await?.metadata.is_deleted {
+        return Err(ApiError::memory_not_found(&id));
+    }
+
+    let memory = manager
+        .update_memory(UpdateMemoryRequest {
+            memory_id: id,
+            updates: body.updates,
+            context: body.context.unwrap_or_default(),
+        })
+        .await?;
+    Ok(Json(memory))
+}
+
+/// 删除为软删除，已删除的记忆不再出现在读取与检索结果中
+async fn delete_memory(
+    State(manager): State<Arc<MemoryManager>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    if manager.get_memory(&id).await?.metadata.is_deleted {
+        return Err(ApiError::memory_not_found(&id));
+    }
+    manager.delete_memory(&id, true).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn search_memories(
+    State(manager): State<Arc<MemoryManager>>,
+    payload: Result<Json<Query>, JsonRejection>,
+) -> Result<Json<Vec<RetrievalResult>>, ApiError> {
+    let Json(query) = payload?;
+    if query.text.trim().is_empty() {
+        return Err(ApiError::invalid_request("'text' must not be empty"));
+    }
+
+    let results = manager
+        .retrieve_memories(&query, &Context::default())
+        .await?;
+    Ok(Json(results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct EchoBackend;
+
+    #[async_trait]
+    impl ChatBackend for EchoBackend {
+        async fn list_agents(&self) -> Vec<String> {
+            vec!["chat".to_string()]
+        }
+
+        async fn chat(&self, _agent_name: &str, user_input: &str) -> anyhow::Result<String> {
+            Ok(format!("echo: {}", user_input))
+        }
+
+        async fn chat_stream(
+            &self,
+            _agent_name: &str,
+            user_input: &str,
+        ) -> anyhow::Result<flume::Receiver<String>> {
+            let (sender, receiver) = flume::unbounded();
+            sender.send("echo: ".to_string()).unwrap();
+            sender.send(user_input.to_string()).unwrap();
+            Ok(receiver)
+        }
+    }
+
+    async fn spawn_test_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_with_listener(Arc::new(EchoBackend), listener));
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_non_streaming_completion() {
+        let addr = spawn_test_server().await;
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/v1/chat/completions", addr))
+            .json(&serde_json::json!({
+                "model": "chat",
+                "messages": [
+                    {"role": "system", "content": "be nice"},
+                    {"role": "user", "content": "你好"}
+                ]
+            }))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["object"], "chat.completion");
+        assert_eq!(body["model"], "chat");
+        assert_eq!(body["choices"][0]["message"]["role"], "assistant");
+        assert_eq!(body["choices"][0]["message"]["content"], "echo: 你好");
+        assert_eq!(body["choices"][0]["finish_reason"], "stop");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_model_returns_openai_error() {
+        let addr = spawn_test_server().await;
+
+        let response = reqwest::Client::new()
+            .post(format!("http://{}/v1/chat/completions", addr))
+            .json(&serde_json::json!({
+                "model": "missing",
+                "messages": [{"role": "user", "content": "hi"}]
+            }))
+            .send()
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+        assert_eq!(body["error"]["code"], "model_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_list_models() {
+        let addr = spawn_test_server().await;
+
+        let body: serde_json::Value = reqwest::get(format!("http://{}/v1/models", addr))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+
+        assert_eq!(body["object"], "list");
+        assert_eq!(body["data"][0]["id"], "chat");
+    }
+
+    #[tokio::test]
+    async fn test_completions_do_not_write_sessions() {
+        use crate::agent::AgentConfig;
+        use crate::core::config::KitConfig;
+        use crate::core::service::RwkvAgentKit as CoreService;
+        use crate::db::{DatabaseConfig, DatabaseManager};
+        use ai00_core::{ThreadRequest, Token};
+        use std::collections::HashMap;
+        use web_rwkv::tokenizer::Tokenizer;
+
+        // 代替推理线程：每个请求都只回复 "pong"
+        let (sender, requests) = flume::unbounded::<ThreadRequest>();
+        tokio::spawn(async move {
+            while let Ok(request) = requests.recv_async().await {
+                if let ThreadRequest::Generate { sender, .. } = request {
+                    let _ = sender.send_async(Token::Content("pong".to_string())).await;
+                }
+            }
+        });
+        let vocab: HashMap<u32, Vec<u8>> = (0..=255u8).map(|b| (b as u32 + 1, vec![b])).collect();
+        let tokenizer = Tokenizer::new(&serde_json::to_string(&vocab).unwrap()).unwrap();
+        let core_service =
+            CoreService::with_sender(KitConfig::default(), sender, Arc::new(tokenizer)).unwrap();
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let database_manager = DatabaseManager::new(DatabaseConfig {
+            db_path: temp_dir.path().join("server.db"),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        let config = crate::RwkvAgentKitConfig {
+            agents: vec![AgentConfig {
+                name: "chat".to_string(),
+                save_conversations: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let kit = RwkvAgentKit::with_core_service(
+            Arc::new(RwLock::new(core_service)),
+            database_manager.clone(),
+            config,
+        )
+        .await
+        .unwrap();
+        let session_id = database_manager
+            .get_active_session()
+            .await
+            .unwrap()
+            .unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_with_listener(Arc::new(RwLock::new(kit)), listener));
+
+        let client = reqwest::Client::new();
+        for stream in [false, true] {
+            let body = client
+                .post(format!("http://{}/v1/chat/completions", addr))
+                .json(&serde_json::json!({
+                    "model": "chat",
+                    "messages": [{"role": "user", "content": "ping"}],
+                    "stream": stream
+                }))
+                .send()
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap();
+            assert!(body.contains("pong"), "stream={}: {}", stream, body);
+        }
+
+        assert!(database_manager
+            .list_memory_events(session_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    async fn spawn_memory_server() -> SocketAddr {
+        let db_path = tempfile::NamedTempFile::new()
+            .unwrap()
+            .into_temp_path()
+            .keep()
+            .unwrap();
+        let config = crate::config::Config {
+            database: crate::config::DatabaseConfig {
+                url: format!("sqlite://{}", db_path.display()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let db = Arc::new(
+            crate::database::VectorGraphDB::new(config.clone())
+                .await
+                .unwrap(),
+        );
+        let manager = MemoryManager::new(db, config).await.unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, memory_router(Arc::new(manager)))
+                .await
+                .unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_memory_create_search_delete_cycle() {
+        let addr = spawn_memory_server().await;
+        let client = reqwest::Client::new();
+
+        let request = CreateMemoryRequest {
+            content: "周末和朋友去爬山".to_string(),
+            context: Context::default(),
+            attributes: None,
+            force_connections: None,
+        };
+        let response = client
+            .post(format!("http://{}/memories", addr))
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::CREATED);
+        let created: serde_json::Value = response.json().await.unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+
+        let fetched: serde_json::Value = client
+            .get(format!("http://{}/memories/{}", addr, id))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(fetched["content"], "周末和朋友去爬山");
+
+        let response = client
+            .put(format!("http://{}/memories/{}", addr, id))
+            .json(&serde_json::json!({"updates": [{"TagUpdate": ["户外"]}]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+        let updated: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(updated["attributes"]["tags"], serde_json::json!(["户外"]));
+
+        let query = Query {
+            text: "周末和朋友去爬山".to_string(),
+            query_type: crate::core_types::QueryType::Semantic,
+            filters: Default::default(),
+            limit: Some(5),
+            offset: None,
+            sort_by: None,
+            weights: Default::default(),
+        };
+        let search = |query: &Query| {
+            client
+                .post(format!("http://{}/search", addr))
+                .json(query)
+                .send()
+        };
+        let results: Vec<serde_json::Value> = search(&query).await.unwrap().json().await.unwrap();
+        assert!(results.iter().any(|r| r["memory"]["id"] == id.as_str()));
+
+        let response = client
+            .delete(format!("http://{}/memories/{}", addr, id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NO_CONTENT);
+
+        let response = client
+            .get(format!("http://{}/memories/{}", addr, id))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "memory_not_found");
+
+        let results: Vec<serde_json::Value> = search(&query).await.unwrap().json().await.unwrap();
+        assert!(results.iter().all(|r| r["memory"]["id"] != id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_memory_routes_validate_bodies() {
+        let addr = spawn_memory_server().await;
+        let client = reqwest::Client::new();
+
+        let response = client
+            .post(format!("http://{}/memories", addr))
+            .json(&serde_json::json!({"content": "缺少上下文"}))
+            .send()
+            .await
+            .unwrap();
+        assert!(response.status().is_client_error());
+
+        let request = CreateMemoryRequest {
+            content: "   ".to_string(),
+            context: Context::default(),
+            attributes: None,
+            force_connections: None,
+        };
+        let response = client
+            .post(format!("http://{}/memories", addr))
+            .json(&request)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        let response = client
+            .put(format!("http://{}/memories/missing", addr))
+            .json(&serde_json::json!({"updates": [{"TagUpdate": []}]}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,97 @@
+//! 运行指标与追踪模块
+//!
+//! 启用 `metrics` 特性后，检索、缓存与嵌入生成会通过 `metrics` crate 上报指标，
+//! 并可通过 [`metrics_handle`] 以 Prometheus 文本格式导出；未启用时记录函数均为空操作。
+//! 启用 `tracing` 特性后，可通过 [`init_tracing`] 安装输出检索管线 span 的订阅器。
+
+use std::time::Duration;
+
+/// 记录一次记忆检索及其耗时
+pub(crate) fn record_retrieval(strategy: &str, elapsed: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::counter!("retrievals_total", "strategy" => strategy.to_string()).increment(1);
+        metrics::histogram!("retrieval_latency_seconds", "strategy" => strategy.to_string())
+            .record(elapsed.as_secs_f64());
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = (strategy, elapsed);
+}
+
+/// 记录一次检索缓存查询结果
+pub(crate) fn record_cache_lookup(hit: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        if hit {
+            metrics::counter!("cache_hits_total").increment(1);
+        } else {
+            metrics::counter!("cache_misses_total").increment(1);
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = hit;
+}
+
+/// 记录模型实际编码出的嵌入向量数量（不含缓存命中）
+pub(crate) fn record_embeddings_generated(count: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!("embeddings_generated_total").increment(count as u64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = count;
+}
+
+/// 更新记忆总数
+pub(crate) fn set_memories_total(total: u64) {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!("memories_total").set(total as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = total;
+}
+
+/// 更新限流队列中等待的请求数
+pub(crate) fn set_queue_depth(queue: &'static str, depth: usize) {
+    #[cfg(feature = "metrics")]
+    metrics::gauge!("queue_depth", "queue" => queue).set(depth as f64);
+    #[cfg(not(feature = "metrics"))]
+    let _ = (queue, depth);
+}
+
+/// 获取 Prometheus 导出句柄
+///
+/// 首次调用时安装全局指标记录器，因此应在系统启动时调用一次；
+/// 之后通过 `handle.render()` 获得 Prometheus 文本格式，可挂载到任意 HTTP 服务上。
+/// 若进程中已安装了其他记录器，返回的句柄将不包含任何指标。
+#[cfg(feature = "metrics")]
+pub fn metrics_handle() -> metrics_exporter_prometheus::PrometheusHandle {
+    use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+    use std::sync::OnceLock;
+
+    static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+    HANDLE
+        .get_or_init(|| {
+            PrometheusBuilder::new()
+                .install_recorder()
+                .unwrap_or_else(|e| {
+                    log::warn!("Failed to install Prometheus recorder: {}", e);
+                    PrometheusBuilder::new().build_recorder().handle()
+                })
+        })
+        .clone()
+}
+
+/// 初始化 tracing 订阅器
+///
+/// 日志级别由 `RUST_LOG` 控制（默认 `info`），并通过 `tracing-log` 桥接现有的 `log` 调用。
+/// 进程中已安装其他 `log` 记录器或全局订阅器时返回错误。
+#[cfg(feature = "tracing")]
+pub fn init_tracing() -> anyhow::Result<()> {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let subscriber = tracing_subscriber::fmt().with_env_filter(filter).finish();
+
+    tracing_log::LogTracer::init()?;
+    tracing::subscriber::set_global_default(subscriber)?;
+    Ok(())
+}
@@ -11,7 +11,11 @@ use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
 use std::io::{Read, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use uuid::Uuid;
+use web_rwkv::tokenizer::Tokenizer;
 
 /// 向量工具
 pub struct VectorUtils;
@@ -106,15 +110,109 @@ impl VectorUtils {
             return Ok(0.0);
         }
 
-        let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-        let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-        let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let (dot_product, norm_a_sq, norm_b_sq) = Self::dot_and_norms(a, b);
+        Ok(Self::cosine_from_parts(dot_product, norm_a_sq, norm_b_sq))
+    }
 
-        if norm_a == 0.0 || norm_b == 0.0 {
-            return Ok(0.0);
+    /// 由点积与两个平方范数计算余弦相似度，任一范数为 0 时返回 0
+    pub fn cosine_from_parts(dot_product: f32, norm_a_sq: f32, norm_b_sq: f32) -> f32 {
+        let norm = (norm_a_sq * norm_b_sq).sqrt();
+        if norm == 0.0 {
+            0.0
+        } else {
+            dot_product / norm
+        }
+    }
+
+    /// 一次遍历同时计算点积与两个平方范数 `(a·b, |a|², |b|²)`
+    ///
+    /// 启用 `simd` 特性时使用 8 路 SIMD，否则使用标量实现。长度不一致时按较短者计算。
+    pub fn dot_and_norms(a: &[f32], b: &[f32]) -> (f32, f32, f32) {
+        #[cfg(feature = "simd")]
+        {
+            Self::dot_and_norms_simd(a, b)
+        }
+        #[cfg(not(feature = "simd"))]
+        {
+            Self::dot_and_norms_scalar(a, b)
+        }
+    }
+
+    /// 计算点积（用于已归一化的向量）
+    pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+        #[cfg(feature = "simd")]
+        {
+            Self::dot_simd(a, b)
+        }
+        #[cfg(not(feature = "simd"))]
+        {
+            a.iter().zip(b).map(|(x, y)| x * y).sum()
+        }
+    }
+
+    /// [`VectorUtils::dot_and_norms`] 的标量实现
+    pub fn dot_and_norms_scalar(a: &[f32], b: &[f32]) -> (f32, f32, f32) {
+        a.iter()
+            .zip(b)
+            .fold((0.0, 0.0, 0.0), |(dot, norm_a, norm_b), (x, y)| {
+                (dot + x * y, norm_a + x * x, norm_b + y * y)
+            })
+    }
+
+    /// [`VectorUtils::dot_and_norms`] 的 SIMD 实现，不足 8 个的尾部元素按标量累加
+    #[cfg(feature = "simd")]
+    pub fn dot_and_norms_simd(a: &[f32], b: &[f32]) -> (f32, f32, f32) {
+        use wide::f32x8;
+
+        let len = a.len().min(b.len());
+        let (a, b) = (&a[..len], &b[..len]);
+        let mut dot = f32x8::ZERO;
+        let mut norm_a = f32x8::ZERO;
+        let mut norm_b = f32x8::ZERO;
+
+        let chunks_a = a.chunks_exact(8);
+        let chunks_b = b.chunks_exact(8);
+        let (tail_a, tail_b) = (chunks_a.remainder(), chunks_b.remainder());
+        for (x, y) in chunks_a.zip(chunks_b) {
+            let x = f32x8::new(x.try_into().expect("chunk of 8"));
+            let y = f32x8::new(y.try_into().expect("chunk of 8"));
+            dot = x.mul_add(y, dot);
+            norm_a = x.mul_add(x, norm_a);
+            norm_b = y.mul_add(y, norm_b);
+        }
+
+        let (tail_dot, tail_a, tail_b) = Self::dot_and_norms_scalar(tail_a, tail_b);
+        (
+            dot.reduce_add() + tail_dot,
+            norm_a.reduce_add() + tail_a,
+            norm_b.reduce_add() + tail_b,
+        )
+    }
+
+    /// [`VectorUtils::dot`] 的 SIMD 实现
+    #[cfg(feature = "simd")]
+    fn dot_simd(a: &[f32], b: &[f32]) -> f32 {
+        use wide::f32x8;
+
+        let len = a.len().min(b.len());
+        let (a, b) = (&a[..len], &b[..len]);
+        let mut dot = f32x8::ZERO;
+
+        let chunks_a = a.chunks_exact(8);
+        let chunks_b = b.chunks_exact(8);
+        let tail: f32 = chunks_a
+            .remainder()
+            .iter()
+            .zip(chunks_b.remainder())
+            .map(|(x, y)| x * y)
+            .sum();
+        for (x, y) in chunks_a.zip(chunks_b) {
+            let x = f32x8::new(x.try_into().expect("chunk of 8"));
+            let y = f32x8::new(y.try_into().expect("chunk of 8"));
+            dot = x.mul_add(y, dot);
         }
 
-        Ok(dot_product / (norm_a * norm_b))
+        dot.reduce_add() + tail
     }
 
     /// 计算欧几里得距离
@@ -347,7 +445,7 @@ impl TextUtils {
     }
 
     /// 简单的语言检测
-    fn detect_language(text: &str) -> Option<String> {
+    pub fn detect_language(text: &str) -> Option<String> {
         let chinese_chars = text
             .chars()
             .filter(|c| {
@@ -420,7 +518,7 @@ impl TextUtils {
         }
 
         let mut keywords: Vec<(String, usize)> = word_freq.into_iter().collect();
-        keywords.sort_by(|a, b| b.1.cmp(&a.1));
+        keywords.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
 
         keywords
             .into_iter()
@@ -468,6 +566,199 @@ impl TextUtils {
             .join(". ")
             + "."
     }
+
+    /// 估算文本的 token 数（无分词器时的近似）
+    ///
+    /// CJK 字符按每字 1 个 token 计，其余字符按每 4 个字符 1 个 token 计。
+    pub fn estimate_tokens(text: &str) -> usize {
+        let mut cjk = 0usize;
+        let mut other = 0usize;
+        for c in text.chars() {
+            if is_cjk(c) {
+                cjk += 1;
+            } else {
+                other += 1;
+            }
+        }
+        cjk + other.div_ceil(4)
+    }
+}
+
+/// 统计文本的 token 数
+///
+/// 传入已加载的 RWKV 分词器时按分词结果计数，否则回退到
+/// [`TextUtils::estimate_tokens`] 的字符近似。
+pub fn count_tokens(tokenizer: Option<&Tokenizer>, text: &str) -> usize {
+    match tokenizer.map(|t| t.encode(text.as_bytes())) {
+        Some(Ok(tokens)) => tokens.len(),
+        _ => TextUtils::estimate_tokens(text),
+    }
+}
+
+/// 将文本截断到不超过 `max` 个 token，截断点总落在字符边界上
+pub fn truncate_to_tokens(tokenizer: Option<&Tokenizer>, text: &str, max: usize) -> String {
+    if let Some(tokenizer) = tokenizer {
+        if let Ok(tokens) = tokenizer.encode(text.as_bytes()) {
+            if tokens.len() <= max {
+                return text.to_string();
+            }
+            if let Ok(bytes) = tokenizer.decode(&tokens[..max]) {
+                // 截断可能落在多字节字符中间，只保留完整的 UTF-8 前缀
+                let valid = match std::str::from_utf8(&bytes) {
+                    Ok(s) => s.len(),
+                    Err(e) => e.valid_up_to(),
+                };
+                let mut truncated = String::from_utf8_lossy(&bytes[..valid]).into_owned();
+                while count_tokens(Some(tokenizer), &truncated) > max {
+                    truncated.pop();
+                }
+                return truncated;
+            }
+        }
+    }
+
+    let mut cjk = 0usize;
+    let mut other = 0usize;
+    for (idx, c) in text.char_indices() {
+        if is_cjk(c) {
+            cjk += 1;
+        } else {
+            other += 1;
+        }
+        if cjk + other.div_ceil(4) > max {
+            return text[..idx].to_string();
+        }
+    }
+    text.to_string()
+}
+
+/// 将长文本切分为不超过 `max_tokens` 的重叠片段
+///
+/// 优先在段落/句子边界切分，只有单个句子超出上限时才硬切（有空白时按词，
+/// 无空白的 CJK 文本按字符窗口）。相邻片段之间重叠约 `overlap` 个 token，
+/// 重叠量最多为 `max_tokens` 的一半。`max_tokens` 为 0 时整段返回。
+pub fn chunk_text(text: &str, max_tokens: usize, overlap: usize) -> Vec<String> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+    if max_tokens == 0 {
+        return vec![text.trim().to_string()];
+    }
+    let overlap = overlap.min(max_tokens / 2);
+
+    let mut segments = Vec::new();
+    for segment in split_segments(text) {
+        if count_tokens(None, segment) <= max_tokens {
+            segments.push(segment.to_string());
+        } else {
+            // 为重叠部分预留空间
+            segments.extend(hard_split(segment, max_tokens - overlap));
+        }
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for segment in segments {
+        let candidate = format!("{}{}", current, segment);
+        if current.is_empty() || count_tokens(None, &candidate) <= max_tokens {
+            current = candidate;
+            continue;
+        }
+
+        // 以上一片段的末尾作为新片段的开头，保证检索时的上下文连续
+        let room = max_tokens.saturating_sub(count_tokens(None, &segment));
+        let seed = tail_tokens(&current, overlap.min(room)).to_string();
+        chunks.push(std::mem::take(&mut current));
+        current = seed + &segment;
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+        .into_iter()
+        .map(|chunk| chunk.trim().to_string())
+        .filter(|chunk| !chunk.is_empty())
+        .collect()
+}
+
+/// 按段落和句子边界切分，分隔符及其后的空白保留在前一段末尾
+fn split_segments(text: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        if !matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '\n') {
+            continue;
+        }
+        let mut end = idx + c.len_utf8();
+        while let Some(&(next_idx, next)) = chars.peek() {
+            if !next.is_whitespace() {
+                break;
+            }
+            end = next_idx + next.len_utf8();
+            chars.next();
+        }
+        segments.push(&text[start..end]);
+        start = end;
+    }
+    if start < text.len() {
+        segments.push(&text[start..]);
+    }
+    segments
+}
+
+/// 将超长片段硬切为不超过 `max_tokens` 的窗口，有空白时尽量在词边界切
+fn hard_split(segment: &str, max_tokens: usize) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut rest = segment;
+    while !rest.is_empty() {
+        let mut piece = truncate_to_tokens(None, rest, max_tokens);
+        if piece.is_empty() {
+            // 单个字符已超出上限时仍需前进
+            let first = rest.chars().next().map_or(rest.len(), char::len_utf8);
+            piece = rest[..first].to_string();
+        } else if piece.len() < rest.len() {
+            let boundary = piece.char_indices().rev().find(|(_, c)| c.is_whitespace());
+            if let Some((pos, ws)) = boundary {
+                if pos > 0 {
+                    piece.truncate(pos + ws.len_utf8());
+                }
+            }
+        }
+        rest = &rest[piece.len()..];
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// 取文本末尾不超过 `max` 个 token 的部分，有空白时从词边界开始
+fn tail_tokens(text: &str, max: usize) -> &str {
+    if max == 0 {
+        return "";
+    }
+    let mut start = text.len();
+    for (idx, _) in text.char_indices().rev() {
+        if count_tokens(None, &text[idx..]) > max {
+            break;
+        }
+        start = idx;
+    }
+    let tail = &text[start..];
+    if start > 0 && !text[..start].ends_with(char::is_whitespace) {
+        if let Some(pos) = tail.find(char::is_whitespace) {
+            return &tail[pos..];
+        }
+    }
+    tail
+}
+
+/// 是否为 CJK 字符（汉字、假名、谚文及全角标点）
+fn is_cjk(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x3000..=0x303F | 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF
+    )
 }
 
 impl TimeUtils {
@@ -500,6 +791,19 @@ impl TimeUtils {
         (0.5_f64.powf(elapsed_seconds / half_life_seconds)) as f32
     }
 
+    /// 计算指数时间衰减 exp(-age/τ)，age 为 `timestamp` 到 `reference` 的时长
+    ///
+    /// 晚于 `reference` 的时间视为 age = 0；τ 非正时不衰减。
+    pub fn recency_decay(timestamp: DateTime<Utc>, reference: DateTime<Utc>, tau: Duration) -> f32 {
+        let tau_seconds = tau.num_seconds() as f64;
+        if tau_seconds <= 0.0 {
+            return 1.0;
+        }
+
+        let age_seconds = (reference - timestamp).num_seconds().max(0) as f64;
+        (-age_seconds / tau_seconds).exp() as f32
+    }
+
     /// 获取时间段描述
     pub fn get_time_period_description(timestamp: DateTime<Utc>) -> String {
         let now = Utc::now();
@@ -817,6 +1121,67 @@ impl PerformanceUtils {
     }
 }
 
+/// 并发限流器
+///
+/// 限制同时进行的嵌入生成或模型推理数量，超出上限的请求排队等待，
+/// 等待数量通过 `queue_depth` 指标（按 `queue` 标签区分）上报。未设置上限时不做限制。
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimiter {
+    queue: &'static str,
+    limit: Option<usize>,
+    semaphore: Option<Arc<Semaphore>>,
+    waiting: Arc<AtomicUsize>,
+}
+
+impl ConcurrencyLimiter {
+    /// 创建限流器，`limit` 为 `None` 时不限制并发
+    pub fn new(queue: &'static str, limit: Option<usize>) -> Self {
+        let limit = limit.map(|limit| limit.max(1));
+        Self {
+            queue,
+            limit,
+            semaphore: limit.map(|limit| Arc::new(Semaphore::new(limit))),
+            waiting: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// 等待执行许可，许可在返回值被丢弃时归还；未设置上限时立即返回 `None`
+    pub async fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>> {
+        let Some(semaphore) = &self.semaphore else {
+            return Ok(None);
+        };
+
+        // 等待期间计入队列深度，调用方取消等待时同样会出队
+        struct Queued<'a>(&'a ConcurrencyLimiter);
+        impl Drop for Queued<'_> {
+            fn drop(&mut self) {
+                let depth = self.0.waiting.fetch_sub(1, Ordering::SeqCst) - 1;
+                crate::telemetry::set_queue_depth(self.0.queue, depth);
+            }
+        }
+
+        let depth = self.waiting.fetch_add(1, Ordering::SeqCst) + 1;
+        crate::telemetry::set_queue_depth(self.queue, depth);
+        let queued = Queued(self);
+        let permit = semaphore.clone().acquire_owned().await;
+        drop(queued);
+
+        permit.map(Some).map_err(|e| MemoryError::Internal {
+            message: format!("{} limiter closed: {}", self.queue, e),
+        })
+    }
+
+    /// 并发上限
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// 当前排队等待的请求数
+    pub fn queue_depth(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -856,7 +1221,56 @@ mod tests {
         let keywords = TextUtils::extract_keywords(text, 3);
 
         assert!(!keywords.is_empty());
-        assert!(keywords.len() > 0);
+        assert!(!keywords.is_empty());
+    }
+
+    /// 确定性的伪随机向量（线性同余），取值 [-1, 1)
+    fn pseudo_random_vector(len: usize, seed: u64) -> Vec<f32> {
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                ((state >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_dot_and_norms_matches_naive() {
+        for len in [0, 1, 7, 8, 9, 33, 256, 1000] {
+            let a = pseudo_random_vector(len, len as u64 + 1);
+            let b = pseudo_random_vector(len, len as u64 + 1000);
+
+            let naive_dot: f32 = a.iter().zip(&b).map(|(x, y)| x * y).sum();
+            let naive_norm_a: f32 = a.iter().map(|x| x * x).sum();
+            let (dot, norm_a, _) = VectorUtils::dot_and_norms(&a, &b);
+            assert!((dot - naive_dot).abs() <= 1e-4 * naive_dot.abs().max(1.0));
+            assert!((norm_a - naive_norm_a).abs() <= 1e-4 * naive_norm_a.max(1.0));
+            assert!((VectorUtils::dot(&a, &b) - dot).abs() <= 1e-4 * dot.abs().max(1.0));
+        }
+    }
+
+    #[cfg(feature = "simd")]
+    #[test]
+    fn test_simd_matches_scalar() {
+        for len in [1, 3, 8, 15, 16, 17, 100, 384, 1023] {
+            let a = pseudo_random_vector(len, len as u64 * 7 + 3);
+            let b = pseudo_random_vector(len, len as u64 * 13 + 5);
+
+            let (dot_s, norm_a_s, norm_b_s) = VectorUtils::dot_and_norms_scalar(&a, &b);
+            let (dot_v, norm_a_v, norm_b_v) = VectorUtils::dot_and_norms_simd(&a, &b);
+            let cosine_s = VectorUtils::cosine_from_parts(dot_s, norm_a_s, norm_b_s);
+            let cosine_v = VectorUtils::cosine_from_parts(dot_v, norm_a_v, norm_b_v);
+            assert!(
+                (cosine_s - cosine_v).abs() < 1e-5,
+                "len {}: scalar {} simd {}",
+                len,
+                cosine_s,
+                cosine_v
+            );
+        }
     }
 
     #[test]
@@ -929,6 +1343,71 @@ mod tests {
         assert_eq!(hit_rate_zero, 0.0);
     }
 
+    #[test]
+    fn test_count_tokens_monotonic() {
+        let text = "RWKV is an RNN with transformer-level performance. 它可以像 GPT 一样并行训练。";
+        let mut previous = 0;
+        for (idx, _) in text
+            .char_indices()
+            .chain(std::iter::once((text.len(), ' ')))
+        {
+            let count = count_tokens(None, &text[..idx]);
+            assert!(count >= previous);
+            previous = count;
+        }
+        assert_eq!(count_tokens(None, ""), 0);
+        assert_eq!(count_tokens(None, "你好"), 2);
+    }
+
+    #[test]
+    fn test_truncate_to_tokens_respects_cap() {
+        let text =
+            "Memory systems need budgets. 记忆系统需要预算控制，否则上下文会溢出。".repeat(4);
+        for max in [0, 1, 5, 17, 40] {
+            let truncated = truncate_to_tokens(None, &text, max);
+            assert!(count_tokens(None, &truncated) <= max);
+            assert!(text.starts_with(&truncated));
+        }
+        let total = count_tokens(None, &text);
+        assert_eq!(truncate_to_tokens(None, &text, total), text);
+    }
+
+    #[test]
+    fn test_chunk_text_overlap_and_boundaries() {
+        let english = "The first sentence talks about memory. The second one covers retrieval! \
+                       Does the third ask a question? ";
+        let chinese =
+            "长期记忆需要在检索时保持上下文连续，因此相邻片段之间需要保留一定的重叠内容".repeat(3);
+        let text = format!("{}\n\n{}{}", english.repeat(3), chinese, english);
+        let max_tokens = 24;
+
+        let chunks = chunk_text(&text, max_tokens, 6);
+
+        assert!(chunks.len() > 3);
+        for chunk in &chunks {
+            assert!(count_tokens(None, chunk) <= max_tokens);
+        }
+        // 英文部分在句子边界切分
+        assert!(chunks[0].ends_with(['.', '!', '?']));
+        // 相邻片段共享前一片段的末尾
+        for pair in chunks.windows(2) {
+            let shared = pair[1]
+                .char_indices()
+                .skip(1)
+                .any(|(idx, _)| pair[0].ends_with(&pair[1][..idx]));
+            assert!(shared, "no overlap between {:?} and {:?}", pair[0], pair[1]);
+        }
+        // 无空白的中文被按字符窗口硬切，且内容不丢失
+        assert!(chunks.iter().any(|c| c.chars().all(|ch| !ch.is_ascii())));
+        assert!(chunks.iter().any(|c| c.contains("保留一定的重叠内容")));
+
+        assert_eq!(
+            chunk_text("short text.", max_tokens, 6),
+            vec!["short text."]
+        );
+        assert!(chunk_text("   ", max_tokens, 6).is_empty());
+    }
+
     #[tokio::test]
     async fn test_performance_measurement() {
         let (result, duration) = PerformanceUtils::measure_time(async {
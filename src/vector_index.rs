@@ -0,0 +1,330 @@
+//! 向量倒排（IVF）索引
+//!
+//! 用 k-means 把向量划分到 `nlist` 个聚类，查询时只扫描离查询向量最近的 `nprobe` 个聚类，
+//! 代替逐条扫描整个向量表。索引可以序列化到旁路文件，启动时按向量表的行数与最大更新时间
+//! 判断是否过期，未过期时直接加载而不必重建。
+
+use crate::error::{MemoryError, Result};
+use crate::utils::VectorUtils;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// 构建索引时 k-means 的迭代次数
+const KMEANS_ITERATIONS: usize = 10;
+
+/// 旁路文件格式版本，格式变化时递增，旧文件会被视为过期
+const SIDECAR_VERSION: u32 = 1;
+
+/// 向量表的状态，用于判断旁路文件中的索引是否过期
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexState {
+    /// 向量表的行数
+    pub row_count: u64,
+    /// 向量表中最大的 `updated_at`，表为空时为 `None`
+    pub max_updated_at: Option<String>,
+}
+
+/// 旁路文件内容
+#[derive(Debug, Serialize, Deserialize)]
+struct Sidecar {
+    version: u32,
+    state: IndexState,
+    index: IvfIndex,
+}
+
+/// IVF 索引
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IvfIndex {
+    dimension: usize,
+    nlist: usize,
+    /// 余弦度量下先把向量归一化再聚类
+    normalize: bool,
+    centroids: Vec<Vec<f32>>,
+    lists: Vec<Vec<String>>,
+    /// 向量ID到所在聚类的映射
+    assignments: HashMap<String, usize>,
+}
+
+impl IvfIndex {
+    /// 创建空索引，前 `nlist` 个插入的向量各自成为一个聚类中心
+    pub fn new(dimension: usize, nlist: usize, normalize: bool) -> Self {
+        Self {
+            dimension,
+            nlist: nlist.max(1),
+            normalize,
+            centroids: Vec::new(),
+            lists: Vec::new(),
+            assignments: HashMap::new(),
+        }
+    }
+
+    /// 用 k-means 从已有向量构建索引，维度不符的向量不会被索引
+    pub fn build<'a>(
+        dimension: usize,
+        nlist: usize,
+        normalize: bool,
+        vectors: impl IntoIterator<Item = (&'a str, &'a [f32])>,
+    ) -> Self {
+        let mut index = Self::new(dimension, nlist, normalize);
+        let mut points: Vec<(&str, Vec<f32>)> = vectors
+            .into_iter()
+            .filter(|(_, embedding)| embedding.len() == dimension)
+            .map(|(id, embedding)| (id, index.prepare(embedding)))
+            .collect();
+        if points.is_empty() {
+            return index;
+        }
+        // 按ID排序，保证同样的数据总是得到同样的索引
+        points.sort_by(|a, b| a.0.cmp(b.0));
+
+        // 以均匀间隔选取的点作为初始中心
+        let k = index.nlist.min(points.len());
+        let step = points.len() / k;
+        index.centroids = (0..k).map(|i| points[i * step].1.clone()).collect();
+
+        let mut assignment = vec![0; points.len()];
+        for _ in 0..KMEANS_ITERATIONS {
+            let mut changed = false;
+            for (slot, (_, point)) in assignment.iter_mut().zip(&points) {
+                let nearest = index.nearest_centroid(point);
+                changed |= *slot != nearest;
+                *slot = nearest;
+            }
+
+            let mut sums = vec![vec![0.0f32; dimension]; k];
+            let mut counts = vec![0usize; k];
+            for (&cluster, (_, point)) in assignment.iter().zip(&points) {
+                counts[cluster] += 1;
+                for (sum, value) in sums[cluster].iter_mut().zip(point) {
+                    *sum += value;
+                }
+            }
+            // 空聚类保留原来的中心
+            for ((centroid, sum), count) in index.centroids.iter_mut().zip(sums).zip(counts) {
+                if count > 0 {
+                    *centroid = sum.into_iter().map(|v| v / count as f32).collect();
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        index.lists = vec![Vec::new(); k];
+        for (id, point) in &points {
+            let cluster = index.nearest_centroid(point);
+            index.lists[cluster].push(id.to_string());
+            index.assignments.insert(id.to_string(), cluster);
+        }
+        index
+    }
+
+    /// 已索引的向量数
+    pub fn len(&self) -> usize {
+        self.assignments.len()
+    }
+
+    /// 索引是否为空
+    pub fn is_empty(&self) -> bool {
+        self.assignments.is_empty()
+    }
+
+    /// 向量维度
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// 聚类数上限
+    pub fn nlist(&self) -> usize {
+        self.nlist
+    }
+
+    /// 加入或更新一个向量，中心保持不变；维度不符时忽略
+    pub fn insert(&mut self, id: &str, embedding: &[f32]) {
+        if embedding.len() != self.dimension {
+            return;
+        }
+        self.remove(id);
+
+        let point = self.prepare(embedding);
+        let cluster = if self.centroids.len() < self.nlist {
+            self.centroids.push(point);
+            self.lists.push(Vec::new());
+            self.centroids.len() - 1
+        } else {
+            self.nearest_centroid(&point)
+        };
+        self.lists[cluster].push(id.to_string());
+        self.assignments.insert(id.to_string(), cluster);
+    }
+
+    /// 移除一个向量
+    pub fn remove(&mut self, id: &str) {
+        if let Some(cluster) = self.assignments.remove(id) {
+            self.lists[cluster].retain(|existing| existing != id);
+        }
+    }
+
+    /// 清空索引
+    pub fn clear(&mut self) {
+        self.centroids.clear();
+        self.lists.clear();
+        self.assignments.clear();
+    }
+
+    /// 离查询向量最近的 `nprobe` 个聚类中的全部向量ID
+    pub fn candidates(&self, query: &[f32], nprobe: usize) -> Vec<String> {
+        if query.len() != self.dimension {
+            return Vec::new();
+        }
+        let query = self.prepare(query);
+        let mut clusters: Vec<(usize, f32)> = self
+            .centroids
+            .iter()
+            .enumerate()
+            .map(|(i, centroid)| (i, squared_distance(centroid, &query)))
+            .collect();
+        clusters.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        clusters
+            .into_iter()
+            .take(nprobe.max(1))
+            .flat_map(|(i, _)| self.lists[i].iter().cloned())
+            .collect()
+    }
+
+    /// 把索引连同向量表状态写入旁路文件
+    pub fn save(&self, path: &Path, state: &IndexState) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let sidecar = Sidecar {
+            version: SIDECAR_VERSION,
+            state: state.clone(),
+            index: self.clone(),
+        };
+        // 先写临时文件再改名，中途失败不会留下半个文件
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec(&sidecar)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// 从旁路文件加载索引；文件不存在或版本不符时返回 `None`
+    pub fn load(path: &Path) -> Result<Option<(Self, IndexState)>> {
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(MemoryError::Io(e)),
+        };
+        let sidecar: Sidecar = serde_json::from_slice(&bytes)?;
+        if sidecar.version != SIDECAR_VERSION {
+            return Ok(None);
+        }
+        Ok(Some((sidecar.index, sidecar.state)))
+    }
+
+    fn prepare(&self, embedding: &[f32]) -> Vec<f32> {
+        let mut point = embedding.to_vec();
+        if self.normalize {
+            // 零向量无法归一化，保持原样
+            let _ = VectorUtils::normalize(&mut point);
+        }
+        point
+    }
+
+    fn nearest_centroid(&self, point: &[f32]) -> usize {
+        self.centroids
+            .iter()
+            .enumerate()
+            .map(|(i, centroid)| (i, squared_distance(centroid, point)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+            .unwrap_or(0)
+    }
+}
+
+fn squared_distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clustered_points() -> Vec<(String, Vec<f32>)> {
+        (0..20)
+            .map(|i| {
+                let offset = i as f32 * 0.01;
+                let embedding = if i % 2 == 0 {
+                    vec![1.0, offset]
+                } else {
+                    vec![-1.0, offset]
+                };
+                (format!("v{:02}", i), embedding)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_probe_returns_nearest_cluster_only() {
+        let points = clustered_points();
+        let index = IvfIndex::build(
+            2,
+            2,
+            false,
+            points.iter().map(|(id, e)| (id.as_str(), e.as_slice())),
+        );
+        assert_eq!(index.len(), 20);
+
+        let candidates = index.candidates(&[0.9, 0.0], 1);
+        assert_eq!(candidates.len(), 10);
+        assert!(candidates
+            .iter()
+            .all(|id| id[1..].parse::<usize>().unwrap() % 2 == 0));
+        assert_eq!(index.candidates(&[0.9, 0.0], 2).len(), 20);
+    }
+
+    #[test]
+    fn test_insert_and_remove_keep_lists_consistent() {
+        let mut index = IvfIndex::new(2, 2, false);
+        index.insert("a", &[1.0, 0.0]);
+        index.insert("b", &[-1.0, 0.0]);
+        index.insert("c", &[0.9, 0.1]);
+        index.insert("bad", &[1.0]);
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.candidates(&[1.0, 0.0], 1), vec!["a", "c"]);
+
+        // 更新后移到另一个聚类
+        index.insert("c", &[-0.9, 0.1]);
+        assert_eq!(index.candidates(&[1.0, 0.0], 1), vec!["a"]);
+        index.remove("a");
+        assert!(index.candidates(&[1.0, 0.0], 1).is_empty());
+    }
+
+    #[test]
+    fn test_sidecar_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("index").join("vectors.ivf");
+        assert!(IvfIndex::load(&path).unwrap().is_none());
+
+        let points = clustered_points();
+        let index = IvfIndex::build(
+            2,
+            4,
+            true,
+            points.iter().map(|(id, e)| (id.as_str(), e.as_slice())),
+        );
+        let state = IndexState {
+            row_count: 20,
+            max_updated_at: Some("2024-01-01T00:00:00+00:00".to_string()),
+        };
+        index.save(&path, &state).unwrap();
+
+        let (loaded, loaded_state) = IvfIndex::load(&path).unwrap().unwrap();
+        assert_eq!(loaded, index);
+        assert_eq!(loaded_state, state);
+    }
+}